@@ -0,0 +1,90 @@
+//! Generates the admin gRPC server from proto/ (pure Rust, no system protoc)
+//! and embeds the wire fixtures for the startup self-test, the wire JSON
+//! Schema for publish-time validation, the application command definitions
+//! and the NATS routing constants
+
+use std::path::Path;
+
+/// Committed wire fixtures, when building inside the monorepo
+const FIXTURES_DIR: &str = "../../packages/shared/nats-schemas/fixtures";
+
+/// Envelope JSON Schema, when building inside the monorepo
+const SCHEMA_PATH: &str = "../../packages/shared/nats-schemas/json-schema/gateway-event.schema.json";
+
+/// Application command definitions, when building inside the monorepo
+const COMMANDS_PATH: &str = "../../packages/shared/nats-schemas/commands.json";
+
+/// Stream names, subjects and settings, when building inside the monorepo
+const ROUTING_PATH: &str = "../../packages/shared/nats-schemas/nats-routing.json";
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("cargo:rerun-if-changed=proto");
+    println!("cargo:rerun-if-changed={FIXTURES_DIR}");
+    println!("cargo:rerun-if-changed={SCHEMA_PATH}");
+    println!("cargo:rerun-if-changed={COMMANDS_PATH}");
+    println!("cargo:rerun-if-changed={ROUTING_PATH}");
+
+    let fds = protox::compile(["proto/admin.proto"], ["proto"])?;
+    tonic_prost_build::configure().build_client(false).compile_fds(fds)?;
+
+    embed_fixtures()?;
+    embed_schema()?;
+    embed_commands()?;
+    embed_routing()
+}
+
+/// Write `fixtures.rs`: `(name, json)` pairs for every fixture, or none when
+/// the build context has only this crate (the Docker image)
+fn embed_fixtures() -> Result<(), Box<dyn std::error::Error>> {
+    let mut entries = Vec::new();
+    if let Ok(dir) = std::fs::read_dir(FIXTURES_DIR) {
+        for entry in dir {
+            let path = std::fs::canonicalize(entry?.path())?;
+            if path.extension().is_some_and(|ext| ext == "json") {
+                let name = path.file_stem().and_then(|stem| stem.to_str()).ok_or("fixture name")?;
+                entries.push(format!("    ({name:?}, include_str!({:?})),\n", path.display().to_string()));
+            }
+        }
+    }
+    entries.sort();
+
+    let out = Path::new(&std::env::var("OUT_DIR")?).join("fixtures.rs");
+    std::fs::write(out, format!("pub const FIXTURES: &[(&str, &str)] = &[\n{}];\n", entries.concat()))?;
+    Ok(())
+}
+
+/// Write `schema.rs`: the envelope JSON Schema, or None outside the monorepo
+fn embed_schema() -> Result<(), Box<dyn std::error::Error>> {
+    let schema = match std::fs::canonicalize(SCHEMA_PATH) {
+        Ok(path) => format!("Some(include_str!({:?}))", path.display().to_string()),
+        Err(_) => "None".to_string(),
+    };
+
+    let out = Path::new(&std::env::var("OUT_DIR")?).join("schema.rs");
+    std::fs::write(out, format!("pub const GATEWAY_EVENT: Option<&str> = {schema};\n"))?;
+    Ok(())
+}
+
+/// Write `commands.rs`: commands.json, or None outside the monorepo
+fn embed_commands() -> Result<(), Box<dyn std::error::Error>> {
+    let commands = match std::fs::canonicalize(COMMANDS_PATH) {
+        Ok(path) => format!("Some(include_str!({:?}))", path.display().to_string()),
+        Err(_) => "None".to_string(),
+    };
+
+    let out = Path::new(&std::env::var("OUT_DIR")?).join("commands.rs");
+    std::fs::write(out, format!("pub const COMMANDS: Option<&str> = {commands};\n"))?;
+    Ok(())
+}
+
+/// Write `routing.rs`: nats-routing.json, or None outside the monorepo
+fn embed_routing() -> Result<(), Box<dyn std::error::Error>> {
+    let routing = match std::fs::canonicalize(ROUTING_PATH) {
+        Ok(path) => format!("Some(include_str!({:?}))", path.display().to_string()),
+        Err(_) => "None".to_string(),
+    };
+
+    let out = Path::new(&std::env::var("OUT_DIR")?).join("routing.rs");
+    std::fs::write(out, format!("pub const ROUTING: Option<&str> = {routing};\n"))?;
+    Ok(())
+}
@@ -0,0 +1,172 @@
+//! Operator actions shared by the admin HTTP and gRPC APIs
+//!
+//! Both APIs authenticate their own way (bearer token, client certificate)
+//! and then call into `Admin`, so a command refused over one is refused over
+//! the other for the same reason.
+
+use crate::nats::dlq;
+use crate::nats::kv::{self, buckets};
+use crate::nats::replay::{self, ReplayError, ReplayRequest};
+use crate::nats::NatsPublisher;
+use crate::overrides::{keys, Overrides};
+use crate::shard::control::{ShardCommand, ShardControl};
+use crate::shard::token::{Rejected, TokenRotation};
+use crate::shard::{ShardHealth, ShardState};
+use std::sync::Arc;
+use tracing::info;
+
+/// Why an admin request was not carried out
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Refused {
+    /// This pool doesn't run the shard, or the feature isn't configured
+    NotFound(String),
+    /// The request itself is wrong
+    Invalid(String),
+    /// The shard's state, or another run in progress, rules it out
+    Conflict(String),
+    /// The pool or NATS can't take it right now
+    Unavailable(String),
+    /// NATS failed while carrying it out
+    Failed(String),
+}
+
+/// Shard commands, replays, token rotation and config overrides of one pool
+#[derive(Clone)]
+pub struct Admin {
+    pub shard_state: ShardState,
+    pub control: ShardControl,
+    pub nats: Option<Arc<NatsPublisher>>,
+    pub rotation: Option<Arc<TokenRotation>>,
+    /// Set with `DYNAMIC_CONFIG_ENABLED`
+    pub overrides: Option<Arc<Overrides>>,
+    /// Held while an event replay runs
+    replaying: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl Admin {
+    pub fn new(
+        shard_state: ShardState,
+        control: ShardControl,
+        nats: Option<Arc<NatsPublisher>>,
+        rotation: Option<Arc<TokenRotation>>,
+        overrides: Option<Arc<Overrides>>,
+    ) -> Self {
+        Self { shard_state, control, nats, rotation, overrides, replaying: Arc::default() }
+    }
+
+    /// Queue a command for one of this pool's shards; returns the shard's
+    /// health when it was queued
+    pub fn command(&self, shard_id: u64, command: ShardCommand) -> Result<ShardHealth, Refused> {
+        let Some(health) = self.shard_state.get_health(shard_id) else {
+            let pool_id = self.shard_state.pool_id();
+            return Err(Refused::NotFound(format!("shard {shard_id} is not run by pool {pool_id}")));
+        };
+        if matches!(command, ShardCommand::Drain | ShardCommand::Handoff { .. })
+            && matches!(health, ShardHealth::Dead | ShardHealth::Drained)
+        {
+            return Err(Refused::Conflict(format!("shard {shard_id} is not running ({})", health.as_str())));
+        }
+        if !self.control.send(shard_id, command) {
+            return Err(Refused::Unavailable("the pool is not accepting commands".to_string()));
+        }
+        info!(shard_id, command = command.as_str(), "Admin shard command queued");
+        Ok(health)
+    }
+
+    /// Move a shard to another pool
+    pub fn hand_off(&self, shard_id: u64, to_pool: u64) -> Result<ShardHealth, Refused> {
+        if to_pool == self.shard_state.pool_id() {
+            return Err(Refused::Invalid(format!("shard {shard_id} is already run by pool {to_pool}")));
+        }
+        if self.nats.is_none() {
+            return Err(Refused::Unavailable("NATS is not connected".to_string()));
+        }
+        self.command(shard_id, ShardCommand::Handoff { to_pool })
+    }
+
+    /// Republish the dead-letter queue
+    pub async fn replay_dead_letters(&self) -> Result<dlq::Replayed, Refused> {
+        let nats = self.nats()?;
+        let Some(dead_letters) = nats.dead_letters() else {
+            return Err(Refused::NotFound("no dead-letter queue is configured (DLQ_DIR)".to_string()));
+        };
+        dead_letters
+            .replay(nats)
+            .await
+            .ok_or_else(|| Refused::Conflict("a replay is already running".to_string()))
+    }
+
+    /// Republish stored events on `replay.>`
+    pub async fn replay_events(&self, request: &ReplayRequest) -> Result<replay::Replayed, Refused> {
+        let range = request.validate(std::time::SystemTime::now().into()).map_err(Refused::Invalid)?;
+        let nats = self.nats()?;
+        let Ok(_replaying) = self.replaying.try_lock() else {
+            return Err(Refused::Conflict("a replay is already running".to_string()));
+        };
+        replay::replay(nats, &range).await.map_err(|e| match e {
+            ReplayError::NoStream(reason) => Refused::NotFound(reason),
+            ReplayError::Failed(reason) => Refused::Failed(reason),
+        })
+    }
+
+    /// Roll a new Discord token through the pool; returns the shards restarted
+    pub async fn rotate_token(&self, token: String) -> Result<Vec<u64>, Refused> {
+        let Some(ref rotation) = self.rotation else {
+            return Err(Refused::Unavailable("token rotation is not available".to_string()));
+        };
+        rotation.start(token).await.map_err(|e| match e {
+            Rejected::Busy => Refused::Conflict("a token rotation is already running".to_string()),
+            Rejected::Invalid(reason) => Refused::Invalid(reason),
+        })
+    }
+
+    /// Set a runtime override in the config bucket, or delete it (`None`) to
+    /// restore the startup value. Every pool watching the bucket applies it.
+    pub async fn set_override(&self, key: &str, value: Option<String>) -> Result<(), Refused> {
+        if self.overrides.is_none() {
+            return Err(Refused::NotFound("config overrides are off (DYNAMIC_CONFIG_ENABLED)".to_string()));
+        }
+        if !keys::ALL.contains(&key) {
+            return Err(Refused::Invalid(format!("unknown key {key:?}; expected one of {}", keys::ALL.join(", "))));
+        }
+        let nats = self.nats()?;
+        let store = kv::open_bucket(nats.jetstream(), buckets::CONFIG, "Runtime config overrides")
+            .await
+            .map_err(|e| Refused::Failed(e.to_string()))?;
+        let written = match value {
+            Some(value) => store.put(key, value.into_bytes().into()).await.map(drop).map_err(|e| e.to_string()),
+            None => store.delete(key).await.map_err(|e| e.to_string()),
+        };
+        written.map_err(Refused::Failed)?;
+        info!(key, "Config override written by admin request");
+        Ok(())
+    }
+
+    fn nats(&self) -> Result<&NatsPublisher, Refused> {
+        self.nats.as_deref().ok_or_else(|| Refused::Unavailable("NATS is not connected".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn admin() -> Admin {
+        let (control, _commands) = ShardControl::channel();
+        Admin::new(ShardState::new(1, 25..27, 50, 25), control, None, None, None)
+    }
+
+    #[tokio::test]
+    async fn test_overrides_need_dynamic_config_and_a_known_key() {
+        let mut admin = admin();
+
+        let set = admin.set_override(keys::LOG_LEVEL, Some("debug".to_string())).await;
+        assert!(matches!(set, Err(Refused::NotFound(_))));
+
+        admin.overrides = Some(Arc::default());
+        let set = admin.set_override("log_levle", Some("debug".to_string())).await;
+        assert!(matches!(set, Err(Refused::Invalid(_))));
+        let set = admin.set_override(keys::LOG_LEVEL, None).await;
+        assert!(matches!(set, Err(Refused::Unavailable(_))));
+    }
+}
@@ -0,0 +1,282 @@
+//! Admin HTTP endpoints for shard recovery
+//!
+//! `POST /admin/shards/{id}/restart` and `POST /admin/shards/{id}/drain`,
+//! served next to the health endpoints when `ADMIN_TOKEN` is set. Requests
+//! must carry `Authorization: Bearer <ADMIN_TOKEN>`. Commands are applied by
+//! the pool asynchronously, so success is `202 Accepted`; follow the shard's
+//! health on `/ready` or the admin gRPC API.
+//!
+//! `POST /admin/shards/{id}/handoff` with `{ "pool_id": N }` moves a shard to
+//! another pool, which resumes its session (`shard::handoff`).
+//!
+//! `POST /admin/dlq/replay` republishes the dead-letter queue and answers
+//! once done, with how many events were replayed and how many remain.
+//!
+//! `POST /admin/replay` republishes events still in JetStream, published on a
+//! subject filter between two times, on `replay.>` (`nats::replay`). It also
+//! answers once done; one replay runs at a time.
+//!
+//! `POST /admin/token` rotates the Discord token: the pool's shards are
+//! restarted on it one at a time (`shard::token`).
+
+use super::control::{Admin, Refused};
+use crate::nats::replay::ReplayRequest;
+use crate::shard::control::ShardCommand;
+use axum::extract::{Path, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{Json, Router};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use tracing::warn;
+
+#[derive(Clone)]
+struct AdminHttp {
+    admin: Admin,
+    token: Arc<str>,
+}
+
+/// `POST /admin/token` body
+#[derive(Deserialize)]
+struct NewToken {
+    token: String,
+}
+
+/// `POST /admin/shards/{id}/handoff` body
+#[derive(Deserialize)]
+struct Handoff {
+    pool_id: u64,
+}
+
+/// Shard command, replay and token routes, guarded by the admin token
+pub fn router(admin: Admin, token: String) -> Router {
+    Router::new()
+        .route("/admin/shards/{shard_id}/restart", post(restart_handler))
+        .route("/admin/shards/{shard_id}/drain", post(drain_handler))
+        .route("/admin/shards/{shard_id}/handoff", post(handoff_handler))
+        .route("/admin/dlq/replay", post(replay_handler))
+        .route("/admin/replay", post(event_replay_handler))
+        .route("/admin/token", post(token_handler))
+        .with_state(AdminHttp { admin, token: token.into() })
+}
+
+async fn restart_handler(State(admin): State<AdminHttp>, headers: HeaderMap, Path(shard_id): Path<u64>) -> Response {
+    command(&admin, &headers, shard_id, ShardCommand::Restart)
+}
+
+async fn drain_handler(State(admin): State<AdminHttp>, headers: HeaderMap, Path(shard_id): Path<u64>) -> Response {
+    command(&admin, &headers, shard_id, ShardCommand::Drain)
+}
+
+async fn handoff_handler(
+    State(admin): State<AdminHttp>,
+    headers: HeaderMap,
+    Path(shard_id): Path<u64>,
+    Json(handoff): Json<Handoff>,
+) -> Response {
+    if !authorized(&headers, &admin.token) {
+        warn!(shard_id, "Rejected unauthenticated shard handoff");
+        return unauthorized();
+    }
+    let command = ShardCommand::Handoff { to_pool: handoff.pool_id };
+    match admin.admin.hand_off(shard_id, handoff.pool_id) {
+        Ok(health) => accepted(shard_id, command, health.as_str()),
+        Err(refused) => refused_response(refused),
+    }
+}
+
+async fn replay_handler(State(admin): State<AdminHttp>, headers: HeaderMap) -> Response {
+    if !authorized(&headers, &admin.token) {
+        warn!("Rejected unauthenticated dead-letter replay");
+        return unauthorized();
+    }
+    match admin.admin.replay_dead_letters().await {
+        Ok(replayed) => Json(replayed).into_response(),
+        Err(refused) => refused_response(refused),
+    }
+}
+
+async fn event_replay_handler(
+    State(admin): State<AdminHttp>,
+    headers: HeaderMap,
+    Json(request): Json<ReplayRequest>,
+) -> Response {
+    if !authorized(&headers, &admin.token) {
+        warn!("Rejected unauthenticated event replay");
+        return unauthorized();
+    }
+    match admin.admin.replay_events(&request).await {
+        Ok(replayed) => Json(replayed).into_response(),
+        Err(refused) => refused_response(refused),
+    }
+}
+
+async fn token_handler(State(admin): State<AdminHttp>, headers: HeaderMap, Json(new): Json<NewToken>) -> Response {
+    if !authorized(&headers, &admin.token) {
+        warn!("Rejected unauthenticated token rotation");
+        return unauthorized();
+    }
+    match admin.admin.rotate_token(new.token).await {
+        Ok(shards) => (StatusCode::ACCEPTED, Json(json!({ "restarting": shards }))).into_response(),
+        Err(refused) => refused_response(refused),
+    }
+}
+
+fn command(admin: &AdminHttp, headers: &HeaderMap, shard_id: u64, command: ShardCommand) -> Response {
+    if !authorized(headers, &admin.token) {
+        warn!(shard_id, command = command.as_str(), "Rejected unauthenticated admin request");
+        return unauthorized();
+    }
+    match admin.admin.command(shard_id, command) {
+        Ok(health) => accepted(shard_id, command, health.as_str()),
+        Err(refused) => refused_response(refused),
+    }
+}
+
+fn accepted(shard_id: u64, command: ShardCommand, health: &str) -> Response {
+    let body = json!({ "shard_id": shard_id, "command": command.as_str(), "health": health });
+    (StatusCode::ACCEPTED, Json(body)).into_response()
+}
+
+fn refused_response(refused: Refused) -> Response {
+    match refused {
+        Refused::NotFound(message) => error(StatusCode::NOT_FOUND, message),
+        Refused::Invalid(message) => error(StatusCode::BAD_REQUEST, message),
+        Refused::Conflict(message) => error(StatusCode::CONFLICT, message),
+        Refused::Unavailable(message) => error(StatusCode::SERVICE_UNAVAILABLE, message),
+        Refused::Failed(message) => error(StatusCode::BAD_GATEWAY, message),
+    }
+}
+
+fn error(status: StatusCode, message: String) -> Response {
+    (status, Json(json!({ "error": message }))).into_response()
+}
+
+fn unauthorized() -> Response {
+    let mut response = error(StatusCode::UNAUTHORIZED, "missing or invalid admin token".to_string());
+    response.headers_mut().insert(header::WWW_AUTHENTICATE, header::HeaderValue::from_static("Bearer"));
+    response
+}
+
+/// Bearer token check, compared in constant time
+fn authorized(headers: &HeaderMap, token: &str) -> bool {
+    let Some(presented) = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+    else {
+        return false;
+    };
+
+    presented.len() == token.len()
+        && presented.bytes().zip(token.bytes()).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shard::control::ShardControl;
+    use crate::shard::{ShardHealth, ShardState};
+
+    fn admin() -> (AdminHttp, tokio::sync::mpsc::Receiver<(u64, ShardCommand)>) {
+        let (control, commands) = ShardControl::channel();
+        let admin = Admin::new(ShardState::new(1, 25..27, 50, 25), control, None, None, None);
+        (AdminHttp { admin, token: "s3cret".into() }, commands)
+    }
+
+    fn bearer(token: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::AUTHORIZATION, format!("Bearer {token}").parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_commands_need_the_admin_token() {
+        let (admin, mut commands) = admin();
+
+        assert_eq!(command(&admin, &HeaderMap::new(), 25, ShardCommand::Restart).status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(command(&admin, &bearer("s3cre"), 25, ShardCommand::Restart).status(), StatusCode::UNAUTHORIZED);
+        assert!(commands.try_recv().is_err());
+
+        assert_eq!(command(&admin, &bearer("s3cret"), 25, ShardCommand::Restart).status(), StatusCode::ACCEPTED);
+        assert_eq!(commands.try_recv().unwrap(), (25, ShardCommand::Restart));
+    }
+
+    #[test]
+    fn test_commands_target_running_shards_of_this_pool() {
+        let (admin, mut commands) = admin();
+        let headers = bearer("s3cret");
+
+        assert_eq!(command(&admin, &headers, 3, ShardCommand::Restart).status(), StatusCode::NOT_FOUND);
+
+        admin.admin.shard_state.set_health(26, ShardHealth::Dead);
+        assert_eq!(command(&admin, &headers, 26, ShardCommand::Drain).status(), StatusCode::CONFLICT);
+        assert_eq!(command(&admin, &headers, 26, ShardCommand::Restart).status(), StatusCode::ACCEPTED);
+        assert_eq!(commands.try_recv().unwrap(), (26, ShardCommand::Restart));
+    }
+
+    #[tokio::test]
+    async fn test_handoffs_go_to_another_pool_over_nats() {
+        let (admin, mut commands) = admin();
+        let to = |pool_id| Json(Handoff { pool_id });
+
+        let response = handoff_handler(State(admin.clone()), HeaderMap::new(), Path(25), to(2)).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = handoff_handler(State(admin.clone()), bearer("s3cret"), Path(25), to(1)).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = handoff_handler(State(admin.clone()), bearer("s3cret"), Path(25), to(2)).await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert!(commands.try_recv().is_err());
+
+        // Drained shards have nothing to hand off
+        admin.admin.shard_state.set_health(26, ShardHealth::Drained);
+        let handoff = ShardCommand::Handoff { to_pool: 2 };
+        assert_eq!(command(&admin, &bearer("s3cret"), 26, handoff).status(), StatusCode::CONFLICT);
+        assert_eq!(command(&admin, &bearer("s3cret"), 25, handoff).status(), StatusCode::ACCEPTED);
+        assert_eq!(commands.try_recv().unwrap(), (25, handoff));
+    }
+
+    #[tokio::test]
+    async fn test_token_rotation_needs_the_admin_token() {
+        let (admin, mut commands) = admin();
+        let new = || Json(NewToken { token: "MTIz.new.secret".to_string() });
+
+        let response = token_handler(State(admin.clone()), HeaderMap::new(), new()).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = token_handler(State(admin), bearer("s3cret"), new()).await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert!(commands.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_dlq_replay_needs_the_token_and_nats() {
+        let (admin, _commands) = admin();
+
+        let response = replay_handler(State(admin.clone()), HeaderMap::new()).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = replay_handler(State(admin), bearer("s3cret")).await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn test_event_replay_is_validated_before_nats() {
+        let (admin, _commands) = admin();
+        let request = |from: &str| {
+            Json(ReplayRequest {
+                subject: "events.member.>".to_string(),
+                from: from.to_string(),
+                to: None,
+                limit: None,
+            })
+        };
+
+        let response = event_replay_handler(State(admin.clone()), HeaderMap::new(), request("2026-01-01T00:00:00Z")).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = event_replay_handler(State(admin.clone()), bearer("s3cret"), request("last week")).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = event_replay_handler(State(admin), bearer("s3cret"), request("2026-01-01T00:00:00Z")).await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+}
@@ -0,0 +1,320 @@
+//! Admin gRPC API
+//!
+//! Typed access to pool and shard state for operator tooling, served on
+//! `ADMIN_GRPC_PORT` with mutual TLS: clients must present a certificate
+//! signed by `ADMIN_GRPC_CLIENT_CA`. The service definition is in
+//! `proto/admin.proto`.
+//!
+//! Besides state, it offers everything the admin HTTP endpoints (`http`) do:
+//! shard restart, drain and handoff, token rotation, dead-letter and event
+//! replay. Both go through `control::Admin`, so the same checks apply; the
+//! client certificate stands in for `ADMIN_TOKEN`. It also reads and writes
+//! the runtime config overrides (`overrides`).
+
+pub mod control;
+pub mod http;
+
+use crate::error::GatewayError;
+use crate::health::{self, AppState};
+use crate::nats::replay::ReplayRequest;
+use crate::overrides::AuditEntry;
+use crate::shard::control::ShardCommand;
+use crate::shard::{ShardHealth, ShardSnapshot};
+use control::{Admin, Refused};
+use std::fmt;
+use std::net::SocketAddr;
+use tonic::transport::{Certificate, Identity, Server, ServerTlsConfig};
+use tonic::{Request, Response, Status};
+use tracing::info;
+
+#[allow(clippy::all, clippy::pedantic)]
+pub mod proto {
+    tonic::include_proto!("arrakis.gateway.admin.v1");
+}
+
+use proto::gateway_admin_server::{GatewayAdmin, GatewayAdminServer};
+
+/// Admin server settings
+#[derive(Clone)]
+pub struct AdminGrpcConfig {
+    pub port: u16,
+    /// Server certificate chain (PEM)
+    pub cert: String,
+    /// Server private key (PEM)
+    pub key: String,
+    /// CA that signs operator client certificates (PEM)
+    pub client_ca: String,
+}
+
+impl fmt::Debug for AdminGrpcConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AdminGrpcConfig")
+            .field("port", &self.port)
+            .field("key", &"<redacted>")
+            .finish_non_exhaustive()
+    }
+}
+
+/// gRPC handlers over the same state as the HTTP endpoints
+struct AdminService {
+    state: AppState,
+    admin: Admin,
+}
+
+#[tonic::async_trait]
+impl GatewayAdmin for AdminService {
+    async fn get_pool(&self, _request: Request<proto::GetPoolRequest>) -> Result<Response<proto::Pool>, Status> {
+        let ready = health::readiness(&self.state);
+        let build = health::build_info(&self.state);
+
+        Ok(Response::new(proto::Pool {
+            pool_id: ready.pool_id,
+            version: build.build.version.to_string(),
+            ready: ready.ready,
+            shards_total: ready.shards_total as u32,
+            shards_ready: ready.shards_ready as u32,
+            nats_connected: ready.nats_connected,
+            guilds_total: ready.guilds_total,
+            degraded_capabilities: ready.degraded_capabilities.iter().map(ToString::to_string).collect(),
+            discord_api_mode: build.build.discord_api.mode.as_str().to_string(),
+            discord_api_version: u32::from(build.build.discord_api.gateway),
+            compression: build.build.compression.to_string(),
+            flags: build
+                .flags
+                .into_iter()
+                .map(|flag| proto::FeatureFlag {
+                    key: flag.key.to_string(),
+                    value: flag.value,
+                    reason: flag.reason,
+                })
+                .collect(),
+        }))
+    }
+
+    async fn list_shards(
+        &self,
+        _request: Request<proto::ListShardsRequest>,
+    ) -> Result<Response<proto::ListShardsResponse>, Status> {
+        Ok(Response::new(proto::ListShardsResponse {
+            shards: self.state.shard_state.snapshots().iter().map(shard_message).collect(),
+        }))
+    }
+
+    async fn get_shard(&self, request: Request<proto::GetShardRequest>) -> Result<Response<proto::Shard>, Status> {
+        let shard_id = request.into_inner().shard_id;
+        self.state
+            .shard_state
+            .snapshot(shard_id)
+            .map(|shard| Response::new(shard_message(&shard)))
+            .ok_or_else(|| {
+                Status::not_found(format!(
+                    "shard {shard_id} is not run by pool {}",
+                    self.state.shard_state.pool_id()
+                ))
+            })
+    }
+
+    async fn restart_shard(
+        &self,
+        request: Request<proto::ShardCommandRequest>,
+    ) -> Result<Response<proto::ShardCommandResponse>, Status> {
+        self.command(request.into_inner().shard_id, ShardCommand::Restart)
+    }
+
+    async fn drain_shard(
+        &self,
+        request: Request<proto::ShardCommandRequest>,
+    ) -> Result<Response<proto::ShardCommandResponse>, Status> {
+        self.command(request.into_inner().shard_id, ShardCommand::Drain)
+    }
+
+    async fn hand_off_shard(
+        &self,
+        request: Request<proto::HandOffShardRequest>,
+    ) -> Result<Response<proto::ShardCommandResponse>, Status> {
+        let request = request.into_inner();
+        let health = self.admin.hand_off(request.shard_id, request.pool_id).map_err(status)?;
+        let command = ShardCommand::Handoff { to_pool: request.pool_id };
+        Ok(Response::new(command_response(request.shard_id, command, health)))
+    }
+
+    async fn rotate_token(
+        &self,
+        request: Request<proto::RotateTokenRequest>,
+    ) -> Result<Response<proto::RotateTokenResponse>, Status> {
+        let restarting = self.admin.rotate_token(request.into_inner().token).await.map_err(status)?;
+        Ok(Response::new(proto::RotateTokenResponse { restarting }))
+    }
+
+    async fn replay_dead_letters(
+        &self,
+        _request: Request<proto::ReplayDeadLettersRequest>,
+    ) -> Result<Response<proto::ReplayDeadLettersResponse>, Status> {
+        let replayed = self.admin.replay_dead_letters().await.map_err(status)?;
+        Ok(Response::new(proto::ReplayDeadLettersResponse {
+            replayed: replayed.replayed as u64,
+            remaining: replayed.remaining as u64,
+            error: replayed.error,
+        }))
+    }
+
+    async fn replay_events(
+        &self,
+        request: Request<proto::ReplayEventsRequest>,
+    ) -> Result<Response<proto::ReplayEventsResponse>, Status> {
+        let request = request.into_inner();
+        let request = ReplayRequest { subject: request.subject, from: request.from, to: request.to, limit: request.limit };
+        let replayed = self.admin.replay_events(&request).await.map_err(status)?;
+        Ok(Response::new(proto::ReplayEventsResponse {
+            replay_id: replayed.replay_id,
+            stream: replayed.stream,
+            target: replayed.target,
+            replayed: replayed.replayed,
+            truncated: replayed.truncated,
+            error: replayed.error,
+        }))
+    }
+
+    async fn get_config(
+        &self,
+        _request: Request<proto::GetConfigRequest>,
+    ) -> Result<Response<proto::ConfigOverrides>, Status> {
+        let Some(ref overrides) = self.admin.overrides else {
+            return Err(status(Refused::NotFound("config overrides are off (DYNAMIC_CONFIG_ENABLED)".to_string())));
+        };
+        let report = overrides.report();
+        Ok(Response::new(proto::ConfigOverrides {
+            bucket: report.bucket.to_string(),
+            active: report.active.into_iter().collect(),
+            audit: report.audit.into_iter().map(config_change).collect(),
+        }))
+    }
+
+    async fn set_config_override(
+        &self,
+        request: Request<proto::SetConfigOverrideRequest>,
+    ) -> Result<Response<proto::SetConfigOverrideResponse>, Status> {
+        let request = request.into_inner();
+        self.admin.set_override(&request.key, request.value).await.map_err(status)?;
+        Ok(Response::new(proto::SetConfigOverrideResponse {}))
+    }
+}
+
+impl AdminService {
+    fn command(&self, shard_id: u64, command: ShardCommand) -> Result<Response<proto::ShardCommandResponse>, Status> {
+        let health = self.admin.command(shard_id, command).map_err(status)?;
+        Ok(Response::new(command_response(shard_id, command, health)))
+    }
+}
+
+fn command_response(shard_id: u64, command: ShardCommand, health: ShardHealth) -> proto::ShardCommandResponse {
+    proto::ShardCommandResponse {
+        shard_id,
+        command: command.as_str().to_string(),
+        health: health_message(health).into(),
+    }
+}
+
+fn config_change(entry: AuditEntry) -> proto::ConfigChange {
+    proto::ConfigChange {
+        key: entry.key,
+        value: entry.value,
+        previous: entry.previous,
+        revision: entry.revision,
+        outcome: entry.outcome.to_string(),
+        error: entry.error,
+        at: entry.at,
+    }
+}
+
+/// gRPC status for a refused request
+fn status(refused: Refused) -> Status {
+    match refused {
+        Refused::NotFound(message) => Status::not_found(message),
+        Refused::Invalid(message) => Status::invalid_argument(message),
+        Refused::Conflict(message) => Status::failed_precondition(message),
+        Refused::Unavailable(message) => Status::unavailable(message),
+        Refused::Failed(message) => Status::internal(message),
+    }
+}
+
+fn health_message(health: ShardHealth) -> proto::ShardHealth {
+    match health {
+        ShardHealth::Connecting => proto::ShardHealth::Connecting,
+        ShardHealth::Ready => proto::ShardHealth::Ready,
+        ShardHealth::Resuming => proto::ShardHealth::Resuming,
+        ShardHealth::Disconnected => proto::ShardHealth::Disconnected,
+        ShardHealth::Dead => proto::ShardHealth::Dead,
+        ShardHealth::Drained => proto::ShardHealth::Drained,
+    }
+}
+
+fn shard_message(shard: &ShardSnapshot) -> proto::Shard {
+    proto::Shard {
+        shard_id: shard.shard_id,
+        health: health_message(shard.health).into(),
+        guilds: shard.guilds,
+        events_received: shard.events_received,
+        events_routed: shard.events_routed,
+        route_failures: shard.route_failures,
+        heartbeat_age_seconds: shard.heartbeat_age.map(|age| age.as_secs_f64()),
+        uptime_seconds: shard.uptime.map(|uptime| uptime.as_secs_f64()),
+        session_uptime_seconds: shard.session_uptime.map(|uptime| uptime.as_secs_f64()),
+        resumable: shard.resumable,
+    }
+}
+
+/// Serve the admin API until the process exits
+pub async fn serve(config: AdminGrpcConfig, state: AppState, admin: Admin) -> Result<(), GatewayError> {
+    let tls = ServerTlsConfig::new()
+        .identity(Identity::from_pem(&config.cert, &config.key))
+        .client_ca_root(Certificate::from_pem(&config.client_ca));
+    let addr: SocketAddr = ([0, 0, 0, 0], config.port).into();
+
+    info!(port = config.port, "Starting admin gRPC server (mTLS)");
+
+    Server::builder()
+        .tls_config(tls)
+        .map_err(|e| GatewayError::AdminGrpcFailed(Box::new(e)))?
+        .add_service(GatewayAdminServer::new(AdminService { state, admin }))
+        .serve(addr)
+        .await
+        .map_err(|e| GatewayError::AdminGrpcFailed(Box::new(e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_shard_message() {
+        let shard = ShardSnapshot {
+            shard_id: 7,
+            health: ShardHealth::Dead,
+            guilds: 1200,
+            events_received: 50,
+            events_routed: 48,
+            route_failures: 2,
+            events_filtered: 0,
+            events_queued: 0,
+            resumes: 0,
+            heartbeat_age: Some(Duration::from_millis(1500)),
+            uptime: None,
+            session_uptime: None,
+            resumable: false,
+        };
+
+        let message = shard_message(&shard);
+        assert_eq!(message.health(), proto::ShardHealth::Dead);
+        assert_eq!(message.heartbeat_age_seconds, Some(1.5));
+        assert_eq!(message.session_uptime_seconds, None);
+    }
+
+    #[test]
+    fn test_refusals_map_to_grpc_codes() {
+        assert_eq!(status(Refused::NotFound("shard 3".to_string())).code(), tonic::Code::NotFound);
+        assert_eq!(status(Refused::Conflict("not running".to_string())).code(), tonic::Code::FailedPrecondition);
+        assert_eq!(status(Refused::Unavailable("no NATS".to_string())).code(), tonic::Code::Unavailable);
+    }
+}
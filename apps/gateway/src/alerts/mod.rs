@@ -0,0 +1,368 @@
+//! Operational alerts
+//!
+//! Watches for conditions the on-call team has to hear about: dead shards,
+//! NATS outages, publish queues nearing capacity, and shards whose received
+//! events no longer add up. Notifications go out
+//! over direct HTTP rather than NATS, so a broker outage still gets reported
+//! when the broker is the thing that's down.
+//!
+//! Every alert is posted to the Discord webhook; fatal ones also page through
+//! PagerDuty or Opsgenie.
+
+mod pager;
+mod webhook;
+
+pub use pager::{Pager, PagerProvider, PagerTarget};
+pub use webhook::{DiscordWebhook, WebhookTarget};
+
+use crate::error::GatewayError;
+use crate::metrics::GatewayMetrics;
+use crate::nats::NatsPublisher;
+use crate::shard::ShardState;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+/// How often the monitor checks alert conditions
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Ops alert settings
+#[derive(Debug, Clone, Default)]
+pub struct OpsAlertConfig {
+    /// Discord webhook to notify (`OPS_ALERT_WEBHOOK_URL`)
+    pub webhook: Option<WebhookTarget>,
+    /// Pager for fatal conditions (`OPS_ALERT_PAGER`)
+    pub pager: Option<PagerTarget>,
+    /// How long NATS must be unreachable before alerting
+    pub nats_outage: Duration,
+    /// Publish queue depth that counts as nearly full (None disables)
+    pub queue_depth: Option<u64>,
+}
+
+/// A condition that can fire an alert
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Condition {
+    ShardDead { shard_id: u64 },
+    NatsOutage,
+    QueueNearlyFull { lane: &'static str },
+    EventsDiverged { shard_id: u64 },
+}
+
+impl Condition {
+    /// Label for metrics and logs
+    pub const fn kind(&self) -> &'static str {
+        match self {
+            Self::ShardDead { .. } => "shard_dead",
+            Self::NatsOutage => "nats_outage",
+            Self::QueueNearlyFull { .. } => "queue_nearly_full",
+            Self::EventsDiverged { .. } => "events_diverged",
+        }
+    }
+}
+
+/// Whether an alert is starting or clearing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertStatus {
+    Firing,
+    Resolved,
+}
+
+/// A notification to send
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alert {
+    pub pool_id: u64,
+    /// Set for the pools of the process's other bots (`BOTS`)
+    pub bot_id: Option<String>,
+    pub condition: Condition,
+    pub status: AlertStatus,
+    /// Human-readable description, captured when the alert fired
+    pub summary: String,
+}
+
+impl Alert {
+    /// `pool 2`, or `staging pool 2` for another bot's pool
+    pub fn pool(&self) -> String {
+        match self.bot_id {
+            Some(ref bot_id) => format!("{bot_id} pool {}", self.pool_id),
+            None => format!("pool {}", self.pool_id),
+        }
+    }
+}
+
+/// Gateway state as seen by one monitor check
+#[derive(Debug, Default)]
+pub struct Observation {
+    pub dead_shards: Vec<u64>,
+    /// How long NATS has been unreachable (None while connected)
+    pub nats_down_for: Option<Duration>,
+    pub queue_depths: Vec<(&'static str, u64)>,
+    /// Shards flagged by the divergence watchdog, with the events unaccounted for
+    pub diverged_shards: Vec<(u64, u64)>,
+}
+
+/// Edge-triggered condition tracking: an alert fires once when its condition
+/// starts and resolves once when it clears.
+#[derive(Debug)]
+pub struct AlertTracker {
+    pool_id: u64,
+    bot_id: Option<String>,
+    nats_outage: Duration,
+    queue_depth: Option<u64>,
+    active: BTreeMap<Condition, String>,
+}
+
+impl AlertTracker {
+    pub fn new(pool_id: u64, bot_id: Option<String>, config: &OpsAlertConfig) -> Self {
+        Self {
+            pool_id,
+            bot_id,
+            nats_outage: config.nats_outage,
+            queue_depth: config.queue_depth,
+            active: BTreeMap::new(),
+        }
+    }
+
+    /// Compare an observation against the active alerts
+    pub fn evaluate(&mut self, observation: &Observation) -> Vec<Alert> {
+        let mut current = BTreeMap::new();
+
+        for &shard_id in &observation.dead_shards {
+            current.insert(Condition::ShardDead { shard_id }, format!("Shard {shard_id} is dead"));
+        }
+        if let Some(down_for) = observation.nats_down_for.filter(|d| *d >= self.nats_outage) {
+            current.insert(
+                Condition::NatsOutage,
+                format!("NATS unreachable for {}s", down_for.as_secs()),
+            );
+        }
+        if let Some(threshold) = self.queue_depth {
+            for &(lane, depth) in observation.queue_depths.iter().filter(|(_, d)| *d >= threshold) {
+                current.insert(
+                    Condition::QueueNearlyFull { lane },
+                    format!("Publish queue `{lane}` nearly full ({depth} queued, threshold {threshold})"),
+                );
+            }
+        }
+
+        for &(shard_id, unaccounted) in &observation.diverged_shards {
+            current.insert(
+                Condition::EventsDiverged { shard_id },
+                format!("Shard {shard_id}: {unaccounted} received events not routed, filtered or failed"),
+            );
+        }
+
+        let cleared: Vec<Condition> = self
+            .active
+            .keys()
+            .filter(|condition| !current.contains_key(condition))
+            .cloned()
+            .collect();
+
+        let mut alerts = Vec::new();
+        for condition in cleared {
+            if let Some(summary) = self.active.remove(&condition) {
+                alerts.push(self.alert(condition, AlertStatus::Resolved, summary));
+            }
+        }
+        for (condition, summary) in current {
+            if !self.active.contains_key(&condition) {
+                alerts.push(self.alert(condition.clone(), AlertStatus::Firing, summary.clone()));
+                self.active.insert(condition, summary);
+            }
+        }
+
+        alerts
+    }
+
+    fn alert(&self, condition: Condition, status: AlertStatus, summary: String) -> Alert {
+        Alert {
+            pool_id: self.pool_id,
+            bot_id: self.bot_id.clone(),
+            condition,
+            status,
+            summary,
+        }
+    }
+}
+
+/// Configured alert destinations
+pub struct Notifiers {
+    webhook: Option<DiscordWebhook>,
+    pager: Option<Pager>,
+}
+
+impl Notifiers {
+    /// Build the configured notifiers; None when no destination is set
+    pub fn from_config(config: &OpsAlertConfig) -> Result<Option<Self>, GatewayError> {
+        let webhook = config.webhook.clone().map(DiscordWebhook::new);
+        let pager = config.pager.clone().map(Pager::new).transpose()?;
+
+        if webhook.is_none() && pager.is_none() {
+            return Ok(None);
+        }
+        Ok(Some(Self { webhook, pager }))
+    }
+
+    /// Send an alert to every destination that takes it
+    async fn notify(&self, alert: &Alert, metrics: &GatewayMetrics) {
+        if let Some(ref webhook) = self.webhook {
+            report(alert, "discord_webhook", webhook.send(alert).await, metrics);
+        }
+        if let Some(ref pager) = self.pager {
+            if Pager::pages(&alert.condition) {
+                report(alert, "pager", pager.send(alert).await, metrics);
+            }
+        }
+    }
+}
+
+fn report(alert: &Alert, sink: &'static str, result: Result<(), GatewayError>, metrics: &GatewayMetrics) {
+    let kind = alert.condition.kind();
+    match result {
+        Ok(()) => {
+            metrics.record_ops_alert(kind, sink, "sent");
+            info!(alert = kind, sink, status = ?alert.status, summary = %alert.summary, "Ops alert sent");
+        }
+        Err(e) => {
+            metrics.record_ops_alert(kind, sink, "failed");
+            warn!(alert = kind, sink, error = %e, "Failed to send ops alert");
+        }
+    }
+}
+
+/// Check alert conditions until the process exits, notifying every
+/// configured destination.
+///
+/// `nats_expected` is true when NATS_URL is set: a pool that failed to
+/// connect at startup (`nats` is None) counts as an outage. The pools of
+/// the other bots (`bots`) are watched for dead and diverged shards; NATS and
+/// the publish queues are the process's, reported once, for `state`'s pool.
+pub async fn run_monitor(
+    config: OpsAlertConfig,
+    notifiers: Notifiers,
+    state: ShardState,
+    bots: Vec<(String, ShardState)>,
+    nats: Option<Arc<NatsPublisher>>,
+    nats_expected: bool,
+    metrics: Arc<GatewayMetrics>,
+) {
+    let mut tracker = AlertTracker::new(state.pool_id(), None, &config);
+    let mut bot_trackers: Vec<_> = bots
+        .into_iter()
+        .map(|(bot_id, state)| (AlertTracker::new(state.pool_id(), Some(bot_id), &config), state))
+        .collect();
+    let mut nats_down_since: Option<Instant> = None;
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+
+    info!(pool_id = state.pool_id(), bots = bot_trackers.len(), "Ops alert monitor started");
+
+    loop {
+        interval.tick().await;
+
+        let nats_up = !nats_expected || nats.as_ref().is_some_and(|n| n.is_server_connected());
+        nats_down_since = if nats_up {
+            None
+        } else {
+            Some(nats_down_since.unwrap_or_else(Instant::now))
+        };
+
+        let observation = Observation {
+            dead_shards: state.dead_shards(),
+            nats_down_for: nats_down_since.map(|since| since.elapsed()),
+            queue_depths: metrics.publish_queue_depths(),
+            diverged_shards: state.diverged_shards(),
+        };
+        let mut alerts = tracker.evaluate(&observation);
+        for (tracker, state) in &mut bot_trackers {
+            alerts.extend(tracker.evaluate(&Observation {
+                dead_shards: state.dead_shards(),
+                diverged_shards: state.diverged_shards(),
+                ..Observation::default()
+            }));
+        }
+
+        for alert in alerts {
+            notifiers.notify(&alert, &metrics).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracker() -> AlertTracker {
+        AlertTracker::new(
+            2,
+            None,
+            &OpsAlertConfig {
+                webhook: None,
+                pager: None,
+                nats_outage: Duration::from_secs(30),
+                queue_depth: Some(100),
+            },
+        )
+    }
+
+    #[test]
+    fn test_alert_fires_once_and_resolves() {
+        let mut tracker = tracker();
+        let dead = Observation {
+            dead_shards: vec![7],
+            ..Default::default()
+        };
+
+        let alerts = tracker.evaluate(&dead);
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].condition, Condition::ShardDead { shard_id: 7 });
+        assert_eq!(alerts[0].status, AlertStatus::Firing);
+        assert!(tracker.evaluate(&dead).is_empty());
+
+        let alerts = tracker.evaluate(&Observation::default());
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].status, AlertStatus::Resolved);
+        assert!(tracker.evaluate(&Observation::default()).is_empty());
+    }
+
+    #[test]
+    fn test_thresholds_gate_nats_and_queue_alerts() {
+        let mut tracker = tracker();
+        let below = Observation {
+            nats_down_for: Some(Duration::from_secs(10)),
+            queue_depths: vec![("events", 99)],
+            ..Default::default()
+        };
+        assert!(tracker.evaluate(&below).is_empty());
+
+        let above = Observation {
+            nats_down_for: Some(Duration::from_secs(45)),
+            queue_depths: vec![("events", 150)],
+            ..Default::default()
+        };
+        let alerts = tracker.evaluate(&above);
+        assert_eq!(alerts.len(), 2);
+        assert!(alerts.iter().any(|a| a.condition == Condition::NatsOutage));
+        assert!(alerts.iter().any(|a| a.condition == Condition::QueueNearlyFull { lane: "events" }));
+    }
+
+    #[test]
+    fn test_divergence_alert_keeps_its_first_summary() {
+        let mut tracker = tracker();
+        let diverged = |unaccounted| Observation {
+            diverged_shards: vec![(3, unaccounted)],
+            ..Default::default()
+        };
+
+        let alerts = tracker.evaluate(&diverged(25));
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].condition, Condition::EventsDiverged { shard_id: 3 });
+        assert_eq!(alerts[0].summary, "Shard 3: 25 received events not routed, filtered or failed");
+        assert!(tracker.evaluate(&diverged(40)).is_empty());
+
+        let alerts = tracker.evaluate(&Observation::default());
+        assert_eq!(alerts[0].status, AlertStatus::Resolved);
+        assert_eq!(alerts[0].summary, "Shard 3: 25 received events not routed, filtered or failed");
+    }
+}
@@ -0,0 +1,230 @@
+//! Pager notifier (PagerDuty Events API v2 / Opsgenie Alert API)
+//!
+//! Only fatal conditions page: a dead shard, or NATS unreachable long enough
+//! that the publisher is effectively circuit-open. Alerts are keyed per shard
+//! and per pool, so repeated checks update one incident and resolution closes it.
+
+use super::{Alert, AlertStatus, Condition};
+use crate::error::GatewayError;
+use serde_json::{json, Value};
+use std::fmt;
+use std::time::Duration;
+
+const PAGERDUTY_URL: &str = "https://events.pagerduty.com/v2/enqueue";
+const OPSGENIE_URL: &str = "https://api.opsgenie.com";
+
+/// Timeout for a single pager request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Incident management provider
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PagerProvider {
+    PagerDuty,
+    Opsgenie,
+}
+
+impl PagerProvider {
+    /// Parse an `OPS_ALERT_PAGER` value
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "pagerduty" => Some(Self::PagerDuty),
+            "opsgenie" => Some(Self::Opsgenie),
+            _ => None,
+        }
+    }
+
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::PagerDuty => "pagerduty",
+            Self::Opsgenie => "opsgenie",
+        }
+    }
+}
+
+impl fmt::Display for PagerProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Provider, credentials, and endpoint for pager alerts
+#[derive(Clone, PartialEq, Eq)]
+pub struct PagerTarget {
+    pub provider: PagerProvider,
+    /// PagerDuty routing key or Opsgenie API key
+    pub key: String,
+    /// Endpoint override (e.g. Opsgenie's EU instance)
+    pub url: Option<String>,
+}
+
+// Routing/API keys grant alert creation: keep them out of config dumps and logs
+impl fmt::Debug for PagerTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PagerTarget")
+            .field("provider", &self.provider)
+            .field("key", &"<redacted>")
+            .field("url", &self.url)
+            .finish()
+    }
+}
+
+/// Sends fatal alerts to PagerDuty or Opsgenie
+pub struct Pager {
+    http: reqwest::Client,
+    target: PagerTarget,
+}
+
+impl Pager {
+    pub fn new(target: PagerTarget) -> Result<Self, GatewayError> {
+        let http = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(|e| GatewayError::Config(format!("Failed to build pager HTTP client: {e}")))?;
+        Ok(Self { http, target })
+    }
+
+    /// Whether a condition is severe enough to page
+    pub const fn pages(condition: &Condition) -> bool {
+        matches!(condition, Condition::ShardDead { .. } | Condition::NatsOutage)
+    }
+
+    /// Trigger or resolve the incident for an alert
+    pub async fn send(&self, alert: &Alert) -> Result<(), GatewayError> {
+        let request = match self.target.provider {
+            PagerProvider::PagerDuty => self
+                .http
+                .post(self.target.url.as_deref().unwrap_or(PAGERDUTY_URL))
+                .json(&pagerduty_event(alert, &self.target.key)),
+            PagerProvider::Opsgenie => {
+                let base = self.target.url.as_deref().unwrap_or(OPSGENIE_URL);
+                let (url, body) = opsgenie_request(base, alert);
+                self.http
+                    .post(url)
+                    .header("Authorization", format!("GenieKey {}", self.target.key))
+                    .json(&body)
+            }
+        };
+
+        request
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|e| GatewayError::PagerRequestFailed {
+                provider: self.target.provider.as_str(),
+                source: Box::new(e),
+            })?;
+        Ok(())
+    }
+}
+
+/// Incident key: one per shard for dead shards, one per pool for NATS
+pub fn dedup_key(alert: &Alert) -> String {
+    let pool = pool_key(alert);
+    match &alert.condition {
+        Condition::ShardDead { shard_id } => format!("arrakis-gateway:{pool}:shard-{shard_id}"),
+        Condition::NatsOutage => format!("arrakis-gateway:{pool}:nats"),
+        Condition::QueueNearlyFull { lane } => format!("arrakis-gateway:{pool}:queue-{lane}"),
+        Condition::EventsDiverged { shard_id } => format!("arrakis-gateway:{pool}:divergence-{shard_id}"),
+    }
+}
+
+/// `pool-2`, or `staging-pool-2` for another bot's pool
+fn pool_key(alert: &Alert) -> String {
+    alert.pool().replace(' ', "-")
+}
+
+/// PagerDuty Events API v2 body
+fn pagerduty_event(alert: &Alert, routing_key: &str) -> Value {
+    match alert.status {
+        AlertStatus::Firing => json!({
+            "routing_key": routing_key,
+            "event_action": "trigger",
+            "dedup_key": dedup_key(alert),
+            "payload": {
+                "summary": format!("arrakis-gateway {}: {}", alert.pool(), alert.summary),
+                "source": format!("arrakis-gateway-{}", pool_key(alert)),
+                "severity": "critical",
+                "component": "gateway",
+                "group": pool_key(alert),
+                "class": alert.condition.kind(),
+            },
+        }),
+        AlertStatus::Resolved => json!({
+            "routing_key": routing_key,
+            "event_action": "resolve",
+            "dedup_key": dedup_key(alert),
+        }),
+    }
+}
+
+/// Opsgenie create or close-by-alias request
+fn opsgenie_request(base: &str, alert: &Alert) -> (String, Value) {
+    let base = base.trim_end_matches('/');
+    let alias = dedup_key(alert);
+    let source = format!("arrakis-gateway-{}", pool_key(alert));
+
+    match alert.status {
+        AlertStatus::Firing => (
+            format!("{base}/v2/alerts"),
+            json!({
+                "message": format!("arrakis-gateway {}: {}", alert.pool(), alert.summary),
+                "alias": alias,
+                "priority": "P1",
+                "source": source,
+                "tags": ["arrakis-gateway", alert.condition.kind()],
+            }),
+        ),
+        AlertStatus::Resolved => (
+            format!("{base}/v2/alerts/{alias}/close?identifierType=alias"),
+            json!({ "source": source, "note": "Resolved by gateway" }),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alert(status: AlertStatus) -> Alert {
+        Alert {
+            pool_id: 2,
+            bot_id: None,
+            condition: Condition::ShardDead { shard_id: 57 },
+            status,
+            summary: "Shard 57 is dead".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_pagerduty_trigger_and_resolve_share_dedup_key() {
+        let trigger = pagerduty_event(&alert(AlertStatus::Firing), "rk");
+        assert_eq!(trigger["event_action"], "trigger");
+        assert_eq!(trigger["dedup_key"], "arrakis-gateway:pool-2:shard-57");
+        assert_eq!(trigger["payload"]["severity"], "critical");
+
+        let resolve = pagerduty_event(&alert(AlertStatus::Resolved), "rk");
+        assert_eq!(resolve["event_action"], "resolve");
+        assert_eq!(resolve["dedup_key"], trigger["dedup_key"]);
+    }
+
+    #[test]
+    fn test_opsgenie_closes_by_alias() {
+        let (url, body) = opsgenie_request("https://api.eu.opsgenie.com/", &alert(AlertStatus::Firing));
+        assert_eq!(url, "https://api.eu.opsgenie.com/v2/alerts");
+        assert_eq!(body["alias"], "arrakis-gateway:pool-2:shard-57");
+
+        let (url, _) = opsgenie_request(OPSGENIE_URL, &alert(AlertStatus::Resolved));
+        assert_eq!(
+            url,
+            "https://api.opsgenie.com/v2/alerts/arrakis-gateway:pool-2:shard-57/close?identifierType=alias"
+        );
+    }
+
+    #[test]
+    fn test_only_fatal_conditions_page() {
+        assert!(Pager::pages(&Condition::ShardDead { shard_id: 1 }));
+        assert!(Pager::pages(&Condition::NatsOutage));
+        assert!(!Pager::pages(&Condition::QueueNearlyFull { lane: "events" }));
+        assert!(!Pager::pages(&Condition::EventsDiverged { shard_id: 1 }));
+    }
+}
@@ -0,0 +1,129 @@
+//! Discord webhook notifier for ops alerts
+
+use super::{Alert, AlertStatus};
+use crate::error::GatewayError;
+use std::fmt;
+use twilight_http::Client;
+use twilight_model::channel::message::AllowedMentions;
+use twilight_model::id::marker::WebhookMarker;
+use twilight_model::id::Id;
+
+/// Webhook ID and token parsed from a webhook URL
+#[derive(Clone, PartialEq, Eq)]
+pub struct WebhookTarget {
+    pub id: Id<WebhookMarker>,
+    pub token: String,
+}
+
+impl WebhookTarget {
+    /// Parse `https://discord.com/api/webhooks/{id}/{token}` (any API host or
+    /// version prefix)
+    pub fn parse(url: &str) -> Result<Self, GatewayError> {
+        let invalid = || GatewayError::Config("OPS_ALERT_WEBHOOK_URL is not a Discord webhook URL".to_string());
+
+        let (_, path) = url.split_once("/webhooks/").ok_or_else(invalid)?;
+        let path = path.split(['?', '#']).next().unwrap_or_default();
+        let mut parts = path.split('/');
+
+        let id = parts
+            .next()
+            .and_then(|id| id.parse().ok())
+            .and_then(Id::new_checked)
+            .ok_or_else(invalid)?;
+        let token = parts.next().filter(|t| !t.is_empty()).ok_or_else(invalid)?;
+
+        Ok(Self {
+            id,
+            token: token.to_string(),
+        })
+    }
+}
+
+// The token grants posting rights: keep it out of config dumps and logs
+impl fmt::Debug for WebhookTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebhookTarget")
+            .field("id", &self.id)
+            .field("token", &"<redacted>")
+            .finish()
+    }
+}
+
+/// Posts ops alerts to a Discord channel webhook
+pub struct DiscordWebhook {
+    client: Client,
+    target: WebhookTarget,
+    no_mentions: AllowedMentions,
+}
+
+impl DiscordWebhook {
+    pub fn new(target: WebhookTarget) -> Self {
+        Self {
+            // Webhook execution is authorized by the token in the URL
+            client: Client::builder().build(),
+            target,
+            no_mentions: AllowedMentions::default(),
+        }
+    }
+
+    /// Post one alert
+    pub async fn send(&self, alert: &Alert) -> Result<(), GatewayError> {
+        let content = message(alert);
+        self.client
+            .execute_webhook(self.target.id, &self.target.token)
+            .username("arrakis-gateway")
+            .allowed_mentions(Some(&self.no_mentions))
+            .content(&content)
+            .await
+            .map_err(|e| GatewayError::DiscordRequestFailed {
+                route: "POST /webhooks/{webhook.id}/{webhook.token}",
+                source: Box::new(e),
+            })?;
+        Ok(())
+    }
+}
+
+/// Message text for an alert
+fn message(alert: &Alert) -> String {
+    let pool = match alert.bot_id {
+        Some(_) => alert.pool(),
+        None => format!("Pool {}", alert.pool_id),
+    };
+    match alert.status {
+        AlertStatus::Firing => format!("🔴 **{pool}**: {}", alert.summary),
+        AlertStatus::Resolved => format!("🟢 **{pool}** resolved: {}", alert.summary),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alerts::Condition;
+
+    #[test]
+    fn test_parse_webhook_url() {
+        let target = WebhookTarget::parse("https://discord.com/api/v10/webhooks/123456/abc-DEF_tok?wait=true").unwrap();
+        assert_eq!(target.id.get(), 123456);
+        assert_eq!(target.token, "abc-DEF_tok");
+        assert!(!format!("{target:?}").contains("abc-DEF_tok"));
+
+        assert!(WebhookTarget::parse("https://discord.com/api/webhooks/0/tok").is_err());
+        assert!(WebhookTarget::parse("https://discord.com/api/webhooks/123456").is_err());
+        assert!(WebhookTarget::parse("https://example.com/hook").is_err());
+    }
+
+    #[test]
+    fn test_message_marks_status() {
+        let mut alert = Alert {
+            pool_id: 3,
+            bot_id: None,
+            condition: Condition::ShardDead { shard_id: 77 },
+            status: AlertStatus::Resolved,
+            summary: "Shard 77 is dead".to_string(),
+        };
+        assert_eq!(message(&alert), "🟢 **Pool 3** resolved: Shard 77 is dead");
+
+        alert.bot_id = Some("staging".to_string());
+        assert_eq!(message(&alert), "🟢 **staging pool 3** resolved: Shard 77 is dead");
+    }
+}
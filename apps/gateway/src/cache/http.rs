@@ -0,0 +1,41 @@
+//! `GET /cache/guilds/{guild_id}`, served next to the health endpoints when
+//! `GUILD_CACHE_ENABLED` is set. A guild another pool runs is a 404 here;
+//! `gateway.requests.guild` reaches whichever pool has it.
+
+use super::GuildCache;
+use crate::shard::ShardState;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::routing::get;
+use axum::{Json, Router};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use twilight_model::id::Id;
+
+#[derive(Clone)]
+struct CacheHttp {
+    cache: Arc<GuildCache>,
+    shard_state: ShardState,
+}
+
+/// Cached guild lookups
+pub fn router(cache: Arc<GuildCache>, shard_state: ShardState) -> Router {
+    Router::new()
+        .route("/cache/guilds/{guild_id}", get(guild_handler))
+        .with_state(CacheHttp { cache, shard_state })
+}
+
+async fn guild_handler(State(http): State<CacheHttp>, Path(guild_id): Path<String>) -> (StatusCode, Json<Value>) {
+    let Some(guild_id) = guild_id.parse().ok().and_then(Id::new_checked) else {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "error": format!("invalid guild ID {guild_id:?}") })));
+    };
+    match http.cache.guild(guild_id) {
+        Some(guild) => (StatusCode::OK, Json(guild)),
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": format!("guild {guild_id} is not cached by pool {}", http.shard_state.pool_id())
+            })),
+        ),
+    }
+}
@@ -0,0 +1,193 @@
+//! In-memory guild cache
+//!
+//! Every shard feeds its events into a Twilight in-memory cache holding
+//! guilds, channels and roles, so workers can read a guild's metadata,
+//! channel list and role map from the gateway instead of Discord REST. A
+//! pool caches only its own shards' guilds. Served at
+//! `GET /cache/guilds/{guild_id}` and on `gateway.requests.guild`
+//! (`nats::guilds`), both answering with `fixtures/cached-guild.json`.
+//! `redis` writes guild, role and member state to Redis for workers that
+//! don't talk to the gateway at all.
+
+pub mod http;
+pub mod redis;
+
+use crate::events::entities;
+use serde_json::{json, Map, Value};
+use twilight_cache_inmemory::{DefaultInMemoryCache, ResourceType};
+use twilight_model::gateway::event::Event;
+use twilight_model::id::marker::GuildMarker;
+use twilight_model::id::Id;
+
+/// Rough per-entry footprints for the memory report
+const GUILD_BYTES: u64 = 1024;
+const CHANNEL_BYTES: u64 = 256;
+const ROLE_BYTES: u64 = 256;
+
+/// Guilds, channels and roles of this pool's shards
+pub struct GuildCache {
+    cache: DefaultInMemoryCache,
+}
+
+impl std::fmt::Debug for GuildCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let stats = self.cache.stats();
+        f.debug_struct("GuildCache").field("guilds", &stats.guilds()).field("channels", &stats.channels()).finish()
+    }
+}
+
+impl Default for GuildCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GuildCache {
+    pub fn new() -> Self {
+        Self {
+            cache: DefaultInMemoryCache::builder()
+                .resource_types(ResourceType::GUILD | ResourceType::CHANNEL | ResourceType::ROLE)
+                .build(),
+        }
+    }
+
+    /// Apply an event received by a shard
+    pub fn update(&self, event: &Event) {
+        self.cache.update(event);
+    }
+
+    /// Approximate footprint, for `/debug/memory`
+    pub fn estimated_bytes(&self) -> u64 {
+        let stats = self.cache.stats();
+        stats.guilds() as u64 * GUILD_BYTES + stats.channels() as u64 * CHANNEL_BYTES + stats.roles() as u64 * ROLE_BYTES
+    }
+
+    /// A guild's metadata, channels (by position, threads left out) and
+    /// roles keyed by ID; None if this pool hasn't cached it
+    pub fn guild(&self, guild_id: Id<GuildMarker>) -> Option<Value> {
+        let guild = self.cache.guild(guild_id)?;
+
+        let mut channels: Vec<_> = self
+            .cache
+            .guild_channels(guild_id)
+            .map(|ids| ids.iter().filter_map(|id| self.cache.channel(*id)).collect())
+            .unwrap_or_default();
+        channels.retain(|channel| !channel.kind.is_thread());
+        channels.sort_by_key(|channel| (channel.position, channel.id));
+
+        let roles: Map<String, Value> = self
+            .cache
+            .guild_roles(guild_id)
+            .map(|ids| {
+                ids.iter()
+                    .filter_map(|id| self.cache.role(*id))
+                    .map(|role| (role.id.to_string(), entities::guild_role(role.resource())))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Some(json!({
+            "guild_id": guild.id().to_string(),
+            "name": guild.name(),
+            "icon": guild.icon().map(ToString::to_string),
+            "owner_id": guild.owner_id().to_string(),
+            "member_count": guild.member_count(),
+            "preferred_locale": guild.preferred_locale(),
+            "premium_tier": u8::from(guild.premium_tier()),
+            "features": guild.features().map(|feature| json!(feature)).collect::<Vec<_>>(),
+            "channels": channels.iter().map(|channel| entities::guild_channel(channel)).collect::<Vec<_>>(),
+            "roles": roles,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::policy;
+    use twilight_model::gateway::payload::incoming::{GuildCreate, RoleDelete};
+    use twilight_model::guild::Guild;
+    use twilight_model::id::marker::RoleMarker;
+
+    fn fixture(name: &str) -> Value {
+        let path = format!("{}/../../packages/shared/nats-schemas/fixtures/{name}.json", env!("CARGO_MANIFEST_DIR"));
+        serde_json::from_str(&std::fs::read_to_string(path).expect("Failed to read fixture")).unwrap()
+    }
+
+    fn guild_create() -> Event {
+        let guild: Guild = serde_json::from_value(json!({
+            "id": "123456789012345678",
+            "name": "Honey Jar",
+            "icon": null,
+            "owner_id": "987654321098765432",
+            "member_count": 12345,
+            "preferred_locale": "en-US",
+            "premium_tier": 2,
+            "premium_progress_bar_enabled": false,
+            "features": ["COMMUNITY"],
+            "afk_timeout": 300,
+            "default_message_notifications": 1,
+            "explicit_content_filter": 2,
+            "mfa_level": 0,
+            "nsfw_level": 0,
+            "system_channel_flags": 0,
+            "verification_level": 1,
+            "emojis": [],
+            "stickers": [],
+            "roles": [{
+                "id": "888888888888888888",
+                "name": "Holder",
+                "color": 15844367,
+                "colors": { "primary_color": 15844367 },
+                "hoist": true,
+                "icon": null,
+                "unicode_emoji": "🍯",
+                "position": 5,
+                "permissions": "104324673",
+                "managed": false,
+                "mentionable": true,
+                "flags": 0
+            }],
+            "channels": [
+                { "id": "555555555555555555", "type": 0, "name": "general", "position": 0,
+                  "topic": null, "nsfw": false, "parent_id": null },
+                { "id": "333333333333333333", "type": 0, "name": "holders-lounge", "position": 4,
+                  "topic": "Verified holders only", "nsfw": false, "parent_id": "444444444444444444" }
+            ],
+            "threads": [],
+            "members": [],
+            "presences": [],
+            "voice_states": [],
+            "stage_instances": [],
+            "guild_scheduled_events": []
+        }))
+        .unwrap();
+        Event::GuildCreate(Box::new(GuildCreate::Available(guild)))
+    }
+
+    #[test]
+    fn snapshot_matches_fixture() {
+        let cache = GuildCache::new();
+        cache.update(&guild_create());
+
+        let guild = cache.guild(Id::new(123456789012345678)).expect("guild is cached");
+        assert_eq!(guild, fixture("cached-guild"));
+        assert_eq!(policy::violations(&guild), Vec::<String>::new());
+        assert_eq!(cache.cache.stats().guilds(), 1);
+        assert!(cache.estimated_bytes() > 0);
+    }
+
+    #[test]
+    fn role_events_update_the_snapshot() {
+        let cache = GuildCache::new();
+        cache.update(&guild_create());
+        cache.update(&Event::RoleDelete(RoleDelete {
+            guild_id: Id::new(123456789012345678),
+            role_id: Id::<RoleMarker>::new(888888888888888888),
+        }));
+
+        let guild = cache.guild(Id::new(123456789012345678)).unwrap();
+        assert_eq!(guild["roles"], json!({}));
+        assert!(cache.guild(Id::new(1)).is_none());
+    }
+}
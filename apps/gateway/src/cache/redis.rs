@@ -0,0 +1,366 @@
+//! Guild, role and member state written to Redis
+//!
+//! Stateless workers (in any language) read entity state from Redis hashes
+//! instead of holding a gateway connection. Shards hand the events they
+//! receive to `RedisCache::observe`, which turns them into writes queued for
+//! one writer task, so a slow Redis never holds up a shard; writes that find
+//! the queue full are dropped and counted.
+//!
+//! Keys, under `REDIS_CACHE_PREFIX`:
+//!
+//! - `{prefix}:guild:{guild_id}`: name, icon, owner_id, member_count,
+//!   preferred_locale, premium_tier
+//! - `{prefix}:guild:{guild_id}:roles`: set of the guild's role IDs
+//! - `{prefix}:role:{role_id}`: the `role.*` data fields plus guild_id
+//! - `{prefix}:member:{guild_id}:{user_id}`: the member-list fields
+//!
+//! Fields are strings: snowflakes and numbers as text, booleans as
+//! `true`/`false`, null as an empty string, and arrays and objects (a
+//! member's `roles` and `user`) as JSON. Every write refreshes the key's TTL,
+//! which bounds how long an entry whose delete the gateway missed survives.
+//!
+//! `REDACTION_POLICY` does not apply here: member hashes hold usernames and
+//! nicknames as Discord sent them, so Redis needs the access control the
+//! unredacted events would.
+//! Delete events remove their keys; leaving a guild removes it and its roles
+//! (its members expire).
+
+use crate::error::GatewayError;
+use crate::events::entities;
+use crate::metrics::GatewayMetrics;
+use redis::aio::ConnectionManager;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+use twilight_model::gateway::event::Event;
+use twilight_model::gateway::payload::incoming::GuildCreate;
+use twilight_model::guild::{Member, PartialGuild, Role};
+use twilight_model::id::marker::{GuildMarker, RoleMarker, UserMarker};
+use twilight_model::id::Id;
+
+/// Batches of writes waiting for the writer task
+const QUEUE_CAPACITY: usize = 10_000;
+
+/// Redis cache settings
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedisCacheConfig {
+    /// `redis://` URL (`REDIS_CACHE_URL`)
+    pub url: String,
+    /// Key prefix (`REDIS_CACHE_PREFIX`)
+    pub prefix: String,
+    /// Expiry refreshed on every write (`REDIS_CACHE_TTL_SECS`)
+    pub ttl: Duration,
+}
+
+/// One Redis change
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Write {
+    /// Set hash fields and refresh the key's TTL
+    Hash { key: String, fields: Vec<(String, String)> },
+    /// Add a member to a set and refresh its TTL
+    SetAdd { key: String, member: String },
+    SetRemove { key: String, member: String },
+    Delete(String),
+    /// Delete the role keys listed in a guild's role set, then the set
+    DeleteRoles { roles_key: String },
+}
+
+/// Key layout under a prefix
+#[derive(Debug, Clone)]
+struct Keys {
+    prefix: String,
+}
+
+impl Keys {
+    fn guild(&self, guild_id: Id<GuildMarker>) -> String {
+        format!("{}:guild:{guild_id}", self.prefix)
+    }
+
+    fn roles(&self, guild_id: Id<GuildMarker>) -> String {
+        format!("{}:guild:{guild_id}:roles", self.prefix)
+    }
+
+    fn role(&self, role_id: impl std::fmt::Display) -> String {
+        format!("{}:role:{role_id}", self.prefix)
+    }
+
+    fn member(&self, guild_id: Id<GuildMarker>, user_id: Id<UserMarker>) -> String {
+        format!("{}:member:{guild_id}:{user_id}", self.prefix)
+    }
+
+    fn write_guild(&self, guild_id: Id<GuildMarker>, fields: Value) -> Write {
+        Write::Hash { key: self.guild(guild_id), fields: hash_fields(&fields) }
+    }
+
+    fn write_role(&self, writes: &mut Vec<Write>, guild_id: Id<GuildMarker>, role: &Role) {
+        let mut fields = entities::guild_role(role);
+        fields["guild_id"] = guild_id.to_string().into();
+        writes.push(Write::Hash { key: self.role(role.id), fields: hash_fields(&fields) });
+        writes.push(Write::SetAdd { key: self.roles(guild_id), member: role.id.to_string() });
+    }
+
+    fn write_member(&self, guild_id: Id<GuildMarker>, member: &Member) -> Write {
+        Write::Hash {
+            key: self.member(guild_id, member.user.id),
+            fields: hash_fields(&entities::guild_member(member)),
+        }
+    }
+
+    fn delete_role(&self, guild_id: Id<GuildMarker>, role_id: Id<RoleMarker>) -> [Write; 2] {
+        [
+            Write::Delete(self.role(role_id)),
+            Write::SetRemove { key: self.roles(guild_id), member: role_id.to_string() },
+        ]
+    }
+
+    /// The writes an event causes (none for events the cache doesn't hold)
+    fn writes(&self, event: &Event) -> Vec<Write> {
+        let mut writes = Vec::new();
+        match event {
+            Event::GuildCreate(guild) => {
+                if let GuildCreate::Available(guild) = guild.as_ref() {
+                    writes.push(self.write_guild(
+                        guild.id,
+                        guild_fields(
+                            &guild.name,
+                            guild.icon.map(|hash| hash.to_string()),
+                            guild.owner_id.to_string(),
+                            guild.member_count,
+                            &guild.preferred_locale,
+                            u8::from(guild.premium_tier),
+                        ),
+                    ));
+                    for role in &guild.roles {
+                        self.write_role(&mut writes, guild.id, role);
+                    }
+                    writes.extend(guild.members.iter().map(|member| self.write_member(guild.id, member)));
+                }
+            }
+            Event::GuildUpdate(update) => {
+                let guild: &PartialGuild = update;
+                writes.push(self.write_guild(
+                    guild.id,
+                    guild_fields(
+                        &guild.name,
+                        guild.icon.map(|hash| hash.to_string()),
+                        guild.owner_id.to_string(),
+                        guild.member_count,
+                        &guild.preferred_locale,
+                        u8::from(guild.premium_tier),
+                    ),
+                ));
+                for role in &guild.roles {
+                    self.write_role(&mut writes, guild.id, role);
+                }
+            }
+            // An outage keeps the guild's state
+            Event::GuildDelete(guild) if guild.unavailable != Some(true) => {
+                writes.push(Write::DeleteRoles { roles_key: self.roles(guild.id) });
+                writes.push(Write::Delete(self.guild(guild.id)));
+            }
+            Event::RoleCreate(create) => self.write_role(&mut writes, create.guild_id, &create.role),
+            Event::RoleUpdate(update) => self.write_role(&mut writes, update.guild_id, &update.role),
+            Event::RoleDelete(delete) => writes.extend(self.delete_role(delete.guild_id, delete.role_id)),
+            Event::MemberAdd(add) => writes.push(self.write_member(add.guild_id, &add.member)),
+            Event::MemberUpdate(update) => writes.push(Write::Hash {
+                key: self.member(update.guild_id, update.user.id),
+                fields: hash_fields(&json!({
+                    "user": entities::user(&update.user),
+                    "nick": update.nick,
+                    "roles": update.roles.iter().map(ToString::to_string).collect::<Vec<_>>(),
+                    "avatar": update.avatar.map(|hash| hash.to_string()),
+                    "pending": update.pending,
+                })),
+            }),
+            Event::MemberRemove(remove) => writes.push(Write::Delete(self.member(remove.guild_id, remove.user.id))),
+            Event::MemberChunk(chunk) => {
+                writes.extend(chunk.members.iter().map(|member| self.write_member(chunk.guild_id, member)));
+            }
+            _ => {}
+        }
+        writes
+    }
+}
+
+fn guild_fields(
+    name: &str,
+    icon: Option<String>,
+    owner_id: String,
+    member_count: Option<u64>,
+    preferred_locale: &str,
+    premium_tier: u8,
+) -> Value {
+    json!({
+        "name": name,
+        "icon": icon,
+        "owner_id": owner_id,
+        "member_count": member_count,
+        "preferred_locale": preferred_locale,
+        "premium_tier": premium_tier,
+    })
+}
+
+/// Flatten a normalized entity into string hash fields
+fn hash_fields(value: &Value) -> Vec<(String, String)> {
+    let Some(object) = value.as_object() else {
+        return Vec::new();
+    };
+    object
+        .iter()
+        .map(|(field, value)| {
+            let value = match value {
+                Value::Null => String::new(),
+                Value::String(text) => text.clone(),
+                other => other.to_string(),
+            };
+            (field.clone(), value)
+        })
+        .collect()
+}
+
+/// Queues the writes for the events shards receive
+pub struct RedisCache {
+    keys: Keys,
+    writes: mpsc::Sender<Vec<Write>>,
+    metrics: Arc<GatewayMetrics>,
+}
+
+impl std::fmt::Debug for RedisCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RedisCache").field("prefix", &self.keys.prefix).finish()
+    }
+}
+
+impl RedisCache {
+    /// Connect and start the writer task
+    pub async fn connect(config: &RedisCacheConfig, metrics: Arc<GatewayMetrics>) -> Result<Arc<Self>, GatewayError> {
+        let client = redis::Client::open(config.url.as_str()).map_err(|e| GatewayError::RedisFailed(Box::new(e)))?;
+        let connection = ConnectionManager::new(client).await.map_err(|e| GatewayError::RedisFailed(Box::new(e)))?;
+
+        let (writes, queue) = mpsc::channel(QUEUE_CAPACITY);
+        tokio::spawn(run_writer(connection, queue, config.ttl, Arc::clone(&metrics)));
+        info!(prefix = config.prefix, ttl_secs = config.ttl.as_secs(), "Writing guild state to Redis");
+
+        Ok(Arc::new(Self { keys: Keys { prefix: config.prefix.clone() }, writes, metrics }))
+    }
+
+    /// Queue the writes for an event a shard received
+    pub fn observe(&self, event: &Event) {
+        let writes = self.keys.writes(event);
+        if writes.is_empty() {
+            return;
+        }
+        if self.writes.try_send(writes).is_err() {
+            self.metrics.record_redis_cache_write("dropped");
+        }
+    }
+}
+
+/// Apply queued writes until every sender is gone
+async fn run_writer(
+    mut connection: ConnectionManager,
+    mut queue: mpsc::Receiver<Vec<Write>>,
+    ttl: Duration,
+    metrics: Arc<GatewayMetrics>,
+) {
+    while let Some(writes) = queue.recv().await {
+        match apply(&mut connection, &writes, ttl).await {
+            Ok(()) => metrics.record_redis_cache_write("ok"),
+            Err(e) => {
+                metrics.record_redis_cache_write("failed");
+                warn!(error = %e, "Redis cache write failed");
+            }
+        }
+    }
+    debug!("Redis cache writer stopped");
+}
+
+async fn apply(connection: &mut ConnectionManager, writes: &[Write], ttl: Duration) -> redis::RedisResult<()> {
+    let ttl = ttl.as_secs() as i64;
+    let mut pipe = redis::pipe();
+    for write in writes {
+        match write {
+            Write::Hash { key, fields } => {
+                pipe.hset_multiple(key, fields.as_slice()).ignore().expire(key, ttl).ignore();
+            }
+            Write::SetAdd { key, member } => {
+                pipe.sadd(key, member).ignore().expire(key, ttl).ignore();
+            }
+            Write::SetRemove { key, member } => {
+                pipe.srem(key, member).ignore();
+            }
+            Write::Delete(key) => {
+                pipe.del(key).ignore();
+            }
+            Write::DeleteRoles { roles_key } => {
+                let role_ids: Vec<String> = redis::cmd("SMEMBERS").arg(roles_key).query_async(connection).await?;
+                let prefix = roles_key.split(":guild:").next().unwrap_or_default();
+                for role_id in role_ids {
+                    pipe.del(format!("{prefix}:role:{role_id}")).ignore();
+                }
+                pipe.del(roles_key).ignore();
+            }
+        }
+    }
+    pipe.query_async(connection).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use twilight_model::gateway::payload::incoming::{MemberRemove, RoleCreate, RoleDelete};
+
+    fn keys() -> Keys {
+        Keys { prefix: "gateway".to_string() }
+    }
+
+    fn role_create() -> Event {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/../../packages/shared/nats-schemas/fixtures/role-create.json");
+        let fixture: Value = serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        let mut role = fixture["data"].clone();
+        role["colors"] = json!({ "primary_color": role["color"] });
+        role["flags"] = 0.into();
+        Event::RoleCreate(RoleCreate {
+            guild_id: Id::new(123456789012345678),
+            role: serde_json::from_value(role).unwrap(),
+        })
+    }
+
+    #[test]
+    fn roles_are_hashed_and_indexed_by_guild() {
+        let writes = keys().writes(&role_create());
+        let [Write::Hash { key, fields }, Write::SetAdd { key: set, member }] = writes.as_slice() else {
+            panic!("unexpected writes {writes:?}");
+        };
+        assert_eq!(key, "gateway:role:888888888888888888");
+        assert_eq!(set, "gateway:guild:123456789012345678:roles");
+        assert_eq!(member, "888888888888888888");
+
+        let field = |name: &str| fields.iter().find(|(field, _)| field == name).map(|(_, value)| value.as_str());
+        assert_eq!(field("guild_id"), Some("123456789012345678"));
+        assert_eq!(field("permissions"), Some("104324673"));
+        assert_eq!(field("hoist"), Some("true"));
+        assert_eq!(field("icon"), Some(""));
+    }
+
+    #[test]
+    fn deletes_invalidate() {
+        let guild_id = Id::new(123456789012345678);
+        let writes = keys().writes(&Event::RoleDelete(RoleDelete { guild_id, role_id: Id::new(888888888888888888) }));
+        assert_eq!(writes[0], Write::Delete("gateway:role:888888888888888888".to_string()));
+
+        let user = serde_json::from_value(json!({
+            "id": "987654321098765432", "username": "holder", "discriminator": "0", "avatar": null
+        }))
+        .unwrap();
+        let writes = keys().writes(&Event::MemberRemove(MemberRemove { guild_id, user }));
+        assert_eq!(writes, vec![Write::Delete("gateway:member:123456789012345678:987654321098765432".to_string())]);
+    }
+
+    #[test]
+    fn other_events_write_nothing() {
+        assert!(keys().writes(&Event::GatewayHeartbeatAck).is_empty());
+    }
+}
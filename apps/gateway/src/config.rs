@@ -1,33 +1,292 @@
 //! Gateway configuration module
 //!
 //! Sprint S-4: Enhanced configuration for shard pools and NATS
-//! Handles loading configuration from environment variables.
+//! Handles loading configuration from environment variables, over an
+//! optional TOML or YAML file (`--config`).
 
+mod file;
+
+use crate::admin::AdminGrpcConfig;
+use crate::alerts::{OpsAlertConfig, PagerProvider, PagerTarget, WebhookTarget};
+use crate::cache::redis::RedisCacheConfig;
+use crate::discord::{ApiVersion, ApiVersionMode};
 use crate::error::GatewayError;
+use crate::hex;
+use crate::discord::commands::SyncMode;
+use crate::discord::defer::{self, DeferRule};
+use crate::events::aggregate::{self, AggregateRule};
+use crate::events::guild_limit::{GuildLimitConfig, GuildRate};
+use crate::events::raid::RaidConfig;
+use crate::events::redaction::{RedactionConfig, RedactionPolicy};
+use crate::events::sampling::EventSampling;
+use crate::events::schema::ValidationMode;
+use crate::events::serialize::WireFormat;
+use crate::events::versions;
+use crate::flags::{FlagConfig, FlagSource};
+use crate::health::ReadyPolicy;
+use crate::metrics::{parse_buckets, HistogramBuckets, MetricsBackend, DOGSTATSD_DEFAULT_ADDR};
+use crate::nats::auth::{NatsAuth, NatsSecurity};
+use crate::nats::batch::BatchConfig;
+use crate::nats::canary::CanaryConfig;
+use crate::nats::dlq::{DlqConfig, RetryPolicy};
+use crate::nats::eligibility::EligibilityTriggers;
+use crate::nats::encryption::{self, EncryptionConfig};
+use crate::nats::migration::SchemaVersions;
+use crate::shard::canary::CanaryShard;
+use crate::overrides;
+use crate::shard::circuit::{self, CircuitConfig};
+use crate::nats::outbox::OutboxConfig;
+use crate::nats::CommandRouting;
+use crate::nats::quota::{self, StreamBudget};
+use crate::nats::raw::RawPassthrough;
+use crate::nats::routing::{self, StreamOverrides, StreamSpec};
+use crate::nats::signing::{SigningAlgorithm, SigningConfig};
+use crate::nats::topology;
+use crate::secrets::SecretSource;
+use crate::shard::pipeline::{OverflowPolicy, QueueConfig};
+use crate::shard::reshard::ReshardConfig;
+use crate::shard::watchdog::{self, DivergenceConfig};
+use crate::shard::{validate_pool, IdentifyPacing, TransportCompression, DEFAULT_SHARDS_PER_POOL};
+use crate::telemetry::TraceConfig;
+use async_nats::jetstream::stream::StorageType;
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
 use std::env;
+use std::path::PathBuf;
+use std::time::Duration;
 use twilight_gateway::Intents;
 
+/// Intents that must be enabled in the Discord developer portal.
+///
+/// Discord closes the connection with 4014 if any are requested without approval.
+pub const PRIVILEGED_INTENTS: Intents = Intents::GUILD_MEMBERS
+    .union(Intents::GUILD_PRESENCES)
+    .union(Intents::MESSAGE_CONTENT);
+
+/// Intents requested when `INTENTS` is unset
+pub const DEFAULT_INTENTS: Intents = Intents::GUILDS.union(Intents::GUILD_MEMBERS);
+
+/// Another bot run in this process, with its own token and pool (`BOTS`)
+#[derive(Debug, Clone)]
+pub struct BotConfig {
+    /// Label on the bot's events and metrics
+    pub id: String,
+    /// Read from `token_source` by `read_secrets`
+    pub token: String,
+    pub token_source: SecretSource,
+    pub pool_id: u64,
+    pub total_shards: u64,
+    pub shards_per_pool: u64,
+    /// Intents before the `FORWARD_*` additions
+    pub intents: Intents,
+}
+
 /// Gateway configuration
 #[derive(Debug, Clone)]
 pub struct GatewayConfig {
-    /// Discord bot token
+    /// Discord bot token, read from `discord_token_source` by `read_secrets`
     pub discord_token: String,
+    /// `DISCORD_TOKEN` itself, a file or a secrets manager reference
+    pub discord_token_source: SecretSource,
+    /// How often secrets other than inline values are read again (None disables)
+    pub secrets_refresh: Option<Duration>,
 
     /// Pool ID for this gateway instance (0-indexed)
-    /// Each pool manages SHARDS_PER_POOL shards
+    /// Each pool manages `shards_per_pool` shards
     pub pool_id: u64,
 
-    /// Total number of shards across all pools
+    /// Size of each pool's shard range; must match across the cluster
+    pub shards_per_pool: u64,
+
+    /// Total number of shards across all pools (0 until discovered when
+    /// `discover_shards` is set)
     pub total_shards: u64,
 
+    /// Take the shard count from Discord's recommendation (`TOTAL_SHARDS=auto`)
+    pub discover_shards: bool,
+
     /// NATS server URL(s) - comma-separated for multiple servers
     pub nats_url: Option<String>,
+    /// Streams created at startup when missing (`ENSURE_STREAMS`; None leaves
+    /// stream setup to the workers or a setup job)
+    pub ensure_streams: Option<Vec<StreamSpec>>,
+    /// NATS authentication and TLS
+    pub nats_security: NatsSecurity,
 
     /// Health/metrics HTTP port
     pub http_port: u16,
 
     /// Log level (trace, debug, info, warn, error)
     pub log_level: String,
+
+    /// Check the session start limit before identifying (requires Discord REST)
+    pub identify_budget_check: bool,
+
+    /// Identifies to keep in reserve; a burst that would dip below is refused
+    pub identify_budget_reserve: u32,
+
+    /// Claim identify slots across pools through NATS KV
+    pub identify_coordination: bool,
+
+    /// Save sessions on shutdown and resume them on start
+    pub resume_sessions: bool,
+
+    /// Keep saved sessions in this file instead of NATS KV (single pool)
+    pub session_file: Option<PathBuf>,
+
+    /// Bound on the shutdown drain (stopping shards, flushing NATS)
+    pub shutdown_timeout: Duration,
+
+    /// Fail `/health` after this long without a heartbeat from any shard
+    pub liveness_stale_after: Option<Duration>,
+
+    /// Ready shards `/ready` requires
+    pub ready_policy: ReadyPolicy,
+
+    /// Pool start delay (× pool ID) and per-identify jitter
+    pub identify_pacing: IdentifyPacing,
+
+    /// Run only these shards from the pool's range (debugging)
+    pub only_shards: Option<BTreeSet<u64>>,
+
+    /// Gateway transport compression (must match the compiled codec)
+    pub compression: TransportCompression,
+
+    /// Estimate compressed wire bytes with a shadow compressor per shard
+    pub compression_metrics: bool,
+
+    /// Discord API version (fleet pin, this pool's mode, compiled versions)
+    pub api_version: ApiVersion,
+
+    /// Ops alert webhook and thresholds
+    pub ops_alerts: OpsAlertConfig,
+
+    /// Metrics export: Prometheus scrape (default) or DogStatsD push
+    pub metrics_backend: MetricsBackend,
+    /// Histogram bucket boundaries replacing the built-in layouts (Prometheus)
+    pub metrics_buckets: Vec<HistogramBuckets>,
+    /// Publishes slower than this are logged with their trace ID (None disables)
+    pub slow_publish: Option<Duration>,
+    /// OTLP trace export (None disables tracing)
+    pub traces: Option<TraceConfig>,
+
+    /// Runtime feature flag provider
+    pub flags: FlagConfig,
+
+    /// How often worker consumer lag is polled (None disables)
+    pub consumer_lag_interval: Option<Duration>,
+
+    /// Admin gRPC server (None disables)
+    pub admin_grpc: Option<AdminGrpcConfig>,
+
+    /// Bearer token for the admin HTTP shard commands (unset disables them)
+    pub admin_token: Option<String>,
+    /// How long each shard may take to be ready on a rotated token
+    pub token_rotation_timeout: Duration,
+
+    /// How often this pool's topology snapshot is published (None disables)
+    pub topology_interval: Option<Duration>,
+
+    /// Intents requested before the `FORWARD_*` additions (`INTENTS`)
+    pub intents: Intents,
+    /// Stamped on the primary bot's events and metrics (`BOT_ID`)
+    pub bot_id: Option<String>,
+    /// Further bots run in this process, each with its own pool (`BOTS`)
+    pub bots: Vec<BotConfig>,
+    /// Subscribe to guild messages and publish message events on `messages.>`
+    pub forward_messages: bool,
+    /// Subscribe to message reactions and publish `reaction.*`
+    pub forward_reactions: bool,
+    /// Subscribe to voice states and publish `voice.*` on the VOICE stream
+    pub forward_voice: bool,
+
+    /// Classify `member.leave` as leave, kick or ban via the audit log
+    pub member_removal_audit: bool,
+    /// Slash commands the gateway defers before publishing (`INTERACTION_DEFER_COMMANDS`)
+    pub interaction_defer: Vec<DeferRule>,
+    /// Execute Discord REST calls for workers on `rest.requests.>`
+    pub rest_proxy: bool,
+    /// Application command sync at startup (`COMMAND_SYNC`)
+    pub command_sync: SyncMode,
+    /// Command definitions replacing the embedded commands.json
+    pub commands_file: Option<PathBuf>,
+
+    /// Cache guilds, channels and roles and serve them to workers
+    pub guild_cache: bool,
+    /// Guild, role and member state written to Redis (None disables)
+    pub redis_cache: Option<RedisCacheConfig>,
+
+    /// Publish minute/hour ticks and per-guild scheduled ticks
+    pub ticks: bool,
+
+    /// Event types summarized in windows (`AGGREGATE_EVENTS`)
+    pub aggregate: Vec<AggregateRule>,
+    /// Share of each event type that is published (`EVENT_SAMPLING`)
+    pub event_sampling: EventSampling,
+    /// Join raid detection (`RAID_DETECTION_JOINS`, None disables)
+    pub raid_detection: Option<RaidConfig>,
+    /// Per-guild token buckets (`GUILD_RATE_LIMIT`, None disables)
+    pub guild_rate_limit: Option<GuildLimitConfig>,
+    /// Consecutive receive errors before a shard's circuit opens
+    /// (`CIRCUIT_BREAKER_MAX_ERRORS`; `circuit_breaker_max_errors` overrides it)
+    pub circuit_breaker_max_errors: u32,
+    /// Cool-down before a tripped shard's half-open reconnect
+    pub circuit_breaker: CircuitConfig,
+    /// Apply overrides from the `gateway_config` KV bucket at runtime
+    pub dynamic_config: bool,
+    /// Publish only guilds this layout owns while resharding
+    /// (`RESHARDING_ENABLED`, `RESHARD_FROM_SHARDS`; None disables)
+    pub resharding: Option<ReshardConfig>,
+
+    /// Check the serializer against the wire fixtures before starting
+    pub self_test: bool,
+
+    /// Recently published events kept for `/debug/events/{event_id}` (0 disables)
+    pub event_index_size: usize,
+
+    /// Dual-write canary of a candidate wire format (None disables)
+    pub canary: Option<CanaryConfig>,
+    /// Per-stream publish budgets (empty leaves publishing unlimited)
+    pub publish_budgets: Vec<StreamBudget>,
+    /// Batched publish acks (None waits for each ack)
+    pub publish_batch: Option<BatchConfig>,
+    /// Bounded queues between each shard and NATS
+    pub publish_queue: QueueConfig,
+    /// Events whose Discord payload is published to `raw.>` (None disables)
+    pub raw_passthrough: Option<RawPassthrough>,
+    /// Interactions that request an eligibility check on `eligibility.check.>` (None disables)
+    pub eligibility: Option<EligibilityTriggers>,
+    /// What happens to events failing the wire schema (`SCHEMA_VALIDATION`)
+    pub schema_validation: ValidationMode,
+    /// PII fields redacted from event payloads before publishing (None disables)
+    pub redaction: Option<RedactionConfig>,
+    /// Disk-backed buffer for NATS outages (None disables)
+    pub outbox: Option<OutboxConfig>,
+    /// Retries for failed publishes
+    pub publish_retry: RetryPolicy,
+    /// Disk-backed dead-letter queue (None disables)
+    pub dlq: Option<DlqConfig>,
+    /// Subjects for guild command interactions (`COMMAND_SUBJECTS`)
+    pub command_routing: CommandRouting,
+    /// Encoding of events on their primary subjects (`WIRE_FORMAT`)
+    pub wire_format: WireFormat,
+    /// Schema version on the primary subjects and the dual-published one
+    /// (`WIRE_SCHEMA_VERSION`, `SCHEMA_DUAL_PUBLISH`)
+    pub schema_versions: SchemaVersions,
+    /// One shard publishing with candidate settings (None disables)
+    pub canary_shard: Option<CanaryShard>,
+    /// Payload signatures on published events (None disables)
+    pub signing: Option<SigningConfig>,
+    /// Encrypted payloads on sensitive subjects (None disables)
+    pub encryption: Option<EncryptionConfig>,
+
+    /// Received-vs-routed divergence watchdog (None disables)
+    pub divergence: Option<DivergenceConfig>,
+
+    /// File given with `--config`, under the environment
+    pub config_file: Option<PathBuf>,
+    /// Keys in that file no setting read
+    pub unused_file_keys: Vec<String>,
 }
 
 impl GatewayConfig {
@@ -35,52 +294,1066 @@ impl GatewayConfig {
     pub fn from_env() -> Result<Self, GatewayError> {
         dotenvy::dotenv().ok();
 
-        let discord_token = env::var("DISCORD_TOKEN")
-            .or_else(|_| env::var("DISCORD_BOT_TOKEN"))
-            .map_err(|_| GatewayError::Config(
-                "DISCORD_TOKEN or DISCORD_BOT_TOKEN must be set".to_string(),
-            ))?;
+        let discord_token_source = match SecretSource::lookup("DISCORD_TOKEN")? {
+            Some(source) => source,
+            None => SecretSource::lookup("DISCORD_BOT_TOKEN")?
+                .or_else(|| {
+                    var("TOKEN_FILE")
+                        .ok()
+                        .filter(|path| !path.trim().is_empty())
+                        .map(|path| SecretSource::File(PathBuf::from(path.trim())))
+                })
+                .ok_or_else(|| {
+                    GatewayError::Config("DISCORD_TOKEN, DISCORD_TOKEN_FILE or TOKEN_FILE must be set".to_string())
+                })?,
+        };
+        let secrets_refresh = Some(Duration::from_secs(env_parse("SECRETS_REFRESH_SECS", 300)?))
+            .filter(|every| !every.is_zero());
 
         // Pool ID replaces shard_id for multi-shard pools
-        let pool_id = env::var("POOL_ID")
-            .or_else(|_| env::var("SHARD_ID")) // Backwards compat
-            .unwrap_or_else(|_| "0".to_string())
-            .parse()
-            .map_err(|e| GatewayError::Config(format!("POOL_ID must be a valid number: {e}")))?;
+        let pool_id = var("POOL_ID")
+            .or_else(|_| var("SHARD_ID")) // Backwards compat
+            .unwrap_or_else(|_| "0".to_string());
+        let pool_id = if pool_id.trim() == "hostname" {
+            // StatefulSet pods are named {statefulset}-{ordinal}
+            let hostname = var("HOSTNAME")
+                .map_err(|_| GatewayError::Config("POOL_ID=hostname but HOSTNAME is not set".to_string()))?;
+            pool_id_from_hostname(&hostname).ok_or_else(|| {
+                GatewayError::Config(format!("POOL_ID=hostname but HOSTNAME {hostname:?} has no -<ordinal> suffix"))
+            })?
+        } else {
+            pool_id
+                .parse()
+                .map_err(|e| GatewayError::Config(format!("POOL_ID must be a valid number or hostname: {e}")))?
+        };
 
-        let total_shards = env::var("TOTAL_SHARDS")
-            .unwrap_or_else(|_| "1".to_string())
-            .parse()
-            .map_err(|e| GatewayError::Config(format!("TOTAL_SHARDS must be a valid number: {e}")))?;
+        let total_shards = var("TOTAL_SHARDS").unwrap_or_else(|_| "1".to_string());
+        let discover_shards = total_shards.trim() == "auto";
+        let total_shards = if discover_shards {
+            0
+        } else {
+            total_shards
+                .parse()
+                .map_err(|e| GatewayError::Config(format!("TOTAL_SHARDS must be a valid number or auto: {e}")))?
+        };
 
-        let nats_url = env::var("NATS_URL").ok();
+        let shards_per_pool = env_parse("SHARDS_PER_POOL", DEFAULT_SHARDS_PER_POOL)?;
+        if shards_per_pool == 0 {
+            return Err(GatewayError::Config("SHARDS_PER_POOL must be at least 1".to_string()));
+        }
+        // Discovered counts are checked once known
+        if !discover_shards {
+            validate_pool(pool_id, total_shards, shards_per_pool)?;
+        }
 
-        let http_port = env::var("HTTP_PORT")
-            .or_else(|_| env::var("METRICS_PORT")) // Backwards compat
+        let nats_url = var("NATS_URL").ok();
+        let nats_security = nats_security_from_env()?;
+
+        let http_port = var("HTTP_PORT")
+            .or_else(|_| var("METRICS_PORT")) // Backwards compat
             .unwrap_or_else(|_| "9090".to_string())
             .parse()
             .map_err(|e| GatewayError::Config(format!("HTTP_PORT must be a valid port number: {e}")))?;
 
-        let log_level = env::var("LOG_LEVEL").unwrap_or_else(|_| "info".to_string());
+        let log_level = var("LOG_LEVEL").unwrap_or_else(|_| "info".to_string());
+
+        let identify_budget_check = env_flag("IDENTIFY_BUDGET_CHECK", true)?;
+        let identify_budget_reserve = env_parse("IDENTIFY_BUDGET_RESERVE", 10)?;
+        let identify_coordination = env_flag("IDENTIFY_COORDINATION", true)?;
+        let resume_sessions = env_flag("RESUME_SESSIONS", true)?;
+        let session_file = var("SESSION_FILE").ok().filter(|path| !path.is_empty()).map(PathBuf::from);
+        let shutdown_timeout = Duration::from_secs(env_parse("SHUTDOWN_TIMEOUT_SECS", 25)?.max(1));
+        let liveness_stale_after = Some(env_parse("LIVENESS_STALE_SECS", 0)?)
+            .filter(|&secs| secs > 0)
+            .map(Duration::from_secs);
+        let ready_policy = ReadyPolicy {
+            min_shards: env_parse("READY_MIN_SHARDS", 1)?,
+            min_fraction: env_parse("READY_MIN_FRACTION", 0.0)?,
+        };
+        if !(0.0..=1.0).contains(&ready_policy.min_fraction) {
+            return Err(GatewayError::Config(format!(
+                "READY_MIN_FRACTION must be between 0 and 1, got {}",
+                ready_policy.min_fraction
+            )));
+        }
+        let identify_pacing = IdentifyPacing {
+            pool_stagger: Duration::from_millis(env_parse("IDENTIFY_POOL_STAGGER_MS", 0)?),
+            jitter: Duration::from_millis(env_parse("IDENTIFY_JITTER_MS", 0)?),
+        };
+
+        let only_shards = var("ONLY_SHARDS")
+            .ok()
+            .map(|list| parse_shard_list(&list))
+            .transpose()?;
+
+        let compression = match var("GATEWAY_COMPRESSION") {
+            Ok(value) => TransportCompression::parse(&value).ok_or_else(|| {
+                GatewayError::Config(format!(
+                    "GATEWAY_COMPRESSION must be none, zlib-stream or zstd-stream, got {value:?}"
+                ))
+            })?,
+            Err(_) => TransportCompression::compiled(),
+        };
+        check_compression(compression, TransportCompression::compiled())?;
+        let compression_metrics = env_flag("GATEWAY_COMPRESSION_METRICS", true)?;
+
+        let api_mode = match var("DISCORD_API_MODE") {
+            Ok(value) => ApiVersionMode::parse(&value).ok_or_else(|| {
+                GatewayError::Config(format!("DISCORD_API_MODE must be pinned or canary, got {value:?}"))
+            })?,
+            Err(_) => ApiVersionMode::Pinned,
+        };
+        let api_pinned = env_parse("DISCORD_API_VERSION", twilight_gateway::API_VERSION)?;
+        let api_version = ApiVersion::resolve(api_pinned, api_mode)?;
+
+        let metrics_backend = match var("METRICS_BACKEND") {
+            Ok(value) => {
+                // DD_AGENT_HOST is what Datadog's Kubernetes setup injects
+                let addr = var("DOGSTATSD_ADDR")
+                    .ok()
+                    .or_else(|| var("DD_AGENT_HOST").ok().map(|host| format!("{host}:8125")))
+                    .unwrap_or_else(|| DOGSTATSD_DEFAULT_ADDR.to_string());
+                MetricsBackend::parse(&value, &addr)?
+            }
+            Err(_) => MetricsBackend::Prometheus,
+        };
+        let metrics_buckets = match var("METRICS_BUCKETS") {
+            Ok(spec) => parse_buckets(&spec)?,
+            Err(_) => Vec::new(),
+        };
+        let slow_publish = Some(env_parse("SLOW_PUBLISH_MS", 0)?)
+            .filter(|&ms| ms > 0)
+            .map(Duration::from_millis);
+
+        let environment = var("ENVIRONMENT").ok();
+        let flag_file = var("FEATURE_FLAGS_FILE").ok().filter(|v| !v.is_empty());
+        let flagd_url = var("FEATURE_FLAGS_FLAGD_URL").ok().filter(|v| !v.is_empty());
+        let flags = FlagConfig {
+            source: match (flag_file, flagd_url) {
+                (Some(_), Some(_)) => {
+                    return Err(GatewayError::Config(
+                        "Set only one of FEATURE_FLAGS_FILE and FEATURE_FLAGS_FLAGD_URL".to_string(),
+                    ))
+                }
+                (Some(path), None) => FlagSource::File(path.into()),
+                (None, Some(url)) => FlagSource::Flagd(url),
+                (None, None) => FlagSource::Defaults,
+            },
+            environment: environment.clone(),
+            refresh: Duration::from_secs(env_parse("FEATURE_FLAGS_REFRESH_SECS", 30)?.max(1)),
+        };
+
+        let traces = match var("OTEL_EXPORTER_OTLP_ENDPOINT").ok().filter(|endpoint| !endpoint.is_empty()) {
+            Some(endpoint) => {
+                let sample_ratio: f64 = env_parse("OTEL_TRACES_SAMPLE_RATIO", 1.0)?;
+                if !(0.0..=1.0).contains(&sample_ratio) {
+                    return Err(GatewayError::Config(format!(
+                        "OTEL_TRACES_SAMPLE_RATIO must be between 0 and 1, got {sample_ratio}"
+                    )));
+                }
+                Some(TraceConfig { endpoint, sample_ratio })
+            }
+            None => None,
+        };
+
+        let consumer_lag_interval = Some(env_parse("CONSUMER_LAG_INTERVAL_SECS", 30)?)
+            .filter(|secs| *secs > 0)
+            .map(Duration::from_secs);
+
+        let admin_grpc = admin_grpc_from_env()?;
+        let admin_token = var("ADMIN_TOKEN").ok().filter(|token| !token.is_empty());
+        let token_rotation_timeout = Duration::from_secs(env_parse("TOKEN_ROTATION_SHARD_TIMEOUT_SECS", 60)?.max(1));
+
+        let topology_interval = Some(env_parse("TOPOLOGY_INTERVAL_SECS", 30)?)
+            .filter(|secs| *secs > 0)
+            .map(Duration::from_secs);
+        if topology_interval.is_some_and(|every| every * 2 > topology::TTL) {
+            return Err(GatewayError::Config(format!(
+                "TOPOLOGY_INTERVAL_SECS must be at most {} (half the topology entry TTL)",
+                topology::TTL.as_secs() / 2
+            )));
+        }
+
+        let intents = match var("INTENTS") {
+            Ok(spec) => parse_intents(&spec)?,
+            Err(_) => DEFAULT_INTENTS,
+        };
+        let bot_id = var("BOT_ID").ok().filter(|id| !id.is_empty());
+        if let Some(ref id) = bot_id {
+            validate_bot_id("BOT_ID", id)?;
+        }
+        let bots = bots_from_env(bot_id.as_deref(), intents)?;
+        let forward_messages = env_flag("FORWARD_MESSAGES", false)?;
+        let forward_reactions = env_flag("FORWARD_REACTIONS", false)?;
+        let forward_voice = env_flag("FORWARD_VOICE", false)?;
+        let member_removal_audit = env_flag("MEMBER_REMOVAL_AUDIT", false)?;
+        let interaction_defer = defer::parse_rules(&var("INTERACTION_DEFER_COMMANDS").unwrap_or_default())?;
+        let rest_proxy = env_flag("REST_PROXY_ENABLED", false)?;
+        let command_sync = match var("COMMAND_SYNC") {
+            Ok(value) => SyncMode::parse(&value).ok_or_else(|| {
+                GatewayError::Config(format!("COMMAND_SYNC must be off, diff or apply, got {value:?}"))
+            })?,
+            Err(_) => SyncMode::Off,
+        };
+        let commands_file = var("COMMANDS_FILE").ok().filter(|path| !path.is_empty()).map(PathBuf::from);
+        let guild_cache = env_flag("GUILD_CACHE_ENABLED", false)?;
+        let redis_cache = match var("REDIS_CACHE_URL").ok().filter(|url| !url.is_empty()) {
+            Some(url) => Some(RedisCacheConfig {
+                url,
+                prefix: var("REDIS_CACHE_PREFIX")
+                    .ok()
+                    .filter(|prefix| !prefix.is_empty())
+                    .unwrap_or_else(|| "gateway".to_string()),
+                ttl: Duration::from_secs(env_parse("REDIS_CACHE_TTL_SECS", 86_400)?.max(1)),
+            }),
+            None => None,
+        };
+        let ticks = env_flag("TICKS_ENABLED", false)?;
+        let aggregate = match var("AGGREGATE_EVENTS") {
+            Ok(spec) => aggregate::parse_rules(&spec)?,
+            Err(_) => Vec::new(),
+        };
+        let event_sampling = EventSampling::parse(&var("EVENT_SAMPLING").unwrap_or_default())?;
+        let raid_detection = match env_parse("RAID_DETECTION_JOINS", 0u64)? {
+            0 => None,
+            threshold => Some(RaidConfig {
+                threshold,
+                window: Duration::from_secs(env_parse("RAID_DETECTION_WINDOW_SECS", 10u64)?.max(1)),
+            }),
+        };
+        let guild_rate_limit = match var("GUILD_RATE_LIMIT").ok().filter(|value| !value.is_empty()) {
+            Some(rate) => Some(GuildLimitConfig {
+                default: GuildRate::parse("GUILD_RATE_LIMIT", &rate)?,
+                guilds: GuildLimitConfig::parse_guilds(&var("GUILD_RATE_LIMIT_GUILDS").unwrap_or_default())?,
+                summary_window: Some(env_parse("GUILD_RATE_LIMIT_SUMMARY_SECS", 0u64)?)
+                    .filter(|secs| *secs > 0)
+                    .map(Duration::from_secs),
+            }),
+            None => None,
+        };
+        let circuit_breaker_max_errors = env_parse("CIRCUIT_BREAKER_MAX_ERRORS", overrides::DEFAULT_MAX_CONSECUTIVE_ERRORS)?;
+        if circuit_breaker_max_errors == 0 {
+            return Err(GatewayError::Config("CIRCUIT_BREAKER_MAX_ERRORS must be at least 1".to_string()));
+        }
+        let circuit_breaker = CircuitConfig {
+            cooldown: Duration::from_secs(env_parse("CIRCUIT_BREAKER_COOLDOWN_SECS", circuit::DEFAULT_COOLDOWN.as_secs())?),
+            max_cooldown: Duration::from_secs(env_parse(
+                "CIRCUIT_BREAKER_MAX_COOLDOWN_SECS",
+                circuit::DEFAULT_MAX_COOLDOWN.as_secs(),
+            )?),
+        };
+        if session_file.is_some() && !bots.is_empty() {
+            return Err(GatewayError::Config("SESSION_FILE holds one pool's sessions; BOTS resume through NATS KV".to_string()));
+        }
+        let dynamic_config = env_flag("DYNAMIC_CONFIG_ENABLED", false)?;
+        let reshard_from = Some(env_parse("RESHARD_FROM_SHARDS", 0u64)?).filter(|from| *from > 0);
+        let resharding = if env_flag("RESHARDING_ENABLED", reshard_from.is_some())? {
+            if discover_shards {
+                return Err(GatewayError::Config("RESHARDING_ENABLED needs a numeric TOTAL_SHARDS, not auto".to_string()));
+            }
+            if !bots.is_empty() {
+                return Err(GatewayError::Config("RESHARDING_ENABLED reshards one bot; run BOTS in their own processes".to_string()));
+            }
+            if reshard_from == Some(total_shards) {
+                return Err(GatewayError::Config(format!(
+                    "RESHARD_FROM_SHARDS must differ from TOTAL_SHARDS ({total_shards})"
+                )));
+            }
+            Some(ReshardConfig { epoch: total_shards, from: reshard_from })
+        } else {
+            None
+        };
+        let production = environment.as_deref().is_some_and(|env| matches!(env, "production" | "prod"));
+        let self_test = env_flag("SELF_TEST", !production)?;
+        let ensure_streams = if env_flag("ENSURE_STREAMS", false)? {
+            let routing_file = var("NATS_ROUTING_FILE").ok().filter(|path| !path.is_empty()).map(PathBuf::from);
+            let profile = var("STREAM_PROFILE")
+                .ok()
+                .or_else(|| production.then(|| "production".to_string()))
+                .filter(|profile| !profile.is_empty());
+            Some(routing::load(routing_file.as_deref(), profile.as_deref(), stream_overrides_from_env)?)
+        } else {
+            None
+        };
+
+        let event_index_size = env_parse("EVENT_INDEX_SIZE", 10_000)?;
+
+        let canary_percent: f64 = env_parse("CANARY_PERCENT", 0.0)?;
+        if !(0.0..=100.0).contains(&canary_percent) {
+            return Err(GatewayError::Config(format!(
+                "CANARY_PERCENT must be between 0 and 100, got {canary_percent}"
+            )));
+        }
+        let canary_format = match var("CANARY_FORMAT") {
+            Ok(value) => WireFormat::parse(&value)
+                .ok_or_else(|| GatewayError::Config(format!("CANARY_FORMAT must be json or protobuf, got {value:?}")))?,
+            Err(_) => WireFormat::Json,
+        };
+        let canary = (canary_percent > 0.0).then_some(CanaryConfig {
+            percent: canary_percent,
+            format: canary_format,
+        });
+
+        let publish_budgets = match var("PUBLISH_BUDGETS") {
+            Ok(spec) => quota::parse_budgets(&spec)?,
+            Err(_) => Vec::new(),
+        };
+        let publish_queue = QueueConfig {
+            capacity: env_parse("PUBLISH_QUEUE_SIZE", QueueConfig::default().capacity)?.max(1),
+            overflow: match var("PUBLISH_QUEUE_OVERFLOW") {
+                Ok(value) => OverflowPolicy::parse(&value).ok_or_else(|| {
+                    GatewayError::Config(format!("PUBLISH_QUEUE_OVERFLOW must be block or drop, got {value:?}"))
+                })?,
+                Err(_) => OverflowPolicy::Block,
+            },
+        };
+        let publish_batch = match env_parse("PUBLISH_BATCH_MAX_EVENTS", 0)? {
+            0 => None,
+            max_events => Some(BatchConfig {
+                max_events,
+                max_delay: Duration::from_millis(env_parse("PUBLISH_BATCH_MAX_DELAY_MS", 5)?.max(1)),
+            }),
+        };
+
+        let raw_passthrough = RawPassthrough::parse(
+            &var("RAW_PASSTHROUGH_EVENTS").unwrap_or_default(),
+            &var("RAW_PASSTHROUGH_GUILDS").unwrap_or_default(),
+        )?;
+        let eligibility = EligibilityTriggers::parse(
+            &var("ELIGIBILITY_COMMANDS").unwrap_or_default(),
+            &var("ELIGIBILITY_WALLET_OPTION").unwrap_or_else(|_| "wallet".to_string()),
+            &var("ELIGIBILITY_CLAIM_PREFIX").unwrap_or_default(),
+        )?;
+
+        let redaction_policy = match (var("REDACTION_POLICY"), var("REDACTION_POLICY_FILE")) {
+            (Ok(_), Ok(_)) => {
+                return Err(GatewayError::Config("Set only one of REDACTION_POLICY and REDACTION_POLICY_FILE".to_string()))
+            }
+            (Ok(json), _) => Some(json),
+            (_, Ok(path)) => Some(std::fs::read_to_string(&path).map_err(|e| {
+                GatewayError::Config(format!("REDACTION_POLICY_FILE {path} can't be read: {e}"))
+            })?),
+            _ => None,
+        };
+        let redaction = match redaction_policy.filter(|json| !json.trim().is_empty()) {
+            Some(json) => Some(RedactionConfig {
+                policy: RedactionPolicy::parse(&json)?,
+                hash_key: match var("REDACTION_HASH_KEY") {
+                    Ok(key) => Some(
+                        hex::decode(&key)
+                            .filter(|key| !key.is_empty())
+                            .ok_or_else(|| GatewayError::Config("REDACTION_HASH_KEY must be hex".to_string()))?,
+                    ),
+                    Err(_) => None,
+                },
+            }),
+            None => None,
+        };
+
+        let schema_validation = match var("SCHEMA_VALIDATION") {
+            Ok(value) => ValidationMode::parse(&value).ok_or_else(|| {
+                GatewayError::Config(format!("SCHEMA_VALIDATION must be off, warn or enforce, got {value:?}"))
+            })?,
+            Err(_) => ValidationMode::Warn,
+        };
+
+        let wire_format = match var("WIRE_FORMAT") {
+            Ok(value) => WireFormat::parse(&value).ok_or_else(|| {
+                GatewayError::Config(format!("WIRE_FORMAT must be json or protobuf, got {value:?}"))
+            })?,
+            Err(_) => WireFormat::Json,
+        };
+
+        let schema_versions = SchemaVersions {
+            wire: match var("WIRE_SCHEMA_VERSION") {
+                Ok(value) => versions::parse("WIRE_SCHEMA_VERSION", &value)?,
+                Err(_) => versions::CURRENT,
+            },
+            dual_publish: match var("SCHEMA_DUAL_PUBLISH").ok().filter(|value| value != "off" && !value.is_empty()) {
+                Some(value) => Some(versions::parse("SCHEMA_DUAL_PUBLISH", &value)?),
+                None => None,
+            },
+        };
+        if schema_versions.dual_publish == Some(schema_versions.wire) {
+            return Err(GatewayError::Config(format!(
+                "SCHEMA_DUAL_PUBLISH must differ from WIRE_SCHEMA_VERSION ({})",
+                schema_versions.wire
+            )));
+        }
+
+        let canary_shard = match var("CANARY_SHARD").ok().filter(|value| !value.is_empty()) {
+            Some(value) => {
+                let shard_id: u64 = value.trim().parse().map_err(|_| {
+                    GatewayError::Config(format!("CANARY_SHARD must be a shard ID, got {value:?}"))
+                })?;
+                if !discover_shards && shard_id >= total_shards {
+                    return Err(GatewayError::Config(format!(
+                        "CANARY_SHARD {shard_id} is not below TOTAL_SHARDS ({total_shards})"
+                    )));
+                }
+                Some(CanaryShard {
+                    shard_id,
+                    wire_format: match var("CANARY_SHARD_WIRE_FORMAT") {
+                        Ok(value) => Some(WireFormat::parse(&value).ok_or_else(|| {
+                            GatewayError::Config(format!(
+                                "CANARY_SHARD_WIRE_FORMAT must be json or protobuf, got {value:?}"
+                            ))
+                        })?),
+                        Err(_) => None,
+                    },
+                    schema_version: match var("CANARY_SHARD_SCHEMA_VERSION") {
+                        Ok(value) => Some(versions::parse("CANARY_SHARD_SCHEMA_VERSION", &value)?),
+                        Err(_) => None,
+                    },
+                    event_sampling: EventSampling::parse(&var("CANARY_SHARD_EVENT_SAMPLING").unwrap_or_default())?,
+                })
+            }
+            None => None,
+        };
+
+        let signing = match var("EVENT_SIGNING").ok().filter(|value| value != "off" && !value.is_empty()) {
+            Some(value) => {
+                let algorithm = SigningAlgorithm::parse(&value).ok_or_else(|| {
+                    GatewayError::Config(format!("EVENT_SIGNING must be off, hmac-sha256 or ed25519, got {value:?}"))
+                })?;
+                let key = var("EVENT_SIGNING_KEY")
+                    .map_err(|_| GatewayError::Config("EVENT_SIGNING requires EVENT_SIGNING_KEY".to_string()))?;
+                let key = hex::decode(&key)
+                    .ok_or_else(|| GatewayError::Config("EVENT_SIGNING_KEY must be hex".to_string()))?;
+                let key_id = var("EVENT_SIGNING_KEY_ID").ok().filter(|id| !id.is_empty());
+                Some(SigningConfig { algorithm, key, key_id })
+            }
+            None => None,
+        };
+
+        let encryption_subjects = encryption::parse_subjects(&var("PAYLOAD_ENCRYPTION_SUBJECTS").unwrap_or_default())?;
+        let encryption = match (encryption_subjects.is_empty(), SecretSource::lookup("PAYLOAD_ENCRYPTION_KEY")?) {
+            (true, _) => None,
+            (false, Some(key)) => Some(EncryptionConfig {
+                subjects: encryption_subjects,
+                key,
+                key_id: var("PAYLOAD_ENCRYPTION_KEY_ID").ok().filter(|id| !id.is_empty()),
+            }),
+            (false, None) => {
+                return Err(GatewayError::Config(
+                    "PAYLOAD_ENCRYPTION_SUBJECTS requires PAYLOAD_ENCRYPTION_KEY (or _FILE)".to_string(),
+                ))
+            }
+        };
+
+        let command_routing = match var("COMMAND_SUBJECTS") {
+            Ok(value) => CommandRouting::parse(&value).ok_or_else(|| {
+                GatewayError::Config(format!("COMMAND_SUBJECTS must be interaction or per-command, got {value:?}"))
+            })?,
+            Err(_) => CommandRouting::Interaction,
+        };
+
+        let outbox = match var("OUTBOX_DIR").ok().filter(|dir| !dir.is_empty()) {
+            Some(dir) => Some(OutboxConfig {
+                dir: dir.into(),
+                max_events: env_parse("OUTBOX_MAX_EVENTS", 100_000)?,
+                max_bytes: env_parse("OUTBOX_MAX_BYTES", 256 * 1024 * 1024)?,
+                max_age: Duration::from_secs(env_parse("OUTBOX_MAX_AGE_SECS", 3600)?),
+            }),
+            None => None,
+        };
+        let publish_retry = RetryPolicy {
+            retries: env_parse("PUBLISH_RETRIES", 3)?,
+            base_delay: Duration::from_millis(env_parse("PUBLISH_RETRY_BASE_MS", 100)?),
+        };
+        let dlq = match var("DLQ_DIR").ok().filter(|dir| !dir.is_empty()) {
+            Some(dir) => Some(DlqConfig {
+                dir: dir.into(),
+                max_events: env_parse("DLQ_MAX_EVENTS", 100_000)?,
+                max_bytes: env_parse("DLQ_MAX_BYTES", 256 * 1024 * 1024)?,
+                max_age: Duration::from_secs(env_parse("DLQ_MAX_AGE_SECS", 7 * 86_400)?),
+            }),
+            None => None,
+        };
+
+        let min_window = watchdog::CHECK_INTERVAL * 2;
+        let divergence = match env_parse("EVENT_DIVERGENCE_WINDOW_SECS", 60)? {
+            0 => None,
+            secs if Duration::from_secs(secs) < min_window => {
+                return Err(GatewayError::Config(format!(
+                    "EVENT_DIVERGENCE_WINDOW_SECS must be 0 (disabled) or at least {}",
+                    min_window.as_secs()
+                )))
+            }
+            secs => Some(DivergenceConfig {
+                window: Duration::from_secs(secs),
+                tolerance: env_parse("EVENT_DIVERGENCE_TOLERANCE", 10)?,
+            }),
+        };
+
+        let ops_alerts = OpsAlertConfig {
+            webhook: var("OPS_ALERT_WEBHOOK_URL")
+                .ok()
+                .filter(|url| !url.is_empty())
+                .map(|url| WebhookTarget::parse(&url))
+                .transpose()?,
+            pager: pager_from_env()?,
+            nats_outage: Duration::from_secs(env_parse("OPS_ALERT_NATS_OUTAGE_SECS", 60)?),
+            queue_depth: Some(env_parse("OPS_ALERT_QUEUE_DEPTH", 0)?).filter(|depth| *depth > 0),
+        };
 
         Ok(Self {
-            discord_token,
+            discord_token: String::new(),
+            discord_token_source,
+            secrets_refresh,
             pool_id,
+            shards_per_pool,
             total_shards,
+            discover_shards,
             nats_url,
+            ensure_streams,
+            nats_security,
             http_port,
             log_level,
+            identify_budget_check,
+            identify_budget_reserve,
+            identify_coordination,
+            resume_sessions,
+            session_file,
+            shutdown_timeout,
+            liveness_stale_after,
+            ready_policy,
+            identify_pacing,
+            only_shards,
+            compression,
+            compression_metrics,
+            api_version,
+            ops_alerts,
+            metrics_backend,
+            metrics_buckets,
+            slow_publish,
+            traces,
+            flags,
+            consumer_lag_interval,
+            admin_grpc,
+            admin_token,
+            token_rotation_timeout,
+            topology_interval,
+            intents,
+            bot_id,
+            bots,
+            forward_messages,
+            forward_reactions,
+            forward_voice,
+            member_removal_audit,
+            interaction_defer,
+            rest_proxy,
+            command_sync,
+            commands_file,
+            guild_cache,
+            redis_cache,
+            ticks,
+            aggregate,
+            raid_detection,
+            guild_rate_limit,
+            event_sampling,
+            circuit_breaker_max_errors,
+            circuit_breaker,
+            dynamic_config,
+            resharding,
+            self_test,
+            event_index_size,
+            canary,
+            publish_budgets,
+            publish_batch,
+            publish_queue,
+            raw_passthrough,
+            eligibility,
+            schema_validation,
+            redaction,
+            outbox,
+            publish_retry,
+            dlq,
+            command_routing,
+            wire_format,
+            schema_versions,
+            canary_shard,
+            signing,
+            encryption,
+            divergence,
+            config_file: None,
+            unused_file_keys: Vec::new(),
         })
     }
 
+    /// Load configuration in layers: built-in defaults, the `--config`
+    /// file, the environment, then the other command-line flags
+    pub fn load(args: Vec<String>) -> Result<Self, GatewayError> {
+        let (path, args) = file::take_path(args)?;
+        let Some(path) = path else {
+            let mut config = Self::from_env()?;
+            config.apply_args(args)?;
+            return Ok(config);
+        };
+
+        let file = file::install(file::ConfigFile::load(&path)?);
+        let mut config = Self::from_env().map_err(|e| match e {
+            GatewayError::Config(message) => GatewayError::Config(file.locate(&message)),
+            e => e,
+        })?;
+        config.config_file = Some(file.path().to_path_buf());
+        config.unused_file_keys = file.unused();
+        config.apply_args(args)?;
+        Ok(config)
+    }
+
+    /// Read the bot tokens from their sources (the environment, files,
+    /// Vault or AWS Secrets Manager)
+    pub async fn read_secrets(&mut self) -> Result<(), GatewayError> {
+        self.discord_token = self.discord_token_source.read("DISCORD_TOKEN").await?;
+        for bot in &mut self.bots {
+            let name = format!("BOT_{}_DISCORD_TOKEN", bot.id.to_ascii_uppercase());
+            bot.token = bot.token_source.read(&name).await?;
+        }
+        Ok(())
+    }
+
+    /// Apply command-line flags (override the environment).
+    ///
+    /// Supported: `--only-shards 3,7` / `--only-shards=3,7` (`--config` is
+    /// taken by `load`)
+    pub fn apply_args(&mut self, args: impl IntoIterator<Item = String>) -> Result<(), GatewayError> {
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let value = match arg.split_once('=') {
+                Some(("--only-shards", value)) => value.to_string(),
+                None if arg == "--only-shards" => args.next().ok_or_else(|| {
+                    GatewayError::Config("--only-shards requires a shard list".to_string())
+                })?,
+                _ => return Err(GatewayError::Config(format!("Unknown argument: {arg}"))),
+            };
+            self.only_shards = Some(parse_shard_list(&value)?);
+        }
+        Ok(())
+    }
+
     /// Get configured Discord intents
     ///
-    /// Per SDD §5.1.2, we use minimal intents:
+    /// Per SDD §5.1.2, we use minimal intents by default (`base` is
+    /// `DEFAULT_INTENTS` unless `INTENTS` is set):
     /// - GUILDS: Required for guild lifecycle events
     /// - GUILD_MEMBERS: Required for member events (privileged)
-    /// - GUILD_MESSAGES: Optional, for message-based features
-    pub fn intents() -> Intents {
-        Intents::GUILDS | Intents::GUILD_MEMBERS
+    /// - GUILD_MESSAGES + MESSAGE_CONTENT: Only with `FORWARD_MESSAGES`;
+    ///   without MESSAGE_CONTENT (privileged) Discord strips attachments too
+    /// - GUILD_MESSAGE_REACTIONS: Only with `FORWARD_REACTIONS`
+    /// - GUILD_VOICE_STATES: Only with `FORWARD_VOICE`
+    pub fn intents(base: Intents, forward_messages: bool, forward_reactions: bool, forward_voice: bool) -> Intents {
+        let mut intents = base;
+        if forward_messages {
+            intents |= Intents::GUILD_MESSAGES | Intents::MESSAGE_CONTENT;
+        }
+        if forward_reactions {
+            intents |= Intents::GUILD_MESSAGE_REACTIONS;
+        }
+        if forward_voice {
+            intents |= Intents::GUILD_VOICE_STATES;
+        }
+        intents
+    }
+
+    /// Settings for `/status`: no token, URLs or keys, only whether the
+    /// features they enable are on
+    pub fn summary(&self) -> Value {
+        json!({
+            "pool_id": self.pool_id,
+            "total_shards": self.total_shards,
+            "shards_per_pool": self.shards_per_pool,
+            "discover_shards": self.discover_shards,
+            "only_shards": self.only_shards,
+            "nats": self.nats_url.is_some(),
+            "ensure_streams": self.ensure_streams.is_some(),
+            "nats_auth": self.nats_security.auth.as_ref().map(NatsAuth::as_str),
+            "nats_tls_client_cert": self.nats_security.client_cert.is_some(),
+            "http_port": self.http_port,
+            "log_level": self.log_level,
+            "compression": self.compression.as_str(),
+            "wire_format": self.wire_format.as_str(),
+            "wire_schema_version": self.schema_versions.wire,
+            "schema_dual_publish": self.schema_versions.dual_publish,
+            "canary_shard": self.canary_shard.as_ref().map(|canary| json!({
+                "shard_id": canary.shard_id,
+                "changes": canary.changes(),
+            })),
+            "schema_validation": self.schema_validation.as_str(),
+            "redaction": self.redaction.as_ref().map(|redaction| redaction.policy.rules.keys().collect::<Vec<_>>()),
+            "resume_sessions": self.resume_sessions,
+            "shutdown_timeout_secs": self.shutdown_timeout.as_secs(),
+            "liveness_stale_secs": self.liveness_stale_after.map(|after| after.as_secs()),
+            "ready_policy": self.ready_policy,
+            "identify_coordination": self.identify_coordination,
+            "discord_token_source": self.discord_token_source.provider(),
+            "secrets_refresh_secs": self.secrets_refresh.map(|every| every.as_secs()),
+            "intents": self.intents.iter_names().map(|(name, _)| name).collect::<Vec<_>>(),
+            "bot_id": self.bot_id,
+            "bots": self.bots.iter().map(|bot| json!({
+                "bot_id": bot.id,
+                "pool_id": bot.pool_id,
+                "total_shards": bot.total_shards,
+                "shards_per_pool": bot.shards_per_pool,
+            })).collect::<Vec<_>>(),
+            "forward_messages": self.forward_messages,
+            "forward_reactions": self.forward_reactions,
+            "forward_voice": self.forward_voice,
+            "guild_cache": self.guild_cache,
+            "redis_cache": self.redis_cache.is_some(),
+            "rest_proxy": self.rest_proxy,
+            "ticks": self.ticks,
+            "raid_detection": self.raid_detection.map(|raid| json!({
+                "joins": raid.threshold,
+                "window_secs": raid.window.as_secs(),
+            })),
+            "guild_rate_limit": self.guild_rate_limit.as_ref().map(|limit| json!({
+                "rate": limit.default.per_second,
+                "burst": limit.default.burst,
+                "guild_overrides": limit.guilds.len(),
+                "summary_secs": limit.summary_window.map(|window| window.as_secs()),
+            })),
+            "circuit_breaker": json!({
+                "max_errors": self.circuit_breaker_max_errors,
+                "cooldown_secs": self.circuit_breaker.cooldown.as_secs(),
+                "max_cooldown_secs": self.circuit_breaker.max_cooldown.as_secs(),
+            }),
+            "dynamic_config": self.dynamic_config,
+            "resharding": self.resharding.map(|reshard| json!({
+                "epoch": reshard.epoch,
+                "from": reshard.from,
+            })),
+            "config_file": self.config_file,
+            "outbox": self.outbox.is_some(),
+            "dlq": self.dlq.is_some(),
+            "publish_batch": self.publish_batch.is_some(),
+            "publish_queue_capacity": self.publish_queue.capacity,
+            "publish_retries": self.publish_retry.retries,
+            "eligibility": self.eligibility.is_some(),
+            "signing": self.signing.is_some(),
+            "encryption_subjects": self.encryption.as_ref().map(|encryption| &encryption.subjects),
+            "admin_http": self.admin_token.is_some(),
+            "admin_grpc": self.admin_grpc.is_some(),
+            "tracing": self.traces.is_some(),
+        })
+    }
+}
+
+/// Parse `INTENTS`: comma-separated intent names
+/// (`GUILDS,GUILD_MEMBERS,GUILD_VOICE_STATES`) or a bitmask (`641`, `0x281`)
+fn parse_intents(spec: &str) -> Result<Intents, GatewayError> {
+    let spec = spec.trim();
+    let mask = match spec.strip_prefix("0x").or_else(|| spec.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => spec.parse::<u64>().ok(),
+    };
+    let intents = match mask {
+        Some(mask) => Intents::from_bits(mask).ok_or_else(|| {
+            GatewayError::Config(format!("INTENTS bitmask {spec} has bits that aren't Discord intents"))
+        })?,
+        None => spec
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(|name| {
+                Intents::from_name(&name.to_ascii_uppercase())
+                    .ok_or_else(|| GatewayError::Config(format!("INTENTS has unknown intent {name:?}")))
+            })
+            .collect::<Result<Intents, _>>()?,
+    };
+
+    if !intents.contains(Intents::GUILDS) {
+        return Err(GatewayError::Config(
+            "INTENTS must include GUILDS (shards track their guilds from guild events)".to_string(),
+        ));
+    }
+    if intents.contains(Intents::MESSAGE_CONTENT)
+        && !intents.intersects(Intents::GUILD_MESSAGES | Intents::DIRECT_MESSAGES)
+    {
+        return Err(GatewayError::Config(
+            "INTENTS requests the privileged MESSAGE_CONTENT without GUILD_MESSAGES or DIRECT_MESSAGES".to_string(),
+        ));
+    }
+    Ok(intents)
+}
+
+/// Bots from `BOTS`: each `<id>` needs `BOT_<ID>_DISCORD_TOKEN` and may set
+/// `BOT_<ID>_POOL_ID`, `_TOTAL_SHARDS`, `_SHARDS_PER_POOL` and `_INTENTS`
+/// (defaults: pool 0 of 1 shard, the primary bot's `INTENTS`)
+fn bots_from_env(primary: Option<&str>, intents: Intents) -> Result<Vec<BotConfig>, GatewayError> {
+    let ids = parse_bot_ids(&var("BOTS").unwrap_or_default(), primary)?;
+    ids.into_iter()
+        .map(|id| {
+            let key = |name: &str| format!("BOT_{}_{name}", id.to_ascii_uppercase());
+            let token_source = SecretSource::lookup(&key("DISCORD_TOKEN"))?.ok_or_else(|| {
+                GatewayError::Config(format!("BOTS lists {id} but {} (or _FILE) is not set", key("DISCORD_TOKEN")))
+            })?;
+
+            let pool_id = env_parse(&key("POOL_ID"), 0)?;
+            let total_shards = env_parse(&key("TOTAL_SHARDS"), 1)?;
+            let shards_per_pool = env_parse(&key("SHARDS_PER_POOL"), DEFAULT_SHARDS_PER_POOL)?;
+            if total_shards == 0 || shards_per_pool == 0 {
+                return Err(GatewayError::Config(format!(
+                    "{} and {} must be at least 1",
+                    key("TOTAL_SHARDS"),
+                    key("SHARDS_PER_POOL")
+                )));
+            }
+            validate_pool(pool_id, total_shards, shards_per_pool).map_err(|e| match e {
+                GatewayError::Config(message) => GatewayError::Config(format!("Bot {id}: {message}")),
+                e => e,
+            })?;
+            let intents = match var(&key("INTENTS")) {
+                Ok(spec) => parse_intents(&spec)?,
+                Err(_) => intents,
+            };
+
+            Ok(BotConfig {
+                id,
+                token: String::new(),
+                token_source,
+                pool_id,
+                total_shards,
+                shards_per_pool,
+                intents,
+            })
+        })
+        .collect()
+}
+
+/// Parse `BOTS`: comma-separated ids, unique and different from `BOT_ID`,
+/// which must be set so the primary bot's events can be told apart
+fn parse_bot_ids(list: &str, primary: Option<&str>) -> Result<Vec<String>, GatewayError> {
+    let mut ids: Vec<String> = Vec::new();
+    for id in list.split(',').map(str::trim).filter(|id| !id.is_empty()) {
+        validate_bot_id("BOTS", id)?;
+        if primary == Some(id) || ids.iter().any(|seen| seen == id) {
+            return Err(GatewayError::Config(format!("Bot id {id} is used twice (BOT_ID, BOTS)")));
+        }
+        ids.push(id.to_string());
+    }
+    if !ids.is_empty() && primary.is_none() {
+        return Err(GatewayError::Config("BOTS requires BOT_ID for the primary bot".to_string()));
+    }
+    Ok(ids)
+}
+
+/// Bot ids name environment variables, so they are limited to `[a-z0-9_]`
+fn validate_bot_id(key: &str, id: &str) -> Result<(), GatewayError> {
+    if id.is_empty() || !id.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'_') {
+        return Err(GatewayError::Config(format!("{key}: bot id {id:?} must be lowercase letters, digits or _")));
+    }
+    Ok(())
+}
+
+/// Pager settings: `OPS_ALERT_PAGER` selects the provider and requires a key
+fn pager_from_env() -> Result<Option<PagerTarget>, GatewayError> {
+    let Some(provider) = var("OPS_ALERT_PAGER").ok().filter(|p| !p.is_empty()) else {
+        return Ok(None);
+    };
+    let provider = PagerProvider::parse(&provider).ok_or_else(|| {
+        GatewayError::Config(format!("OPS_ALERT_PAGER must be pagerduty or opsgenie, got {provider:?}"))
+    })?;
+    let key = var("OPS_ALERT_PAGER_KEY")
+        .ok()
+        .filter(|k| !k.is_empty())
+        .ok_or_else(|| GatewayError::Config(format!("OPS_ALERT_PAGER={provider} requires OPS_ALERT_PAGER_KEY")))?;
+
+    Ok(Some(PagerTarget {
+        provider,
+        key,
+        url: var("OPS_ALERT_PAGER_URL").ok().filter(|u| !u.is_empty()),
+    }))
+}
+
+/// NATS authentication and TLS (`topo` connects with these too). More than
+/// one authentication method is a configuration error rather than a guess.
+pub fn nats_security_from_env() -> Result<NatsSecurity, GatewayError> {
+    let mut methods = Vec::new();
+    if let Some(creds) = SecretSource::lookup("NATS_CREDS")? {
+        methods.push(NatsAuth::Credentials(creds));
+    }
+    if let Some(seed) = SecretSource::lookup("NATS_NKEY")? {
+        methods.push(NatsAuth::NKey(seed));
+    }
+    match (var("NATS_USER").ok().filter(|user| !user.is_empty()), SecretSource::lookup("NATS_PASSWORD")?) {
+        (Some(user), Some(password)) => methods.push(NatsAuth::UserPassword { user, password }),
+        (None, None) => {}
+        _ => return Err(GatewayError::Config("NATS_USER and NATS_PASSWORD must be set together".to_string())),
+    }
+    if let Some(token) = SecretSource::lookup("NATS_TOKEN")? {
+        methods.push(NatsAuth::Token(token));
+    }
+    if methods.len() > 1 {
+        let names: Vec<_> = methods.iter().map(NatsAuth::as_str).collect();
+        return Err(GatewayError::Config(format!(
+            "Set one NATS authentication method, got {}",
+            names.join(", ")
+        )));
+    }
+
+    let client_cert = match (SecretSource::lookup("NATS_TLS_CERT")?, SecretSource::lookup("NATS_TLS_KEY")?) {
+        (Some(cert), Some(key)) => Some((cert, key)),
+        (None, None) => None,
+        _ => return Err(GatewayError::Config("NATS_TLS_CERT and NATS_TLS_KEY must be set together".to_string())),
+    };
+    Ok(NatsSecurity {
+        auth: methods.pop(),
+        require_tls: env_flag("NATS_TLS", false)?,
+        ca: SecretSource::lookup("NATS_TLS_CA")?,
+        client_cert,
+    })
+}
+
+/// Admin gRPC settings. mTLS is mandatory: a port without all three PEMs is
+/// a configuration error rather than a plaintext admin API.
+fn admin_grpc_from_env() -> Result<Option<AdminGrpcConfig>, GatewayError> {
+    let port: u16 = env_parse("ADMIN_GRPC_PORT", 0)?;
+    if port == 0 {
+        return Ok(None);
+    }
+    let pem = |key: &str| {
+        var(key)
+            .ok()
+            .filter(|v| !v.trim().is_empty())
+            .ok_or_else(|| GatewayError::Config(format!("ADMIN_GRPC_PORT requires {key} (mTLS)")))
+    };
+
+    Ok(Some(AdminGrpcConfig {
+        port,
+        cert: pem("ADMIN_GRPC_TLS_CERT")?,
+        key: pem("ADMIN_GRPC_TLS_KEY")?,
+        client_ca: pem("ADMIN_GRPC_CLIENT_CA")?,
+    }))
+}
+
+/// Parse a shard list such as `3,7` or `0-4,9`
+pub fn parse_shard_list(list: &str) -> Result<BTreeSet<u64>, GatewayError> {
+    let invalid = |part: &str| GatewayError::Config(format!("Invalid shard list entry {part:?} in {list:?}"));
+
+    let mut shards = BTreeSet::new();
+    for part in list.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        match part.split_once('-') {
+            Some((start, end)) => {
+                let start: u64 = start.trim().parse().map_err(|_| invalid(part))?;
+                let end: u64 = end.trim().parse().map_err(|_| invalid(part))?;
+                if start > end {
+                    return Err(invalid(part));
+                }
+                shards.extend(start..=end);
+            }
+            None => {
+                shards.insert(part.parse().map_err(|_| invalid(part))?);
+            }
+        }
+    }
+
+    if shards.is_empty() {
+        return Err(GatewayError::Config("Shard list is empty".to_string()));
+    }
+    Ok(shards)
+}
+
+/// Twilight fixes the transport codec at compile time: refuse to run a build
+/// whose codec differs from the one the deployment asked for.
+fn check_compression(
+    requested: TransportCompression,
+    compiled: TransportCompression,
+) -> Result<(), GatewayError> {
+    if requested == compiled {
+        return Ok(());
+    }
+    let build = match requested.feature() {
+        Some(feature) => format!("rebuild with `--no-default-features --features jemalloc,{feature}`"),
+        None => "rebuild with `--no-default-features --features jemalloc`".to_string(),
+    };
+    Err(GatewayError::Config(format!(
+        "GATEWAY_COMPRESSION={requested} but this binary was built with {compiled}; {build}"
+    )))
+}
+
+/// `STREAM_<NAME>_STORAGE` (`file` or `memory`), `_MAX_AGE_SECS`,
+/// `_MAX_MSGS`, `_MAX_BYTES` and `_REPLICAS` for a stream `ENSURE_STREAMS`
+/// creates
+fn stream_overrides_from_env(stream: &str) -> Result<StreamOverrides, GatewayError> {
+    let key = |setting: &str| format!("STREAM_{stream}_{setting}");
+    let storage = match var(&key("STORAGE")) {
+        Ok(value) => Some(match value.trim() {
+            "file" => StorageType::File,
+            "memory" => StorageType::Memory,
+            _ => return Err(GatewayError::Config(format!("{} must be file or memory, got {value:?}", key("STORAGE")))),
+        }),
+        Err(_) => None,
+    };
+    Ok(StreamOverrides {
+        storage,
+        max_age_secs: env_parse_opt(&key("MAX_AGE_SECS"))?,
+        max_msgs: env_parse_opt(&key("MAX_MSGS"))?,
+        max_bytes: env_parse_opt(&key("MAX_BYTES"))?,
+        replicas: env_parse_opt(&key("REPLICAS"))?,
+    })
+}
+
+/// The ordinal a StatefulSet gives a pod (`arrakis-gateway-3` is 3)
+fn pool_id_from_hostname(hostname: &str) -> Option<u64> {
+    let (_, ordinal) = hostname.trim().rsplit_once('-')?;
+    if ordinal.is_empty() || !ordinal.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    ordinal.parse().ok()
+}
+
+/// A setting from the environment, or else from the `--config` file
+pub(crate) fn var(key: &str) -> Result<String, env::VarError> {
+    env::var(key).or_else(|e| file::installed().and_then(|file| file.get(key)).map(str::to_string).ok_or(e))
+}
+
+/// Parse an optional environment variable, falling back to `default`
+fn env_parse<T>(key: &str, default: T) -> Result<T, GatewayError>
+where
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    match var(key) {
+        Ok(value) => value
+            .trim()
+            .parse()
+            .map_err(|e| GatewayError::Config(format!("{key} is invalid ({value:?}): {e}"))),
+        Err(_) => Ok(default),
+    }
+}
+
+/// Parse an optional environment variable; None when unset
+fn env_parse_opt<T>(key: &str) -> Result<Option<T>, GatewayError>
+where
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    match var(key) {
+        Ok(value) => value
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|e| GatewayError::Config(format!("{key} is invalid ({value:?}): {e}"))),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Parse an optional boolean environment variable (true/false/1/0/yes/no)
+fn env_flag(key: &str, default: bool) -> Result<bool, GatewayError> {
+    match var(key) {
+        Ok(value) => parse_flag(&value)
+            .ok_or_else(|| GatewayError::Config(format!("{key} must be a boolean, got {value:?}"))),
+        Err(_) => Ok(default),
+    }
+}
+
+/// Parse a boolean flag value
+fn parse_flag(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Some(true),
+        "0" | "false" | "no" | "off" => Some(false),
+        _ => None,
     }
 }
 
@@ -90,7 +1363,7 @@ mod tests {
 
     #[test]
     fn test_intents_are_minimal() {
-        let intents = GatewayConfig::intents();
+        let intents = GatewayConfig::intents(DEFAULT_INTENTS, false, false, false);
 
         // Should have GUILDS and GUILD_MEMBERS
         assert!(intents.contains(Intents::GUILDS));
@@ -98,13 +1371,106 @@ mod tests {
 
         // Should NOT have message content (privileged, not needed)
         assert!(!intents.contains(Intents::MESSAGE_CONTENT));
+        assert!(!intents.contains(Intents::GUILD_MESSAGES));
+    }
+
+    #[test]
+    fn test_forward_messages_adds_message_intents() {
+        let intents = GatewayConfig::intents(DEFAULT_INTENTS, true, false, false);
+        assert!(intents.contains(Intents::GUILD_MESSAGES | Intents::MESSAGE_CONTENT));
+        assert!(intents.contains(GatewayConfig::intents(DEFAULT_INTENTS, false, false, false)));
+    }
+
+    #[test]
+    fn test_forward_reactions_adds_reaction_intent() {
+        let intents = GatewayConfig::intents(DEFAULT_INTENTS, false, true, false);
+        assert!(intents.contains(Intents::GUILD_MESSAGE_REACTIONS));
+        assert!(!intents.contains(Intents::MESSAGE_CONTENT));
+    }
+
+    #[test]
+    fn test_forward_voice_adds_voice_state_intent() {
+        let intents = GatewayConfig::intents(DEFAULT_INTENTS, false, false, true);
+        assert!(intents.contains(Intents::GUILD_VOICE_STATES));
+        assert!(!intents.contains(Intents::GUILD_MESSAGE_REACTIONS));
+    }
+
+    #[test]
+    fn test_intents_by_name_or_bitmask() {
+        let voice = Intents::GUILDS | Intents::GUILD_VOICE_STATES;
+        assert_eq!(parse_intents("GUILDS, guild_voice_states").unwrap(), voice);
+        assert_eq!(parse_intents(&voice.bits().to_string()).unwrap(), voice);
+        assert_eq!(parse_intents(&format!("{:#x}", voice.bits())).unwrap(), voice);
+
+        let intents = GatewayConfig::intents(voice, true, false, false);
+        assert!(!intents.contains(Intents::GUILD_MEMBERS), "INTENTS replaces the default set");
+        assert!(intents.contains(Intents::GUILD_MESSAGES));
+    }
+
+    #[test]
+    fn test_bot_ids() {
+        assert_eq!(parse_bot_ids("staging, partner_2", Some("main")).unwrap(), ["staging", "partner_2"]);
+        assert!(parse_bot_ids("", None).unwrap().is_empty());
+
+        assert!(parse_bot_ids("staging", None).is_err(), "BOT_ID is required");
+        assert!(parse_bot_ids("staging,staging", Some("main")).is_err());
+        assert!(parse_bot_ids("main", Some("main")).is_err());
+        assert!(parse_bot_ids("Staging", Some("main")).is_err());
+        assert!(parse_bot_ids("stag-ing", Some("main")).is_err());
+    }
+
+    #[test]
+    fn test_invalid_intents_are_rejected() {
+        for spec in ["GUILDS,GUILD_TYPOS", "GUILD_MEMBERS", "GUILDS,MESSAGE_CONTENT", "0x8000000000"] {
+            assert!(parse_intents(spec).is_err(), "{spec}");
+        }
+    }
+
+    #[test]
+    fn test_parse_flag() {
+        assert_eq!(parse_flag("true"), Some(true));
+        assert_eq!(parse_flag(" YES "), Some(true));
+        assert_eq!(parse_flag("0"), Some(false));
+        assert_eq!(parse_flag("maybe"), None);
+    }
+
+    #[test]
+    fn test_parse_shard_list() {
+        assert_eq!(parse_shard_list("3,7").unwrap(), BTreeSet::from([3, 7]));
+        assert_eq!(parse_shard_list("0-2, 9").unwrap(), BTreeSet::from([0, 1, 2, 9]));
+        assert!(parse_shard_list("5-3").is_err());
+        assert!(parse_shard_list("x").is_err());
+        assert!(parse_shard_list("").is_err());
+    }
+
+    #[test]
+    fn test_compression_must_match_build() {
+        let compiled = TransportCompression::compiled();
+        assert!(check_compression(compiled, compiled).is_ok());
+
+        let other = if compiled == TransportCompression::None {
+            TransportCompression::ZlibStream
+        } else {
+            TransportCompression::None
+        };
+        assert!(check_compression(other, compiled).is_err());
+    }
+
+    #[test]
+    fn test_pool_id_from_statefulset_hostname() {
+        assert_eq!(pool_id_from_hostname("arrakis-gateway-3"), Some(3));
+        assert_eq!(pool_id_from_hostname("arrakis-gateway-0\n"), Some(0));
+        assert_eq!(pool_id_from_hostname("arrakis-gateway-7d9f8b6c4-x2x9z"), None, "Deployment pod names have no ordinal");
+        assert_eq!(pool_id_from_hostname("gateway"), None);
+        assert_eq!(pool_id_from_hostname("gateway-"), None);
+        assert_eq!(pool_id_from_hostname("gateway-+1"), None);
     }
 
     #[test]
     fn test_default_values() {
         // Pool ID should default to 0
         assert_eq!(
-            env::var("POOL_ID")
+            var("POOL_ID")
                 .unwrap_or_else(|_| "0".to_string())
                 .parse::<u64>()
                 .unwrap(),
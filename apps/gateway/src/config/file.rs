@@ -0,0 +1,227 @@
+//! Settings from a TOML or YAML file (`--config`)
+//!
+//! A file uses the environment variable names, lowercased, and may nest
+//! them on underscores: `nats_url = "..."` and `[nats]` with `url = "..."`
+//! both set `NATS_URL`. Lists become comma-separated values. The environment
+//! still wins over the file, and built-in defaults apply to anything neither
+//! sets.
+
+use crate::error::GatewayError;
+use ::config::{Config, File, Map, Value, ValueKind};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+/// The file settings are read from, once loaded
+static INSTALLED: OnceLock<ConfigFile> = OnceLock::new();
+
+/// One setting from the file
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Setting {
+    /// Where it is in the file, e.g. `nats.url`
+    key: String,
+    value: String,
+}
+
+/// Settings loaded from a config file, by environment variable name
+#[derive(Debug)]
+pub struct ConfigFile {
+    path: PathBuf,
+    settings: BTreeMap<String, Setting>,
+    /// Names looked up without an environment value, so the file's was used
+    used: Mutex<BTreeSet<String>>,
+}
+
+impl ConfigFile {
+    /// Read and flatten a file; the format follows the extension
+    /// (`.toml`, `.yaml` or `.yml`)
+    pub fn load(path: &Path) -> Result<Self, GatewayError> {
+        let table = Config::builder()
+            .add_source(File::from(path))
+            .build()
+            .and_then(|config| config.try_deserialize::<Map<String, Value>>())
+            .map_err(|e| GatewayError::Config(format!("Config file {}: {e}", path.display())))?;
+        Self::from_table(path, table)
+    }
+
+    fn from_table(path: &Path, table: Map<String, Value>) -> Result<Self, GatewayError> {
+        let mut file = Self {
+            path: path.to_path_buf(),
+            settings: BTreeMap::new(),
+            used: Mutex::default(),
+        };
+        file.flatten(&[], table)?;
+        Ok(file)
+    }
+
+    fn flatten(&mut self, parents: &[String], table: Map<String, Value>) -> Result<(), GatewayError> {
+        for (name, value) in table {
+            let path: Vec<String> = parents.iter().cloned().chain([name]).collect();
+            let key = path.join(".");
+            let value = match value.kind {
+                ValueKind::Table(table) => {
+                    self.flatten(&path, table)?;
+                    continue;
+                }
+                ValueKind::Nil => continue,
+                ValueKind::Array(items) => items
+                    .into_iter()
+                    .map(|item| match item.kind {
+                        ValueKind::Table(_) | ValueKind::Array(_) => Err(GatewayError::Config(format!(
+                            "`{key}` in {} must be a list of plain values",
+                            self.path.display()
+                        ))),
+                        kind => Ok(kind.to_string()),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?
+                    .join(","),
+                kind => kind.to_string(),
+            };
+
+            let name = path.join("_").to_ascii_uppercase();
+            let setting = Setting { key: key.clone(), value };
+            if let Some(earlier) = self.settings.insert(name.clone(), setting) {
+                return Err(GatewayError::Config(format!(
+                    "`{key}` and `{}` in {} both set {name}",
+                    earlier.key,
+                    self.path.display()
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The file's value for an environment variable name
+    pub fn get(&self, name: &str) -> Option<&str> {
+        let setting = self.settings.get(name)?;
+        self.used.lock().unwrap_or_else(|e| e.into_inner()).insert(name.to_string());
+        Some(&setting.value)
+    }
+
+    /// File keys that were never read: unknown settings, ones the
+    /// environment overrides, or ones other settings leave unused
+    pub fn unused(&self) -> Vec<String> {
+        let used = self.used.lock().unwrap_or_else(|e| e.into_inner());
+        self.settings
+            .iter()
+            .filter(|(name, _)| !used.contains(*name))
+            .map(|(_, setting)| setting.key.clone())
+            .collect()
+    }
+
+    /// Point an error about a setting at the file key it came from
+    pub fn locate(&self, message: &str) -> String {
+        let used = self.used.lock().unwrap_or_else(|e| e.into_inner());
+        let keys: Vec<String> = self
+            .settings
+            .iter()
+            .filter(|(name, _)| used.contains(*name) && names(message, name))
+            .map(|(_, setting)| format!("`{}`", setting.key))
+            .collect();
+        if keys.is_empty() {
+            return message.to_string();
+        }
+        format!("{message} (set by {} in {})", keys.join(", "), self.path.display())
+    }
+}
+
+/// Whether `message` mentions the variable `name` as a whole word
+fn names(message: &str, name: &str) -> bool {
+    let part_of_name = |c: char| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_';
+    message.match_indices(name).any(|(at, _)| {
+        !message[..at].ends_with(part_of_name) && !message[at + name.len()..].starts_with(part_of_name)
+    })
+}
+
+/// Make a loaded file the fallback for every setting
+pub fn install(file: ConfigFile) -> &'static ConfigFile {
+    INSTALLED.get_or_init(|| file)
+}
+
+/// The installed file, if `--config` was given
+pub fn installed() -> Option<&'static ConfigFile> {
+    INSTALLED.get()
+}
+
+/// Split `--config <path>` (or `--config=<path>`) from the other arguments
+pub fn take_path(args: Vec<String>) -> Result<(Option<PathBuf>, Vec<String>), GatewayError> {
+    let mut path = None;
+    let mut rest = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.split_once('=') {
+            Some(("--config", value)) => path = Some(PathBuf::from(value)),
+            None if arg == "--config" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| GatewayError::Config("--config requires a file path".to_string()))?;
+                path = Some(PathBuf::from(value));
+            }
+            _ => rest.push(arg),
+        }
+    }
+    Ok((path, rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::config::FileFormat;
+
+    fn parse(source: &str, format: FileFormat) -> Result<ConfigFile, GatewayError> {
+        let table = Config::builder()
+            .add_source(File::from_str(source, format))
+            .build()
+            .and_then(|config| config.try_deserialize::<Map<String, Value>>())
+            .unwrap();
+        ConfigFile::from_table(Path::new("gateway.conf"), table)
+    }
+
+    #[test]
+    fn nested_keys_map_to_variable_names() {
+        let toml = "pool_id = 2\nonly_shards = [3, 7]\n\n[nats]\nurl = \"nats://nats:4222\"\n\n[ready]\nmin_fraction = 0.5\n";
+        let yaml = "pool_id: 2\nonly_shards: [3, 7]\nnats:\n  url: nats://nats:4222\nready:\n  min_fraction: 0.5\n";
+
+        for file in [parse(toml, FileFormat::Toml).unwrap(), parse(yaml, FileFormat::Yaml).unwrap()] {
+            assert_eq!(file.get("POOL_ID"), Some("2"));
+            assert_eq!(file.get("ONLY_SHARDS"), Some("3,7"));
+            assert_eq!(file.get("NATS_URL"), Some("nats://nats:4222"));
+            assert_eq!(file.get("READY_MIN_FRACTION"), Some("0.5"));
+            assert_eq!(file.get("LOG_LEVEL"), None);
+        }
+    }
+
+    #[test]
+    fn a_setting_given_twice_is_rejected() {
+        let error = parse("nats_url = \"a\"\n[nats]\nurl = \"b\"\n", FileFormat::Toml).unwrap_err();
+        assert!(error.to_string().contains("NATS_URL"), "{error}");
+    }
+
+    #[test]
+    fn errors_and_leftovers_name_the_file_key() {
+        let file = parse("shards_per_pool = 0\nnats_ulr = \"nats://nats:4222\"\n", FileFormat::Toml).unwrap();
+        file.get("SHARDS_PER_POOL");
+
+        assert_eq!(
+            file.locate("SHARDS_PER_POOL must be at least 1"),
+            "SHARDS_PER_POOL must be at least 1 (set by `shards_per_pool` in gateway.conf)"
+        );
+        assert_eq!(file.locate("NATS_URL is required"), "NATS_URL is required");
+        assert_eq!(file.unused(), ["nats_ulr"]);
+    }
+
+    #[test]
+    fn config_path_is_taken_from_the_arguments() {
+        let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        let (path, rest) = take_path(args(&["--only-shards", "3", "--config", "gateway.toml"])).unwrap();
+        assert_eq!(path, Some(PathBuf::from("gateway.toml")));
+        assert_eq!(rest, ["--only-shards", "3"]);
+
+        assert_eq!(take_path(args(&["--config=g.yaml"])).unwrap().0, Some(PathBuf::from("g.yaml")));
+        assert!(take_path(args(&["--config"])).is_err());
+    }
+}
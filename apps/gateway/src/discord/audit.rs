@@ -0,0 +1,131 @@
+//! Member removal classification from the guild audit log
+//!
+//! GUILD_MEMBER_REMOVE looks the same for a leave, a kick and a ban. Kicks
+//! and bans write an audit log entry targeting the member at about the time
+//! the gateway event arrives; no such entry means the member left. Needs the
+//! View Audit Log permission in each guild.
+
+use crate::error::GatewayError;
+use std::time::Duration;
+use twilight_http::Client;
+use twilight_model::guild::audit_log::{AuditLogEntry, AuditLogEventType};
+use twilight_model::id::marker::{GuildMarker, UserMarker};
+use twilight_model::id::Id;
+
+/// Wait before looking, since the entry can be written after the event
+pub const LOOKUP_DELAY: Duration = Duration::from_secs(1);
+
+/// How far an entry's creation may be from the removal to count as its cause
+pub const WINDOW: Duration = Duration::from_secs(10);
+
+/// Entries fetched per lookup (Discord allows up to 100)
+const ENTRY_LIMIT: u16 = 50;
+
+/// Discord epoch (2015-01-01) in Unix milliseconds
+const DISCORD_EPOCH_MS: u64 = 1_420_070_400_000;
+
+/// Why a member left a guild
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RemovalReason {
+    Leave,
+    Kick,
+    Ban,
+    /// The audit log could not be read
+    Unknown,
+}
+
+impl RemovalReason {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Leave => "leave",
+            Self::Kick => "kick",
+            Self::Ban => "ban",
+            Self::Unknown => "unknown",
+        }
+    }
+}
+
+/// Classify a removal at `removed_at_ms` from recent audit log entries
+pub fn classify(entries: &[AuditLogEntry], user_id: Id<UserMarker>, removed_at_ms: u64) -> RemovalReason {
+    let window_ms = WINDOW.as_millis() as u64;
+
+    entries
+        .iter()
+        .filter(|entry| entry.target_id.is_some_and(|target| target.get() == user_id.get()))
+        .filter(|entry| snowflake_millis(entry.id.get()).abs_diff(removed_at_ms) <= window_ms)
+        .find_map(|entry| match entry.action_type {
+            AuditLogEventType::MemberKick => Some(RemovalReason::Kick),
+            AuditLogEventType::MemberBanAdd => Some(RemovalReason::Ban),
+            _ => None,
+        })
+        .unwrap_or(RemovalReason::Leave)
+}
+
+/// Look up why `user_id` was removed from `guild_id`
+pub async fn removal_reason(
+    client: &Client,
+    guild_id: Id<GuildMarker>,
+    user_id: Id<UserMarker>,
+    removed_at_ms: u64,
+) -> Result<RemovalReason, GatewayError> {
+    let failed = |e: Box<dyn std::error::Error + Send + Sync>| GatewayError::DiscordRequestFailed {
+        route: "GET /guilds/{guild.id}/audit-logs",
+        source: e,
+    };
+
+    let log = client
+        .audit_log(guild_id)
+        .limit(ENTRY_LIMIT)
+        .await
+        .map_err(|e| failed(Box::new(e)))?
+        .model()
+        .await
+        .map_err(|e| failed(Box::new(e)))?;
+
+    Ok(classify(&log.entries, user_id, removed_at_ms))
+}
+
+/// Creation time of a snowflake in Unix milliseconds
+fn snowflake_millis(id: u64) -> u64 {
+    (id >> 22) + DISCORD_EPOCH_MS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USER: u64 = 987654321098765432;
+    const REMOVED_AT: u64 = 1_700_000_000_000;
+
+    /// An entry created `offset_ms` after the removal
+    fn entry(action_type: u16, target: u64, offset_ms: i64) -> AuditLogEntry {
+        let created = (REMOVED_AT as i64 + offset_ms) as u64;
+        serde_json::from_value(serde_json::json!({
+            "action_type": action_type,
+            "id": (((created - DISCORD_EPOCH_MS) << 22) | 1).to_string(),
+            "target_id": target.to_string(),
+            "user_id": "111111111111111111",
+            "reason": null
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_kick_and_ban_within_window() {
+        let user = Id::new(USER);
+        assert_eq!(classify(&[entry(20, USER, 800)], user, REMOVED_AT), RemovalReason::Kick);
+        assert_eq!(classify(&[entry(22, USER, -300)], user, REMOVED_AT), RemovalReason::Ban);
+    }
+
+    #[test]
+    fn test_unrelated_or_stale_entries_mean_leave() {
+        let user = Id::new(USER);
+        let entries = [
+            entry(20, 222222222222222222, 0),
+            entry(22, USER, -60_000),
+            entry(25, USER, 0),
+        ];
+        assert_eq!(classify(&entries, user, REMOVED_AT), RemovalReason::Leave);
+        assert_eq!(classify(&[], user, REMOVED_AT), RemovalReason::Leave);
+    }
+}
@@ -0,0 +1,371 @@
+//! Application command sync (`COMMAND_SYNC`)
+//!
+//! Slash command definitions live in `packages/shared/nats-schemas/commands.json`
+//! (embedded at build time, see build.rs; `COMMANDS_FILE` overrides it), next
+//! to the routing constants the workers import, so every environment
+//! registers the commands its workers handle. At startup the gateway compares
+//! them with what Discord has registered, globally and per guild, and logs the
+//! drift (`diff`) or also upserts the missing and changed commands (`apply`).
+//! Commands registered with Discord but missing from the file are reported,
+//! never deleted.
+//!
+//! A registered command matches its definition when every field the file
+//! sets has the same value; fields Discord fills in (IDs, `version`, and
+//! defaults such as `nsfw: false`) are ignored.
+
+use crate::error::GatewayError;
+use crate::metrics::GatewayMetrics;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+use tracing::{info, warn};
+use twilight_http::request::{Method, RequestBuilder};
+use twilight_http::Client;
+use twilight_model::id::marker::ApplicationMarker;
+use twilight_model::id::Id;
+
+mod embedded {
+    include!(concat!(env!("OUT_DIR"), "/commands.rs"));
+}
+
+/// What the gateway does with command drift at startup
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SyncMode {
+    #[default]
+    Off,
+    /// Log the drift only
+    Diff,
+    /// Log the drift and upsert missing and changed commands
+    Apply,
+}
+
+impl SyncMode {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim() {
+            "off" => Some(Self::Off),
+            "diff" => Some(Self::Diff),
+            "apply" => Some(Self::Apply),
+            _ => None,
+        }
+    }
+
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Diff => "diff",
+            Self::Apply => "apply",
+        }
+    }
+}
+
+/// Where a set of commands is registered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scope {
+    Global,
+    Guild(u64),
+}
+
+impl Scope {
+    /// Metric and log label
+    fn label(self) -> String {
+        match self {
+            Self::Global => "global".to_string(),
+            Self::Guild(guild_id) => guild_id.to_string(),
+        }
+    }
+
+    fn path(self, application_id: Id<ApplicationMarker>) -> String {
+        match self {
+            Self::Global => format!("applications/{application_id}/commands"),
+            Self::Guild(guild_id) => format!("applications/{application_id}/guilds/{guild_id}/commands"),
+        }
+    }
+
+    const fn route(self, method: Method) -> &'static str {
+        match (self, method) {
+            (Self::Global, Method::Post) => "POST /applications/{application.id}/commands",
+            (Self::Global, _) => "GET /applications/{application.id}/commands",
+            (Self::Guild(_), Method::Post) => "POST /applications/{application.id}/guilds/{guild.id}/commands",
+            (Self::Guild(_), _) => "GET /applications/{application.id}/guilds/{guild.id}/commands",
+        }
+    }
+}
+
+/// Contents of commands.json
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct CommandSet {
+    /// Discord's create-command JSON bodies
+    #[serde(default)]
+    pub global: Vec<Value>,
+    /// By guild ID
+    #[serde(default)]
+    pub guilds: BTreeMap<String, Vec<Value>>,
+}
+
+impl CommandSet {
+    /// Parse and check a commands.json document
+    pub fn parse(json: &str) -> Result<Self, GatewayError> {
+        let set: Self =
+            serde_json::from_str(json).map_err(|e| GatewayError::Config(format!("commands.json is invalid: {e}")))?;
+        for (scope, commands) in set.scopes()? {
+            let mut seen = std::collections::HashSet::new();
+            for command in commands {
+                let key = key(command).ok_or_else(|| {
+                    GatewayError::Config(format!("commands.json has a {} command without a name", scope.label()))
+                })?;
+                if !seen.insert(key) {
+                    return Err(GatewayError::Config(format!(
+                        "commands.json defines {} command {:?} twice",
+                        scope.label(),
+                        key.1
+                    )));
+                }
+            }
+        }
+        Ok(set)
+    }
+
+    /// From `COMMANDS_FILE`, else the copy embedded at build time
+    pub fn load(path: Option<&Path>) -> Result<Self, GatewayError> {
+        match path {
+            Some(path) => Self::parse(&std::fs::read_to_string(path).map_err(|e| {
+                GatewayError::Config(format!("COMMANDS_FILE {} can't be read: {e}", path.display()))
+            })?),
+            None => Self::parse(embedded::COMMANDS.ok_or_else(|| {
+                GatewayError::Config("COMMAND_SYNC needs COMMANDS_FILE (this build has no commands.json)".to_string())
+            })?),
+        }
+    }
+
+    /// Number of commands, across scopes
+    pub fn len(&self) -> usize {
+        self.global.len() + self.guilds.values().map(Vec::len).sum::<usize>()
+    }
+
+    fn scopes(&self) -> Result<Vec<(Scope, &[Value])>, GatewayError> {
+        let mut scopes = vec![(Scope::Global, self.global.as_slice())];
+        for (guild_id, commands) in &self.guilds {
+            let guild_id = guild_id
+                .parse()
+                .ok()
+                .filter(|id| *id != 0)
+                .ok_or_else(|| GatewayError::Config(format!("commands.json guild key {guild_id:?} is not a guild ID")))?;
+            scopes.push((Scope::Guild(guild_id), commands.as_slice()));
+        }
+        Ok(scopes)
+    }
+}
+
+/// A command's identity: type (1, chat input, when unset) and name
+fn key(command: &Value) -> Option<(u64, &str)> {
+    Some((command.get("type").and_then(Value::as_u64).unwrap_or(1), command.get("name")?.as_str()?))
+}
+
+/// Differences between commands.json and Discord, for one scope
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Drift {
+    /// Defined but not registered
+    pub missing: Vec<String>,
+    /// Registered with different values
+    pub changed: Vec<String>,
+    /// Registered but not defined
+    pub unmanaged: Vec<String>,
+    pub unchanged: usize,
+}
+
+/// Compare a scope's definitions with its registered commands
+pub fn diff(defined: &[Value], registered: &[Value]) -> Drift {
+    let mut drift = Drift::default();
+    for command in defined {
+        let Some(key) = key(command) else { continue };
+        match registered.iter().find(|registered| self::key(registered) == Some(key)) {
+            None => drift.missing.push(key.1.to_string()),
+            Some(registered) if !matches(command, Some(registered)) => drift.changed.push(key.1.to_string()),
+            Some(_) => drift.unchanged += 1,
+        }
+    }
+    drift.unmanaged = registered
+        .iter()
+        .filter_map(key)
+        .filter(|key| !defined.iter().any(|command| self::key(command) == Some(*key)))
+        .map(|(_, name)| name.to_string())
+        .collect();
+    drift
+}
+
+/// Whether every field a definition sets has the same registered value
+fn matches(defined: &Value, registered: Option<&Value>) -> bool {
+    match (defined, registered) {
+        (Value::Object(defined), Some(Value::Object(registered))) => {
+            defined.iter().all(|(field, value)| matches(value, registered.get(field)))
+        }
+        (Value::Array(defined), Some(Value::Array(registered))) => {
+            defined.len() == registered.len() && defined.iter().zip(registered).all(|(d, r)| matches(d, Some(r)))
+        }
+        // Discord leaves out fields at their default
+        (defined, None | Some(Value::Null)) => {
+            matches!(defined, Value::Null | Value::Bool(false))
+                || defined.as_array().is_some_and(Vec::is_empty)
+                || defined.as_object().is_some_and(serde_json::Map::is_empty)
+        }
+        (defined, Some(registered)) => defined == registered,
+    }
+}
+
+async fn request(
+    client: &Client,
+    method: Method,
+    path: String,
+    body: Option<&Value>,
+    route: &'static str,
+) -> Result<Vec<u8>, GatewayError> {
+    let failed = |source: Box<dyn std::error::Error + Send + Sync>| GatewayError::DiscordRequestFailed { route, source };
+    let mut builder = RequestBuilder::raw(method, path);
+    if let Some(body) = body {
+        builder = builder.json(body);
+    }
+    let request = builder.build().map_err(|e| failed(Box::new(e)))?;
+    let response = client.request::<Value>(request).await.map_err(|e| failed(Box::new(e)))?;
+    response.bytes().await.map_err(|e| failed(Box::new(e)))
+}
+
+/// Compare commands.json with Discord's commands, and in `apply` mode upsert
+/// what is missing or changed
+pub async fn sync(
+    client: &Client,
+    commands: &CommandSet,
+    mode: SyncMode,
+    metrics: &GatewayMetrics,
+) -> Result<(), GatewayError> {
+    let application_id = client
+        .current_user_application()
+        .await
+        .map_err(|e| GatewayError::DiscordRequestFailed { route: "GET /oauth2/applications/@me", source: Box::new(e) })?
+        .model()
+        .await
+        .map_err(|e| GatewayError::DiscordRequestFailed { route: "GET /oauth2/applications/@me", source: Box::new(e) })?
+        .id;
+
+    for (scope, defined) in commands.scopes()? {
+        let path = scope.path(application_id);
+        let body = request(client, Method::Get, format!("{path}?with_localizations=true"), None, scope.route(Method::Get))
+            .await?;
+        let registered: Vec<Value> = serde_json::from_slice(&body).map_err(|e| GatewayError::DiscordRequestFailed {
+            route: scope.route(Method::Get),
+            source: Box::new(e),
+        })?;
+
+        let drift = diff(defined, &registered);
+        info!(
+            scope = scope.label(),
+            missing = ?drift.missing,
+            changed = ?drift.changed,
+            unmanaged = ?drift.unmanaged,
+            unchanged = drift.unchanged,
+            "Application command drift"
+        );
+
+        let mut drifted = drift.missing.len() + drift.changed.len();
+        if mode == SyncMode::Apply {
+            let upserts = defined.iter().filter(|command| {
+                !registered
+                    .iter()
+                    .any(|registered| key(registered) == key(command) && matches(command, Some(registered)))
+            });
+            for command in upserts {
+                // Creating a command with a registered name updates it
+                match request(client, Method::Post, path.clone(), Some(command), scope.route(Method::Post)).await {
+                    Ok(_) => {
+                        drifted -= 1;
+                        metrics.record_command_upsert("ok");
+                    }
+                    Err(e) => {
+                        warn!(scope = scope.label(), command = ?command.get("name"), error = %e, "Command upsert failed");
+                        metrics.record_command_upsert("failed");
+                    }
+                }
+            }
+        }
+        metrics.set_command_drift(&scope.label(), drifted);
+    }
+    Ok(())
+}
+
+/// Run the startup sync, logging instead of failing
+pub async fn run(client: Arc<Client>, commands: CommandSet, mode: SyncMode, metrics: Arc<GatewayMetrics>) {
+    if let Err(e) = sync(&client, &commands, mode, &metrics).await {
+        warn!(mode = mode.as_str(), error = %e, "Application command sync failed");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn sync_mode_names() {
+        assert_eq!(SyncMode::parse("apply"), Some(SyncMode::Apply));
+        assert_eq!(SyncMode::parse(" diff "), Some(SyncMode::Diff));
+        assert_eq!(SyncMode::parse("prune"), None);
+        assert_eq!(SyncMode::Off.as_str(), "off");
+    }
+
+    #[test]
+    fn committed_commands_parse() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/../../packages/shared/nats-schemas/commands.json");
+        CommandSet::load(Some(Path::new(path))).expect("commands.json is valid");
+    }
+
+    #[test]
+    fn invalid_sets_are_rejected() {
+        let twice = r#"{ "global": [{ "name": "verify" }, { "name": "verify", "type": 1 }] }"#;
+        assert!(CommandSet::parse(twice).is_err());
+        assert!(CommandSet::parse(r#"{ "global": [{ "description": "no name" }] }"#).is_err());
+        assert!(CommandSet::parse(r#"{ "guilds": { "main": [] } }"#).is_err());
+
+        // A message command may share a slash command's name
+        let set = CommandSet::parse(r#"{ "global": [{ "name": "stats" }], "guilds": { "123": [{ "name": "stats", "type": 3 }] } }"#)
+            .unwrap();
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn drift_ignores_fields_discord_fills_in() {
+        let defined = vec![
+            json!({ "name": "verify", "description": "Link a wallet", "options": [], "nsfw": false }),
+            json!({ "name": "stats", "description": "Show stats", "default_member_permissions": "32" }),
+            json!({ "name": "profile", "description": "Show a profile" }),
+        ];
+        let registered = vec![
+            json!({ "id": "1", "application_id": "2", "version": "3", "type": 1, "name": "verify",
+                    "description": "Link a wallet", "default_member_permissions": null }),
+            json!({ "id": "4", "type": 1, "name": "stats", "description": "Show stats",
+                    "default_member_permissions": "8" }),
+            json!({ "id": "5", "type": 1, "name": "legacy", "description": "Old" }),
+        ];
+
+        let drift = diff(&defined, &registered);
+        assert_eq!(
+            drift,
+            Drift {
+                missing: vec!["profile".to_string()],
+                changed: vec!["stats".to_string()],
+                unmanaged: vec!["legacy".to_string()],
+                unchanged: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn option_changes_are_drift() {
+        let defined = json!({ "name": "badge", "options": [{ "name": "user", "type": 6, "required": true }] });
+        let registered = json!({ "name": "badge", "options": [{ "name": "user", "type": 6 }] });
+        assert!(!matches(&defined, Some(&registered)));
+
+        let optional = json!({ "name": "badge", "options": [{ "name": "user", "type": 6, "required": false }] });
+        assert!(matches(&optional, Some(&registered)));
+    }
+}
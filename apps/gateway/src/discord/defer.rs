@@ -0,0 +1,169 @@
+//! Deferred responses to slash commands (`INTERACTION_DEFER_COMMANDS`)
+//!
+//! Discord fails a slash command that gets no response within 3 seconds, and
+//! a slow NATS path can use up that window before a worker sees the event.
+//! For commands matching a configured prefix, the gateway sends the deferred
+//! response (type 5, "thinking...") itself and publishes the interaction with
+//! `deferred: true`; the worker then edits the original response instead of
+//! creating one. A failed deferral is published as `deferred: false`, leaving
+//! the response to the worker as before. Components, modals and autocomplete
+//! are never deferred here, and nothing is while the `auto-defer` flag is off.
+
+use crate::error::GatewayError;
+use std::sync::Arc;
+use twilight_http::Client;
+use twilight_model::application::interaction::{InteractionData, InteractionType};
+use twilight_model::channel::message::MessageFlags;
+use twilight_model::gateway::event::Event;
+use twilight_model::http::interaction::{InteractionResponse, InteractionResponseData, InteractionResponseType};
+use twilight_model::id::marker::{ApplicationMarker, InteractionMarker};
+use twilight_model::id::Id;
+
+/// Commands the gateway defers, by name prefix
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeferRule {
+    /// Empty matches every command
+    pub prefix: String,
+    /// Defer as an ephemeral ("only you can see this") response
+    pub ephemeral: bool,
+}
+
+/// Parse `INTERACTION_DEFER_COMMANDS`: comma-separated command name prefixes,
+/// each optionally `:ephemeral`; `*` matches every command, e.g.
+/// `admin-,setup:ephemeral`
+pub fn parse_rules(spec: &str) -> Result<Vec<DeferRule>, GatewayError> {
+    spec.split(',')
+        .map(str::trim)
+        .filter(|rule| !rule.is_empty())
+        .map(|rule| {
+            let (prefix, ephemeral) = match rule.split_once(':') {
+                Some((prefix, "ephemeral")) => (prefix.trim(), true),
+                Some(_) => {
+                    return Err(GatewayError::Config(format!(
+                        "INTERACTION_DEFER_COMMANDS entry {rule:?} must be a command prefix, optionally :ephemeral"
+                    )))
+                }
+                None => (rule, false),
+            };
+            Ok(DeferRule { prefix: if prefix == "*" { String::new() } else { prefix.to_string() }, ephemeral })
+        })
+        .collect()
+}
+
+/// A command interaction to defer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deferral {
+    application_id: Id<ApplicationMarker>,
+    interaction_id: Id<InteractionMarker>,
+    token: String,
+    ephemeral: bool,
+}
+
+/// Sends deferred responses for configured commands
+#[derive(Debug)]
+pub struct CommandDefer {
+    client: Arc<Client>,
+    rules: Vec<DeferRule>,
+}
+
+impl CommandDefer {
+    pub fn new(client: Arc<Client>, rules: Vec<DeferRule>) -> Self {
+        Self { client, rules }
+    }
+
+    /// The deferral for an event, if it is a slash command to defer
+    pub fn select(&self, event: &Event) -> Option<Deferral> {
+        select(&self.rules, event)
+    }
+
+    /// Send the deferred response
+    pub async fn send(&self, deferral: &Deferral) -> Result<(), GatewayError> {
+        let response = InteractionResponse {
+            kind: InteractionResponseType::DeferredChannelMessageWithSource,
+            data: deferral
+                .ephemeral
+                .then(|| InteractionResponseData { flags: Some(MessageFlags::EPHEMERAL), ..Default::default() }),
+        };
+        self.client
+            .interaction(deferral.application_id)
+            .create_response(deferral.interaction_id, &deferral.token, &response)
+            .await
+            .map_err(|e| GatewayError::DiscordRequestFailed {
+                route: "POST /interactions/{interaction.id}/{interaction.token}/callback",
+                source: Box::new(e),
+            })?;
+        Ok(())
+    }
+}
+
+fn select(rules: &[DeferRule], event: &Event) -> Option<Deferral> {
+    let Event::InteractionCreate(interaction) = event else {
+        return None;
+    };
+    let (InteractionType::ApplicationCommand, Some(InteractionData::ApplicationCommand(command))) =
+        (interaction.kind, interaction.data.as_ref())
+    else {
+        return None;
+    };
+    // The longest matching prefix decides
+    let rule = rules
+        .iter()
+        .filter(|rule| command.name.starts_with(&rule.prefix))
+        .max_by_key(|rule| rule.prefix.len())?;
+
+    Some(Deferral {
+        application_id: interaction.application_id,
+        interaction_id: interaction.id,
+        token: interaction.token.clone(),
+        ephemeral: rule.ephemeral,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use twilight_model::application::interaction::Interaction;
+    use twilight_model::gateway::payload::incoming::InteractionCreate;
+
+    fn command(kind: u8, name: &str) -> Event {
+        let interaction: Interaction = serde_json::from_value(serde_json::json!({
+            "application_id": "100000000000000001",
+            "authorizing_integration_owners": { "0": "123456789012345678" },
+            "channel": { "id": "333333333333333333", "type": 0 },
+            "data": { "id": "555555555555555555", "name": name, "type": 1 },
+            "entitlements": [],
+            "guild_id": "123456789012345678",
+            "id": "444444444444444444",
+            "locale": "en-US",
+            "type": kind,
+            "token": "aW50ZXJhY3Rpb25fdG9rZW5fZXhhbXBsZQ",
+            "user": { "id": "987654321098765432", "username": "user", "discriminator": "0", "avatar": null }
+        }))
+        .unwrap();
+        Event::InteractionCreate(Box::new(InteractionCreate(interaction)))
+    }
+
+    #[test]
+    fn longest_prefix_decides() {
+        let rules = parse_rules("admin-, admin-badge:ephemeral").unwrap();
+        assert!(!select(&rules, &command(2, "admin-stats")).unwrap().ephemeral);
+        assert!(select(&rules, &command(2, "admin-badge")).unwrap().ephemeral);
+        assert_eq!(select(&rules, &command(2, "verify")), None);
+
+        let every = parse_rules("*").unwrap();
+        assert!(select(&every, &command(2, "verify")).is_some());
+    }
+
+    #[test]
+    fn only_slash_commands_are_deferred() {
+        // Autocomplete must be answered with choices, not a deferral
+        assert_eq!(select(&parse_rules("*").unwrap(), &command(4, "verify")), None);
+    }
+
+    #[test]
+    fn rules_are_checked() {
+        assert_eq!(parse_rules("").unwrap(), vec![]);
+        assert_eq!(parse_rules("*").unwrap(), vec![DeferRule { prefix: String::new(), ephemeral: false }]);
+        assert!(parse_rules("setup:private").is_err());
+    }
+}
@@ -0,0 +1,38 @@
+//! Discord REST integration
+//!
+//! Thin helpers over twilight-http for the REST calls the gateway makes
+//! itself (session start limits, shard recommendations, audit log lookups,
+//! deferred command responses, command registration), the REST proxy workers
+//! call through, and API version pinning.
+
+pub mod audit;
+pub mod commands;
+pub mod defer;
+pub mod proxy;
+mod version;
+
+pub use version::{ApiVersion, ApiVersionMode};
+
+use crate::error::GatewayError;
+use twilight_http::Client;
+use twilight_model::gateway::connection_info::BotConnectionInfo;
+
+/// Fetch `GET /gateway/bot`: recommended shards and session start limits
+pub async fn fetch_gateway_bot(client: &Client) -> Result<BotConnectionInfo, GatewayError> {
+    let response = client
+        .gateway()
+        .authed()
+        .await
+        .map_err(|e| GatewayError::DiscordRequestFailed {
+            route: "GET /gateway/bot",
+            source: Box::new(e),
+        })?;
+
+    response
+        .model()
+        .await
+        .map_err(|e| GatewayError::DiscordRequestFailed {
+            route: "GET /gateway/bot",
+            source: Box::new(e),
+        })
+}
@@ -0,0 +1,251 @@
+//! Discord REST proxy for workers (`REST_PROXY_ENABLED`)
+//!
+//! Workers that each hold the bot token trip Discord's shared rate limits
+//! independently and find out from 429s. With the proxy enabled, a worker
+//! sends the call as a NATS request on `rest.requests.{anything}` and the
+//! gateway executes it through twilight-http, whose rate limiter queues it
+//! behind the route's bucket and the global limit. The reply carries
+//! Discord's status and JSON body; calls that never got a response are
+//! answered with a gateway status (400 malformed, 502 failed, 504 timed out).
+//!
+//! Requests are load-balanced over pools in one queue group, and each pool
+//! tracks buckets on its own: enable the proxy on a single pool for one view
+//! of the limits. Anyone who can publish on `rest.requests.>` acts with the
+//! bot token, so restrict it with NATS permissions.
+
+use crate::metrics::GatewayMetrics;
+use crate::nats::NatsPublisher;
+use futures_util::StreamExt as _;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::Semaphore;
+use tracing::{debug, info, warn};
+use twilight_http::error::ErrorType;
+use twilight_http::request::{Method, Request, RequestBuilder};
+use twilight_http::Client;
+
+/// Request subjects: rest.requests.{caller}
+pub const SUBJECT: &str = "rest.requests.>";
+
+/// Queue group, so each request is executed by one pool
+const QUEUE: &str = "arrakis-gateway";
+
+/// Calls executing at once; further requests wait in the subscription
+const MAX_IN_FLIGHT: usize = 64;
+
+/// Discord's audit log reason header
+const REASON_HEADER: &str = "x-audit-log-reason";
+
+/// A Discord call requested by a worker (`fixtures/rest-request.json`)
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ProxyRequest {
+    /// GET, POST, PATCH, PUT or DELETE
+    pub method: String,
+    /// API path with optional query, e.g. `channels/123/messages` (a leading
+    /// slash is ignored)
+    pub path: String,
+    /// JSON body
+    #[serde(default)]
+    pub body: Option<serde_json::Value>,
+    /// Audit log reason
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// Reply to a proxied call (`fixtures/rest-response.json`)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProxyResponse {
+    /// Discord's HTTP status, or the gateway's when Discord never answered
+    pub status: u16,
+    /// Discord's JSON body (None for empty bodies)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body: Option<serde_json::Value>,
+    /// Why the call failed before reaching Discord
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ProxyResponse {
+    fn failed(status: u16, error: impl ToString) -> Self {
+        Self { status, body: None, error: Some(error.to_string()) }
+    }
+
+    /// Metric label for the outcome
+    fn outcome(&self) -> &'static str {
+        match self.status {
+            _ if self.error.is_some() => "error",
+            429 => "429",
+            200..=299 => "2xx",
+            400..=499 => "4xx",
+            _ => "5xx",
+        }
+    }
+}
+
+/// Build the twilight request for a proxied call
+pub fn build(request: &ProxyRequest) -> Result<Request, String> {
+    let method = match request.method.to_ascii_uppercase().as_str() {
+        "GET" => Method::Get,
+        "POST" => Method::Post,
+        "PATCH" => Method::Patch,
+        "PUT" => Method::Put,
+        "DELETE" => Method::Delete,
+        other => return Err(format!("unsupported method {other:?}")),
+    };
+    let path = request.path.trim_start_matches('/');
+    if path.is_empty() || path.contains("://") || path.split(['/', '?']).any(|segment| segment == "..") {
+        return Err(format!("invalid path {:?}", request.path));
+    }
+
+    let mut builder = RequestBuilder::raw(method, path.to_string());
+    if let Some(body) = &request.body {
+        builder = builder.json(body);
+    }
+    if let Some(reason) = request.reason.as_deref().filter(|reason| !reason.is_empty()) {
+        let value = http::HeaderValue::from_str(&encode_reason(reason)).map_err(|e| e.to_string())?;
+        builder = builder.headers(std::iter::once((http::HeaderName::from_static(REASON_HEADER), value)));
+    }
+    builder.build().map_err(|e| e.to_string())
+}
+
+/// Percent-encode an audit log reason (Discord expects it URL-encoded)
+fn encode_reason(reason: &str) -> String {
+    reason
+        .bytes()
+        .map(|byte| match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' => char::from(byte).to_string(),
+            _ => format!("%{byte:02X}"),
+        })
+        .collect()
+}
+
+/// Execute a proxied call and shape Discord's answer into a reply
+async fn execute(client: &Client, request: Request) -> ProxyResponse {
+    match client.request::<serde_json::Value>(request).await {
+        Ok(response) => {
+            let status = response.status().get();
+            match response.bytes().await {
+                Ok(bytes) => ProxyResponse { status, body: parse_body(&bytes), error: None },
+                Err(e) => ProxyResponse::failed(502, e),
+            }
+        }
+        Err(e) => match e.kind() {
+            ErrorType::Response { body, status, .. } => {
+                ProxyResponse { status: status.get(), body: parse_body(body), error: None }
+            }
+            ErrorType::RequestTimedOut => ProxyResponse::failed(504, e),
+            _ => ProxyResponse::failed(502, e),
+        },
+    }
+}
+
+fn parse_body(bytes: &[u8]) -> Option<serde_json::Value> {
+    if bytes.is_empty() {
+        return None;
+    }
+    Some(serde_json::from_slice(bytes).unwrap_or_else(|_| String::from_utf8_lossy(bytes).into()))
+}
+
+/// Answer proxied calls until the process exits
+pub async fn run(nats: Arc<NatsPublisher>, client: Arc<Client>, metrics: Arc<GatewayMetrics>) {
+    let mut requests = match nats.client().queue_subscribe(SUBJECT, QUEUE.to_string()).await {
+        Ok(requests) => requests,
+        Err(e) => {
+            warn!(subject = SUBJECT, error = %e, "Failed to subscribe to REST proxy requests");
+            return;
+        }
+    };
+    info!(subject = SUBJECT, "Discord REST proxy started");
+
+    let in_flight = Arc::new(Semaphore::new(MAX_IN_FLIGHT));
+    while let Some(message) = requests.next().await {
+        let Some(reply) = message.reply else {
+            debug!(subject = %message.subject, "Ignoring REST proxy request without a reply subject");
+            continue;
+        };
+        let Ok(permit) = Arc::clone(&in_flight).acquire_owned().await else {
+            return;
+        };
+        let (nats, client, metrics) = (Arc::clone(&nats), Arc::clone(&client), Arc::clone(&metrics));
+
+        tokio::spawn(async move {
+            let started = Instant::now();
+            let parsed = serde_json::from_slice::<ProxyRequest>(&message.payload).map_err(|e| e.to_string());
+            let (method, response) = match parsed.and_then(|request| build(&request)) {
+                Ok(request) => (request.method().name(), execute(&client, request).await),
+                Err(e) => ("invalid", ProxyResponse::failed(400, e)),
+            };
+            drop(permit);
+
+            metrics.record_rest_proxy(method, response.outcome(), started.elapsed());
+            if let Some(error) = &response.error {
+                debug!(subject = %message.subject, status = response.status, error, "REST proxy call failed");
+            }
+            let payload = serde_json::to_vec(&response).expect("proxy response serializes");
+            if let Err(e) = nats.client().publish(reply, payload.into()).await {
+                debug!(error = %e, "REST proxy reply failed");
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(method: &str, path: &str) -> ProxyRequest {
+        ProxyRequest { method: method.to_string(), path: path.to_string(), body: None, reason: None }
+    }
+
+    #[test]
+    fn requests_are_checked() {
+        let built = build(&request("post", "/channels/123/messages")).unwrap();
+        assert_eq!(built.method(), Method::Post);
+        assert_eq!(built.path(), "channels/123/messages");
+
+        assert!(build(&request("CONNECT", "channels/123")).is_err());
+        for path in ["", "/", "channels/../users/@me", "https://example.com/x"] {
+            assert!(build(&request("GET", path)).is_err(), "{path}");
+        }
+    }
+
+    #[test]
+    fn reason_and_body_are_attached() {
+        let mut ban = request("PUT", "guilds/1/bans/2");
+        ban.body = Some(serde_json::json!({ "delete_message_seconds": 0 }));
+        ban.reason = Some("spam bot ✓".to_string());
+        let built = build(&ban).unwrap();
+
+        assert_eq!(built.body(), Some(br#"{"delete_message_seconds":0}"#.as_slice()));
+        let headers = built.headers().unwrap();
+        assert_eq!(headers.get(REASON_HEADER).unwrap(), "spam%20bot%20%E2%9C%93");
+    }
+
+    #[test]
+    fn replies_keep_discord_bodies() {
+        assert_eq!(parse_body(b""), None);
+        assert_eq!(parse_body(br#"{"code":10003}"#), Some(serde_json::json!({ "code": 10003 })));
+        assert_eq!(parse_body(b"upstream connect error"), Some(serde_json::json!("upstream connect error")));
+
+        let reply = serde_json::to_value(ProxyResponse::failed(400, "invalid path")).unwrap();
+        assert_eq!(reply, serde_json::json!({ "status": 400, "error": "invalid path" }));
+        assert_eq!(ProxyResponse { status: 429, body: None, error: None }.outcome(), "429");
+    }
+
+    #[test]
+    fn fixtures_match() {
+        let fixture = |name: &str| -> serde_json::Value {
+            let path = format!("{}/../../packages/shared/nats-schemas/fixtures/{name}.json", env!("CARGO_MANIFEST_DIR"));
+            serde_json::from_str(&std::fs::read_to_string(path).expect("Failed to read fixture")).unwrap()
+        };
+
+        let request: ProxyRequest = serde_json::from_value(fixture("rest-request")).unwrap();
+        assert_eq!(request.reason.as_deref(), Some("Verified holder"));
+        assert!(build(&request).is_ok());
+
+        let limited = fixture("rest-response");
+        let reply = ProxyResponse { status: 429, body: Some(limited["body"].clone()), error: None };
+        assert_eq!(serde_json::to_value(&reply).unwrap(), limited);
+    }
+}
@@ -0,0 +1,113 @@
+//! Discord API version pinning
+//!
+//! Twilight hardcodes the gateway and REST API versions, so the version a pool
+//! speaks is a property of the binary. The fleet pins a version with
+//! `DISCORD_API_VERSION`; a pool started with `DISCORD_API_MODE=canary` runs a
+//! binary built against the next version while the rest stay pinned. Migrations
+//! then roll out pool by pool instead of with a fleet-wide Twilight upgrade.
+
+use crate::error::GatewayError;
+use serde::Serialize;
+use std::fmt;
+
+/// Gateway API version compiled into this binary
+pub const GATEWAY_API_VERSION: u8 = twilight_gateway::API_VERSION;
+
+/// REST API version compiled into this binary
+pub const HTTP_API_VERSION: u8 = twilight_http::API_VERSION;
+
+/// How this pool relates to the fleet's pinned API version
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApiVersionMode {
+    /// Must run exactly the pinned version
+    Pinned,
+    /// Must run the version after the pinned one
+    Canary,
+}
+
+impl ApiVersionMode {
+    /// Parse a `DISCORD_API_MODE` value
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "pinned" => Some(Self::Pinned),
+            "canary" => Some(Self::Canary),
+            _ => None,
+        }
+    }
+
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Pinned => "pinned",
+            Self::Canary => "canary",
+        }
+    }
+}
+
+impl fmt::Display for ApiVersionMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// API versions for this pool, as reported by `/buildinfo`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ApiVersion {
+    /// Fleet-wide pinned version (`DISCORD_API_VERSION`)
+    pub pinned: u8,
+    pub mode: ApiVersionMode,
+    /// Gateway version this binary connects with
+    pub gateway: u8,
+    /// REST version this binary calls
+    pub http: u8,
+}
+
+impl ApiVersion {
+    /// Check the compiled versions against the pin for this pool's mode
+    pub fn resolve(pinned: u8, mode: ApiVersionMode) -> Result<Self, GatewayError> {
+        Self::check(pinned, mode, GATEWAY_API_VERSION, HTTP_API_VERSION)
+    }
+
+    fn check(pinned: u8, mode: ApiVersionMode, gateway: u8, http: u8) -> Result<Self, GatewayError> {
+        if gateway != http {
+            return Err(GatewayError::Config(format!(
+                "twilight-gateway (v{gateway}) and twilight-http (v{http}) target different API versions"
+            )));
+        }
+
+        let expected = match mode {
+            ApiVersionMode::Pinned => pinned,
+            ApiVersionMode::Canary => pinned.saturating_add(1),
+        };
+        if gateway != expected {
+            return Err(GatewayError::Config(format!(
+                "DISCORD_API_MODE={mode} expects API v{expected} (DISCORD_API_VERSION={pinned}) but this binary speaks v{gateway}"
+            )));
+        }
+
+        Ok(Self { pinned, mode, gateway, http })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pinned_requires_exact_version() {
+        assert!(ApiVersion::check(10, ApiVersionMode::Pinned, 10, 10).is_ok());
+        assert!(ApiVersion::check(9, ApiVersionMode::Pinned, 10, 10).is_err());
+    }
+
+    #[test]
+    fn test_canary_requires_next_version() {
+        let version = ApiVersion::check(9, ApiVersionMode::Canary, 10, 10).unwrap();
+        assert_eq!(version.mode, ApiVersionMode::Canary);
+        assert!(ApiVersion::check(10, ApiVersionMode::Canary, 10, 10).is_err());
+    }
+
+    #[test]
+    fn test_mismatched_twilight_crates_rejected() {
+        assert!(ApiVersion::check(10, ApiVersionMode::Pinned, 10, 9).is_err());
+    }
+}
@@ -67,6 +67,87 @@ pub enum GatewayError {
     /// Shard ID overflow: u64 value exceeds u32::MAX (Twilight API boundary)
     #[error("shard ID overflow: {value} exceeds u32::MAX")]
     ShardIdOverflow { value: u64 },
+
+    /// NATS KV bucket operation failed
+    #[error("NATS KV operation failed on bucket '{bucket}'")]
+    NatsKvFailed {
+        bucket: String,
+        #[source]
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    /// JetStream consumer info lookup failed
+    #[error("NATS consumer info failed for {stream}/{consumer}")]
+    NatsConsumerInfoFailed {
+        stream: &'static str,
+        consumer: &'static str,
+        #[source]
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    /// NATS micro service registration failed
+    #[error("NATS service registration failed")]
+    NatsServiceFailed(#[source] Box<dyn std::error::Error + Send + Sync>),
+
+    /// Admin gRPC server failed to start or stopped
+    #[error("Admin gRPC server failed")]
+    AdminGrpcFailed(#[source] Box<dyn std::error::Error + Send + Sync>),
+
+    /// Discord REST request made by the gateway itself failed
+    #[error("Discord request {route} failed")]
+    DiscordRequestFailed {
+        route: &'static str,
+        #[source]
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    /// PagerDuty / Opsgenie request failed
+    #[error("{provider} alert request failed")]
+    PagerRequestFailed {
+        provider: &'static str,
+        #[source]
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    /// Feature flag provider could not be read
+    #[error("feature flag provider '{provider}' failed")]
+    FlagProviderFailed {
+        provider: &'static str,
+        #[source]
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    /// Identify burst would exhaust the bot's session start limit
+    #[error("identify budget exhausted: {required} identifies needed, {remaining} remaining (reserve {reserve})")]
+    IdentifyBudgetExhausted {
+        required: u32,
+        remaining: u32,
+        reserve: u32,
+    },
+
+    /// Serializer output no longer matches a committed wire fixture
+    #[error("wire contract drift in fixture {fixture}: {detail}")]
+    ContractDrift { fixture: &'static str, detail: String },
+
+    /// Event failed the wire contract's JSON Schema (`SCHEMA_VALIDATION=enforce`)
+    #[error("{event_type} event violates the wire schema: {detail}")]
+    SchemaViolation { event_type: String, detail: String },
+
+    /// Redis cache connection failed
+    #[error("Redis cache connection failed")]
+    RedisFailed(#[source] Box<dyn std::error::Error + Send + Sync>),
+
+    /// A secret could not be read from its file, Vault or AWS Secrets Manager
+    #[error("secret {name} could not be read from {provider}: {detail}")]
+    SecretReadFailed {
+        name: String,
+        provider: &'static str,
+        detail: String,
+    },
+
+    /// Event could not be buffered in the outbox during a NATS outage
+    #[error("outbox rejected event {event_id}: {reason}")]
+    OutboxRejected { event_id: String, reason: String },
 }
 
 impl GatewayError {
@@ -83,6 +164,19 @@ impl GatewayError {
             Self::SerializationFailed { .. } => "serialization",
             Self::Config(_) => "config",
             Self::ShardIdOverflow { .. } => "shard_overflow",
+            Self::NatsKvFailed { .. } => "nats_kv",
+            Self::NatsConsumerInfoFailed { .. } => "nats_consumer_info",
+            Self::NatsServiceFailed(_) => "nats_service",
+            Self::AdminGrpcFailed(_) => "admin_grpc",
+            Self::DiscordRequestFailed { .. } => "discord_request",
+            Self::PagerRequestFailed { .. } => "pager_request",
+            Self::FlagProviderFailed { .. } => "flag_provider",
+            Self::IdentifyBudgetExhausted { .. } => "identify_budget",
+            Self::ContractDrift { .. } => "contract_drift",
+            Self::SchemaViolation { .. } => "schema_violation",
+            Self::OutboxRejected { .. } => "outbox_rejected",
+            Self::RedisFailed(_) => "redis",
+            Self::SecretReadFailed { .. } => "secret_read",
         }
     }
 }
@@ -92,7 +186,7 @@ mod tests {
     use super::*;
 
     fn test_error() -> Box<dyn std::error::Error + Send + Sync> {
-        Box::new(std::io::Error::new(std::io::ErrorKind::Other, "test"))
+        Box::new(std::io::Error::other("test"))
     }
 
     #[test]
@@ -119,6 +213,62 @@ mod tests {
             .error_type_label(),
             GatewayError::Config("test".to_string()).error_type_label(),
             GatewayError::ShardIdOverflow { value: u64::MAX }.error_type_label(),
+            GatewayError::NatsKvFailed {
+                bucket: "test".to_string(),
+                source: test_error(),
+            }
+            .error_type_label(),
+            GatewayError::NatsConsumerInfoFailed {
+                stream: "EVENTS",
+                consumer: "event-worker",
+                source: test_error(),
+            }
+            .error_type_label(),
+            GatewayError::NatsServiceFailed(test_error()).error_type_label(),
+            GatewayError::AdminGrpcFailed(test_error()).error_type_label(),
+            GatewayError::DiscordRequestFailed {
+                route: "GET /gateway/bot",
+                source: test_error(),
+            }
+            .error_type_label(),
+            GatewayError::PagerRequestFailed {
+                provider: "pagerduty",
+                source: test_error(),
+            }
+            .error_type_label(),
+            GatewayError::FlagProviderFailed {
+                provider: "file",
+                source: test_error(),
+            }
+            .error_type_label(),
+            GatewayError::IdentifyBudgetExhausted {
+                required: 25,
+                remaining: 10,
+                reserve: 5,
+            }
+            .error_type_label(),
+            GatewayError::ContractDrift {
+                fixture: "member-join",
+                detail: "test".to_string(),
+            }
+            .error_type_label(),
+            GatewayError::SchemaViolation {
+                event_type: "member.join".to_string(),
+                detail: "test".to_string(),
+            }
+            .error_type_label(),
+            GatewayError::OutboxRejected {
+                event_id: "test".to_string(),
+                reason: "outbox full".to_string(),
+            }
+            .error_type_label(),
+            GatewayError::RedisFailed(test_error()).error_type_label(),
+            GatewayError::SecretReadFailed {
+                name: "DISCORD_TOKEN".to_string(),
+                provider: "vault",
+                detail: "test".to_string(),
+            }
+            .error_type_label(),
         ];
 
         // All labels are unique
@@ -0,0 +1,272 @@
+//! Windowed aggregation of high-volume events
+//!
+//! During a raid or a message storm, workers often only need "how many, and
+//! a few examples". Configured event types are counted per guild in tumbling
+//! windows; when a window closes, an `event.summary` carrying the count, a
+//! sample of IDs and the window bounds is published. The raw events are
+//! published alongside it or dropped, per event type.
+//!
+//! Events dropped by other filters (`GUILD_RATE_LIMIT`) can be tallied the
+//! same way; their summaries name why in `reason`.
+
+use super::serialize::{now_millis, GatewayEvent};
+use crate::error::GatewayError;
+use crate::nats::NatsPublisher;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{debug, info};
+use uuid::Uuid;
+
+/// Event type of the summaries
+pub const SUMMARY_EVENT_TYPE: &str = "event.summary";
+
+/// IDs kept per window
+pub const SAMPLE_SIZE: usize = 10;
+
+/// How often closed windows are flushed
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// What happens to the raw events of an aggregated type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AggregateMode {
+    /// Publish raw events and summaries
+    Alongside,
+    /// Publish summaries only
+    Instead,
+}
+
+/// Aggregation for one event type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AggregateRule {
+    pub event_type: String,
+    pub window: Duration,
+    pub mode: AggregateMode,
+}
+
+/// Parse `AGGREGATE_EVENTS`: comma-separated `event_type=window_secs[:instead]`,
+/// e.g. `member.join=10,message.create=5:instead`
+pub fn parse_rules(spec: &str) -> Result<Vec<AggregateRule>, GatewayError> {
+    spec.split(',')
+        .map(str::trim)
+        .filter(|rule| !rule.is_empty())
+        .map(|rule| {
+            let invalid = || {
+                GatewayError::Config(format!(
+                    "AGGREGATE_EVENTS entry {rule:?} must be event_type=window_secs[:instead]"
+                ))
+            };
+            let (event_type, rest) = rule.split_once('=').ok_or_else(invalid)?;
+            let (secs, mode) = match rest.split_once(':') {
+                Some((secs, "instead")) => (secs, AggregateMode::Instead),
+                Some((secs, "alongside")) => (secs, AggregateMode::Alongside),
+                Some(_) => return Err(invalid()),
+                None => (rest, AggregateMode::Alongside),
+            };
+            let secs: u64 = secs.trim().parse().map_err(|_| invalid())?;
+            if event_type.is_empty() || secs == 0 {
+                return Err(invalid());
+            }
+
+            Ok(AggregateRule {
+                event_type: event_type.trim().to_string(),
+                window: Duration::from_secs(secs),
+                mode,
+            })
+        })
+        .collect()
+}
+
+/// One open window for an event type in a guild
+#[derive(Debug)]
+struct Window {
+    shard_id: u64,
+    bot_id: Option<String>,
+    start_ms: u64,
+    end_ms: u64,
+    count: u64,
+    sample: Vec<String>,
+    /// Why the events were dropped, for tallies of dropped events
+    reason: Option<&'static str>,
+}
+
+/// A window's bot, event type and guild
+type WindowKey = (Option<String>, String, Option<String>);
+
+/// Counts configured event types; shared by a pool's shards
+#[derive(Debug, Default)]
+pub struct Aggregator {
+    rules: HashMap<String, AggregateRule>,
+    windows: Mutex<HashMap<WindowKey, Window>>,
+}
+
+impl Aggregator {
+    pub fn new(rules: Vec<AggregateRule>) -> Self {
+        Self {
+            rules: rules.into_iter().map(|rule| (rule.event_type.clone(), rule)).collect(),
+            windows: Mutex::default(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Count an event. Returns true when the raw event should not be published.
+    pub fn observe(&self, event: &GatewayEvent) -> bool {
+        let Some(rule) = self.rules.get(&event.event_type) else {
+            return false;
+        };
+        self.count(event, rule.window, None);
+        rule.mode == AggregateMode::Instead
+    }
+
+    /// Count an event dropped for `reason` in a window of its own
+    pub fn tally(&self, event: &GatewayEvent, window: Duration, reason: &'static str) {
+        self.count(event, window, Some(reason));
+    }
+
+    fn count(&self, event: &GatewayEvent, length: Duration, reason: Option<&'static str>) {
+        let key = (event.bot_id.clone(), event.event_type.clone(), event.guild_id.clone());
+        let mut windows = self.windows.lock().unwrap_or_else(|e| e.into_inner());
+        let window = windows.entry(key).or_insert_with(|| Window {
+            shard_id: event.shard_id,
+            bot_id: event.bot_id.clone(),
+            start_ms: event.timestamp,
+            end_ms: event.timestamp + length.as_millis() as u64,
+            count: 0,
+            sample: Vec::with_capacity(SAMPLE_SIZE),
+            reason,
+        });
+        window.count += 1;
+        if window.sample.len() < SAMPLE_SIZE {
+            window.sample.push(event.user_id.clone().unwrap_or_else(|| event.event_id.clone()));
+        }
+    }
+
+    /// Close the windows that ended by `now_ms` and build their summaries
+    pub fn drain_ended(&self, now_ms: u64) -> Vec<GatewayEvent> {
+        let mut windows = self.windows.lock().unwrap_or_else(|e| e.into_inner());
+        let ended: Vec<_> = windows
+            .iter()
+            .filter(|(_, window)| window.end_ms <= now_ms)
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        ended
+            .into_iter()
+            .filter_map(|key| windows.remove_entry(&key))
+            .map(|((_, event_type, guild_id), window)| summary_event(&event_type, guild_id, &window, now_ms))
+            .collect()
+    }
+}
+
+fn summary_event(event_type: &str, guild_id: Option<String>, window: &Window, now_ms: u64) -> GatewayEvent {
+    GatewayEvent {
+        event_id: Uuid::new_v4().to_string(),
+        event_type: SUMMARY_EVENT_TYPE.to_string(),
+        schema_version: crate::events::versions::CURRENT,
+        shard_id: window.shard_id,
+        bot_id: window.bot_id.clone(),
+        seq: None,
+        seq_epoch: None,
+        timestamp: now_ms,
+        guild_id,
+        channel_id: None,
+        user_id: None,
+        data: {
+            let mut data = serde_json::json!({
+                "event_type": event_type,
+                "count": window.count,
+                "sample_ids": window.sample,
+                "window_start": window.start_ms,
+                "window_end": window.end_ms,
+            });
+            if let Some(reason) = window.reason {
+                data["reason"] = reason.into();
+            }
+            data
+        },
+    }
+}
+
+/// Publish summaries as windows close, until the process exits
+pub async fn run_flusher(aggregator: Arc<Aggregator>, nats: Arc<NatsPublisher>) {
+    if !aggregator.is_empty() {
+        info!(event_types = ?aggregator.rules.keys().collect::<Vec<_>>(), "Event aggregation enabled");
+    }
+
+    let mut interval = tokio::time::interval(FLUSH_INTERVAL);
+    loop {
+        interval.tick().await;
+        for summary in aggregator.drain_ended(now_millis()) {
+            if let Err(e) = nats.publish_event(&summary).await {
+                debug!(error = %e, "Failed to publish event summary");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn join(user: u64, timestamp: u64) -> GatewayEvent {
+        GatewayEvent {
+            event_id: Uuid::new_v4().to_string(),
+            shard_id: 2,
+            timestamp,
+            guild_id: Some("123456789012345678".to_string()),
+            user_id: Some(user.to_string()),
+            ..GatewayEvent::for_test("member.join")
+        }
+    }
+
+    #[test]
+    fn test_parse_rules() {
+        let rules = parse_rules("member.join=10, message.create=5:instead").unwrap();
+        assert_eq!(rules[0].window, Duration::from_secs(10));
+        assert_eq!(rules[0].mode, AggregateMode::Alongside);
+        assert_eq!(rules[1].event_type, "message.create");
+        assert_eq!(rules[1].mode, AggregateMode::Instead);
+
+        assert!(parse_rules("").unwrap().is_empty());
+        assert!(parse_rules("member.join").is_err());
+        assert!(parse_rules("member.join=0").is_err());
+        assert!(parse_rules("member.join=5:sometimes").is_err());
+    }
+
+    #[test]
+    fn test_windows_summarize_and_sample() {
+        let aggregator = Aggregator::new(parse_rules("member.join=10:instead").unwrap());
+
+        for user in 0..25 {
+            assert!(aggregator.observe(&join(user, 1_000 + user)));
+        }
+        assert!(aggregator.drain_ended(10_999).is_empty());
+
+        let summaries = aggregator.drain_ended(11_000);
+        assert_eq!(summaries.len(), 1);
+        let summary = &summaries[0];
+        assert_eq!(summary.event_type, SUMMARY_EVENT_TYPE);
+        assert_eq!(summary.shard_id, 2);
+        assert_eq!(summary.data["event_type"], "member.join");
+        assert_eq!(summary.data["count"], 25);
+        assert_eq!(summary.data["sample_ids"].as_array().unwrap().len(), SAMPLE_SIZE);
+        assert_eq!(summary.data["window_start"], 1_000);
+        assert_eq!(summary.data["window_end"], 11_000);
+        let value = serde_json::to_value(summary).unwrap();
+        assert!(super::super::policy::violations(&value).is_empty());
+
+        // The next event opens a new window
+        assert!(aggregator.observe(&join(99, 12_000)));
+        assert!(aggregator.drain_ended(12_000).is_empty());
+    }
+
+    #[test]
+    fn test_unconfigured_types_pass_through() {
+        let aggregator = Aggregator::new(parse_rules("member.leave=10").unwrap());
+        assert!(!aggregator.observe(&join(1, 0)));
+        assert!(aggregator.drain_ended(u64::MAX).is_empty());
+    }
+}
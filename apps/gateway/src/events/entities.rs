@@ -0,0 +1,234 @@
+//! Normalized Discord entities for event `data`
+//!
+//! Workers get the fields they act on in a stable shape instead of Twilight's
+//! (or Discord's) full models. Snowflakes and permission bitsets are strings,
+//! as on Discord's wire, and timestamps are Unix milliseconds (see `policy`).
+
+use super::policy;
+use serde_json::{Map, Value};
+use twilight_model::application::interaction::application_command::{CommandDataOption, CommandOptionValue};
+use twilight_model::application::interaction::modal::ModalInteractionComponent;
+use twilight_model::application::interaction::{InteractionChannel, InteractionDataResolved, InteractionMember};
+use twilight_model::channel::message::EmojiReactionType;
+use twilight_model::channel::{Attachment, Channel};
+use twilight_model::guild::{Member, Role};
+use twilight_model::user::User;
+
+pub fn user(user: &User) -> Value {
+    serde_json::json!({
+        "id": user.id.to_string(),
+        "username": user.name,
+        "global_name": user.global_name,
+        "avatar": user.avatar.map(|hash| hash.to_string()),
+        "bot": user.bot,
+    })
+}
+
+pub fn member(member: &InteractionMember) -> Value {
+    serde_json::json!({
+        "nick": member.nick,
+        "roles": member.roles.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        "joined_at": member.joined_at.map(policy::timestamp),
+        "avatar": member.avatar.map(|hash| hash.to_string()),
+        "permissions": member.permissions.bits().to_string(),
+    })
+}
+
+/// A guild member with its user (member lists, where there's no envelope
+/// `user_id`)
+pub fn guild_member(member: &Member) -> Value {
+    serde_json::json!({
+        "user": user(&member.user),
+        "nick": member.nick,
+        "roles": member.roles.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        "joined_at": member.joined_at.map(policy::timestamp),
+        "premium_since": member.premium_since.map(policy::timestamp),
+        "avatar": member.avatar.map(|hash| hash.to_string()),
+        "pending": member.pending,
+    })
+}
+
+pub fn role(role: &Role) -> Value {
+    serde_json::json!({
+        "id": role.id.to_string(),
+        "name": role.name,
+        "color": role.colors.primary_color,
+        "position": role.position,
+        "permissions": role.permissions.bits().to_string(),
+        "managed": role.managed,
+        "mentionable": role.mentionable,
+    })
+}
+
+pub fn channel(channel: &InteractionChannel) -> Value {
+    serde_json::json!({
+        "id": channel.id.to_string(),
+        "name": channel.name,
+        "type": channel.kind.name(),
+        "parent_id": channel.parent_id.map(|id| id.to_string()),
+        "permissions": channel.permissions.bits().to_string(),
+    })
+}
+
+/// A role with its display settings and tags, for role events. `tags` says
+/// what manages a managed role (a bot, an integration, boosting or a
+/// subscription).
+pub fn guild_role(role: &Role) -> Value {
+    let mut normalized = self::role(role);
+    normalized["hoist"] = role.hoist.into();
+    normalized["icon"] = role.icon.map(|hash| hash.to_string()).into();
+    normalized["unicode_emoji"] = role.unicode_emoji.clone().into();
+    normalized["tags"] = role.tags.as_ref().map_or(Value::Null, |tags| {
+        serde_json::json!({
+            "bot_id": tags.bot_id.map(|id| id.to_string()),
+            "integration_id": tags.integration_id.map(|id| id.to_string()),
+            "premium_subscriber": tags.premium_subscriber,
+            "subscription_listing_id": tags.subscription_listing_id.map(|id| id.to_string()),
+            "available_for_purchase": tags.available_for_purchase,
+            "guild_connections": tags.guild_connections,
+        })
+    });
+    normalized
+}
+
+/// A guild channel from channel events (`type` as in `channel`)
+pub fn guild_channel(channel: &Channel) -> Value {
+    serde_json::json!({
+        "id": channel.id.to_string(),
+        "name": channel.name,
+        "type": channel.kind.name(),
+        "parent_id": channel.parent_id.map(|id| id.to_string()),
+        "position": channel.position,
+        "topic": channel.topic,
+        "nsfw": channel.nsfw,
+    })
+}
+
+/// A thread with its metadata; `parent_id` is the channel it lives in
+pub fn thread(thread: &Channel) -> Value {
+    let metadata = thread.thread_metadata.as_ref();
+    serde_json::json!({
+        "id": thread.id.to_string(),
+        "name": thread.name,
+        "type": thread.kind.name(),
+        "parent_id": thread.parent_id.map(|id| id.to_string()),
+        "owner_id": thread.owner_id.map(|id| id.to_string()),
+        "archived": metadata.map(|metadata| metadata.archived),
+        "locked": metadata.map(|metadata| metadata.locked),
+        "auto_archive_minutes": metadata.map(|metadata| metadata.auto_archive_duration.number()),
+        "created_at": metadata.and_then(|metadata| metadata.create_timestamp).map(policy::timestamp),
+        "member_count": thread.member_count,
+        "message_count": thread.message_count,
+    })
+}
+
+pub fn attachment(attachment: &Attachment) -> Value {
+    serde_json::json!({
+        "id": attachment.id.to_string(),
+        "filename": attachment.filename,
+        "content_type": attachment.content_type,
+        "size": attachment.size,
+        "url": attachment.url,
+    })
+}
+
+/// A reaction's emoji: `id` is null for Unicode emoji, `name` is null for
+/// custom emoji that no longer exist
+pub fn emoji(emoji: &EmojiReactionType) -> Value {
+    match emoji {
+        EmojiReactionType::Custom { animated, id, name } => {
+            serde_json::json!({ "id": id.to_string(), "name": name, "animated": animated })
+        }
+        EmojiReactionType::Unicode { name } => serde_json::json!({ "id": null, "name": name, "animated": false }),
+    }
+}
+
+/// Entities a command referenced, keyed by ID (users, members, roles,
+/// channels, attachments). Members are keyed by user ID.
+pub fn resolved(resolved: &InteractionDataResolved) -> Value {
+    fn keyed<'a, K: ToString + 'a, V: 'a>(
+        entries: impl IntoIterator<Item = (&'a K, &'a V)>,
+        normalize: impl Fn(&V) -> Value,
+    ) -> Value {
+        Value::Object(
+            entries
+                .into_iter()
+                .map(|(id, entity)| (id.to_string(), normalize(entity)))
+                .collect::<Map<_, _>>(),
+        )
+    }
+
+    serde_json::json!({
+        "users": keyed(&resolved.users, user),
+        "members": keyed(&resolved.members, member),
+        "roles": keyed(&resolved.roles, role),
+        "channels": keyed(&resolved.channels, channel),
+        "attachments": keyed(&resolved.attachments, attachment),
+    })
+}
+
+/// Command options with their types. Entity values are IDs to look up in
+/// `resolved`; subcommands nest their own `options`.
+pub fn options(options: &[CommandDataOption]) -> Value {
+    Value::Array(options.iter().map(option).collect())
+}
+
+fn option(option: &CommandDataOption) -> Value {
+    let mut normalized = serde_json::json!({
+        "name": option.name,
+        "type": option.value.kind().kind(),
+    });
+
+    let (key, value) = match &option.value {
+        CommandOptionValue::SubCommand(nested) | CommandOptionValue::SubCommandGroup(nested) => {
+            ("options", options(nested))
+        }
+        CommandOptionValue::String(value) => ("value", Value::from(value.as_str())),
+        CommandOptionValue::Integer(value) => ("value", Value::from(*value)),
+        CommandOptionValue::Number(value) => ("value", Value::from(*value)),
+        CommandOptionValue::Boolean(value) => ("value", Value::from(*value)),
+        CommandOptionValue::User(id) => ("value", Value::from(id.to_string())),
+        CommandOptionValue::Channel(id) => ("value", Value::from(id.to_string())),
+        CommandOptionValue::Role(id) => ("value", Value::from(id.to_string())),
+        CommandOptionValue::Mentionable(id) => ("value", Value::from(id.to_string())),
+        CommandOptionValue::Attachment(id) => ("value", Value::from(id.to_string())),
+        // Autocomplete: the partial input, unvalidated, always a string
+        CommandOptionValue::Focused(value, _) => {
+            normalized["focused"] = Value::Bool(true);
+            ("value", Value::from(value.as_str()))
+        }
+    };
+    normalized[key] = value;
+    normalized
+}
+
+/// Modal inputs in display order, flattened out of rows and labels. Text
+/// inputs carry their `value`; selects and file uploads their chosen IDs (or
+/// options) as `values`.
+pub fn modal_fields(components: &[ModalInteractionComponent]) -> Value {
+    fn ids<T: ToString>(values: &[T]) -> Value {
+        values.iter().map(ToString::to_string).collect()
+    }
+    fn collect(component: &ModalInteractionComponent, fields: &mut Vec<Value>) {
+        let (custom_id, key, value) = match component {
+            ModalInteractionComponent::ActionRow(row) => {
+                row.components.iter().for_each(|nested| collect(nested, fields));
+                return;
+            }
+            ModalInteractionComponent::Label(label) => return collect(&label.component, fields),
+            ModalInteractionComponent::TextInput(input) => (&input.custom_id, "value", Value::from(input.value.as_str())),
+            ModalInteractionComponent::StringSelect(select) => (&select.custom_id, "values", ids(&select.values)),
+            ModalInteractionComponent::UserSelect(select) => (&select.custom_id, "values", ids(&select.values)),
+            ModalInteractionComponent::RoleSelect(select) => (&select.custom_id, "values", ids(&select.values)),
+            ModalInteractionComponent::MentionableSelect(select) => (&select.custom_id, "values", ids(&select.values)),
+            ModalInteractionComponent::ChannelSelect(select) => (&select.custom_id, "values", ids(&select.values)),
+            ModalInteractionComponent::FileUpload(upload) => (&upload.custom_id, "values", ids(&upload.values)),
+            ModalInteractionComponent::TextDisplay(_) | ModalInteractionComponent::Unknown(_) => return,
+        };
+        fields.push(serde_json::json!({ "custom_id": custom_id, key: value }));
+    }
+
+    let mut fields = Vec::new();
+    components.iter().for_each(|component| collect(component, &mut fields));
+    Value::Array(fields)
+}
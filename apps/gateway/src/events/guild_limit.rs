@@ -0,0 +1,262 @@
+//! Per-guild event rate limits (`GUILD_RATE_LIMIT`)
+//!
+//! One guild in a bot raid can add thousands of members a minute and starve
+//! every other guild of the EVENTS stream. Each guild gets a token bucket:
+//! `rate` events a second on average, in bursts of up to `burst`. Events over
+//! the limit are dropped before they are numbered, so they leave no gaps in
+//! the sequence, and are counted in `gateway_guild_rate_limited_total`.
+//!
+//! With `GUILD_RATE_LIMIT_SUMMARY_SECS`, dropped events are tallied like
+//! `AGGREGATE_EVENTS`: one `event.summary` per guild and event type per
+//! window, with `reason: "rate_limited"`, so workers still learn how many
+//! events they missed.
+//!
+//! Interactions are never limited (a dropped one is a command that never
+//! gets an answer), nor are events without a guild.
+
+use super::aggregate::Aggregator;
+use super::serialize::GatewayEvent;
+use crate::error::GatewayError;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// `reason` of summaries of rate-limited events
+pub const SUMMARY_REASON: &str = "rate_limited";
+
+/// Event types that are never limited
+const EXEMPT: &[&str] = &["interaction.create"];
+
+/// Burst when only a rate is given: this many seconds' worth of events
+const DEFAULT_BURST_SECS: f64 = 10.0;
+
+/// Past this many buckets, idle ones are dropped
+const MAX_BUCKETS: usize = 100_000;
+
+/// A bucket untouched this long is full again and can be dropped
+const IDLE: Duration = Duration::from_secs(300);
+
+/// Events a second, and how many may come at once
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GuildRate {
+    pub per_second: f64,
+    pub burst: f64,
+}
+
+impl GuildRate {
+    /// Parse `rate[/burst]`, e.g. `50` or `50/1000`
+    pub fn parse(name: &str, value: &str) -> Result<Self, GatewayError> {
+        let invalid = || GatewayError::Config(format!("{name} must be rate[/burst] in events a second, got {value:?}"));
+        let (rate, burst) = match value.trim().split_once('/') {
+            Some((rate, burst)) => (rate, Some(burst)),
+            None => (value.trim(), None),
+        };
+        let per_second: f64 = rate.trim().parse().map_err(|_| invalid())?;
+        let burst = match burst {
+            Some(burst) => burst.trim().parse().map_err(|_| invalid())?,
+            None => per_second * DEFAULT_BURST_SECS,
+        };
+        if !per_second.is_finite() || per_second <= 0.0 || !burst.is_finite() || burst < 1.0 {
+            return Err(invalid());
+        }
+        Ok(Self { per_second, burst })
+    }
+}
+
+/// Rate limit settings
+#[derive(Debug, Clone, PartialEq)]
+pub struct GuildLimitConfig {
+    /// Limit of every guild (`GUILD_RATE_LIMIT`)
+    pub default: GuildRate,
+    /// Limits of specific guilds (`GUILD_RATE_LIMIT_GUILDS`)
+    pub guilds: HashMap<String, GuildRate>,
+    /// Summarize dropped events in windows this long (None drops them silently)
+    pub summary_window: Option<Duration>,
+}
+
+impl GuildLimitConfig {
+    /// Parse `GUILD_RATE_LIMIT_GUILDS`: comma-separated `guild_id=rate[/burst]`
+    pub fn parse_guilds(spec: &str) -> Result<HashMap<String, GuildRate>, GatewayError> {
+        spec.split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                let (guild_id, rate) = entry.split_once('=').ok_or_else(|| {
+                    GatewayError::Config(format!("GUILD_RATE_LIMIT_GUILDS entry {entry:?} must be guild_id=rate[/burst]"))
+                })?;
+                let guild_id = guild_id.trim();
+                if guild_id.parse::<u64>().is_err() {
+                    return Err(GatewayError::Config(format!(
+                        "GUILD_RATE_LIMIT_GUILDS entry {entry:?} must start with a guild ID"
+                    )));
+                }
+                Ok((guild_id.to_string(), GuildRate::parse("GUILD_RATE_LIMIT_GUILDS", rate)?))
+            })
+            .collect()
+    }
+
+    fn rate(&self, guild_id: &str) -> GuildRate {
+        self.guilds.get(guild_id).copied().unwrap_or(self.default)
+    }
+}
+
+/// One guild's tokens
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+    /// Dropping events since the last one that got through
+    limited: bool,
+}
+
+/// A bucket's bot and guild
+type BucketKey = (Option<String>, String);
+
+/// Token buckets per guild; shared by a pool's shards
+#[derive(Debug)]
+pub struct GuildRateLimiter {
+    config: GuildLimitConfig,
+    buckets: Mutex<HashMap<BucketKey, Bucket>>,
+    dropped: Arc<Aggregator>,
+}
+
+impl GuildRateLimiter {
+    pub fn new(config: GuildLimitConfig) -> Self {
+        Self { config, buckets: Mutex::default(), dropped: Arc::default() }
+    }
+
+    /// Tallies of dropped events, for `aggregate::run_flusher`
+    pub fn dropped(&self) -> Arc<Aggregator> {
+        Arc::clone(&self.dropped)
+    }
+
+    pub fn summarizes(&self) -> bool {
+        self.config.summary_window.is_some()
+    }
+
+    /// Take a token for an event's guild. Returns false when it is over its limit.
+    pub fn admits(&self, event: &GatewayEvent) -> bool {
+        self.admits_at(event, Instant::now())
+    }
+
+    fn admits_at(&self, event: &GatewayEvent, now: Instant) -> bool {
+        let Some(ref guild_id) = event.guild_id else {
+            return true;
+        };
+        if EXEMPT.contains(&event.event_type.as_str()) {
+            return true;
+        }
+
+        let rate = self.config.rate(guild_id);
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        if buckets.len() >= MAX_BUCKETS {
+            buckets.retain(|_, bucket| now.saturating_duration_since(bucket.updated) < IDLE);
+        }
+        let bucket = buckets
+            .entry((event.bot_id.clone(), guild_id.clone()))
+            .or_insert(Bucket { tokens: rate.burst, updated: now, limited: false });
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate.per_second).min(rate.burst);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            if bucket.limited {
+                bucket.limited = false;
+                info!(guild_id, bot_id = ?event.bot_id, "Guild back under its event rate limit");
+            }
+            return true;
+        }
+        if !bucket.limited {
+            bucket.limited = true;
+            warn!(
+                guild_id,
+                bot_id = ?event.bot_id,
+                rate = rate.per_second,
+                burst = rate.burst,
+                "Guild over its event rate limit, dropping events"
+            );
+        }
+        drop(buckets);
+
+        if let Some(window) = self.config.summary_window {
+            self.dropped.tally(event, window, SUMMARY_REASON);
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(event_type: &str, guild_id: Option<&str>, timestamp: u64) -> GatewayEvent {
+        GatewayEvent {
+            event_id: uuid::Uuid::new_v4().to_string(),
+            timestamp,
+            guild_id: guild_id.map(str::to_string),
+            user_id: Some(timestamp.to_string()),
+            ..GatewayEvent::for_test(event_type)
+        }
+    }
+
+    fn limiter(summary_window: Option<Duration>) -> GuildRateLimiter {
+        GuildRateLimiter::new(GuildLimitConfig {
+            default: GuildRate::parse("GUILD_RATE_LIMIT", "2/5").unwrap(),
+            guilds: GuildLimitConfig::parse_guilds("222222222222222222=100").unwrap(),
+            summary_window,
+        })
+    }
+
+    #[test]
+    fn test_rates_are_parsed() {
+        assert_eq!(GuildRate::parse("X", "50").unwrap(), GuildRate { per_second: 50.0, burst: 500.0 });
+        assert_eq!(GuildRate::parse("X", "0.5/3").unwrap(), GuildRate { per_second: 0.5, burst: 3.0 });
+        assert!(GuildRate::parse("X", "0").is_err());
+        assert!(GuildRate::parse("X", "10/0").is_err());
+        assert!(GuildRate::parse("X", "fast").is_err());
+        assert!(GuildLimitConfig::parse_guilds("guild=10").is_err());
+        assert!(GuildLimitConfig::parse_guilds("123").is_err());
+        assert!(GuildLimitConfig::parse_guilds("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_buckets_drain_and_refill_per_guild() {
+        let limiter = limiter(None);
+        let start = Instant::now();
+        let raided = Some("111111111111111111");
+
+        let admitted = (0..20).filter(|n| limiter.admits_at(&event("member.join", raided, *n), start)).count();
+        assert_eq!(admitted, 5, "a full bucket allows a burst");
+
+        // Other guilds keep their own buckets, and overrides their own rates
+        assert!(limiter.admits_at(&event("member.join", Some("333333333333333333"), 0), start));
+        let vip = (0..100).filter(|n| limiter.admits_at(&event("member.join", Some("222222222222222222"), *n), start));
+        assert_eq!(vip.count(), 100);
+
+        // Two events a second come back
+        let later = start + Duration::from_secs(1);
+        let admitted = (0..5).filter(|n| limiter.admits_at(&event("member.join", raided, *n), later)).count();
+        assert_eq!(admitted, 2);
+    }
+
+    #[test]
+    fn test_exempt_events_and_summaries() {
+        let limiter = limiter(Some(Duration::from_secs(10)));
+        let start = Instant::now();
+        let raided = Some("111111111111111111");
+
+        for n in 0..8 {
+            limiter.admits_at(&event("member.join", raided, 1_000 + n), start);
+        }
+        assert!(limiter.admits_at(&event("interaction.create", raided, 1_010), start));
+        assert!(limiter.admits_at(&event("user.update", None, 1_010), start));
+
+        let summaries = limiter.dropped().drain_ended(11_005);
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].data["count"], 3);
+        assert_eq!(summaries[0].data["reason"], SUMMARY_REASON);
+        assert_eq!(summaries[0].guild_id.as_deref(), raided);
+    }
+}
@@ -1,6 +1,17 @@
 //! Event handling module
 //!
-//! Provides event serialization and routing to message broker.
+//! Provides event serialization, aggregation and routing to message broker.
 
+pub mod aggregate;
+pub(crate) mod entities;
+pub mod guild_limit;
+pub(crate) mod policy;
+mod protobuf;
+pub mod raid;
+pub mod redaction;
+pub mod sampling;
+pub mod schema;
+pub mod selftest;
 pub mod serialize;
+pub mod versions;
 
@@ -0,0 +1,184 @@
+//! Number serialization policy for published payloads
+//!
+//! Workers parse JSON in JavaScript, where every number is an f64 and
+//! integers above 2^53 silently lose precision. Every payload the gateway
+//! publishes therefore follows three rules:
+//!
+//! - Snowflakes (`id`, `*_id`, `*_ids`) are strings, as are Discord
+//!   identifiers that look numeric, such as discriminators.
+//! - Timestamps (`timestamp`, `*_at`, `window_start`, `window_end`) are Unix
+//!   milliseconds as unsigned integers.
+//! - No other number exceeds JavaScript's safe integer range.
+//!
+//! `shard_id` and `pool_id` are gateway-assigned counters, not snowflakes.
+//! `guild.join` data is Discord's guild object as-is, so its timestamps stay
+//! ISO 8601 strings; the other rules still hold there.
+//!
+//! Serializers build these fields with the helpers below. `violations` checks
+//! a payload against the rules; the tests and the startup self-test run every
+//! event type through it.
+
+use serde_json::Value;
+use twilight_model::user::User;
+use twilight_model::util::Timestamp;
+
+/// Largest integer a JavaScript number represents exactly (2^53 - 1)
+const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+/// Numeric fields whose names look like snowflakes
+const COUNTER_IDS: &[&str] = &["shard_id", "pool_id"];
+
+/// Event types whose data passes Discord's model through unchanged
+const PASSTHROUGH_EVENT_TYPES: &[&str] = &["guild.join"];
+
+/// A Discord timestamp as Unix milliseconds
+pub fn timestamp(at: Timestamp) -> u64 {
+    (at.as_micros() / 1000).max(0) as u64
+}
+
+/// A user's discriminator as Discord sends it: `"0"` for migrated users,
+/// zero-padded to four digits otherwise
+pub fn discriminator(user: &User) -> String {
+    user.discriminator().to_string()
+}
+
+/// Policy violations in a published payload, as `path: problem`
+pub fn violations(payload: &Value) -> Vec<String> {
+    let passthrough = payload["event_type"]
+        .as_str()
+        .is_some_and(|event_type| PASSTHROUGH_EVENT_TYPES.contains(&event_type));
+
+    let mut found = Vec::new();
+    if let Value::Object(fields) = payload {
+        for (key, value) in fields {
+            let timestamps = !(passthrough && key == "data");
+            walk(key, value, key, timestamps, &mut found);
+        }
+    }
+    found
+}
+
+fn walk(key: &str, value: &Value, path: &str, timestamps: bool, found: &mut Vec<String>) {
+    let is_snowflake = (key == "id" || key.ends_with("_id")) && !COUNTER_IDS.contains(&key);
+    let is_timestamp = key == "timestamp" || key.ends_with("_at") || key == "window_start" || key == "window_end";
+
+    match value {
+        Value::Number(n) => {
+            let problem = if is_snowflake || key == "discriminator" {
+                Some("must be a string")
+            } else if is_timestamp {
+                n.as_u64().is_none_or(|n| n > MAX_SAFE_INTEGER).then_some("must be Unix milliseconds")
+            } else if n.is_f64() {
+                None
+            } else {
+                n.as_i64()
+                    .is_none_or(|n| n.unsigned_abs() > MAX_SAFE_INTEGER)
+                    .then_some("exceeds the safe integer range")
+            };
+            if let Some(problem) = problem {
+                found.push(format!("{path}: {n} {problem}"));
+            }
+        }
+        Value::String(s) if is_timestamp && timestamps => {
+            found.push(format!("{path}: {s:?} must be Unix milliseconds"));
+        }
+        Value::Array(items) if key.ends_with("_ids") => {
+            for (i, item) in items.iter().enumerate().filter(|(_, item)| !item.is_string()) {
+                found.push(format!("{path}[{i}]: {item} must be a string"));
+            }
+        }
+        Value::Array(items) => {
+            for (i, item) in items.iter().enumerate() {
+                walk(key, item, &format!("{path}[{i}]"), timestamps, found);
+            }
+        }
+        Value::Object(fields) => {
+            for (child, value) in fields {
+                walk(child, value, &format!("{path}.{child}"), timestamps, found);
+            }
+        }
+        Value::Null | Value::String(_) | Value::Bool(_) => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const FIXTURES_DIR: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../packages/shared/nats-schemas/fixtures"
+    );
+
+    #[test]
+    fn test_violations() {
+        let payload = json!({
+            "event_type": "member.join",
+            "shard_id": 3,
+            "timestamp": 1_700_000_000_000_u64,
+            "guild_id": 123456789012345678_u64,
+            "data": {
+                "discriminator": 0,
+                "joined_at": "2024-01-01T00:00:00.000000+00:00",
+                "sample_ids": ["1", 2],
+                "count": 9_007_199_254_740_993_u64,
+                "options": [{ "name": "tier", "value": -3 }, { "name": "ratio", "value": 0.5 }],
+            },
+        });
+
+        assert_eq!(
+            violations(&payload),
+            [
+                "data.count: 9007199254740993 exceeds the safe integer range",
+                "data.discriminator: 0 must be a string",
+                "data.joined_at: \"2024-01-01T00:00:00.000000+00:00\" must be Unix milliseconds",
+                "data.sample_ids[1]: 2 must be a string",
+                "guild_id: 123456789012345678 must be a string",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_guild_join_data_keeps_discord_timestamps() {
+        let payload = json!({
+            "event_type": "guild.join",
+            "timestamp": "2024-01-01T00:00:00Z",
+            "data": { "id": "1", "joined_at": "2024-01-01T00:00:00.000000+00:00", "owner_id": 2 },
+        });
+        assert_eq!(
+            violations(&payload),
+            ["data.owner_id: 2 must be a string", "timestamp: \"2024-01-01T00:00:00Z\" must be Unix milliseconds"]
+        );
+    }
+
+    #[test]
+    fn test_helpers() {
+        let at = Timestamp::parse("2024-01-01T00:00:00.123000+00:00").unwrap();
+        assert_eq!(timestamp(at), 1_704_067_200_123);
+
+        let mut user: User = serde_json::from_value(json!({
+            "id": "987654321098765432", "username": "user", "discriminator": "0", "avatar": null
+        }))
+        .unwrap();
+        assert_eq!(discriminator(&user), "0");
+        user.discriminator = 42;
+        assert_eq!(discriminator(&user), "0042");
+    }
+
+    /// Every committed fixture, including ones added later, follows the policy
+    #[test]
+    fn every_fixture_follows_policy() {
+        let mut checked = 0;
+        for entry in std::fs::read_dir(FIXTURES_DIR).expect("Failed to read fixtures") {
+            let path = entry.unwrap().path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            let fixture: Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+            assert_eq!(violations(&fixture), Vec::<String>::new(), "{}", path.display());
+            checked += 1;
+        }
+        assert!(checked > 0);
+    }
+}
@@ -0,0 +1,182 @@
+//! Protobuf envelope (`WIRE_FORMAT=protobuf`)
+//!
+//! Mirrors `packages/shared/nats-schemas/proto/gateway_event.proto`. The
+//! messages are declared here rather than generated so images built from
+//! this crate alone (without the shared package) still compile; keep tags in
+//! step with the schema.
+
+use super::serialize::GatewayEvent;
+use prost::Message;
+use std::collections::BTreeMap;
+
+#[derive(Clone, PartialEq, Message)]
+pub struct ProtoEvent {
+    #[prost(string, tag = "1")]
+    pub event_id: String,
+    #[prost(string, tag = "2")]
+    pub event_type: String,
+    #[prost(uint64, tag = "3")]
+    pub shard_id: u64,
+    #[prost(uint64, tag = "4")]
+    pub timestamp: u64,
+    #[prost(string, optional, tag = "5")]
+    pub guild_id: Option<String>,
+    #[prost(string, optional, tag = "6")]
+    pub channel_id: Option<String>,
+    #[prost(string, optional, tag = "7")]
+    pub user_id: Option<String>,
+    #[prost(message, optional, tag = "8")]
+    pub data: Option<ProtoValue>,
+    #[prost(string, optional, tag = "9")]
+    pub bot_id: Option<String>,
+    #[prost(uint64, optional, tag = "10")]
+    pub seq: Option<u64>,
+    #[prost(uint64, optional, tag = "11")]
+    pub seq_epoch: Option<u64>,
+    /// 0 (unset) means 1
+    #[prost(uint32, tag = "12")]
+    pub schema_version: u32,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct ProtoValue {
+    #[prost(oneof = "Kind", tags = "1, 2, 3, 4, 5, 6, 7, 8")]
+    pub kind: Option<Kind>,
+}
+
+#[derive(Clone, PartialEq, prost::Oneof)]
+pub enum Kind {
+    #[prost(enumeration = "NullValue", tag = "1")]
+    Null(i32),
+    #[prost(bool, tag = "2")]
+    Bool(bool),
+    #[prost(sint64, tag = "3")]
+    Int(i64),
+    #[prost(uint64, tag = "4")]
+    Uint(u64),
+    #[prost(double, tag = "5")]
+    Double(f64),
+    #[prost(string, tag = "6")]
+    String(String),
+    #[prost(message, tag = "7")]
+    List(ListValue),
+    #[prost(message, tag = "8")]
+    Struct(Struct),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum NullValue {
+    NullValue = 0,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct ListValue {
+    #[prost(message, repeated, tag = "1")]
+    pub values: Vec<ProtoValue>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Struct {
+    #[prost(btree_map = "string, message", tag = "1")]
+    pub fields: BTreeMap<String, ProtoValue>,
+}
+
+/// Encode an event as a `GatewayEvent` message
+pub fn encode(event: &GatewayEvent) -> Vec<u8> {
+    ProtoEvent {
+        event_id: event.event_id.clone(),
+        event_type: event.event_type.clone(),
+        shard_id: event.shard_id,
+        timestamp: event.timestamp,
+        guild_id: event.guild_id.clone(),
+        channel_id: event.channel_id.clone(),
+        user_id: event.user_id.clone(),
+        data: Some(value(&event.data)),
+        bot_id: event.bot_id.clone(),
+        seq: event.seq,
+        seq_epoch: event.seq_epoch,
+        schema_version: event.schema_version,
+    }
+    .encode_to_vec()
+}
+
+fn value(json: &serde_json::Value) -> ProtoValue {
+    let kind = match json {
+        serde_json::Value::Null => Kind::Null(NullValue::NullValue as i32),
+        serde_json::Value::Bool(b) => Kind::Bool(*b),
+        serde_json::Value::Number(n) => match (n.as_u64(), n.as_i64()) {
+            (Some(n), _) => Kind::Uint(n),
+            (None, Some(n)) => Kind::Int(n),
+            _ => Kind::Double(n.as_f64().unwrap_or_default()),
+        },
+        serde_json::Value::String(s) => Kind::String(s.clone()),
+        serde_json::Value::Array(values) => Kind::List(ListValue { values: values.iter().map(value).collect() }),
+        serde_json::Value::Object(fields) => Kind::Struct(Struct {
+            fields: fields.iter().map(|(key, field)| (key.clone(), value(field))).collect(),
+        }),
+    };
+    ProtoValue { kind: Some(kind) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// What a consumer decoding the message sees, back in JSON form
+    fn to_json(value: &ProtoValue) -> serde_json::Value {
+        match value.kind.as_ref() {
+            None | Some(Kind::Null(_)) => serde_json::Value::Null,
+            Some(Kind::Bool(b)) => json!(b),
+            Some(Kind::Int(n)) => json!(n),
+            Some(Kind::Uint(n)) => json!(n),
+            Some(Kind::Double(n)) => json!(n),
+            Some(Kind::String(s)) => json!(s),
+            Some(Kind::List(list)) => list.values.iter().map(to_json).collect(),
+            Some(Kind::Struct(s)) => s.fields.iter().map(|(key, field)| (key.clone(), to_json(field))).collect(),
+        }
+    }
+
+    fn event() -> GatewayEvent {
+        GatewayEvent {
+            shard_id: 3,
+            seq: Some(42),
+            seq_epoch: Some(1_699_999_000_000),
+            guild_id: Some("1234567890123456789".to_string()),
+            user_id: Some("9876543210987654321".to_string()),
+            data: json!({
+                "nick": null,
+                "roles": ["111", "222"],
+                "pending": false,
+                "flags": 0,
+                "delta": -3,
+                "ratio": 0.25,
+                "avatar": { "hash": "a_1f" },
+            }),
+            ..GatewayEvent::for_test("member.update")
+        }
+    }
+
+    #[test]
+    fn decodes_back_to_the_json_envelope() {
+        let event = event();
+        let decoded = ProtoEvent::decode(encode(&event).as_slice()).unwrap();
+
+        assert_eq!(decoded.event_id, event.event_id);
+        assert_eq!(decoded.event_type, event.event_type);
+        assert_eq!(decoded.schema_version, event.schema_version);
+        assert_eq!((decoded.shard_id, decoded.timestamp), (3, 1_700_000_000_000));
+        assert_eq!((decoded.seq, decoded.seq_epoch), (Some(42), Some(1_699_999_000_000)));
+        assert_eq!(decoded.guild_id, event.guild_id);
+        assert_eq!(decoded.channel_id, None);
+        assert_eq!(decoded.user_id, event.user_id);
+        assert_eq!(to_json(decoded.data.as_ref().unwrap()), event.data);
+    }
+
+    #[test]
+    fn smaller_than_json() {
+        let event = event();
+        assert!(encode(&event).len() < serde_json::to_vec(&event).unwrap().len());
+    }
+}
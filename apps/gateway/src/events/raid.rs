@@ -0,0 +1,310 @@
+//! Join raid detection (`RAID_DETECTION_JOINS`)
+//!
+//! During a join raid, workers get thousands of individual `member.join`
+//! events they mostly handle the same way. Joins are counted per guild in
+//! tumbling windows of `RAID_DETECTION_WINDOW_SECS`; once a window reaches
+//! `RAID_DETECTION_JOINS`, the guild is in a raid:
+//!
+//! - a `guild.raid_alert` (`phase: "started"`) is published
+//! - its joins are no longer published one by one; each window, one
+//!   `member.join.burst` carries the count, a sample of user IDs and the
+//!   window bounds
+//! - the first window with fewer joins than the threshold ends the raid with a
+//!   `guild.raid_alert` (`phase: "ended"`) and the total
+//!
+//! Alerts and bursts are published by `run_flusher` within a second.
+
+use super::serialize::{now_millis, GatewayEvent};
+use crate::metrics::GatewayMetrics;
+use crate::nats::NatsPublisher;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+/// Event type of the aggregated joins
+pub const BURST_EVENT_TYPE: &str = "member.join.burst";
+
+/// Event type of the alerts
+pub const ALERT_EVENT_TYPE: &str = "guild.raid_alert";
+
+/// User IDs kept per burst
+pub const SAMPLE_SIZE: usize = 25;
+
+/// How often windows are closed and alerts published
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Raid detection settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RaidConfig {
+    /// Joins in one window that start a raid
+    pub threshold: u64,
+    pub window: Duration,
+}
+
+/// A guild's joins in the current window
+#[derive(Debug)]
+struct Joins {
+    shard_id: u64,
+    start_ms: u64,
+    count: u64,
+    /// Set while the guild is in a raid
+    raid: Option<Raid>,
+}
+
+#[derive(Debug)]
+struct Raid {
+    started_ms: u64,
+    total: u64,
+    sample: Vec<String>,
+}
+
+/// A guild's bot and ID
+type GuildKey = (Option<String>, String);
+
+/// Counts joins per guild; shared by a pool's shards
+#[derive(Debug)]
+pub struct RaidDetector {
+    config: RaidConfig,
+    guilds: Mutex<HashMap<GuildKey, Joins>>,
+    /// Alerts waiting for the flusher
+    alerts: Mutex<Vec<GatewayEvent>>,
+}
+
+impl RaidDetector {
+    pub fn new(config: RaidConfig) -> Self {
+        Self { config, guilds: Mutex::default(), alerts: Mutex::default() }
+    }
+
+    fn window_ms(&self) -> u64 {
+        self.config.window.as_millis() as u64
+    }
+
+    /// Count a `member.join`. Returns true when its guild is in a raid and
+    /// the raw event should not be published.
+    pub fn observe(&self, event: &GatewayEvent) -> bool {
+        let Some(ref guild_id) = event.guild_id else {
+            return false;
+        };
+        if event.event_type != "member.join" {
+            return false;
+        }
+
+        let window_ms = self.window_ms();
+        let mut guilds = self.guilds.lock().unwrap_or_else(|e| e.into_inner());
+        let joins = guilds.entry((event.bot_id.clone(), guild_id.clone())).or_insert_with(|| Joins {
+            shard_id: event.shard_id,
+            start_ms: event.timestamp,
+            count: 0,
+            raid: None,
+        });
+        // Outside a raid, windows are only closed here; a raid's are closed by the flusher
+        if joins.raid.is_none() && event.timestamp >= joins.start_ms + window_ms {
+            joins.start_ms = event.timestamp;
+            joins.count = 0;
+        }
+        joins.count += 1;
+
+        if joins.raid.is_none() && joins.count >= self.config.threshold {
+            warn!(
+                guild_id,
+                bot_id = ?event.bot_id,
+                joins = joins.count,
+                window_secs = self.config.window.as_secs(),
+                "Join raid detected, publishing member.join.burst instead of joins"
+            );
+            // The window's earlier joins went out raw; the burst starts here
+            joins.raid = Some(Raid { started_ms: event.timestamp, total: 0, sample: Vec::new() });
+            joins.start_ms = event.timestamp;
+            joins.count = 1;
+            let key = (event.bot_id.clone(), guild_id.clone());
+            let alert = self.alert(&key, joins.shard_id, "started", self.config.threshold, event.timestamp, None);
+            self.alerts.lock().unwrap_or_else(|e| e.into_inner()).push(alert);
+        }
+
+        let Some(ref mut raid) = joins.raid else {
+            return false;
+        };
+        raid.total += 1;
+        if raid.sample.len() < SAMPLE_SIZE {
+            raid.sample.push(event.user_id.clone().unwrap_or_else(|| event.event_id.clone()));
+        }
+        true
+    }
+
+    /// Close the windows that ended by `now_ms`: a burst for each raiding
+    /// guild that had joins, and an alert for each raid that ended
+    pub fn drain_ended(&self, now_ms: u64) -> Vec<GatewayEvent> {
+        let window_ms = self.window_ms();
+        let mut events = std::mem::take(&mut *self.alerts.lock().unwrap_or_else(|e| e.into_inner()));
+        let mut guilds = self.guilds.lock().unwrap_or_else(|e| e.into_inner());
+        guilds.retain(|key, joins| {
+            let end_ms = joins.start_ms + window_ms;
+            if end_ms > now_ms {
+                return true;
+            }
+            let Some(raid) = joins.raid.take() else {
+                return false;
+            };
+
+            if joins.count > 0 {
+                events.push(burst_event(key, joins, &raid.sample, end_ms, now_ms));
+            }
+            if joins.count >= self.config.threshold {
+                joins.start_ms = end_ms;
+                joins.count = 0;
+                joins.raid = Some(Raid { sample: Vec::new(), ..raid });
+                return true;
+            }
+
+            info!(guild_id = %key.1, bot_id = ?key.0, joins = raid.total, "Join raid ended");
+            events.push(self.alert(key, joins.shard_id, "ended", raid.total, raid.started_ms, Some(end_ms)));
+            false
+        });
+        events
+    }
+
+    fn alert(
+        &self,
+        (bot_id, guild_id): &GuildKey,
+        shard_id: u64,
+        phase: &str,
+        joins: u64,
+        started_ms: u64,
+        ended_ms: Option<u64>,
+    ) -> GatewayEvent {
+        GatewayEvent {
+            event_id: Uuid::new_v4().to_string(),
+            event_type: ALERT_EVENT_TYPE.to_string(),
+            schema_version: crate::events::versions::CURRENT,
+            shard_id,
+            bot_id: bot_id.clone(),
+            seq: None,
+            seq_epoch: None,
+            timestamp: ended_ms.unwrap_or(started_ms),
+            guild_id: Some(guild_id.clone()),
+            channel_id: None,
+            user_id: None,
+            data: serde_json::json!({
+                "phase": phase,
+                "joins": joins,
+                "threshold": self.config.threshold,
+                "window_secs": self.config.window.as_secs(),
+                "started_at": started_ms,
+                "ended_at": ended_ms,
+            }),
+        }
+    }
+}
+
+fn burst_event((bot_id, guild_id): &GuildKey, joins: &Joins, sample: &[String], end_ms: u64, now_ms: u64) -> GatewayEvent {
+    GatewayEvent {
+        event_id: Uuid::new_v4().to_string(),
+        event_type: BURST_EVENT_TYPE.to_string(),
+        schema_version: crate::events::versions::CURRENT,
+        shard_id: joins.shard_id,
+        bot_id: bot_id.clone(),
+        seq: None,
+        seq_epoch: None,
+        timestamp: now_ms,
+        guild_id: Some(guild_id.clone()),
+        channel_id: None,
+        user_id: None,
+        data: serde_json::json!({
+            "count": joins.count,
+            "sample_user_ids": sample,
+            "window_start": joins.start_ms,
+            "window_end": end_ms,
+        }),
+    }
+}
+
+/// Publish alerts and bursts as windows close, until the process exits
+pub async fn run_flusher(detector: Arc<RaidDetector>, nats: Arc<NatsPublisher>, metrics: Arc<GatewayMetrics>) {
+    info!(
+        joins = detector.config.threshold,
+        window_secs = detector.config.window.as_secs(),
+        "Join raid detection enabled"
+    );
+
+    let mut interval = tokio::time::interval(FLUSH_INTERVAL);
+    loop {
+        interval.tick().await;
+        for event in detector.drain_ended(now_millis()) {
+            if event.event_type == ALERT_EVENT_TYPE {
+                metrics.record_raid_alert(if event.data["phase"] == "started" { "started" } else { "ended" });
+            }
+            if let Err(e) = nats.publish_event(&event).await {
+                debug!(event_type = %event.event_type, error = %e, "Failed to publish raid event");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GUILD: &str = "123456789012345678";
+
+    fn join(user: u64, timestamp: u64) -> GatewayEvent {
+        GatewayEvent {
+            event_id: Uuid::new_v4().to_string(),
+            shard_id: 1,
+            timestamp,
+            guild_id: Some(GUILD.to_string()),
+            user_id: Some(user.to_string()),
+            ..GatewayEvent::for_test("member.join")
+        }
+    }
+
+    fn detector() -> RaidDetector {
+        RaidDetector::new(RaidConfig { threshold: 5, window: Duration::from_secs(10) })
+    }
+
+    #[test]
+    fn test_quiet_guilds_publish_joins() {
+        let detector = detector();
+        // Four joins per window never reach the threshold
+        for window in 0..3 {
+            for user in 0..4 {
+                assert!(!detector.observe(&join(user, window * 10_000 + user)));
+            }
+        }
+        assert!(detector.drain_ended(u64::MAX).is_empty());
+        assert!(detector.guilds.lock().unwrap().is_empty(), "quiet windows are dropped");
+    }
+
+    #[test]
+    fn test_raids_are_burst_and_alerted() {
+        let detector = detector();
+        let absorbed: Vec<_> = (0..40).map(|user| detector.observe(&join(user, 1_000 + user))).collect();
+        assert_eq!(absorbed.iter().filter(|absorbed| !**absorbed).count(), 4, "joins before the threshold go out");
+
+        let started = detector.drain_ended(1_100);
+        assert_eq!(started.len(), 1);
+        assert_eq!(started[0].event_type, ALERT_EVENT_TYPE);
+        assert_eq!(started[0].data["phase"], "started");
+        assert_eq!(started[0].data["started_at"], 1_004);
+
+        let burst = detector.drain_ended(11_004);
+        assert_eq!(burst.len(), 1);
+        assert_eq!(burst[0].event_type, BURST_EVENT_TYPE);
+        assert_eq!(burst[0].data["count"], 36);
+        assert_eq!(burst[0].data["sample_user_ids"].as_array().unwrap().len(), SAMPLE_SIZE);
+        assert_eq!((burst[0].data["window_start"].as_u64(), burst[0].data["window_end"].as_u64()), (Some(1_004), Some(11_004)));
+        let value = serde_json::to_value(&burst[0]).unwrap();
+        assert!(super::super::policy::violations(&value).is_empty());
+
+        // Still raiding: joins stay absorbed until a window falls under the threshold
+        assert!(detector.observe(&join(99, 12_000)));
+        let ended = detector.drain_ended(21_004);
+        assert_eq!(ended.len(), 2);
+        assert_eq!(ended[0].data["count"], 1);
+        assert_eq!(ended[1].data["phase"], "ended");
+        assert_eq!(ended[1].data["joins"], 37);
+        assert_eq!(ended[1].data["ended_at"], 21_004);
+        assert!(!detector.observe(&join(100, 22_000)));
+    }
+}
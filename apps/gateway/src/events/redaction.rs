@@ -0,0 +1,289 @@
+//! PII redaction of event payloads (`REDACTION_POLICY`)
+//!
+//! Some deployments can't ship user PII to every downstream worker. A
+//! redaction policy, given as JSON, names fields of `data` per event type
+//! (`member.join`) or family (`member.*`, `*` for every type) and what happens
+//! to them before the event is published:
+//!
+//! - `hash`: a string or number becomes the hex HMAC-SHA256 of its value under
+//!   `REDACTION_HASH_KEY`, so workers can still tell users apart
+//! - `strip`: the value is emptied (`""`, `[]`, `{}`; other values become
+//!   `null`), keeping the field's type for schemas that require it
+//! - `remove`: the field is dropped
+//!
+//! ```json
+//! { "rules": {
+//!     "member.*": { "hash": ["username", "nick"], "strip": ["avatar"] },
+//!     "message.*": { "strip": ["content", "author.avatar"], "hash": ["author.username"] } } }
+//! ```
+//!
+//! Paths are dotted field names; arrays along a path apply the rest of it to
+//! every element. As with `EVENT_SAMPLING`, an exact type ranks above any
+//! family and longer families above shorter ones; only the best-ranked rule
+//! applies. Envelope fields (IDs, timestamps) are never redacted.
+//!
+//! Raw passthrough is not published for event types with a rule: Discord's
+//! payload would carry the fields in full. The Redis cache is not redacted
+//! either; its hashes hold Discord's member fields, not event `data`, so
+//! treat Redis as holding the PII the policy keeps off NATS.
+
+use super::serialize::GatewayEvent;
+use crate::error::GatewayError;
+use crate::hex;
+use ring::hmac;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+/// Fields of one event type's `data` and what happens to them
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RedactionRule {
+    #[serde(default)]
+    pub hash: Vec<String>,
+    #[serde(default)]
+    pub strip: Vec<String>,
+    #[serde(default)]
+    pub remove: Vec<String>,
+}
+
+/// Redaction rules by event type or family
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RedactionPolicy {
+    pub rules: BTreeMap<String, RedactionRule>,
+}
+
+impl RedactionPolicy {
+    /// Parse and check a policy document
+    pub fn parse(json: &str) -> Result<Self, GatewayError> {
+        let policy: Self = serde_json::from_str(json)
+            .map_err(|e| GatewayError::Config(format!("REDACTION_POLICY is invalid: {e}")))?;
+
+        for (pattern, rule) in &policy.rules {
+            let invalid = |reason: String| GatewayError::Config(format!("REDACTION_POLICY rule {pattern:?} {reason}"));
+            let family = pattern.strip_suffix('*');
+            if pattern.is_empty() || family.is_some_and(|prefix| !prefix.is_empty() && !prefix.ends_with('.')) {
+                return Err(invalid("must be an event type, family.* or *".to_string()));
+            }
+            let mut seen = std::collections::BTreeSet::new();
+            for path in rule.hash.iter().chain(&rule.strip).chain(&rule.remove) {
+                if path.split('.').any(str::is_empty) {
+                    return Err(invalid(format!("has an invalid field path {path:?}")));
+                }
+                if !seen.insert(path) {
+                    return Err(invalid(format!("names {path:?} more than once")));
+                }
+            }
+        }
+        Ok(policy)
+    }
+
+    /// Whether any rule hashes a field (and so needs a key)
+    pub fn hashes(&self) -> bool {
+        self.rules.values().any(|rule| !rule.hash.is_empty())
+    }
+
+    /// The rule for an event type: exact above any family, longer families
+    /// above shorter ones
+    fn rule(&self, event_type: &str) -> Option<&RedactionRule> {
+        self.rules
+            .iter()
+            .filter_map(|(pattern, rule)| {
+                let rank = match pattern.strip_suffix('*') {
+                    Some(prefix) => event_type.starts_with(prefix).then_some(prefix.len()),
+                    None => (pattern == event_type).then_some(usize::MAX),
+                };
+                rank.map(|rank| (rank, rule))
+            })
+            .max_by_key(|(rank, _)| *rank)
+            .map(|(_, rule)| rule)
+    }
+}
+
+/// Redaction settings
+#[derive(Clone)]
+pub struct RedactionConfig {
+    pub policy: RedactionPolicy,
+    /// HMAC key for hashed fields (required when a rule hashes)
+    pub hash_key: Option<Vec<u8>>,
+}
+
+impl std::fmt::Debug for RedactionConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RedactionConfig")
+            .field("policy", &self.policy)
+            .field("hash_key", &self.hash_key.as_ref().map(|_| "<redacted>"))
+            .finish()
+    }
+}
+
+/// Redacts event payloads under a policy
+pub struct Redactor {
+    policy: RedactionPolicy,
+    key: Option<hmac::Key>,
+}
+
+impl Redactor {
+    pub fn new(config: &RedactionConfig) -> Result<Self, GatewayError> {
+        if config.policy.hashes() && config.hash_key.is_none() {
+            return Err(GatewayError::Config("REDACTION_POLICY hashes fields but REDACTION_HASH_KEY is not set".to_string()));
+        }
+        Ok(Self {
+            policy: config.policy.clone(),
+            key: config.hash_key.as_ref().map(|key| hmac::Key::new(hmac::HMAC_SHA256, key)),
+        })
+    }
+
+    /// Event types or families with a rule
+    pub fn patterns(&self) -> impl Iterator<Item = &str> {
+        self.policy.rules.keys().map(String::as_str)
+    }
+
+    /// Whether a rule applies to an event type
+    pub fn covers(&self, event_type: &str) -> bool {
+        self.policy.rule(event_type).is_some()
+    }
+
+    /// A redacted copy of an event, or None when no rule applies to its type
+    pub fn redact(&self, event: &GatewayEvent) -> Option<GatewayEvent> {
+        let rule = self.policy.rule(&event.event_type)?;
+        let mut redacted = event.clone();
+        for path in &rule.hash {
+            apply(&mut redacted.data, path, &mut |fields, key| {
+                if let Some(value) = fields.get_mut(key) {
+                    self.hash(value);
+                }
+            });
+        }
+        for path in &rule.strip {
+            apply(&mut redacted.data, path, &mut |fields, key| {
+                if let Some(value) = fields.get_mut(key) {
+                    *value = empty(value);
+                }
+            });
+        }
+        for path in &rule.remove {
+            apply(&mut redacted.data, path, &mut |fields, key| {
+                fields.remove(key);
+            });
+        }
+        Some(redacted)
+    }
+
+    fn hash(&self, value: &mut Value) {
+        let Some(ref key) = self.key else {
+            return;
+        };
+        match value {
+            Value::String(text) => *value = Value::String(hex::encode(hmac::sign(key, text.as_bytes()).as_ref())),
+            Value::Number(n) => *value = Value::String(hex::encode(hmac::sign(key, n.to_string().as_bytes()).as_ref())),
+            Value::Array(items) => items.iter_mut().for_each(|item| self.hash(item)),
+            Value::Null | Value::Bool(_) | Value::Object(_) => {}
+        }
+    }
+}
+
+/// Call `action` with the object holding the last field of a dotted path,
+/// for every match (arrays along the path fan out)
+fn apply(value: &mut Value, path: &str, action: &mut impl FnMut(&mut Map<String, Value>, &str)) {
+    match value {
+        Value::Array(items) => items.iter_mut().for_each(|item| apply(item, path, action)),
+        Value::Object(fields) => match path.split_once('.') {
+            None => action(fields, path),
+            Some((key, rest)) => {
+                if let Some(inner) = fields.get_mut(key) {
+                    apply(inner, rest, action);
+                }
+            }
+        },
+        _ => {}
+    }
+}
+
+/// An empty value of the same type
+fn empty(value: &Value) -> Value {
+    match value {
+        Value::String(_) => Value::String(String::new()),
+        Value::Array(_) => Value::Array(Vec::new()),
+        Value::Object(_) => Value::Object(Map::new()),
+        _ => Value::Null,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const POLICY: &str = r#"{ "rules": {
+        "member.*": { "hash": ["username", "nick"], "strip": ["avatar"] },
+        "member.leave": { "remove": ["username"] },
+        "message.*": { "strip": ["content", "author.avatar"], "hash": ["author.username"], "remove": ["attachments.filename"] }
+    } }"#;
+
+    fn redactor() -> Redactor {
+        let policy = RedactionPolicy::parse(POLICY).unwrap();
+        Redactor::new(&RedactionConfig { policy, hash_key: Some(b"k".to_vec()) }).unwrap()
+    }
+
+    fn event(event_type: &str, data: Value) -> GatewayEvent {
+        GatewayEvent {
+            guild_id: Some("123456789012345678".to_string()),
+            user_id: Some("987654321098765432".to_string()),
+            data,
+            ..GatewayEvent::for_test(event_type)
+        }
+    }
+
+    #[test]
+    fn test_fields_are_hashed_stripped_and_removed() {
+        let redactor = redactor();
+        let join = redactor
+            .redact(&event("member.join", json!({ "username": "testuser", "nick": null, "avatar": "a1b2", "is_bot": false })))
+            .unwrap();
+        let username = join.data["username"].as_str().unwrap();
+        assert_eq!(username.len(), 64);
+        assert_ne!(username, "testuser");
+        assert_eq!(join.data["nick"], Value::Null);
+        assert_eq!(join.data["avatar"], "");
+        assert_eq!(join.data["is_bot"], false);
+        assert_eq!(join.user_id.as_deref(), Some("987654321098765432"), "envelope fields are kept");
+
+        // The same value hashes the same, so workers can still correlate
+        let update = redactor.redact(&event("member.update", json!({ "username": "testuser" }))).unwrap();
+        assert_eq!(update.data["username"], username);
+
+        // The exact rule replaces the family's
+        let leave = redactor.redact(&event("member.leave", json!({ "username": "testuser", "avatar": "a1b2" }))).unwrap();
+        assert_eq!(leave.data, json!({ "avatar": "a1b2" }));
+
+        assert!(redactor.redact(&event("guild.join", json!({ "name": "Guild" }))).is_none());
+        assert!(redactor.covers("member.leave") && !redactor.covers("guild.join"));
+    }
+
+    #[test]
+    fn test_paths_reach_into_objects_and_arrays() {
+        let message = json!({
+            "content": "gm",
+            "author": { "id": "987654321098765432", "username": "user", "avatar": null },
+            "attachments": [{ "id": "1", "filename": "a.png" }, { "id": "2", "filename": "b.png" }]
+        });
+        let redacted = redactor().redact(&event("message.create", message)).unwrap();
+        assert_eq!(redacted.data["content"], "");
+        assert_eq!(redacted.data["author"]["avatar"], Value::Null);
+        assert_eq!(redacted.data["author"]["username"].as_str().unwrap().len(), 64);
+        assert_eq!(redacted.data["attachments"], json!([{ "id": "1" }, { "id": "2" }]));
+    }
+
+    #[test]
+    fn test_invalid_policies_are_rejected() {
+        assert!(RedactionPolicy::parse(r#"{ "rules": { "member*": { "strip": ["nick"] } } }"#).is_err());
+        assert!(RedactionPolicy::parse(r#"{ "rules": { "*": { "strip": ["author..avatar"] } } }"#).is_err());
+        assert!(RedactionPolicy::parse(r#"{ "rules": { "*": { "strip": ["nick"], "hash": ["nick"] } } }"#).is_err());
+        assert!(RedactionPolicy::parse(r#"{ "rules": { "*": { "mask": ["nick"] } } }"#).is_err());
+
+        let policy = RedactionPolicy::parse(r#"{ "rules": { "*": { "hash": ["username"] } } }"#).unwrap();
+        assert!(Redactor::new(&RedactionConfig { policy, hash_key: None }).is_err(), "hashing needs a key");
+    }
+}
@@ -0,0 +1,127 @@
+//! Per-event-type publish sampling
+//!
+//! `EVENT_SAMPLING` lets operators cut EVENTS stream volume without a code
+//! change: each entry publishes a share of one event type (`member.update=10`)
+//! or of a family (`message.*=25`), and `off` drops it entirely. Types without
+//! an entry are published in full. Sampling happens after aggregation, so
+//! `event.summary` counts still cover every event. Interactions can't be
+//! sampled: a dropped one is a command that never gets an answer.
+
+use crate::error::GatewayError;
+
+/// Event types that are always published
+const UNSAMPLED: &[&str] = &["interaction.create"];
+
+/// Share of one event type (or `family.*`) that is published
+#[derive(Debug, Clone, PartialEq)]
+pub struct SampleRule {
+    pub event_type: String,
+    /// 0 to 100
+    pub percent: f64,
+}
+
+impl SampleRule {
+    /// Matched event types, or None when the rule doesn't apply. Exact rules
+    /// rank above any family; longer families rank above shorter ones.
+    fn rank(&self, event_type: &str) -> Option<usize> {
+        match self.event_type.strip_suffix('*') {
+            Some(prefix) => event_type.starts_with(prefix).then_some(prefix.len()),
+            None => (self.event_type == event_type).then_some(usize::MAX),
+        }
+    }
+}
+
+/// Which events are published, by type
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EventSampling {
+    rules: Vec<SampleRule>,
+}
+
+impl EventSampling {
+    /// Parse `EVENT_SAMPLING`: comma-separated `event_type=percent|off`, e.g.
+    /// `member.update=10,presence.update=off,message.*=25`
+    pub fn parse(spec: &str) -> Result<Self, GatewayError> {
+        let rules = spec
+            .split(',')
+            .map(str::trim)
+            .filter(|rule| !rule.is_empty())
+            .map(|rule| {
+                let invalid = || {
+                    GatewayError::Config(format!(
+                        "EVENT_SAMPLING entry {rule:?} must be event_type=percent (0-100) or event_type=off"
+                    ))
+                };
+                let (event_type, share) = rule.split_once('=').ok_or_else(invalid)?;
+                let event_type = event_type.trim();
+                let percent = match share.trim() {
+                    "off" => 0.0,
+                    share => share.parse::<f64>().map_err(|_| invalid())?,
+                };
+                if event_type.is_empty() || !(0.0..=100.0).contains(&percent) {
+                    return Err(invalid());
+                }
+                if let Some(unsampled) = UNSAMPLED.iter().find(|unsampled| {
+                    SampleRule { event_type: event_type.to_string(), percent }.rank(unsampled).is_some()
+                }) {
+                    return Err(GatewayError::Config(format!("EVENT_SAMPLING can't sample {unsampled} ({rule:?})")));
+                }
+                Ok(SampleRule { event_type: event_type.to_string(), percent })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { rules })
+    }
+
+    pub fn rules(&self) -> &[SampleRule] {
+        &self.rules
+    }
+
+    /// Share of an event type that is published
+    pub fn percent(&self, event_type: &str) -> f64 {
+        self.rules
+            .iter()
+            .filter_map(|rule| Some((rule.rank(event_type)?, rule.percent)))
+            .max_by_key(|(rank, _)| *rank)
+            .map_or(100.0, |(_, percent)| percent)
+    }
+
+    /// Whether to publish this event (random within the type's share)
+    pub fn admits(&self, event_type: &str) -> bool {
+        match self.percent(event_type) {
+            percent if percent >= 100.0 => true,
+            percent if percent <= 0.0 => false,
+            percent => fastrand::f64() * 100.0 < percent,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn most_specific_rule_wins() {
+        let sampling = EventSampling::parse("message.*=25, message.delete=off, member.update=10").unwrap();
+        assert_eq!(sampling.percent("member.update"), 10.0);
+        assert_eq!(sampling.percent("message.create"), 25.0);
+        assert_eq!(sampling.percent("message.delete"), 0.0);
+        assert_eq!(sampling.percent("member.join"), 100.0);
+
+        assert!(!sampling.admits("message.delete"));
+        assert!(sampling.admits("guild.join"));
+        assert_eq!(EventSampling::parse("").unwrap(), EventSampling::default());
+    }
+
+    #[test]
+    fn sampling_keeps_roughly_the_share() {
+        let sampling = EventSampling::parse("member.update=10").unwrap();
+        let admitted = (0..10_000).filter(|_| sampling.admits("member.update")).count();
+        assert!((700..1300).contains(&admitted), "{admitted} of 10000 admitted");
+    }
+
+    #[test]
+    fn invalid_entries_are_rejected() {
+        for spec in ["member.update", "member.update=150", "member.update=half", "=10", "interaction.create=off", "*=50"] {
+            assert!(EventSampling::parse(spec).is_err(), "{spec}");
+        }
+    }
+}
@@ -0,0 +1,186 @@
+//! Publish-time validation against the wire JSON Schema
+//!
+//! Every event is checked against
+//! `packages/shared/nats-schemas/json-schema/gateway-event.schema.json`
+//! (embedded at build time, see build.rs) before it is published, so an
+//! envelope TypeScript consumers would reject is caught at the producer. In
+//! `warn` mode violations are logged and counted and the event is published
+//! anyway; in `enforce` mode it is not published.
+
+use super::serialize::GatewayEvent;
+use crate::error::GatewayError;
+use crate::metrics::GatewayMetrics;
+use jsonschema::Validator;
+use serde_json::Value;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+mod embedded {
+    include!(concat!(env!("OUT_DIR"), "/schema.rs"));
+}
+
+/// What happens to an event that fails the schema
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidationMode {
+    Off,
+    /// Log and count, then publish
+    Warn,
+    /// Log and count, and don't publish
+    Enforce,
+}
+
+impl ValidationMode {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim() {
+            "off" => Some(Self::Off),
+            "warn" => Some(Self::Warn),
+            "enforce" => Some(Self::Enforce),
+            _ => None,
+        }
+    }
+
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Warn => "warn",
+            Self::Enforce => "enforce",
+        }
+    }
+}
+
+/// Checks events against the compiled wire schema
+pub struct SchemaValidator {
+    mode: ValidationMode,
+    validator: Validator,
+    metrics: Arc<GatewayMetrics>,
+}
+
+impl SchemaValidator {
+    /// Compile the embedded schema; None when validation is off or the build
+    /// had no schema (the Docker image)
+    pub fn new(mode: ValidationMode, metrics: Arc<GatewayMetrics>) -> Result<Option<Self>, GatewayError> {
+        if mode == ValidationMode::Off {
+            return Ok(None);
+        }
+        let Some(schema) = embedded::GATEWAY_EVENT else {
+            warn!("Built without the wire schema; skipping publish-time validation");
+            return Ok(None);
+        };
+
+        info!(mode = mode.as_str(), "Publish-time schema validation enabled");
+        Ok(Some(Self { mode, validator: compile(schema)?, metrics }))
+    }
+
+    /// Check an event; an error means it must not be published
+    pub fn check(&self, event: &GatewayEvent) -> Result<(), GatewayError> {
+        let value = serde_json::to_value(event).map_err(|e| GatewayError::SerializationFailed {
+            event_type: event.event_type.clone(),
+            shard_id: event.shard_id,
+            source: e,
+        })?;
+        let Some(detail) = violation(&self.validator, &value) else {
+            return Ok(());
+        };
+
+        self.metrics.record_schema_violation(&event.event_type, self.mode.as_str());
+        warn!(
+            event_id = %event.event_id,
+            event_type = %event.event_type,
+            mode = self.mode.as_str(),
+            detail,
+            "Event violates the wire schema"
+        );
+        match self.mode {
+            ValidationMode::Enforce => Err(GatewayError::SchemaViolation {
+                event_type: event.event_type.clone(),
+                detail,
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn compile(schema: &str) -> Result<Validator, GatewayError> {
+    let schema: Value = serde_json::from_str(schema)
+        .map_err(|e| GatewayError::Config(format!("Wire schema is not valid JSON: {e}")))?;
+    jsonschema::validator_for(&schema).map_err(|e| GatewayError::Config(format!("Invalid wire schema: {e}")))
+}
+
+/// First violation, as `pointer: message`
+fn violation(validator: &Validator, value: &Value) -> Option<String> {
+    let error = validator.validate(value).err()?;
+    Some(format!("{}: {error}", error.instance_path()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod fixtures {
+        include!(concat!(env!("OUT_DIR"), "/fixtures.rs"));
+    }
+
+    /// Fixtures that are not GatewayEvent envelopes
+    const NOT_ENVELOPES: &[&str] = &[
+        "backfill-progress",
+        "backfill-request",
+        "cached-guild",
+        "canary-result",
+        "eligibility-check",
+        "gateway-topology",
+        "guild-request",
+        "guild-shard",
+        "member-chunk",
+        "member-chunk-request",
+        "presence-update",
+        "raw-event",
+        "rest-request",
+        "rest-response",
+        "tick",
+    ];
+
+    fn validator() -> Validator {
+        compile(embedded::GATEWAY_EVENT.expect("schema is embedded in the monorepo")).unwrap()
+    }
+
+    #[test]
+    fn every_envelope_fixture_validates() {
+        let validator = validator();
+        for (name, contents) in fixtures::FIXTURES.iter().filter(|(name, _)| !NOT_ENVELOPES.contains(name)) {
+            let fixture: Value = serde_json::from_str(contents).unwrap();
+            assert_eq!(violation(&validator, &fixture), None, "{name}");
+        }
+    }
+
+    #[test]
+    fn violations_name_the_field() {
+        let validator = validator();
+        let (_, contents) = fixtures::FIXTURES.iter().find(|(name, _)| *name == "interaction-create").unwrap();
+        let mut event: Value = serde_json::from_str(contents).unwrap();
+
+        // BB60-20: the token under the wrong name
+        let token = event["data"].as_object_mut().unwrap().remove("interaction_token").unwrap();
+        event["data"]["token"] = token;
+        let detail = violation(&validator, &event).unwrap();
+        assert!(detail.starts_with("/data: "), "{detail}");
+        assert!(detail.contains("interaction_token"), "{detail}");
+
+        event["shard_id"] = Value::from("0");
+        assert!(violation(&validator, &event).is_some());
+    }
+
+    #[test]
+    fn unknown_event_types_only_need_the_envelope() {
+        let event = serde_json::json!({
+            "event_id": "00000000-0000-4000-8000-000000000001", "event_type": "guild.update", "shard_id": 0,
+            "timestamp": 1700000000000_u64, "guild_id": "1", "channel_id": null, "user_id": null, "data": { "any": 1 }
+        });
+        assert_eq!(violation(&validator(), &event), None);
+    }
+
+    #[test]
+    fn test_parse_mode() {
+        assert_eq!(ValidationMode::parse("enforce"), Some(ValidationMode::Enforce));
+        assert_eq!(ValidationMode::parse("strict"), None);
+    }
+}
@@ -0,0 +1,443 @@
+//! Startup self-test of the wire contract
+//!
+//! Runs the serializer over built-in Discord payloads and compares the output
+//! with the committed fixtures embedded at build time (see build.rs), so a
+//! gateway whose serialization drifted from the contract refuses to start
+//! instead of publishing the drift. Each output is also checked against the
+//! number policy.
+//!
+//! Only the envelope's `event_id` and `timestamp` are taken from the fixture;
+//! everything else must match exactly. `guild.join` (Discord's guild object
+//! passed through), `event.summary`, the raid events, `member.sync` and ticks
+//! have no sample.
+
+use super::policy;
+use super::serialize::{capability_degraded_event, serialize_event, set_removal_reason, GatewayEvent};
+use crate::discord::audit::RemovalReason;
+use crate::error::GatewayError;
+use crate::shard::circuit::{CircuitState, Transition};
+use serde_json::{json, Value};
+use tracing::{info, warn};
+use twilight_model::application::interaction::Interaction;
+use twilight_model::channel::Message;
+use twilight_model::gateway::event::Event;
+use twilight_model::gateway::payload::incoming::{
+    ChannelCreate, GuildDelete, InteractionCreate, MemberAdd, MemberRemove, MemberUpdate, MessageCreate,
+    MessageDelete, MessageDeleteBulk, MessageUpdate, ReactionAdd, ReactionRemoveAll, RoleCreate, RoleDelete,
+    ThreadCreate, ThreadListSync, VoiceServerUpdate, VoiceStateUpdate,
+};
+use twilight_model::id::Id;
+
+mod embedded {
+    include!(concat!(env!("OUT_DIR"), "/fixtures.rs"));
+}
+
+/// Produces a sample event through the serializer
+type Sample = fn() -> Result<GatewayEvent, serde_json::Error>;
+
+/// Fixtures with a built-in sample, and how to produce each from the serializer
+const SAMPLES: &[(&str, Sample)] = &[
+    ("guild-leave", guild_leave),
+    ("member-join", member_join),
+    ("member-leave", member_leave),
+    ("member-leave-kick", member_leave_kick),
+    ("member-update", member_update),
+    ("interaction-create", interaction_create),
+    ("interaction-create-dm", interaction_create_dm),
+    ("interaction-component", interaction_component),
+    ("interaction-modal", interaction_modal),
+    ("message-create", message_create),
+    ("message-update", message_update),
+    ("message-delete", message_delete),
+    ("message-bulk-delete", message_bulk_delete),
+    ("reaction-add", reaction_add),
+    ("reaction-remove-all", reaction_remove_all),
+    ("voice-state-update", voice_state_update),
+    ("voice-server-update", voice_server_update),
+    ("role-create", role_create),
+    ("role-delete", role_delete),
+    ("channel-create", channel_create),
+    ("thread-create", thread_create),
+    ("thread-list-sync", thread_list_sync),
+    ("gateway-capability-degraded", capability_degraded),
+    ("gateway-circuit-state", circuit_state),
+];
+
+const GUILD: &str = "123456789012345678";
+const USER: &str = "987654321098765432";
+
+/// Check every sample against its fixture
+pub fn run() -> Result<(), GatewayError> {
+    run_against(embedded::FIXTURES)
+}
+
+fn run_against(fixtures: &[(&str, &str)]) -> Result<(), GatewayError> {
+    if fixtures.is_empty() {
+        warn!("Built without wire fixtures; skipping the contract self-test");
+        return Ok(());
+    }
+
+    let mut checked = Vec::new();
+    for &(name, sample) in SAMPLES {
+        let Some(&(_, contents)) = fixtures.iter().find(|(fixture, _)| *fixture == name) else {
+            continue;
+        };
+        let drift = |detail: String| GatewayError::ContractDrift { fixture: name, detail };
+
+        let fixture: Value = serde_json::from_str(contents).map_err(|e| drift(format!("fixture: {e}")))?;
+        let output = sample().map_err(|e| drift(format!("sample: {e}")))?;
+        let mut output = serde_json::to_value(output).map_err(|e| drift(format!("output: {e}")))?;
+        output["event_id"] = fixture["event_id"].clone();
+        output["timestamp"] = fixture["timestamp"].clone();
+
+        if let Some(path) = first_difference(&fixture, &output, "") {
+            return Err(drift(format!(
+                "{path}: expected {}, serialized {}",
+                pointer(&fixture, &path),
+                pointer(&output, &path)
+            )));
+        }
+        if let Some(violation) = policy::violations(&output).into_iter().next() {
+            return Err(drift(violation));
+        }
+        checked.push(name);
+    }
+
+    info!(fixtures = ?checked, "Wire contract self-test passed");
+    Ok(())
+}
+
+/// JSON pointer of the first place `a` and `b` differ
+fn first_difference(a: &Value, b: &Value, path: &str) -> Option<String> {
+    match (a, b) {
+        (Value::Object(a), Value::Object(b)) => a
+            .keys()
+            .chain(b.keys().filter(|key| !a.contains_key(*key)))
+            .find_map(|key| match (a.get(key), b.get(key)) {
+                (Some(a), Some(b)) => first_difference(a, b, &format!("{path}/{key}")),
+                _ => Some(format!("{path}/{key}")),
+            }),
+        (Value::Array(a), Value::Array(b)) if a.len() == b.len() => a
+            .iter()
+            .zip(b)
+            .enumerate()
+            .find_map(|(i, (a, b))| first_difference(a, b, &format!("{path}/{i}"))),
+        _ => (a != b).then(|| path.to_string()),
+    }
+}
+
+fn pointer(value: &Value, path: &str) -> String {
+    value.pointer(path).map_or_else(|| "nothing".to_string(), Value::to_string)
+}
+
+fn serialized(event: Event) -> Result<GatewayEvent, serde_json::Error> {
+    serialize_event(&event, 0).ok_or_else(|| serde::de::Error::custom("event is not forwarded"))
+}
+
+fn user(name: &str) -> Value {
+    json!({ "id": USER, "username": name, "discriminator": "0", "avatar": null })
+}
+
+fn guild_leave() -> Result<GatewayEvent, serde_json::Error> {
+    serialized(Event::GuildDelete(GuildDelete { id: Id::new(123456789012345678), unavailable: Some(false) }))
+}
+
+fn member_join() -> Result<GatewayEvent, serde_json::Error> {
+    let member: MemberAdd = serde_json::from_value(json!({
+        "guild_id": GUILD, "user": user("testuser"), "roles": [], "nick": null,
+        "joined_at": "2023-11-14T22:13:20.000000+00:00", "deaf": false, "mute": false, "flags": 0
+    }))?;
+    serialized(Event::MemberAdd(Box::new(member)))
+}
+
+fn member_leave() -> Result<GatewayEvent, serde_json::Error> {
+    let member: MemberRemove = serde_json::from_value(json!({ "guild_id": GUILD, "user": user("testuser") }))?;
+    serialized(Event::MemberRemove(member))
+}
+
+fn member_leave_kick() -> Result<GatewayEvent, serde_json::Error> {
+    let mut event = member_leave()?;
+    set_removal_reason(&mut event.data, RemovalReason::Kick);
+    Ok(event)
+}
+
+fn member_update() -> Result<GatewayEvent, serde_json::Error> {
+    let member: MemberUpdate = serde_json::from_value(json!({
+        "guild_id": GUILD, "user": user("testuser"), "roles": ["111111111111111111", "222222222222222222"],
+        "nick": "testnick", "joined_at": null, "avatar": null, "communication_disabled_until": null,
+        "premium_since": null, "deaf": false, "mute": false
+    }))?;
+    serialized(Event::MemberUpdate(Box::new(member)))
+}
+
+fn interaction(fields: Value) -> Result<GatewayEvent, serde_json::Error> {
+    let mut interaction = json!({
+        "application_id": "100000000000000001",
+        "channel": { "id": "333333333333333333", "type": 0 },
+        "entitlements": [],
+        "id": "444444444444444444",
+        "type": 2,
+        "token": "aW50ZXJhY3Rpb25fdG9rZW5fZXhhbXBsZQ",
+    });
+    if let (Value::Object(base), Value::Object(fields)) = (&mut interaction, fields) {
+        base.extend(fields);
+    }
+    let interaction: Interaction = serde_json::from_value(interaction)?;
+    serialized(Event::InteractionCreate(Box::new(InteractionCreate(interaction))))
+}
+
+/// Fields of an interaction in the sample guild
+fn in_guild(fields: Value) -> Result<GatewayEvent, serde_json::Error> {
+    let mut guild = json!({
+        "guild_id": GUILD,
+        "context": 0,
+        "authorizing_integration_owners": { "0": GUILD },
+        "locale": "en-US",
+        "guild_locale": "de",
+        "member": {
+            "user": user("user"), "roles": [], "joined_at": "2023-01-01T00:00:00.000000+00:00",
+            "deaf": false, "mute": false, "flags": 0, "permissions": "8"
+        },
+    });
+    if let (Value::Object(base), Value::Object(fields)) = (&mut guild, fields) {
+        base.extend(fields);
+    }
+    interaction(guild)
+}
+
+fn interaction_create() -> Result<GatewayEvent, serde_json::Error> {
+    in_guild(json!({
+        "data": {
+            "id": "555555555555555555",
+            "name": "verify",
+            "type": 1,
+            "options": [
+                { "name": "member", "type": 6, "value": "222222222222222222" },
+                { "name": "tier", "type": 4, "value": 3 }
+            ],
+            "resolved": {
+                "users": { "222222222222222222": {
+                    "id": "222222222222222222", "username": "holder", "global_name": "Holder",
+                    "discriminator": "0", "avatar": null
+                } },
+                "members": { "222222222222222222": {
+                    "roles": ["888888888888888888"], "joined_at": "2024-01-01T00:00:00.000000+00:00",
+                    "nick": null, "permissions": "1024", "flags": 0, "pending": false
+                } }
+            }
+        }
+    }))
+}
+
+fn interaction_create_dm() -> Result<GatewayEvent, serde_json::Error> {
+    interaction(json!({
+        "channel": { "id": "333333333333333333", "type": 1 },
+        "context": 1,
+        "authorizing_integration_owners": { "1": USER },
+        "locale": "pt-BR",
+        "user": user("user"),
+        "data": { "id": "555555555555555555", "name": "verify", "type": 1 }
+    }))
+}
+
+fn interaction_component() -> Result<GatewayEvent, serde_json::Error> {
+    in_guild(json!({
+        "type": 3,
+        "data": { "custom_id": "role-picker", "component_type": 3, "values": ["holder", "og"] }
+    }))
+}
+
+fn interaction_modal() -> Result<GatewayEvent, serde_json::Error> {
+    in_guild(json!({
+        "type": 5,
+        "data": {
+            "custom_id": "verify-modal",
+            "components": [
+                { "type": 18, "id": 1, "component": { "type": 4, "id": 2, "custom_id": "wallet", "value": "0x1234" } },
+                { "type": 1, "id": 3, "components": [{ "type": 4, "id": 4, "custom_id": "note", "value": "gm" }] },
+                { "type": 18, "id": 5, "component": { "type": 3, "id": 6, "custom_id": "chain", "values": ["berachain"] } }
+            ]
+        }
+    }))
+}
+
+fn message_create() -> Result<GatewayEvent, serde_json::Error> {
+    serialized(Event::MessageCreate(Box::new(MessageCreate(message(Value::Null)?))))
+}
+
+fn message_update() -> Result<GatewayEvent, serde_json::Error> {
+    let edited = json!("2023-11-14T22:15:00.000000+00:00");
+    serialized(Event::MessageUpdate(Box::new(MessageUpdate(message(edited)?))))
+}
+
+fn message_delete() -> Result<GatewayEvent, serde_json::Error> {
+    serialized(Event::MessageDelete(serde_json::from_value::<MessageDelete>(json!({
+        "id": "666666666666666666", "channel_id": "333333333333333333", "guild_id": GUILD
+    }))?))
+}
+
+fn message_bulk_delete() -> Result<GatewayEvent, serde_json::Error> {
+    serialized(Event::MessageDeleteBulk(serde_json::from_value::<MessageDeleteBulk>(json!({
+        "ids": ["666666666666666666", "666666666666666667"], "channel_id": "333333333333333333", "guild_id": GUILD
+    }))?))
+}
+
+fn reaction_add() -> Result<GatewayEvent, serde_json::Error> {
+    let reaction: ReactionAdd = serde_json::from_value(json!({
+        "user_id": USER, "channel_id": "333333333333333333", "message_id": "666666666666666666",
+        "message_author_id": "222222222222222222", "guild_id": GUILD, "burst": false,
+        "emoji": { "id": "999999999999999999", "name": "honey", "animated": false },
+        "member": {
+            "user": user("user"), "roles": [], "nick": null,
+            "joined_at": "2023-11-14T22:13:20.000000+00:00", "deaf": false, "mute": false, "flags": 0
+        }
+    }))?;
+    serialized(Event::ReactionAdd(Box::new(reaction)))
+}
+
+fn reaction_remove_all() -> Result<GatewayEvent, serde_json::Error> {
+    serialized(Event::ReactionRemoveAll(serde_json::from_value::<ReactionRemoveAll>(json!({
+        "channel_id": "333333333333333333", "message_id": "666666666666666666", "guild_id": GUILD
+    }))?))
+}
+
+fn voice_state_update() -> Result<GatewayEvent, serde_json::Error> {
+    let state: VoiceStateUpdate = serde_json::from_value(json!({
+        "guild_id": GUILD, "channel_id": "777777777777777777", "user_id": USER,
+        "session_id": "9f8e7d6c5b4a39281706f5e4d3c2b1a0", "deaf": false, "mute": false,
+        "self_deaf": false, "self_mute": true, "self_video": false, "suppress": false,
+        "request_to_speak_timestamp": null,
+        "member": {
+            "user": user("user"), "roles": [], "nick": null,
+            "joined_at": "2023-11-14T22:13:20.000000+00:00", "deaf": false, "mute": false, "flags": 0
+        }
+    }))?;
+    serialized(Event::VoiceStateUpdate(Box::new(state)))
+}
+
+fn voice_server_update() -> Result<GatewayEvent, serde_json::Error> {
+    serialized(Event::VoiceServerUpdate(serde_json::from_value::<VoiceServerUpdate>(json!({
+        "guild_id": GUILD, "endpoint": "us-east1234.discord.media:443", "token": "0f1e2d3c4b5a6978"
+    }))?))
+}
+
+fn role_create() -> Result<GatewayEvent, serde_json::Error> {
+    serialized(Event::RoleCreate(serde_json::from_value::<RoleCreate>(json!({
+        "guild_id": GUILD,
+        "role": {
+            "id": "888888888888888888", "name": "Holder", "color": 15844367,
+            "colors": { "primary_color": 15844367, "secondary_color": null, "tertiary_color": null },
+            "hoist": true, "icon": null, "unicode_emoji": "🍯", "position": 5, "permissions": "104324673",
+            "managed": false, "mentionable": true, "flags": 0
+        }
+    }))?))
+}
+
+fn role_delete() -> Result<GatewayEvent, serde_json::Error> {
+    serialized(Event::RoleDelete(serde_json::from_value::<RoleDelete>(json!({
+        "guild_id": GUILD, "role_id": "888888888888888888"
+    }))?))
+}
+
+fn channel_create() -> Result<GatewayEvent, serde_json::Error> {
+    serialized(Event::ChannelCreate(Box::new(serde_json::from_value::<ChannelCreate>(json!({
+        "id": "333333333333333333", "type": 0, "guild_id": GUILD, "name": "holders-lounge", "position": 4,
+        "parent_id": "444444444444444444", "topic": "Verified holders only", "nsfw": false,
+        "permission_overwrites": []
+    }))?)))
+}
+
+fn thread_create() -> Result<GatewayEvent, serde_json::Error> {
+    serialized(Event::ThreadCreate(Box::new(serde_json::from_value::<ThreadCreate>(thread())?)))
+}
+
+fn thread_list_sync() -> Result<GatewayEvent, serde_json::Error> {
+    serialized(Event::ThreadListSync(serde_json::from_value::<ThreadListSync>(json!({
+        "guild_id": GUILD, "channel_ids": ["333333333333333333"], "threads": [thread()], "members": []
+    }))?))
+}
+
+/// A public thread in the sample channel
+fn thread() -> Value {
+    json!({
+        "id": "555555555555555555", "type": 11, "guild_id": GUILD, "parent_id": "333333333333333333",
+        "owner_id": USER, "name": "Mint day", "member_count": 3, "message_count": 12,
+        "thread_metadata": {
+            "archived": false, "auto_archive_duration": 1440, "locked": false,
+            "archive_timestamp": "2023-11-14T22:13:20.000000+00:00",
+            "create_timestamp": "2023-11-14T22:13:20.000000+00:00"
+        }
+    })
+}
+
+/// A guild message with one attachment
+fn message(edited_timestamp: Value) -> Result<Message, serde_json::Error> {
+    serde_json::from_value(json!({
+        "id": "666666666666666666",
+        "channel_id": "333333333333333333",
+        "guild_id": GUILD,
+        "author": user("user"),
+        "content": "",
+        "timestamp": "2023-11-14T22:13:20.000000+00:00",
+        "edited_timestamp": edited_timestamp,
+        "tts": false,
+        "mention_everyone": false,
+        "mentions": [],
+        "mention_roles": [],
+        "attachments": [{
+            "id": "777777777777777777", "filename": "clip.mp4", "content_type": "video/mp4", "size": 1048576,
+            "url": "https://cdn.discordapp.com/attachments/333333333333333333/777777777777777777/clip.mp4",
+            "proxy_url": "https://media.discordapp.net/attachments/333333333333333333/777777777777777777/clip.mp4"
+        }],
+        "embeds": [],
+        "pinned": false,
+        "type": 0
+    }))
+}
+
+fn capability_degraded() -> Result<GatewayEvent, serde_json::Error> {
+    Ok(capability_degraded_event(0, &["GUILD_MEMBERS"], &["GUILDS"]))
+}
+
+fn circuit_state() -> Result<GatewayEvent, serde_json::Error> {
+    let transition = Transition {
+        shard_id: 0,
+        from: CircuitState::Closed,
+        to: CircuitState::Open,
+        errors: 10,
+        trips: 1,
+        cooldown: Some(std::time::Duration::from_secs(30)),
+    };
+    Ok(transition.event(None))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_sample_matches_its_fixture() {
+        run().unwrap();
+        for (name, _) in SAMPLES {
+            assert!(embedded::FIXTURES.iter().any(|(fixture, _)| fixture == name), "{name} is not embedded");
+        }
+    }
+
+    #[test]
+    fn drift_is_reported_with_its_path() {
+        let (_, contents) = embedded::FIXTURES.iter().find(|(name, _)| *name == "member-join").unwrap();
+        let drifted = contents.replace("\"testuser\"", "\"renamed\"");
+
+        let err = run_against(&[("member-join", &drifted)]).unwrap_err();
+        assert!(matches!(err, GatewayError::ContractDrift { fixture: "member-join", .. }));
+        assert_eq!(
+            err.to_string(),
+            "wire contract drift in fixture member-join: /data/username: expected \"renamed\", serialized \"testuser\""
+        );
+    }
+
+    #[test]
+    fn missing_fixtures_skip_the_test() {
+        assert!(run_against(&[]).is_ok());
+    }
+}
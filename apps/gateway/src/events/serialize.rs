@@ -3,17 +3,66 @@
 //! Converts Twilight events to JSON payloads for NATS publishing.
 #![allow(dead_code)] // Scaffolded for future event routing
 
+use super::{entities, policy, versions};
+use crate::discord::audit::RemovalReason;
 use serde::{Deserialize, Serialize};
 use tracing::warn;
+use twilight_model::application::interaction::application_command::{CommandData, CommandOptionValue};
+use twilight_model::application::interaction::{
+    Interaction, InteractionContextType, InteractionData,
+};
+use twilight_model::channel::{Channel, Message};
 use twilight_model::gateway::event::Event;
+use twilight_model::gateway::{GatewayReaction, Intents};
+use twilight_model::guild::Role;
+use twilight_model::id::marker::GuildMarker;
+use twilight_model::id::Id;
 use uuid::Uuid;
 
+/// Event types in the original envelope set. Publishing anything newer is
+/// gated by the `new-event-types` feature flag.
+pub const STABLE_EVENT_TYPES: &[&str] = &[
+    "guild.join",
+    "guild.leave",
+    "guild.update",
+    "member.join",
+    "member.leave",
+    "member.update",
+    "interaction.create",
+];
+
+/// Intents whose events `serialize_event` publishes. Others can be
+/// requested, but their events are received and dropped.
+pub const SERIALIZED_INTENTS: Intents = Intents::GUILDS
+    .union(Intents::GUILD_MEMBERS)
+    .union(Intents::GUILD_MESSAGES)
+    .union(Intents::MESSAGE_CONTENT)
+    .union(Intents::GUILD_MESSAGE_REACTIONS)
+    .union(Intents::GUILD_VOICE_STATES);
+
+/// Namespace of the event IDs derived from Discord dispatches (UUIDv5)
+pub(crate) const DISPATCH_NAMESPACE: Uuid = Uuid::from_u128(0x3b0d_6f1e_8c4a_4f6e_9a51_2d7c_0e9b_4a18);
+
 /// Generic gateway event payload
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GatewayEvent {
     pub event_id: String,
     pub event_type: String,
+    /// Envelope version (`events::versions`); absent means 1
+    #[serde(default = "versions::default_version")]
+    pub schema_version: u32,
     pub shard_id: u64,
+    /// Which bot received the event, when one process runs several (`BOT_ID`, `BOTS`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bot_id: Option<String>,
+    /// Position among the shard's events on this stream, from 1, so
+    /// consumers can spot lost events (only on events from Discord)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seq: Option<u64>,
+    /// When the pool started numbering (Unix ms); `seq` starts again from 1
+    /// under a new epoch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seq_epoch: Option<u64>,
     pub timestamp: u64,
     pub guild_id: Option<String>,
     pub channel_id: Option<String>,
@@ -21,30 +70,171 @@ pub struct GatewayEvent {
     pub data: serde_json::Value,
 }
 
-/// Interaction-specific event payload
+#[cfg(test)]
+impl GatewayEvent {
+    /// An event of `event_type` on shard 0 with no IDs or data; tests fill in
+    /// what they need with struct update syntax
+    pub fn for_test(event_type: &str) -> Self {
+        Self {
+            event_id: "550e8400-e29b-41d4-a716-446655440000".to_string(),
+            event_type: event_type.to_string(),
+            schema_version: versions::CURRENT,
+            shard_id: 0,
+            bot_id: None,
+            seq: None,
+            seq_epoch: None,
+            timestamp: 1_700_000_000_000,
+            guild_id: None,
+            channel_id: None,
+            user_id: None,
+            data: serde_json::Value::Null,
+        }
+    }
+}
+
+/// Header naming a message's wire format, so consumers can pick a decoder;
+/// messages without it are JSON
+pub const CONTENT_TYPE_HEADER: &str = "Content-Type";
+
+/// How events are encoded on the wire.
+///
+/// The primary subjects use `WIRE_FORMAT`; the canary can trial another
+/// format on a share of events first (`CANARY_FORMAT`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum WireFormat {
+    /// The JSON envelope (`fixtures/*.json`)
+    #[default]
+    Json,
+    /// `proto/gateway_event.proto` in the shared schemas package
+    Protobuf,
+}
+
+impl WireFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim() {
+            "json" => Some(Self::Json),
+            "protobuf" => Some(Self::Protobuf),
+            _ => None,
+        }
+    }
+
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Protobuf => "protobuf",
+        }
+    }
+
+    /// `Content-Type` header value
+    pub const fn content_type(&self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::Protobuf => "application/x-protobuf",
+        }
+    }
+
+    /// Encode an event in this format
+    pub fn encode(&self, event: &GatewayEvent) -> Result<Vec<u8>, serde_json::Error> {
+        match self {
+            Self::Json => serde_json::to_vec(event),
+            Self::Protobuf => Ok(super::protobuf::encode(event)),
+        }
+    }
+}
+
+/// `interaction.create` data
+///
+/// The interaction_token is Discord's response token (15-min TTL), needed by
+/// the command handler to reply. NATS is internal-only, but explicit naming
+/// prevents accidental external logging.
 #[derive(Debug, Clone, Serialize)]
 pub struct InteractionEvent {
-    pub event_id: String,
-    pub shard_id: u64,
-    pub timestamp: u64,
     pub interaction_id: String,
+    /// Discord's interaction type name (`ApplicationCommand`, `MessageComponent`, ...)
+    pub interaction_type: &'static str,
     pub interaction_token: String,
-    pub guild_id: Option<String>,
-    pub channel_id: String,
-    pub user_id: String,
+    pub application_id: String,
+    /// The invoking user's bot flag
+    pub is_bot: Option<bool>,
+    /// Commands and autocomplete
     pub command_name: Option<String>,
+    /// Subcommand invoked, prefixed by its group (`award`, `badges award`)
     pub subcommand: Option<String>,
-    pub data: serde_json::Value,
+    /// Components and modals
+    pub custom_id: Option<String>,
+    /// Component type name (`Button`, `SelectMenu`)
+    pub component_type: Option<&'static str>,
+    /// Select menu choices
+    pub values: Option<Vec<String>>,
+    /// Modal inputs (`entities::modal_fields`)
+    pub fields: Option<serde_json::Value>,
+    /// Where the interaction came from (`guild`, `bot_dm`, `private_channel`)
+    pub context: Option<&'static str>,
+    /// Which installation authorized it
+    pub authorizing_integration_owners: serde_json::Value,
+    /// Typed command options (`entities::options`)
+    pub options: Option<serde_json::Value>,
+    /// Entities options and select menus reference (`entities::resolved`)
+    pub resolved: Option<serde_json::Value>,
+    /// The invoking user's language
+    pub locale: Option<String>,
+    /// The guild's preferred language (absent outside guilds)
+    pub guild_locale: Option<String>,
+    /// The gateway already sent a deferred response (`INTERACTION_DEFER_COMMANDS`);
+    /// workers edit the original response instead of creating one
+    pub deferred: bool,
+}
+
+impl InteractionEvent {
+    pub fn new(interaction: &Interaction) -> Self {
+        let command = command_data(interaction);
+        let (custom_id, component_type, values, fields, resolved) = match interaction.data.as_ref() {
+            Some(InteractionData::MessageComponent(data)) => (
+                Some(data.custom_id.clone()),
+                Some(data.component_type.name()),
+                Some(data.values.clone()),
+                None,
+                data.resolved.as_ref(),
+            ),
+            Some(InteractionData::ModalSubmit(data)) => (
+                Some(data.custom_id.clone()),
+                None,
+                None,
+                Some(entities::modal_fields(&data.components)),
+                data.resolved.as_ref(),
+            ),
+            _ => (None, None, None, None, command.and_then(|c| c.resolved.as_ref())),
+        };
+
+        Self {
+            interaction_id: interaction.id.to_string(),
+            interaction_type: interaction.kind.kind(),
+            interaction_token: interaction.token.clone(),
+            application_id: interaction.application_id.to_string(),
+            is_bot: interaction.author().map(|user| user.bot),
+            command_name: command.map(|c| c.name.clone()),
+            subcommand: command.and_then(subcommand),
+            custom_id,
+            component_type,
+            values,
+            fields,
+            context: interaction.context.map(context_name),
+            authorizing_integration_owners: serde_json::to_value(&interaction.authorizing_integration_owners)
+                .unwrap_or(serde_json::Value::Null),
+            options: command.map(|c| entities::options(&c.options)),
+            resolved: resolved.map(entities::resolved),
+            locale: interaction.locale.clone(),
+            guild_locale: interaction.guild_locale.clone(),
+            deferred: false,
+        }
+    }
 }
 
 /// Serialize a Twilight event to a GatewayEvent payload
 ///
 /// Returns None for events we don't need to forward (e.g., heartbeats)
 pub fn serialize_event(event: &Event, shard_id: u64) -> Option<GatewayEvent> {
-    let timestamp = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_millis() as u64;
+    let timestamp = now_millis();
 
     match event {
         Event::GuildCreate(guild) => {
@@ -57,7 +247,11 @@ pub fn serialize_event(event: &Event, shard_id: u64) -> Option<GatewayEvent> {
             Some(GatewayEvent {
                 event_id: Uuid::new_v4().to_string(),
                 event_type: "guild.join".to_string(),
+                schema_version: versions::CURRENT,
                 shard_id,
+                bot_id: None,
+                seq: None,
+                seq_epoch: None,
                 timestamp,
                 guild_id: Some(guild.id().to_string()),
                 channel_id: None,
@@ -69,7 +263,11 @@ pub fn serialize_event(event: &Event, shard_id: u64) -> Option<GatewayEvent> {
         Event::GuildDelete(guild) => Some(GatewayEvent {
             event_id: Uuid::new_v4().to_string(),
             event_type: "guild.leave".to_string(),
+            schema_version: versions::CURRENT,
             shard_id,
+            bot_id: None,
+            seq: None,
+            seq_epoch: None,
             timestamp,
             guild_id: Some(guild.id.to_string()),
             channel_id: None,
@@ -82,32 +280,45 @@ pub fn serialize_event(event: &Event, shard_id: u64) -> Option<GatewayEvent> {
         Event::MemberAdd(member) => Some(GatewayEvent {
             event_id: Uuid::new_v4().to_string(),
             event_type: "member.join".to_string(),
+            schema_version: versions::CURRENT,
             shard_id,
+            bot_id: None,
+            seq: None,
+            seq_epoch: None,
             timestamp,
             guild_id: Some(member.guild_id.to_string()),
             channel_id: None,
             user_id: Some(member.user.id.to_string()),
             data: serde_json::json!({
                 "username": member.user.name,
-                "discriminator": member.user.discriminator,
+                "discriminator": policy::discriminator(&member.user),
+                "is_bot": member.user.bot,
             }),
         }),
 
         Event::MemberRemove(member) => Some(GatewayEvent {
             event_id: Uuid::new_v4().to_string(),
             event_type: "member.leave".to_string(),
+            schema_version: versions::CURRENT,
             shard_id,
+            bot_id: None,
+            seq: None,
+            seq_epoch: None,
             timestamp,
             guild_id: Some(member.guild_id.to_string()),
             channel_id: None,
             user_id: Some(member.user.id.to_string()),
-            data: serde_json::Value::Null,
+            data: serde_json::json!({ "is_bot": member.user.bot }),
         }),
 
         Event::MemberUpdate(member) => Some(GatewayEvent {
             event_id: Uuid::new_v4().to_string(),
             event_type: "member.update".to_string(),
+            schema_version: versions::CURRENT,
             shard_id,
+            bot_id: None,
+            seq: None,
+            seq_epoch: None,
             timestamp,
             guild_id: Some(member.guild_id.to_string()),
             channel_id: None,
@@ -115,30 +326,234 @@ pub fn serialize_event(event: &Event, shard_id: u64) -> Option<GatewayEvent> {
             data: serde_json::json!({
                 "roles": member.roles.iter().map(|r| r.to_string()).collect::<Vec<_>>(),
                 "nick": member.nick,
+                "is_bot": member.user.bot,
             }),
         }),
 
+        // guild_id is null for DMs and user-installed apps; the data's
+        // `context` and `authorizing_integration_owners` say where the
+        // interaction came from
         Event::InteractionCreate(interaction) => {
-            // Interactions are serialized as generic events.
-            // The interaction_token is Discord's response token (15-min TTL),
-            // needed by the command handler to reply. NATS is internal-only,
-            // but explicit naming prevents accidental external logging.
+            let data = serde_json::to_value(InteractionEvent::new(interaction)).unwrap_or_else(|e| {
+                warn!(shard_id, error = %e, "Failed to serialize interaction data");
+                serde_json::Value::Null
+            });
             Some(GatewayEvent {
                 event_id: Uuid::new_v4().to_string(),
                 event_type: "interaction.create".to_string(),
+                schema_version: versions::CURRENT,
                 shard_id,
+                bot_id: None,
+                seq: None,
+                seq_epoch: None,
                 timestamp,
                 guild_id: interaction.guild_id.map(|id| id.to_string()),
                 channel_id: interaction.channel.as_ref().map(|c| c.id.to_string()),
                 user_id: interaction.author_id().map(|id| id.to_string()),
+                data,
+            })
+        }
+
+        // Message events are only received with FORWARD_MESSAGES. Workers
+        // get the text, author and attachment metadata; embeds, components
+        // and the rest of the raw message stay behind.
+        Event::MessageCreate(message) => Some(GatewayEvent {
+            event_id: Uuid::new_v4().to_string(),
+            event_type: "message.create".to_string(),
+            schema_version: versions::CURRENT,
+            shard_id,
+            bot_id: None,
+            seq: None,
+            seq_epoch: None,
+            timestamp,
+            guild_id: message.guild_id.map(|id| id.to_string()),
+            channel_id: Some(message.channel_id.to_string()),
+            user_id: Some(message.author.id.to_string()),
+            data: message_data(message),
+        }),
+
+        Event::MessageUpdate(message) => {
+            let mut data = message_data(message);
+            data["edited_at"] = serde_json::json!(message.edited_timestamp.map(policy::timestamp));
+            Some(GatewayEvent {
+                event_id: Uuid::new_v4().to_string(),
+                event_type: "message.update".to_string(),
+                schema_version: versions::CURRENT,
+                shard_id,
+                bot_id: None,
+                seq: None,
+                seq_epoch: None,
+                timestamp,
+                guild_id: message.guild_id.map(|id| id.to_string()),
+                channel_id: Some(message.channel_id.to_string()),
+                user_id: Some(message.author.id.to_string()),
+                data,
+            })
+        }
+
+        // Deletes only carry IDs; Discord doesn't say who deleted the message
+        Event::MessageDelete(delete) => Some(GatewayEvent {
+            event_id: Uuid::new_v4().to_string(),
+            event_type: "message.delete".to_string(),
+            schema_version: versions::CURRENT,
+            shard_id,
+            bot_id: None,
+            seq: None,
+            seq_epoch: None,
+            timestamp,
+            guild_id: delete.guild_id.map(|id| id.to_string()),
+            channel_id: Some(delete.channel_id.to_string()),
+            user_id: None,
+            data: serde_json::json!({ "message_id": delete.id.to_string() }),
+        }),
+
+        Event::MessageDeleteBulk(delete) => Some(GatewayEvent {
+            event_id: Uuid::new_v4().to_string(),
+            event_type: "message.bulk_delete".to_string(),
+            schema_version: versions::CURRENT,
+            shard_id,
+            bot_id: None,
+            seq: None,
+            seq_epoch: None,
+            timestamp,
+            guild_id: delete.guild_id.map(|id| id.to_string()),
+            channel_id: Some(delete.channel_id.to_string()),
+            user_id: None,
+            data: serde_json::json!({
+                "message_ids": delete.ids.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            }),
+        }),
+
+        // Reaction events are only received with FORWARD_REACTIONS
+        Event::ReactionAdd(reaction) => Some(reaction_event("reaction.add", &reaction.0, shard_id, timestamp)),
+        Event::ReactionRemove(reaction) => Some(reaction_event("reaction.remove", &reaction.0, shard_id, timestamp)),
+        Event::ReactionRemoveAll(removed) => Some(GatewayEvent {
+            event_id: Uuid::new_v4().to_string(),
+            event_type: "reaction.remove_all".to_string(),
+            schema_version: versions::CURRENT,
+            shard_id,
+            bot_id: None,
+            seq: None,
+            seq_epoch: None,
+            timestamp,
+            guild_id: removed.guild_id.map(|id| id.to_string()),
+            channel_id: Some(removed.channel_id.to_string()),
+            user_id: None,
+            data: serde_json::json!({ "message_id": removed.message_id.to_string() }),
+        }),
+
+        // Voice events are only received with FORWARD_VOICE. The envelope's
+        // channel_id is the voice channel, null once the user has left.
+        Event::VoiceStateUpdate(update) => {
+            let state = &update.0;
+            Some(GatewayEvent {
+                event_id: Uuid::new_v4().to_string(),
+                event_type: "voice.state_update".to_string(),
+                schema_version: versions::CURRENT,
+                shard_id,
+                bot_id: None,
+                seq: None,
+                seq_epoch: None,
+                timestamp,
+                guild_id: state.guild_id.map(|id| id.to_string()),
+                channel_id: state.channel_id.map(|id| id.to_string()),
+                user_id: Some(state.user_id.to_string()),
                 data: serde_json::json!({
-                    "interaction_id": interaction.id.to_string(),
-                    "interaction_type": format!("{:?}", interaction.kind),
-                    "interaction_token": interaction.token,
+                    "session_id": state.session_id,
+                    "deaf": state.deaf,
+                    "mute": state.mute,
+                    "self_deaf": state.self_deaf,
+                    "self_mute": state.self_mute,
+                    "self_stream": state.self_stream,
+                    "self_video": state.self_video,
+                    "suppress": state.suppress,
+                    "request_to_speak_at": state.request_to_speak_timestamp.map(policy::timestamp),
+                    "is_bot": state.member.as_ref().map(|member| member.user.bot),
                 }),
             })
         }
 
+        // Carries the voice connection token, only useful together with the
+        // bot's own voice.state_update session_id
+        Event::VoiceServerUpdate(server) => Some(GatewayEvent {
+            event_id: Uuid::new_v4().to_string(),
+            event_type: "voice.server_update".to_string(),
+            schema_version: versions::CURRENT,
+            shard_id,
+            bot_id: None,
+            seq: None,
+            seq_epoch: None,
+            timestamp,
+            guild_id: Some(server.guild_id.to_string()),
+            channel_id: None,
+            user_id: None,
+            data: serde_json::json!({ "endpoint": server.endpoint, "token": server.token }),
+        }),
+
+        // Role events (GUILDS intent) carry the whole role, so workers can
+        // update cached role mappings without a sync
+        Event::RoleCreate(role) => Some(role_event("role.create", role.guild_id, &role.role, shard_id, timestamp)),
+        Event::RoleUpdate(role) => Some(role_event("role.update", role.guild_id, &role.role, shard_id, timestamp)),
+        Event::RoleDelete(role) => Some(GatewayEvent {
+            event_id: Uuid::new_v4().to_string(),
+            event_type: "role.delete".to_string(),
+            schema_version: versions::CURRENT,
+            shard_id,
+            bot_id: None,
+            seq: None,
+            seq_epoch: None,
+            timestamp,
+            guild_id: Some(role.guild_id.to_string()),
+            channel_id: None,
+            user_id: None,
+            data: serde_json::json!({ "role_id": role.role_id.to_string() }),
+        }),
+
+        // Channel and thread lifecycle (GUILDS intent). The envelope's
+        // channel_id is the channel or thread itself.
+        Event::ChannelCreate(channel) => Some(channel_event("channel.create", &channel.0, shard_id, timestamp)),
+        Event::ChannelUpdate(channel) => Some(channel_event("channel.update", &channel.0, shard_id, timestamp)),
+        Event::ChannelDelete(channel) => Some(channel_event("channel.delete", &channel.0, shard_id, timestamp)),
+        Event::ThreadCreate(thread) => Some(thread_event("thread.create", &thread.0, shard_id, timestamp)),
+        Event::ThreadUpdate(thread) => Some(thread_event("thread.update", &thread.0, shard_id, timestamp)),
+        Event::ThreadDelete(thread) => Some(GatewayEvent {
+            event_id: Uuid::new_v4().to_string(),
+            event_type: "thread.delete".to_string(),
+            schema_version: versions::CURRENT,
+            shard_id,
+            bot_id: None,
+            seq: None,
+            seq_epoch: None,
+            timestamp,
+            guild_id: Some(thread.guild_id.to_string()),
+            channel_id: Some(thread.id.to_string()),
+            user_id: None,
+            data: serde_json::json!({
+                "type": thread.kind.name(),
+                "parent_id": thread.parent_id.to_string(),
+            }),
+        }),
+
+        // Sent when the bot gains access to channels: the active threads in
+        // `channel_ids`, or in the whole guild when that list is empty
+        Event::ThreadListSync(sync) => Some(GatewayEvent {
+            event_id: Uuid::new_v4().to_string(),
+            event_type: "thread.list_sync".to_string(),
+            schema_version: versions::CURRENT,
+            shard_id,
+            bot_id: None,
+            seq: None,
+            seq_epoch: None,
+            timestamp,
+            guild_id: Some(sync.guild_id.to_string()),
+            channel_id: None,
+            user_id: None,
+            data: serde_json::json!({
+                "channel_ids": sync.channel_ids.iter().map(ToString::to_string).collect::<Vec<_>>(),
+                "threads": sync.threads.iter().map(entities::thread).collect::<Vec<_>>(),
+            }),
+        }),
+
         // Events we don't forward
         Event::GatewayHeartbeat
         | Event::GatewayHeartbeatAck
@@ -154,6 +569,188 @@ pub fn serialize_event(event: &Event, shard_id: u64) -> Option<GatewayEvent> {
     }
 }
 
+/// Event ID that is the same each time Discord delivers the dispatch, so
+/// JetStream drops a second publish of it (the ID is the `Nats-Msg-Id`),
+/// such as a dispatch Discord replays after a resume.
+///
+/// Interactions are keyed by their own ID; other events by the session ID
+/// and sequence number that delivered them, which a resume replay keeps.
+/// None without a session, leaving the random ID.
+pub fn dispatch_event_id(event: &Event, event_type: &str, session: Option<(&str, u64)>) -> Option<String> {
+    let key = match event {
+        Event::InteractionCreate(interaction) => format!("interaction:{}", interaction.id),
+        _ => {
+            let (session_id, sequence) = session?;
+            format!("{session_id}:{sequence}:{event_type}")
+        }
+    };
+    Some(Uuid::new_v5(&DISPATCH_NAMESPACE, key.as_bytes()).to_string())
+}
+
+/// `reaction.add` / `reaction.remove`; `is_bot` is only known on add, where
+/// Discord includes the reacting member
+fn reaction_event(event_type: &str, reaction: &GatewayReaction, shard_id: u64, timestamp: u64) -> GatewayEvent {
+    GatewayEvent {
+        event_id: Uuid::new_v4().to_string(),
+        event_type: event_type.to_string(),
+        schema_version: versions::CURRENT,
+        shard_id,
+        bot_id: None,
+        seq: None,
+        seq_epoch: None,
+        timestamp,
+        guild_id: reaction.guild_id.map(|id| id.to_string()),
+        channel_id: Some(reaction.channel_id.to_string()),
+        user_id: Some(reaction.user_id.to_string()),
+        data: serde_json::json!({
+            "message_id": reaction.message_id.to_string(),
+            "message_author_id": reaction.message_author_id.map(|id| id.to_string()),
+            "emoji": entities::emoji(&reaction.emoji),
+            "burst": reaction.burst,
+            "is_bot": reaction.member.as_ref().map(|member| member.user.bot),
+        }),
+    }
+}
+
+/// `role.create` / `role.update`
+fn role_event(event_type: &str, guild_id: Id<GuildMarker>, role: &Role, shard_id: u64, timestamp: u64) -> GatewayEvent {
+    GatewayEvent {
+        event_id: Uuid::new_v4().to_string(),
+        event_type: event_type.to_string(),
+        schema_version: versions::CURRENT,
+        shard_id,
+        bot_id: None,
+        seq: None,
+        seq_epoch: None,
+        timestamp,
+        guild_id: Some(guild_id.to_string()),
+        channel_id: None,
+        user_id: None,
+        data: entities::guild_role(role),
+    }
+}
+
+/// `channel.create` / `channel.update` / `channel.delete`
+fn channel_event(event_type: &str, channel: &Channel, shard_id: u64, timestamp: u64) -> GatewayEvent {
+    GatewayEvent {
+        event_id: Uuid::new_v4().to_string(),
+        event_type: event_type.to_string(),
+        schema_version: versions::CURRENT,
+        shard_id,
+        bot_id: None,
+        seq: None,
+        seq_epoch: None,
+        timestamp,
+        guild_id: channel.guild_id.map(|id| id.to_string()),
+        channel_id: Some(channel.id.to_string()),
+        user_id: None,
+        data: entities::guild_channel(channel),
+    }
+}
+
+/// `thread.create` / `thread.update`; the envelope's user_id is the thread's
+/// creator
+fn thread_event(event_type: &str, thread: &Channel, shard_id: u64, timestamp: u64) -> GatewayEvent {
+    GatewayEvent {
+        event_id: Uuid::new_v4().to_string(),
+        event_type: event_type.to_string(),
+        schema_version: versions::CURRENT,
+        shard_id,
+        bot_id: None,
+        seq: None,
+        seq_epoch: None,
+        timestamp,
+        guild_id: thread.guild_id.map(|id| id.to_string()),
+        channel_id: Some(thread.id.to_string()),
+        user_id: thread.owner_id.map(|id| id.to_string()),
+        data: entities::thread(thread),
+    }
+}
+
+/// `message.create` data, also the base of `message.update`
+fn message_data(message: &Message) -> serde_json::Value {
+    serde_json::json!({
+        "message_id": message.id.to_string(),
+        "content": message.content,
+        "author": entities::user(&message.author),
+        "attachments": message.attachments.iter().map(entities::attachment).collect::<Vec<_>>(),
+    })
+}
+
+/// Add the classified removal reason to `member.leave` data
+/// (`MEMBER_REMOVAL_AUDIT`); without it the data has no `removal_reason`
+pub fn set_removal_reason(data: &mut serde_json::Value, reason: RemovalReason) {
+    data["removal_reason"] = reason.as_str().into();
+}
+
+/// Build a `gateway.capability_degraded` event
+///
+/// Emitted when Discord closes a shard with 4014 (disallowed intents) and the
+/// shard reconnects without its privileged intents.
+pub fn capability_degraded_event(
+    shard_id: u64,
+    missing_intents: &[&str],
+    active_intents: &[&str],
+) -> GatewayEvent {
+    GatewayEvent {
+        event_id: Uuid::new_v4().to_string(),
+        event_type: "gateway.capability_degraded".to_string(),
+        schema_version: versions::CURRENT,
+        shard_id,
+        bot_id: None,
+        seq: None,
+        seq_epoch: None,
+        timestamp: now_millis(),
+        guild_id: None,
+        channel_id: None,
+        user_id: None,
+        data: serde_json::json!({
+            "close_code": 4014,
+            "missing_intents": missing_intents,
+            "active_intents": active_intents,
+        }),
+    }
+}
+
+/// Application command data, if the interaction is a command (or autocomplete)
+fn command_data(interaction: &Interaction) -> Option<&CommandData> {
+    match interaction.data.as_ref()? {
+        InteractionData::ApplicationCommand(data) => Some(data.as_ref()),
+        _ => None,
+    }
+}
+
+/// The subcommand a command invoked, prefixed by its group
+fn subcommand(command: &CommandData) -> Option<String> {
+    let option = command.options.first()?;
+    match &option.value {
+        CommandOptionValue::SubCommand(_) => Some(option.name.clone()),
+        CommandOptionValue::SubCommandGroup(nested) => {
+            let sub = nested.first().map_or("", |sub| sub.name.as_str());
+            Some(format!("{} {sub}", option.name).trim_end().to_string())
+        }
+        _ => None,
+    }
+}
+
+/// Wire name for an interaction context
+fn context_name(context: InteractionContextType) -> &'static str {
+    match context {
+        InteractionContextType::Guild => "guild",
+        InteractionContextType::BotDm => "bot_dm",
+        InteractionContextType::PrivateChannel => "private_channel",
+        _ => "unknown",
+    }
+}
+
+/// Current Unix time in milliseconds
+pub(crate) fn now_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -164,6 +761,398 @@ mod tests {
         assert!(serialize_event(&event, 0).is_none());
     }
 
+    #[test]
+    fn test_dm_interaction_carries_context_and_owners() {
+        use twilight_model::gateway::payload::incoming::InteractionCreate;
+
+        let interaction: Interaction = serde_json::from_value(serde_json::json!({
+            "application_id": "100000000000000001",
+            "authorizing_integration_owners": { "1": "987654321098765432" },
+            "channel": { "id": "333333333333333333", "type": 1 },
+            "context": 1,
+            "data": { "id": "555555555555555555", "name": "verify", "type": 1 },
+            "entitlements": [],
+            "id": "444444444444444444",
+            "locale": "pt-BR",
+            "type": 2,
+            "token": "aW50ZXJhY3Rpb25fdG9rZW5fZXhhbXBsZQ",
+            "user": {
+                "id": "987654321098765432",
+                "username": "user",
+                "discriminator": "0",
+                "avatar": null
+            }
+        }))
+        .expect("valid DM interaction");
+
+        let event = Event::InteractionCreate(Box::new(InteractionCreate(interaction)));
+        let payload = serialize_event(&event, 0).expect("interactions are forwarded");
+
+        assert!(payload.guild_id.is_none());
+        assert_eq!(payload.user_id.as_deref(), Some("987654321098765432"));
+        assert_eq!(payload.data["command_name"], "verify");
+        assert_eq!(payload.data["context"], "bot_dm");
+        assert_eq!(payload.data["authorizing_integration_owners"]["1"], "987654321098765432");
+        assert_eq!(payload.data["locale"], "pt-BR");
+        assert!(payload.data["guild_locale"].is_null());
+        assert_eq!(payload.data["application_id"], "100000000000000001");
+        assert_eq!(payload.data["is_bot"], false);
+    }
+
+    /// A guild slash command with options and resolved entities
+    fn command_interaction() -> Event {
+        use twilight_model::gateway::payload::incoming::InteractionCreate;
+
+        let interaction: Interaction = serde_json::from_value(serde_json::json!({
+            "application_id": "100000000000000001",
+            "authorizing_integration_owners": { "0": "123456789012345678" },
+            "channel": { "id": "333333333333333333", "type": 0 },
+            "data": {
+                "id": "555555555555555555",
+                "name": "admin-badge",
+                "type": 1,
+                "options": [{
+                    "name": "award",
+                    "type": 1,
+                    "options": [
+                        { "name": "member", "type": 6, "value": "222222222222222222" },
+                        { "name": "tier", "type": 4, "value": 3 },
+                        { "name": "proof", "type": 11, "value": "777777777777777777" }
+                    ]
+                }],
+                "resolved": {
+                    "users": { "222222222222222222": {
+                        "id": "222222222222222222", "username": "holder", "discriminator": "0",
+                        "avatar": null, "global_name": "Holder"
+                    } },
+                    "members": { "222222222222222222": {
+                        "roles": ["888888888888888888"], "joined_at": "2024-01-01T00:00:00.000000+00:00",
+                        "nick": null, "permissions": "1024", "flags": 0, "pending": false
+                    } },
+                    "attachments": { "777777777777777777": {
+                        "id": "777777777777777777", "filename": "proof.png", "content_type": "image/png",
+                        "size": 2048, "url": "https://cdn.discordapp.com/proof.png",
+                        "proxy_url": "https://media.discordapp.net/proof.png"
+                    } }
+                }
+            },
+            "entitlements": [],
+            "guild_id": "123456789012345678",
+            "id": "444444444444444444",
+            "type": 2,
+            "token": "aW50ZXJhY3Rpb25fdG9rZW5fZXhhbXBsZQ",
+            "member": {
+                "user": { "id": "987654321098765432", "username": "admin", "discriminator": "0", "avatar": null },
+                "roles": [], "joined_at": "2023-01-01T00:00:00.000000+00:00", "deaf": false, "mute": false,
+                "flags": 0, "permissions": "8"
+            }
+        }))
+        .expect("valid command interaction");
+
+        Event::InteractionCreate(Box::new(InteractionCreate(interaction)))
+    }
+
+    #[test]
+    fn test_command_options_and_resolved_entities() {
+        let data = serialize_event(&command_interaction(), 0).expect("interactions are forwarded").data;
+
+        assert_eq!(data["subcommand"], "award");
+        let award = &data["options"][0];
+        assert_eq!(award["type"], "SubCommand");
+        assert_eq!(award["options"][0], serde_json::json!({ "name": "member", "type": "User", "value": "222222222222222222" }));
+        assert_eq!(award["options"][1]["value"], 3);
+
+        let resolved = &data["resolved"];
+        assert_eq!(resolved["users"]["222222222222222222"]["global_name"], "Holder");
+        assert_eq!(resolved["members"]["222222222222222222"]["roles"][0], "888888888888888888");
+        assert_eq!(resolved["members"]["222222222222222222"]["permissions"], "1024");
+        assert_eq!(resolved["members"]["222222222222222222"]["joined_at"], 1_704_067_200_000_u64);
+        assert_eq!(resolved["attachments"]["777777777777777777"]["content_type"], "image/png");
+        assert_eq!(resolved["roles"], serde_json::json!({}));
+    }
+
+    #[test]
+    fn test_subcommand_names_its_group() {
+        let command: CommandData = serde_json::from_value(serde_json::json!({
+            "id": "555555555555555555",
+            "name": "admin",
+            "type": 1,
+            "options": [{ "name": "badges", "type": 2, "options": [{ "name": "award", "type": 1, "options": [] }] }]
+        }))
+        .expect("valid command data");
+        assert_eq!(subcommand(&command).as_deref(), Some("badges award"));
+    }
+
+    /// A guild message with one attachment
+    fn message_with_attachment() -> Event {
+        use twilight_model::channel::Message;
+        use twilight_model::gateway::payload::incoming::MessageCreate;
+
+        let message: Message = serde_json::from_value(serde_json::json!({
+            "id": "666666666666666666",
+            "channel_id": "333333333333333333",
+            "guild_id": "123456789012345678",
+            "author": { "id": "987654321098765432", "username": "user", "discriminator": "0", "avatar": null },
+            "content": "",
+            "timestamp": "2024-01-01T00:00:00.000000+00:00",
+            "edited_timestamp": null,
+            "tts": false,
+            "mention_everyone": false,
+            "mentions": [],
+            "mention_roles": [],
+            "attachments": [{
+                "id": "777777777777777777", "filename": "clip.mp4", "content_type": "video/mp4",
+                "size": 1048576, "url": "https://cdn.discordapp.com/attachments/clip.mp4",
+                "proxy_url": "https://media.discordapp.net/attachments/clip.mp4"
+            }],
+            "embeds": [],
+            "pinned": false,
+            "type": 0
+        }))
+        .expect("valid message");
+
+        Event::MessageCreate(Box::new(MessageCreate(message)))
+    }
+
+    #[test]
+    fn test_message_attachments() {
+        let payload = serialize_event(&message_with_attachment(), 0).expect("messages are forwarded");
+
+        assert_eq!(payload.event_type, "message.create");
+        assert_eq!(payload.channel_id.as_deref(), Some("333333333333333333"));
+        assert_eq!(payload.data["author"]["bot"], false);
+        assert_eq!(
+            payload.data["attachments"][0],
+            serde_json::json!({
+                "id": "777777777777777777",
+                "filename": "clip.mp4",
+                "content_type": "video/mp4",
+                "size": 1048576,
+                "url": "https://cdn.discordapp.com/attachments/clip.mp4",
+            })
+        );
+        assert!(payload.data.get("embeds").is_none());
+    }
+
+    #[test]
+    fn test_message_update_and_deletes() {
+        use twilight_model::gateway::payload::incoming::{MessageDelete, MessageDeleteBulk, MessageUpdate};
+
+        let Event::MessageCreate(message) = message_with_attachment() else { unreachable!() };
+        let mut edited = message.0;
+        edited.edited_timestamp = Some(twilight_model::util::Timestamp::from_secs(1_704_067_260).unwrap());
+        let payload = serialize_event(&Event::MessageUpdate(Box::new(MessageUpdate(edited))), 0).unwrap();
+        assert_eq!(payload.event_type, "message.update");
+        assert_eq!(payload.data["edited_at"], 1_704_067_260_000_u64);
+        assert_eq!(payload.data["attachments"][0]["filename"], "clip.mp4");
+
+        let delete: MessageDelete = serde_json::from_value(serde_json::json!({
+            "id": "666666666666666666", "channel_id": "333333333333333333", "guild_id": "123456789012345678"
+        }))
+        .unwrap();
+        let payload = serialize_event(&Event::MessageDelete(delete), 0).unwrap();
+        assert_eq!(payload.event_type, "message.delete");
+        assert_eq!(payload.data, serde_json::json!({ "message_id": "666666666666666666" }));
+        assert!(payload.user_id.is_none());
+
+        let bulk: MessageDeleteBulk = serde_json::from_value(serde_json::json!({
+            "ids": ["1", "2"], "channel_id": "333333333333333333"
+        }))
+        .unwrap();
+        let payload = serialize_event(&Event::MessageDeleteBulk(bulk), 0).unwrap();
+        assert_eq!(payload.event_type, "message.bulk_delete");
+        assert_eq!(payload.data["message_ids"], serde_json::json!(["1", "2"]));
+        assert!(payload.guild_id.is_none());
+    }
+
+    #[test]
+    fn test_reaction_with_unicode_emoji() {
+        use twilight_model::gateway::payload::incoming::ReactionRemove;
+
+        let reaction: ReactionRemove = serde_json::from_value(serde_json::json!({
+            "user_id": "987654321098765432", "channel_id": "333333333333333333",
+            "message_id": "666666666666666666", "guild_id": "123456789012345678",
+            "burst": true, "emoji": { "id": null, "name": "🍯" }
+        }))
+        .expect("valid reaction");
+        let payload = serialize_event(&Event::ReactionRemove(Box::new(reaction)), 0).expect("reactions are forwarded");
+
+        assert_eq!(payload.event_type, "reaction.remove");
+        assert_eq!(payload.user_id.as_deref(), Some("987654321098765432"));
+        assert_eq!(payload.data["emoji"], serde_json::json!({ "id": null, "name": "🍯", "animated": false }));
+        assert_eq!(payload.data["burst"], true);
+        // Discord only sends the member on add
+        assert_eq!(payload.data["is_bot"], serde_json::Value::Null);
+        assert_eq!(payload.data["message_author_id"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn test_voice_state_after_leaving() {
+        use twilight_model::gateway::payload::incoming::VoiceStateUpdate;
+
+        let state: VoiceStateUpdate = serde_json::from_value(serde_json::json!({
+            "guild_id": "123456789012345678", "channel_id": null, "user_id": "987654321098765432",
+            "session_id": "9f8e7d6c5b4a39281706f5e4d3c2b1a0", "deaf": false, "mute": false,
+            "self_deaf": false, "self_mute": false, "self_video": false, "suppress": true,
+            "request_to_speak_timestamp": "2023-11-14T22:13:20.000000+00:00"
+        }))
+        .expect("valid voice state");
+        let payload = serialize_event(&Event::VoiceStateUpdate(Box::new(state)), 0).expect("voice states are forwarded");
+
+        assert_eq!(payload.event_type, "voice.state_update");
+        assert_eq!(payload.channel_id, None);
+        assert_eq!(payload.data["request_to_speak_at"], 1_700_000_000_000_u64);
+        assert_eq!(payload.data["is_bot"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn test_managed_role_carries_tags() {
+        use twilight_model::gateway::payload::incoming::RoleUpdate;
+
+        let role: RoleUpdate = serde_json::from_value(serde_json::json!({
+            "guild_id": "123456789012345678",
+            "role": {
+                "id": "888888888888888888", "name": "Collab Bot", "color": 0,
+                "colors": { "primary_color": 0, "secondary_color": null, "tertiary_color": null },
+                "hoist": false, "position": 12, "permissions": "268435456", "managed": true,
+                "mentionable": false, "flags": 0,
+                "tags": { "bot_id": "222222222222222222" }
+            }
+        }))
+        .expect("valid role update");
+        let payload = serialize_event(&Event::RoleUpdate(role), 0).expect("roles are forwarded");
+
+        assert_eq!(payload.event_type, "role.update");
+        assert_eq!(payload.data["managed"], true);
+        assert_eq!(payload.data["permissions"], "268435456");
+        assert_eq!(payload.data["tags"]["bot_id"], "222222222222222222");
+        assert_eq!(payload.data["tags"]["premium_subscriber"], false);
+        assert_eq!(policy::violations(&serde_json::to_value(&payload).unwrap()), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_thread_delete_and_uncategorized_channel() {
+        use twilight_model::gateway::payload::incoming::{ChannelDelete, ThreadDelete};
+
+        let thread: ThreadDelete = serde_json::from_value(serde_json::json!({
+            "id": "555555555555555555", "guild_id": "123456789012345678",
+            "parent_id": "333333333333333333", "type": 12
+        }))
+        .expect("valid thread delete");
+        let payload = serialize_event(&Event::ThreadDelete(thread), 0).expect("thread deletes are forwarded");
+        assert_eq!(payload.event_type, "thread.delete");
+        assert_eq!(payload.channel_id.as_deref(), Some("555555555555555555"));
+        assert_eq!(payload.data, serde_json::json!({ "type": "PrivateThread", "parent_id": "333333333333333333" }));
+
+        let channel: ChannelDelete = serde_json::from_value(serde_json::json!({
+            "id": "333333333333333333", "type": 2, "guild_id": "123456789012345678", "name": "Stage",
+            "position": 0, "permission_overwrites": []
+        }))
+        .expect("valid channel delete");
+        let payload = serialize_event(&Event::ChannelDelete(Box::new(channel)), 0).expect("channel deletes are forwarded");
+        assert_eq!(payload.event_type, "channel.delete");
+        assert_eq!(payload.data["type"], "GuildVoice");
+        assert_eq!(payload.data["parent_id"], serde_json::Value::Null);
+        assert_eq!(payload.data["topic"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn test_dispatch_event_ids_are_deterministic() {
+        let message = message_with_attachment();
+        let id = |session| dispatch_event_id(&message, "message.create", session);
+
+        // A resume replays the dispatch with its session and sequence
+        assert_eq!(id(Some(("abc", 42))), id(Some(("abc", 42))));
+        assert_ne!(id(Some(("abc", 42))), id(Some(("abc", 43))));
+        assert_ne!(id(Some(("abc", 42))), id(Some(("def", 42))));
+        assert_eq!(id(None), None);
+        assert!(Uuid::parse_str(&id(Some(("abc", 42))).unwrap()).is_ok());
+
+        // Interactions are keyed by their own ID
+        let interaction = command_interaction();
+        assert_eq!(
+            dispatch_event_id(&interaction, "interaction.create", None),
+            dispatch_event_id(&interaction, "interaction.create", Some(("abc", 7)))
+        );
+        assert!(dispatch_event_id(&interaction, "interaction.create", None).is_some());
+    }
+
+    #[test]
+    fn test_capability_degraded_event_shape() {
+        let event = capability_degraded_event(3, &["GUILD_MEMBERS"], &["GUILDS"]);
+        assert_eq!(event.event_type, "gateway.capability_degraded");
+        assert_eq!(event.shard_id, 3);
+        assert!(event.guild_id.is_none());
+        assert_eq!(event.data["close_code"], 4014);
+        assert_eq!(event.data["missing_intents"][0], "GUILD_MEMBERS");
+        assert_eq!(event.data["active_intents"][0], "GUILDS");
+    }
+
+    #[test]
+    fn test_member_events_tag_bots() {
+        use twilight_model::gateway::payload::incoming::{MemberAdd, MemberRemove};
+
+        let user = serde_json::json!({
+            "id": "987654321098765432", "username": "helper", "discriminator": "0", "avatar": null, "bot": true
+        });
+        let member: MemberAdd = serde_json::from_value(serde_json::json!({
+            "guild_id": "123456789012345678", "user": user, "roles": [], "nick": null,
+            "joined_at": "2024-01-01T00:00:00.000000+00:00", "deaf": false, "mute": false, "flags": 0
+        }))
+        .unwrap();
+        let join = serialize_event(&Event::MemberAdd(Box::new(member)), 0).unwrap();
+        assert_eq!(join.data["is_bot"], true);
+
+        let removed: MemberRemove =
+            serde_json::from_value(serde_json::json!({ "guild_id": "123456789012345678", "user": user })).unwrap();
+        let mut leave = serialize_event(&Event::MemberRemove(removed), 0).unwrap();
+        set_removal_reason(&mut leave.data, RemovalReason::Ban);
+        assert_eq!(leave.data, serde_json::json!({ "is_bot": true, "removal_reason": "ban" }));
+    }
+
+    /// Every serializer's output follows the number policy (snowflakes as
+    /// strings, timestamps as Unix milliseconds); see `policy`
+    #[test]
+    fn every_event_type_follows_number_policy() {
+        use twilight_model::gateway::payload::incoming::{GuildCreate, GuildDelete, MemberAdd, MemberRemove, MemberUpdate};
+        use twilight_model::id::Id;
+
+        let user = serde_json::json!({
+            "id": "987654321098765432", "username": "legacy", "discriminator": "0042", "avatar": null
+        });
+        let member = serde_json::json!({
+            "guild_id": "123456789012345678", "user": user, "roles": ["888888888888888888"], "nick": null,
+            "joined_at": "2024-01-01T00:00:00.000000+00:00", "deaf": false, "mute": false, "flags": 0, "avatar": null
+        });
+        let guild = serde_json::json!({ "id": "123456789012345678", "unavailable": true });
+        let events = [
+            Event::GuildCreate(Box::new(GuildCreate::Unavailable(serde_json::from_value(guild).unwrap()))),
+            Event::GuildDelete(GuildDelete { id: Id::new(123456789012345678), unavailable: Some(true) }),
+            Event::MemberAdd(Box::new(serde_json::from_value::<MemberAdd>(member.clone()).unwrap())),
+            Event::MemberUpdate(Box::new(serde_json::from_value::<MemberUpdate>(member).unwrap())),
+            Event::MemberRemove(
+                serde_json::from_value::<MemberRemove>(serde_json::json!({ "guild_id": "123456789012345678", "user": user }))
+                    .unwrap(),
+            ),
+            command_interaction(),
+            message_with_attachment(),
+        ];
+
+        let mut payloads: Vec<GatewayEvent> = events.iter().filter_map(|event| serialize_event(event, 3)).collect();
+        assert_eq!(payloads.len(), events.len());
+        assert_eq!(payloads[2].data["discriminator"], "0042");
+
+        let mut leave = payloads[4].clone();
+        set_removal_reason(&mut leave.data, RemovalReason::Kick);
+        payloads.push(leave);
+        payloads.push(capability_degraded_event(3, &["GUILD_MEMBERS"], &["GUILDS"]));
+
+        for payload in payloads {
+            let value = serde_json::to_value(&payload).unwrap();
+            assert_eq!(policy::violations(&value), Vec::<String>::new(), "{}", payload.event_type);
+        }
+    }
+
     /// Fixture conformance: Rust must be able to round-trip deserialize
     /// every committed JSON fixture. If this fails, the Rust GatewayEvent
     /// struct has drifted from the wire format contract.
@@ -218,6 +1207,15 @@ mod tests {
             assert_eq!(event.event_type, "member.leave");
         }
 
+        #[test]
+        fn member_leave_kick_fixture_carries_removal_reason() {
+            let event = deserialize_fixture("member-leave-kick");
+            assert_eq!(event.event_type, "member.leave");
+            let mut data = serde_json::json!({ "is_bot": false });
+            set_removal_reason(&mut data, RemovalReason::Kick);
+            assert_eq!(event.data, data);
+        }
+
         #[test]
         fn member_update_fixture_deserializes() {
             let event = deserialize_fixture("member-update");
@@ -234,12 +1232,66 @@ mod tests {
             assert!(!data.contains_key("token"), "BB60-20: must NOT have bare 'token' field");
         }
 
+        #[test]
+        fn interaction_create_dm_fixture_deserializes() {
+            let event = deserialize_fixture("interaction-create-dm");
+            assert_eq!(event.event_type, "interaction.create");
+            assert!(event.guild_id.is_none());
+            assert_eq!(event.data["context"], "bot_dm");
+        }
+
+        #[test]
+        fn gateway_capability_degraded_fixture_deserializes() {
+            let event = deserialize_fixture("gateway-capability-degraded");
+            assert_eq!(event.event_type, "gateway.capability_degraded");
+            assert!(event.guild_id.is_none());
+            assert!(event.data["missing_intents"].is_array());
+        }
+
+        #[test]
+        fn message_create_fixture_deserializes() {
+            let event = deserialize_fixture("message-create");
+            assert_eq!(event.event_type, "message.create");
+            assert!(event.data["attachments"].is_array());
+        }
+
+        #[test]
+        fn message_delete_fixtures_deserialize() {
+            let event = deserialize_fixture("message-delete");
+            assert_eq!(event.event_type, "message.delete");
+            assert!(event.user_id.is_none());
+            let event = deserialize_fixture("message-bulk-delete");
+            assert_eq!(event.data["message_ids"].as_array().map(Vec::len), Some(2));
+        }
+
+        #[test]
+        fn event_summary_fixture_deserializes() {
+            let event = deserialize_fixture("event-summary");
+            assert_eq!(event.event_type, crate::events::aggregate::SUMMARY_EVENT_TYPE);
+            assert_eq!(event.data["event_type"], "member.join");
+        }
+
+        #[test]
+        fn raid_fixtures_deserialize() {
+            let burst = deserialize_fixture("member-join-burst");
+            assert_eq!(burst.event_type, crate::events::raid::BURST_EVENT_TYPE);
+            assert!(burst.data["sample_user_ids"].is_array());
+            let alert = deserialize_fixture("guild-raid-alert");
+            assert_eq!(alert.event_type, crate::events::raid::ALERT_EVENT_TYPE);
+            assert_eq!(alert.data["phase"], "started");
+        }
+
         #[test]
         fn all_fixtures_round_trip_through_serde() {
             let fixtures = [
                 "guild-join", "guild-leave",
-                "member-join", "member-leave", "member-update",
-                "interaction-create",
+                "member-join", "member-leave", "member-leave-kick", "member-update",
+                "interaction-create", "interaction-create-dm", "interaction-component", "interaction-modal",
+                "message-create", "message-update", "message-delete", "message-bulk-delete",
+                "reaction-add", "reaction-remove-all", "voice-state-update", "voice-server-update",
+                "role-create", "role-delete", "channel-create", "thread-create", "thread-list-sync",
+                "gateway-capability-degraded", "event-summary", "member-join-burst", "guild-raid-alert",
+                "member-sync", "gateway-circuit-state",
             ];
             for name in fixtures {
                 let event = deserialize_fixture(name);
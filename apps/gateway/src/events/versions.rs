@@ -0,0 +1,129 @@
+//! Envelope schema versions
+//!
+//! Every event carries `schema_version`. The serializers build events in the
+//! current version; a breaking change to the envelope or a payload bumps it by
+//! adding a `Migration` that turns an event of the new version back into the
+//! previous one. The gateway can then publish an older version on the primary
+//! subjects (`WIRE_SCHEMA_VERSION`) while dual-publishing another one on
+//! `v{version}.{subject}` (`SCHEMA_DUAL_PUBLISH`, see `nats::migration`), so
+//! workers move to a new shape one at a time instead of in a big-bang deploy
+//! with the gateway.
+//!
+//! Events without the field (published before versioning, or read back from
+//! an old outbox file) are version 1.
+
+use super::serialize::GatewayEvent;
+use crate::error::GatewayError;
+use std::borrow::Cow;
+
+/// A breaking change, and how to undo it
+pub struct Migration {
+    /// The version the change introduced
+    pub version: u32,
+    /// What changed, for logs
+    pub change: &'static str,
+    /// Turn an event of `version` into one of `version - 1`
+    pub downgrade: fn(&mut GatewayEvent),
+}
+
+/// Every breaking change since version 1, oldest first
+pub const MIGRATIONS: &[Migration] = &[];
+
+/// Version the serializers build events in
+pub const CURRENT: u32 = 1 + MIGRATIONS.len() as u32;
+
+/// `schema_version` of events that don't carry one
+pub const fn default_version() -> u32 {
+    1
+}
+
+/// Parse a schema version setting: 1 to `CURRENT`
+pub fn parse(name: &str, value: &str) -> Result<u32, GatewayError> {
+    match value.trim().trim_start_matches('v').parse() {
+        Ok(version) if (1..=CURRENT).contains(&version) => Ok(version),
+        _ => Err(GatewayError::Config(format!("{name} must be a schema version from 1 to {CURRENT}, got {value:?}"))),
+    }
+}
+
+/// An event in an older (or the same) version
+pub fn render(event: &GatewayEvent, version: u32) -> Cow<'_, GatewayEvent> {
+    render_with(MIGRATIONS, event, version)
+}
+
+fn render_with<'a>(migrations: &[Migration], event: &'a GatewayEvent, version: u32) -> Cow<'a, GatewayEvent> {
+    if event.schema_version <= version {
+        return Cow::Borrowed(event);
+    }
+    let mut rendered = event.clone();
+    for migration in migrations.iter().rev() {
+        if migration.version <= event.schema_version && migration.version > version {
+            (migration.downgrade)(&mut rendered);
+        }
+    }
+    rendered.schema_version = version;
+    Cow::Owned(rendered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// v2 renames `interaction_token` to `token`; v3 nests the user's bot flag
+    const TEST_MIGRATIONS: &[Migration] = &[
+        Migration {
+            version: 2,
+            change: "interaction_token renamed to token",
+            downgrade: |event| {
+                if let Some(token) = event.data.as_object_mut().and_then(|data| data.remove("token")) {
+                    event.data["interaction_token"] = token;
+                }
+            },
+        },
+        Migration {
+            version: 3,
+            change: "is_bot moved under user",
+            downgrade: |event| {
+                let is_bot = event.data["user"]["is_bot"].take();
+                if let Some(data) = event.data.as_object_mut() {
+                    data.remove("user");
+                    data.insert("is_bot".to_string(), is_bot);
+                }
+            },
+        },
+    ];
+
+    fn event(schema_version: u32, data: serde_json::Value) -> GatewayEvent {
+        GatewayEvent { schema_version, data, ..GatewayEvent::for_test("interaction.create") }
+    }
+
+    #[test]
+    fn test_events_are_downgraded_step_by_step() {
+        let v3 = event(3, json!({ "token": "t", "user": { "is_bot": false } }));
+
+        let v2 = render_with(TEST_MIGRATIONS, &v3, 2);
+        assert_eq!((v2.schema_version, &v2.data), (2, &json!({ "token": "t", "is_bot": false })));
+
+        let v1 = render_with(TEST_MIGRATIONS, &v3, 1);
+        assert_eq!((v1.schema_version, &v1.data), (1, &json!({ "interaction_token": "t", "is_bot": false })));
+
+        // Nothing to undo for an event already at or below the version
+        assert!(matches!(render_with(TEST_MIGRATIONS, &v1, 3), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_versions_are_parsed_and_defaulted() {
+        assert_eq!(parse("WIRE_SCHEMA_VERSION", "1").unwrap(), 1);
+        assert_eq!(parse("WIRE_SCHEMA_VERSION", "v1").unwrap(), 1);
+        assert!(parse("WIRE_SCHEMA_VERSION", "0").is_err());
+        assert!(parse("WIRE_SCHEMA_VERSION", &(CURRENT + 1).to_string()).is_err());
+
+        let legacy: GatewayEvent = serde_json::from_value(json!({
+            "event_id": "550e8400-e29b-41d4-a716-446655440000", "event_type": "member.join", "shard_id": 0,
+            "timestamp": 1_700_000_000_000u64, "guild_id": null, "channel_id": null, "user_id": null, "data": {}
+        }))
+        .unwrap();
+        assert_eq!(legacy.schema_version, 1);
+        assert_eq!(serde_json::to_value(&legacy).unwrap()["schema_version"], 1);
+    }
+}
@@ -0,0 +1,97 @@
+//! flagd flag definition file provider
+//!
+//! Reads the flagd file format (`{"flags": {"key": {"state", "variants",
+//! "defaultVariant", "targeting"}}}`). Targeting rules are not evaluated: a
+//! flag resolves to its default variant. Use the flagd provider when a flag
+//! needs targeting.
+
+use super::{resolve_all, FlagEvaluation};
+use crate::error::GatewayError;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+#[derive(Debug, Deserialize)]
+struct FlagFile {
+    #[serde(default)]
+    flags: HashMap<String, FlagSpec>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FlagSpec {
+    state: String,
+    variants: HashMap<String, serde_json::Value>,
+    default_variant: String,
+    #[serde(default)]
+    targeting: Option<serde_json::Value>,
+}
+
+/// Read and evaluate the flag file
+pub fn evaluate(path: &Path) -> Result<Vec<FlagEvaluation>, GatewayError> {
+    let contents = std::fs::read_to_string(path).map_err(|e| GatewayError::FlagProviderFailed {
+        provider: "file",
+        source: Box::new(e),
+    })?;
+    evaluate_str(&contents)
+}
+
+fn evaluate_str(contents: &str) -> Result<Vec<FlagEvaluation>, GatewayError> {
+    let file: FlagFile = serde_json::from_str(contents).map_err(|e| GatewayError::FlagProviderFailed {
+        provider: "file",
+        source: Box::new(e),
+    })?;
+
+    Ok(resolve_all(|flag| {
+        let spec = file.flags.get(flag.key)?;
+        let mut evaluation = FlagEvaluation {
+            key: flag.key,
+            value: flag.default,
+            variant: None,
+            reason: "DISABLED".to_string(),
+        };
+        if spec.state != "ENABLED" {
+            return Some(evaluation);
+        }
+
+        match spec.variants.get(&spec.default_variant).and_then(serde_json::Value::as_bool) {
+            Some(value) => {
+                evaluation.value = value;
+                evaluation.variant = Some(spec.default_variant.clone());
+                let targeted = spec.targeting.as_ref().is_some_and(|t| t.as_object().is_none_or(|o| !o.is_empty()));
+                evaluation.reason = if targeted { "DEFAULT" } else { "STATIC" }.to_string();
+            }
+            None => evaluation.reason = "ERROR".to_string(),
+        }
+        Some(evaluation)
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flags::{DUAL_PUBLISH, NEW_EVENT_TYPES};
+
+    #[test]
+    fn test_flag_file_resolves_default_variant() {
+        let evaluations = evaluate_str(
+            r#"{
+              "flags": {
+                "dual-publish": { "state": "ENABLED", "variants": { "on": true, "off": false }, "defaultVariant": "on" },
+                "new-event-types": { "state": "DISABLED", "variants": { "on": true, "off": false }, "defaultVariant": "off" },
+                "auto-defer": { "state": "ENABLED", "variants": { "on": "yes" }, "defaultVariant": "on" }
+              }
+            }"#,
+        )
+        .unwrap();
+
+        let get = |key: &str| evaluations.iter().find(|e| e.key == key).unwrap();
+        assert!(get(DUAL_PUBLISH).value);
+        assert_eq!(get(DUAL_PUBLISH).reason, "STATIC");
+        // Disabled flags keep the built-in default
+        assert!(get(NEW_EVENT_TYPES).value);
+        assert_eq!(get(NEW_EVENT_TYPES).reason, "DISABLED");
+        // Non-boolean variant
+        assert_eq!(get("auto-defer").reason, "ERROR");
+    }
+}
@@ -0,0 +1,219 @@
+//! Runtime feature flags
+//!
+//! Gates risky runtime behaviour per environment without a redeploy. Flags
+//! come from an OpenFeature-compatible provider: a flagd flag definition file
+//! (`FEATURE_FLAGS_FILE`) or a flagd service over the OpenFeature Remote
+//! Evaluation Protocol (`FEATURE_FLAGS_FLAGD_URL`). Values are refreshed in the
+//! background; the hot path only reads the last evaluation.
+
+mod file;
+mod ofrep;
+
+use crate::error::GatewayError;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Publish event types added after the original envelope set
+pub const NEW_EVENT_TYPES: &str = "new-event-types";
+/// Publish events a second time in a new wire format or schema version
+pub const DUAL_PUBLISH: &str = "dual-publish";
+/// Send deferred interaction responses from the gateway
+pub const AUTO_DEFER: &str = "auto-defer";
+
+/// A flag the gateway knows about
+#[derive(Debug, Clone, Copy)]
+pub struct FlagDefinition {
+    pub key: &'static str,
+    /// Value when the provider has no answer
+    pub default: bool,
+}
+
+/// Every flag the gateway evaluates
+pub const FLAGS: &[FlagDefinition] = &[
+    FlagDefinition { key: NEW_EVENT_TYPES, default: true },
+    FlagDefinition { key: DUAL_PUBLISH, default: false },
+    FlagDefinition { key: AUTO_DEFER, default: false },
+];
+
+/// Result of evaluating one flag (OpenFeature resolution details)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FlagEvaluation {
+    pub key: &'static str,
+    pub value: bool,
+    pub variant: Option<String>,
+    /// OpenFeature reason: STATIC, TARGETING_MATCH, DEFAULT, DISABLED, ERROR, ...
+    pub reason: String,
+}
+
+impl FlagEvaluation {
+    fn default_for(flag: &FlagDefinition, reason: &str) -> Self {
+        Self {
+            key: flag.key,
+            value: flag.default,
+            variant: None,
+            reason: reason.to_string(),
+        }
+    }
+}
+
+/// Where flag values come from
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum FlagSource {
+    /// Built-in defaults only
+    #[default]
+    Defaults,
+    /// flagd flag definition file
+    File(PathBuf),
+    /// flagd (or any OFREP service) base URL
+    Flagd(String),
+}
+
+impl FlagSource {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Defaults => "defaults",
+            Self::File(_) => "file",
+            Self::Flagd(_) => "flagd",
+        }
+    }
+}
+
+/// Feature flag settings
+#[derive(Debug, Clone, Default)]
+pub struct FlagConfig {
+    pub source: FlagSource,
+    /// Evaluation context: deployment environment (`ENVIRONMENT`)
+    pub environment: Option<String>,
+    pub refresh: Duration,
+}
+
+/// Current flag values, shared by everything that gates on them
+#[derive(Debug)]
+pub struct FeatureFlags {
+    values: RwLock<Vec<FlagEvaluation>>,
+}
+
+impl Default for FeatureFlags {
+    fn default() -> Self {
+        Self::from_evaluations(FLAGS.iter().map(|f| FlagEvaluation::default_for(f, "DEFAULT")).collect())
+    }
+}
+
+impl FeatureFlags {
+    /// Flags with fixed values
+    pub(crate) fn from_evaluations(values: Vec<FlagEvaluation>) -> Self {
+        Self {
+            values: RwLock::new(values),
+        }
+    }
+
+    /// Load the initial flag values.
+    ///
+    /// A missing or malformed flag file is a configuration error. An
+    /// unreachable flagd falls back to defaults (it may start after us) and is
+    /// retried on every refresh.
+    pub async fn load(config: &FlagConfig, pool_id: u64) -> Result<Arc<Self>, GatewayError> {
+        let flags = Arc::new(Self::default());
+        match &config.source {
+            FlagSource::Defaults => {}
+            FlagSource::File(path) => flags.store(file::evaluate(path)?),
+            FlagSource::Flagd(_) => {
+                if let Err(e) = flags.refresh(config, pool_id).await {
+                    warn!(error = %e, "flagd unavailable - using default flag values");
+                    flags.store(FLAGS.iter().map(|f| FlagEvaluation::default_for(f, "ERROR")).collect());
+                }
+            }
+        }
+        Ok(flags)
+    }
+
+    /// Whether a flag is on (unknown keys are off)
+    pub fn is_enabled(&self, key: &str) -> bool {
+        self.values
+            .read()
+            .map(|values| values.iter().any(|e| e.key == key && e.value))
+            .unwrap_or(false)
+    }
+
+    /// Current evaluations, for `/buildinfo`
+    pub fn snapshot(&self) -> Vec<FlagEvaluation> {
+        self.values.read().map(|values| values.clone()).unwrap_or_default()
+    }
+
+    fn store(&self, values: Vec<FlagEvaluation>) {
+        if let Ok(mut current) = self.values.write() {
+            *current = values;
+        }
+    }
+
+    /// Re-evaluate every flag from the configured source
+    async fn refresh(&self, config: &FlagConfig, pool_id: u64) -> Result<(), GatewayError> {
+        let values = match &config.source {
+            FlagSource::Defaults => return Ok(()),
+            FlagSource::File(path) => file::evaluate(path)?,
+            FlagSource::Flagd(url) => ofrep::evaluate(url, &context(config, pool_id)).await?,
+        };
+
+        let changed: Vec<&FlagEvaluation> = {
+            let current = self.snapshot();
+            values.iter().filter(|v| !current.contains(v)).collect()
+        };
+        for flag in changed {
+            info!(flag = flag.key, value = flag.value, reason = %flag.reason, "Feature flag changed");
+        }
+
+        self.store(values);
+        Ok(())
+    }
+}
+
+/// OpenFeature evaluation context for this pool
+fn context(config: &FlagConfig, pool_id: u64) -> serde_json::Value {
+    serde_json::json!({
+        "targetingKey": format!("pool-{pool_id}"),
+        "pool_id": pool_id,
+        "environment": config.environment,
+    })
+}
+
+/// Refresh flag values until the process exits; failures keep the last values
+pub async fn run_refresh(flags: Arc<FeatureFlags>, config: FlagConfig, pool_id: u64) {
+    if config.source == FlagSource::Defaults {
+        return;
+    }
+
+    let mut interval = tokio::time::interval(config.refresh);
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        if let Err(e) = flags.refresh(&config, pool_id).await {
+            warn!(error = %e, source = config.source.as_str(), "Feature flag refresh failed - keeping last values");
+        }
+    }
+}
+
+/// Evaluate every known flag against provider results, falling back to the
+/// default for flags the provider doesn't define
+fn resolve_all(mut lookup: impl FnMut(&FlagDefinition) -> Option<FlagEvaluation>) -> Vec<FlagEvaluation> {
+    FLAGS
+        .iter()
+        .map(|flag| lookup(flag).unwrap_or_else(|| FlagEvaluation::default_for(flag, "DEFAULT")))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults_and_unknown_keys() {
+        let flags = FeatureFlags::default();
+        assert!(flags.is_enabled(NEW_EVENT_TYPES));
+        assert!(!flags.is_enabled(DUAL_PUBLISH));
+        assert!(!flags.is_enabled("no-such-flag"));
+        assert_eq!(flags.snapshot().len(), FLAGS.len());
+    }
+}
@@ -0,0 +1,101 @@
+//! flagd provider over the OpenFeature Remote Evaluation Protocol (OFREP)
+//!
+//! Bulk-evaluates all flags with `POST {base}/ofrep/v1/evaluate/flags`, so
+//! targeting rules (per environment, per pool) are applied by flagd.
+
+use super::{resolve_all, FlagEvaluation};
+use crate::error::GatewayError;
+use crate::http_client::client;
+use serde::Deserialize;
+use std::time::Duration;
+
+#[derive(Debug, Deserialize)]
+struct BulkResponse {
+    flags: Vec<FlagResult>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FlagResult {
+    key: String,
+    #[serde(default)]
+    value: Option<serde_json::Value>,
+    #[serde(default)]
+    variant: Option<String>,
+    #[serde(default)]
+    reason: Option<String>,
+    #[serde(default)]
+    error_code: Option<String>,
+}
+
+/// flagd answers from memory; a slow one shouldn't hold up a refresh
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Evaluate every flag against flagd
+pub async fn evaluate(base: &str, context: &serde_json::Value) -> Result<Vec<FlagEvaluation>, GatewayError> {
+    let failed = |e: reqwest::Error| GatewayError::FlagProviderFailed {
+        provider: "flagd",
+        source: Box::new(e),
+    };
+
+    let response: BulkResponse = client()
+        .post(format!("{}/ofrep/v1/evaluate/flags", base.trim_end_matches('/')))
+        .json(&serde_json::json!({ "context": context }))
+        .timeout(TIMEOUT)
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(failed)?
+        .json()
+        .await
+        .map_err(failed)?;
+
+    Ok(resolve(response))
+}
+
+fn resolve(response: BulkResponse) -> Vec<FlagEvaluation> {
+    resolve_all(|flag| {
+        let result = response.flags.iter().find(|r| r.key == flag.key)?;
+        match result.value.as_ref().and_then(serde_json::Value::as_bool) {
+            Some(value) if result.error_code.is_none() => Some(FlagEvaluation {
+                key: flag.key,
+                value,
+                variant: result.variant.clone(),
+                reason: result.reason.clone().unwrap_or_else(|| "UNKNOWN".to_string()),
+            }),
+            // FLAG_NOT_FOUND, TYPE_MISMATCH, ...: keep the default
+            _ => Some(FlagEvaluation {
+                key: flag.key,
+                value: flag.default,
+                variant: None,
+                reason: "ERROR".to_string(),
+            }),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flags::{AUTO_DEFER, DUAL_PUBLISH, NEW_EVENT_TYPES};
+
+    #[test]
+    fn test_bulk_response_maps_to_known_flags() {
+        let response: BulkResponse = serde_json::from_str(
+            r#"{ "flags": [
+                { "key": "dual-publish", "value": true, "variant": "on", "reason": "TARGETING_MATCH" },
+                { "key": "auto-defer", "errorCode": "TYPE_MISMATCH" },
+                { "key": "unrelated", "value": true }
+            ] }"#,
+        )
+        .unwrap();
+
+        let evaluations = resolve(response);
+        let get = |key: &str| evaluations.iter().find(|e| e.key == key).unwrap();
+        assert!(get(DUAL_PUBLISH).value);
+        assert_eq!(get(DUAL_PUBLISH).reason, "TARGETING_MATCH");
+        assert_eq!(get(AUTO_DEFER).reason, "ERROR");
+        assert_eq!(get(NEW_EVENT_TYPES).reason, "DEFAULT");
+        assert_eq!(evaluations.len(), 3);
+    }
+}
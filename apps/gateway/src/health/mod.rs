@@ -2,25 +2,72 @@
 //!
 //! Sprint S-4: Health Endpoints per SDD §8.2
 
+pub mod status;
+
+use crate::config::GatewayConfig;
+use crate::discord::ApiVersion;
+use crate::events::serialize::now_millis;
+use crate::flags::{FeatureFlags, FlagEvaluation};
 use crate::metrics::GatewayMetrics;
+use crate::nats::topology::{self, ClusterReport, PoolTopology};
 use crate::nats::NatsPublisher;
-use crate::shard::ShardState;
+use crate::shard::{ShardSnapshot, ShardState};
 use axum::{
-    extract::State,
+    extract::{Path, State},
     http::StatusCode,
     response::IntoResponse,
     routing::get,
     Json, Router,
 };
 use serde::Serialize;
+use serde_json::{json, Value};
 use std::sync::Arc;
+use std::time::Duration;
 
 /// Health check response
 #[derive(Debug, Serialize)]
 pub struct HealthResponse {
+    /// `healthy`, or `stale` when no shard has heartbeated within
+    /// `LIVENESS_STALE_SECS`
     pub status: &'static str,
     pub version: &'static str,
     pub pool_id: u64,
+    /// Time since any shard last heartbeated
+    pub heartbeat_silence_seconds: f64,
+}
+
+/// How many of the pool's shards must be ready for `/ready` to pass
+/// (`READY_MIN_SHARDS`, `READY_MIN_FRACTION`); the stricter one applies
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct ReadyPolicy {
+    pub min_shards: usize,
+    /// Fraction of the pool's shards, 0 to 1
+    pub min_fraction: f64,
+}
+
+impl Default for ReadyPolicy {
+    /// Any one ready shard
+    fn default() -> Self {
+        Self { min_shards: 1, min_fraction: 0.0 }
+    }
+}
+
+impl ReadyPolicy {
+    /// Ready shards needed out of `shards_total`, never more than the pool
+    /// runs and never none
+    pub fn required(&self, shards_total: usize) -> usize {
+        let by_fraction = (self.min_fraction * shards_total as f64).ceil() as usize;
+        self.min_shards.max(by_fraction).min(shards_total).max(1)
+    }
+}
+
+/// The policy `/ready` was evaluated against
+#[derive(Debug, Serialize)]
+pub struct ReadyPolicyReport {
+    #[serde(flatten)]
+    pub policy: ReadyPolicy,
+    /// Ready shards this pool needs
+    pub required_shards: usize,
 }
 
 /// Readiness check response
@@ -28,10 +75,99 @@ pub struct HealthResponse {
 pub struct ReadyResponse {
     pub ready: bool,
     pub pool_id: u64,
+    /// Shards this pool runs
     pub shards_total: usize,
+    /// Size of each pool's shard range (`SHARDS_PER_POOL`)
+    pub shards_per_pool: u64,
     pub shards_ready: usize,
     pub nats_connected: bool,
+    /// Shutting down: shards are stopping and publishes are being flushed
+    pub draining: bool,
     pub guilds_total: u64,
+    /// Capabilities lost because Discord disallowed their intents (4014)
+    pub degraded_capabilities: Vec<&'static str>,
+    pub policy: ReadyPolicyReport,
+}
+
+/// One shard's diagnostics (`/shards`, `/shards/{id}`)
+#[derive(Debug, Serialize)]
+pub struct ShardStatus {
+    pub shard_id: u64,
+    pub health: &'static str,
+    pub guilds: u64,
+    /// Unset until the first heartbeat ack
+    pub heartbeat_age_seconds: Option<f64>,
+    pub events_received: u64,
+    pub events_routed: u64,
+    pub events_filtered: u64,
+    pub route_failures: u64,
+    /// Events waiting in the shard's publish queues
+    pub events_queued: u64,
+    /// Sessions resumed since the pool started
+    pub resumes: u64,
+    /// Time since the current connection became ready; unset while down
+    pub uptime_seconds: Option<f64>,
+    /// Unset without an active session
+    pub session_uptime_seconds: Option<f64>,
+    pub resumable: bool,
+}
+
+impl From<&ShardSnapshot> for ShardStatus {
+    fn from(shard: &ShardSnapshot) -> Self {
+        Self {
+            shard_id: shard.shard_id,
+            health: shard.health.as_str(),
+            guilds: shard.guilds,
+            heartbeat_age_seconds: shard.heartbeat_age.map(|age| age.as_secs_f64()),
+            events_received: shard.events_received,
+            events_routed: shard.events_routed,
+            events_filtered: shard.events_filtered,
+            route_failures: shard.route_failures,
+            events_queued: shard.events_queued,
+            resumes: shard.resumes,
+            uptime_seconds: shard.uptime.map(|uptime| uptime.as_secs_f64()),
+            session_uptime_seconds: shard.session_uptime.map(|uptime| uptime.as_secs_f64()),
+            resumable: shard.resumable,
+        }
+    }
+}
+
+/// Build and protocol information
+#[derive(Debug, Clone, Serialize)]
+pub struct BuildInfo {
+    pub version: &'static str,
+    pub pool_id: u64,
+    /// Discord API version pin, this pool's mode, and compiled versions
+    pub discord_api: ApiVersion,
+    /// Gateway transport compression codec
+    pub compression: &'static str,
+    /// Cargo features compiled in
+    pub features: Vec<&'static str>,
+    /// Feature flag provider (`defaults`, `file`, `flagd`)
+    pub flag_source: &'static str,
+}
+
+impl BuildInfo {
+    pub fn new(config: &GatewayConfig) -> Self {
+        let features = [
+            ("jemalloc", cfg!(feature = "jemalloc")),
+            ("compression-zlib", cfg!(feature = "compression-zlib")),
+            ("compression-zstd", cfg!(feature = "compression-zstd")),
+            ("tokio-console", cfg!(feature = "tokio-console")),
+        ]
+        .into_iter()
+        .filter_map(|(name, enabled)| enabled.then_some(name))
+        .collect();
+
+        Self {
+            version: env!("CARGO_PKG_VERSION"),
+            pool_id: config.pool_id,
+            discord_api: config.api_version,
+            compression: config.compression.as_str(),
+            features,
+            flag_source: config.flags.source.as_str(),
+        }
+    }
 }
 
 /// Application state for health endpoints
@@ -40,6 +176,24 @@ pub struct AppState {
     pub shard_state: ShardState,
     pub nats: Option<Arc<NatsPublisher>>,
     pub metrics: Arc<GatewayMetrics>,
+    pub build_info: Arc<BuildInfo>,
+    pub flags: Arc<FeatureFlags>,
+    /// Heartbeat silence after which `/health` fails (None: always healthy)
+    pub liveness_stale_after: Option<Duration>,
+    pub ready_policy: ReadyPolicy,
+    /// Non-secret configuration, for `/status`
+    pub config_summary: Arc<Value>,
+    /// Pools of the other bots in this process (`BOTS`), for `/status`;
+    /// readiness follows the primary pool only
+    pub bots: Vec<(String, ShardState)>,
+}
+
+/// `/buildinfo` response: build info plus current feature flag evaluations
+#[derive(Debug, Serialize)]
+pub struct BuildInfoResponse {
+    #[serde(flatten)]
+    pub build: BuildInfo,
+    pub flags: Vec<FlagEvaluation>,
 }
 
 /// Create the health check router
@@ -47,46 +201,153 @@ pub fn router(state: AppState) -> Router {
     Router::new()
         .route("/health", get(health_handler))
         .route("/ready", get(ready_handler))
+        .route("/status", get(status_handler))
+        .route("/shards", get(shards_handler))
+        .route("/shards/{shard_id}", get(shard_handler))
+        .route("/guilds/{guild_id}/shard", get(guild_shard_handler))
+        .route("/cluster", get(cluster_handler))
         .route("/metrics", get(metrics_handler))
+        .route("/buildinfo", get(buildinfo_handler))
+        .route("/debug/memory", get(memory_handler))
+        .route("/debug/tasks", get(tasks_handler))
+        .route("/debug/events/{event_id}", get(event_handler))
         .with_state(state)
 }
 
-/// Health endpoint - always returns 200 if process is running
-async fn health_handler(State(state): State<AppState>) -> impl IntoResponse {
-    Json(HealthResponse {
-        status: "healthy",
+/// Liveness of this process: a pool whose shards have all stopped
+/// heartbeating is stuck, and restarting it is the fix
+pub fn health(state: &AppState) -> HealthResponse {
+    let silence = state.shard_state.heartbeat_silence();
+    let stale = state.liveness_stale_after.is_some_and(|after| silence > after);
+    HealthResponse {
+        status: if stale { "stale" } else { "healthy" },
         version: env!("CARGO_PKG_VERSION"),
         pool_id: state.shard_state.pool_id(),
-    })
+        heartbeat_silence_seconds: silence.as_secs_f64(),
+    }
 }
 
-/// Readiness endpoint - returns 200 if at least one shard is ready
-async fn ready_handler(State(state): State<AppState>) -> impl IntoResponse {
-    let shards_ready = state.shard_state.ready_shards();
+/// Readiness: enough shards ready for the policy, NATS (if configured)
+/// connected and not shutting down
+pub fn readiness(state: &AppState) -> ReadyResponse {
     let shards_total = state.shard_state.shard_count();
+    let shards_ready = state.shard_state.ready_shards();
     let nats_connected = state.nats.as_ref().is_none_or(|n| n.is_connected());
+    let draining = state.shard_state.is_draining();
+    let required_shards = state.ready_policy.required(shards_total);
 
-    let is_ready = shards_ready > 0 && nats_connected;
-
-    let response = ReadyResponse {
-        ready: is_ready,
+    ReadyResponse {
+        ready: shards_ready >= required_shards && nats_connected && !draining,
         pool_id: state.shard_state.pool_id(),
         shards_total,
+        shards_per_pool: state.shard_state.shards_per_pool(),
         shards_ready,
         nats_connected,
+        draining,
         guilds_total: state.shard_state.total_guilds(),
-    };
+        degraded_capabilities: state.shard_state.degraded_capabilities(),
+        policy: ReadyPolicyReport { policy: state.ready_policy, required_shards },
+    }
+}
+
+/// Build info plus current feature flag evaluations
+pub fn build_info(state: &AppState) -> BuildInfoResponse {
+    BuildInfoResponse {
+        build: state.build_info.as_ref().clone(),
+        flags: state.flags.snapshot(),
+    }
+}
+
+/// Health endpoint - returns 200 unless the shards' heartbeats have gone stale
+async fn health_handler(State(state): State<AppState>) -> impl IntoResponse {
+    let response = health(&state);
+
+    if response.status == "healthy" {
+        (StatusCode::OK, Json(response))
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, Json(response))
+    }
+}
+
+/// Readiness endpoint - returns 200 if enough shards are ready
+async fn ready_handler(State(state): State<AppState>) -> impl IntoResponse {
+    let response = readiness(&state);
 
-    if is_ready {
+    if response.ready {
         (StatusCode::OK, Json(response))
     } else {
         (StatusCode::SERVICE_UNAVAILABLE, Json(response))
     }
 }
 
+/// Status endpoint - readiness, shards, rates and config in one document
+async fn status_handler(State(state): State<AppState>) -> impl IntoResponse {
+    Json(status::status(&state))
+}
+
+/// Shards endpoint - per-shard diagnostics for this pool
+async fn shards_handler(State(state): State<AppState>) -> impl IntoResponse {
+    let shards: Vec<ShardStatus> = state.shard_state.snapshots().iter().map(ShardStatus::from).collect();
+    Json(json!({ "pool_id": state.shard_state.pool_id(), "shards": shards }))
+}
+
+/// Shard endpoint - one shard's diagnostics, 404 if this pool doesn't run it
+async fn shard_handler(State(state): State<AppState>, Path(shard_id): Path<u64>) -> (StatusCode, Json<Value>) {
+    match state.shard_state.snapshot(shard_id) {
+        Some(shard) => (StatusCode::OK, Json(json!(ShardStatus::from(&shard)))),
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": format!("shard {shard_id} is not run by pool {}", state.shard_state.pool_id())
+            })),
+        ),
+    }
+}
+
+/// Guild shard endpoint - which shard and pool own a guild, and whether this pool runs it
+async fn guild_shard_handler(State(state): State<AppState>, Path(guild_id): Path<String>) -> (StatusCode, Json<Value>) {
+    match guild_id.parse::<u64>() {
+        Ok(id) if id > 0 => (StatusCode::OK, Json(crate::nats::ownership::owner(id, &state.shard_state))),
+        _ => (StatusCode::BAD_REQUEST, Json(json!({ "error": format!("invalid guild ID {guild_id:?}") }))),
+    }
+}
+
+/// Cluster endpoint - every pool's topology document summed up, with this pool's taken live
+async fn cluster_handler(State(state): State<AppState>) -> (StatusCode, Json<Value>) {
+    let Some(ref nats) = state.nats else {
+        return (StatusCode::SERVICE_UNAVAILABLE, Json(json!({ "error": "NATS is not connected" })));
+    };
+    let mut pools = match topology::list(nats).await {
+        Ok(pools) => pools,
+        Err(e) => return (StatusCode::SERVICE_UNAVAILABLE, Json(json!({ "error": e.to_string() }))),
+    };
+    let now_ms = now_millis();
+    pools.retain(|pool| pool.pool_id != state.shard_state.pool_id());
+    pools.push(PoolTopology::capture(&state, now_ms));
+    (StatusCode::OK, Json(json!(ClusterReport::aggregate(&pools, now_ms, topology::STALE_AFTER))))
+}
+
 /// Metrics endpoint - returns Prometheus format metrics
 async fn metrics_handler(State(state): State<AppState>) -> impl IntoResponse {
     // Update current metrics
+    export_gauges(&state);
+
+    match state.metrics.render() {
+        Some(body) => (
+            StatusCode::OK,
+            [(axum::http::header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+            body,
+        ),
+        None => (
+            StatusCode::NOT_FOUND,
+            [(axum::http::header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+            "metrics are pushed to DogStatsD (METRICS_BACKEND=dogstatsd)\n".to_string(),
+        ),
+    }
+}
+
+/// Set the gauges computed from current state rather than on the hot path
+fn export_gauges(state: &AppState) {
     state.metrics.set_shards_ready(
         state.shard_state.pool_id(),
         state.shard_state.ready_shards(),
@@ -96,11 +357,61 @@ async fn metrics_handler(State(state): State<AppState>) -> impl IntoResponse {
         state.metrics.set_nats_connected(nats.is_connected());
     }
 
-    (
-        StatusCode::OK,
-        [(axum::http::header::CONTENT_TYPE, "text/plain; charset=utf-8")],
-        state.metrics.render(),
-    )
+    for (shard_id, uptime) in state.shard_state.session_uptimes() {
+        state.metrics.set_session_uptime(shard_id, uptime);
+    }
+    for (bot_id, pool) in &state.bots {
+        let metrics = state.metrics.for_bot(bot_id);
+        metrics.set_shards_ready(pool.pool_id(), pool.ready_shards());
+        for (shard_id, uptime) in pool.session_uptimes() {
+            metrics.set_session_uptime(shard_id, uptime);
+        }
+    }
+
+    state.metrics.export_publish_queues();
+    state.metrics.export_memory();
+}
+
+/// Export state gauges on an interval, for push backends that are never scraped
+pub async fn push_gauges(state: AppState, every: Duration) {
+    let mut interval = tokio::time::interval(every);
+    loop {
+        interval.tick().await;
+        export_gauges(&state);
+    }
+}
+
+/// Build info endpoint - version, Discord API pin, and compiled features
+async fn buildinfo_handler(State(state): State<AppState>) -> impl IntoResponse {
+    Json(build_info(&state))
+}
+
+/// Memory endpoint - allocator stats and per-subsystem estimates
+async fn memory_handler(State(state): State<AppState>) -> impl IntoResponse {
+    Json(state.metrics.memory_report())
+}
+
+/// Tasks endpoint - Tokio runtime task counts, queue depths and worker busy time
+async fn tasks_handler() -> impl IntoResponse {
+    Json(crate::metrics::runtime_report())
+}
+
+/// Event lookup - where a recently published event went, 404 if not indexed
+async fn event_handler(State(state): State<AppState>, Path(event_id): Path<String>) -> (StatusCode, Json<Value>) {
+    let Some(ref nats) = state.nats else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({ "event_id": event_id, "error": "NATS is not connected" })),
+        );
+    };
+    match nats.recent_events().get(&event_id) {
+        Ok(event) => (StatusCode::OK, Json(json!({ "published": true, "event": event }))),
+        // Not indexed: never published, or evicted (compare oldest_published_at)
+        Err(index) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "event_id": event_id, "published": false, "index": index })),
+        ),
+    }
 }
 
 #[cfg(test)]
@@ -113,6 +424,7 @@ mod tests {
             status: "healthy",
             version: "0.2.0",
             pool_id: 0,
+            heartbeat_silence_seconds: 41.25,
         };
 
         let json = serde_json::to_string(&response).unwrap();
@@ -125,12 +437,74 @@ mod tests {
             ready: true,
             pool_id: 0,
             shards_total: 25,
+            shards_per_pool: 25,
             shards_ready: 25,
             nats_connected: true,
+            draining: false,
             guilds_total: 1000,
+            degraded_capabilities: vec!["GUILD_MEMBERS"],
+            policy: ReadyPolicyReport { policy: ReadyPolicy::default(), required_shards: 1 },
         };
 
         let json = serde_json::to_string(&response).unwrap();
         assert!(json.contains("\"ready\":true"));
+        assert!(json.contains("\"degraded_capabilities\":[\"GUILD_MEMBERS\"]"));
+        assert!(json.contains("\"policy\":{\"min_shards\":1,\"min_fraction\":0.0,\"required_shards\":1}"));
+    }
+
+    #[test]
+    fn test_ready_policy_takes_the_stricter_bound() {
+        assert_eq!(ReadyPolicy::default().required(25), 1);
+        let policy = ReadyPolicy { min_shards: 5, min_fraction: 0.8 };
+        assert_eq!(policy.required(25), 20);
+        assert_eq!(policy.required(4), 4, "capped at the shards the pool runs");
+        assert_eq!(ReadyPolicy { min_shards: 5, min_fraction: 0.1 }.required(25), 5);
+        assert_eq!(ReadyPolicy { min_shards: 0, min_fraction: 0.0 }.required(25), 1, "never none");
+    }
+
+    #[test]
+    fn test_shard_status_reports_seconds() {
+        use crate::shard::ShardHealth;
+
+        let status = ShardStatus::from(&ShardSnapshot {
+            shard_id: 79,
+            health: ShardHealth::Disconnected,
+            guilds: 1200,
+            events_received: 50,
+            events_routed: 47,
+            route_failures: 3,
+            events_filtered: 0,
+            events_queued: 0,
+            resumes: 0,
+            heartbeat_age: Some(Duration::from_millis(41_250)),
+            uptime: None,
+            session_uptime: Some(Duration::from_secs(3600)),
+            resumable: true,
+        });
+
+        let json = serde_json::to_value(&status).unwrap();
+        assert_eq!(json["health"], "disconnected");
+        assert_eq!(json["heartbeat_age_seconds"], 41.25);
+        assert_eq!(json["uptime_seconds"], Value::Null);
+        assert_eq!(json["route_failures"], 3);
+    }
+
+    #[test]
+    fn test_build_info_serialization() {
+        use crate::discord::ApiVersionMode;
+
+        let info = BuildInfo {
+            version: "0.2.0",
+            pool_id: 1,
+            discord_api: ApiVersion::resolve(twilight_gateway::API_VERSION, ApiVersionMode::Pinned)
+                .unwrap(),
+            compression: "zstd-stream",
+            features: vec!["jemalloc"],
+            flag_source: "defaults",
+        };
+
+        let json = serde_json::to_value(&info).unwrap();
+        assert_eq!(json["discord_api"]["gateway"], twilight_gateway::API_VERSION);
+        assert_eq!(json["discord_api"]["mode"], "pinned");
     }
 }
@@ -0,0 +1,60 @@
+//! `GET /status`: one document for on-call
+//!
+//! Readiness, every shard's diagnostics, event and publish rates over the
+//! last minute, resumes, uptime and the pool's configuration (without
+//! secrets), so a sick pool can be read without Prometheus. Pools of other
+//! bots in the process (`BOTS`) are listed under `bots`.
+
+use super::{build_info, readiness, AppState, ShardStatus};
+use crate::shard::rate::WINDOW_SECS;
+use serde_json::{json, Value};
+
+/// The status document
+pub fn status(state: &AppState) -> Value {
+    let shards: Vec<ShardStatus> = state.shard_state.snapshots().iter().map(ShardStatus::from).collect();
+    let resumes: u64 = shards.iter().map(|shard| shard.resumes).sum();
+    let (received, routed, failed) = state.shard_state.event_rates();
+    let (published, failures) = (routed.total(), failed.total());
+
+    json!({
+        "pool_id": state.shard_state.pool_id(),
+        "uptime_seconds": state.shard_state.uptime().as_secs_f64(),
+        "build": build_info(state),
+        "ready": readiness(state),
+        "rates": {
+            "window_seconds": WINDOW_SECS,
+            "events_received_per_second": received.per_second(),
+            "events_published_per_second": routed.per_second(),
+            "publish_failures_per_second": failed.per_second(),
+            "publish_success_rate": success_rate(published, failures),
+        },
+        "resumes_total": resumes,
+        "shards": shards,
+        "bots": state.bots.iter().map(|(bot_id, pool)| json!({
+            "bot_id": bot_id,
+            "pool_id": pool.pool_id(),
+            "shards_ready": pool.ready_shards(),
+            "shards_total": pool.shard_count(),
+            "shards": pool.snapshots().iter().map(ShardStatus::from).collect::<Vec<_>>(),
+        })).collect::<Vec<_>>(),
+        "config": state.config_summary.as_ref(),
+    })
+}
+
+/// Share of publishes that succeeded; None when nothing was published
+fn success_rate(published: u64, failures: u64) -> Option<f64> {
+    let attempts = published + failures;
+    (attempts > 0).then(|| published as f64 / attempts as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn success_rate_is_unset_without_publishes() {
+        assert_eq!(success_rate(0, 0), None);
+        assert_eq!(success_rate(99, 1), Some(0.99));
+        assert_eq!(success_rate(0, 5), Some(0.0));
+    }
+}
@@ -0,0 +1,31 @@
+//! Lowercase hex, the form keys are configured in and digests, signatures
+//! and redacted values are written in
+
+pub fn encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Decode a hex key (`EVENT_SIGNING_KEY`, `PAYLOAD_ENCRYPTION_KEY`)
+pub fn decode(value: &str) -> Option<Vec<u8>> {
+    let value = value.trim();
+    if !value.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..value.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(value.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_either_case() {
+        assert_eq!(decode("0aFf"), Some(vec![0x0a, 0xff]));
+        assert_eq!(encode(&[0x0a, 0xff]), "0aff");
+        assert_eq!(decode("0af"), None);
+        assert_eq!(decode("zz"), None);
+    }
+}
@@ -0,0 +1,16 @@
+//! Outbound HTTP client of the secret backends and the flag provider
+
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// One connection pool for Vault, AWS and flagd; requests that need a
+/// shorter limit set their own `timeout`
+pub fn client() -> &'static reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT.get_or_init(|| {
+        reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .unwrap_or_default()
+    })
+}
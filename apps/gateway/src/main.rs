@@ -8,139 +8,944 @@
 //! - Exposes health/ready endpoints for Kubernetes
 //! - Exports Prometheus metrics for observability
 
+// The `/status` config summary is one `json!` literal
+#![recursion_limit = "256"]
+
 use anyhow::Result;
 use std::net::SocketAddr;
 use std::sync::Arc;
 use tokio::signal;
-use tracing::{error, info};
+use tracing::{error, info, warn};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::Layer as _;
+use twilight_model::gateway::SessionStartLimit;
 
+mod admin;
+mod alerts;
+mod cache;
 mod config;
+mod discord;
 pub mod error;
 mod events;
+mod flags;
 mod health;
+mod hex;
+mod http_client;
 mod metrics;
 mod nats;
+mod overrides;
+mod secrets;
 mod shard;
+mod telemetry;
+mod topo;
+
+use cache::GuildCache;
+use config::{BotConfig, GatewayConfig};
+use discord::commands::{CommandSet, SyncMode};
+use discord::defer::CommandDefer;
+use events::aggregate::{self, Aggregator};
+use events::guild_limit::GuildRateLimiter;
+use events::raid::{self, RaidDetector};
+use events::schema::SchemaValidator;
+use health::{AppState, BuildInfo};
+use metrics::{GatewayMetrics, MetricsBackend};
+use nats::members::MemberRequests;
+use nats::presence::PresenceUpdates;
+use nats::{NatsPublisher, PublisherOptions};
+use shard::coordinator::IdentifyCoordinator;
+use shard::reshard::{self, Resharding};
+use shard::session::SessionStore;
+use shard::token::TokenRotation;
+use shard::{budget, select_shards, PoolOptions, ShardPool};
+
+/// How often state gauges are exported when metrics are pushed (DogStatsD)
+const GAUGE_PUSH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
 
-use config::GatewayConfig;
-use health::AppState;
-use metrics::GatewayMetrics;
-use nats::NatsPublisher;
-use shard::ShardPool;
+#[cfg(all(feature = "tokio-console", not(tokio_unstable)))]
+compile_error!("the tokio-console feature needs RUSTFLAGS=\"--cfg tokio_unstable\"");
+
+#[cfg(feature = "jemalloc")]
+#[global_allocator]
+static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Load configuration first to get log level
-    let gateway_config = GatewayConfig::from_env()?;
-
-    // Initialize tracing with configured log level
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::from_default_env()
-                .add_directive(format!("arrakis_gateway={}", gateway_config.log_level).parse()?)
-                .add_directive("twilight_gateway=info".parse()?)
-                .add_directive("async_nats=warn".parse()?),
-        )
+    // Select the TLS crypto provider before any connection is built
+    // (Twilight shards load TLS config on construction)
+    install_crypto_provider();
+
+    // Subcommands run without the gateway's configuration
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().is_some_and(|arg| arg == "topo") {
+        return Ok(topo::run(&args[1..]).await?);
+    }
+
+    // Load configuration first to get log level (the environment overrides
+    // the --config file, and flags override the environment)
+    let mut gateway_config = GatewayConfig::load(args)?;
+
+    // Initialize tracing with configured log level; spans are exported only
+    // when OTLP is configured (logs stay one flat JSON object per line). The
+    // level filter applies to logs and spans only, so tokio-console still
+    // sees the runtime's task events. The filter sits behind a reload handle
+    // so `log_level` overrides can replace it.
+    let (filter, log_handle) = tracing_subscriber::reload::Layer::new(log_filter(&gateway_config.log_level)?);
+    let tracer_provider =
+        gateway_config.traces.as_ref().map(|traces| telemetry::init(traces, gateway_config.pool_id)).transpose()?;
+    let logs = tracing_subscriber::fmt::layer()
         .json()
-        .init();
+        .with_current_span(false)
+        .with_span_list(false)
+        .and_then(tracer_provider.as_ref().map(telemetry::layer))
+        .with_filter(filter);
+    let registry = tracing_subscriber::registry();
+    #[cfg(feature = "tokio-console")]
+    let registry = registry.with(console_subscriber::spawn());
+    registry.with(logs).init();
+    if let Some(ref path) = gateway_config.config_file {
+        info!(path = %path.display(), "Loaded config file");
+        if !gateway_config.unused_file_keys.is_empty() {
+            warn!(
+                keys = ?gateway_config.unused_file_keys,
+                "Config file keys not used (unknown, overridden by the environment, or not needed by other settings)"
+            );
+        }
+    }
+    if let Some(ref traces) = gateway_config.traces {
+        info!(endpoint = traces.endpoint, sample_ratio = traces.sample_ratio, "Exporting traces over OTLP");
+    }
 
     info!(
         version = env!("CARGO_PKG_VERSION"),
         pool_id = gateway_config.pool_id,
         total_shards = gateway_config.total_shards,
+        discover_shards = gateway_config.discover_shards,
         "Starting Arrakis Gateway"
     );
 
+    // Tokens from files or a secrets manager, before anything talks to Discord
+    gateway_config.read_secrets().await?;
+    info!(source = gateway_config.discord_token_source.provider(), "Discord token read");
+    if !gateway_config.discord_token_source.refreshes() {
+        warn!("DISCORD_TOKEN is set in the environment - prefer DISCORD_TOKEN_FILE or a secrets manager reference");
+    }
+
+    // Refuse to start if serialization drifted from the wire contract
+    if gateway_config.self_test {
+        events::selftest::run()?;
+    }
+
     // Initialize metrics
-    let metrics = Arc::new(GatewayMetrics::new());
-    info!("Prometheus metrics initialized");
+    let metrics = Arc::new(GatewayMetrics::with_backend(&gateway_config.metrics_backend, &gateway_config.metrics_buckets));
+    info!(backend = %gateway_config.metrics_backend, "Metrics initialized");
+
+    metrics.set_transport_compression(gateway_config.compression.as_str());
+    info!(
+        compression = %gateway_config.compression,
+        estimate_wire_bytes = gateway_config.compression_metrics,
+        "Gateway transport compression"
+    );
+
+    let api_version = gateway_config.api_version;
+    metrics.set_discord_api_version(api_version.gateway, api_version.mode.as_str());
+    info!(
+        api_version = api_version.gateway,
+        pinned = api_version.pinned,
+        mode = %api_version.mode,
+        "Discord API version"
+    );
+    metrics.set_shards_per_pool(gateway_config.shards_per_pool);
+
+    // Load runtime feature flags before any gated behaviour starts
+    let flags = flags::FeatureFlags::load(&gateway_config.flags, gateway_config.pool_id).await?;
+    info!(
+        source = gateway_config.flags.source.as_str(),
+        flags = ?flags.snapshot(),
+        "Feature flags loaded"
+    );
+    tokio::spawn(flags::run_refresh(
+        Arc::clone(&flags),
+        gateway_config.flags.clone(),
+        gateway_config.pool_id,
+    ));
 
     // Connect to NATS if configured
-    let nats = if let Some(ref url) = gateway_config.nats_url {
-        match NatsPublisher::connect(url).await {
-            Ok(publisher) => {
-                info!(url, "Connected to NATS");
-                metrics.set_nats_connected(true);
-                Some(publisher)
-            }
-            Err(e) => {
-                error!(error = %e, "Failed to connect to NATS - running in local mode");
-                metrics.set_nats_connected(false);
-                None
-            }
+    let nats = match gateway_config.nats_url {
+        Some(ref url) => connect_nats(url, &gateway_config, &flags, &metrics).await?,
+        None => {
+            info!("No NATS_URL configured - running in local mode");
+            None
         }
-    } else {
-        info!("No NATS_URL configured - running in local mode");
-        None
     };
 
+    // Create missing streams before anything is published to them
+    if let (Some(ref nats), Some(ref specs)) = (&nats, &gateway_config.ensure_streams) {
+        nats::ensure_streams(nats.jetstream(), specs).await?;
+    }
+
+    // Publish events buffered during NATS outages once the connection is back
+    if let Some(nats) = nats.as_ref().filter(|nats| nats.outbox().is_some()) {
+        tokio::spawn(nats::outbox::run_drain(Arc::clone(nats)));
+    }
+
+    // Remove expired dead letters
+    if let Some(nats) = nats.as_ref().filter(|nats| nats.dead_letters().is_some()) {
+        tokio::spawn(nats::dlq::run_gc(Arc::clone(nats)));
+    }
+
+    // Confirm partial publish batches on a timer
+    if let Some(nats) = nats.as_ref().filter(|nats| nats.batch_config().is_some()) {
+        tokio::spawn(nats::batch::run_flush(Arc::clone(nats)));
+    }
+
+    // Count consumer reports on canary copies
+    if let (Some(ref nats), Some(_)) = (&nats, gateway_config.canary) {
+        tokio::spawn(nats::canary::run_results(Arc::clone(nats), Arc::clone(&metrics)));
+    }
+
+    // Export downstream worker backlog next to our own publish metrics
+    if let (Some(ref nats), Some(every)) = (&nats, gateway_config.consumer_lag_interval) {
+        tokio::spawn(nats::lag::run_lag_monitor(Arc::clone(nats), Arc::clone(&metrics), every));
+    }
+
+    // TOTAL_SHARDS=auto: adopt the cluster's recorded count, or record Discord's
+    if gateway_config.discover_shards {
+        let count = shard::discovery::discover(
+            &gateway_config.discord_token,
+            nats.as_deref().map(NatsPublisher::jetstream),
+            events::serialize::now_millis(),
+        )
+        .await?;
+        gateway_config.total_shards = count.total_shards;
+        shard::validate_pool(gateway_config.pool_id, count.total_shards, gateway_config.shards_per_pool)?;
+    }
+
+    // Saved sessions, the identify budget and shared identify slots
+    let identify = Identify::prepare(&gateway_config, None, nats.as_ref()).await?;
+
+    let resharding = gateway_config.resharding.map(|config| Arc::new(Resharding::new(config)));
+    if let Some(ref limit) = gateway_config.guild_rate_limit {
+        info!(
+            rate = limit.default.per_second,
+            burst = limit.default.burst,
+            guild_overrides = limit.guilds.len(),
+            summary_secs = ?limit.summary_window.map(|window| window.as_secs()),
+            "Per-guild event rate limit enabled"
+        );
+    }
+    for rule in gateway_config.event_sampling.rules() {
+        info!(event_type = %rule.event_type, percent = rule.percent, "Event type sampled");
+    }
+
+    // Sampling, filters, breaker threshold and log level, replaceable from NATS KV
+    let overrides = Arc::new(
+        overrides::Overrides::new(gateway_config.event_sampling.clone(), gateway_config.log_level.clone())
+            .with_max_consecutive_errors(gateway_config.circuit_breaker_max_errors)
+            .with_log_reload(Box::new(move |level| {
+                let filter = log_filter(level).map_err(|e| e.to_string())?;
+                log_handle.reload(filter).map_err(|e| e.to_string())
+            })),
+    );
+    if let Some(nats) = nats.as_ref().filter(|_| gateway_config.dynamic_config) {
+        tokio::spawn(overrides::run_watcher(Arc::clone(nats), Arc::clone(&overrides), Arc::clone(&metrics)));
+    }
+
     // Get Discord intents
-    let intents = GatewayConfig::intents();
+    let intents = GatewayConfig::intents(
+        gateway_config.intents,
+        gateway_config.forward_messages,
+        gateway_config.forward_reactions,
+        gateway_config.forward_voice,
+    );
     info!(?intents, "Using Discord intents");
+    let privileged = intents & config::PRIVILEGED_INTENTS;
+    if !privileged.is_empty() {
+        info!(?privileged, "Privileged intents must be enabled in the Discord developer portal");
+    }
+    for (intent, _) in (intents - events::serialize::SERIALIZED_INTENTS).iter_names() {
+        warn!(intent, "Intent enabled, but the gateway doesn't publish its events yet");
+    }
+    info!(
+        capacity = gateway_config.publish_queue.capacity,
+        overflow = gateway_config.publish_queue.overflow.as_str(),
+        "Shard publish queues"
+    );
+
+    // REST client for the calls shards make themselves, and the ones workers make through the proxy
+    let rest = (gateway_config.member_removal_audit
+        || !gateway_config.interaction_defer.is_empty()
+        || gateway_config.rest_proxy
+        || gateway_config.command_sync != SyncMode::Off)
+        .then(|| Arc::new(twilight_http::Client::new(gateway_config.discord_token.clone())));
+
+    // Register the commands workers handle (a bad commands.json is fatal, Discord errors are not)
+    if let Some(client) = rest.clone().filter(|_| gateway_config.command_sync != SyncMode::Off) {
+        let commands = CommandSet::load(gateway_config.commands_file.as_deref())?;
+        info!(mode = gateway_config.command_sync.as_str(), commands = commands.len(), "Syncing application commands");
+        tokio::spawn(discord::commands::run(client, commands, gateway_config.command_sync, Arc::clone(&metrics)));
+    }
+
+    // Member lists for workers, answered by the shard that owns the guild
+    let member_requests =
+        nats.as_ref().map(|nats| Arc::new(MemberRequests::new(Arc::clone(nats), Arc::clone(&metrics))));
+    // Bot presence set by workers
+    let presence = nats.as_ref().map(|nats| Arc::new(PresenceUpdates::new(Arc::clone(nats), Arc::clone(&metrics))));
+    // Guild metadata workers would otherwise fetch from Discord REST
+    let guild_cache = gateway_config.guild_cache.then(|| {
+        let cache = Arc::new(GuildCache::new());
+        let estimate = Arc::clone(&cache);
+        metrics.register_memory_estimator("guild_cache", Arc::new(move || estimate.estimated_bytes()));
+        info!("Caching guilds, channels and roles");
+        cache
+    });
+    // The same state in Redis, for workers without a gateway connection
+    let redis_cache = match gateway_config.redis_cache {
+        Some(ref config) => match cache::redis::RedisCache::connect(config, Arc::clone(&metrics)).await {
+            Ok(cache) => Some(cache),
+            Err(e) => {
+                error!(error = %e, "Failed to connect to Redis - not writing the Redis cache");
+                None
+            }
+        },
+        None => None,
+    };
 
-    // Create shard pool
+    // Create shard pool (its metrics labelled with BOT_ID, if set)
+    let pool_metrics = match gateway_config.bot_id {
+        Some(ref bot_id) => Arc::new(metrics.for_bot(bot_id)),
+        None => Arc::clone(&metrics),
+    };
+    let guild_watch = GuildWatch::start(&gateway_config, nats.as_ref(), &metrics);
     let pool = ShardPool::new(
         gateway_config.pool_id,
         gateway_config.total_shards,
         gateway_config.discord_token.clone(),
         intents,
         nats.clone(),
-        Arc::clone(&metrics),
+        Arc::clone(&pool_metrics),
+        PoolOptions {
+            shards_per_pool: gateway_config.shards_per_pool,
+            bot_id: gateway_config.bot_id.clone(),
+            estimate_wire_bytes: gateway_config.compression_metrics,
+            pacing: gateway_config.identify_pacing,
+            only_shards: gateway_config.only_shards.clone(),
+            flags: Arc::clone(&flags),
+            aggregator: guild_watch.aggregator,
+            raid_detector: guild_watch.raid_detector,
+            guild_limit: guild_watch.guild_limit,
+            resharding: resharding.clone(),
+            overrides: Arc::clone(&overrides),
+            circuit_breaker: gateway_config.circuit_breaker,
+            canary_shard: gateway_config.canary_shard.clone(),
+            identify_limit: identify.limit,
+            coordinator: identify.coordinator,
+            sessions: identify.sessions,
+            publish_queue: gateway_config.publish_queue,
+            removal_audit: rest.clone().filter(|_| gateway_config.member_removal_audit),
+            command_defer: rest.clone().filter(|_| !gateway_config.interaction_defer.is_empty()).map(|client| {
+                info!(commands = ?gateway_config.interaction_defer, "Deferring slash commands before publishing");
+                Arc::new(CommandDefer::new(client, gateway_config.interaction_defer.clone()))
+            }),
+            member_requests: member_requests.clone(),
+            presence: presence.clone(),
+            guild_cache: guild_cache.clone(),
+            redis_cache,
+        },
     )
     .await?;
 
     let pool_state = pool.state();
+    if let Some(ref canary) = gateway_config.canary_shard {
+        if pool_state.snapshot(canary.shard_id).is_some() {
+            info!(shard_id = canary.shard_id, changes = ?canary.changes(), "Running the canary shard");
+        }
+    }
     info!(
         pool_id = gateway_config.pool_id,
         shard_count = pool_state.shard_count(),
         "Shard pool created"
     );
 
+    // Other bots' pools share NATS, flags and overrides; caches, member
+    // requests, presence and the REST features stay with the primary bot
+    let mut bot_pools = Vec::new();
+    for bot in &gateway_config.bots {
+        let bot_pool = start_bot_pool(bot, &gateway_config, nats.as_ref(), &metrics, &flags, &overrides).await?;
+        bot_pools.push((bot.id.clone(), bot_pool));
+    }
+
+    // Flag shards whose received events stop adding up (read by ops alerts)
+    if let Some(divergence) = gateway_config.divergence {
+        tokio::spawn(shard::watchdog::run_watchdog(divergence, pool_state.clone(), Arc::clone(&pool_metrics)));
+    }
+
+    // Ops alerts use direct HTTP, so they work while NATS is down
+    if let Some(notifiers) = alerts::Notifiers::from_config(&gateway_config.ops_alerts)? {
+        info!(
+            webhook = gateway_config.ops_alerts.webhook.is_some(),
+            pager = ?gateway_config.ops_alerts.pager.as_ref().map(|p| p.provider),
+            "Ops alerts configured"
+        );
+        tokio::spawn(alerts::run_monitor(
+            gateway_config.ops_alerts.clone(),
+            notifiers,
+            pool_state.clone(),
+            bot_pools.iter().map(|(bot_id, pool)| (bot_id.clone(), pool.state())).collect(),
+            nats.clone(),
+            gateway_config.nats_url.is_some(),
+            Arc::clone(&metrics),
+        ));
+    }
+
     // Start health server
     let app_state = AppState {
         shard_state: pool_state.clone(),
         nats: nats.clone(),
-        metrics: Arc::clone(&metrics),
+        metrics: pool_metrics,
+        build_info: Arc::new(BuildInfo::new(&gateway_config)),
+        flags,
+        liveness_stale_after: gateway_config.liveness_stale_after,
+        ready_policy: gateway_config.ready_policy,
+        config_summary: Arc::new(gateway_config.summary()),
+        bots: bot_pools.iter().map(|(bot_id, pool)| (bot_id.clone(), pool.state())).collect(),
+    };
+
+    // Push backends are never scraped: export state gauges on a timer
+    if gateway_config.metrics_backend != MetricsBackend::Prometheus {
+        tokio::spawn(health::push_gauges(app_state.clone(), GAUGE_PUSH_INTERVAL));
+    }
+
+    // Same state queries over NATS request-reply, discoverable with `nats micro ls`
+    let nats_service = match nats {
+        Some(ref nats) => match nats::service::start(nats, app_state.clone()).await {
+            Ok(service) => Some(service),
+            Err(e) => {
+                warn!(error = %e, "NATS service registration failed - state queries are HTTP-only");
+                None
+            }
+        },
+        None => None,
     };
 
-    let health_router = health::router(app_state);
+    // Tell workers which shards this pool runs
+    if let (Some(ref nats), Some(every)) = (&nats, gateway_config.topology_interval) {
+        tokio::spawn(nats::topology::run_publisher(Arc::clone(nats), app_state.clone(), every));
+    }
+
+    // Workers' Discord calls, behind one rate limiter
+    if let (Some(ref nats), Some(client)) = (&nats, rest.filter(|_| gateway_config.rest_proxy)) {
+        tokio::spawn(discord::proxy::run(Arc::clone(nats), client, Arc::clone(&metrics)));
+    }
+
+    if let Some(requests) = member_requests {
+        tokio::spawn(nats::backfill::run(Arc::clone(&requests), pool.control(), pool_state.clone(), intents));
+        tokio::spawn(requests.run(pool.control(), pool_state.clone(), intents));
+    }
+    if let Some(presence) = presence {
+        tokio::spawn(presence.run(pool.control()));
+    }
+    if let Some(ref nats) = nats {
+        tokio::spawn(nats::ownership::run(Arc::clone(nats), pool_state.clone()));
+        tokio::spawn(shard::handoff::run(Arc::clone(nats), pool.control(), pool_state.clone(), Arc::clone(&metrics)));
+    }
+    if let (Some(ref nats), Some(ref cache)) = (&nats, &guild_cache) {
+        tokio::spawn(nats::guilds::run(
+            Arc::clone(nats),
+            Arc::clone(cache),
+            pool_state.clone(),
+            Arc::clone(&metrics),
+        ));
+    }
+
+    if let Some((nats, resharding)) = nats.as_ref().zip(resharding.as_ref()) {
+        tokio::spawn(reshard::run_watcher(Arc::clone(nats), Arc::clone(resharding), Arc::clone(&metrics)));
+    }
+
+    // One clock for workers' periodic jobs
+    if let Some(nats) = nats.as_ref().filter(|_| gateway_config.ticks) {
+        tokio::spawn(nats::ticks::run_scheduler(Arc::clone(nats), pool_state.clone()));
+    }
+
+    // New tokens from /admin/token or a changed secret, rolled through the pool
+    let rotation = Arc::new(TokenRotation::new(
+        pool.token(),
+        pool.control(),
+        pool_state.clone(),
+        Arc::clone(&metrics),
+        gateway_config.token_rotation_timeout,
+    ));
+    if let Some(every) = gateway_config.secrets_refresh.filter(|_| gateway_config.discord_token_source.refreshes()) {
+        tokio::spawn(secrets::watch_token(
+            gateway_config.discord_token_source.clone(),
+            gateway_config.discord_token.clone(),
+            Arc::clone(&rotation),
+            Arc::clone(&metrics),
+            every,
+        ));
+    }
+
+    // Shard commands, replays, token rotation and overrides for the admin APIs
+    let admin = admin::control::Admin::new(
+        pool_state.clone(),
+        pool.control(),
+        nats.clone(),
+        Some(rotation),
+        gateway_config.dynamic_config.then(|| Arc::clone(&overrides)),
+    );
+    if let Some(admin_config) = gateway_config.admin_grpc.clone() {
+        let (admin_state, admin) = (app_state.clone(), admin.clone());
+        tokio::spawn(async move {
+            if let Err(e) = admin::serve(admin_config, admin_state, admin).await {
+                error!(error = %e, "Admin gRPC server stopped");
+            }
+        });
+    }
+
+    let mut health_router = health::router(app_state);
+    if let Some(token) = gateway_config.admin_token.clone() {
+        info!("Admin shard commands enabled on /admin/shards");
+        health_router = health_router.merge(admin::http::router(admin, token));
+    }
+    if let Some(cache) = guild_cache {
+        health_router = health_router.merge(cache::http::router(cache, pool_state.clone()));
+    }
+    if gateway_config.dynamic_config {
+        health_router = health_router.merge(overrides::http::router(overrides));
+    }
     let addr: SocketAddr = ([0, 0, 0, 0], gateway_config.http_port).into();
 
     info!(port = gateway_config.http_port, "Starting HTTP server");
 
-    let http_server = axum::serve(
-        tokio::net::TcpListener::bind(addr).await?,
-        health_router,
-    );
+    // Spawned so /ready keeps answering (503) while the pool drains
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    let mut http_server = tokio::spawn(async move { axum::serve(listener, health_router).await });
 
     // Run everything concurrently
-    tokio::select! {
-        result = pool.run() => {
+    let mut bot_runs = tokio::task::JoinSet::new();
+    let mut bot_shutdowns = Vec::new();
+    for (bot_id, bot_pool) in bot_pools {
+        bot_shutdowns.push(bot_pool.shutdown_handle());
+        bot_runs.spawn(async move {
+            if let Err(e) = bot_pool.run().await {
+                error!(bot_id = %bot_id, error = %e, "Bot shard pool error");
+            }
+        });
+    }
+    let pool_shutdown = pool.shutdown_handle();
+    let pool_run = pool.run();
+    tokio::pin!(pool_run);
+    let signalled = tokio::select! {
+        result = &mut pool_run => {
             if let Err(e) = result {
                 error!(error = %e, "Shard pool error");
             }
+            false
         }
-        result = http_server => {
-            if let Err(e) = result {
-                error!(error = %e, "HTTP server error");
+        result = &mut http_server => {
+            match result {
+                Ok(Err(e)) => error!(error = %e, "HTTP server error"),
+                Err(e) => error!(error = %e, "HTTP server task failed"),
+                Ok(Ok(())) => {}
             }
+            false
         }
         _ = shutdown_signal() => {
             info!("Shutdown signal received");
+            true
         }
+    };
+
+    // Drain within SHUTDOWN_TIMEOUT_SECS: report not ready, let the shards
+    // stop between events and save their sessions, then flush what they
+    // published before the NATS connection goes
+    info!(timeout_secs = gateway_config.shutdown_timeout.as_secs(), "Shutting down gateway...");
+    pool_state.set_draining();
+    let deadline = tokio::time::Instant::now() + gateway_config.shutdown_timeout;
+
+    if signalled {
+        let _ = pool_shutdown.send(());
+        if tokio::time::timeout_at(deadline, pool_run).await.is_err() {
+            warn!("Shards did not stop in time - their sessions were not saved");
+        }
+    }
+    for bot_shutdown in bot_shutdowns {
+        let _ = bot_shutdown.send(());
+    }
+    if tokio::time::timeout_at(deadline, async { while bot_runs.join_next().await.is_some() {} })
+        .await
+        .is_err()
+    {
+        warn!("Other bots' shards did not stop in time");
     }
 
-    // Graceful shutdown
-    info!("Shutting down gateway...");
+    let flush = async {
+        if let Some(service) = nats_service {
+            nats::service::stop(service).await;
+        }
 
-    if let Some(ref nats) = nats {
-        nats.close().await;
+        if let Some(ref nats) = nats {
+            if gateway_config.topology_interval.is_some() {
+                if let Err(e) = nats::topology::withdraw(nats, gateway_config.pool_id).await {
+                    warn!(error = %e, "Failed to withdraw topology entry");
+                }
+            }
+            nats.close().await;
+        }
+    };
+    if tokio::time::timeout_at(deadline, flush).await.is_err() {
+        warn!("NATS did not flush in time - pending publishes may be lost");
     }
+    http_server.abort();
 
     info!("Gateway shutdown complete");
+    if let Some(provider) = tracer_provider {
+        telemetry::shutdown(provider).await;
+    }
     Ok(())
 }
 
+/// Connect to NATS with the publisher's options; a failed connection leaves
+/// the pool in local mode
+async fn connect_nats(
+    url: &str,
+    config: &GatewayConfig,
+    flags: &Arc<flags::FeatureFlags>,
+    metrics: &Arc<GatewayMetrics>,
+) -> Result<Option<Arc<NatsPublisher>>> {
+    let canary = config.canary.map(|canary| {
+        info!(percent = canary.percent, format = canary.format.as_str(), "Canary dual-write configured");
+        nats::canary::Canary::new(canary, Arc::clone(flags), Arc::clone(metrics))
+    });
+    let quotas = (!config.publish_budgets.is_empty()).then(|| {
+        info!(budgets = ?config.publish_budgets, "Publish budgets configured");
+        nats::quota::PublishQuotas::new(&config.publish_budgets, Arc::clone(metrics))
+    });
+    let signer = config.signing.as_ref().map(nats::signing::EventSigner::new).transpose()?;
+    if let Some(ref signer) = signer {
+        info!(
+            algorithm = signer.algorithm().as_str(),
+            public_key = signer.public_key(),
+            "Signing published events"
+        );
+    }
+    let encryption = match config.encryption {
+        Some(ref encryption) => Some(nats::encryption::PayloadCipher::load(encryption).await?),
+        None => None,
+    };
+    if let Some(ref cipher) = encryption {
+        info!(subjects = ?cipher.subjects(), "Encrypting payloads on sensitive subjects");
+    }
+    info!(wire_format = config.wire_format.as_str(), "Event wire format");
+    let schema_versions = config.schema_versions;
+    if schema_versions.wire != events::versions::CURRENT || schema_versions.dual_publish.is_some() {
+        warn!(
+            wire = schema_versions.wire,
+            dual_publish = schema_versions.dual_publish,
+            current = events::versions::CURRENT,
+            "Schema migration in progress"
+        );
+        for migration in events::versions::MIGRATIONS {
+            info!(version = migration.version, change = migration.change, "Schema version");
+        }
+    }
+    if let Some(ref raw) = config.raw_passthrough {
+        warn!(event_types = ?raw.event_types, guilds = ?raw.guild_ids, "Raw-event passthrough enabled (debugging)");
+    }
+    let redaction = config.redaction.as_ref().map(events::redaction::Redactor::new).transpose()?;
+    if let Some(ref redaction) = redaction {
+        info!(rules = ?redaction.patterns().collect::<Vec<_>>(), "Redacting PII from event payloads");
+        if config.raw_passthrough.is_some() {
+            warn!("Raw-event passthrough skips event types with a redaction rule");
+        }
+    }
+    if let Some(ref eligibility) = config.eligibility {
+        info!(
+            commands = ?eligibility.commands,
+            claim_prefix = eligibility.claim_prefix.as_deref().unwrap_or(""),
+            "Publishing eligibility checks for wallet-link and role-claim interactions"
+        );
+    }
+    let options = PublisherOptions {
+        event_index_size: config.event_index_size,
+        canary,
+        quotas,
+        raw: config.raw_passthrough.clone(),
+        eligibility: config.eligibility.clone(),
+        redaction,
+        schema: SchemaValidator::new(config.schema_validation, Arc::clone(metrics))?,
+        outbox: config
+            .outbox
+            .clone()
+            .map(|outbox| nats::outbox::Outbox::open(outbox, Arc::clone(metrics)))
+            .transpose()?,
+        retry: config.publish_retry,
+        dead_letters: config
+            .dlq
+            .clone()
+            .map(|dlq| nats::dlq::DeadLetters::open(dlq, Arc::clone(metrics)))
+            .transpose()?,
+        metrics: Some(Arc::clone(metrics)),
+        command_routing: config.command_routing,
+        signer,
+        encryption,
+        wire_format: config.wire_format,
+        schema_versions,
+        flags: Arc::clone(flags),
+        canary_shard: config.canary_shard.clone(),
+        shard_epoch: config.resharding.map(|reshard| reshard.epoch),
+        slow_publish: config.slow_publish,
+        security: config.nats_security.clone(),
+        batch: config.publish_batch.map(|batch| {
+            info!(
+                max_events = batch.max_events,
+                max_delay_ms = batch.max_delay.as_millis() as u64,
+                "Publish acks confirmed in batches"
+            );
+            nats::batch::PublishBatch::new(batch, Arc::clone(metrics))
+        }),
+    };
+    match NatsPublisher::connect(url, options).await {
+        Ok(publisher) => {
+            info!(url, "Connected to NATS");
+            metrics.set_nats_connected(true);
+            Ok(Some(publisher))
+        }
+        Err(e) => {
+            error!(error = %e, "Failed to connect to NATS - running in local mode");
+            metrics.set_nats_connected(false);
+            Ok(None)
+        }
+    }
+}
+
+/// A pool's saved sessions, identify budget and shared identify slots
+struct Identify {
+    sessions: Option<Arc<SessionStore>>,
+    limit: Option<SessionStartLimit>,
+    coordinator: Option<Arc<IdentifyCoordinator>>,
+}
+
+impl Identify {
+    /// For the primary bot's pool, or `bot`'s. Another bot's KV entries are
+    /// kept under its id, since its budget and rate limits are its own.
+    async fn prepare(
+        config: &GatewayConfig,
+        bot: Option<&BotConfig>,
+        nats: Option<&Arc<NatsPublisher>>,
+    ) -> Result<Self> {
+        let bot_id = bot.map(|bot| bot.id.as_str());
+        let js = nats.map(|nats| nats.jetstream());
+
+        // Sessions saved by the previous run, resumed instead of identifying
+        let sessions = match (&config.session_file, js) {
+            _ if !config.resume_sessions => None,
+            (Some(path), _) => Some(Arc::new(SessionStore::File(path.clone()))),
+            (None, Some(js)) => Some(Arc::new(SessionStore::open_kv(js, bot_id).await?)),
+            (None, None) => None,
+        };
+
+        // Check the identify budget before this pool's identify burst
+        let limit = if config.identify_budget_check {
+            check_identify_budget(config, bot, nats.map(Arc::as_ref), sessions.as_deref()).await?
+        } else {
+            None
+        };
+
+        // Share identify rate-limit buckets with the other pools
+        let coordinator = match js {
+            Some(js) if config.identify_coordination => {
+                let max_concurrency = limit.as_ref().map_or(1, |limit| limit.max_concurrency);
+                Some(Arc::new(IdentifyCoordinator::open(js, max_concurrency, bot_id).await?))
+            }
+            _ => None,
+        };
+        Ok(Self { sessions, limit, coordinator })
+    }
+}
+
+/// Event aggregation, raid detection and the per-guild rate limit of one
+/// bot's pool, each flushing its summaries to NATS
+struct GuildWatch {
+    aggregator: Arc<Aggregator>,
+    raid_detector: Option<Arc<RaidDetector>>,
+    guild_limit: Option<Arc<GuildRateLimiter>>,
+}
+
+impl GuildWatch {
+    fn start(config: &GatewayConfig, nats: Option<&Arc<NatsPublisher>>, metrics: &Arc<GatewayMetrics>) -> Self {
+        let aggregator = Arc::new(Aggregator::new(config.aggregate.clone()));
+        let raid_detector = config.raid_detection.map(|raid| Arc::new(RaidDetector::new(raid)));
+        let guild_limit = config.guild_rate_limit.clone().map(|limit| Arc::new(GuildRateLimiter::new(limit)));
+        if let Some(nats) = nats {
+            if !aggregator.is_empty() {
+                tokio::spawn(aggregate::run_flusher(Arc::clone(&aggregator), Arc::clone(nats)));
+            }
+            if let Some(ref detector) = raid_detector {
+                tokio::spawn(raid::run_flusher(Arc::clone(detector), Arc::clone(nats), Arc::clone(metrics)));
+            }
+            if let Some(limit) = guild_limit.as_ref().filter(|limit| limit.summarizes()) {
+                tokio::spawn(aggregate::run_flusher(limit.dropped(), Arc::clone(nats)));
+            }
+        }
+        Self { aggregator, raid_detector, guild_limit }
+    }
+}
+
+/// Create the shard pool of another bot (`BOTS`), with its own identify
+/// budget, sessions, guild tracking and divergence watchdog
+async fn start_bot_pool(
+    bot: &BotConfig,
+    config: &GatewayConfig,
+    nats: Option<&Arc<NatsPublisher>>,
+    metrics: &GatewayMetrics,
+    flags: &Arc<flags::FeatureFlags>,
+    overrides: &Arc<overrides::Overrides>,
+) -> Result<ShardPool> {
+    let intents = GatewayConfig::intents(bot.intents, config.forward_messages, config.forward_reactions, config.forward_voice);
+    let metrics = Arc::new(metrics.for_bot(&bot.id));
+    let identify = Identify::prepare(config, Some(bot), nats).await?;
+    let guild_watch = GuildWatch::start(config, nats, &metrics);
+    let pool = ShardPool::new(
+        bot.pool_id,
+        bot.total_shards,
+        bot.token.clone(),
+        intents,
+        nats.cloned(),
+        Arc::clone(&metrics),
+        PoolOptions {
+            shards_per_pool: bot.shards_per_pool,
+            bot_id: Some(bot.id.clone()),
+            estimate_wire_bytes: config.compression_metrics,
+            pacing: config.identify_pacing,
+            only_shards: None,
+            flags: Arc::clone(flags),
+            aggregator: guild_watch.aggregator,
+            raid_detector: guild_watch.raid_detector,
+            guild_limit: guild_watch.guild_limit,
+            resharding: None,
+            overrides: Arc::clone(overrides),
+            circuit_breaker: config.circuit_breaker,
+            canary_shard: config.canary_shard.clone(),
+            identify_limit: identify.limit,
+            coordinator: identify.coordinator,
+            sessions: identify.sessions,
+            publish_queue: config.publish_queue,
+            removal_audit: None,
+            command_defer: None,
+            member_requests: None,
+            presence: None,
+            guild_cache: None,
+            redis_cache: None,
+        },
+    )
+    .await?;
+
+    if let Some(divergence) = config.divergence {
+        tokio::spawn(shard::watchdog::run_watchdog(divergence, pool.state(), metrics));
+    }
+    info!(
+        bot_id = %bot.id,
+        pool_id = bot.pool_id,
+        shard_count = pool.state().shard_count(),
+        ?intents,
+        "Bot shard pool created"
+    );
+    Ok(pool)
+}
+
+/// Validate large-bot sharding and reserve this pool's identifies (the
+/// primary bot's, or `bot`'s).
+///
+/// If Discord cannot be reached the check is skipped (the shards will surface
+/// the real problem); an exhausted budget or invalid shard count is fatal.
+/// Returns the session start limit for the identify queue.
+async fn check_identify_budget(
+    config: &GatewayConfig,
+    bot: Option<&BotConfig>,
+    nats: Option<&NatsPublisher>,
+    sessions: Option<&SessionStore>,
+) -> Result<Option<SessionStartLimit>> {
+    let (token, pool_id, total_shards, shards_per_pool, only_shards) = match bot {
+        Some(bot) => (&bot.token, bot.pool_id, bot.total_shards, bot.shards_per_pool, None),
+        None => (
+            &config.discord_token,
+            config.pool_id,
+            config.total_shards,
+            config.shards_per_pool,
+            config.only_shards.as_ref(),
+        ),
+    };
+    let client = twilight_http::Client::new(token.clone());
+    let info = match discord::fetch_gateway_bot(&client).await {
+        Ok(info) => info,
+        Err(e) => {
+            warn!(error = %e, "Could not fetch session start limit - skipping identify budget check");
+            return Ok(None);
+        }
+    };
+
+    let limit = &info.session_start_limit;
+    budget::validate_shard_count(total_shards, limit.max_concurrency)?;
+
+    let shards = select_shards(pool_id, total_shards, shards_per_pool, only_shards)?;
+    let now_ms = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_millis() as u64;
+    // Shards with a saved session resume without identifying
+    let resumable = match sessions {
+        Some(store) => store.load(&shards, total_shards, now_ms).await.len(),
+        None => 0,
+    };
+    let identifies = u32::try_from(shards.len() - resumable)?;
+
+    let budget = budget::reserve(
+        nats.map(NatsPublisher::jetstream),
+        limit,
+        identifies,
+        config.identify_budget_reserve,
+        now_ms,
+        bot.map(|bot| bot.id.as_str()),
+    )
+    .await?;
+
+    info!(
+        bot_id = bot.map(|bot| bot.id.as_str()),
+        identifies,
+        resumable,
+        remaining = budget.remaining,
+        total = budget.total,
+        max_concurrency = budget.max_concurrency,
+        "Identify budget checked"
+    );
+    Ok(Some(info.session_start_limit))
+}
+
+/// Install ring as the process-wide rustls crypto provider.
+///
+/// Idempotent: a provider installed earlier (e.g. by a test) is kept.
+pub(crate) fn install_crypto_provider() {
+    let _ = rustls::crypto::ring::default_provider().install_default();
+}
+
+/// Log filter from `LOG_LEVEL` or a `log_level` override: the gateway's own
+/// level (`debug`) or directives (`arrakis_gateway=debug,async_nats=info`),
+/// on top of `RUST_LOG`
+fn log_filter(level: &str) -> Result<tracing_subscriber::EnvFilter, tracing_subscriber::filter::ParseError> {
+    let mut filter = tracing_subscriber::EnvFilter::from_default_env()
+        .add_directive("twilight_gateway=info".parse()?)
+        .add_directive("async_nats=warn".parse()?);
+    for directive in level.split(',').map(str::trim).filter(|directive| !directive.is_empty()) {
+        let directive = if directive.contains('=') {
+            directive.parse()?
+        } else {
+            format!("arrakis_gateway={directive}").parse()?
+        };
+        filter = filter.add_directive(directive);
+    }
+    Ok(filter)
+}
+
 /// Wait for shutdown signal (SIGTERM or SIGINT)
 async fn shutdown_signal() {
     let ctrl_c = async {
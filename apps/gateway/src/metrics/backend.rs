@@ -0,0 +1,173 @@
+//! Metrics backend selection
+//!
+//! Metrics are recorded through the `metrics` facade; the backend decides where
+//! they go. Prometheus (the default) is scraped from `/metrics`. DogStatsD
+//! pushes to a Datadog agent for deployments standardized on Datadog; values
+//! the Prometheus handler computes at scrape time are then pushed on an
+//! interval instead.
+//!
+//! Prometheus renders a histogram without configured buckets as a summary,
+//! which can't be aggregated across pools. The publish latency histograms get
+//! a fine-grained layout reaching into the tail (`LATENCY_BUCKETS`), and
+//! `METRICS_BUCKETS` sets any histogram's boundaries.
+
+use crate::error::GatewayError;
+use metrics_exporter_dogstatsd::DogStatsDBuilder;
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use std::fmt;
+
+/// Default DogStatsD agent address
+pub const DOGSTATSD_DEFAULT_ADDR: &str = "127.0.0.1:8125";
+
+/// Publish latency histograms, bucketed with `LATENCY_BUCKETS` by default
+pub const LATENCY_HISTOGRAMS: &[&str] = &[
+    "gateway_event_route_duration_seconds",
+    "gateway_event_serialize_duration_seconds",
+    "gateway_nats_ack_duration_seconds",
+    "gateway_rest_proxy_duration_seconds",
+];
+
+/// 250µs to 10s, dense around the millisecond range interactions live in
+pub const LATENCY_BUCKETS: &[f64] = &[
+    0.00025, 0.0005, 0.001, 0.002, 0.003, 0.005, 0.0075, 0.01, 0.015, 0.025, 0.05, 0.075, 0.1, 0.25, 0.5, 1.0,
+    2.5, 5.0, 10.0,
+];
+
+/// Bucket boundaries for one histogram (`METRICS_BUCKETS`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistogramBuckets {
+    pub metric: String,
+    /// Upper bounds in ascending order
+    pub bounds: Vec<f64>,
+}
+
+/// Parse `metric=b1,b2,...;metric=...`
+pub fn parse_buckets(spec: &str) -> Result<Vec<HistogramBuckets>, GatewayError> {
+    let invalid = |entry: &str, reason: &str| GatewayError::Config(format!("METRICS_BUCKETS entry {entry:?}: {reason}"));
+    spec.split(';')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (metric, bounds) = entry.split_once('=').ok_or_else(|| invalid(entry, "expected metric=b1,b2,..."))?;
+            let bounds = bounds
+                .split(',')
+                .map(|bound| bound.trim().parse::<f64>().map_err(|_| invalid(entry, "bounds must be numbers")))
+                .collect::<Result<Vec<_>, _>>()?;
+            if bounds.iter().any(|bound| !bound.is_finite()) || bounds.windows(2).any(|pair| pair[0] >= pair[1]) {
+                return Err(invalid(entry, "bounds must be finite and strictly ascending"));
+            }
+            Ok(HistogramBuckets { metric: metric.trim().to_string(), bounds })
+        })
+        .collect()
+}
+
+/// Where metrics are exported
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum MetricsBackend {
+    /// Prometheus exposition on `/metrics`
+    #[default]
+    Prometheus,
+    /// Push to a DogStatsD agent (`host:port` over UDP, or `unix://` / `unixgram://` socket)
+    DogStatsd { addr: String },
+}
+
+impl MetricsBackend {
+    /// Build from `METRICS_BACKEND` and the agent address, validating the address
+    pub fn parse(value: &str, dogstatsd_addr: &str) -> Result<Self, GatewayError> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "prometheus" => Ok(Self::Prometheus),
+            "dogstatsd" | "statsd" => {
+                DogStatsDBuilder::default()
+                    .with_remote_address(dogstatsd_addr)
+                    .map_err(|e| GatewayError::Config(format!("DOGSTATSD_ADDR is invalid ({dogstatsd_addr:?}): {e}")))?;
+                Ok(Self::DogStatsd {
+                    addr: dogstatsd_addr.to_string(),
+                })
+            }
+            other => Err(GatewayError::Config(format!(
+                "METRICS_BACKEND must be prometheus or dogstatsd, got {other:?}"
+            ))),
+        }
+    }
+
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Prometheus => "prometheus",
+            Self::DogStatsd { .. } => "dogstatsd",
+        }
+    }
+
+    /// Install the global recorder. Returns the Prometheus render handle, if
+    /// this backend is scraped. `buckets` override the built-in layouts.
+    pub(super) fn install(&self, session_lifetime_buckets: &[f64], buckets: &[HistogramBuckets]) -> Option<PrometheusHandle> {
+        match self {
+            Self::Prometheus => {
+                let defaults = LATENCY_HISTOGRAMS
+                    .iter()
+                    .map(|metric| (*metric, LATENCY_BUCKETS))
+                    .chain([("gateway_shard_session_lifetime_seconds", session_lifetime_buckets)]);
+                let overrides = buckets.iter().map(|buckets| (buckets.metric.as_str(), buckets.bounds.as_slice()));
+                let builder = defaults
+                    .filter(|(metric, _)| !buckets.iter().any(|buckets| buckets.metric == *metric))
+                    .chain(overrides)
+                    .fold(PrometheusBuilder::new(), |builder, (metric, bounds)| {
+                        builder
+                            .set_buckets_for_metric(Matcher::Full(metric.to_string()), bounds)
+                            .expect("Bucket layouts are non-empty")
+                    });
+                Some(builder.install_recorder().expect("Failed to install Prometheus recorder"))
+            }
+            Self::DogStatsd { addr } => {
+                // Histograms go out as distributions, so the agent computes
+                // percentiles and no bucket layout is needed
+                DogStatsDBuilder::default()
+                    .with_remote_address(addr)
+                    .expect("DOGSTATSD_ADDR validated in config")
+                    .with_telemetry(false)
+                    .install()
+                    .expect("Failed to install DogStatsD recorder");
+                None
+            }
+        }
+    }
+}
+
+impl fmt::Display for MetricsBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_backend() {
+        assert_eq!(MetricsBackend::parse("Prometheus", DOGSTATSD_DEFAULT_ADDR).unwrap(), MetricsBackend::Prometheus);
+        assert_eq!(
+            MetricsBackend::parse("dogstatsd", "10.0.0.5:8125").unwrap(),
+            MetricsBackend::DogStatsd {
+                addr: "10.0.0.5:8125".to_string()
+            }
+        );
+        assert!(MetricsBackend::parse("dogstatsd", "ftp://agent").is_err());
+        assert!(MetricsBackend::parse("graphite", DOGSTATSD_DEFAULT_ADDR).is_err());
+    }
+
+    #[test]
+    fn test_parse_buckets() {
+        assert_eq!(
+            parse_buckets("gateway_event_route_duration_seconds=0.001, 0.01 ,0.1; ").unwrap(),
+            vec![HistogramBuckets {
+                metric: "gateway_event_route_duration_seconds".to_string(),
+                bounds: vec![0.001, 0.01, 0.1],
+            }]
+        );
+        assert!(parse_buckets("").unwrap().is_empty());
+        assert!(parse_buckets("gateway_event_route_duration_seconds").is_err());
+        assert!(parse_buckets("gateway_event_route_duration_seconds=0.1,0.01").is_err(), "descending");
+        assert!(parse_buckets("gateway_event_route_duration_seconds=fast").is_err());
+        assert!(LATENCY_BUCKETS.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+}
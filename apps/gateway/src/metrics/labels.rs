@@ -0,0 +1,50 @@
+//! Cardinality guard for label values that come from events
+//!
+//! Event types and subject families are bounded by the code that produces
+//! them, but a bug or a new payload shape must not be able to mint a time
+//! series per value. The first `max` distinct values pass through; later
+//! ones report as `other`.
+
+use dashmap::DashSet;
+
+/// Label value reported once the limit is reached
+pub const OTHER: &str = "other";
+
+/// Distinct values admitted for one label
+#[derive(Debug)]
+pub struct LabelLimit {
+    max: usize,
+    seen: DashSet<String>,
+}
+
+impl LabelLimit {
+    pub fn new(max: usize) -> Self {
+        Self { max, seen: DashSet::new() }
+    }
+
+    /// The value to record for `value`
+    pub fn label(&self, value: &str) -> String {
+        if self.seen.contains(value) {
+            return value.to_string();
+        }
+        if self.seen.len() < self.max {
+            self.seen.insert(value.to_string());
+            return value.to_string();
+        }
+        OTHER.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_past_the_limit_report_as_other() {
+        let limit = LabelLimit::new(2);
+        assert_eq!(limit.label("member.join"), "member.join");
+        assert_eq!(limit.label("member.leave"), "member.leave");
+        assert_eq!(limit.label("guild.join"), OTHER);
+        assert_eq!(limit.label("member.join"), "member.join", "admitted values keep reporting");
+    }
+}
@@ -0,0 +1,120 @@
+//! Allocator and per-subsystem memory reporting
+//!
+//! With the `jemalloc` feature (default), allocator statistics are read from
+//! jemalloc's mallctl interface. Subsystems that hold buffered data (publish
+//! queues, caches, spools) register an estimator returning their approximate
+//! footprint in bytes. Both are served at `/debug/memory` and exported as gauges.
+
+use dashmap::DashMap;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// Estimator returning a subsystem's approximate memory footprint in bytes
+pub type MemoryEstimator = Arc<dyn Fn() -> u64 + Send + Sync>;
+
+/// Allocator statistics in bytes (see jemalloc `stats.*` mallctls)
+#[derive(Debug, Clone, Serialize)]
+pub struct AllocatorStats {
+    pub backend: &'static str,
+    pub allocated: u64,
+    pub active: u64,
+    pub resident: u64,
+    pub mapped: u64,
+    pub retained: u64,
+}
+
+impl AllocatorStats {
+    /// Stat name/value pairs, used as gauge labels
+    pub fn entries(&self) -> [(&'static str, u64); 5] {
+        [
+            ("allocated", self.allocated),
+            ("active", self.active),
+            ("resident", self.resident),
+            ("mapped", self.mapped),
+            ("retained", self.retained),
+        ]
+    }
+}
+
+/// Full memory report served by `/debug/memory`
+#[derive(Debug, Clone, Serialize)]
+pub struct MemoryReport {
+    /// None when built without the `jemalloc` feature
+    pub allocator: Option<AllocatorStats>,
+    /// Estimated bytes per registered subsystem
+    pub subsystems: BTreeMap<&'static str, u64>,
+}
+
+/// Registry of subsystem memory estimators
+#[derive(Default)]
+pub struct MemoryRegistry {
+    estimators: DashMap<&'static str, MemoryEstimator>,
+}
+
+impl MemoryRegistry {
+    /// Register (or replace) the estimator for a subsystem
+    pub fn register(&self, subsystem: &'static str, estimator: MemoryEstimator) {
+        self.estimators.insert(subsystem, estimator);
+    }
+
+    /// Evaluate every estimator and read allocator stats
+    pub fn report(&self) -> MemoryReport {
+        let subsystems = self
+            .estimators
+            .iter()
+            .map(|entry| (*entry.key(), (entry.value())()))
+            .collect();
+
+        MemoryReport {
+            allocator: allocator_stats(),
+            subsystems,
+        }
+    }
+}
+
+/// Read jemalloc statistics (advancing the stats epoch first)
+#[cfg(feature = "jemalloc")]
+pub fn allocator_stats() -> Option<AllocatorStats> {
+    use tikv_jemalloc_ctl::{epoch, stats};
+
+    epoch::advance().ok()?;
+    Some(AllocatorStats {
+        backend: "jemalloc",
+        allocated: stats::allocated::read().ok()? as u64,
+        active: stats::active::read().ok()? as u64,
+        resident: stats::resident::read().ok()? as u64,
+        mapped: stats::mapped::read().ok()? as u64,
+        retained: stats::retained::read().ok()? as u64,
+    })
+}
+
+/// Allocator statistics are unavailable with the system allocator
+#[cfg(not(feature = "jemalloc"))]
+pub fn allocator_stats() -> Option<AllocatorStats> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_evaluates_registered_estimators() {
+        let registry = MemoryRegistry::default();
+        registry.register("publish_queues", Arc::new(|| 4096));
+        registry.register("cache", Arc::new(|| 0));
+
+        let report = registry.report();
+        assert_eq!(report.subsystems.get("publish_queues"), Some(&4096));
+        assert_eq!(report.subsystems.get("cache"), Some(&0));
+    }
+
+    #[cfg(feature = "jemalloc")]
+    #[test]
+    fn jemalloc_stats_are_readable() {
+        let stats = allocator_stats().expect("jemalloc stats should be readable");
+        assert_eq!(stats.backend, "jemalloc");
+        assert!(stats.resident >= stats.active);
+    }
+}
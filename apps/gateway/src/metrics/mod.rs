@@ -2,33 +2,107 @@
 //!
 //! Sprint S-4: Gateway Metrics per SDD §10.1.1
 
-use metrics::{counter, gauge, histogram, describe_counter, describe_gauge, describe_histogram, Unit};
-use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
+mod backend;
+mod labels;
+mod memory;
+mod queue;
+mod runtime;
+
+use labels::LabelLimit;
+
+pub use backend::{parse_buckets, HistogramBuckets, MetricsBackend, DOGSTATSD_DEFAULT_ADDR};
+pub use memory::{MemoryEstimator, MemoryRegistry, MemoryReport};
+pub use queue::{LaneDepth, PublishQueueStats};
+pub use runtime::runtime_report;
+
+use metrics::{counter, gauge, histogram, describe_counter, describe_gauge, describe_histogram, Label, Unit};
+use metrics_exporter_prometheus::PrometheusHandle;
 use std::sync::Arc;
 use std::time::Duration;
+use crate::nats::lag::ConsumerLag;
 use twilight_model::gateway::event::Event;
 
+/// Distinct `event_type`, `subject` and `stream` label values on the
+/// publish latency histograms before further values report as `other`
+const MAX_EVENT_TYPE_LABELS: usize = 64;
+const MAX_SUBJECT_LABELS: usize = 32;
+
+/// Session lifetime buckets (seconds): 1m to 7d
+const SESSION_LIFETIME_BUCKETS: &[f64] = &[
+    60.0, 300.0, 900.0, 3_600.0, 14_400.0, 43_200.0, 86_400.0, 259_200.0, 604_800.0,
+];
+
 /// Gateway metrics collector
 #[derive(Clone)]
 pub struct GatewayMetrics {
-    handle: Arc<PrometheusHandle>,
+    /// Render handle (Prometheus backend only)
+    handle: Option<Arc<PrometheusHandle>>,
+    publish_queues: Arc<PublishQueueStats>,
+    memory: Arc<MemoryRegistry>,
+    event_types: Arc<LabelLimit>,
+    subjects: Arc<LabelLimit>,
+    streams: Arc<LabelLimit>,
+    /// `bot_id` label on shard and pool series (set when a process runs
+    /// several bots, whose shard IDs overlap)
+    bot_id: Option<Arc<str>>,
 }
 
 impl GatewayMetrics {
-    /// Initialize metrics and return handle
+    /// Initialize metrics with the Prometheus backend
     pub fn new() -> Self {
-        let handle = PrometheusBuilder::new()
-            .install_recorder()
-            .expect("Failed to install Prometheus recorder");
+        Self::with_backend(&MetricsBackend::Prometheus, &[])
+    }
+
+    /// Initialize metrics, installing the backend's global recorder
+    pub fn with_backend(backend: &MetricsBackend, buckets: &[HistogramBuckets]) -> Self {
+        let handle = backend.install(SESSION_LIFETIME_BUCKETS, buckets);
 
         // Register metric descriptions
         Self::register_metrics();
 
+        let publish_queues = Arc::new(PublishQueueStats::default());
+        let memory = Arc::new(MemoryRegistry::default());
+
+        let queues = Arc::clone(&publish_queues);
+        memory.register("publish_queues", Arc::new(move || queues.total_bytes()));
+
+        Self {
+            handle: handle.map(Arc::new),
+            publish_queues,
+            memory,
+            event_types: Arc::new(LabelLimit::new(MAX_EVENT_TYPE_LABELS)),
+            subjects: Arc::new(LabelLimit::new(MAX_SUBJECT_LABELS)),
+            streams: Arc::new(LabelLimit::new(MAX_SUBJECT_LABELS)),
+            bot_id: None,
+        }
+    }
+
+    /// The same collector, labelling shard and pool series with `bot_id`
+    pub fn for_bot(&self, bot_id: &str) -> Self {
         Self {
-            handle: Arc::new(handle),
+            bot_id: Some(Arc::from(bot_id)),
+            ..self.clone()
         }
     }
 
+    /// `shard_id`, plus `bot_id` when set
+    fn shard_labels(&self, shard_id: u64) -> Vec<Label> {
+        self.with_bot(Label::new("shard_id", shard_id.to_string()))
+    }
+
+    /// `pool_id`, plus `bot_id` when set
+    fn pool_labels(&self, pool_id: u64) -> Vec<Label> {
+        self.with_bot(Label::new("pool_id", pool_id.to_string()))
+    }
+
+    fn with_bot(&self, label: Label) -> Vec<Label> {
+        let mut labels = vec![label];
+        if let Some(ref bot_id) = self.bot_id {
+            labels.push(Label::new("bot_id", bot_id.to_string()));
+        }
+        labels
+    }
+
     /// Register metric descriptions
     fn register_metrics() {
         // Event counters
@@ -42,22 +116,184 @@ impl GatewayMetrics {
             Unit::Count,
             "Total events routed to NATS"
         );
+        describe_counter!(
+            "gateway_cohort_events_total",
+            Unit::Count,
+            "Events routed or failed by canary shard cohort (canary or baseline)"
+        );
         describe_counter!(
             "gateway_route_failures_total",
             Unit::Count,
             "Failed event routes to NATS"
         );
+        describe_counter!(
+            "gateway_events_filtered_total",
+            Unit::Count,
+            "Received events deliberately not published to NATS"
+        );
+        describe_counter!(
+            "gateway_interaction_defers_total",
+            Unit::Count,
+            "Deferred responses the gateway sent for slash commands, by outcome"
+        );
+        describe_counter!(
+            "gateway_eligibility_requests_total",
+            Unit::Count,
+            "Eligibility checks requested by wallet-link and role-claim interactions, by source and outcome"
+        );
+        describe_counter!(
+            "gateway_events_sampled_out_total",
+            Unit::Count,
+            "Events left unpublished by EVENT_SAMPLING or a filter override, by event type"
+        );
+        describe_counter!(
+            "gateway_config_overrides_total",
+            Unit::Count,
+            "Runtime config overrides seen in the gateway_config KV bucket, by key and outcome"
+        );
+        describe_counter!(
+            "gateway_secret_reads_total",
+            Unit::Count,
+            "Periodic re-reads of secrets from files, Vault or AWS Secrets Manager, by provider and outcome"
+        );
+        describe_counter!(
+            "gateway_token_rotations_total",
+            Unit::Count,
+            "Discord token rotations requested on /admin/token, by outcome"
+        );
+        describe_counter!(
+            "gateway_command_upserts_total",
+            Unit::Count,
+            "Application commands registered or updated from commands.json, by outcome"
+        );
+        describe_counter!(
+            "gateway_member_requests_total",
+            Unit::Count,
+            "Guild member requests from workers, by outcome"
+        );
+        describe_counter!(
+            "gateway_guild_cache_requests_total",
+            Unit::Count,
+            "Cached guild requests from workers this pool answered, by outcome"
+        );
+        describe_counter!(
+            "gateway_redis_cache_writes_total",
+            Unit::Count,
+            "Batches of Redis cache writes, by outcome"
+        );
+        describe_counter!(
+            "gateway_presence_updates_total",
+            Unit::Count,
+            "Presence updates from workers sent to shards, by outcome"
+        );
+        describe_counter!(
+            "gateway_rest_proxy_requests_total",
+            Unit::Count,
+            "Discord REST calls made for workers through the proxy, by method and outcome"
+        );
+        describe_counter!(
+            "gateway_event_divergence_total",
+            Unit::Count,
+            "Times a shard's received events diverged from routed + filtered + failed"
+        );
         describe_counter!(
             "gateway_errors_total",
             Unit::Count,
             "Total gateway errors"
         );
 
+        // Session churn counters
+        describe_counter!(
+            "gateway_shard_identifies_total",
+            Unit::Count,
+            "Successful identifies (READY received) per shard"
+        );
+        describe_counter!(
+            "gateway_shard_resumes_total",
+            Unit::Count,
+            "Successful session resumes (RESUMED received) per shard"
+        );
+        describe_counter!(
+            "gateway_shard_resume_fallbacks_total",
+            Unit::Count,
+            "Resumes that connected via the default gateway URL instead of resume_gateway_url"
+        );
+        describe_counter!(
+            "gateway_shard_invalid_sessions_total",
+            Unit::Count,
+            "Invalid session notices from Discord per shard"
+        );
+        describe_counter!(
+            "gateway_shard_reconnects_total",
+            Unit::Count,
+            "Reconnects requested by Discord (op 7) per shard"
+        );
+
+        // Transport bytes
+        describe_counter!(
+            "gateway_shard_payload_bytes_total",
+            Unit::Bytes,
+            "Decompressed gateway payload bytes received per shard"
+        );
+        describe_counter!(
+            "gateway_shard_wire_bytes_total",
+            Unit::Bytes,
+            "Estimated transport (compressed) bytes received per shard"
+        );
+        describe_gauge!(
+            "gateway_shard_latency_seconds",
+            Unit::Seconds,
+            "Round trip of the shard's latest Discord heartbeat"
+        );
+        describe_gauge!(
+            "gateway_transport_compression",
+            Unit::Count,
+            "Gateway transport compression codec in use (1=active)"
+        );
+
+        describe_gauge!(
+            "gateway_discord_api_version",
+            Unit::Count,
+            "Discord API version this pool speaks, labelled pinned or canary"
+        );
+
         // Latency histogram
         describe_histogram!(
             "gateway_event_route_duration_seconds",
             Unit::Seconds,
-            "Time to route event to NATS"
+            "Time to route event to NATS, by event type and subject family"
+        );
+        describe_histogram!(
+            "gateway_event_serialize_duration_seconds",
+            Unit::Seconds,
+            "Time to encode an event in the wire format"
+        );
+        describe_histogram!(
+            "gateway_cohort_route_duration_seconds",
+            Unit::Seconds,
+            "Time to route event to NATS, by canary shard cohort (canary or baseline)"
+        );
+        describe_histogram!(
+            "gateway_nats_ack_duration_seconds",
+            Unit::Seconds,
+            "Time from sending a publish to its JetStream ack, by stream"
+        );
+        describe_histogram!(
+            "gateway_rest_proxy_duration_seconds",
+            Unit::Seconds,
+            "Time from a proxied REST request to its reply, including rate limit waits"
+        );
+
+        // Session longevity
+        describe_histogram!(
+            "gateway_shard_session_lifetime_seconds",
+            Unit::Seconds,
+            "Lifetime of ended Discord sessions (READY until invalidated, replaced, or dead)"
+        );
+        describe_gauge!(
+            "gateway_shard_session_uptime_seconds",
+            Unit::Seconds,
+            "Age of each shard's current Discord session"
         );
 
         // Gauges
@@ -66,16 +302,181 @@ impl GatewayMetrics {
             Unit::Count,
             "Number of shards in ready state"
         );
+        describe_gauge!(
+            "gateway_command_drift",
+            Unit::Count,
+            "Commands in commands.json missing or different on Discord after the startup sync, by scope"
+        );
+        describe_gauge!(
+            "gateway_shards_per_pool",
+            Unit::Count,
+            "Configured size of each pool's shard range (SHARDS_PER_POOL)"
+        );
         describe_gauge!(
             "gateway_guilds_total",
             Unit::Count,
             "Total guilds across all shards"
         );
+        describe_gauge!(
+            "gateway_canary_success_ratio",
+            Unit::Count,
+            "Share of canary copies a consumer reported handling (ok / all reports)"
+        );
+        describe_gauge!(
+            "gateway_events_unaccounted",
+            Unit::Count,
+            "Received events not routed, filtered or failed within the divergence window"
+        );
+        describe_gauge!(
+            "gateway_capability_degraded",
+            Unit::Count,
+            "Capabilities lost to a disallowed-intents close (1=degraded)"
+        );
         describe_gauge!(
             "gateway_nats_connected",
             Unit::Count,
             "NATS connection status (1=connected, 0=disconnected)"
         );
+
+        // Publish queue gauges (exported at scrape time)
+        describe_gauge!(
+            "gateway_publish_queue_depth",
+            Unit::Count,
+            "Current depth of each publish queue lane"
+        );
+        describe_gauge!(
+            "gateway_publish_queue_high_watermark",
+            Unit::Count,
+            "Peak publish queue depth since the previous scrape"
+        );
+        describe_counter!(
+            "gateway_publish_queue_overflow_total",
+            Unit::Count,
+            "Events dropped because a publish queue lane was full"
+        );
+        // Worker consumer lag (polled from JetStream)
+        describe_gauge!(
+            "gateway_consumer_up",
+            Unit::Count,
+            "1 when the worker consumer's info was readable on the last poll"
+        );
+        describe_gauge!(
+            "gateway_consumer_pending_messages",
+            Unit::Count,
+            "Messages matching the consumer's filter not yet delivered"
+        );
+        describe_gauge!(
+            "gateway_consumer_ack_pending_messages",
+            Unit::Count,
+            "Messages delivered to the consumer but not yet acknowledged"
+        );
+        describe_gauge!(
+            "gateway_consumer_redelivered_messages",
+            Unit::Count,
+            "Messages redelivered to the consumer at least once"
+        );
+        describe_gauge!(
+            "gateway_consumer_waiting_pulls",
+            Unit::Count,
+            "Pull requests waiting on the consumer"
+        );
+        describe_gauge!(
+            "gateway_consumer_ack_floor_lag",
+            Unit::Count,
+            "Stream sequences between the stream head and the consumer's ack floor"
+        );
+        describe_gauge!(
+            "gateway_publish_quota_utilization",
+            Unit::Count,
+            "Share of a stream's one-second publish budget in use, by dimension (events or bytes; above 1 while shaping)"
+        );
+        describe_counter!(
+            "gateway_publish_over_budget_total",
+            Unit::Count,
+            "Publishes over their stream's budget, by action (shaped or passed for priority types)"
+        );
+        describe_histogram!(
+            "gateway_publish_shaping_delay_seconds",
+            Unit::Seconds,
+            "Delay added to publishes shaped to their stream's budget"
+        );
+        describe_counter!(
+            "gateway_nats_reconnects_total",
+            Unit::Count,
+            "NATS reconnects after a lost connection"
+        );
+        describe_gauge!(
+            "gateway_outbox_events",
+            Unit::Count,
+            "Events buffered in the outbox waiting for NATS"
+        );
+        describe_gauge!(
+            "gateway_outbox_bytes",
+            Unit::Bytes,
+            "Size of the outbox backlog file"
+        );
+        describe_counter!(
+            "gateway_outbox_events_total",
+            Unit::Count,
+            "Outbox entries by outcome (queued, drained, expired, rejected)"
+        );
+        describe_counter!(
+            "gateway_publish_retries_total",
+            Unit::Count,
+            "Publishes retried after a failure"
+        );
+        describe_gauge!(
+            "gateway_dlq_events",
+            Unit::Count,
+            "Events held in the dead-letter queue"
+        );
+        describe_gauge!(
+            "gateway_dlq_bytes",
+            Unit::Bytes,
+            "Size of the dead-letter file"
+        );
+        describe_counter!(
+            "gateway_dlq_events_total",
+            Unit::Count,
+            "Dead-letter entries by outcome (dead_lettered, replayed, rejected)"
+        );
+        describe_counter!(
+            "gateway_schema_violations_total",
+            Unit::Count,
+            "Events failing the wire JSON Schema before publishing, by event type and validation mode"
+        );
+        describe_counter!(
+            "gateway_canary_published_total",
+            Unit::Count,
+            "Canary copies of published events by wire format and outcome"
+        );
+        describe_counter!(
+            "gateway_schema_copies_total",
+            Unit::Count,
+            "Dual-published copies of events in a second schema version by version and outcome"
+        );
+        describe_counter!(
+            "gateway_canary_results_total",
+            Unit::Count,
+            "Consumer reports on canary copies by wire format, consumer, and outcome"
+        );
+        describe_counter!(
+            "gateway_ops_alerts_total",
+            Unit::Count,
+            "Ops alert notifications by alert, sink, and outcome"
+        );
+
+        // Memory gauges (exported at scrape time)
+        describe_gauge!(
+            "gateway_memory_allocator_bytes",
+            Unit::Bytes,
+            "Allocator statistics by stat (jemalloc builds only)"
+        );
+        describe_gauge!(
+            "gateway_memory_subsystem_bytes",
+            Unit::Bytes,
+            "Estimated memory held by each gateway subsystem"
+        );
     }
 
     /// Record an event received
@@ -88,61 +489,305 @@ impl GatewayMetrics {
             Event::MemberRemove(_) => "member_remove",
             Event::MemberUpdate(_) => "member_update",
             Event::InteractionCreate(_) => "interaction_create",
+            Event::MessageCreate(_) => "message_create",
+            Event::MessageUpdate(_) => "message_update",
+            Event::MessageDelete(_) => "message_delete",
+            Event::MessageDeleteBulk(_) => "message_delete_bulk",
+            Event::ReactionAdd(_) => "reaction_add",
+            Event::ReactionRemove(_) => "reaction_remove",
+            Event::ReactionRemoveAll(_) => "reaction_remove_all",
+            Event::VoiceStateUpdate(_) => "voice_state_update",
+            Event::VoiceServerUpdate(_) => "voice_server_update",
+            Event::RoleCreate(_) => "role_create",
+            Event::RoleUpdate(_) => "role_update",
+            Event::RoleDelete(_) => "role_delete",
+            Event::ChannelCreate(_) => "channel_create",
+            Event::ChannelUpdate(_) => "channel_update",
+            Event::ChannelDelete(_) => "channel_delete",
+            Event::ThreadCreate(_) => "thread_create",
+            Event::ThreadUpdate(_) => "thread_update",
+            Event::ThreadDelete(_) => "thread_delete",
+            Event::ThreadListSync(_) => "thread_list_sync",
             Event::Ready(_) => "ready",
             Event::Resumed => "resumed",
+            Event::GatewayInvalidateSession(_) => "invalid_session",
+            Event::GatewayReconnect => "reconnect",
             Event::GatewayHeartbeatAck => "heartbeat_ack",
             _ => "other",
         };
 
-        counter!(
-            "gateway_events_received_total",
-            "shard_id" => shard_id.to_string(),
-            "event_type" => event_type
-        )
+        let mut labels = self.shard_labels(shard_id);
+        labels.push(Label::new("event_type", event_type));
+        counter!("gateway_events_received_total", labels).increment(1);
+    }
+
+    /// Record successful route to NATS. `subject` is reported by family
+    /// (`events.member.join` as `events.member`).
+    pub fn record_route_success(&self, shard_id: u64, event_type: &str, subject: &str, duration: Duration) {
+        counter!("gateway_events_routed_total", self.shard_labels(shard_id))
         .increment(1);
+
+        let family = subject.rsplit_once('.').map_or(subject, |(family, _)| family);
+        let mut labels = self.shard_labels(shard_id);
+        labels.push(Label::new("event_type", self.event_types.label(event_type)));
+        labels.push(Label::new("subject", self.subjects.label(family)));
+        histogram!("gateway_event_route_duration_seconds", labels).record(duration.as_secs_f64());
     }
 
-    /// Record successful route to NATS
-    pub fn record_route_success(&self, shard_id: u64, duration: Duration) {
-        counter!(
-            "gateway_events_routed_total",
-            "shard_id" => shard_id.to_string()
+    /// Record the time to encode an event
+    pub fn record_serialize(&self, event_type: &str, duration: Duration) {
+        histogram!(
+            "gateway_event_serialize_duration_seconds",
+            "event_type" => self.event_types.label(event_type)
         )
-        .increment(1);
+        .record(duration.as_secs_f64());
+    }
 
+    /// Record the time a JetStream ack took, by the stream that stored the event
+    pub fn record_nats_ack(&self, stream: &str, duration: Duration) {
         histogram!(
-            "gateway_event_route_duration_seconds",
-            "shard_id" => shard_id.to_string()
+            "gateway_nats_ack_duration_seconds",
+            "stream" => self.streams.label(stream)
         )
         .record(duration.as_secs_f64());
     }
 
+    /// Count a route by canary shard cohort, with its duration
+    pub fn record_cohort_route(&self, cohort: &'static str, routed: bool, duration: Duration) {
+        let outcome = if routed { "routed" } else { "failed" };
+        counter!("gateway_cohort_events_total", "cohort" => cohort, "outcome" => outcome).increment(1);
+        histogram!("gateway_cohort_route_duration_seconds", "cohort" => cohort).record(duration.as_secs_f64());
+    }
+
     /// Record failed route
     pub fn record_route_failure(&self, shard_id: u64) {
-        counter!(
-            "gateway_route_failures_total",
-            "shard_id" => shard_id.to_string()
-        )
+        counter!("gateway_route_failures_total", self.shard_labels(shard_id))
+        .increment(1);
+    }
+
+    /// Record a received event that was deliberately not published
+    pub fn record_filtered(&self, shard_id: u64) {
+        counter!("gateway_events_filtered_total", self.shard_labels(shard_id))
+        .increment(1);
+    }
+
+    /// Count a deferred response sent for a slash command (`sent` or `failed`)
+    pub fn record_interaction_defer(&self, outcome: &'static str) {
+        counter!("gateway_interaction_defers_total", "outcome" => outcome).increment(1);
+    }
+
+    /// Count an eligibility check request (`command` or `component`;
+    /// `published` or `failed`)
+    pub fn record_eligibility_request(&self, source: &'static str, outcome: &'static str) {
+        counter!("gateway_eligibility_requests_total", "source" => source, "outcome" => outcome).increment(1);
+    }
+
+    /// Count a command upsert (`ok` or `failed`)
+    pub fn record_command_upsert(&self, outcome: &'static str) {
+        counter!("gateway_command_upserts_total", "outcome" => outcome).increment(1);
+    }
+
+    /// Set the commands still drifted in a scope (`global` or a guild ID)
+    pub fn set_command_drift(&self, scope: &str, commands: usize) {
+        gauge!("gateway_command_drift", "scope" => scope.to_string()).set(commands as f64);
+    }
+
+    /// Record a worker's member request (`sent`, `rejected`, `completed` or `expired`)
+    pub fn record_member_request(&self, outcome: &'static str) {
+        counter!("gateway_member_requests_total", "outcome" => outcome).increment(1);
+    }
+
+    /// Record a backfill (`started`, `rejected`, `completed`, `failed` or `expired`)
+    pub fn record_backfill(&self, outcome: &'static str) {
+        counter!("gateway_backfills_total", "outcome" => outcome).increment(1);
+    }
+
+    /// Record a shard handoff, as seen by the source (`handed_off` or `failed`)
+    /// or the target (`adopted` or `rejected`)
+    pub fn record_shard_handoff(&self, role: &'static str, outcome: &'static str) {
+        counter!("gateway_shard_handoffs_total", "role" => role, "outcome" => outcome).increment(1);
+    }
+
+    /// Record members published in `member.sync` pages
+    pub fn record_backfill_members(&self, members: u64) {
+        counter!("gateway_backfill_members_total").increment(members);
+    }
+
+    /// Record a worker's cached guild request (`hit` or `miss`)
+    pub fn record_guild_cache_request(&self, outcome: &'static str) {
+        counter!("gateway_guild_cache_requests_total", "outcome" => outcome).increment(1);
+    }
+
+    /// Record a batch of Redis cache writes for one event (`ok`, `failed` or `dropped`)
+    pub fn record_redis_cache_write(&self, outcome: &'static str) {
+        counter!("gateway_redis_cache_writes_total", "outcome" => outcome).increment(1);
+    }
+
+    /// Record a presence update for a shard (`sent`, `restored`, `unsent` or `invalid`)
+    pub fn record_presence_update(&self, outcome: &'static str) {
+        counter!("gateway_presence_updates_total", "outcome" => outcome).increment(1);
+    }
+
+    /// Record a proxied REST call (outcome `2xx`, `4xx`, `429`, `5xx` or `error`)
+    pub fn record_rest_proxy(&self, method: &'static str, outcome: &'static str, duration: Duration) {
+        counter!("gateway_rest_proxy_requests_total", "method" => method, "outcome" => outcome).increment(1);
+        histogram!("gateway_rest_proxy_duration_seconds", "method" => method).record(duration.as_secs_f64());
+    }
+
+    /// Record an event left unpublished by `EVENT_SAMPLING` or an override
+    pub fn record_sampled_out(&self, event_type: &str) {
+        counter!("gateway_events_sampled_out_total", "event_type" => event_type.to_string()).increment(1);
+    }
+
+    /// Record an event dropped by its guild's rate limit (`GUILD_RATE_LIMIT`)
+    pub fn record_guild_rate_limited(&self, event_type: &str) {
+        counter!("gateway_guild_rate_limited_total", "event_type" => event_type.to_string()).increment(1);
+    }
+
+    /// Record joins left unpublished during a join raid (counted in bursts)
+    pub fn record_raid_join(&self) {
+        counter!("gateway_raid_joins_total").increment(1);
+    }
+
+    /// Record a join raid alert (`started` or `ended`)
+    pub fn record_raid_alert(&self, phase: &'static str) {
+        counter!("gateway_raid_alerts_total", "phase" => phase).increment(1);
+    }
+
+    /// Record an event left to the other layout while resharding
+    pub fn record_reshard_suppressed(&self, event_type: &str) {
+        counter!("gateway_reshard_suppressed_total", "event_type" => event_type.to_string()).increment(1);
+    }
+
+    /// Set how many guilds the resharding bucket assigns to a layout
+    pub fn set_reshard_guilds(&self, epoch: u64, guilds: usize) {
+        gauge!("gateway_reshard_guilds", "epoch" => epoch.to_string()).set(guilds as f64);
+    }
+
+    /// Record a runtime config override (`applied`, `reset` or `rejected`)
+    pub fn record_config_override(&self, key: &str, outcome: &'static str) {
+        let key = if crate::overrides::keys::ALL.contains(&key) { key.to_string() } else { labels::OTHER.to_string() };
+        counter!("gateway_config_overrides_total", "key" => key, "outcome" => outcome).increment(1);
+    }
+
+    /// Record a secret re-read (`ok` or `failed`)
+    pub fn record_secret_read(&self, provider: &'static str, outcome: &'static str) {
+        counter!("gateway_secret_reads_total", "provider" => provider, "outcome" => outcome).increment(1);
+    }
+
+    /// Record a token rotation (`completed`, `failed` or `rejected`)
+    pub fn record_token_rotation(&self, outcome: &'static str) {
+        counter!("gateway_token_rotations_total", "outcome" => outcome).increment(1);
+    }
+
+    /// Record a shard crossing the divergence tolerance
+    pub fn record_event_divergence(&self, shard_id: u64) {
+        counter!("gateway_event_divergence_total", self.shard_labels(shard_id))
         .increment(1);
     }
 
+    /// Set the events unaccounted for over the divergence window
+    pub fn set_events_unaccounted(&self, shard_id: u64, count: u64) {
+        gauge!("gateway_events_unaccounted", self.shard_labels(shard_id))
+        .set(count as f64);
+    }
+
     /// Record gateway error with structured error type label
     pub fn record_error(&self, shard_id: u64, error_type: &str) {
-        counter!(
-            "gateway_errors_total",
-            "shard_id" => shard_id.to_string(),
-            "error_type" => error_type.to_string()
-        )
+        let mut labels = self.shard_labels(shard_id);
+        labels.push(Label::new("error_type", error_type.to_string()));
+        counter!("gateway_errors_total", labels).increment(1);
+    }
+
+    /// Record a shard's circuit breaker moving between `closed`, `open` and
+    /// `half_open`, and set its current state (0 closed, 1 half-open, 2 open)
+    pub fn record_circuit_transition(&self, shard_id: u64, from: &'static str, to: &'static str) {
+        let mut labels = self.shard_labels(shard_id);
+        labels.push(Label::new("from", from));
+        labels.push(Label::new("to", to));
+        counter!("gateway_circuit_transitions_total", labels).increment(1);
+        let state = match to {
+            "open" => 2.0,
+            "half_open" => 1.0,
+            _ => 0.0,
+        };
+        gauge!("gateway_circuit_state", self.shard_labels(shard_id)).set(state);
+    }
+
+    /// Record a successful identify (READY)
+    pub fn record_identify(&self, shard_id: u64) {
+        counter!("gateway_shard_identifies_total", self.shard_labels(shard_id))
+        .increment(1);
+    }
+
+    /// Record a successful resume (RESUMED)
+    pub fn record_resume(&self, shard_id: u64) {
+        counter!("gateway_shard_resumes_total", self.shard_labels(shard_id))
         .increment(1);
     }
 
+    /// Record a resume that fell back to the default gateway URL
+    pub fn record_resume_fallback(&self, shard_id: u64) {
+        counter!("gateway_shard_resume_fallbacks_total", self.shard_labels(shard_id))
+        .increment(1);
+    }
+
+    /// Record an invalid session, labelled by whether Discord allows resuming
+    pub fn record_invalid_session(&self, shard_id: u64, resumable: bool) {
+        let mut labels = self.shard_labels(shard_id);
+        labels.push(Label::new("resumable", resumable.to_string()));
+        counter!("gateway_shard_invalid_sessions_total", labels).increment(1);
+    }
+
+    /// Record a reconnect forced by Discord
+    pub fn record_forced_reconnect(&self, shard_id: u64) {
+        counter!("gateway_shard_reconnects_total", self.shard_labels(shard_id))
+        .increment(1);
+    }
+
+    /// Record a received payload: decompressed bytes and, when estimated,
+    /// transport bytes
+    pub fn record_transport_bytes(&self, shard_id: u64, payload: u64, wire: Option<u64>) {
+        counter!("gateway_shard_payload_bytes_total", self.shard_labels(shard_id))
+        .increment(payload);
+
+        if let Some(wire) = wire {
+            counter!("gateway_shard_wire_bytes_total", self.shard_labels(shard_id))
+            .increment(wire);
+        }
+    }
+
+    /// Flag the transport compression codec in use
+    pub fn set_transport_compression(&self, codec: &'static str) {
+        gauge!("gateway_transport_compression", "codec" => codec).set(1.0);
+    }
+
+    /// Set the Discord API version this pool speaks
+    pub fn set_discord_api_version(&self, version: u8, mode: &'static str) {
+        gauge!("gateway_discord_api_version", "mode" => mode).set(f64::from(version));
+    }
+
+    /// Set the configured shards per pool
+    pub fn set_shards_per_pool(&self, count: u64) {
+        gauge!("gateway_shards_per_pool").set(count as f64);
+    }
+
+    /// Record the lifetime of an ended session
+    pub fn record_session_lifetime(&self, shard_id: u64, lifetime: Duration) {
+        histogram!("gateway_shard_session_lifetime_seconds", self.shard_labels(shard_id))
+        .record(lifetime.as_secs_f64());
+    }
+
+    /// Set the current session uptime for a shard
+    pub fn set_session_uptime(&self, shard_id: u64, uptime: Duration) {
+        gauge!("gateway_shard_session_uptime_seconds", self.shard_labels(shard_id))
+        .set(uptime.as_secs_f64());
+    }
+
     /// Record heartbeat
     pub fn record_heartbeat(&self, shard_id: u64) {
         // Heartbeats are frequent, just update a gauge
-        gauge!(
-            "gateway_last_heartbeat_timestamp",
-            "shard_id" => shard_id.to_string()
-        )
+        gauge!("gateway_last_heartbeat_timestamp", self.shard_labels(shard_id))
         .set(
             std::time::SystemTime::now()
                 .duration_since(std::time::UNIX_EPOCH)
@@ -151,22 +796,27 @@ impl GatewayMetrics {
         );
     }
 
+    /// Set a shard's heartbeat round trip (send to ack)
+    pub fn set_shard_latency(&self, shard_id: u64, latency: Duration) {
+        gauge!("gateway_shard_latency_seconds", self.shard_labels(shard_id)).set(latency.as_secs_f64());
+    }
+
     /// Set guild count for a shard
     pub fn set_guilds(&self, shard_id: u64, count: u64) {
-        gauge!(
-            "gateway_guilds_total",
-            "shard_id" => shard_id.to_string()
-        )
+        gauge!("gateway_guilds_total", self.shard_labels(shard_id))
         .set(count as f64);
     }
 
     /// Set shards ready count
     pub fn set_shards_ready(&self, pool_id: u64, count: usize) {
-        gauge!(
-            "gateway_shards_ready",
-            "pool_id" => pool_id.to_string()
-        )
-        .set(count as f64);
+        gauge!("gateway_shards_ready", self.pool_labels(pool_id)).set(count as f64);
+    }
+
+    /// Flag capabilities (intent names) lost to a disallowed-intents close
+    pub fn set_capability_degraded(&self, capabilities: &[&'static str]) {
+        for capability in capabilities {
+            gauge!("gateway_capability_degraded", "capability" => *capability).set(1.0);
+        }
     }
 
     /// Set NATS connection status
@@ -174,9 +824,187 @@ impl GatewayMetrics {
         gauge!("gateway_nats_connected").set(if connected { 1.0 } else { 0.0 });
     }
 
-    /// Render metrics in Prometheus format
-    pub fn render(&self) -> String {
-        self.handle.render()
+    /// Export a worker consumer's backlog
+    pub fn set_consumer_lag(&self, lag: &ConsumerLag) {
+        let (stream, consumer) = (lag.durable.stream, lag.durable.consumer);
+        gauge!("gateway_consumer_up", "stream" => stream, "consumer" => consumer).set(1.0);
+        gauge!("gateway_consumer_pending_messages", "stream" => stream, "consumer" => consumer)
+            .set(lag.pending as f64);
+        gauge!("gateway_consumer_ack_pending_messages", "stream" => stream, "consumer" => consumer)
+            .set(lag.ack_pending as f64);
+        gauge!("gateway_consumer_redelivered_messages", "stream" => stream, "consumer" => consumer)
+            .set(lag.redelivered as f64);
+        gauge!("gateway_consumer_waiting_pulls", "stream" => stream, "consumer" => consumer)
+            .set(lag.waiting as f64);
+        gauge!("gateway_consumer_ack_floor_lag", "stream" => stream, "consumer" => consumer)
+            .set(lag.ack_floor_lag as f64);
+    }
+
+    /// Flag whether a worker consumer's info could be read
+    pub fn set_consumer_up(&self, stream: &'static str, consumer: &'static str, up: bool) {
+        gauge!("gateway_consumer_up", "stream" => stream, "consumer" => consumer)
+            .set(if up { 1.0 } else { 0.0 });
+    }
+
+    /// Get the depth counters for a publish queue lane
+    pub fn publish_queue_lane(&self, lane: &'static str) -> Arc<LaneDepth> {
+        self.publish_queues.lane(lane)
+    }
+
+    /// Current depth of each publish queue lane
+    pub fn publish_queue_depths(&self) -> Vec<(&'static str, u64)> {
+        self.publish_queues.depths()
+    }
+
+    /// Set how much of a stream's publish budget is in use
+    pub fn set_publish_quota_utilization(&self, stream: &'static str, dimension: &'static str, utilization: f64) {
+        gauge!("gateway_publish_quota_utilization", "stream" => stream, "dimension" => dimension).set(utilization);
+    }
+
+    /// Count a publish over its stream's budget (`action` is `shaped` or `passed`)
+    pub fn record_publish_over_budget(&self, stream: &'static str, action: &'static str) {
+        counter!("gateway_publish_over_budget_total", "stream" => stream, "action" => action).increment(1);
+    }
+
+    /// Record the delay a publish was shaped by
+    pub fn record_publish_shaping_delay(&self, stream: &'static str, delay: Duration) {
+        histogram!("gateway_publish_shaping_delay_seconds", "stream" => stream).record(delay.as_secs_f64());
+    }
+
+    /// Record the size of a batch of publish acks confirmed together
+    pub fn record_publish_batch(&self, events: usize) {
+        histogram!("gateway_publish_batch_events").record(events as f64);
+    }
+
+    /// Count a NATS reconnect after a lost connection
+    pub fn record_nats_reconnect(&self) {
+        counter!("gateway_nats_reconnects_total").increment(1);
+    }
+
+    /// Export the outbox backlog
+    pub fn set_outbox_backlog(&self, events: usize, bytes: u64) {
+        gauge!("gateway_outbox_events").set(events as f64);
+        gauge!("gateway_outbox_bytes").set(bytes as f64);
+    }
+
+    /// Count outbox entries (`queued`, `drained`, `undeliverable`, `expired`
+    /// or `rejected`)
+    pub fn record_outbox_events(&self, outcome: &'static str, count: u64) {
+        counter!("gateway_outbox_events_total", "outcome" => outcome).increment(count);
+    }
+
+    /// Count bytes compaction removed from the outbox file
+    pub fn record_outbox_reclaimed(&self, bytes: u64) {
+        counter!("gateway_outbox_reclaimed_bytes_total").increment(bytes);
+    }
+
+    /// Count a retried publish
+    pub fn record_publish_retry(&self) {
+        counter!("gateway_publish_retries_total").increment(1);
+    }
+
+    /// Export the dead-letter queue depth
+    pub fn set_dlq_depth(&self, events: usize, bytes: u64) {
+        gauge!("gateway_dlq_events").set(events as f64);
+        gauge!("gateway_dlq_bytes").set(bytes as f64);
+    }
+
+    /// Count dead-letter entries (`dead_lettered`, `replayed`, `expired`,
+    /// `evicted` or `rejected`)
+    pub fn record_dlq_events(&self, outcome: &'static str, count: u64) {
+        counter!("gateway_dlq_events_total", "outcome" => outcome).increment(count);
+    }
+
+    /// Count bytes compaction removed from the dead-letter file
+    pub fn record_dlq_reclaimed(&self, bytes: u64) {
+        counter!("gateway_dlq_reclaimed_bytes_total").increment(bytes);
+    }
+
+    /// Count an event failing the wire schema (`mode` is `warn` or `enforce`)
+    pub fn record_schema_violation(&self, event_type: &str, mode: &'static str) {
+        counter!(
+            "gateway_schema_violations_total",
+            "event_type" => event_type.to_string(),
+            "mode" => mode
+        )
+        .increment(1);
+    }
+
+    /// Count a canary copy (`outcome` is `published` or `failed`)
+    pub fn record_canary_publish(&self, format: &'static str, outcome: &'static str) {
+        counter!("gateway_canary_published_total", "format" => format, "outcome" => outcome).increment(1);
+    }
+
+    /// Count a dual-published schema copy (`outcome` is `published` or `failed`)
+    pub fn record_schema_copy(&self, version: u32, outcome: &'static str) {
+        counter!("gateway_schema_copies_total", "version" => version.to_string(), "outcome" => outcome).increment(1);
+    }
+
+    /// Count a consumer's report on a canary copy (`outcome` is `ok` or `rejected`)
+    pub fn record_canary_result(&self, format: &'static str, consumer: &'static str, outcome: &'static str) {
+        counter!(
+            "gateway_canary_results_total",
+            "format" => format,
+            "consumer" => consumer,
+            "outcome" => outcome
+        )
+        .increment(1);
+    }
+
+    /// Set a consumer's canary success ratio
+    pub fn set_canary_success_ratio(&self, format: &'static str, consumer: &'static str, ratio: f64) {
+        gauge!("gateway_canary_success_ratio", "format" => format, "consumer" => consumer).set(ratio);
+    }
+
+    /// Count an ops alert notification (`outcome` is `sent` or `failed`)
+    pub fn record_ops_alert(&self, alert: &'static str, sink: &'static str, outcome: &'static str) {
+        counter!("gateway_ops_alerts_total", "alert" => alert, "sink" => sink, "outcome" => outcome)
+            .increment(1);
+    }
+
+    /// Export publish queue depth, high-watermark, and overflow drops.
+    ///
+    /// Called once per scrape: high-watermarks reset on every call.
+    pub fn export_publish_queues(&self) {
+        for lane in self.publish_queues.snapshot() {
+            gauge!("gateway_publish_queue_depth", "lane" => lane.lane)
+                .set(lane.depth as f64);
+            gauge!("gateway_publish_queue_high_watermark", "lane" => lane.lane)
+                .set(lane.high_watermark as f64);
+            counter!("gateway_publish_queue_overflow_total", "lane" => lane.lane)
+                .absolute(lane.overflow_drops);
+        }
+    }
+
+    /// Register a memory estimator for a subsystem (cache, spool, ...)
+    pub fn register_memory_estimator(&self, subsystem: &'static str, estimator: MemoryEstimator) {
+        self.memory.register(subsystem, estimator);
+    }
+
+    /// Build the allocator and per-subsystem memory report
+    pub fn memory_report(&self) -> MemoryReport {
+        self.memory.report()
+    }
+
+    /// Export allocator and subsystem memory gauges
+    pub fn export_memory(&self) {
+        let report = self.memory_report();
+
+        if let Some(ref allocator) = report.allocator {
+            for (stat, bytes) in allocator.entries() {
+                gauge!("gateway_memory_allocator_bytes", "stat" => stat).set(bytes as f64);
+            }
+        }
+
+        for (subsystem, bytes) in report.subsystems {
+            gauge!("gateway_memory_subsystem_bytes", "subsystem" => subsystem)
+                .set(bytes as f64);
+        }
+    }
+
+    /// Render metrics in Prometheus format (None when pushing to DogStatsD)
+    pub fn render(&self) -> Option<String> {
+        self.handle.as_ref().map(|handle| handle.render())
     }
 }
 
@@ -0,0 +1,156 @@
+//! Publish queue depth tracking
+//!
+//! Tracks per-lane depth, high-watermark, and overflow drops for the bounded
+//! publish queues between the shard loops and NATS. Lanes are updated on the
+//! hot path with atomics only; values are exported to Prometheus at scrape time.
+
+use dashmap::DashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Depth counters for a single publish queue lane
+#[derive(Debug, Default)]
+pub struct LaneDepth {
+    depth: AtomicU64,
+    bytes: AtomicU64,
+    high_watermark: AtomicU64,
+    overflow_drops: AtomicU64,
+}
+
+impl LaneDepth {
+    /// Record an item of `bytes` payload size entering the queue
+    pub fn record_enqueue(&self, bytes: usize) {
+        let depth = self.depth.fetch_add(1, Ordering::Relaxed) + 1;
+        self.bytes.fetch_add(bytes as u64, Ordering::Relaxed);
+        self.high_watermark.fetch_max(depth, Ordering::Relaxed);
+    }
+
+    /// Record an item of `bytes` payload size leaving the queue
+    pub fn record_dequeue(&self, bytes: usize) {
+        let _ = self
+            .depth
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |d| Some(d.saturating_sub(1)));
+        let _ = self.bytes.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |b| {
+            Some(b.saturating_sub(bytes as u64))
+        });
+    }
+
+    /// Record an item dropped because the queue was full
+    pub fn record_overflow(&self) {
+        self.overflow_drops.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Current queue depth
+    pub fn depth(&self) -> u64 {
+        self.depth.load(Ordering::Relaxed)
+    }
+
+    /// Payload bytes currently queued
+    pub fn bytes(&self) -> u64 {
+        self.bytes.load(Ordering::Relaxed)
+    }
+
+    /// Total overflow drops since startup
+    pub fn overflow_drops(&self) -> u64 {
+        self.overflow_drops.load(Ordering::Relaxed)
+    }
+
+    /// Return the high-watermark since the last call and reset it to the
+    /// current depth, so each scrape reports the peak within its interval.
+    pub fn take_high_watermark(&self) -> u64 {
+        self.high_watermark
+            .swap(self.depth.load(Ordering::Relaxed), Ordering::Relaxed)
+    }
+}
+
+/// Point-in-time view of a lane, taken at scrape
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LaneSnapshot {
+    pub lane: &'static str,
+    pub depth: u64,
+    pub high_watermark: u64,
+    pub overflow_drops: u64,
+}
+
+/// Registry of publish queue lanes
+#[derive(Debug, Default)]
+pub struct PublishQueueStats {
+    lanes: DashMap<&'static str, Arc<LaneDepth>>,
+}
+
+impl PublishQueueStats {
+    /// Get (or create) the counters for a lane
+    pub fn lane(&self, name: &'static str) -> Arc<LaneDepth> {
+        Arc::clone(&self.lanes.entry(name).or_default())
+    }
+
+    /// Payload bytes queued across all lanes
+    pub fn total_bytes(&self) -> u64 {
+        self.lanes.iter().map(|entry| entry.bytes()).sum()
+    }
+
+    /// Current depth of every lane (leaves high-watermarks untouched)
+    pub fn depths(&self) -> Vec<(&'static str, u64)> {
+        self.lanes.iter().map(|entry| (*entry.key(), entry.depth())).collect()
+    }
+
+    /// Snapshot every lane, resetting high-watermarks
+    pub fn snapshot(&self) -> Vec<LaneSnapshot> {
+        let mut snapshots: Vec<LaneSnapshot> = self
+            .lanes
+            .iter()
+            .map(|entry| LaneSnapshot {
+                lane: entry.key(),
+                depth: entry.depth(),
+                high_watermark: entry.take_high_watermark(),
+                overflow_drops: entry.overflow_drops(),
+            })
+            .collect();
+        snapshots.sort_by_key(|s| s.lane);
+        snapshots
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn high_watermark_tracks_peak_and_resets_to_current_depth() {
+        let lane = LaneDepth::default();
+        lane.record_enqueue(100);
+        lane.record_enqueue(200);
+        lane.record_enqueue(300);
+        lane.record_dequeue(100);
+        lane.record_dequeue(200);
+
+        assert_eq!(lane.depth(), 1);
+        assert_eq!(lane.bytes(), 300);
+        assert_eq!(lane.take_high_watermark(), 3);
+        // Next interval starts from the current depth, not zero
+        assert_eq!(lane.take_high_watermark(), 1);
+    }
+
+    #[test]
+    fn dequeue_never_underflows() {
+        let lane = LaneDepth::default();
+        lane.record_dequeue(64);
+        assert_eq!(lane.depth(), 0);
+        assert_eq!(lane.bytes(), 0);
+    }
+
+    #[test]
+    fn snapshot_covers_all_lanes_in_order() {
+        let stats = PublishQueueStats::default();
+        stats.lane("events").record_enqueue(512);
+        stats.lane("commands").record_overflow();
+
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.len(), 2);
+        assert_eq!(snapshot[0].lane, "commands");
+        assert_eq!(snapshot[0].overflow_drops, 1);
+        assert_eq!(snapshot[1].lane, "events");
+        assert_eq!(snapshot[1].depth, 1);
+        assert_eq!(stats.total_bytes(), 512);
+    }
+}
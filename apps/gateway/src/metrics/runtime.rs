@@ -0,0 +1,103 @@
+//! Tokio runtime statistics for `/debug/tasks`
+//!
+//! Task counts, queue depths and per-worker busy time, for telling an
+//! event-loop stall (busy workers, deep queues) from a slow dependency when a
+//! pool falls behind. Poll counts and times, spawned tasks and blocking-pool
+//! figures need a build with `RUSTFLAGS="--cfg tokio_unstable"` (also required
+//! by the `tokio-console` feature) and are unset otherwise.
+
+use serde::Serialize;
+use tokio::runtime::Handle;
+
+/// One worker thread's statistics
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkerStats {
+    pub worker: usize,
+    /// Time spent running tasks since the runtime started
+    pub busy_seconds: f64,
+    /// Times the worker ran out of work and parked
+    pub park_count: u64,
+    pub poll_count: Option<u64>,
+    /// Moving average of task poll times
+    pub mean_poll_time_us: Option<f64>,
+    pub local_queue_depth: Option<usize>,
+}
+
+/// Full runtime report served by `/debug/tasks`
+#[derive(Debug, Clone, Serialize)]
+pub struct RuntimeReport {
+    pub workers: usize,
+    /// Tasks spawned and not yet finished
+    pub alive_tasks: usize,
+    /// Tasks waiting in the shared injection queue
+    pub global_queue_depth: usize,
+    /// Built with `--cfg tokio_unstable`
+    pub unstable_metrics: bool,
+    pub spawned_tasks: Option<u64>,
+    pub blocking_threads: Option<usize>,
+    pub blocking_queue_depth: Option<usize>,
+    pub worker_stats: Vec<WorkerStats>,
+}
+
+/// Statistics of the runtime this is called from
+pub fn runtime_report() -> RuntimeReport {
+    let metrics = Handle::current().metrics();
+    let workers = metrics.num_workers();
+
+    let worker_stats = (0..workers)
+        .map(|worker| WorkerStats {
+            worker,
+            busy_seconds: metrics.worker_total_busy_duration(worker).as_secs_f64(),
+            park_count: metrics.worker_park_count(worker),
+            #[cfg(tokio_unstable)]
+            poll_count: Some(metrics.worker_poll_count(worker)),
+            #[cfg(not(tokio_unstable))]
+            poll_count: None,
+            #[cfg(tokio_unstable)]
+            mean_poll_time_us: Some(metrics.worker_mean_poll_time(worker).as_secs_f64() * 1e6),
+            #[cfg(not(tokio_unstable))]
+            mean_poll_time_us: None,
+            #[cfg(tokio_unstable)]
+            local_queue_depth: Some(metrics.worker_local_queue_depth(worker)),
+            #[cfg(not(tokio_unstable))]
+            local_queue_depth: None,
+        })
+        .collect();
+
+    RuntimeReport {
+        workers,
+        alive_tasks: metrics.num_alive_tasks(),
+        global_queue_depth: metrics.global_queue_depth(),
+        unstable_metrics: cfg!(tokio_unstable),
+        #[cfg(tokio_unstable)]
+        spawned_tasks: Some(metrics.spawned_tasks_count()),
+        #[cfg(not(tokio_unstable))]
+        spawned_tasks: None,
+        #[cfg(tokio_unstable)]
+        blocking_threads: Some(metrics.num_blocking_threads()),
+        #[cfg(not(tokio_unstable))]
+        blocking_threads: None,
+        #[cfg(tokio_unstable)]
+        blocking_queue_depth: Some(metrics.blocking_queue_depth()),
+        #[cfg(not(tokio_unstable))]
+        blocking_queue_depth: None,
+        worker_stats,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn reports_every_worker() {
+        let task = tokio::spawn(std::future::pending::<()>());
+        let report = runtime_report();
+        task.abort();
+
+        assert_eq!(report.workers, 2);
+        assert_eq!(report.worker_stats.len(), 2);
+        assert!(report.alive_tasks >= 1);
+        assert_eq!(report.unstable_metrics, report.spawned_tasks.is_some());
+    }
+}
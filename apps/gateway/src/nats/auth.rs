@@ -0,0 +1,219 @@
+//! NATS authentication and TLS
+//!
+//! At most one authentication method: a `.creds` file (user JWT and NKey
+//! seed, `NATS_CREDS`), an NKey seed (`NATS_NKEY`), `NATS_USER` with
+//! `NATS_PASSWORD`, or `NATS_TOKEN`. Each secret is a `SecretSource`, so it
+//! can come from a file, Vault or AWS Secrets Manager like `DISCORD_TOKEN`.
+//! It is read again on every (re)connect, so a rotated secret is used on the
+//! next reconnect without a restart.
+//! TLS takes a CA bundle (`NATS_TLS_CA`) and, for mutual TLS, a client
+//! certificate (`NATS_TLS_CERT`, `NATS_TLS_KEY`). They are loaded into the
+//! TLS config in memory; the private key is never written to disk.
+
+use crate::error::GatewayError;
+use crate::secrets::SecretSource;
+use async_nats::rustls::pki_types::pem::PemObject;
+use async_nats::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use async_nats::rustls::{self, ClientConfig, RootCertStore};
+use async_nats::{Auth, AuthError, ConnectOptions};
+use nkeys::KeyPair;
+use std::sync::Arc;
+
+/// How the gateway authenticates to the NATS server
+#[derive(Debug, Clone)]
+pub enum NatsAuth {
+    /// A `.creds` file's contents: the user JWT and its NKey seed
+    Credentials(SecretSource),
+    /// An NKey seed the server knows the public key of
+    NKey(SecretSource),
+    UserPassword { user: String, password: SecretSource },
+    Token(SecretSource),
+}
+
+impl NatsAuth {
+    /// Method name, for logs and `/status`
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Credentials(_) => "creds",
+            Self::NKey(_) => "nkey",
+            Self::UserPassword { .. } => "user_password",
+            Self::Token(_) => "token",
+        }
+    }
+}
+
+/// Authentication and TLS for the NATS connection
+#[derive(Debug, Clone, Default)]
+pub struct NatsSecurity {
+    /// None connects anonymously (or with credentials in the URL)
+    pub auth: Option<NatsAuth>,
+    /// Use TLS even for `nats://` URLs (`NATS_TLS`)
+    pub require_tls: bool,
+    /// CA bundle the server certificate is verified against (PEM)
+    pub ca: Option<SecretSource>,
+    /// Client certificate chain and private key (PEM) for mutual TLS
+    pub client_cert: Option<(SecretSource, SecretSource)>,
+}
+
+impl NatsSecurity {
+    /// Whether the connection must use TLS: a `tls://` URL, `NATS_TLS` or a
+    /// client certificate
+    pub fn requires_tls(&self, servers: &str) -> bool {
+        self.require_tls || self.client_cert.is_some() || servers.contains("tls://")
+    }
+
+    /// Connection options that authenticate and set up TLS. The credentials
+    /// are read here, so a bad secret fails startup, and then again for
+    /// every connection attempt.
+    pub(super) async fn connect_options(&self) -> Result<ConnectOptions, GatewayError> {
+        let mut opts = match self.auth {
+            None => ConnectOptions::new(),
+            Some(ref auth) => {
+                auth.sign(&[]).await?;
+                let auth = Arc::new(auth.clone());
+                ConnectOptions::with_auth_callback(move |nonce| {
+                    let auth = Arc::clone(&auth);
+                    async move {
+                        // On a task of its own: async-nats wants a Sync future,
+                        // and the secret backends' aren't
+                        let signed = tokio::spawn(async move { auth.sign(&nonce).await });
+                        signed.await.map_err(AuthError::new)?.map_err(AuthError::new)
+                    }
+                })
+            }
+        };
+        if self.ca.is_some() || self.client_cert.is_some() {
+            opts = opts.tls_client_config(self.tls_config().await?);
+        }
+        Ok(opts)
+    }
+
+    /// TLS config trusting `NATS_TLS_CA` (else the system roots), with the
+    /// client certificate if one is set
+    async fn tls_config(&self) -> Result<ClientConfig, GatewayError> {
+        let mut roots = RootCertStore::empty();
+        match self.ca {
+            Some(ref ca) => {
+                for cert in certificates("NATS_TLS_CA", &ca.read("NATS_TLS_CA").await?)? {
+                    roots
+                        .add(cert)
+                        .map_err(|e| GatewayError::Config(format!("NATS_TLS_CA holds an unusable certificate: {e}")))?;
+                }
+            }
+            None => {
+                roots.add_parsable_certificates(rustls_native_certs::load_native_certs().certs);
+            }
+        }
+        let builder = ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .map_err(|e| GatewayError::Config(format!("NATS TLS: {e}")))?
+            .with_root_certificates(roots);
+        let Some((ref cert, ref key)) = self.client_cert else {
+            return Ok(builder.with_no_client_auth());
+        };
+        let chain = certificates("NATS_TLS_CERT", &cert.read("NATS_TLS_CERT").await?)?;
+        let key = PrivateKeyDer::from_pem_slice(key.read("NATS_TLS_KEY").await?.as_bytes())
+            .map_err(|e| GatewayError::Config(format!("NATS_TLS_KEY is not a PEM private key: {e}")))?;
+        builder
+            .with_client_auth_cert(chain, key)
+            .map_err(|e| GatewayError::Config(format!("NATS_TLS_CERT does not match NATS_TLS_KEY: {e}")))
+    }
+}
+
+impl NatsAuth {
+    /// Read the secret and answer the server's nonce with it
+    async fn sign(&self, nonce: &[u8]) -> Result<Auth, GatewayError> {
+        let mut auth = Auth::new();
+        match self {
+            Self::Credentials(creds) => {
+                let creds = creds.read("NATS_CREDS").await?;
+                let invalid = || GatewayError::Config("NATS_CREDS is not a valid creds file".to_string());
+                let (jwt, seed) = parse_creds(&creds).ok_or_else(invalid)?;
+                let key_pair = KeyPair::from_seed(seed).map_err(|_| invalid())?;
+                auth.jwt = Some(jwt.to_string());
+                auth.signature = Some(key_pair.sign(nonce).map_err(|_| invalid())?);
+            }
+            Self::NKey(seed) => {
+                let invalid = || GatewayError::Config("NATS_NKEY is not a valid NKey seed".to_string());
+                let key_pair = KeyPair::from_seed(seed.read("NATS_NKEY").await?.trim()).map_err(|_| invalid())?;
+                auth.nkey = Some(key_pair.public_key());
+                auth.signature = Some(key_pair.sign(nonce).map_err(|_| invalid())?);
+            }
+            Self::UserPassword { user, password } => {
+                auth.username = Some(user.clone());
+                auth.password = Some(password.read("NATS_PASSWORD").await?);
+            }
+            Self::Token(token) => auth.token = Some(token.read("NATS_TOKEN").await?),
+        }
+        Ok(auth)
+    }
+}
+
+/// The user JWT and NKey seed of a `.creds` file: each is the first line
+/// after its `-----BEGIN ...-----` marker
+fn parse_creds(creds: &str) -> Option<(&str, &str)> {
+    let after = |marker: &str| {
+        let mut lines = creds.lines().map(str::trim).skip_while(|line| !(line.starts_with("---") && line.contains(marker)));
+        lines.nth(1).filter(|line| !line.is_empty() && !line.starts_with("---"))
+    };
+    Some((after("BEGIN NATS USER JWT")?, after("BEGIN USER NKEY SEED")?))
+}
+
+/// The certificates of a PEM bundle (at least one)
+fn certificates(name: &str, pem: &str) -> Result<Vec<CertificateDer<'static>>, GatewayError> {
+    let certs = CertificateDer::pem_slice_iter(pem.as_bytes())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| GatewayError::Config(format!("{name} is not PEM: {e}")))?;
+    if certs.is_empty() {
+        return Err(GatewayError::Config(format!("{name} holds no certificate")));
+    }
+    Ok(certs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tls_is_required_by_url_flag_or_client_certificate() {
+        let mut security = NatsSecurity::default();
+        assert!(!security.requires_tls("nats://nats-0:4222"));
+        assert!(security.requires_tls("nats://nats-0:4222,tls://nats-1:4222"));
+
+        security.client_cert = Some((SecretSource::File("cert.pem".into()), SecretSource::File("key.pem".into())));
+        assert!(security.requires_tls("nats://nats-0:4222"));
+    }
+
+    #[test]
+    fn creds_files_are_parsed() {
+        let creds = "-----BEGIN NATS USER JWT-----\neyJ0eXAi.eyJqdGki.sig\n------END NATS USER JWT------\n\n\
+                     ************************* IMPORTANT *************************\n\n\
+                     -----BEGIN USER NKEY SEED-----\nSUAEXAMPLE\n------END USER NKEY SEED------\n";
+        assert_eq!(parse_creds(creds), Some(("eyJ0eXAi.eyJqdGki.sig", "SUAEXAMPLE")));
+        assert_eq!(parse_creds("-----BEGIN NATS USER JWT-----\neyJ0eXAi\n"), None);
+    }
+
+    #[tokio::test]
+    async fn nkeys_sign_the_server_nonce() {
+        let key_pair = KeyPair::new_user();
+        let auth = NatsAuth::NKey(SecretSource::Inline(key_pair.seed().unwrap()));
+        let signed = auth.sign(b"nonce").await.unwrap();
+        assert_eq!(signed.nkey, Some(key_pair.public_key()));
+        assert!(key_pair.verify(b"nonce", &signed.signature.unwrap()).is_ok());
+
+        let invalid = NatsAuth::NKey(SecretSource::Inline("SUAnotaseed".to_string()));
+        assert!(matches!(invalid.sign(b"nonce").await, Err(GatewayError::Config(_))));
+    }
+
+    #[tokio::test]
+    async fn pems_are_checked_in_memory() {
+        let security = NatsSecurity {
+            ca: Some(SecretSource::Inline("not a certificate".to_string())),
+            ..NatsSecurity::default()
+        };
+        let Err(GatewayError::Config(reason)) = security.connect_options().await else {
+            panic!("a CA without certificates must be rejected");
+        };
+        assert!(reason.contains("NATS_TLS_CA"), "{reason}");
+    }
+}
@@ -0,0 +1,223 @@
+//! Full guild member sync on demand
+//!
+//! Onboarding a guild used to take a separate script with its own bot
+//! connection to list the guild's members. Instead, a worker sends
+//! `{ "guild_id": ... }` (`fixtures/backfill-request.json`) as a request on
+//! `gateway.requests.backfill`. The pool running the guild's shard answers
+//! with `{ job_id, guild_id, shard_id }`, asks Discord for every member over
+//! that shard (as `members` does) and publishes each chunk Discord answers
+//! with as a `member.sync` event on `events.member.sync`: one page of members,
+//! in order, with `page` and `page_count`. Pages are published from the
+//! shard's pipeline, not its read loop, so a guild of hundreds of chunks
+//! doesn't hold up the shard's heartbeats.
+//!
+//! After each page, the job's progress is published on
+//! `gateway.backfill.progress` (`fixtures/backfill-progress.json`), ending in
+//! `completed`, `failed` (a page couldn't be published; the rest are dropped)
+//! or `expired` (Discord stopped answering). A request the shard can't serve
+//! gets `{ "error": ... }`. Other pools ignore the request.
+
+use super::members::MemberRequests;
+use super::requests::{self, Listener, Request};
+use super::NatsPublisher;
+use crate::events::entities;
+use crate::events::serialize::{now_millis, GatewayEvent};
+use crate::metrics::GatewayMetrics;
+use crate::shard::control::ShardControl;
+use crate::shard::{shard_for_guild, ShardState};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tracing::{debug, info, warn};
+use twilight_gateway::Intents;
+use twilight_model::gateway::payload::incoming::MemberChunk;
+use twilight_model::id::marker::GuildMarker;
+use twilight_model::id::Id;
+use uuid::Uuid;
+
+/// Request subject (mirrors `subjects.gateway_requests` in nats-routing.json)
+pub const SUBJECT: &str = "gateway.requests.backfill";
+
+/// Progress subject (mirrors `subjects.backfill` in nats-routing.json)
+pub const PROGRESS_SUBJECT: &str = "gateway.backfill.progress";
+
+/// Event type of the pages
+pub const EVENT_TYPE: &str = "member.sync";
+
+/// A backfill request from a worker
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct BackfillRequest {
+    pub guild_id: Id<GuildMarker>,
+}
+
+/// A running backfill
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Backfill {
+    pub job_id: String,
+    pub guild_id: Id<GuildMarker>,
+    pub shard_id: u64,
+    /// Members in the pages so far
+    pub members: u64,
+}
+
+impl Backfill {
+    pub fn new(guild_id: Id<GuildMarker>, shard_id: u64) -> Self {
+        Self { job_id: Uuid::new_v4().to_string(), guild_id, shard_id, members: 0 }
+    }
+
+    /// One chunk as a `member.sync` page
+    pub fn page(&self, chunk: &MemberChunk) -> GatewayEvent {
+        GatewayEvent {
+            event_id: Uuid::new_v4().to_string(),
+            event_type: EVENT_TYPE.to_string(),
+            schema_version: crate::events::versions::CURRENT,
+            shard_id: self.shard_id,
+            bot_id: None,
+            seq: None,
+            seq_epoch: None,
+            timestamp: now_millis(),
+            guild_id: Some(self.guild_id.to_string()),
+            channel_id: None,
+            user_id: None,
+            data: json!({
+                "job_id": self.job_id,
+                "page": chunk.chunk_index,
+                "page_count": chunk.chunk_count,
+                "members": chunk.members.iter().map(entities::guild_member).collect::<Vec<_>>(),
+            }),
+        }
+    }
+
+    /// Progress payload: `running`, `completed`, `failed` or `expired`
+    pub fn progress(&self, status: &str, pages_done: u32, page_count: Option<u32>) -> Value {
+        json!({
+            "job_id": self.job_id,
+            "guild_id": self.guild_id.to_string(),
+            "shard_id": self.shard_id,
+            "status": status,
+            "pages_done": pages_done,
+            "page_count": page_count,
+            "members": self.members,
+        })
+    }
+}
+
+/// Publish a chunk as a page, then the job's progress. Returns false when the
+/// page couldn't be published and the job has failed.
+pub(super) async fn publish_page(
+    nats: &NatsPublisher,
+    metrics: &GatewayMetrics,
+    job: &Backfill,
+    chunk: &MemberChunk,
+) -> bool {
+    let published = nats.publish_event(&job.page(chunk)).await;
+    let last = chunk.chunk_index + 1 >= chunk.chunk_count;
+    let status = match published {
+        Err(ref e) => {
+            warn!(job_id = %job.job_id, guild_id = %job.guild_id, page = chunk.chunk_index, error = %e, "Backfill page publish failed");
+            "failed"
+        }
+        Ok(()) if last => "completed",
+        Ok(()) => "running",
+    };
+    metrics.record_backfill_members(chunk.members.len() as u64);
+    if status != "running" {
+        info!(job_id = %job.job_id, guild_id = %job.guild_id, members = job.members, status, "Backfill finished");
+        metrics.record_backfill(if last && published.is_ok() { "completed" } else { "failed" });
+    }
+    report(nats, &job.progress(status, chunk.chunk_index + 1, Some(chunk.chunk_count))).await;
+    published.is_ok()
+}
+
+/// Publish a job's progress (core NATS, not retained)
+pub(super) async fn report(nats: &NatsPublisher, progress: &Value) {
+    let payload = serde_json::to_vec(progress).expect("backfill progress serializes");
+    if let Err(e) = nats.client().publish(PROGRESS_SUBJECT, payload.into()).await {
+        debug!(error = %e, "Backfill progress publish failed");
+    }
+}
+
+/// Start backfills for this pool's guilds until the process exits
+pub async fn run(members: Arc<MemberRequests>, control: ShardControl, state: ShardState, intents: Intents) {
+    let nats = members.nats();
+    let Some(mut incoming) = Listener::subscribe(nats, SUBJECT, "backfill request").await else {
+        return;
+    };
+    while let Some(Request { body: request, reply }) = incoming.next::<BackfillRequest>().await {
+        members.expire().await;
+        // Every pool hears the request; the guild's shard answers it
+        let shard_id = shard_for_guild(request.guild_id.get(), state.total_shards());
+        if state.get_health(shard_id).is_none() {
+            continue;
+        }
+
+        let started = if !intents.contains(Intents::GUILD_MEMBERS)
+            || state.degraded_capabilities().contains(&"GUILD_MEMBERS")
+        {
+            Err("gateway is running without GUILD_MEMBERS".to_string())
+        } else {
+            members.start_backfill(Backfill::new(request.guild_id, shard_id), &control)
+        };
+
+        let answer = match started {
+            Ok(job) => {
+                info!(job_id = %job.job_id, guild_id = %job.guild_id, shard_id, "Backfill started");
+                members.metrics().record_backfill("started");
+                json!({ "job_id": job.job_id, "guild_id": job.guild_id.to_string(), "shard_id": shard_id })
+            }
+            Err(error) => {
+                debug!(guild_id = %request.guild_id, shard_id, error, "Backfill request rejected");
+                members.metrics().record_backfill("rejected");
+                json!({ "error": error })
+            }
+        };
+        requests::reply(nats, reply, &answer).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::policy;
+
+    fn fixture(name: &str) -> Value {
+        let path = format!("{}/../../packages/shared/nats-schemas/fixtures/{name}.json", env!("CARGO_MANIFEST_DIR"));
+        serde_json::from_str(&std::fs::read_to_string(path).expect("Failed to read fixture")).unwrap()
+    }
+
+    #[test]
+    fn pages_and_progress_match_fixtures() {
+        let request: BackfillRequest = serde_json::from_value(fixture("backfill-request")).unwrap();
+        let mut job = Backfill { job_id: "7d9f4c2a-1b3e-4f5a-8c6d-0e1f2a3b4c5d".to_string(), ..Backfill::new(request.guild_id, 0) };
+        let chunk: MemberChunk = serde_json::from_value(json!({
+            "guild_id": "123456789012345678",
+            "chunk_index": 0,
+            "chunk_count": 2,
+            "nonce": "2f1c7a9e0b4d4c5e9a8b7c6d5e4f3a2b",
+            "members": [{
+                "user": { "id": "987654321098765432", "username": "holder", "discriminator": "0",
+                          "global_name": "Holder", "avatar": null },
+                "nick": "fremen",
+                "roles": ["111111111111111111"],
+                "joined_at": "2024-01-15T10:30:00.000000+00:00",
+                "premium_since": null,
+                "deaf": false,
+                "mute": false,
+                "flags": 0,
+                "pending": false
+            }],
+            "not_found": [],
+            "presences": []
+        }))
+        .unwrap();
+
+        let page = job.page(&chunk);
+        let expected = fixture("member-sync");
+        assert_eq!(page.event_type, EVENT_TYPE);
+        assert_eq!(page.data, expected["data"]);
+        assert_eq!(policy::violations(&serde_json::to_value(&page).unwrap()), Vec::<String>::new());
+
+        job.members += chunk.members.len() as u64;
+        assert_eq!(job.progress("running", 1, Some(2)), fixture("backfill-progress"));
+    }
+}
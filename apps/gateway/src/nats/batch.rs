@@ -0,0 +1,106 @@
+//! Pipelined publish acknowledgements
+//!
+//! A JetStream publish is sent right away, but waiting for its ack costs a
+//! server round trip per event, which dominates during member-join storms.
+//! With `PUBLISH_BATCH_MAX_EVENTS` set, publishes don't wait: their acks are
+//! collected and confirmed together once the batch is full or
+//! `PUBLISH_BATCH_MAX_DELAY_MS` has passed, and on shutdown. Events whose ack
+//! fails are retried like any failed publish, then go to the outbox when
+//! they'd be buffered there anyway, else to the dead-letter queue, and are
+//! counted as route failures.
+//!
+//! Interactions (`commands.>`) always wait for their ack: they must be
+//! answered within seconds, and a failure has to reach the retries at once.
+//! So do outbox drains and dead-letter replays, which remove an entry only
+//! once it is stored.
+//!
+//! Events are still sent in order; only the confirmation is deferred, so
+//! `/debug/events` and the canary see an event once its batch is confirmed.
+
+use super::publisher::subjects;
+use super::NatsPublisher;
+use crate::events::serialize::GatewayEvent;
+use crate::metrics::GatewayMetrics;
+use async_nats::jetstream::context::PublishAckFuture;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Batching settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchConfig {
+    /// Unconfirmed publishes before the publisher waits for their acks
+    pub max_events: usize,
+    /// Longest an ack goes unconfirmed
+    pub max_delay: Duration,
+}
+
+/// A sent publish waiting for its ack
+pub(super) struct Pending {
+    pub event: GatewayEvent,
+    pub subject: String,
+    pub ack: PublishAckFuture,
+    /// When the publish was sent, for the ack latency
+    pub sent_at: Instant,
+}
+
+/// Publishes sent but not yet confirmed
+pub struct PublishBatch {
+    config: BatchConfig,
+    pending: Mutex<Vec<Pending>>,
+    metrics: Arc<GatewayMetrics>,
+}
+
+impl PublishBatch {
+    pub fn new(config: BatchConfig, metrics: Arc<GatewayMetrics>) -> Self {
+        Self { config, pending: Mutex::new(Vec::with_capacity(config.max_events)), metrics }
+    }
+
+    pub fn config(&self) -> BatchConfig {
+        self.config
+    }
+
+    /// Whether publishes on this subject are batched
+    pub fn batches(&self, subject: &str) -> bool {
+        subject.split('.').next() != Some(subjects::COMMANDS)
+    }
+
+    /// Add a sent publish; returns the batch to confirm once it is full
+    pub(super) fn push(&self, pending: Pending) -> Option<Vec<Pending>> {
+        let mut batch = self.pending.lock().expect("batch lock poisoned");
+        batch.push(pending);
+        (batch.len() >= self.config.max_events).then(|| self.take_locked(&mut batch))
+    }
+
+    /// Everything pending
+    pub(super) fn take(&self) -> Vec<Pending> {
+        let mut batch = self.pending.lock().expect("batch lock poisoned");
+        self.take_locked(&mut batch)
+    }
+
+    /// Count an event lost to a failed ack as a route failure (it was
+    /// counted as routed when sent)
+    pub(super) fn record_lost(&self, shard_id: u64) {
+        self.metrics.record_route_failure(shard_id);
+    }
+
+    fn take_locked(&self, batch: &mut Vec<Pending>) -> Vec<Pending> {
+        let taken = std::mem::replace(batch, Vec::with_capacity(self.config.max_events));
+        if !taken.is_empty() {
+            self.metrics.record_publish_batch(taken.len());
+        }
+        taken
+    }
+}
+
+/// Confirm partial batches every `max_delay`
+pub async fn run_flush(nats: Arc<NatsPublisher>) {
+    let Some(every) = nats.batch_config().map(|config| config.max_delay) else {
+        return;
+    };
+    let mut interval = tokio::time::interval(every);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        interval.tick().await;
+        nats.flush_batch().await;
+    }
+}
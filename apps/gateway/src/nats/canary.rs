@@ -0,0 +1,264 @@
+//! Dual-write canary for wire-format changes
+//!
+//! With `CANARY_PERCENT` set and the `dual-publish` flag on, that share of
+//! published events is published a second time, in the candidate wire format
+//! (`CANARY_FORMAT`), on `canary.{format}.{subject}` in the CANARY stream.
+//! Consumers validating the candidate report each copy they handled on
+//! `canary.results`; the gateway counts the reports per consumer and outcome
+//! next to the copies it published, so an envelope migration can be checked
+//! against real traffic before cutover.
+
+use super::lag::KNOWN_DURABLES;
+use super::NatsPublisher;
+use crate::events::serialize::{GatewayEvent, WireFormat};
+use crate::flags::{FeatureFlags, DUAL_PUBLISH};
+use crate::metrics::GatewayMetrics;
+use async_nats::jetstream::stream::{Config, RetentionPolicy, StorageType};
+use futures_util::StreamExt as _;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// JetStream stream holding canary copies (mirrors nats-routing.json)
+pub const STREAM: &str = "CANARY";
+
+/// Canary subjects (mirror nats-routing.json)
+pub mod subjects {
+    /// Copies: canary.{format}.{subject}
+    pub const PREFIX: &str = "canary";
+    /// Consumer reports (`fixtures/canary-result.json`)
+    pub const RESULTS: &str = "canary.results";
+}
+
+/// Header naming the wire format of a canary copy
+pub const FORMAT_HEADER: &str = "Arrakis-Wire-Format";
+
+/// Canary copies nobody consumed are dropped after this long
+const MAX_AGE: Duration = Duration::from_secs(3600);
+
+/// Queue group for result reports, so each is counted by one pool
+const RESULTS_QUEUE: &str = "arrakis-gateway";
+
+/// Canary subject for an event's primary subject
+pub fn subject(format: WireFormat, primary: &str) -> String {
+    format!("{}.{}.{primary}", subjects::PREFIX, format.as_str())
+}
+
+/// Canary settings
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CanaryConfig {
+    /// Share of published events copied, 0–100
+    pub percent: f64,
+    pub format: WireFormat,
+}
+
+/// Samples published events and publishes their canary copies
+pub struct Canary {
+    config: CanaryConfig,
+    flags: Arc<FeatureFlags>,
+    metrics: Arc<GatewayMetrics>,
+    stream_ready: AtomicBool,
+}
+
+impl Canary {
+    pub fn new(config: CanaryConfig, flags: Arc<FeatureFlags>, metrics: Arc<GatewayMetrics>) -> Self {
+        Self {
+            config,
+            flags,
+            metrics,
+            stream_ready: AtomicBool::new(false),
+        }
+    }
+
+    /// Copy a published event if it is sampled
+    pub(super) async fn publish(&self, nats: &NatsPublisher, event: &GatewayEvent, primary: &str) {
+        if !self.flags.is_enabled(DUAL_PUBLISH) || fastrand::f64() * 100.0 >= self.config.percent {
+            return;
+        }
+        if !self.stream_ready.load(Ordering::Relaxed) && ensure_stream(nats).await {
+            self.stream_ready.store(true, Ordering::Relaxed);
+        }
+
+        let format = self.config.format;
+        let mut headers = async_nats::HeaderMap::new();
+        headers.insert(FORMAT_HEADER, format.as_str());
+        let outcome = match nats.publish_copy(event, format, primary, &subject(format, primary), headers).await {
+            Ok(()) => "published",
+            Err(e) => {
+                debug!(event_id = %event.event_id, error = %e, "Canary publish failed");
+                "failed"
+            }
+        };
+        self.metrics.record_canary_publish(format.as_str(), outcome);
+    }
+}
+
+/// How a consumer handled a canary copy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    /// Parsed and handled
+    Ok,
+    /// Failed to parse or validate
+    Rejected,
+}
+
+impl Outcome {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::Rejected => "rejected",
+        }
+    }
+}
+
+/// A consumer's report on one canary copy (`fixtures/canary-result.json`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CanaryResult {
+    pub event_id: String,
+    pub format: String,
+    /// Durable consumer name
+    pub consumer: String,
+    pub outcome: Outcome,
+    /// Why the copy was rejected
+    pub error: Option<String>,
+}
+
+/// Running ok/rejected counts per format and consumer
+#[derive(Debug, Default)]
+pub struct Comparison {
+    tallies: HashMap<(WireFormat, &'static str), [u64; 2]>,
+}
+
+impl Comparison {
+    /// Count a report. Returns its format and consumer labels and the
+    /// consumer's success ratio so far; None for an unknown format.
+    ///
+    /// Consumers outside the known durables are counted as `other`, so
+    /// reports can't grow the metric label set.
+    pub fn record(&mut self, result: &CanaryResult) -> Option<(WireFormat, &'static str, f64)> {
+        let format = WireFormat::parse(&result.format)?;
+        let consumer = KNOWN_DURABLES
+            .iter()
+            .map(|durable| durable.consumer)
+            .find(|known| *known == result.consumer)
+            .unwrap_or("other");
+
+        let tally = self.tallies.entry((format, consumer)).or_default();
+        tally[usize::from(result.outcome == Outcome::Rejected)] += 1;
+        Some((format, consumer, tally[0] as f64 / (tally[0] + tally[1]) as f64))
+    }
+}
+
+/// Count consumer reports until the process exits
+pub async fn run_results(nats: Arc<NatsPublisher>, metrics: Arc<GatewayMetrics>) {
+    let mut reports = match nats.client().queue_subscribe(subjects::RESULTS, RESULTS_QUEUE.to_string()).await {
+        Ok(reports) => reports,
+        Err(e) => {
+            warn!(subject = subjects::RESULTS, error = %e, "Failed to subscribe to canary results");
+            return;
+        }
+    };
+    info!(subject = subjects::RESULTS, "Canary result listener started");
+
+    let mut comparison = Comparison::default();
+    while let Some(message) = reports.next().await {
+        let result: CanaryResult = match serde_json::from_slice(&message.payload) {
+            Ok(result) => result,
+            Err(e) => {
+                debug!(error = %e, "Ignoring malformed canary result");
+                continue;
+            }
+        };
+        let Some((format, consumer, success_ratio)) = comparison.record(&result) else {
+            debug!(format = %result.format, "Ignoring canary result for an unknown format");
+            continue;
+        };
+
+        metrics.record_canary_result(format.as_str(), consumer, result.outcome.as_str());
+        metrics.set_canary_success_ratio(format.as_str(), consumer, success_ratio);
+        if result.outcome == Outcome::Rejected {
+            debug!(
+                event_id = %result.event_id,
+                consumer,
+                format = format.as_str(),
+                error = result.error.as_deref().unwrap_or_default(),
+                "Canary copy rejected"
+            );
+        }
+    }
+}
+
+async fn ensure_stream(nats: &NatsPublisher) -> bool {
+    let config = Config {
+        name: STREAM.to_string(),
+        subjects: vec![format!("{}.>", subjects::PREFIX)],
+        retention: RetentionPolicy::Limits,
+        max_age: MAX_AGE,
+        storage: StorageType::Memory,
+        ..Default::default()
+    };
+
+    match nats.jetstream().get_or_create_stream(config).await {
+        Ok(_) => true,
+        Err(e) => {
+            warn!(stream = STREAM, error = %e, "Failed to create canary stream");
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../packages/shared/nats-schemas/fixtures/canary-result.json"
+    );
+
+    fn result(consumer: &str, outcome: Outcome) -> CanaryResult {
+        CanaryResult {
+            event_id: "550e8400-e29b-41d4-a716-446655440000".to_string(),
+            format: "json".to_string(),
+            consumer: consumer.to_string(),
+            outcome,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_success_ratio_per_consumer() {
+        let mut comparison = Comparison::default();
+        assert_eq!(comparison.record(&result("event-worker", Outcome::Ok)), Some((WireFormat::Json, "event-worker", 1.0)));
+        assert_eq!(
+            comparison.record(&result("event-worker", Outcome::Rejected)),
+            Some((WireFormat::Json, "event-worker", 0.5))
+        );
+        assert_eq!(comparison.record(&result("command-worker", Outcome::Ok)).unwrap().2, 1.0);
+
+        // Unknown consumers share a label; unknown formats are not counted
+        assert_eq!(comparison.record(&result("made-up", Outcome::Ok)).unwrap().1, "other");
+        let unknown = CanaryResult { format: "xml".to_string(), ..result("event-worker", Outcome::Ok) };
+        assert_eq!(comparison.record(&unknown), None);
+    }
+
+    #[test]
+    fn test_canary_subject_wraps_primary() {
+        assert_eq!(subject(WireFormat::Json, "events.member.join"), "canary.json.events.member.join");
+        assert_eq!(WireFormat::parse("json"), Some(WireFormat::Json));
+        assert_eq!(WireFormat::parse("results"), None);
+    }
+
+    #[test]
+    fn fixture_round_trips() {
+        let fixture: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(FIXTURE).expect("Failed to read canary-result.json"))
+                .unwrap();
+        let result: CanaryResult = serde_json::from_value(fixture.clone()).expect("fixture matches CanaryResult");
+        assert_eq!(serde_json::to_value(&result).unwrap(), fixture);
+    }
+}
@@ -0,0 +1,281 @@
+//! Publish retries and the dead-letter queue
+//!
+//! A publish that fails (no ack, stream full, connection dropped mid-send) is
+//! retried `PUBLISH_RETRIES` times, waiting `PUBLISH_RETRY_BASE_MS` and
+//! doubling per attempt. Events still unpublished after that go to the outbox
+//! when it buffers their subject; everything else is appended to
+//! `{DLQ_DIR}/dlq.jsonl` with the reason it failed. Operators replay the file
+//! with `POST /admin/dlq/replay` once the cause is fixed; replayed events keep
+//! their `event_id`, so consumers can drop duplicates.
+//!
+//! Dead letters are kept for `DLQ_MAX_AGE_SECS`. A full queue makes room for
+//! a new event by dropping its oldest ones, which are the least likely to
+//! still be worth replaying.
+
+use super::jsonl::{JsonlLog, Limits};
+use super::NatsPublisher;
+use crate::error::GatewayError;
+use crate::events::serialize::{now_millis, GatewayEvent};
+use crate::metrics::GatewayMetrics;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Dead-letter file inside `DLQ_DIR`
+const FILE_NAME: &str = "dlq.jsonl";
+
+/// How often expired dead letters are removed
+const GC_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Longest wait between two attempts
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(5);
+
+/// How failed publishes are retried
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Attempts after the first (0 disables retries)
+    pub retries: u32,
+    /// Wait before the first retry; doubles per further retry
+    pub base_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// Wait before retry `retry` (0-based)
+    pub fn delay(&self, retry: u32) -> Duration {
+        self.base_delay.saturating_mul(1 << retry.min(16)).min(RETRY_MAX_DELAY)
+    }
+}
+
+/// Dead-letter file location and limits
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DlqConfig {
+    pub dir: PathBuf,
+    pub max_events: usize,
+    pub max_bytes: u64,
+    /// Dead letters older than this are removed
+    pub max_age: Duration,
+}
+
+impl DlqConfig {
+    fn limits(&self) -> Limits {
+        Limits { max_events: self.max_events, max_bytes: self.max_bytes }
+    }
+}
+
+/// One dead-lettered event, as a line of the file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Entry {
+    /// When the event was given up on (Unix millis)
+    dead_at: u64,
+    subject: String,
+    reason: String,
+    event: GatewayEvent,
+}
+
+impl Entry {
+    fn expired(&self, now_ms: u64, max_age: Duration) -> bool {
+        now_ms.saturating_sub(self.dead_at) > max_age.as_millis() as u64
+    }
+}
+
+/// Outcome of a replay
+#[derive(Debug, Default, Serialize, PartialEq, Eq)]
+pub struct Replayed {
+    pub replayed: usize,
+    pub remaining: usize,
+    /// Why the replay stopped early
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Events that could not be published
+pub struct DeadLetters {
+    config: DlqConfig,
+    letters: Arc<Mutex<JsonlLog<Entry>>>,
+    /// Held while a replay runs
+    replaying: tokio::sync::Mutex<()>,
+    metrics: Arc<GatewayMetrics>,
+}
+
+impl DeadLetters {
+    /// Open the dead-letter queue, picking up entries left by a previous run
+    pub fn open(config: DlqConfig, metrics: Arc<GatewayMetrics>) -> Result<Self, GatewayError> {
+        let path = config.dir.join(FILE_NAME);
+        let now_ms = now_millis();
+        let reclaimed = {
+            let metrics = Arc::clone(&metrics);
+            move |bytes| metrics.record_dlq_reclaimed(bytes)
+        };
+        let (letters, loaded) = std::fs::create_dir_all(&config.dir)
+            .and_then(|()| {
+                JsonlLog::open("dead-letter queue", &path, |entry: &Entry| !entry.expired(now_ms, config.max_age), reclaimed)
+            })
+            .map_err(|e| GatewayError::Config(format!("Failed to open dead-letter queue {}: {e}", path.display())))?;
+
+        info!(
+            path = %path.display(),
+            pending = letters.len(),
+            expired = loaded.dropped,
+            corrupt = loaded.corrupt,
+            "Dead-letter queue opened"
+        );
+        metrics.record_dlq_events("expired", loaded.dropped as u64);
+        metrics.record_dlq_events("rejected", loaded.corrupt as u64);
+        metrics.set_dlq_depth(letters.len(), letters.bytes());
+
+        let letters = Arc::new(Mutex::new(letters));
+        let estimate = Arc::clone(&letters);
+        metrics.register_memory_estimator(
+            "dlq",
+            Arc::new(move || estimate.lock().unwrap_or_else(|e| e.into_inner()).bytes()),
+        );
+
+        Ok(Self { config, letters, replaying: tokio::sync::Mutex::new(()), metrics })
+    }
+
+    /// Events held
+    pub fn pending(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Record an event given up on, dropping the oldest dead letters if
+    /// there's no room; logged when it can't be kept either
+    pub fn push(&self, subject: &str, event: &GatewayEvent, reason: String) {
+        self.expire();
+        let entry = Entry { dead_at: now_millis(), subject: subject.to_string(), reason, event: event.clone() };
+        let mut letters = self.lock();
+        let pushed = letters.push_evicting(entry, self.config.limits());
+        self.metrics.set_dlq_depth(letters.len(), letters.bytes());
+
+        match pushed {
+            Ok(evicted) => {
+                self.metrics.record_dlq_events("dead_lettered", 1);
+                if evicted > 0 {
+                    self.metrics.record_dlq_events("evicted", evicted as u64);
+                    warn!(evicted, "Dead-letter queue full - dropped its oldest events");
+                }
+                warn!(event_id = %event.event_id, subject, "Event dead-lettered");
+            }
+            Err(reason) => {
+                self.metrics.record_dlq_events("rejected", 1);
+                warn!(event_id = %event.event_id, subject, reason, "Dead-letter queue rejected event - event lost");
+            }
+        }
+    }
+
+    /// Publish the dead letters held when the replay starts, oldest first,
+    /// stopping at the first failure. None while another replay runs.
+    pub async fn replay(&self, nats: &NatsPublisher) -> Option<Replayed> {
+        let _replaying = self.replaying.try_lock().ok()?;
+        let mut result = Replayed::default();
+
+        for _ in 0..self.pending() {
+            let Some((subject, event)) = self.front() else {
+                break;
+            };
+            if let Err(e) = nats.publish_to(&event, subject).await {
+                result.error = Some(e.to_string());
+                break;
+            }
+            // Unless it expired or was evicted meanwhile
+            let mut letters = self.lock();
+            if letters.front().is_some_and(|entry| entry.event.event_id == event.event_id) {
+                letters.pop_front();
+            }
+            result.replayed += 1;
+        }
+
+        let letters = self.lock();
+        self.metrics.record_dlq_events("replayed", result.replayed as u64);
+        self.metrics.set_dlq_depth(letters.len(), letters.bytes());
+        result.remaining = letters.len();
+        info!(replayed = result.replayed, remaining = result.remaining, "Dead-letter replay finished");
+        Some(result)
+    }
+
+    /// Remove dead letters older than `DLQ_MAX_AGE_SECS`
+    fn expire(&self) {
+        let now_ms = now_millis();
+        let mut letters = self.lock();
+        let expired = letters.pop_front_while(|entry| entry.expired(now_ms, self.config.max_age));
+        if expired > 0 {
+            info!(expired, "Removed dead letters older than DLQ_MAX_AGE_SECS");
+            self.metrics.record_dlq_events("expired", expired as u64);
+            self.metrics.set_dlq_depth(letters.len(), letters.bytes());
+        }
+    }
+
+    fn front(&self) -> Option<(String, GatewayEvent)> {
+        let letters = self.lock();
+        let entry = letters.front()?;
+        Some((entry.subject.clone(), entry.event.clone()))
+    }
+
+    /// Wait until the dead-letter file has caught up
+    pub async fn flush(&self) {
+        let flushed = self.lock().flush();
+        let _ = flushed.await;
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, JsonlLog<Entry>> {
+        self.letters.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Remove expired dead letters, also while nothing new fails
+pub async fn run_gc(nats: Arc<NatsPublisher>) {
+    let mut interval = tokio::time::interval(GC_INTERVAL);
+    loop {
+        interval.tick().await;
+        match nats.dead_letters() {
+            Some(dead_letters) => dead_letters.expire(),
+            None => return,
+        }
+    }
+}
+
+/// Why a publish failed, with the underlying cause
+pub fn reason(error: &GatewayError) -> String {
+    match std::error::Error::source(error) {
+        Some(source) => format!("{error}: {source}"),
+        None => error.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retry_delay_doubles_up_to_the_cap() {
+        let policy = RetryPolicy { retries: 3, base_delay: Duration::from_millis(100) };
+        assert_eq!(policy.delay(0), Duration::from_millis(100));
+        assert_eq!(policy.delay(2), Duration::from_millis(400));
+        assert_eq!(policy.delay(10), RETRY_MAX_DELAY);
+        assert_eq!(policy.delay(u32::MAX), RETRY_MAX_DELAY);
+    }
+
+    #[test]
+    fn letters_expire_after_max_age() {
+        let day = Duration::from_secs(86_400);
+        let letter = Entry {
+            dead_at: 1_000,
+            subject: "events.member.join".to_string(),
+            reason: "stream full".to_string(),
+            event: GatewayEvent::for_test("member.join"),
+        };
+        assert!(!letter.expired(1_000 + day.as_millis() as u64, day));
+        assert!(letter.expired(1_001 + day.as_millis() as u64, day));
+    }
+
+    #[test]
+    fn reason_includes_the_cause() {
+        let error = GatewayError::NatsPublishFailed {
+            subject: "messages.create".to_string(),
+            source: Box::new(std::io::Error::other("timed out")),
+        };
+        assert_eq!(reason(&error), "NATS publish failed for subject 'messages.create': timed out");
+    }
+}
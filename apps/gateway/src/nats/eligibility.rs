@@ -0,0 +1,312 @@
+//! Eligibility check requests from interactions
+//!
+//! The eligibility workers check a member's token holdings against a
+//! community's rules. Two kinds of guild interaction ask for that: the
+//! wallet-link commands (`ELIGIBILITY_COMMANDS`, e.g. `link-wallet`), whose
+//! wallet option (`ELIGIBILITY_WALLET_OPTION`) carries the address, and
+//! role-claim components, whose custom_id starts with
+//! `ELIGIBILITY_CLAIM_PREFIX` (`role-claim:holder,og` checks the `holder` and
+//! `og` rules; a select menu adds its chosen values). For each, an
+//! `eligibility.check` request is published on `eligibility.check.{guild_id}`
+//! (ELIGIBILITY stream) next to the interaction itself, which still goes to
+//! the command workers for the reply.
+
+use super::NatsPublisher;
+use crate::error::GatewayError;
+use crate::events::serialize::{GatewayEvent, DISPATCH_NAMESPACE};
+use crate::metrics::GatewayMetrics;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+/// Eligibility subjects (mirror nats-routing.json)
+pub mod subjects {
+    /// Single-member checks: eligibility.check.{guild_id}
+    pub const CHECK: &str = "eligibility.check";
+}
+
+/// Which interactions request an eligibility check
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EligibilityTriggers {
+    /// Slash commands that link a wallet
+    pub commands: BTreeSet<String>,
+    /// Name of their option holding the wallet address
+    pub wallet_option: String,
+    /// custom_id prefix of role-claim buttons and select menus
+    pub claim_prefix: Option<String>,
+}
+
+impl EligibilityTriggers {
+    /// Parse the comma-separated command list and the claim prefix; None
+    /// when both are empty
+    pub fn parse(commands: &str, wallet_option: &str, claim_prefix: &str) -> Result<Option<Self>, GatewayError> {
+        let commands: BTreeSet<String> = commands
+            .split(',')
+            .map(|name| name.trim().trim_start_matches('/'))
+            .filter(|name| !name.is_empty())
+            .map(String::from)
+            .collect();
+        let wallet_option = wallet_option.trim();
+        if !commands.is_empty() && wallet_option.is_empty() {
+            return Err(GatewayError::Config("ELIGIBILITY_WALLET_OPTION must not be empty".to_string()));
+        }
+        let claim_prefix = Some(claim_prefix.trim()).filter(|prefix| !prefix.is_empty()).map(String::from);
+
+        let enabled = !commands.is_empty() || claim_prefix.is_some();
+        Ok(enabled.then(|| Self { commands, wallet_option: wallet_option.to_string(), claim_prefix }))
+    }
+
+    /// The eligibility check an interaction asks for, if any
+    pub fn extract(&self, event: &GatewayEvent) -> Option<EligibilityCheck> {
+        if event.event_type != "interaction.create" {
+            return None;
+        }
+        let guild_id = event.guild_id.as_ref()?;
+        let data = &event.data;
+
+        let (source, wallet_address, rule_ids) = match data["interaction_type"].as_str()? {
+            "ApplicationCommand" => {
+                let command = data["command_name"].as_str()?;
+                if !self.commands.contains(command) {
+                    return None;
+                }
+                let wallet = find_option(&data["options"], &self.wallet_option)
+                    .map(str::trim)
+                    .filter(|wallet| !wallet.is_empty())
+                    .map(String::from);
+                ("command", wallet, None)
+            }
+            "MessageComponent" => {
+                let rules = data["custom_id"].as_str()?.strip_prefix(self.claim_prefix.as_deref()?)?;
+                let selected = data["values"].as_array().into_iter().flatten().filter_map(Value::as_str);
+                let rule_ids: Vec<String> = rules
+                    .split(',')
+                    .chain(selected)
+                    .map(str::trim)
+                    .filter(|rule| !rule.is_empty())
+                    .map(String::from)
+                    .collect();
+                // No rules named: the community's full rule set
+                ("component", None, Some(rule_ids).filter(|rules| !rules.is_empty()))
+            }
+            _ => return None,
+        };
+
+        Some(EligibilityCheck {
+            event_id: check_id(&event.event_id),
+            event_type: "eligibility.check".to_string(),
+            timestamp: event.timestamp,
+            // Communities are keyed by their guild until one is linked to another
+            community_id: guild_id.clone(),
+            guild_id: guild_id.clone(),
+            user_id: event.user_id.clone(),
+            wallet_address,
+            check_type: "single".to_string(),
+            rule_ids,
+            data: json!({
+                "source": source,
+                "interaction_event_id": event.event_id,
+                "interaction_id": data["interaction_id"],
+                "command_name": data["command_name"],
+                "custom_id": data["custom_id"],
+                "shard_id": event.shard_id,
+            }),
+        })
+    }
+}
+
+/// An eligibility request, as the eligibility workers read it
+/// (`fixtures/eligibility-check.json`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EligibilityCheck {
+    pub event_id: String,
+    /// `eligibility.check`
+    pub event_type: String,
+    pub timestamp: u64,
+    pub community_id: String,
+    pub guild_id: String,
+    pub user_id: Option<String>,
+    /// The address given to a wallet-link command; workers use the member's
+    /// linked wallet when None
+    pub wallet_address: Option<String>,
+    /// `single`: one member
+    pub check_type: String,
+    /// Rules to check; all of the community's when absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule_ids: Option<Vec<String>>,
+    /// Where the request came from (`source`, the interaction's IDs)
+    pub data: Value,
+}
+
+impl EligibilityCheck {
+    pub fn subject(&self) -> String {
+        format!("{}.{}", subjects::CHECK, self.guild_id)
+    }
+}
+
+/// Derived from the interaction's event ID, so a replayed interaction is
+/// deduplicated by JetStream like the interaction itself
+fn check_id(interaction_event_id: &str) -> String {
+    Uuid::new_v5(&DISPATCH_NAMESPACE, format!("eligibility:{interaction_event_id}").as_bytes()).to_string()
+}
+
+/// A string option's value by name, looking inside subcommands
+fn find_option<'a>(options: &'a Value, name: &str) -> Option<&'a str> {
+    options.as_array()?.iter().find_map(|option| match option["options"] {
+        Value::Array(_) => find_option(&option["options"], name),
+        _ if option["name"] == name => option["value"].as_str(),
+        _ => None,
+    })
+}
+
+/// Publish the eligibility check an interaction asks for, if any
+pub(super) async fn publish(
+    nats: &NatsPublisher,
+    triggers: &EligibilityTriggers,
+    event: &GatewayEvent,
+    metrics: Option<&GatewayMetrics>,
+) {
+    let Some(check) = triggers.extract(event) else {
+        return;
+    };
+    let source = if check.data["source"] == "command" { "command" } else { "component" };
+    let payload = match serde_json::to_vec(&check) {
+        Ok(payload) => payload,
+        Err(e) => {
+            debug!(event_id = %event.event_id, error = %e, "Failed to serialize eligibility check");
+            return;
+        }
+    };
+
+    let subject = check.subject();
+    let mut headers = async_nats::HeaderMap::new();
+    headers.insert(async_nats::header::NATS_MESSAGE_ID, check.event_id.as_str());
+    let payload = match nats.seal(&subject, payload, &mut headers) {
+        Ok(payload) => payload,
+        Err(e) => {
+            warn!(subject, interaction = %event.event_id, error = %e, "Failed to encrypt eligibility check");
+            return;
+        }
+    };
+    let published = match nats.jetstream().publish_with_headers(subject.clone(), headers, payload.into()).await {
+        Ok(ack) => ack.await.map(|_| ()).map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    if let Some(metrics) = metrics {
+        metrics.record_eligibility_request(source, if published.is_ok() { "published" } else { "failed" });
+    }
+    match published {
+        Ok(()) => debug!(subject, event_id = %check.event_id, interaction = %event.event_id, "Eligibility check requested"),
+        Err(e) => warn!(subject, interaction = %event.event_id, error = %e, "Failed to publish eligibility check"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../packages/shared/nats-schemas/fixtures/eligibility-check.json"
+    );
+
+    fn triggers() -> EligibilityTriggers {
+        EligibilityTriggers::parse("/link-wallet, verify", "wallet", "role-claim:").unwrap().unwrap()
+    }
+
+    fn interaction(guild_id: Option<&str>, data: Value) -> GatewayEvent {
+        GatewayEvent {
+            event_id: "00000000-0000-4000-8000-000000000012".to_string(),
+            guild_id: guild_id.map(String::from),
+            user_id: Some("987654321098765432".to_string()),
+            data,
+            ..GatewayEvent::for_test("interaction.create")
+        }
+    }
+
+    #[test]
+    fn wallet_link_commands_carry_the_address() {
+        let event = interaction(
+            Some("123456789012345678"),
+            json!({
+                "interaction_id": "444444444444444444",
+                "interaction_type": "ApplicationCommand",
+                "command_name": "link-wallet",
+                "options": [{ "name": "wallet", "type": "String", "value": " 0xAbC0000000000000000000000000000000000001 " }],
+            }),
+        );
+        let check = triggers().extract(&event).unwrap();
+        assert_eq!(check.subject(), "eligibility.check.123456789012345678");
+        assert_eq!(check.community_id, "123456789012345678");
+        assert_eq!(check.wallet_address.as_deref(), Some("0xAbC0000000000000000000000000000000000001"));
+        assert_eq!(check.rule_ids, None);
+        assert_eq!(check.data["source"], "command");
+
+        // Same interaction, same request ID (JetStream dedupes a replay)
+        assert_eq!(triggers().extract(&event).unwrap().event_id, check.event_id);
+        assert_ne!(check.event_id, event.event_id);
+
+        // Subcommands are searched for the option
+        let nested = interaction(
+            Some("123456789012345678"),
+            json!({
+                "interaction_type": "ApplicationCommand",
+                "command_name": "verify",
+                "options": [{ "name": "evm", "type": "SubCommand", "options": [{ "name": "wallet", "type": "String", "value": "0x1" }] }],
+            }),
+        );
+        assert_eq!(triggers().extract(&nested).unwrap().wallet_address.as_deref(), Some("0x1"));
+    }
+
+    #[test]
+    fn role_claims_name_their_rules() {
+        let button = interaction(
+            Some("123456789012345678"),
+            json!({ "interaction_type": "MessageComponent", "custom_id": "role-claim:holder,og", "values": [] }),
+        );
+        let check = triggers().extract(&button).unwrap();
+        assert_eq!(check.rule_ids, Some(vec!["holder".to_string(), "og".to_string()]));
+        assert_eq!(check.wallet_address, None);
+
+        let select = interaction(
+            Some("123456789012345678"),
+            json!({ "interaction_type": "MessageComponent", "custom_id": "role-claim:", "values": ["naib"] }),
+        );
+        assert_eq!(triggers().extract(&select).unwrap().rule_ids, Some(vec!["naib".to_string()]));
+
+        let all = interaction(Some("123456789012345678"), json!({ "interaction_type": "MessageComponent", "custom_id": "role-claim:" }));
+        assert_eq!(triggers().extract(&all).unwrap().rule_ids, None);
+    }
+
+    #[test]
+    fn other_interactions_are_ignored() {
+        let triggers = triggers();
+        let guild = Some("123456789012345678");
+        let ignored = [
+            interaction(guild, json!({ "interaction_type": "ApplicationCommand", "command_name": "badges" })),
+            interaction(guild, json!({ "interaction_type": "ApplicationCommandAutocomplete", "command_name": "link-wallet" })),
+            interaction(guild, json!({ "interaction_type": "MessageComponent", "custom_id": "role-picker" })),
+            // No guild, no community
+            interaction(None, json!({ "interaction_type": "ApplicationCommand", "command_name": "link-wallet" })),
+        ];
+        for event in ignored {
+            assert_eq!(triggers.extract(&event), None, "{}", event.data);
+        }
+
+        assert_eq!(EligibilityTriggers::parse(" ", "wallet", "").unwrap(), None);
+        assert!(EligibilityTriggers::parse("link-wallet", " ", "").is_err());
+    }
+
+    #[test]
+    fn fixture_round_trips() {
+        let contents = std::fs::read_to_string(FIXTURE).expect("Failed to read eligibility-check.json");
+        let check: EligibilityCheck = serde_json::from_str(&contents).expect("fixture matches EligibilityCheck");
+        assert_eq!(check.event_type, "eligibility.check");
+        assert_eq!(check.event_id, check_id(check.data["interaction_event_id"].as_str().unwrap()));
+
+        let fixture: Value = serde_json::from_str(&contents).unwrap();
+        assert_eq!(serde_json::to_value(&check).unwrap(), fixture);
+    }
+}
@@ -0,0 +1,179 @@
+//! Payload encryption for sensitive subjects
+//!
+//! Some subjects carry data not every NATS user should read, such as the
+//! wallet links on `eligibility.>`. With `PAYLOAD_ENCRYPTION_SUBJECTS` set,
+//! payloads published on matching subjects are encrypted with AES-256-GCM
+//! under `PAYLOAD_ENCRYPTION_KEY` (32 bytes, hex; a secret setting, so it can
+//! come from a file, Vault or AWS Secrets Manager). Consumers holding the key
+//! decrypt; anyone else sees ciphertext.
+//!
+//! The message payload is the 12-byte random nonce followed by the ciphertext
+//! and its 16-byte tag. Encrypted messages carry:
+//!
+//! - `Arrakis-Encryption`: `aes-256-gcm`
+//! - `Arrakis-Encryption-Key`: `PAYLOAD_ENCRYPTION_KEY_ID`, when set (rotation)
+//!
+//! `Content-Type` still names the plaintext's encoding. Signatures
+//! (`EVENT_SIGNING`) cover the encrypted payload, so they can be checked
+//! without the key.
+
+use crate::error::GatewayError;
+use crate::hex;
+use crate::secrets::SecretSource;
+use async_nats::HeaderMap;
+use ring::aead::{self, Aad, LessSafeKey, Nonce, UnboundKey, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+
+pub const ALGORITHM_HEADER: &str = "Arrakis-Encryption";
+pub const KEY_ID_HEADER: &str = "Arrakis-Encryption-Key";
+pub const ALGORITHM: &str = "aes-256-gcm";
+
+/// Payload encryption settings
+#[derive(Debug, Clone)]
+pub struct EncryptionConfig {
+    /// Subject filters whose payloads are encrypted, with NATS wildcards
+    pub subjects: Vec<String>,
+    pub key: SecretSource,
+    pub key_id: Option<String>,
+}
+
+/// Parse `PAYLOAD_ENCRYPTION_SUBJECTS`: comma-separated subject filters, e.g.
+/// `eligibility.>,events.member.*`
+pub fn parse_subjects(spec: &str) -> Result<Vec<String>, GatewayError> {
+    let subjects: Vec<String> = spec.split(',').map(str::trim).filter(|s| !s.is_empty()).map(String::from).collect();
+    for filter in &subjects {
+        let tokens: Vec<&str> = filter.split('.').collect();
+        let tail = tokens.iter().position(|token| *token == ">");
+        if tokens.iter().any(|token| token.is_empty()) || tail.is_some_and(|at| at != tokens.len() - 1) {
+            return Err(GatewayError::Config(format!(
+                "PAYLOAD_ENCRYPTION_SUBJECTS has an invalid subject filter: {filter:?}"
+            )));
+        }
+    }
+    Ok(subjects)
+}
+
+/// Whether a subject matches a filter (`*` is one token, a final `>` the rest)
+pub fn subject_matches(filter: &str, subject: &str) -> bool {
+    let mut subject = subject.split('.');
+    for token in filter.split('.') {
+        match (token, subject.next()) {
+            (">", Some(_)) => return true,
+            ("*", Some(_)) => {}
+            (token, Some(part)) if token == part => {}
+            _ => return false,
+        }
+    }
+    subject.next().is_none()
+}
+
+/// Encrypts payloads published on the configured subjects
+pub struct PayloadCipher {
+    subjects: Vec<String>,
+    key: LessSafeKey,
+    key_id: Option<String>,
+    rng: SystemRandom,
+}
+
+impl PayloadCipher {
+    /// Read the key from its secret source
+    pub async fn load(config: &EncryptionConfig) -> Result<Self, GatewayError> {
+        let key = config.key.read("PAYLOAD_ENCRYPTION_KEY").await?;
+        let key = hex::decode(&key)
+            .ok_or_else(|| GatewayError::Config("PAYLOAD_ENCRYPTION_KEY must be hex".to_string()))?;
+        Self::new(config.subjects.clone(), &key, config.key_id.clone())
+    }
+
+    pub fn new(subjects: Vec<String>, key: &[u8], key_id: Option<String>) -> Result<Self, GatewayError> {
+        let key = UnboundKey::new(&aead::AES_256_GCM, key).map_err(|_| {
+            GatewayError::Config(format!("PAYLOAD_ENCRYPTION_KEY must be 32 bytes, got {}", key.len()))
+        })?;
+        Ok(Self { subjects, key: LessSafeKey::new(key), key_id, rng: SystemRandom::new() })
+    }
+
+    pub fn subjects(&self) -> &[String] {
+        &self.subjects
+    }
+
+    /// Whether payloads on a subject are encrypted
+    pub fn covers(&self, subject: &str) -> bool {
+        self.subjects.iter().any(|filter| subject_matches(filter, subject))
+    }
+
+    /// Encrypt a payload and add the encryption headers
+    pub fn seal(&self, subject: &str, payload: Vec<u8>, headers: &mut HeaderMap) -> Result<Vec<u8>, GatewayError> {
+        let failed = |_| GatewayError::NatsPublishFailed {
+            subject: subject.to_string(),
+            source: "payload encryption failed".into(),
+        };
+        let mut nonce = [0u8; NONCE_LEN];
+        self.rng.fill(&mut nonce).map_err(failed)?;
+
+        let mut sealed = Vec::with_capacity(NONCE_LEN + payload.len() + aead::AES_256_GCM.tag_len());
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&payload);
+        let tag = self
+            .key
+            .seal_in_place_separate_tag(Nonce::assume_unique_for_key(nonce), Aad::empty(), &mut sealed[NONCE_LEN..])
+            .map_err(failed)?;
+        sealed.extend_from_slice(tag.as_ref());
+
+        headers.insert(ALGORITHM_HEADER, ALGORITHM);
+        if let Some(ref key_id) = self.key_id {
+            headers.insert(KEY_ID_HEADER, key_id.as_str());
+        }
+        Ok(sealed)
+    }
+
+    /// Decrypt a sealed payload, as a consumer does
+    #[cfg(test)]
+    fn open(&self, sealed: &[u8]) -> Option<Vec<u8>> {
+        let (nonce, ciphertext) = sealed.split_at_checked(NONCE_LEN)?;
+        let nonce = Nonce::try_assume_unique_for_key(nonce).ok()?;
+        let mut plaintext = ciphertext.to_vec();
+        let len = self.key.open_in_place(nonce, Aad::empty(), &mut plaintext).ok()?.len();
+        plaintext.truncate(len);
+        Some(plaintext)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: [u8; 32] = [7; 32];
+
+    #[test]
+    fn test_subject_filters_use_nats_wildcards() {
+        assert!(subject_matches("eligibility.>", "eligibility.check.123456789012345678"));
+        assert!(!subject_matches("eligibility.>", "eligibility"));
+        assert!(subject_matches("events.member.*", "events.member.join"));
+        assert!(!subject_matches("events.member.*", "events.member.join.extra"));
+        assert!(!subject_matches("events.member.join", "events.member"));
+
+        assert_eq!(parse_subjects(" eligibility.>, events.member.* ").unwrap(), ["eligibility.>", "events.member.*"]);
+        assert!(parse_subjects("eligibility.>.check").is_err());
+        assert!(parse_subjects("eligibility..check").is_err());
+    }
+
+    #[test]
+    fn test_sealed_payloads_open_with_the_key_only() {
+        let cipher = PayloadCipher::new(vec!["eligibility.>".to_string()], &KEY, Some("2026-10".to_string())).unwrap();
+        let payload = br#"{"wallet_address":"0xabc"}"#.to_vec();
+        let mut headers = HeaderMap::new();
+
+        let sealed = cipher.seal("eligibility.check.1", payload.clone(), &mut headers).unwrap();
+        assert_eq!(sealed.len(), NONCE_LEN + payload.len() + 16);
+        assert!(!sealed.windows(7).any(|window| window == b"wallet_"), "ciphertext hides the payload");
+        assert_eq!(headers.get(ALGORITHM_HEADER).unwrap().as_str(), ALGORITHM);
+        assert_eq!(headers.get(KEY_ID_HEADER).unwrap().as_str(), "2026-10");
+        assert_eq!(cipher.open(&sealed).unwrap(), payload);
+
+        // A fresh nonce each time
+        assert_ne!(cipher.seal("eligibility.check.1", payload.clone(), &mut headers).unwrap(), sealed);
+
+        let other = PayloadCipher::new(vec![], &[8; 32], None).unwrap();
+        assert!(other.open(&sealed).is_none());
+        assert!(PayloadCipher::new(vec![], &[7; 16], None).is_err());
+    }
+}
@@ -0,0 +1,66 @@
+//! Cached guild lookups for workers over NATS
+//!
+//! A worker sends `{ "guild_id": ... }` (`fixtures/guild-request.json`) as a
+//! request on `gateway.requests.guild`. The pool running the guild's shard
+//! answers from its guild cache with `fixtures/cached-guild.json`, or with
+//! `{ "error": ... }` when it hasn't cached the guild (not joined, or still
+//! unavailable). Other pools ignore the request.
+
+use super::requests::{self, Listener, Request};
+use super::NatsPublisher;
+use crate::cache::GuildCache;
+use crate::metrics::GatewayMetrics;
+use crate::shard::{shard_for_guild, ShardState};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use twilight_model::id::marker::GuildMarker;
+use twilight_model::id::Id;
+
+/// Request subject (mirrors `subjects.gateway_requests` in nats-routing.json)
+pub const SUBJECT: &str = "gateway.requests.guild";
+
+/// A cached guild request from a worker
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct GuildRequest {
+    pub guild_id: Id<GuildMarker>,
+}
+
+/// Answer guild requests for this pool's guilds until the process exits
+pub async fn run(nats: Arc<NatsPublisher>, cache: Arc<GuildCache>, state: ShardState, metrics: Arc<GatewayMetrics>) {
+    let Some(mut incoming) = Listener::subscribe(&nats, SUBJECT, "guild request").await else {
+        return;
+    };
+    while let Some(Request { body: request, reply }) = incoming.next::<GuildRequest>().await {
+        // Every pool hears the request; the guild's shard answers it
+        let shard_id = shard_for_guild(request.guild_id.get(), state.total_shards());
+        if state.get_health(shard_id).is_none() {
+            continue;
+        }
+
+        let answer = match cache.guild(request.guild_id) {
+            Some(guild) => {
+                metrics.record_guild_cache_request("hit");
+                guild
+            }
+            None => {
+                metrics.record_guild_cache_request("miss");
+                json!({ "error": format!("guild {} is not cached", request.guild_id) })
+            }
+        };
+        requests::reply(&nats, reply, &answer).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixture_parses() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/../../packages/shared/nats-schemas/fixtures/guild-request.json");
+        let request: GuildRequest =
+            serde_json::from_str(&std::fs::read_to_string(path).expect("Failed to read fixture")).unwrap();
+        assert_eq!(request.guild_id, Id::new(123456789012345678));
+    }
+}
@@ -0,0 +1,387 @@
+//! Append-only JSON Lines files behind the outbox and dead-letter queue
+//!
+//! Entries are held in memory, oldest first, and appended to the file as
+//! one JSON document per line. Removing the oldest entry appends a
+//! `{"drained":1}` marker instead of rewriting; the file is compacted every
+//! `COMPACT_EVERY` removals, and whenever it empties. A line cut short by a
+//! crash is skipped when the file is read back.
+//!
+//! Bytes a compaction removes from the file are reported to the log's owner
+//! as reclaimed space.
+//!
+//! Only opening touches the disk from the caller. Appends, markers and
+//! compactions go, in order, to a writer thread per file, so publishing
+//! never waits on a write or an fsync; `flush` waits for them to land.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::Write as _;
+use std::path::Path;
+use std::sync::mpsc;
+use tokio::sync::oneshot;
+use tracing::warn;
+
+/// Removals between rewrites of the file
+const COMPACT_EVERY: usize = 1_000;
+
+/// Marker appended when the oldest entry is removed
+const DRAINED_LINE: &[u8] = b"{\"drained\":1}\n";
+
+/// File operations, applied by the writer thread in the order they were sent
+enum Write {
+    Append(Vec<u8>),
+    Drained,
+    /// Rewrite the file without the drained entries and markers
+    Compact,
+    /// Answer once everything sent before has been written
+    Flush(oneshot::Sender<()>),
+}
+
+/// A line of the file: an entry, or a marker that the oldest entry was removed
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Line<T> {
+    Entry(Box<T>),
+    Drained { drained: u64 },
+}
+
+/// Limits on what a log holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    pub max_events: usize,
+    pub max_bytes: u64,
+}
+
+/// Entries dropped while reading the file back
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Loaded {
+    /// Entries the caller no longer wanted (e.g. expired)
+    pub dropped: usize,
+    /// Unparseable lines (a write cut short by a crash)
+    pub corrupt: usize,
+}
+
+/// Entries in memory and in their append-only file
+#[derive(Debug)]
+pub struct JsonlLog<T> {
+    /// What the log holds, for error messages ("outbox")
+    name: &'static str,
+    writer: mpsc::Sender<Write>,
+    /// Entries with their size on disk
+    entries: VecDeque<(T, u64)>,
+    bytes: u64,
+    drained: usize,
+}
+
+impl<T: Serialize + DeserializeOwned> JsonlLog<T> {
+    /// Read the file back, keeping the entries `keep` accepts, and compact
+    /// it; `reclaimed` is called with the bytes each compaction frees
+    pub fn open(
+        name: &'static str,
+        path: &Path,
+        keep: impl Fn(&T) -> bool,
+        reclaimed: impl Fn(u64) + Send + 'static,
+    ) -> std::io::Result<(Self, Loaded)> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e),
+        };
+
+        let mut loaded = Loaded::default();
+        let mut entries = VecDeque::new();
+        for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+            match serde_json::from_str::<Line<T>>(line) {
+                Ok(Line::Entry(entry)) => entries.push_back((*entry, line.len() as u64 + 1)),
+                Ok(Line::Drained { drained }) => {
+                    entries.drain(..entries.len().min(drained as usize));
+                }
+                Err(_) => loaded.corrupt += 1,
+            }
+        }
+        let before = entries.len();
+        entries.retain(|(entry, _)| keep(entry));
+        loaded.dropped = before - entries.len();
+
+        let mut compacted = Vec::new();
+        for (entry, _) in &entries {
+            serde_json::to_writer(&mut compacted, entry)?;
+            compacted.push(b'\n');
+        }
+        let file = replace_file(path, &compacted)?;
+        reclaimed((contents.len() as u64).saturating_sub(compacted.len() as u64));
+
+        let (writer, writes) = mpsc::channel();
+        let path = path.to_path_buf();
+        std::thread::Builder::new()
+            .name(format!("{name} writer"))
+            .spawn(move || run_writer(name, &path, file, writes, reclaimed))?;
+
+        let log = Self {
+            name,
+            writer,
+            bytes: entries.iter().map(|(_, bytes)| bytes).sum(),
+            entries,
+            drained: 0,
+        };
+        Ok((log, loaded))
+    }
+
+    /// Append an entry, unless that would exceed the limits. A failed write
+    /// is logged; the entry is kept in memory either way.
+    pub fn push(&mut self, entry: T, limits: Limits) -> Result<(), String> {
+        let line = line(&entry)?;
+        if !self.fits(line.len() as u64, limits) {
+            return Err(format!("{} full ({} events, {} bytes)", self.name, self.entries.len(), self.bytes));
+        }
+        self.append(entry, line);
+        Ok(())
+    }
+
+    /// Append an entry, removing the oldest ones to make room; returns how
+    /// many were removed. Fails only for an entry the limits can't hold at all.
+    pub fn push_evicting(&mut self, entry: T, limits: Limits) -> Result<usize, String> {
+        let line = line(&entry)?;
+        let bytes = line.len() as u64;
+        if limits.max_events == 0 || bytes > limits.max_bytes {
+            return Err(format!("{} can't hold a {bytes}-byte entry", self.name));
+        }
+        let mut evicted = 0;
+        while !self.fits(bytes, limits) {
+            self.pop_front();
+            evicted += 1;
+        }
+        self.append(entry, line);
+        Ok(evicted)
+    }
+
+    fn fits(&self, bytes: u64, limits: Limits) -> bool {
+        self.entries.len() < limits.max_events && self.bytes + bytes <= limits.max_bytes
+    }
+
+    fn append(&mut self, entry: T, line: Vec<u8>) {
+        let bytes = line.len() as u64;
+        self.write(Write::Append(line));
+        self.entries.push_back((entry, bytes));
+        self.bytes += bytes;
+    }
+
+    /// Remove the oldest entry
+    pub fn pop_front(&mut self) {
+        let Some((_, bytes)) = self.entries.pop_front() else {
+            return;
+        };
+        self.bytes -= bytes;
+        self.drained += 1;
+        self.write(Write::Drained);
+        if self.entries.is_empty() || self.drained >= COMPACT_EVERY {
+            self.write(Write::Compact);
+            self.drained = 0;
+        }
+    }
+
+    /// Remove entries from the front while `drop` accepts them; returns how many
+    pub fn pop_front_while(&mut self, drop: impl Fn(&T) -> bool) -> usize {
+        let mut dropped = 0;
+        while self.entries.front().is_some_and(|(entry, _)| drop(entry)) {
+            self.pop_front();
+            dropped += 1;
+        }
+        dropped
+    }
+
+    /// Resolves once every write so far has reached the file
+    pub fn flush(&self) -> oneshot::Receiver<()> {
+        let (done, flushed) = oneshot::channel();
+        self.write(Write::Flush(done));
+        flushed
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.entries.front().map(|(entry, _)| entry)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Size of the held entries on disk
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+
+    fn write(&self, write: Write) {
+        // The writer only stops when the log is dropped
+        let _ = self.writer.send(write);
+    }
+}
+
+/// An entry as a line of the file
+fn line<T: Serialize>(entry: &T) -> Result<Vec<u8>, String> {
+    let mut line = serde_json::to_vec(entry).map_err(|e| e.to_string())?;
+    line.push(b'\n');
+    Ok(line)
+}
+
+/// Apply writes to the file until the log is dropped
+fn run_writer(name: &str, path: &Path, mut file: File, writes: mpsc::Receiver<Write>, reclaimed: impl Fn(u64)) {
+    for write in writes {
+        let written = match write {
+            Write::Append(line) => file.write_all(&line).and_then(|()| file.flush()),
+            Write::Drained => file.write_all(DRAINED_LINE).and_then(|()| file.flush()),
+            Write::Compact => compact(path).map(|(compacted, freed)| {
+                file = compacted;
+                reclaimed(freed);
+            }),
+            Write::Flush(done) => {
+                let _ = done.send(());
+                Ok(())
+            }
+        };
+        if let Err(e) = written {
+            warn!(log = name, path = %path.display(), error = %e, "Failed to write to disk");
+        }
+    }
+}
+
+/// Rewrite the file with only the entries not yet drained; returns it
+/// opened for appending, and the bytes freed
+fn compact(path: &Path) -> std::io::Result<(File, u64)> {
+    let contents = std::fs::read(path)?;
+    let marker = &DRAINED_LINE[..DRAINED_LINE.len() - 1];
+    let mut lines = VecDeque::new();
+    for line in contents.split(|byte| *byte == b'\n').filter(|line| !line.is_empty()) {
+        if line == marker {
+            lines.pop_front();
+        } else {
+            lines.push_back(line);
+        }
+    }
+
+    let mut kept = Vec::with_capacity(contents.len());
+    for line in lines {
+        kept.extend_from_slice(line);
+        kept.push(b'\n');
+    }
+    let freed = contents.len().saturating_sub(kept.len()) as u64;
+    Ok((replace_file(path, &kept)?, freed))
+}
+
+/// Atomically replace the file; returns it opened for appending
+fn replace_file(path: &Path, contents: &[u8]) -> std::io::Result<File> {
+    let tmp = path.with_extension("jsonl.tmp");
+    let mut file = File::create(&tmp)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    std::fs::rename(&tmp, path)?;
+
+    OpenOptions::new().append(true).open(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct Entry {
+        id: String,
+        at: u64,
+    }
+
+    const LIMITS: Limits = Limits { max_events: 10, max_bytes: 1 << 20 };
+
+    fn entry(id: &str, at: u64) -> Entry {
+        Entry { id: id.to_string(), at }
+    }
+
+    fn log_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("gateway-jsonl-{name}-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("log.jsonl");
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    fn ids(log: &JsonlLog<Entry>) -> Vec<&str> {
+        log.entries.iter().map(|(entry, _)| entry.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_log_survives_reopen_in_order() {
+        let path = log_path("reopen");
+        let (mut log, _) = JsonlLog::open("log", &path, |_: &Entry| true, |_| {}).unwrap();
+        for id in ["a", "b", "c"] {
+            log.push(entry(id, 1), LIMITS).unwrap();
+        }
+        log.pop_front();
+        log.flush().blocking_recv().unwrap();
+        drop(log);
+
+        let (log, loaded) = JsonlLog::open("log", &path, |_: &Entry| true, |_| {}).unwrap();
+        assert_eq!(loaded, Loaded::default());
+        assert_eq!(ids(&log), ["b", "c"]);
+        assert_eq!(log.bytes(), std::fs::metadata(&path).unwrap().len());
+    }
+
+    #[test]
+    fn test_open_drops_unwanted_and_torn_lines() {
+        let path = log_path("load");
+        let old = serde_json::to_string(&entry("old", 0)).unwrap();
+        let new = serde_json::to_string(&entry("new", 5)).unwrap();
+        std::fs::write(&path, format!("{old}\n{new}\n{}", &new[..10])).unwrap();
+
+        let (log, loaded) = JsonlLog::open("log", &path, |entry: &Entry| entry.at > 0, |_| {}).unwrap();
+        assert_eq!(loaded, Loaded { dropped: 1, corrupt: 1 });
+        assert_eq!(ids(&log), ["new"]);
+    }
+
+    #[test]
+    fn test_push_respects_limits() {
+        let (mut log, _) = JsonlLog::open("log", &log_path("limits"), |_: &Entry| true, |_| {}).unwrap();
+        log.push(entry("a", 0), LIMITS).unwrap();
+        let one = Limits { max_events: 1, ..LIMITS };
+        assert!(log.push(entry("b", 0), one).unwrap_err().starts_with("log full"));
+
+        let tight = Limits { max_bytes: log.bytes(), ..LIMITS };
+        assert!(log.push(entry("b", 0), tight).is_err());
+        assert_eq!(ids(&log), ["a"]);
+
+        // Evicting makes room by dropping the oldest
+        assert_eq!(log.push_evicting(entry("b", 0), one), Ok(1));
+        assert_eq!(ids(&log), ["b"]);
+        assert!(log.push_evicting(entry("too-long-to-fit", 0), tight).is_err());
+    }
+
+    #[test]
+    fn test_removals_are_marked_then_compacted() {
+        let path = log_path("pop");
+        let reclaimed = Arc::new(AtomicU64::new(0));
+        let counted = Arc::clone(&reclaimed);
+        let on_reclaim = move |bytes| {
+            counted.fetch_add(bytes, Ordering::Relaxed);
+        };
+        let (mut log, _) = JsonlLog::open("log", &path, |_: &Entry| true, on_reclaim).unwrap();
+        log.push(entry("a", 0), LIMITS).unwrap();
+        log.push(entry("b", 5), LIMITS).unwrap();
+
+        assert_eq!(log.pop_front_while(|entry| entry.at < 5), 1);
+        assert_eq!(ids(&log), ["b"]);
+        log.flush().blocking_recv().unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(contents.lines().last(), Some("{\"drained\":1}"), "removals append a marker");
+
+        log.pop_front();
+        log.flush().blocking_recv().unwrap();
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), 0, "an empty log compacts the file");
+        assert_eq!(reclaimed.load(Ordering::Relaxed), contents.len() as u64 + DRAINED_LINE.len() as u64);
+    }
+}
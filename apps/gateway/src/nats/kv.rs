@@ -0,0 +1,85 @@
+//! NATS KV buckets shared between gateway pools
+//!
+//! Buckets are created on first use so fresh environments need no setup job.
+
+use crate::error::GatewayError;
+use async_nats::jetstream::kv::{Config, Store};
+use async_nats::jetstream::Context as JsContext;
+use std::time::Duration;
+use tracing::info;
+
+/// KV bucket names
+pub mod buckets {
+    /// Remaining identify budget (session_start_limit) shared by all pools
+    /// of a bot
+    pub const IDENTIFY_BUDGET: &str = "gateway_identify_budget";
+    /// Last identify per rate-limit bucket, keyed `bucket-{shard % max_concurrency}`
+    /// (see `bot_key` for the other bots of a process)
+    pub const IDENTIFY_SLOTS: &str = "gateway_identify_slots";
+    /// Shard sessions saved at shutdown for resume, keyed `shard-{id}`
+    pub const SESSIONS: &str = "gateway_sessions";
+    /// Per-pool topology documents, keyed `pool-{pool_id}`
+    pub const TOPOLOGY: &str = "gateway_topology";
+    /// Cluster shard count decided at startup (`TOTAL_SHARDS=auto`)
+    pub const SHARD_COUNT: &str = "gateway_shard_count";
+    /// Per-guild tick schedules, keyed by guild ID
+    pub const GUILD_SCHEDULES: &str = "guild_schedules";
+    /// Runtime config overrides, keyed by setting (`DYNAMIC_CONFIG_ENABLED`)
+    pub const CONFIG: &str = "gateway_config";
+    /// Guild ownership while resharding, keyed `default` and `guild-{id}`
+    pub const RESHARDING: &str = "gateway_resharding";
+}
+
+/// Key of an entry in a bucket shared by bots: the other bots of a process
+/// (`BOTS`) prefix theirs with their id, so they don't take the primary
+/// bot's sessions or identify slots
+pub fn bot_key(bot_id: Option<&str>, key: &str) -> String {
+    match bot_id {
+        Some(bot_id) => format!("{bot_id}.{key}"),
+        None => key.to_string(),
+    }
+}
+
+/// Open a KV bucket, creating it if it does not exist yet
+pub async fn open_bucket(
+    js: &JsContext,
+    bucket: &str,
+    description: &str,
+) -> Result<Store, GatewayError> {
+    open(js, bucket, description, Duration::ZERO).await
+}
+
+/// Open a KV bucket whose entries expire `max_age` after their last write.
+///
+/// The age is fixed by whichever pool creates the bucket.
+pub async fn open_expiring_bucket(
+    js: &JsContext,
+    bucket: &str,
+    description: &str,
+    max_age: Duration,
+) -> Result<Store, GatewayError> {
+    open(js, bucket, description, max_age).await
+}
+
+async fn open(js: &JsContext, bucket: &str, description: &str, max_age: Duration) -> Result<Store, GatewayError> {
+    if let Ok(store) = js.get_key_value(bucket).await {
+        return Ok(store);
+    }
+
+    let store = js
+        .create_key_value(Config {
+            bucket: bucket.to_string(),
+            description: description.to_string(),
+            history: 1,
+            max_age,
+            ..Default::default()
+        })
+        .await
+        .map_err(|e| GatewayError::NatsKvFailed {
+            bucket: bucket.to_string(),
+            source: Box::new(e),
+        })?;
+
+    info!(bucket, "Created NATS KV bucket");
+    Ok(store)
+}
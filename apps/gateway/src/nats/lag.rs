@@ -0,0 +1,133 @@
+//! Downstream consumer lag
+//!
+//! Polls JetStream consumer info for the known worker durables and exports
+//! their backlog, so the producer side can see when consumers are the
+//! bottleneck without access to the workers' own metrics.
+
+use super::NatsPublisher;
+use crate::error::GatewayError;
+use crate::metrics::GatewayMetrics;
+use async_nats::jetstream::consumer;
+use async_nats::jetstream::Context as JsContext;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info};
+
+/// A worker durable consumer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Durable {
+    pub stream: &'static str,
+    pub consumer: &'static str,
+}
+
+/// Worker durables (mirrors `consumers` in nats-routing.json)
+pub const KNOWN_DURABLES: &[Durable] = &[
+    Durable { stream: "COMMANDS", consumer: "command-worker" },
+    Durable { stream: "EVENTS", consumer: "event-worker" },
+    Durable { stream: "ELIGIBILITY", consumer: "eligibility-worker" },
+    Durable { stream: "ELIGIBILITY", consumer: "sync-worker" },
+    Durable { stream: "USAGE", consumer: "freeside-usage-finalizer" },
+];
+
+/// Backlog of one consumer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsumerLag {
+    pub durable: Durable,
+    /// Messages matching the filter not yet delivered
+    pub pending: u64,
+    /// Delivered but not yet acknowledged
+    pub ack_pending: u64,
+    /// Messages redelivered at least once
+    pub redelivered: u64,
+    /// Pull requests waiting (0 means no worker is pulling)
+    pub waiting: u64,
+    /// Stream sequences between the stream head and the ack floor
+    pub ack_floor_lag: u64,
+}
+
+impl ConsumerLag {
+    fn from_info(durable: Durable, info: &consumer::Info, stream_last_seq: u64) -> Self {
+        Self {
+            durable,
+            pending: info.num_pending,
+            ack_pending: info.num_ack_pending as u64,
+            redelivered: info.num_redelivered as u64,
+            waiting: info.num_waiting as u64,
+            ack_floor_lag: stream_last_seq.saturating_sub(info.ack_floor.stream_sequence),
+        }
+    }
+}
+
+/// Fetch lag for one durable
+async fn fetch(js: &JsContext, durable: Durable) -> Result<ConsumerLag, GatewayError> {
+    let failed = |source: Box<dyn std::error::Error + Send + Sync>| GatewayError::NatsConsumerInfoFailed {
+        stream: durable.stream,
+        consumer: durable.consumer,
+        source,
+    };
+
+    let stream = js.get_stream(durable.stream).await.map_err(|e| failed(Box::new(e)))?;
+    let last_seq = stream.cached_info().state.last_sequence;
+    let info = stream
+        .consumer_info(durable.consumer)
+        .await
+        .map_err(|e| failed(Box::new(e)))?;
+
+    Ok(ConsumerLag::from_info(durable, &info, last_seq))
+}
+
+/// Export consumer lag every `every` until the process exits
+pub async fn run_lag_monitor(nats: Arc<NatsPublisher>, metrics: Arc<GatewayMetrics>, every: Duration) {
+    info!(interval_secs = every.as_secs(), durables = KNOWN_DURABLES.len(), "Consumer lag monitor started");
+
+    let mut interval = tokio::time::interval(every);
+    loop {
+        interval.tick().await;
+
+        for &durable in KNOWN_DURABLES {
+            match fetch(nats.jetstream(), durable).await {
+                Ok(lag) => {
+                    debug!(stream = durable.stream, consumer = durable.consumer, pending = lag.pending, "Consumer lag");
+                    metrics.set_consumer_lag(&lag);
+                }
+                Err(e) => {
+                    // Missing consumers are normal where a worker isn't deployed
+                    metrics.set_consumer_up(durable.stream, durable.consumer, false);
+                    debug!(error = %e, "Consumer info unavailable");
+                    if !nats.is_server_connected() {
+                        debug!("NATS disconnected - skipping the rest of this consumer lag poll");
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lag_from_consumer_info() {
+        let info: consumer::Info = serde_json::from_value(serde_json::json!({
+            "stream_name": "EVENTS",
+            "name": "event-worker",
+            "created": "2026-01-01T00:00:00Z",
+            "config": { "durable_name": "event-worker", "deliver_policy": "all", "ack_policy": "explicit", "replay_policy": "instant" },
+            "delivered": { "consumer_seq": 120, "stream_seq": 950 },
+            "ack_floor": { "consumer_seq": 100, "stream_seq": 900 },
+            "num_ack_pending": 20,
+            "num_redelivered": 2,
+            "num_waiting": 1,
+            "num_pending": 50
+        }))
+        .unwrap();
+
+        let lag = ConsumerLag::from_info(KNOWN_DURABLES[1], &info, 1_000);
+        assert_eq!(lag.pending, 50);
+        assert_eq!(lag.ack_pending, 20);
+        assert_eq!(lag.waiting, 1);
+        assert_eq!(lag.ack_floor_lag, 100);
+    }
+}
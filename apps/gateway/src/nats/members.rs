@@ -0,0 +1,365 @@
+//! Guild member lists for workers over NATS
+//!
+//! Eligibility scans need a guild's full member list, which only the
+//! websocket hands out (Request Guild Members). A worker sends a request on
+//! `gateway.requests.member_chunk` (`fixtures/member-chunk-request.json`);
+//! the pool running the guild's shard sends the request with a fresh nonce
+//! and publishes every member chunk Discord answers it with to the request's
+//! reply subject (`fixtures/member-chunk.json`), so the worker reads
+//! `chunk_count` messages from its inbox. Other pools ignore the request.
+//! A request the shard can't send (intent missing, shard stopped) gets a
+//! single `{ "error": ... }` reply. Unfinished requests are forgotten after
+//! `PENDING_TTL`. Backfills (`backfill`) list members the same way but
+//! publish the chunks as `member.sync` events.
+
+use super::backfill::{self, Backfill};
+use super::requests::{self, Listener, Request};
+use super::NatsPublisher;
+use crate::events::entities;
+use crate::metrics::GatewayMetrics;
+use crate::shard::control::ShardControl;
+use crate::shard::{shard_for_guild, ShardState};
+use async_nats::Subject;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+use tracing::{debug, warn};
+use twilight_gateway::Intents;
+use twilight_model::gateway::payload::incoming::MemberChunk;
+use twilight_model::gateway::payload::outgoing::RequestGuildMembers;
+use twilight_model::gateway::presence::UserOrId;
+use twilight_model::id::marker::{GuildMarker, UserMarker};
+use twilight_model::id::Id;
+use uuid::Uuid;
+
+/// Request subject (mirrors `subjects.gateway_requests` in nats-routing.json)
+pub const SUBJECT: &str = "gateway.requests.member_chunk";
+
+/// How long a request waits for its last chunk
+const PENDING_TTL: Duration = Duration::from_secs(120);
+
+/// A member list request from a worker
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct MemberRequest {
+    pub guild_id: Id<GuildMarker>,
+    /// Username prefix; empty with `limit` 0 lists every member
+    #[serde(default)]
+    pub query: String,
+    /// Most members to return (0 for no limit)
+    #[serde(default)]
+    pub limit: u64,
+    /// Specific members (at most 100); replaces `query`
+    #[serde(default)]
+    pub user_ids: Vec<Id<UserMarker>>,
+    /// Include the members' presences
+    #[serde(default)]
+    pub presences: bool,
+}
+
+impl MemberRequest {
+    /// Intents Discord requires for the request
+    pub fn required_intents(&self) -> Intents {
+        let mut intents = Intents::empty();
+        if self.user_ids.is_empty() {
+            intents |= Intents::GUILD_MEMBERS;
+        }
+        if self.presences {
+            intents |= Intents::GUILD_PRESENCES;
+        }
+        intents
+    }
+
+    /// The gateway command, tagged with a nonce
+    pub fn command(&self, nonce: &str) -> Result<RequestGuildMembers, String> {
+        let builder = RequestGuildMembers::builder(self.guild_id).nonce(nonce).presences(self.presences);
+        if self.user_ids.is_empty() {
+            Ok(builder.query(self.query.clone(), Some(self.limit)))
+        } else {
+            builder.user_ids(self.user_ids.clone()).map_err(|e| e.to_string())
+        }
+    }
+}
+
+/// Reply payload for one chunk
+pub fn chunk_payload(chunk: &MemberChunk) -> Value {
+    json!({
+        "guild_id": chunk.guild_id.to_string(),
+        "chunk_index": chunk.chunk_index,
+        "chunk_count": chunk.chunk_count,
+        "members": chunk.members.iter().map(entities::guild_member).collect::<Vec<_>>(),
+        "not_found": chunk.not_found.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        "presences": chunk.presences.iter().map(|presence| json!({
+            "user_id": match &presence.user {
+                UserOrId::User(user) => user.id,
+                UserOrId::UserId { id } => *id,
+            }.to_string(),
+            "status": presence.status,
+        })).collect::<Vec<_>>(),
+    })
+}
+
+/// Where a request's chunks go
+#[derive(Clone)]
+enum Target {
+    /// Relayed to the worker's reply subject
+    Reply(Subject),
+    /// Published as `member.sync` pages
+    Backfill(Backfill),
+}
+
+/// A request waiting for its chunks
+struct Pending {
+    target: Target,
+    sent_at: Instant,
+    /// Resolves once the previous chunk has gone out (false if a backfill
+    /// page failed), so each chunk waits for the one before it
+    previous: Option<oneshot::Receiver<bool>>,
+}
+
+/// Requests this pool sent, by nonce
+pub struct MemberRequests {
+    nats: Arc<NatsPublisher>,
+    metrics: Arc<GatewayMetrics>,
+    pending: Mutex<HashMap<String, Pending>>,
+}
+
+impl std::fmt::Debug for MemberRequests {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let pending = self.pending.lock().map_or(0, |pending| pending.len());
+        f.debug_struct("MemberRequests").field("pending", &pending).finish()
+    }
+}
+
+impl MemberRequests {
+    pub fn new(nats: Arc<NatsPublisher>, metrics: Arc<GatewayMetrics>) -> Self {
+        Self { nats, metrics, pending: Mutex::default() }
+    }
+
+    pub fn nats(&self) -> &Arc<NatsPublisher> {
+        &self.nats
+    }
+
+    pub fn metrics(&self) -> &GatewayMetrics {
+        &self.metrics
+    }
+
+    /// Match a chunk to the request it answers. Returns the relay to the
+    /// worker, or the backfill page publish, for the shard to run off its
+    /// read loop; chunks of one request still go out in order.
+    pub fn deliver(self: &Arc<Self>, chunk: &MemberChunk) -> Option<impl Future<Output = ()> + Send + 'static> {
+        let nonce = chunk.nonce.as_deref()?;
+        let last = chunk.chunk_index + 1 >= chunk.chunk_count;
+        let (sent, next) = oneshot::channel();
+        let (target, previous) = {
+            let mut pending = self.pending.lock().expect("member request lock poisoned");
+            let request = pending.get_mut(nonce)?;
+            if let Target::Backfill(ref mut job) = request.target {
+                job.members += chunk.members.len() as u64;
+            }
+            let previous = request.previous.replace(next);
+            let target = request.target.clone();
+            if last {
+                pending.remove(nonce);
+            }
+            (target, previous)
+        };
+
+        let (requests, chunk, nonce) = (Arc::clone(self), chunk.clone(), nonce.to_string());
+        Some(async move {
+            let ok = match previous {
+                Some(previous) => previous.await.unwrap_or(false),
+                None => true,
+            };
+            let ok = match target {
+                Target::Reply(reply) => {
+                    requests.relay(reply, &chunk).await;
+                    ok
+                }
+                // A failed page fails the backfill; the rest are dropped
+                Target::Backfill(job) if ok => {
+                    let published = backfill::publish_page(&requests.nats, &requests.metrics, &job, &chunk).await;
+                    if !published {
+                        requests.pending.lock().expect("member request lock poisoned").remove(&nonce);
+                    }
+                    published
+                }
+                Target::Backfill(_) => false,
+            };
+            let _ = sent.send(ok);
+        })
+    }
+
+    /// Publish a chunk to the worker's reply subject
+    async fn relay(&self, reply: Subject, chunk: &MemberChunk) {
+        let payload = serde_json::to_vec(&chunk_payload(chunk)).expect("chunk payload serializes");
+        if let Err(e) = self.nats.client().publish(reply, payload.into()).await {
+            warn!(guild_id = %chunk.guild_id, error = %e, "Failed to relay member chunk");
+        }
+        if chunk.chunk_index + 1 >= chunk.chunk_count {
+            self.metrics.record_member_request("completed");
+        }
+    }
+
+    /// Forget requests that never got their last chunk
+    pub(super) async fn expire(&self) {
+        let expired: Vec<_> = {
+            let mut pending = self.pending.lock().expect("member request lock poisoned");
+            let mut expired = Vec::new();
+            pending.retain(|_, request| {
+                let keep = request.sent_at.elapsed() < PENDING_TTL;
+                if !keep {
+                    expired.push(request.target.clone());
+                }
+                keep
+            });
+            expired
+        };
+        for target in expired {
+            match target {
+                Target::Reply(_) => self.metrics.record_member_request("expired"),
+                Target::Backfill(job) => {
+                    warn!(job_id = %job.job_id, guild_id = %job.guild_id, members = job.members, "Backfill expired");
+                    self.metrics.record_backfill("expired");
+                    backfill::report(&self.nats, &job.progress("expired", 0, None)).await;
+                }
+            }
+        }
+    }
+
+    /// Send a request over the guild's shard, or the reason it can't be sent
+    fn send(&self, request: &MemberRequest, target: Target, control: &ShardControl, shard_id: u64) -> Result<(), String> {
+        let nonce = Uuid::new_v4().simple().to_string();
+        let command = request.command(&nonce)?;
+        self.pending
+            .lock()
+            .expect("member request lock poisoned")
+            .insert(nonce.clone(), Pending { target, sent_at: Instant::now(), previous: None });
+        if control.send_gateway(shard_id, &command) {
+            return Ok(());
+        }
+        self.pending.lock().expect("member request lock poisoned").remove(&nonce);
+        Err(format!("shard {shard_id} is not running"))
+    }
+
+    /// Request every member of a backfill's guild
+    pub(super) fn start_backfill(&self, job: Backfill, control: &ShardControl) -> Result<Backfill, String> {
+        let request =
+            MemberRequest { guild_id: job.guild_id, query: String::new(), limit: 0, user_ids: Vec::new(), presences: false };
+        self.send(&request, Target::Backfill(job.clone()), control, job.shard_id)?;
+        Ok(job)
+    }
+
+    /// Answer member requests for this pool's guilds until the process exits
+    pub async fn run(self: Arc<Self>, control: ShardControl, state: ShardState, intents: Intents) {
+        let Some(mut incoming) = Listener::subscribe(&self.nats, SUBJECT, "member request").await else {
+            return;
+        };
+        while let Some(Request { body: request, reply }) = incoming.next::<MemberRequest>().await {
+            self.expire().await;
+            // Every pool hears the request; the guild's shard answers it
+            let shard_id = shard_for_guild(request.guild_id.get(), state.total_shards());
+            if state.get_health(shard_id).is_none() {
+                continue;
+            }
+
+            let degraded = state.degraded_capabilities();
+            let missing: Vec<_> = request
+                .required_intents()
+                .iter_names()
+                .filter(|(name, intent)| !intents.contains(*intent) || degraded.contains(name))
+                .map(|(name, _)| name)
+                .collect();
+            let sent = if missing.is_empty() {
+                self.send(&request, Target::Reply(reply.clone()), &control, shard_id)
+            } else {
+                Err(format!("gateway is running without {}", missing.join(", ")))
+            };
+
+            match sent {
+                Ok(()) => self.metrics.record_member_request("sent"),
+                Err(error) => {
+                    debug!(guild_id = %request.guild_id, shard_id, error, "Member request rejected");
+                    self.metrics.record_member_request("rejected");
+                    requests::reply(&self.nats, reply, &json!({ "error": error })).await;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::policy;
+
+    fn fixture(name: &str) -> Value {
+        let path = format!("{}/../../packages/shared/nats-schemas/fixtures/{name}.json", env!("CARGO_MANIFEST_DIR"));
+        serde_json::from_str(&std::fs::read_to_string(path).expect("Failed to read fixture")).unwrap()
+    }
+
+    #[test]
+    fn requests_need_the_right_intents() {
+        let list: MemberRequest = serde_json::from_value(fixture("member-chunk-request")).unwrap();
+        assert_eq!(list.required_intents(), Intents::GUILD_MEMBERS);
+
+        let by_id: MemberRequest = serde_json::from_value(json!({
+            "guild_id": "123456789012345678",
+            "user_ids": ["987654321098765432"],
+            "presences": true
+        }))
+        .unwrap();
+        assert_eq!(by_id.required_intents(), Intents::GUILD_PRESENCES);
+    }
+
+    #[test]
+    fn commands_carry_the_nonce() {
+        let list: MemberRequest = serde_json::from_value(fixture("member-chunk-request")).unwrap();
+        let command = serde_json::to_value(list.command("abc").unwrap()).unwrap();
+        assert_eq!(command["op"], 8);
+        assert_eq!(command["d"]["nonce"], "abc");
+        assert_eq!(command["d"]["query"], "");
+        assert_eq!(command["d"]["limit"], 0);
+
+        let too_many = MemberRequest { user_ids: (1..=101).map(Id::new).collect(), ..list };
+        assert!(too_many.command("abc").is_err());
+    }
+
+    #[test]
+    fn chunk_payload_matches_fixture() {
+        let chunk: MemberChunk = serde_json::from_value(json!({
+            "guild_id": "123456789012345678",
+            "chunk_index": 0,
+            "chunk_count": 2,
+            "nonce": "2f1c7a9e0b4d4c5e9a8b7c6d5e4f3a2b",
+            "members": [{
+                "user": { "id": "987654321098765432", "username": "holder", "discriminator": "0",
+                          "global_name": "Holder", "avatar": null },
+                "nick": "fremen",
+                "roles": ["111111111111111111"],
+                "joined_at": "2024-01-15T10:30:00.000000+00:00",
+                "premium_since": null,
+                "deaf": false,
+                "mute": false,
+                "flags": 0,
+                "pending": false
+            }],
+            "not_found": ["222222222222222222"],
+            "presences": [{
+                "user": { "id": "987654321098765432" },
+                "guild_id": "123456789012345678",
+                "status": "online",
+                "client_status": { "desktop": "online" },
+                "activities": []
+            }]
+        }))
+        .unwrap();
+
+        let payload = chunk_payload(&chunk);
+        assert_eq!(payload, fixture("member-chunk"));
+        assert_eq!(policy::violations(&payload), Vec::<String>::new());
+    }
+}
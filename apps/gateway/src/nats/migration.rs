@@ -0,0 +1,127 @@
+//! Dual-publish of two schema versions during an envelope migration
+//!
+//! A breaking change to the envelope or a payload (`events::versions`) used
+//! to mean deploying the gateway and every worker at once. Instead, the
+//! gateway keeps publishing the version workers understand on the primary
+//! subjects (`WIRE_SCHEMA_VERSION`) and, with `SCHEMA_DUAL_PUBLISH` set,
+//! publishes each event a second time in the other version on
+//! `schema.v{version}.{subject}` in the SCHEMA stream, while the
+//! `dual-publish` flag is on. Workers move to the
+//! copies one by one; once all have, the primary version is bumped and dual
+//! publishing turned off.
+//!
+//! Both copies carry the same `event_id`. Copies are published after the
+//! primary publish succeeds and are not buffered by the outbox.
+
+use super::NatsPublisher;
+use crate::events::serialize::GatewayEvent;
+use crate::events::versions;
+use crate::flags::{FeatureFlags, DUAL_PUBLISH};
+use crate::metrics::GatewayMetrics;
+use async_nats::jetstream::stream::{Config, RetentionPolicy, StorageType};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// JetStream stream holding dual-published copies (mirrors nats-routing.json)
+pub const STREAM: &str = "SCHEMA";
+
+/// Dual-publish subjects (mirror nats-routing.json)
+pub mod subjects {
+    /// Copies: schema.v{version}.{subject}
+    pub const PREFIX: &str = "schema";
+}
+
+/// Copies are dropped after this long; a worker cut over to them keeps up
+/// like on the primary stream
+const MAX_AGE: Duration = Duration::from_secs(24 * 3600);
+
+/// Subject of a copy in `version` of an event published on `primary`
+pub fn subject(version: u32, primary: &str) -> String {
+    format!("{}.v{version}.{primary}", subjects::PREFIX)
+}
+
+/// Schema versions the gateway publishes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SchemaVersions {
+    /// Version on the primary subjects (`WIRE_SCHEMA_VERSION`)
+    pub wire: u32,
+    /// Version copied to `schema.v{version}.>` (`SCHEMA_DUAL_PUBLISH`)
+    pub dual_publish: Option<u32>,
+}
+
+impl Default for SchemaVersions {
+    fn default() -> Self {
+        Self { wire: versions::CURRENT, dual_publish: None }
+    }
+}
+
+/// Publishes the second-version copy of each published event
+pub struct DualPublish {
+    version: u32,
+    flags: Arc<FeatureFlags>,
+    metrics: Option<Arc<GatewayMetrics>>,
+    stream_ready: AtomicBool,
+}
+
+impl DualPublish {
+    pub fn new(version: u32, flags: Arc<FeatureFlags>, metrics: Option<Arc<GatewayMetrics>>) -> Self {
+        Self { version, flags, metrics, stream_ready: AtomicBool::new(false) }
+    }
+
+    /// Copy an event (in the current version) published on `primary`
+    pub(super) async fn publish(&self, nats: &NatsPublisher, event: &GatewayEvent, primary: &str) {
+        if !self.flags.is_enabled(DUAL_PUBLISH) {
+            return;
+        }
+        if !self.stream_ready.load(Ordering::Relaxed) && ensure_stream(nats).await {
+            self.stream_ready.store(true, Ordering::Relaxed);
+        }
+
+        let copy = versions::render(event, self.version);
+        let mut headers = async_nats::HeaderMap::new();
+        headers.insert(async_nats::header::NATS_MESSAGE_ID, event.event_id.as_str());
+        let target = subject(self.version, primary);
+        let outcome = match nats.publish_copy(&copy, nats.wire_format(), primary, &target, headers).await {
+            Ok(()) => "published",
+            Err(e) => {
+                debug!(event_id = %event.event_id, version = self.version, error = %e, "Schema copy publish failed");
+                "failed"
+            }
+        };
+        if let Some(ref metrics) = self.metrics {
+            metrics.record_schema_copy(self.version, outcome);
+        }
+    }
+}
+
+async fn ensure_stream(nats: &NatsPublisher) -> bool {
+    let config = Config {
+        name: STREAM.to_string(),
+        subjects: vec![format!("{}.>", subjects::PREFIX)],
+        retention: RetentionPolicy::Limits,
+        max_age: MAX_AGE,
+        storage: StorageType::File,
+        ..Default::default()
+    };
+
+    match nats.jetstream().get_or_create_stream(config).await {
+        Ok(_) => true,
+        Err(e) => {
+            warn!(stream = STREAM, error = %e, "Failed to create schema migration stream");
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_copies_are_published_under_their_version() {
+        assert_eq!(subject(2, "events.member.join"), "schema.v2.events.member.join");
+        assert_eq!(SchemaVersions::default(), SchemaVersions { wire: versions::CURRENT, dual_publish: None });
+    }
+}
@@ -3,6 +3,32 @@
 //! Sprint S-4: Twilight Gateway Core
 //! Publishes gateway events to NATS streams per SDD §7.1
 
+pub mod auth;
+pub mod backfill;
+pub mod batch;
+pub mod canary;
+pub mod dlq;
+pub mod eligibility;
+pub mod encryption;
+pub mod guilds;
+mod jsonl;
+pub mod kv;
+pub mod lag;
+pub mod members;
+pub mod migration;
+pub mod outbox;
+pub mod ownership;
+pub mod presence;
 mod publisher;
+pub mod quota;
+pub mod raw;
+pub mod recent;
+pub mod replay;
+pub mod requests;
+pub mod routing;
+pub mod service;
+pub mod signing;
+pub mod ticks;
+pub mod topology;
 
-pub use publisher::NatsPublisher;
+pub use publisher::{ensure_streams, CommandRouting, NatsPublisher, PublisherOptions};
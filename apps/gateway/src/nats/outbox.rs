@@ -0,0 +1,248 @@
+//! Disk-backed outbox for NATS outages
+//!
+//! With `OUTBOX_DIR` set, events that can't be published because NATS is
+//! unreachable are appended to `{OUTBOX_DIR}/outbox.jsonl` instead of being
+//! lost, and published in order once the connection is back. While a backlog
+//! exists, new events queue behind it, so consumers still see them in order.
+//! The file survives a restart of the process; entries older than
+//! `OUTBOX_MAX_AGE_SECS` are dropped instead of published. Interactions
+//! (`commands.>`) are never buffered: they must be answered within seconds.
+//!
+//! Only a lost connection pauses the drain. An entry that fails for a reason
+//! of its own (it no longer serializes, is too large, or no stream takes its
+//! subject) would fail forever, so it goes to the dead-letter queue (or is
+//! dropped without one) and the drain moves on to the next.
+
+use super::jsonl::{JsonlLog, Limits};
+use super::NatsPublisher;
+use crate::error::GatewayError;
+use crate::events::serialize::{now_millis, GatewayEvent};
+use crate::metrics::GatewayMetrics;
+use async_nats::jetstream::context::{PublishError, PublishErrorKind};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Backlog file inside `OUTBOX_DIR`
+const FILE_NAME: &str = "outbox.jsonl";
+
+/// How often the drain task checks for a backlog to publish
+const DRAIN_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Subjects that are buffered during an outage
+const BUFFERED_PREFIX: &str = "events.";
+
+/// Outbox location and limits
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutboxConfig {
+    pub dir: PathBuf,
+    pub max_events: usize,
+    pub max_bytes: u64,
+    /// Entries older than this are dropped instead of published
+    pub max_age: Duration,
+}
+
+impl OutboxConfig {
+    fn limits(&self) -> Limits {
+        Limits { max_events: self.max_events, max_bytes: self.max_bytes }
+    }
+}
+
+/// One buffered event, as a line of the backlog file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Entry {
+    /// When the event was buffered (Unix millis)
+    queued_at: u64,
+    subject: String,
+    event: GatewayEvent,
+}
+
+impl Entry {
+    fn expired(&self, now_ms: u64, max_age: Duration) -> bool {
+        now_ms.saturating_sub(self.queued_at) > max_age.as_millis() as u64
+    }
+}
+
+/// Events waiting for NATS to come back
+pub struct Outbox {
+    config: OutboxConfig,
+    backlog: Arc<Mutex<JsonlLog<Entry>>>,
+    metrics: Arc<GatewayMetrics>,
+}
+
+impl Outbox {
+    /// Open the outbox, picking up the backlog left by a previous run
+    pub fn open(config: OutboxConfig, metrics: Arc<GatewayMetrics>) -> Result<Self, GatewayError> {
+        let path = config.dir.join(FILE_NAME);
+        let now_ms = now_millis();
+        let reclaimed = {
+            let metrics = Arc::clone(&metrics);
+            move |bytes| metrics.record_outbox_reclaimed(bytes)
+        };
+        let (backlog, loaded) = std::fs::create_dir_all(&config.dir)
+            .and_then(|()| {
+                JsonlLog::open("outbox", &path, |entry: &Entry| !entry.expired(now_ms, config.max_age), reclaimed)
+            })
+            .map_err(|e| GatewayError::Config(format!("Failed to open outbox {}: {e}", path.display())))?;
+
+        info!(
+            path = %path.display(),
+            pending = backlog.len(),
+            expired = loaded.dropped,
+            corrupt = loaded.corrupt,
+            "Outbox opened"
+        );
+        metrics.record_outbox_events("expired", loaded.dropped as u64);
+        metrics.record_outbox_events("rejected", loaded.corrupt as u64);
+        metrics.set_outbox_backlog(backlog.len(), backlog.bytes());
+
+        let backlog = Arc::new(Mutex::new(backlog));
+        let estimate = Arc::clone(&backlog);
+        metrics.register_memory_estimator(
+            "outbox",
+            Arc::new(move || estimate.lock().unwrap_or_else(|e| e.into_inner()).bytes()),
+        );
+
+        Ok(Self { config, backlog, metrics })
+    }
+
+    /// Whether events on this subject are buffered during an outage
+    pub fn buffers(&self, subject: &str) -> bool {
+        subject.starts_with(BUFFERED_PREFIX)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Buffer an event; an error means it is lost
+    pub fn push(&self, subject: &str, event: &GatewayEvent) -> Result<(), GatewayError> {
+        let entry = Entry {
+            queued_at: now_millis(),
+            subject: subject.to_string(),
+            event: event.clone(),
+        };
+        let mut backlog = self.lock();
+        let pushed = backlog.push(entry, self.config.limits());
+        self.metrics.set_outbox_backlog(backlog.len(), backlog.bytes());
+
+        pushed.map_err(|reason| {
+            self.metrics.record_outbox_events("rejected", 1);
+            GatewayError::OutboxRejected { event_id: event.event_id.clone(), reason }
+        })?;
+        self.metrics.record_outbox_events("queued", 1);
+        Ok(())
+    }
+
+    /// Oldest unexpired entry, left in place until `pop_front`
+    fn front(&self) -> Option<(String, GatewayEvent)> {
+        let mut backlog = self.lock();
+        let now_ms = now_millis();
+        let dropped = backlog.pop_front_while(|entry| entry.expired(now_ms, self.config.max_age));
+        if dropped > 0 {
+            warn!(dropped, "Dropped outbox entries older than OUTBOX_MAX_AGE_SECS");
+            self.metrics.record_outbox_events("expired", dropped as u64);
+        }
+        self.metrics.set_outbox_backlog(backlog.len(), backlog.bytes());
+
+        let entry = backlog.front()?;
+        Some((entry.subject.clone(), entry.event.clone()))
+    }
+
+    /// Remove the oldest entry, counted as `drained` or `undeliverable`
+    fn pop_front(&self, outcome: &'static str) {
+        let mut backlog = self.lock();
+        backlog.pop_front();
+        self.metrics.record_outbox_events(outcome, 1);
+        self.metrics.set_outbox_backlog(backlog.len(), backlog.bytes());
+    }
+
+    /// Wait until the backlog file has caught up
+    pub async fn flush(&self) {
+        let flushed = self.lock().flush();
+        let _ = flushed.await;
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, JsonlLog<Entry>> {
+        self.backlog.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Publish the backlog, oldest first, whenever NATS is reachable
+pub async fn run_drain(nats: Arc<NatsPublisher>) {
+    let mut interval = tokio::time::interval(DRAIN_INTERVAL);
+    loop {
+        interval.tick().await;
+        let Some(outbox) = nats.outbox() else {
+            return;
+        };
+        if outbox.is_empty() || !nats.is_server_connected() {
+            continue;
+        }
+
+        info!("Draining outbox");
+        let mut drained = 0_u64;
+        while let Some((subject, event)) = outbox.front() {
+            match nats.publish_to(&event, subject.clone()).await {
+                Ok(()) => {
+                    outbox.pop_front("drained");
+                    drained += 1;
+                }
+                // Try again once the connection is back
+                Err(e) if connection_lost(&e) || !nats.is_server_connected() => {
+                    warn!(drained, error = %e, "Outbox drain interrupted");
+                    break;
+                }
+                // This entry will never publish; don't hold the backlog behind it
+                Err(e) => {
+                    warn!(event_id = %event.event_id, subject, error = %e, "Outbox entry can't be published, setting it aside");
+                    nats.dead_letter(&event, &subject, &e);
+                    outbox.pop_front("undeliverable");
+                }
+            }
+        }
+        info!(drained, "Outbox drain finished");
+    }
+}
+
+/// Whether a publish failed on the connection rather than on the event
+fn connection_lost(error: &GatewayError) -> bool {
+    let GatewayError::NatsPublishFailed { source, .. } = error else {
+        return false;
+    };
+    source.downcast_ref::<PublishError>().is_some_and(|e| {
+        matches!(e.kind(), PublishErrorKind::TimedOut | PublishErrorKind::BrokenPipe | PublishErrorKind::MaxAckPending)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entries_expire_after_max_age() {
+        let hour = Duration::from_secs(3600);
+        let entry = Entry {
+            queued_at: 1_000,
+            subject: "events.member.join".to_string(),
+            event: GatewayEvent::for_test("member.join"),
+        };
+        assert!(!entry.expired(1_000 + hour.as_millis() as u64, hour));
+        assert!(entry.expired(1_001 + hour.as_millis() as u64, hour));
+    }
+
+    #[test]
+    fn test_only_connection_failures_pause_the_drain() {
+        let failed = |kind| GatewayError::NatsPublishFailed {
+            subject: "events.member.join".to_string(),
+            source: Box::new(PublishError::new(kind)),
+        };
+        assert!(connection_lost(&failed(PublishErrorKind::TimedOut)));
+        assert!(connection_lost(&failed(PublishErrorKind::BrokenPipe)));
+        assert!(!connection_lost(&failed(PublishErrorKind::StreamNotFound)));
+        assert!(!connection_lost(&failed(PublishErrorKind::Other)));
+        assert!(!connection_lost(&GatewayError::Config("event too large".to_string())));
+    }
+}
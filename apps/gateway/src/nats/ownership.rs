@@ -0,0 +1,88 @@
+//! Which shard and pool own a guild
+//!
+//! Routing layers and debugging tools send guild-scoped control messages to
+//! the pool running the guild's shard. A worker sends `{ "guild_id": ... }`
+//! (`fixtures/guild-request.json`) as a request on
+//! `gateway.requests.guild_shard`; the pool running the guild's shard answers
+//! with `fixtures/guild-shard.json`: the shard from Discord's formula, the
+//! pool whose range holds it, and the shard's health. Other pools ignore the
+//! request, so one that times out means no pool is running the shard.
+//!
+//! `GET /guilds/{guild_id}/shard` serves the same body from any pool, with
+//! `running: false` and no health when that pool doesn't run the shard.
+
+use super::guilds::GuildRequest;
+use super::requests::{self, Listener, Request};
+use super::NatsPublisher;
+use crate::shard::{pool_for_shard, shard_for_guild, ShardState};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+/// Request subject (mirrors `subjects.gateway_requests` in nats-routing.json)
+pub const SUBJECT: &str = "gateway.requests.guild_shard";
+
+/// Where a guild lives, as seen from this pool: here when this pool runs
+/// the shard (its own or one handed to it), otherwise the pool whose range
+/// holds it
+pub fn owner(guild_id: u64, state: &ShardState) -> Value {
+    let shard_id = shard_for_guild(guild_id, state.total_shards());
+    let health = state.get_health(shard_id);
+    let pool_id = match health {
+        Some(_) => state.pool_id(),
+        None => pool_for_shard(shard_id, state.shards_per_pool()),
+    };
+    json!({
+        "guild_id": guild_id.to_string(),
+        "shard_id": shard_id,
+        "total_shards": state.total_shards(),
+        "shards_per_pool": state.shards_per_pool(),
+        "pool_id": pool_id,
+        "running": health.is_some(),
+        "health": health.map(|health| health.as_str()),
+    })
+}
+
+/// Answer ownership requests for this pool's guilds until the process exits
+pub async fn run(nats: Arc<NatsPublisher>, state: ShardState) {
+    let Some(mut incoming) = Listener::subscribe(&nats, SUBJECT, "guild shard request").await else {
+        return;
+    };
+    while let Some(Request { body: request, reply }) = incoming.next::<GuildRequest>().await {
+        // Every pool hears the request; the guild's shard answers it
+        let answer = owner(request.guild_id.get(), &state);
+        if answer["running"] == true {
+            requests::reply(&nats, reply, &answer).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shard::ShardHealth;
+
+    fn fixture(name: &str) -> Value {
+        let path = format!("{}/../../packages/shared/nats-schemas/fixtures/{name}.json", env!("CARGO_MANIFEST_DIR"));
+        serde_json::from_str(&std::fs::read_to_string(path).expect("Failed to read fixture")).unwrap()
+    }
+
+    #[test]
+    fn owner_matches_fixture() {
+        let request: GuildRequest = serde_json::from_value(fixture("guild-request")).unwrap();
+        // Pool 0 of 100 shards runs 0..25; the guild is on shard 16
+        let state = ShardState::new(0, 0..25, 100, 25);
+        state.set_health(16, ShardHealth::Ready);
+        assert_eq!(owner(request.guild_id.get(), &state), fixture("guild-shard"));
+
+        // Pool 1 knows where the guild lives but doesn't run it
+        let other = owner(request.guild_id.get(), &ShardState::new(1, 25..50, 100, 25));
+        assert_eq!((other["shard_id"].as_u64(), other["pool_id"].as_u64()), (Some(16), Some(0)));
+        assert_eq!((&other["running"], &other["health"]), (&json!(false), &Value::Null));
+
+        // Pool 2 adopted the shard from pool 0
+        let adopter = ShardState::new(2, 50..75, 100, 25);
+        adopter.adopt(16);
+        let adopted = owner(request.guild_id.get(), &adopter);
+        assert_eq!((adopted["pool_id"].as_u64(), &adopted["running"]), (Some(2), &json!(true)));
+    }
+}
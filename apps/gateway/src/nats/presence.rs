@@ -0,0 +1,234 @@
+//! Bot presence set by workers over NATS
+//!
+//! Workers publish `gateway.presence.update` (`fixtures/presence-update.json`)
+//! to change the bot's status and activity, e.g. "Watching 12,345 holders".
+//! Every pool applies it to all of its shards, or only to `shard_id` when the
+//! message names one. The last presence is kept and sent again when a shard
+//! identifies a new session, since Discord resets presence on identify
+//! (resumed sessions keep theirs).
+
+use super::NatsPublisher;
+use crate::metrics::GatewayMetrics;
+use crate::shard::control::ShardControl;
+use futures_util::StreamExt as _;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tracing::{debug, info, warn};
+use twilight_model::gateway::payload::outgoing::update_presence::UpdatePresencePayload;
+use twilight_model::gateway::payload::outgoing::UpdatePresence;
+use twilight_model::gateway::presence::{Activity, ActivityType, MinimalActivity, Status};
+use twilight_model::gateway::OpCode;
+
+/// Update subject (mirrors `subjects.presence` in nats-routing.json)
+pub const SUBJECT: &str = "gateway.presence.update";
+
+/// What the bot is shown doing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ActivityKind {
+    Playing,
+    Streaming,
+    Listening,
+    Watching,
+    Competing,
+    /// A custom status: just the text
+    Custom,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ActivityUpdate {
+    #[serde(rename = "type")]
+    pub kind: ActivityKind,
+    /// Text shown after the verb ("12,345 holders"), or the custom status
+    pub name: String,
+    /// Stream URL, for `streaming`
+    #[serde(default)]
+    pub url: Option<String>,
+}
+
+/// A presence change from a worker
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct PresenceUpdate {
+    /// Only this shard; all of them when unset
+    #[serde(default)]
+    pub shard_id: Option<u64>,
+    /// online, idle, dnd or invisible (online when unset)
+    #[serde(default)]
+    pub status: Option<Status>,
+    /// None clears the activity
+    #[serde(default)]
+    pub activity: Option<ActivityUpdate>,
+    #[serde(default)]
+    pub afk: bool,
+}
+
+impl PresenceUpdate {
+    /// The gateway command
+    pub fn command(&self) -> Result<UpdatePresence, String> {
+        let status = self.status.unwrap_or(Status::Online);
+        if status == Status::Offline {
+            return Err("status offline is not settable (use invisible)".to_string());
+        }
+        let activities = match &self.activity {
+            Some(activity) if activity.name.trim().is_empty() => return Err("activity name is empty".to_string()),
+            Some(activity) => vec![activity.to_activity()],
+            None => Vec::new(),
+        };
+        // UpdatePresence::new refuses an empty activity list, which is how
+        // an activity is cleared
+        Ok(UpdatePresence {
+            d: UpdatePresencePayload { activities, afk: self.afk, since: None, status },
+            op: OpCode::PresenceUpdate,
+        })
+    }
+}
+
+impl ActivityUpdate {
+    fn to_activity(&self) -> Activity {
+        let (kind, name) = match self.kind {
+            ActivityKind::Playing => (ActivityType::Playing, self.name.clone()),
+            ActivityKind::Streaming => (ActivityType::Streaming, self.name.clone()),
+            ActivityKind::Listening => (ActivityType::Listening, self.name.clone()),
+            ActivityKind::Watching => (ActivityType::Watching, self.name.clone()),
+            ActivityKind::Competing => (ActivityType::Competing, self.name.clone()),
+            // Discord shows a custom status's `state`; `name` is required but unused
+            ActivityKind::Custom => (ActivityType::Custom, "Custom Status".to_string()),
+        };
+        let mut activity = Activity::from(MinimalActivity { kind, name, url: self.url.clone() });
+        if self.kind == ActivityKind::Custom {
+            activity.state = Some(self.name.clone());
+        }
+        activity
+    }
+}
+
+/// The presence workers last set, for shards that identify again
+pub struct PresenceUpdates {
+    nats: Arc<NatsPublisher>,
+    metrics: Arc<GatewayMetrics>,
+    /// Set for every shard
+    all: Mutex<Option<UpdatePresence>>,
+    /// Set for one shard, newer than `all`
+    shards: Mutex<HashMap<u64, UpdatePresence>>,
+}
+
+impl std::fmt::Debug for PresenceUpdates {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PresenceUpdates").field("all", &self.all).field("shards", &self.shards).finish()
+    }
+}
+
+impl PresenceUpdates {
+    pub fn new(nats: Arc<NatsPublisher>, metrics: Arc<GatewayMetrics>) -> Self {
+        Self { nats, metrics, all: Mutex::default(), shards: Mutex::default() }
+    }
+
+    fn remember(&self, shard_id: Option<u64>, command: &UpdatePresence) {
+        let mut shards = self.shards.lock().expect("presence lock poisoned");
+        match shard_id {
+            Some(shard_id) => {
+                shards.insert(shard_id, command.clone());
+            }
+            None => {
+                shards.clear();
+                *self.all.lock().expect("presence lock poisoned") = Some(command.clone());
+            }
+        }
+    }
+
+    fn current(&self, shard_id: u64) -> Option<UpdatePresence> {
+        let shards = self.shards.lock().expect("presence lock poisoned");
+        shards.get(&shard_id).cloned().or_else(|| self.all.lock().expect("presence lock poisoned").clone())
+    }
+
+    /// Send the last presence to a shard that started a new session
+    pub fn restore(&self, shard_id: u64, control: &ShardControl) {
+        if let Some(command) = self.current(shard_id) {
+            let outcome = if control.send_gateway(shard_id, &command) { "restored" } else { "unsent" };
+            self.metrics.record_presence_update(outcome);
+        }
+    }
+
+    /// Apply presence updates to this pool's shards until the process exits
+    pub async fn run(self: Arc<Self>, control: ShardControl) {
+        let mut updates = match self.nats.client().subscribe(SUBJECT).await {
+            Ok(updates) => updates,
+            Err(e) => {
+                warn!(subject = SUBJECT, error = %e, "Failed to subscribe to presence updates");
+                return;
+            }
+        };
+        info!(subject = SUBJECT, "Presence update listener started");
+
+        while let Some(message) = updates.next().await {
+            let parsed = serde_json::from_slice::<PresenceUpdate>(&message.payload)
+                .map_err(|e| e.to_string())
+                .and_then(|update| Ok((update.command()?, update)));
+            let (command, update) = match parsed {
+                Ok(parsed) => parsed,
+                Err(error) => {
+                    debug!(error, "Ignoring invalid presence update");
+                    self.metrics.record_presence_update("invalid");
+                    continue;
+                }
+            };
+
+            let shard_ids = match update.shard_id {
+                // Another pool runs it
+                Some(shard_id) if !control.shard_ids().contains(&shard_id) => continue,
+                Some(shard_id) => vec![shard_id],
+                None => control.shard_ids(),
+            };
+            self.remember(update.shard_id, &command);
+            for shard_id in shard_ids {
+                let outcome = if control.send_gateway(shard_id, &command) { "sent" } else { "unsent" };
+                self.metrics.record_presence_update(outcome);
+            }
+            debug!(shard_id = ?update.shard_id, status = ?command.d.status, "Presence updated");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn update(value: serde_json::Value) -> PresenceUpdate {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn fixture_builds_a_command() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/../../packages/shared/nats-schemas/fixtures/presence-update.json");
+        let fixture: PresenceUpdate =
+            serde_json::from_str(&std::fs::read_to_string(path).expect("Failed to read fixture")).unwrap();
+        let command = serde_json::to_value(fixture.command().unwrap()).unwrap();
+
+        assert_eq!(command["op"], 3);
+        assert_eq!(command["d"]["status"], "online");
+        assert_eq!(command["d"]["activities"][0]["type"], 3);
+        assert_eq!(command["d"]["activities"][0]["name"], "12,345 holders");
+    }
+
+    #[test]
+    fn custom_status_and_clearing() {
+        let custom = update(json!({ "status": "idle", "activity": { "type": "custom", "name": "Syncing roles" } }));
+        let activity = &custom.command().unwrap().d.activities[0];
+        assert_eq!(activity.kind, ActivityType::Custom);
+        assert_eq!(activity.state.as_deref(), Some("Syncing roles"));
+
+        let cleared = update(json!({ "status": "dnd" })).command().unwrap();
+        assert!(cleared.d.activities.is_empty());
+        assert_eq!(cleared.d.status, Status::DoNotDisturb);
+    }
+
+    #[test]
+    fn invalid_updates_are_rejected() {
+        assert!(update(json!({ "status": "offline" })).command().is_err());
+        assert!(update(json!({ "activity": { "type": "watching", "name": " " } })).command().is_err());
+        assert!(serde_json::from_value::<PresenceUpdate>(json!({ "activity": { "type": "sleeping", "name": "x" } }))
+            .is_err());
+    }
+}
@@ -4,12 +4,43 @@
 #![allow(dead_code)] // Scaffolded for NATS event publishing
 
 use crate::error::GatewayError;
-use crate::events::serialize::GatewayEvent;
+use super::auth::NatsSecurity;
+use super::batch::{BatchConfig, Pending, PublishBatch};
+use super::canary::Canary;
+use super::dlq::{self, DeadLetters, RetryPolicy};
+use super::eligibility::{self, EligibilityTriggers};
+use super::encryption::PayloadCipher;
+use super::migration::{DualPublish, SchemaVersions};
+use super::outbox::Outbox;
+use super::quota::PublishQuotas;
+use super::raw::{RawPassthrough, RawTap};
+use super::routing::{self, StreamSpec};
+use crate::events::redaction::Redactor;
+use crate::events::versions;
+use crate::shard::canary::{self as canary_shard, CanaryShard, Cohort};
+use crate::shard::reshard;
+use crate::events::schema::SchemaValidator;
+use super::recent::RecentEvents;
+use super::signing::EventSigner;
+use crate::events::serialize::{now_millis, GatewayEvent, WireFormat, CONTENT_TYPE_HEADER};
+use crate::flags::FeatureFlags;
+use crate::metrics::GatewayMetrics;
+use crate::telemetry;
+use async_nats::jetstream::context::{PublishAckFuture, PublishError};
+use async_nats::jetstream::publish::PublishAck;
+use async_nats::jetstream::stream::Config;
 use async_nats::jetstream::{self, Context as JsContext};
-use async_nats::Client;
+use async_nats::{Client, Event};
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::future::IntoFuture;
 use std::sync::Arc;
-use tracing::{debug, error, info, warn};
+use std::time::{Duration, Instant};
+use tracing::{debug, error, info, info_span, warn, Instrument};
+
+/// Delay after the first failed connection attempt; doubles per further
+/// attempt up to `RECONNECT_MAX_DELAY`
+const RECONNECT_BASE_DELAY: Duration = Duration::from_millis(250);
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(30);
 
 /// Stream names per SDD §7.1.1
 pub mod streams {
@@ -19,80 +50,249 @@ pub mod streams {
     pub const EVENTS: &str = "EVENTS";
     /// Eligibility stream for token checks
     pub const ELIGIBILITY: &str = "ELIGIBILITY";
+    /// Messages stream for message events (FORWARD_MESSAGES)
+    pub const MESSAGES: &str = "MESSAGES";
+    /// Voice stream for voice state and server updates (FORWARD_VOICE)
+    pub const VOICE: &str = "VOICE";
 }
 
 /// Subject prefixes for routing
 pub mod subjects {
     /// Slash commands: commands.{command_name}
     pub const COMMANDS: &str = "commands";
+    /// Interactions without a guild (DMs, user-installed apps): commands.dm.{command_name}
+    pub const DM_COMMANDS: &str = "commands.dm";
     /// Guild events: events.guild.{event_type}
     pub const GUILD_EVENTS: &str = "events.guild";
     /// Member events: events.member.{event_type}
     pub const MEMBER_EVENTS: &str = "events.member";
+    /// Gateway operational events: events.gateway.{event_type}
+    pub const GATEWAY_EVENTS: &str = "events.gateway";
+    /// Reaction events: events.reaction.{event_type}
+    pub const REACTION_EVENTS: &str = "events.reaction";
+    /// Role events: events.role.{event_type}
+    pub const ROLE_EVENTS: &str = "events.role";
+    /// Channel events: events.channel.{event_type}
+    pub const CHANNEL_EVENTS: &str = "events.channel";
+    /// Thread events: events.thread.{event_type}
+    pub const THREAD_EVENTS: &str = "events.thread";
+    /// Message events: messages.{event_type}
+    pub const MESSAGE_EVENTS: &str = "messages";
+    /// Voice events: voice.{event_type}
+    pub const VOICE_EVENTS: &str = "voice";
+    /// Windowed summaries: events.summary.{summarized_event_type}
+    pub const SUMMARY_EVENTS: &str = "events.summary";
+    /// Usage events: inference.usage.{event_type} (produced by loa-finn, not the gateway)
+    pub const USAGE: &str = "inference.usage";
     /// Interactions: commands.interaction
     pub const INTERACTION: &str = "commands.interaction";
 }
 
+/// Where guild command interactions are published (`COMMAND_SUBJECTS`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CommandRouting {
+    /// Every guild interaction on commands.interaction
+    #[default]
+    Interaction,
+    /// Commands and autocomplete on commands.{command_name}, so workers can
+    /// subscribe per command; components and modals stay on commands.interaction
+    PerCommand,
+}
+
+impl CommandRouting {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim() {
+            "interaction" => Some(Self::Interaction),
+            "per-command" => Some(Self::PerCommand),
+            _ => None,
+        }
+    }
+}
+
+/// Optional publish behaviour
+#[derive(Default)]
+pub struct PublisherOptions {
+    /// Recently published events kept for lookup
+    pub event_index_size: usize,
+    /// Copies a sample of events in a candidate wire format
+    pub canary: Option<Canary>,
+    /// Shapes publishes to per-stream budgets
+    pub quotas: Option<PublishQuotas>,
+    /// Selects events whose Discord payload is published too
+    pub raw: Option<RawPassthrough>,
+    /// Interactions that also request an eligibility check
+    pub eligibility: Option<EligibilityTriggers>,
+    /// Redacts PII from payloads before they are published
+    pub redaction: Option<Redactor>,
+    /// Checks events against the wire schema before publishing
+    pub schema: Option<SchemaValidator>,
+    /// Buffers events while NATS is unreachable
+    pub outbox: Option<Outbox>,
+    /// Counts reconnects (None for one-shot connections, e.g. `topo`)
+    pub metrics: Option<Arc<GatewayMetrics>>,
+    /// Subjects for guild command interactions
+    pub command_routing: CommandRouting,
+    /// Signs event payloads
+    pub signer: Option<EventSigner>,
+    /// Encrypts payloads on sensitive subjects
+    pub encryption: Option<PayloadCipher>,
+    /// Encoding of events on their primary subjects
+    pub wire_format: WireFormat,
+    /// Schema version on the primary subjects, and the one dual-published
+    pub schema_versions: SchemaVersions,
+    /// Runtime flags (`dual-publish` turns schema copies on and off)
+    pub flags: Arc<FeatureFlags>,
+    /// One shard publishing with candidate settings
+    pub canary_shard: Option<CanaryShard>,
+    /// Shard count tagged on events while resharding
+    pub shard_epoch: Option<u64>,
+    /// Confirms publish acks in batches instead of one by one
+    pub batch: Option<PublishBatch>,
+    /// Retries for failed publishes
+    pub retry: RetryPolicy,
+    /// Keeps events still unpublished after their retries
+    pub dead_letters: Option<DeadLetters>,
+    /// Publishes slower than this are logged with their trace ID
+    pub slow_publish: Option<Duration>,
+    /// Authentication and TLS for the connection
+    pub security: NatsSecurity,
+}
+
 /// NATS publisher for gateway events
 pub struct NatsPublisher {
     client: Client,
     jetstream: JsContext,
-    connected: AtomicBool,
+    connected: Arc<AtomicBool>,
     messages_published: AtomicU64,
     publish_failures: AtomicU64,
+    recent: RecentEvents,
+    canary: Option<Canary>,
+    quotas: Option<PublishQuotas>,
+    raw: Option<RawTap>,
+    eligibility: Option<EligibilityTriggers>,
+    redaction: Option<Redactor>,
+    schema: Option<SchemaValidator>,
+    outbox: Option<Outbox>,
+    command_routing: CommandRouting,
+    signer: Option<EventSigner>,
+    encryption: Option<PayloadCipher>,
+    wire_format: WireFormat,
+    schema_version: u32,
+    dual_publish: Option<DualPublish>,
+    canary_shard: Option<CanaryShard>,
+    shard_epoch: Option<u64>,
+    batch: Option<PublishBatch>,
+    retry: RetryPolicy,
+    dead_letters: Option<DeadLetters>,
+    slow_publish: Option<Duration>,
+    metrics: Option<Arc<GatewayMetrics>>,
 }
 
 impl NatsPublisher {
     /// Connect to NATS server.
-    /// SEC-4.4: When the URL uses `tls://`, configures TLS with the CA
-    /// certificate from `NATS_TLS_CA` for self-signed cert verification.
-    pub async fn connect(servers: &str) -> Result<Arc<Self>, GatewayError> {
+    /// SEC-4.4: TLS is used for `tls://` URLs, `NATS_TLS` or a client
+    /// certificate, verified against the CA bundle from `NATS_TLS_CA` when
+    /// set (self-signed certs). Credentials are read from their secret
+    /// sources on every (re)connect.
+    ///
+    /// Lost connections are retried with exponential backoff; connection
+    /// events keep `is_connected()` current.
+    pub async fn connect(servers: &str, options: PublisherOptions) -> Result<Arc<Self>, GatewayError> {
         info!(servers, "Connecting to NATS");
 
-        let needs_tls = servers.contains("tls://");
-
-        let client = if needs_tls {
-            let mut opts = async_nats::ConnectOptions::new().require_tls(true);
-
-            // SEC-4.4: Write CA cert to disk for async-nats TLS verification.
-            // Mirrors the NATS server entrypoint pattern (nats.tf).
-            if let Ok(ca_pem) = std::env::var("NATS_TLS_CA") {
-                let ca_path = std::path::PathBuf::from("/tmp/nats-ca.crt");
-                std::fs::write(&ca_path, ca_pem.as_bytes())
-                    .map_err(|e| GatewayError::Config(format!("Failed to write NATS CA cert to {}: {e}", ca_path.display())))?;
-                opts = opts.add_root_certificates(ca_path);
-                info!("NATS TLS configured with custom CA certificate");
-            } else {
-                warn!("NATS TLS URL but NATS_TLS_CA not set — using system root certs");
+        let security = &options.security;
+        let needs_tls = security.requires_tls(servers);
+        let connected = Arc::new(AtomicBool::new(true));
+
+        let mut opts = security
+            .connect_options()
+            .await?
+            .reconnect_delay_callback(reconnect_delay)
+            .event_callback({
+                let connected = Arc::clone(&connected);
+                let metrics = options.metrics.clone();
+                move |event| {
+                    on_connection_event(&connected, metrics.as_deref(), &event);
+                    async {}
+                }
+            });
+
+        if needs_tls {
+            opts = opts.require_tls(true);
+            if security.ca.is_none() {
+                warn!("NATS TLS required but NATS_TLS_CA not set — using system root certs");
             }
+        }
 
-            opts.connect(servers)
-                .await
-                .map_err(|e| GatewayError::NatsConnectionFailed(Box::new(e)))?
-        } else {
-            async_nats::connect(servers)
-                .await
-                .map_err(|e| GatewayError::NatsConnectionFailed(Box::new(e)))?
-        };
+        let client = opts
+            .connect(servers)
+            .await
+            .map_err(|e| GatewayError::NatsConnectionFailed(Box::new(e)))?;
 
         let jetstream = jetstream::new(client.clone());
 
-        info!(tls = needs_tls, "Connected to NATS JetStream");
+        info!(
+            tls = needs_tls,
+            client_cert = security.client_cert.is_some(),
+            auth = security.auth.as_ref().map_or("none", |auth| auth.as_str()),
+            "Connected to NATS JetStream"
+        );
 
         Ok(Arc::new(Self {
             client,
             jetstream,
-            connected: AtomicBool::new(true),
+            connected,
             messages_published: AtomicU64::new(0),
             publish_failures: AtomicU64::new(0),
+            recent: RecentEvents::new(options.event_index_size),
+            canary: options.canary,
+            quotas: options.quotas,
+            raw: options.raw.map(RawTap::new),
+            eligibility: options.eligibility,
+            redaction: options.redaction,
+            schema: options.schema,
+            outbox: options.outbox,
+            command_routing: options.command_routing,
+            signer: options.signer,
+            encryption: options.encryption,
+            wire_format: options.wire_format,
+            schema_version: options.schema_versions.wire,
+            dual_publish: options
+                .schema_versions
+                .dual_publish
+                .map(|version| DualPublish::new(version, Arc::clone(&options.flags), options.metrics.clone())),
+            canary_shard: options.canary_shard,
+            shard_epoch: options.shard_epoch,
+            batch: options.batch,
+            retry: options.retry,
+            dead_letters: options.dead_letters,
+            slow_publish: options.slow_publish,
+            metrics: options.metrics,
         }))
     }
 
+    /// Core NATS client (request-reply services)
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// JetStream context (KV buckets, stream management)
+    pub fn jetstream(&self) -> &JsContext {
+        &self.jetstream
+    }
+
     /// Check if connected
     pub fn is_connected(&self) -> bool {
         self.connected.load(Ordering::SeqCst)
     }
 
+    /// Check the client's live connection to the server (false while
+    /// async-nats is reconnecting)
+    pub fn is_server_connected(&self) -> bool {
+        self.is_connected()
+            && self.client.connection_state() == async_nats::connection::State::Connected
+    }
+
     /// Get total messages published
     pub fn messages_published(&self) -> u64 {
         self.messages_published.load(Ordering::Relaxed)
@@ -103,14 +303,194 @@ impl NatsPublisher {
         self.publish_failures.load(Ordering::Relaxed)
     }
 
+    /// Recently published events, by `event_id`
+    pub fn recent_events(&self) -> &RecentEvents {
+        &self.recent
+    }
+
+    /// Outbox buffering events during NATS outages, if configured
+    pub fn outbox(&self) -> Option<&Outbox> {
+        self.outbox.as_ref()
+    }
+
+    /// Dead-letter queue, if configured
+    pub fn dead_letters(&self) -> Option<&DeadLetters> {
+        self.dead_letters.as_ref()
+    }
+
+    /// Whether raw passthrough is configured (callers keep dispatch payloads)
+    pub fn wants_raw(&self) -> bool {
+        self.raw.is_some()
+    }
+
+    /// Publish the Discord dispatch an event was normalized from, if raw
+    /// passthrough selects the event
+    pub async fn publish_raw(&self, event: &GatewayEvent, dispatch: String) {
+        if let Some(ref raw) = self.raw {
+            let subject = self.subject(event);
+            // Discord's payload would carry what encryption or redaction hides
+            if self.encryption.as_ref().is_some_and(|cipher| cipher.covers(&subject))
+                || self.redaction.as_ref().is_some_and(|redaction| redaction.covers(&event.event_type))
+            {
+                return;
+            }
+            raw.publish(self, event, &subject, dispatch).await;
+        }
+    }
+
+    /// Publish the eligibility check an interaction asks for, if any
+    pub async fn publish_eligibility(&self, event: &GatewayEvent) {
+        if let Some(ref triggers) = self.eligibility {
+            eligibility::publish(self, triggers, event, self.metrics.as_deref()).await;
+        }
+    }
+
     /// Publish a gateway event to the appropriate stream
     pub async fn publish_event(&self, event: &GatewayEvent) -> Result<(), GatewayError> {
-        let subject = self.route_event(event);
-        let payload = serde_json::to_vec(event).map_err(|e| GatewayError::SerializationFailed {
+        // Redacted first, so the outbox, dead letters and the event index
+        // never hold the fields either
+        let redacted = self.redaction.as_ref().and_then(|redaction| redaction.redact(event));
+        let current = redacted.as_ref().unwrap_or(event);
+        let version = match self.canary_shard {
+            Some(ref canary) => canary.schema_version(current.shard_id, self.schema_version),
+            None => self.schema_version,
+        };
+        let rendered = versions::render(current, version);
+        let event = rendered.as_ref();
+        let subject = self.subject(event);
+        let span = info_span!(
+            "nats.publish",
+            otel.kind = "producer",
+            messaging.system = "nats",
+            messaging.destination.name = %subject,
+            event_type = %event.event_type,
+            event_id = %event.event_id,
+            shard_id = event.shard_id,
+        );
+        let Some(threshold) = self.slow_publish else {
+            let published = self.route_and_publish(event, subject.clone()).instrument(span).await;
+            return self.dual_publish(published, current, &subject).await;
+        };
+
+        // The histograms can't carry exemplars; a slow publish is logged with
+        // its trace ID instead, so the log line links to the trace
+        let trace_id = telemetry::trace_id(&span);
+        let start = Instant::now();
+        let published = self.route_and_publish(event, subject.clone()).instrument(span).await;
+        let elapsed = start.elapsed();
+        if elapsed > threshold {
+            warn!(
+                event_type = %event.event_type,
+                event_id = %event.event_id,
+                subject,
+                elapsed_ms = elapsed.as_millis() as u64,
+                trace_id = trace_id.as_deref().unwrap_or(""),
+                "Slow publish"
+            );
+        }
+        self.dual_publish(published, current, &subject).await
+    }
+
+    /// Copy a published event in the dual-published schema version
+    async fn dual_publish(
+        &self,
+        published: Result<(), GatewayError>,
+        event: &GatewayEvent,
+        subject: &str,
+    ) -> Result<(), GatewayError> {
+        if let (Ok(()), Some(dual)) = (&published, &self.dual_publish) {
+            dual.publish(self, event, subject).await;
+        }
+        published
+    }
+
+    async fn route_and_publish(&self, event: &GatewayEvent, subject: String) -> Result<(), GatewayError> {
+        if let Some(ref schema) = self.schema {
+            schema.check(event)?;
+        }
+
+        // Queue behind an outbox backlog (keeps order), or straight into the
+        // outbox while NATS is unreachable
+        let outbox = self.outbox.as_ref().filter(|outbox| outbox.buffers(&subject));
+        if let Some(outbox) = outbox.filter(|outbox| !outbox.is_empty() || !self.is_server_connected()) {
+            return outbox.push(&subject, event);
+        }
+
+        // Batching: the ack is confirmed later, with the rest of its batch
+        let published = match self.batch.as_ref().filter(|batch| batch.batches(&subject)) {
+            Some(batch) => match self.send(event, subject.clone()).await {
+                Ok((ack, sent_at)) => {
+                    if let Some(full) = batch.push(Pending { event: event.clone(), subject, ack, sent_at }) {
+                        self.confirm(full).await;
+                    }
+                    return Ok(());
+                }
+                Err(e) => Err(e),
+            },
+            None => self.publish_to(event, subject.clone()).await,
+        };
+        self.retry_or_keep(event, &subject, published).await
+    }
+
+    /// Retry a failed publish, then hand it to the outbox when it buffers the
+    /// subject, else to the dead-letter queue
+    async fn retry_or_keep(
+        &self,
+        event: &GatewayEvent,
+        subject: &str,
+        mut published: Result<(), GatewayError>,
+    ) -> Result<(), GatewayError> {
+        let outbox = self.outbox.as_ref().filter(|outbox| outbox.buffers(subject));
+        for retry in 0..self.retry.retries {
+            // An outage is the outbox's job
+            let disconnected = outbox.is_some() && !self.is_server_connected();
+            if !matches!(published, Err(GatewayError::NatsPublishFailed { .. })) || disconnected {
+                break;
+            }
+            tokio::time::sleep(self.retry.delay(retry)).await;
+            if let Some(ref metrics) = self.metrics {
+                metrics.record_publish_retry();
+            }
+            published = self.publish_to(event, subject.to_string()).await;
+        }
+
+        match (published, outbox) {
+            (Err(GatewayError::NatsPublishFailed { .. }), Some(outbox)) => outbox.push(subject, event),
+            (Err(e @ GatewayError::NatsPublishFailed { .. }), None) => {
+                self.dead_letter(event, subject, &e);
+                Err(e)
+            }
+            (published, _) => published,
+        }
+    }
+
+    /// Keep an event that could not be published in the dead-letter queue
+    pub(super) fn dead_letter(&self, event: &GatewayEvent, subject: &str, error: &GatewayError) {
+        if let Some(ref dead_letters) = self.dead_letters {
+            dead_letters.push(subject, event, dlq::reason(error));
+        }
+    }
+
+    /// Publish an event on an already routed subject and wait for its ack
+    pub(super) async fn publish_to(&self, event: &GatewayEvent, subject: String) -> Result<(), GatewayError> {
+        let (ack_future, sent_at) = self.send(event, subject.clone()).await?;
+        // In async-nats 0.46, publish returns a PublishAckFuture
+        // that must be awaited to get the actual acknowledgment
+        self.acked(event, subject, ack_future.await, sent_at).await
+    }
+
+    /// Send an event without waiting for its ack
+    async fn send(&self, event: &GatewayEvent, subject: String) -> Result<(PublishAckFuture, Instant), GatewayError> {
+        let encode_start = Instant::now();
+        let format = self.wire_format_for(event);
+        let payload = format.encode(event).map_err(|e| GatewayError::SerializationFailed {
             event_type: event.event_type.clone(),
             shard_id: event.shard_id,
             source: e,
         })?;
+        if let Some(ref metrics) = self.metrics {
+            metrics.record_serialize(&event.event_type, encode_start.elapsed());
+        }
 
         debug!(
             event_type = %event.event_type,
@@ -119,34 +499,54 @@ impl NatsPublisher {
             "Publishing event"
         );
 
-        match self.jetstream.publish(subject.clone(), payload.into()).await {
-            Ok(ack_future) => {
-                // In async-nats 0.46, publish returns a PublishAckFuture
-                // that must be awaited to get the actual acknowledgment
-                match ack_future.await {
-                    Ok(ack) => {
-                        self.messages_published.fetch_add(1, Ordering::Relaxed);
-                        debug!(
-                            subject,
-                            stream = %ack.stream,
-                            seq = ack.sequence,
-                            "Event published"
-                        );
-                        Ok(())
-                    }
-                    Err(e) => {
-                        self.publish_failures.fetch_add(1, Ordering::Relaxed);
-                        warn!(subject, error = %e, "Failed to get publish acknowledgment");
-                        Err(GatewayError::NatsPublishFailed {
-                            subject,
-                            source: Box::new(e),
-                        })
-                    }
+        if let Some(ref quotas) = self.quotas {
+            quotas.acquire(&event.event_type, &subject, payload.len()).await;
+        }
+
+        let (payload, headers) = self.sealed(&subject, event, format, payload)?;
+        let sent_at = Instant::now();
+        match self.jetstream.publish_with_headers(subject.clone(), headers, payload.into()).await {
+            Ok(ack_future) => Ok((ack_future, sent_at)),
+            Err(e) => {
+                self.publish_failures.fetch_add(1, Ordering::Relaxed);
+                warn!(subject, error = %e, "Failed to publish event");
+                Err(GatewayError::NatsPublishFailed {
+                    subject,
+                    source: Box::new(e),
+                })
+            }
+        }
+    }
+
+    /// Record the acknowledgment of an event publish
+    async fn acked(
+        &self,
+        event: &GatewayEvent,
+        subject: String,
+        ack: Result<PublishAck, PublishError>,
+        sent_at: Instant,
+    ) -> Result<(), GatewayError> {
+        match ack {
+            Ok(ack) => {
+                self.messages_published.fetch_add(1, Ordering::Relaxed);
+                if let Some(ref metrics) = self.metrics {
+                    metrics.record_nats_ack(&ack.stream, sent_at.elapsed());
                 }
+                self.recent.record(event, &subject, &ack.stream, ack.sequence, now_millis());
+                debug!(
+                    subject,
+                    stream = %ack.stream,
+                    seq = ack.sequence,
+                    "Event published"
+                );
+                if let Some(ref canary) = self.canary {
+                    canary.publish(self, event, &subject).await;
+                }
+                Ok(())
             }
             Err(e) => {
                 self.publish_failures.fetch_add(1, Ordering::Relaxed);
-                warn!(subject, error = %e, "Failed to publish event");
+                warn!(subject, error = %e, "Failed to get publish acknowledgment");
                 Err(GatewayError::NatsPublishFailed {
                     subject,
                     source: Box::new(e),
@@ -155,9 +555,156 @@ impl NatsPublisher {
         }
     }
 
+    /// Wait for a batch of acks. Unacknowledged events are retried like any
+    /// failed publish, then go to the outbox when it buffers their subject,
+    /// else to the dead-letter queue.
+    async fn confirm(&self, batch: Vec<Pending>) {
+        let (events, acks): (Vec<_>, Vec<_>) = batch
+            .into_iter()
+            .map(|pending| ((pending.event, pending.subject, pending.sent_at), pending.ack.into_future()))
+            .unzip();
+        let acks = futures_util::future::join_all(acks).await;
+
+        for ((event, subject, sent_at), ack) in events.into_iter().zip(acks) {
+            let acked = self.acked(&event, subject.clone(), ack, sent_at).await;
+            if acked.is_ok() {
+                continue;
+            }
+            if let Err(e) = self.retry_or_keep(&event, &subject, acked).await {
+                if let Some(ref batch) = self.batch {
+                    batch.record_lost(event.shard_id);
+                }
+                warn!(event_id = %event.event_id, error = %e, "Batched event was not published");
+            }
+        }
+    }
+
+    /// Confirm every pending batched publish (flush timer and shutdown)
+    pub async fn flush_batch(&self) {
+        if let Some(ref batch) = self.batch {
+            let pending = batch.take();
+            if !pending.is_empty() {
+                self.confirm(pending).await;
+            }
+        }
+    }
+
+    /// Encoding of events on their primary subjects
+    pub fn wire_format(&self) -> WireFormat {
+        self.wire_format
+    }
+
+    /// Encoding of one event on its primary subject (the canary shard's may differ)
+    fn wire_format_for(&self, event: &GatewayEvent) -> WireFormat {
+        match self.canary_shard {
+            Some(ref canary) => canary.wire_format(event.shard_id, self.wire_format),
+            None => self.wire_format,
+        }
+    }
+
+    /// Canary or baseline, when a canary shard is configured
+    pub fn cohort(&self, shard_id: u64) -> Option<Cohort> {
+        self.canary_shard.as_ref().map(|canary| canary.cohort(shard_id))
+    }
+
+    pub fn batch_config(&self) -> Option<BatchConfig> {
+        self.batch.as_ref().map(PublishBatch::config)
+    }
+
+    /// Publish a copy of an event (canary, schema migration) in a wire
+    /// format, encrypted when its primary subject is
+    pub(super) async fn publish_copy(
+        &self,
+        event: &GatewayEvent,
+        format: WireFormat,
+        primary: &str,
+        subject: &str,
+        mut headers: async_nats::HeaderMap,
+    ) -> Result<(), GatewayError> {
+        let payload = format.encode(event).map_err(|e| GatewayError::SerializationFailed {
+            event_type: event.event_type.clone(),
+            shard_id: event.shard_id,
+            source: e,
+        })?;
+        let publish_failed = |e: Box<dyn std::error::Error + Send + Sync>| GatewayError::NatsPublishFailed {
+            subject: subject.to_string(),
+            source: e,
+        };
+
+        headers.insert(CONTENT_TYPE_HEADER, format.content_type());
+        telemetry::inject(&mut headers);
+        let payload = self.seal(primary, payload, &mut headers)?;
+        if let Some(ref signer) = self.signer {
+            signer.apply(&mut headers, &payload);
+        }
+        self.jetstream
+            .publish_with_headers(subject.to_string(), headers, payload.into())
+            .await
+            .map_err(|e| publish_failed(Box::new(e)))?
+            .await
+            .map_err(|e| publish_failed(Box::new(e)))?;
+        Ok(())
+    }
+
+    /// Payload and headers for an event publish: the content type, the
+    /// message ID JetStream deduplicates retries by, the trace context of
+    /// sampled events, the canary shard and shard epoch tags, encryption and
+    /// the payload signature
+    fn sealed(
+        &self,
+        subject: &str,
+        event: &GatewayEvent,
+        format: WireFormat,
+        payload: Vec<u8>,
+    ) -> Result<(Vec<u8>, async_nats::HeaderMap), GatewayError> {
+        let mut headers = telemetry::trace_headers().unwrap_or_default();
+        headers.insert(CONTENT_TYPE_HEADER, format.content_type());
+        headers.insert(async_nats::header::NATS_MESSAGE_ID, event.event_id.as_str());
+        if self.cohort(event.shard_id) == Some(Cohort::Canary) {
+            headers.insert(canary_shard::HEADER, canary_shard::HEADER_VALUE);
+        }
+        if let Some(epoch) = self.shard_epoch {
+            headers.insert(reshard::HEADER, epoch.to_string().as_str());
+        }
+        let payload = self.seal(subject, payload, &mut headers)?;
+        if let Some(ref signer) = self.signer {
+            signer.apply(&mut headers, &payload);
+        }
+        Ok((payload, headers))
+    }
+
+    /// Encrypt a payload if its subject is covered by payload encryption
+    pub(super) fn seal(
+        &self,
+        subject: &str,
+        payload: Vec<u8>,
+        headers: &mut async_nats::HeaderMap,
+    ) -> Result<Vec<u8>, GatewayError> {
+        match self.encryption {
+            Some(ref cipher) if cipher.covers(subject) => cipher.seal(subject, payload, headers),
+            _ => Ok(payload),
+        }
+    }
+
+    /// Subject for an event under this publisher's command routing
+    pub fn subject(&self, event: &GatewayEvent) -> String {
+        match self.command_routing {
+            CommandRouting::PerCommand => command_subject(event).unwrap_or_else(|| Self::route_event(event)),
+            CommandRouting::Interaction => Self::route_event(event),
+        }
+    }
+
     /// Route event to appropriate subject based on event type
-    fn route_event(&self, event: &GatewayEvent) -> String {
+    fn route_event(event: &GatewayEvent) -> String {
         match event.event_type.as_str() {
+            // Interactions without guild context (DMs, user-installed apps)
+            // get their own subject so workers never see a null guild_id on
+            // the guild command path
+            "interaction.create" if event.guild_id.is_none() => {
+                let command = event.data["command_name"].as_str().unwrap_or("unknown");
+                format!("{}.{}", subjects::DM_COMMANDS, subject_token(command))
+            }
+
             // Interactions go to COMMANDS stream
             "interaction.create" => format!("{}.interaction", subjects::COMMANDS),
 
@@ -165,70 +712,191 @@ impl NatsPublisher {
             "guild.join" => format!("{}.join", subjects::GUILD_EVENTS),
             "guild.leave" => format!("{}.leave", subjects::GUILD_EVENTS),
             "guild.update" => format!("{}.update", subjects::GUILD_EVENTS),
+            "guild.raid_alert" => format!("{}.raid_alert", subjects::GUILD_EVENTS),
 
             // Member events go to EVENTS stream
             "member.join" => format!("{}.join", subjects::MEMBER_EVENTS),
             "member.leave" => format!("{}.leave", subjects::MEMBER_EVENTS),
             "member.update" => format!("{}.update", subjects::MEMBER_EVENTS),
+            "member.join.burst" => format!("{}.join_burst", subjects::MEMBER_EVENTS),
+            "member.sync" => format!("{}.sync", subjects::MEMBER_EVENTS),
+
+            // Reaction events go to EVENTS stream
+            "reaction.add" => format!("{}.add", subjects::REACTION_EVENTS),
+            "reaction.remove" => format!("{}.remove", subjects::REACTION_EVENTS),
+            "reaction.remove_all" => format!("{}.remove_all", subjects::REACTION_EVENTS),
+
+            // Role events go to EVENTS stream
+            "role.create" => format!("{}.create", subjects::ROLE_EVENTS),
+            "role.update" => format!("{}.update", subjects::ROLE_EVENTS),
+            "role.delete" => format!("{}.delete", subjects::ROLE_EVENTS),
+
+            // Channel and thread events go to EVENTS stream
+            "channel.create" => format!("{}.create", subjects::CHANNEL_EVENTS),
+            "channel.update" => format!("{}.update", subjects::CHANNEL_EVENTS),
+            "channel.delete" => format!("{}.delete", subjects::CHANNEL_EVENTS),
+            "thread.create" => format!("{}.create", subjects::THREAD_EVENTS),
+            "thread.update" => format!("{}.update", subjects::THREAD_EVENTS),
+            "thread.delete" => format!("{}.delete", subjects::THREAD_EVENTS),
+            "thread.list_sync" => format!("{}.list_sync", subjects::THREAD_EVENTS),
+
+            // Message events go to MESSAGES stream
+            "message.create" => format!("{}.create", subjects::MESSAGE_EVENTS),
+            "message.update" => format!("{}.update", subjects::MESSAGE_EVENTS),
+            "message.delete" => format!("{}.delete", subjects::MESSAGE_EVENTS),
+            "message.bulk_delete" => format!("{}.bulk_delete", subjects::MESSAGE_EVENTS),
+
+            // Voice events go to VOICE stream
+            "voice.state_update" => format!("{}.state_update", subjects::VOICE_EVENTS),
+            "voice.server_update" => format!("{}.server_update", subjects::VOICE_EVENTS),
+
+            // Summaries get a subject per summarized type (member.join ->
+            // events.summary.member_join)
+            "event.summary" => {
+                let summarized = event.data["event_type"].as_str().unwrap_or("unknown");
+                format!("{}.{}", subjects::SUMMARY_EVENTS, subject_token(summarized))
+            }
+
+            // Gateway operational events go to EVENTS stream
+            "gateway.capability_degraded" => {
+                format!("{}.capability_degraded", subjects::GATEWAY_EVENTS)
+            }
+            "gateway.circuit_state" => format!("{}.circuit_state", subjects::GATEWAY_EVENTS),
 
             // Default: generic event
             other => format!("events.{}", other.replace('.', "_")),
         }
     }
 
-    /// Graceful shutdown
+    /// Graceful shutdown: confirm the open batch and write out buffered
+    /// core publishes before the connection is dropped
     pub async fn close(&self) {
+        self.flush_batch().await;
+        // Events the batch couldn't publish may have just been spooled
+        if let Some(ref outbox) = self.outbox {
+            outbox.flush().await;
+        }
+        if let Some(ref dead_letters) = self.dead_letters {
+            dead_letters.flush().await;
+        }
+        if let Err(e) = self.client.flush().await {
+            warn!(error = %e, "Failed to flush NATS connection");
+        }
         info!("Closing NATS connection");
         self.connected.store(false, Ordering::SeqCst);
         // async-nats handles cleanup on drop
     }
 }
 
-/// Ensure streams exist with correct configuration
-///
-/// This is typically run during startup or by a separate setup job.
-pub async fn ensure_streams(js: &JsContext) -> Result<(), GatewayError> {
-    use async_nats::jetstream::stream::{Config, RetentionPolicy, StorageType};
-
-    // COMMANDS stream - memory storage, 60s retention for fast command processing
-    let commands_config = Config {
-        name: streams::COMMANDS.to_string(),
-        subjects: vec!["commands.>".to_string()],
-        retention: RetentionPolicy::Limits,
-        max_age: std::time::Duration::from_secs(60),
-        storage: StorageType::Memory,
-        ..Default::default()
-    };
+/// Per-command subject for a guild command or autocomplete interaction
+fn command_subject(event: &GatewayEvent) -> Option<String> {
+    if event.event_type != "interaction.create" || event.guild_id.is_none() {
+        return None;
+    }
+    let command = event.data["command_name"].as_str()?;
+    Some(format!("{}.{}", subjects::COMMANDS, subject_token(command)))
+}
 
-    match js.create_stream(commands_config).await {
-        Ok(_) => info!("Created COMMANDS stream"),
-        Err(e) if e.to_string().contains("already in use") => {
-            debug!("COMMANDS stream already exists");
+/// Track connection state from async-nats client events
+fn on_connection_event(connected: &AtomicBool, metrics: Option<&GatewayMetrics>, event: &Event) {
+    match event {
+        // The initial connection also reports Connected; only count it after a loss
+        Event::Connected => {
+            if !connected.swap(true, Ordering::SeqCst) {
+                info!("Reconnected to NATS");
+                if let Some(metrics) = metrics {
+                    metrics.record_nats_reconnect();
+                }
+            }
         }
-        Err(e) => {
-            error!(error = %e, "Failed to create COMMANDS stream");
-            return Err(GatewayError::Config(format!("Failed to create COMMANDS stream: {e}")));
+        Event::Disconnected | Event::Closed => {
+            if connected.swap(false, Ordering::SeqCst) {
+                warn!(%event, "Lost connection to NATS - reconnecting");
+            }
         }
+        other => debug!(event = %other, "NATS connection event"),
+    }
+}
+
+/// Delay before connection attempt `attempts` (the first is immediate)
+fn reconnect_delay(attempts: usize) -> Duration {
+    if attempts <= 1 {
+        return Duration::ZERO;
     }
+    let exponent = (attempts - 2).min(16) as u32;
+    RECONNECT_BASE_DELAY.saturating_mul(1 << exponent).min(RECONNECT_MAX_DELAY)
+}
 
-    // EVENTS stream - memory storage, 5min retention for event processing
-    let events_config = Config {
-        name: streams::EVENTS.to_string(),
-        subjects: vec!["events.>".to_string()],
-        retention: RetentionPolicy::Limits,
-        max_age: std::time::Duration::from_secs(300),
-        storage: StorageType::Memory,
-        ..Default::default()
-    };
+/// Make a value safe to use as a single NATS subject token
+pub(super) fn subject_token(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| match c {
+            '.' | '*' | '>' => '_',
+            c if c.is_whitespace() => '_',
+            c => c,
+        })
+        .collect()
+}
 
-    match js.create_stream(events_config).await {
-        Ok(_) => info!("Created EVENTS stream"),
-        Err(e) if e.to_string().contains("already in use") => {
-            debug!("EVENTS stream already exists");
-        }
-        Err(e) => {
-            error!(error = %e, "Failed to create EVENTS stream");
-            return Err(GatewayError::Config(format!("Failed to create EVENTS stream: {e}")));
+/// Create the streams that don't exist yet (`ENSURE_STREAMS`). Existing
+/// streams get the spec's limits and replica count; storage and retention
+/// can't change in place, so a difference there is only logged.
+pub async fn ensure_streams(js: &JsContext, specs: &[StreamSpec]) -> Result<(), GatewayError> {
+    for spec in specs {
+        let wanted = spec.config();
+        match js.create_stream(wanted.clone()).await {
+            Ok(_) => info!(
+                stream = %spec.name,
+                storage = ?spec.storage,
+                max_age_secs = spec.max_age_secs,
+                replicas = spec.replicas,
+                "Created stream"
+            ),
+            Err(e) if e.to_string().contains("already in use") => {
+                let current = match js.get_stream(&spec.name).await {
+                    Ok(stream) => stream.cached_info().config.clone(),
+                    Err(e) => {
+                        warn!(stream = %spec.name, error = %e, "Failed to read existing stream - leaving it as it is");
+                        continue;
+                    }
+                };
+                let (updatable, fixed) = routing::drift(&current, &wanted);
+                if !fixed.is_empty() {
+                    warn!(
+                        stream = %spec.name,
+                        settings = ?fixed,
+                        "Existing stream differs in settings that can't be updated - recreate it to apply them"
+                    );
+                }
+                if !updatable {
+                    debug!(stream = %spec.name, "Stream already exists");
+                    continue;
+                }
+                let updated = Config {
+                    max_age: wanted.max_age,
+                    max_messages: wanted.max_messages,
+                    max_bytes: wanted.max_bytes,
+                    num_replicas: wanted.num_replicas,
+                    ..current
+                };
+                match js.update_stream(&updated).await {
+                    Ok(_) => info!(
+                        stream = %spec.name,
+                        max_age_secs = spec.max_age_secs,
+                        replicas = spec.replicas,
+                        "Updated stream limits"
+                    ),
+                    Err(e) => {
+                        error!(stream = %spec.name, error = %e, "Failed to update stream");
+                        return Err(GatewayError::Config(format!("Failed to update {} stream: {e}", spec.name)));
+                    }
+                }
+            }
+            Err(e) => {
+                error!(stream = %spec.name, error = %e, "Failed to create stream");
+                return Err(GatewayError::Config(format!("Failed to create {} stream: {e}", spec.name)));
+            }
         }
     }
 
@@ -242,20 +910,100 @@ mod tests {
 
     #[test]
     fn test_route_interaction() {
+        let event = GatewayEvent::for_test("interaction.create");
+
+        // No guild context: routed to the DM command subject
+        assert_eq!(NatsPublisher::route_event(&event), "commands.dm.unknown");
+
+        let guild_event = GatewayEvent {
+            guild_id: Some("123".to_string()),
+            ..event.clone()
+        };
+        assert_eq!(NatsPublisher::route_event(&guild_event), "commands.interaction");
+
+        let dm_event = GatewayEvent {
+            data: serde_json::json!({ "command_name": "verify" }),
+            ..event
+        };
+        assert_eq!(NatsPublisher::route_event(&dm_event), "commands.dm.verify");
+    }
+
+    #[test]
+    fn test_per_command_subjects() {
         let event = GatewayEvent {
-            event_id: "test".to_string(),
-            event_type: "interaction.create".to_string(),
-            shard_id: 0,
-            timestamp: 0,
-            guild_id: None,
-            channel_id: None,
-            user_id: None,
-            data: serde_json::Value::Null,
+            guild_id: Some("123".to_string()),
+            data: serde_json::json!({ "command_name": "admin-badge" }),
+            ..GatewayEvent::for_test("interaction.create")
         };
+        assert_eq!(command_subject(&event).as_deref(), Some("commands.admin-badge"));
 
-        // Create a mock publisher would require more setup
-        // For now, test the routing logic separately
-        assert_eq!(event.event_type, "interaction.create");
+        // Components and modals have no command; DMs keep commands.dm.{command_name}
+        let component = GatewayEvent { data: serde_json::json!({ "command_name": null }), ..event.clone() };
+        assert_eq!(command_subject(&component), None);
+        let dm = GatewayEvent { guild_id: None, ..event };
+        assert_eq!(command_subject(&dm), None);
+
+        assert_eq!(CommandRouting::parse("per-command"), Some(CommandRouting::PerCommand));
+        assert_eq!(CommandRouting::parse("commands"), None);
+    }
+
+    #[test]
+    fn test_route_message_and_summary() {
+        let event = GatewayEvent {
+            guild_id: Some("123".to_string()),
+            channel_id: Some("456".to_string()),
+            ..GatewayEvent::for_test("message.create")
+        };
+
+        assert_eq!(NatsPublisher::route_event(&event), "messages.create");
+        let bulk = GatewayEvent { event_type: "message.bulk_delete".to_string(), ..event.clone() };
+        assert_eq!(NatsPublisher::route_event(&bulk), "messages.bulk_delete");
+        let reaction = GatewayEvent { event_type: "reaction.remove_all".to_string(), ..event.clone() };
+        assert_eq!(NatsPublisher::route_event(&reaction), "events.reaction.remove_all");
+        let voice = GatewayEvent { event_type: "voice.server_update".to_string(), ..event.clone() };
+        assert_eq!(NatsPublisher::route_event(&voice), "voice.server_update");
+        let sync = GatewayEvent { event_type: "thread.list_sync".to_string(), ..event.clone() };
+        assert_eq!(NatsPublisher::route_event(&sync), "events.thread.list_sync");
+        let role = GatewayEvent { event_type: "role.update".to_string(), ..event.clone() };
+        assert_eq!(NatsPublisher::route_event(&role), "events.role.update");
+        let burst = GatewayEvent { event_type: "member.join.burst".to_string(), ..event.clone() };
+        assert_eq!(NatsPublisher::route_event(&burst), "events.member.join_burst");
+        let alert = GatewayEvent { event_type: "guild.raid_alert".to_string(), ..event.clone() };
+        assert_eq!(NatsPublisher::route_event(&alert), "events.guild.raid_alert");
+        let circuit = GatewayEvent { event_type: "gateway.circuit_state".to_string(), ..event.clone() };
+        assert_eq!(NatsPublisher::route_event(&circuit), "events.gateway.circuit_state");
+
+        let summary = GatewayEvent {
+            event_type: "event.summary".to_string(),
+            data: serde_json::json!({ "event_type": "member.join" }),
+            ..event
+        };
+        assert_eq!(NatsPublisher::route_event(&summary), "events.summary.member_join");
+    }
+
+    #[test]
+    fn test_connection_events_track_state() {
+        let connected = AtomicBool::new(true);
+        on_connection_event(&connected, None, &Event::Disconnected);
+        assert!(!connected.load(Ordering::SeqCst));
+        on_connection_event(&connected, None, &Event::LameDuckMode);
+        assert!(!connected.load(Ordering::SeqCst));
+        on_connection_event(&connected, None, &Event::Connected);
+        assert!(connected.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_reconnect_delay_backs_off() {
+        assert_eq!(reconnect_delay(1), Duration::ZERO);
+        assert_eq!(reconnect_delay(2), RECONNECT_BASE_DELAY);
+        assert_eq!(reconnect_delay(4), Duration::from_secs(1));
+        assert_eq!(reconnect_delay(9), RECONNECT_MAX_DELAY);
+        assert_eq!(reconnect_delay(usize::MAX), RECONNECT_MAX_DELAY);
+    }
+
+    #[test]
+    fn test_subject_token_strips_wildcards() {
+        assert_eq!(subject_token("a.b*c>d e"), "a_b_c_d_e");
     }
 
     #[test]
@@ -263,6 +1011,8 @@ mod tests {
         assert_eq!(streams::COMMANDS, "COMMANDS");
         assert_eq!(streams::EVENTS, "EVENTS");
         assert_eq!(streams::ELIGIBILITY, "ELIGIBILITY");
+        assert_eq!(streams::MESSAGES, "MESSAGES");
+        assert_eq!(streams::VOICE, "VOICE");
     }
 
     /// Validates that Rust hardcoded constants match the language-neutral
@@ -301,6 +1051,16 @@ mod tests {
                 json_streams["ELIGIBILITY"]["name"].as_str().unwrap(),
                 "ELIGIBILITY stream name mismatch"
             );
+            assert_eq!(
+                streams::MESSAGES,
+                json_streams["MESSAGES"]["name"].as_str().unwrap(),
+                "MESSAGES stream name mismatch"
+            );
+            assert_eq!(
+                streams::VOICE,
+                json_streams["VOICE"]["name"].as_str().unwrap(),
+                "VOICE stream name mismatch"
+            );
         }
 
         #[test]
@@ -333,6 +1093,61 @@ mod tests {
                 json_subjects["commands"]["interaction"].as_str().unwrap(),
                 "interaction subject mismatch"
             );
+            assert_eq!(
+                subjects::DM_COMMANDS,
+                json_subjects["commands"]["dm_prefix"].as_str().unwrap(),
+                "dm command prefix mismatch"
+            );
+            assert_eq!(
+                subjects::REACTION_EVENTS,
+                json_subjects["reaction_events"]["prefix"].as_str().unwrap(),
+                "reaction_events prefix mismatch"
+            );
+            assert_eq!(
+                subjects::ROLE_EVENTS,
+                json_subjects["role_events"]["prefix"].as_str().unwrap(),
+                "role_events prefix mismatch"
+            );
+            assert_eq!(
+                subjects::CHANNEL_EVENTS,
+                json_subjects["channel_events"]["prefix"].as_str().unwrap(),
+                "channel_events prefix mismatch"
+            );
+            assert_eq!(
+                subjects::THREAD_EVENTS,
+                json_subjects["thread_events"]["prefix"].as_str().unwrap(),
+                "thread_events prefix mismatch"
+            );
+            assert_eq!(
+                format!("{}.{{command_name}}", subjects::COMMANDS),
+                json_subjects["commands"]["per_command_pattern"].as_str().unwrap(),
+                "per-command subject mismatch"
+            );
+            assert_eq!(
+                subjects::GATEWAY_EVENTS,
+                json_subjects["gateway_events"]["prefix"].as_str().unwrap(),
+                "gateway_events prefix mismatch"
+            );
+            assert_eq!(
+                subjects::MESSAGE_EVENTS,
+                json_subjects["message_events"]["prefix"].as_str().unwrap(),
+                "message_events prefix mismatch"
+            );
+            assert_eq!(
+                subjects::VOICE_EVENTS,
+                json_subjects["voice_events"]["prefix"].as_str().unwrap(),
+                "voice_events prefix mismatch"
+            );
+            assert_eq!(
+                subjects::SUMMARY_EVENTS,
+                json_subjects["summary_events"]["prefix"].as_str().unwrap(),
+                "summary_events prefix mismatch"
+            );
+            assert_eq!(
+                subjects::USAGE,
+                json_subjects["usage"]["prefix"].as_str().unwrap(),
+                "usage prefix mismatch"
+            );
         }
 
         #[test]
@@ -353,7 +1168,16 @@ mod tests {
                 // Verify the expected subject starts with a known prefix
                 let valid = expected.starts_with(subjects::COMMANDS)
                     || expected.starts_with(subjects::GUILD_EVENTS)
-                    || expected.starts_with(subjects::MEMBER_EVENTS);
+                    || expected.starts_with(subjects::MEMBER_EVENTS)
+                    || expected.starts_with(subjects::GATEWAY_EVENTS)
+                    || expected.starts_with(subjects::REACTION_EVENTS)
+                    || expected.starts_with(subjects::ROLE_EVENTS)
+                    || expected.starts_with(subjects::CHANNEL_EVENTS)
+                    || expected.starts_with(subjects::THREAD_EVENTS)
+                    || expected.starts_with(subjects::MESSAGE_EVENTS)
+                    || expected.starts_with(subjects::VOICE_EVENTS)
+                    || expected.starts_with(subjects::SUMMARY_EVENTS)
+                    || expected.starts_with(subjects::USAGE);
                 assert!(
                     valid,
                     "event_type '{}' maps to subject '{}' which doesn't match any Rust prefix",
@@ -361,5 +1185,102 @@ mod tests {
                 );
             }
         }
+
+        fn routing() -> serde_json::Value {
+            let content = std::fs::read_to_string(ROUTING_JSON).expect("Failed to read nats-routing.json");
+            serde_json::from_str(&content).expect("Failed to parse nats-routing.json")
+        }
+
+        #[test]
+        fn request_subjects_match_routing_json() {
+            use crate::nats::{backfill, guilds, members, ownership, service};
+
+            let routing = routing();
+            let requests = &routing["subjects"]["gateway_requests"];
+            assert_eq!(requests["guild"], guilds::SUBJECT);
+            assert_eq!(requests["guild_shard"], ownership::SUBJECT);
+            assert_eq!(requests["member_chunk"], members::SUBJECT);
+            assert_eq!(requests["backfill"], backfill::SUBJECT);
+            assert_eq!(requests["handoff"], crate::shard::handoff::SUBJECT);
+            assert_eq!(routing["subjects"]["rest"]["requests"], crate::discord::proxy::SUBJECT);
+
+            /// `service` section of nats-routing.json
+            #[derive(serde::Serialize)]
+            struct ServiceRouting {
+                name: &'static str,
+                prefix: &'static str,
+                endpoints: Vec<&'static str>,
+            }
+            let rust = serde_json::to_value(ServiceRouting {
+                name: service::SERVICE_NAME,
+                prefix: service::SUBJECT_PREFIX,
+                endpoints: service::Endpoint::ALL.map(service::Endpoint::name).to_vec(),
+            })
+            .unwrap();
+            assert_eq!(rust, routing["service"], "gateway service drifted from nats-routing.json");
+
+            // Request-reply subjects must not be captured by a stream
+            for stream in routing["streams"].as_object().unwrap().values() {
+                for pattern in stream["subjects"].as_array().unwrap() {
+                    let pattern = pattern.as_str().unwrap();
+                    assert!(!pattern.starts_with("rest."), "stream captures {pattern}");
+                    assert!(!pattern.starts_with(&format!("{}.", service::SUBJECT_PREFIX)), "stream captures {pattern}");
+                }
+            }
+        }
+
+        #[test]
+        fn feature_subjects_match_routing_json() {
+            use crate::nats::kv::buckets;
+            use crate::nats::{backfill, canary, eligibility, migration, presence, raw, replay, ticks, topology};
+
+            let routing = routing();
+            let subjects = &routing["subjects"];
+            assert_eq!(subjects["backfill"]["progress"], backfill::PROGRESS_SUBJECT);
+            assert_eq!(routing["event_type_to_subject"][backfill::EVENT_TYPE], "events.member.sync");
+            assert_eq!(subjects["presence"]["update"], presence::SUBJECT);
+            assert_eq!(subjects["topology"]["gateway"], topology::SUBJECT);
+            assert_eq!(routing["kv_buckets"]["topology"], buckets::TOPOLOGY);
+
+            assert_eq!(routing["streams"][ticks::STREAM]["subjects"][0], "ticks.>");
+            assert_eq!(subjects["ticks"]["minute"], ticks::subjects::MINUTE);
+            assert_eq!(subjects["ticks"]["hour"], ticks::subjects::HOUR);
+            assert_eq!(subjects["ticks"]["guild_prefix"], ticks::subjects::GUILD);
+            assert_eq!(routing["kv_buckets"]["guild_schedules"], buckets::GUILD_SCHEDULES);
+
+            assert_eq!(routing["streams"][streams::ELIGIBILITY]["subjects"][0], "eligibility.>");
+            assert_eq!(subjects["eligibility"]["check_prefix"], eligibility::subjects::CHECK);
+
+            assert_eq!(routing["streams"][canary::STREAM]["subjects"][0], "canary.>");
+            assert_eq!(subjects["canary"]["prefix"], canary::subjects::PREFIX);
+            assert_eq!(subjects["canary"]["results"], canary::subjects::RESULTS);
+
+            assert_eq!(routing["streams"][raw::STREAM]["subjects"][0], "raw.>");
+            assert_eq!(subjects["raw"]["prefix"], raw::subjects::PREFIX);
+
+            assert_eq!(routing["streams"][replay::STREAM]["subjects"][0], format!("{}.>", replay::subjects::PREFIX));
+            assert_eq!(subjects["replay"]["prefix"], replay::subjects::PREFIX);
+
+            assert_eq!(routing["streams"][migration::STREAM]["subjects"][0], format!("{}.>", migration::subjects::PREFIX));
+            assert_eq!(subjects["schema"]["prefix"], migration::subjects::PREFIX);
+        }
+
+        #[test]
+        fn known_durables_match_routing_json() {
+            let routing = routing();
+            let json: Vec<(&str, &str)> = routing["consumers"]
+                .as_array()
+                .expect("consumers should be array")
+                .iter()
+                .map(|c| (c["stream"].as_str().unwrap(), c["durable"].as_str().unwrap()))
+                .collect();
+            let rust: Vec<(&str, &str)> =
+                crate::nats::lag::KNOWN_DURABLES.iter().map(|d| (d.stream, d.consumer)).collect();
+            assert_eq!(rust, json, "KNOWN_DURABLES drifted from nats-routing.json consumers");
+
+            for (stream, _) in json {
+                assert!(routing["streams"][stream].is_object(), "consumer on unknown stream {stream}");
+            }
+        }
     }
 }
@@ -0,0 +1,269 @@
+//! Per-stream publish budgets
+//!
+//! `PUBLISH_BUDGETS` caps how fast the gateway publishes into each JetStream
+//! stream, in events and bytes per second, so one misbehaving event class
+//! can't take the cluster's ingest capacity. Budgets are token buckets holding
+//! one second of burst. Over budget, events are shaped (delayed until the
+//! budget has room, at most `MAX_SHAPING_DELAY` each) rather than dropped;
+//! the delay backs up into the shard that produced them. Interactions and
+//! gateway operational events are never delayed: they draw on the budget but
+//! are published immediately.
+
+use super::publisher::streams;
+use crate::error::GatewayError;
+use crate::metrics::GatewayMetrics;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::debug;
+
+/// Longest an event is held back for its stream's budget
+pub const MAX_SHAPING_DELAY: Duration = Duration::from_secs(1);
+
+/// Event types published without shaping (interactions must be answered
+/// within 3 seconds)
+const PRIORITY_EVENT_TYPES: &[&str] =
+    &["interaction.create", "gateway.capability_degraded", "gateway.circuit_state", "guild.raid_alert"];
+
+/// Streams the publisher writes to, by subject prefix
+const STREAM_PREFIXES: &[(&str, &str)] = &[
+    ("commands.", streams::COMMANDS),
+    ("events.", streams::EVENTS),
+    ("messages.", streams::MESSAGES),
+    ("voice.", streams::VOICE),
+];
+
+/// Publish budget for one stream
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StreamBudget {
+    pub stream: &'static str,
+    pub events_per_sec: f64,
+    /// None leaves bytes unlimited
+    pub bytes_per_sec: Option<f64>,
+}
+
+/// Parse `PUBLISH_BUDGETS`: comma-separated
+/// `STREAM=events_per_sec[:bytes_per_sec]`, e.g. `EVENTS=500:1048576,COMMANDS=200`
+pub fn parse_budgets(spec: &str) -> Result<Vec<StreamBudget>, GatewayError> {
+    let mut budgets: Vec<StreamBudget> = Vec::new();
+    for entry in spec.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+        let invalid = || {
+            GatewayError::Config(format!(
+                "PUBLISH_BUDGETS entry {entry:?} must be STREAM=events_per_sec[:bytes_per_sec] \
+                 with a positive rate for COMMANDS, EVENTS, MESSAGES or VOICE"
+            ))
+        };
+        let rate = |value: &str| value.trim().parse::<f64>().ok().filter(|rate| *rate > 0.0 && rate.is_finite());
+
+        let (stream, rest) = entry.split_once('=').ok_or_else(invalid)?;
+        let stream = STREAM_PREFIXES
+            .iter()
+            .map(|(_, name)| *name)
+            .find(|name| *name == stream.trim())
+            .ok_or_else(invalid)?;
+        let (events, bytes) = match rest.split_once(':') {
+            Some((events, bytes)) => (events, Some(rate(bytes).ok_or_else(invalid)?)),
+            None => (rest, None),
+        };
+        if budgets.iter().any(|budget| budget.stream == stream) {
+            return Err(GatewayError::Config(format!("PUBLISH_BUDGETS lists {stream} twice")));
+        }
+
+        budgets.push(StreamBudget {
+            stream,
+            events_per_sec: rate(events).ok_or_else(invalid)?,
+            bytes_per_sec: bytes,
+        });
+    }
+    Ok(budgets)
+}
+
+/// Token bucket refilling at `rate` per second, holding one second of burst.
+///
+/// Tokens may go negative: a caller told to wait has already taken its share,
+/// so later callers queue up behind it.
+#[derive(Debug)]
+struct Bucket {
+    rate: f64,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn new(rate: f64, now: Instant) -> Self {
+        Self { rate, tokens: rate, updated: now }
+    }
+
+    /// Take `cost` tokens and return how long until the bucket covers them
+    fn take(&mut self, cost: f64, now: Instant) -> Duration {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.updated = now;
+        // Debt is bounded by the longest wait anyone is given
+        let floor = -self.rate * MAX_SHAPING_DELAY.as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.rate);
+        self.tokens = (self.tokens - cost).max(floor);
+
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate).min(MAX_SHAPING_DELAY)
+        }
+    }
+
+    /// Share of the burst in use (above 1 while in debt)
+    fn utilization(&self) -> f64 {
+        1.0 - self.tokens / self.rate
+    }
+}
+
+#[derive(Debug)]
+struct StreamQuota {
+    events: Bucket,
+    bytes: Option<Bucket>,
+}
+
+/// What a publish has to do under its stream's budget
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Admission {
+    /// Delay before publishing (zero when within budget or a priority type)
+    delay: Duration,
+    /// Whether the publish was over budget
+    over_budget: bool,
+    /// Events and bytes utilization of the stream's budget
+    utilization: (f64, Option<f64>),
+}
+
+/// Publish budgets for the streams the gateway writes to
+pub struct PublishQuotas {
+    quotas: Mutex<HashMap<&'static str, StreamQuota>>,
+    metrics: Arc<GatewayMetrics>,
+}
+
+impl PublishQuotas {
+    pub fn new(budgets: &[StreamBudget], metrics: Arc<GatewayMetrics>) -> Self {
+        let now = Instant::now();
+        let quotas = budgets
+            .iter()
+            .map(|budget| {
+                let quota = StreamQuota {
+                    events: Bucket::new(budget.events_per_sec, now),
+                    bytes: budget.bytes_per_sec.map(|rate| Bucket::new(rate, now)),
+                };
+                (budget.stream, quota)
+            })
+            .collect();
+        Self { quotas: Mutex::new(quotas), metrics }
+    }
+
+    /// Wait until an event published on `subject` fits its stream's budget
+    pub async fn acquire(&self, event_type: &str, subject: &str, bytes: usize) {
+        let Some(stream) = stream_for_subject(subject) else {
+            return;
+        };
+        let Some(admission) = self.admit(stream, event_type, bytes, Instant::now()) else {
+            return;
+        };
+
+        let (events, bytes_used) = admission.utilization;
+        self.metrics.set_publish_quota_utilization(stream, "events", events);
+        if let Some(bytes_used) = bytes_used {
+            self.metrics.set_publish_quota_utilization(stream, "bytes", bytes_used);
+        }
+        if !admission.over_budget {
+            return;
+        }
+
+        if admission.delay.is_zero() {
+            self.metrics.record_publish_over_budget(stream, "passed");
+        } else {
+            self.metrics.record_publish_over_budget(stream, "shaped");
+            self.metrics.record_publish_shaping_delay(stream, admission.delay);
+            debug!(stream, event_type, delay_ms = admission.delay.as_millis() as u64, "Shaping publish to budget");
+            tokio::time::sleep(admission.delay).await;
+        }
+    }
+
+    /// Charge a publish to its stream's budget; None for unbudgeted streams
+    fn admit(&self, stream: &str, event_type: &str, bytes: usize, now: Instant) -> Option<Admission> {
+        let mut quotas = self.quotas.lock().unwrap_or_else(|e| e.into_inner());
+        let quota = quotas.get_mut(stream)?;
+
+        let mut wait = quota.events.take(1.0, now);
+        if let Some(ref mut bucket) = quota.bytes {
+            wait = wait.max(bucket.take(bytes as f64, now));
+        }
+        let priority = PRIORITY_EVENT_TYPES.contains(&event_type);
+
+        Some(Admission {
+            delay: if priority { Duration::ZERO } else { wait },
+            over_budget: !wait.is_zero(),
+            utilization: (quota.events.utilization(), quota.bytes.as_ref().map(Bucket::utilization)),
+        })
+    }
+}
+
+/// The stream a subject is published into
+pub(crate) fn stream_for_subject(subject: &str) -> Option<&'static str> {
+    STREAM_PREFIXES
+        .iter()
+        .find(|(prefix, _)| subject.starts_with(prefix))
+        .map(|(_, stream)| *stream)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bucket_waits(rate: f64, costs: &[f64]) -> Vec<Duration> {
+        let now = Instant::now();
+        let mut bucket = Bucket::new(rate, now);
+        costs.iter().map(|cost| bucket.take(*cost, now)).collect()
+    }
+
+    #[test]
+    fn test_parse_budgets() {
+        let budgets = parse_budgets("EVENTS=500:1048576, COMMANDS=200").unwrap();
+        assert_eq!(
+            budgets,
+            [
+                StreamBudget { stream: "EVENTS", events_per_sec: 500.0, bytes_per_sec: Some(1_048_576.0) },
+                StreamBudget { stream: "COMMANDS", events_per_sec: 200.0, bytes_per_sec: None },
+            ]
+        );
+        assert!(parse_budgets("").unwrap().is_empty());
+
+        for invalid in ["EVENTS", "TICKS=10", "EVENTS=0", "EVENTS=10:-1", "EVENTS=10,EVENTS=20"] {
+            assert!(parse_budgets(invalid).is_err(), "{invalid} should be rejected");
+        }
+    }
+
+    #[test]
+    fn test_bucket_queues_callers_over_budget() {
+        // One second of burst, then each event waits its share
+        let waits = bucket_waits(2.0, &[1.0, 1.0, 1.0, 1.0]);
+        assert_eq!(waits, [Duration::ZERO, Duration::ZERO, Duration::from_millis(500), Duration::from_secs(1)]);
+
+        // Debt never exceeds the longest wait
+        let waits = bucket_waits(2.0, &[10.0, 1.0]);
+        assert_eq!(waits, [MAX_SHAPING_DELAY, MAX_SHAPING_DELAY]);
+    }
+
+    #[test]
+    fn test_bucket_refills_over_time() {
+        let start = Instant::now();
+        let mut bucket = Bucket::new(10.0, start);
+        assert!(bucket.take(15.0, start) > Duration::ZERO);
+        assert_eq!(bucket.take(1.0, start + Duration::from_secs(1)), Duration::ZERO);
+        assert_eq!(bucket.utilization(), 0.6);
+    }
+
+    #[test]
+    fn test_stream_for_subject() {
+        assert_eq!(stream_for_subject("commands.interaction"), Some("COMMANDS"));
+        assert_eq!(stream_for_subject("events.member.join"), Some("EVENTS"));
+        assert_eq!(stream_for_subject("messages.delete"), Some("MESSAGES"));
+        assert_eq!(stream_for_subject("voice.state_update"), Some("VOICE"));
+        assert_eq!(stream_for_subject("canary.json.events.member.join"), None);
+    }
+}
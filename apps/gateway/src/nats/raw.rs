@@ -0,0 +1,213 @@
+//! Raw-event passthrough for contract debugging
+//!
+//! For selected event types or guilds (`RAW_PASSTHROUGH_EVENTS`,
+//! `RAW_PASSTHROUGH_GUILDS`), the Discord dispatch payload an event was
+//! normalized from is published, byte for byte, on `raw.{subject}` next to the
+//! normalized envelope. The RAW stream keeps them briefly, so a "Discord sent
+//! X, you emitted Y" dispute can be settled from the two messages, joined on
+//! `event_id`.
+//!
+//! Event types that are encrypted (`PAYLOAD_ENCRYPTION_SUBJECTS`) or have a redaction
+//! rule (`REDACTION_POLICY`) get no raw payload: it would carry the fields in
+//! the clear.
+
+use super::NatsPublisher;
+use crate::error::GatewayError;
+use crate::events::serialize::GatewayEvent;
+use async_nats::jetstream::stream::{Config, RetentionPolicy, StorageType};
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+use std::collections::BTreeSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// JetStream stream holding raw payloads (mirrors nats-routing.json)
+pub const STREAM: &str = "RAW";
+
+/// Raw subjects (mirror nats-routing.json)
+pub mod subjects {
+    /// Raw payloads: raw.{subject of the normalized event}
+    pub const PREFIX: &str = "raw";
+}
+
+/// Raw payloads are dropped after this long
+const MAX_AGE: Duration = Duration::from_secs(900);
+
+/// Which events have their raw payload published
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RawPassthrough {
+    /// Normalized event types, e.g. `member.update`
+    pub event_types: BTreeSet<String>,
+    pub guild_ids: BTreeSet<String>,
+}
+
+impl RawPassthrough {
+    /// Parse the comma-separated event type and guild ID lists; None when both
+    /// are empty
+    pub fn parse(event_types: &str, guild_ids: &str) -> Result<Option<Self>, GatewayError> {
+        let list = |spec: &str| -> BTreeSet<String> {
+            spec.split(',').map(str::trim).filter(|item| !item.is_empty()).map(String::from).collect()
+        };
+        let passthrough = Self { event_types: list(event_types), guild_ids: list(guild_ids) };
+
+        if let Some(invalid) = passthrough.guild_ids.iter().find(|id| id.parse::<u64>().is_err()) {
+            return Err(GatewayError::Config(format!("RAW_PASSTHROUGH_GUILDS has an invalid guild ID: {invalid:?}")));
+        }
+        let enabled = !passthrough.event_types.is_empty() || !passthrough.guild_ids.is_empty();
+        Ok(enabled.then_some(passthrough))
+    }
+
+    /// Whether an event's raw payload is published
+    pub fn selects(&self, event: &GatewayEvent) -> bool {
+        self.event_types.contains(&event.event_type)
+            || event.guild_id.as_ref().is_some_and(|guild_id| self.guild_ids.contains(guild_id))
+    }
+}
+
+/// Raw subject for an event's primary subject
+pub fn subject(primary: &str) -> String {
+    format!("{}.{primary}", subjects::PREFIX)
+}
+
+/// A Discord dispatch next to the event it became (`fixtures/raw-event.json`)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RawEvent {
+    /// event_id of the normalized envelope
+    pub event_id: String,
+    pub event_type: String,
+    pub shard_id: u64,
+    pub guild_id: Option<String>,
+    /// Subject the normalized envelope was published on
+    pub subject: String,
+    /// The gateway payload as Discord sent it (`op`, `t`, `s`, `d`)
+    pub dispatch: Box<RawValue>,
+}
+
+/// Publishes raw payloads of selected events
+pub struct RawTap {
+    passthrough: RawPassthrough,
+    stream_ready: AtomicBool,
+}
+
+impl RawTap {
+    pub fn new(passthrough: RawPassthrough) -> Self {
+        Self {
+            passthrough,
+            stream_ready: AtomicBool::new(false),
+        }
+    }
+
+    /// Publish an event's dispatch payload if the event is selected
+    pub(super) async fn publish(&self, nats: &NatsPublisher, event: &GatewayEvent, primary: &str, dispatch: String) {
+        if !self.passthrough.selects(event) {
+            return;
+        }
+        let dispatch = match RawValue::from_string(dispatch) {
+            Ok(dispatch) => dispatch,
+            Err(e) => {
+                debug!(event_id = %event.event_id, error = %e, "Raw dispatch is not valid JSON");
+                return;
+            }
+        };
+        if !self.stream_ready.load(Ordering::Relaxed) && ensure_stream(nats).await {
+            self.stream_ready.store(true, Ordering::Relaxed);
+        }
+
+        let raw = RawEvent {
+            event_id: event.event_id.clone(),
+            event_type: event.event_type.clone(),
+            shard_id: event.shard_id,
+            guild_id: event.guild_id.clone(),
+            subject: primary.to_string(),
+            dispatch,
+        };
+        let payload = match serde_json::to_vec(&raw) {
+            Ok(payload) => payload,
+            Err(e) => {
+                debug!(event_id = %event.event_id, error = %e, "Failed to serialize raw event");
+                return;
+            }
+        };
+
+        let subject = subject(primary);
+        let published = match nats.jetstream().publish(subject.clone(), payload.into()).await {
+            Ok(ack) => ack.await.map(|_| ()).map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        if let Err(e) = published {
+            debug!(subject, event_id = %event.event_id, error = %e, "Raw passthrough publish failed");
+        }
+    }
+}
+
+async fn ensure_stream(nats: &NatsPublisher) -> bool {
+    let config = Config {
+        name: STREAM.to_string(),
+        subjects: vec![format!("{}.>", subjects::PREFIX)],
+        retention: RetentionPolicy::Limits,
+        max_age: MAX_AGE,
+        storage: StorageType::Memory,
+        ..Default::default()
+    };
+
+    match nats.jetstream().get_or_create_stream(config).await {
+        Ok(_) => true,
+        Err(e) => {
+            warn!(stream = STREAM, error = %e, "Failed to create raw passthrough stream");
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../packages/shared/nats-schemas/fixtures/raw-event.json"
+    );
+
+    fn event(event_type: &str, guild_id: Option<&str>) -> GatewayEvent {
+        GatewayEvent { guild_id: guild_id.map(String::from), ..GatewayEvent::for_test(event_type) }
+    }
+
+    #[test]
+    fn test_selects_by_event_type_or_guild() {
+        let passthrough = RawPassthrough::parse("member.update, message.create", "123456789012345678")
+            .unwrap()
+            .unwrap();
+        assert!(passthrough.selects(&event("member.update", None)));
+        assert!(passthrough.selects(&event("member.join", Some("123456789012345678"))));
+        assert!(!passthrough.selects(&event("member.join", Some("876543210987654321"))));
+
+        assert_eq!(RawPassthrough::parse(" ", "").unwrap(), None);
+        assert!(RawPassthrough::parse("", "guild-1").is_err());
+    }
+
+    #[test]
+    fn fixture_round_trips() {
+        let contents = std::fs::read_to_string(FIXTURE).expect("Failed to read raw-event.json");
+        let raw: RawEvent = serde_json::from_str(&contents).expect("fixture matches RawEvent");
+        assert_eq!(raw.subject, "events.member.update");
+        assert_eq!(subject(&raw.subject), "raw.events.member.update");
+
+        let fixture: serde_json::Value = serde_json::from_str(&contents).unwrap();
+        assert_eq!(serde_json::to_value(&raw).unwrap(), fixture);
+    }
+
+    #[test]
+    fn dispatch_is_published_verbatim() {
+        let dispatch = r#"{"t":"GUILD_MEMBER_UPDATE","s":42,"op":0,"d":{"nick":null,  "id":"1"}}"#;
+        let raw = RawEvent {
+            event_id: "e".to_string(),
+            event_type: "member.update".to_string(),
+            shard_id: 0,
+            guild_id: None,
+            subject: "events.member.update".to_string(),
+            dispatch: RawValue::from_string(dispatch.to_string()).unwrap(),
+        };
+        assert!(serde_json::to_string(&raw).unwrap().ends_with(&format!(r#""dispatch":{dispatch}}}"#)));
+    }
+}
@@ -0,0 +1,143 @@
+//! Index of recently published events
+//!
+//! Keeps the last N acknowledged publishes by `event_id`, served at
+//! `GET /debug/events/{event_id}`, so a consumer's "I never got event X" can
+//! be answered with where (and whether) the gateway published it.
+
+use crate::events::serialize::GatewayEvent;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+/// Where and when an event was published
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PublishedEvent {
+    pub event_id: String,
+    pub event_type: String,
+    pub shard_id: u64,
+    pub guild_id: Option<String>,
+    pub subject: String,
+    pub stream: String,
+    /// JetStream sequence from the publish ack
+    pub sequence: u64,
+    /// Event envelope timestamp (Unix millis)
+    pub timestamp: u64,
+    /// When the publish was acknowledged (Unix millis)
+    pub published_at: u64,
+}
+
+/// Index lookup result for an event not found
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexWindow {
+    /// Events currently indexed
+    pub indexed: usize,
+    pub capacity: usize,
+    /// Ack time of the oldest indexed event; older events have been evicted
+    pub oldest_published_at: Option<u64>,
+}
+
+#[derive(Debug, Default)]
+struct Entries {
+    by_id: HashMap<String, PublishedEvent>,
+    order: VecDeque<String>,
+}
+
+/// Bounded, insertion-ordered event index (oldest evicted first)
+#[derive(Debug)]
+pub struct RecentEvents {
+    capacity: usize,
+    entries: Mutex<Entries>,
+}
+
+impl RecentEvents {
+    /// Index of up to `capacity` events (0 keeps nothing)
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Mutex::default(),
+        }
+    }
+
+    /// Record an acknowledged publish
+    pub fn record(&self, event: &GatewayEvent, subject: &str, stream: &str, sequence: u64, published_at: u64) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let published = PublishedEvent {
+            event_id: event.event_id.clone(),
+            event_type: event.event_type.clone(),
+            shard_id: event.shard_id,
+            guild_id: event.guild_id.clone(),
+            subject: subject.to_string(),
+            stream: stream.to_string(),
+            sequence,
+            timestamp: event.timestamp,
+            published_at,
+        };
+
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        // A republished event_id (retry, redelivery) keeps its latest publish
+        if entries.by_id.insert(published.event_id.clone(), published).is_none() {
+            entries.order.push_back(event.event_id.clone());
+        }
+        while entries.order.len() > self.capacity {
+            if let Some(evicted) = entries.order.pop_front() {
+                entries.by_id.remove(&evicted);
+            }
+        }
+    }
+
+    /// Look up an event, or describe the window it would have been in
+    pub fn get(&self, event_id: &str) -> Result<PublishedEvent, IndexWindow> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.by_id.get(event_id).cloned().ok_or_else(|| IndexWindow {
+            indexed: entries.order.len(),
+            capacity: self.capacity,
+            oldest_published_at: entries
+                .order
+                .front()
+                .and_then(|id| entries.by_id.get(id))
+                .map(|oldest| oldest.published_at),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(event_id: &str) -> GatewayEvent {
+        GatewayEvent {
+            event_id: event_id.to_string(),
+            shard_id: 3,
+            guild_id: Some("123456789012345678".to_string()),
+            ..GatewayEvent::for_test("member.join")
+        }
+    }
+
+    #[test]
+    fn test_oldest_events_are_evicted() {
+        let index = RecentEvents::new(2);
+        for (seq, id) in ["a", "b", "c"].into_iter().enumerate() {
+            index.record(&event(id), "events.member.join", "EVENTS", seq as u64 + 1, 1_000 + seq as u64);
+        }
+
+        let found = index.get("c").unwrap();
+        assert_eq!((found.subject.as_str(), found.stream.as_str(), found.sequence), ("events.member.join", "EVENTS", 3));
+
+        let missing = index.get("a").unwrap_err();
+        assert_eq!((missing.indexed, missing.capacity, missing.oldest_published_at), (2, 2, Some(1_001)));
+    }
+
+    #[test]
+    fn test_republished_event_keeps_latest_publish() {
+        let index = RecentEvents::new(2);
+        index.record(&event("a"), "events.member.join", "EVENTS", 1, 1_000);
+        index.record(&event("a"), "events.member.join", "EVENTS", 7, 2_000);
+        index.record(&event("b"), "events.member.join", "EVENTS", 8, 2_001);
+
+        assert_eq!(index.get("a").unwrap().sequence, 7);
+        assert!(index.get("b").is_ok());
+    }
+}
@@ -0,0 +1,292 @@
+//! Replay of published events from JetStream (`POST /admin/replay`)
+//!
+//! After a worker bug is fixed, the events it mishandled are usually still in
+//! their stream. A replay reads the ones published in a time range on a
+//! subject filter (`events.member.>`) through an ephemeral ordered consumer,
+//! and republishes each, headers and payload unchanged, on
+//! `replay.{original subject}`. Workers subscribe to `replay.>` to reprocess
+//! them; the REPLAY stream keeps them for an hour.
+//!
+//! The replay stops at the end of the range, after `limit` events, or at the
+//! messages the stream held when it started, whichever comes first.
+
+use super::NatsPublisher;
+use async_nats::jetstream::consumer::pull::OrderedConfig;
+use async_nats::jetstream::consumer::DeliverPolicy;
+use async_nats::jetstream::stream::{Config, RetentionPolicy, StorageType};
+use chrono::{DateTime, Utc};
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use time::OffsetDateTime;
+use tracing::{info, warn};
+
+/// JetStream stream holding replayed events (mirrors nats-routing.json)
+pub const STREAM: &str = "REPLAY";
+
+/// Replay subjects (mirror nats-routing.json)
+pub mod subjects {
+    /// Replayed events: replay.{original subject}
+    pub const PREFIX: &str = "replay";
+}
+
+/// Header naming the replay an event was republished by
+pub const REPLAY_ID_HEADER: &str = "Arrakis-Replay-Id";
+
+/// Replayed events are dropped after this long
+const MAX_AGE: Duration = Duration::from_secs(3600);
+
+/// Events replayed when the request sets no limit
+const DEFAULT_LIMIT: u64 = 10_000;
+const MAX_LIMIT: u64 = 1_000_000;
+
+/// A replay ends when no message arrives for this long
+const IDLE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// `POST /admin/replay` body
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReplayRequest {
+    /// Subject filter, e.g. `events.member.>`
+    pub subject: String,
+    /// Start of the range (RFC 3339)
+    pub from: String,
+    /// End of the range (RFC 3339), now when unset
+    #[serde(default)]
+    pub to: Option<String>,
+    #[serde(default)]
+    pub limit: Option<u64>,
+}
+
+/// A validated replay request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayRange {
+    pub subject: String,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub limit: u64,
+}
+
+impl ReplayRequest {
+    /// Check the subject filter and the time range
+    pub fn validate(&self, now: DateTime<Utc>) -> Result<ReplayRange, String> {
+        let subject = self.subject.trim();
+        if subject.is_empty() || subject.split('.').any(str::is_empty) || subject.contains(char::is_whitespace) {
+            return Err(format!("invalid subject filter {:?}", self.subject));
+        }
+        if subject == ">" || subject.split('.').next() == Some(subjects::PREFIX) {
+            return Err(format!("subject filter {subject:?} would replay replayed events"));
+        }
+
+        let time = |field: &str, value: &str| {
+            DateTime::parse_from_rfc3339(value)
+                .map(|time| time.with_timezone(&Utc))
+                .map_err(|e| format!("{field} is not an RFC 3339 time: {e}"))
+        };
+        let from = time("from", &self.from)?;
+        let to = match self.to {
+            Some(ref to) => time("to", to)?,
+            None => now,
+        };
+        if from >= to {
+            return Err("from must be before to".to_string());
+        }
+        if from > now {
+            return Err("from is in the future".to_string());
+        }
+
+        let limit = self.limit.unwrap_or(DEFAULT_LIMIT);
+        if !(1..=MAX_LIMIT).contains(&limit) {
+            return Err(format!("limit must be 1 to {MAX_LIMIT}"));
+        }
+        Ok(ReplayRange { subject: subject.to_string(), from, to, limit })
+    }
+}
+
+/// Outcome of a replay
+#[derive(Debug, Default, Serialize, PartialEq, Eq)]
+pub struct Replayed {
+    pub replay_id: String,
+    pub stream: String,
+    /// Subject filter the replayed events were published on, under `replay.`
+    pub target: String,
+    pub replayed: u64,
+    /// Whether the limit stopped the replay before the end of the range
+    pub truncated: bool,
+    /// Why the replay stopped early
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Why a replay could not start
+#[derive(Debug)]
+pub enum ReplayError {
+    /// The request names no stream's subjects
+    NoStream(String),
+    Failed(String),
+}
+
+/// Replay subject for an event's original subject
+pub fn subject(original: &str) -> String {
+    format!("{}.{original}", subjects::PREFIX)
+}
+
+/// Republish the events published on `range.subject` between `range.from`
+/// and `range.to`
+pub async fn replay(nats: &NatsPublisher, range: &ReplayRange) -> Result<Replayed, ReplayError> {
+    let js = nats.jetstream();
+    let stream_name = js
+        .stream_by_subject(range.subject.clone())
+        .await
+        .map_err(|e| ReplayError::NoStream(format!("no stream holds {}: {e}", range.subject)))?;
+    ensure_stream(nats).await?;
+
+    let mut stream = js
+        .get_stream(&stream_name)
+        .await
+        .map_err(|e| ReplayError::Failed(format!("stream {stream_name}: {e}")))?;
+    // Messages published after the replay starts are left for the live consumers
+    let last_sequence = stream
+        .info()
+        .await
+        .map_err(|e| ReplayError::Failed(format!("stream {stream_name}: {e}")))?
+        .state
+        .last_sequence;
+    let consumer = stream
+        .create_consumer(OrderedConfig {
+            description: Some("arrakis-gateway replay".to_string()),
+            filter_subject: range.subject.clone(),
+            deliver_policy: DeliverPolicy::ByStartTime { start_time: offset_time(range.from) },
+            ..Default::default()
+        })
+        .await
+        .map_err(|e| ReplayError::Failed(format!("replay consumer on {stream_name}: {e}")))?;
+    let mut messages = consumer
+        .messages()
+        .await
+        .map_err(|e| ReplayError::Failed(format!("replay consumer on {stream_name}: {e}")))?;
+
+    let mut result = Replayed {
+        replay_id: uuid::Uuid::new_v4().to_string(),
+        stream: stream_name,
+        target: subject(&range.subject),
+        ..Default::default()
+    };
+    let to = offset_time(range.to);
+    info!(replay_id = %result.replay_id, stream = %result.stream, subject = %range.subject, from = %range.from, to = %range.to, "Event replay started");
+
+    loop {
+        let message = match tokio::time::timeout(IDLE_TIMEOUT, messages.next()).await {
+            Err(_) | Ok(None) => break,
+            Ok(Some(Err(e))) => {
+                result.error = Some(e.to_string());
+                break;
+            }
+            Ok(Some(Ok(message))) => message,
+        };
+        let (sequence, published, pending) = match message.info() {
+            Ok(info) => (info.stream_sequence, info.published, info.pending),
+            Err(e) => {
+                result.error = Some(e.to_string());
+                break;
+            }
+        };
+        if published > to || sequence > last_sequence {
+            break;
+        }
+        if result.replayed == range.limit {
+            result.truncated = true;
+            break;
+        }
+
+        let mut headers = message.headers.clone().unwrap_or_default();
+        headers.insert(REPLAY_ID_HEADER, result.replay_id.as_str());
+        // A fresh ID, so republishing an event twice isn't deduplicated away
+        headers.insert(async_nats::header::NATS_MESSAGE_ID, format!("{}:{sequence}", result.replay_id).as_str());
+        let target = subject(message.subject.as_str());
+        let published = match js.publish_with_headers(target, headers, message.payload.clone()).await {
+            Ok(ack) => ack.await.map(|_| ()).map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        if let Err(e) = published {
+            result.error = Some(e);
+            break;
+        }
+        result.replayed += 1;
+        if pending == 0 || sequence == last_sequence {
+            break;
+        }
+    }
+
+    info!(
+        replay_id = %result.replay_id,
+        replayed = result.replayed,
+        truncated = result.truncated,
+        error = result.error.as_deref(),
+        "Event replay finished"
+    );
+    Ok(result)
+}
+
+fn offset_time(time: DateTime<Utc>) -> OffsetDateTime {
+    OffsetDateTime::from_unix_timestamp_nanos(i128::from(time.timestamp_micros()) * 1000)
+        .unwrap_or(OffsetDateTime::UNIX_EPOCH)
+}
+
+async fn ensure_stream(nats: &NatsPublisher) -> Result<(), ReplayError> {
+    let config = Config {
+        name: STREAM.to_string(),
+        subjects: vec![format!("{}.>", subjects::PREFIX)],
+        retention: RetentionPolicy::Limits,
+        max_age: MAX_AGE,
+        storage: StorageType::File,
+        ..Default::default()
+    };
+
+    nats.jetstream().get_or_create_stream(config).await.map(|_| ()).map_err(|e| {
+        warn!(stream = STREAM, error = %e, "Failed to create replay stream");
+        ReplayError::Failed(format!("stream {STREAM}: {e}"))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(subject: &str, from: &str, to: Option<&str>) -> ReplayRequest {
+        ReplayRequest { subject: subject.to_string(), from: from.to_string(), to: to.map(String::from), limit: None }
+    }
+
+    fn now() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2026-10-17T12:00:00Z").unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_requests_are_validated() {
+        let range = request("events.member.>", "2026-10-17T09:00:00+02:00", None).validate(now()).unwrap();
+        assert_eq!(range.from, DateTime::parse_from_rfc3339("2026-10-17T07:00:00Z").unwrap());
+        assert_eq!((range.to, range.limit), (now(), DEFAULT_LIMIT));
+
+        for subject in ["", "events..member", "replay.events.>", ">", "events member"] {
+            assert!(request(subject, "2026-10-17T09:00:00Z", None).validate(now()).is_err(), "{subject:?}");
+        }
+        assert!(request("events.>", "yesterday", None).validate(now()).is_err());
+        assert!(request("events.>", "2026-10-17T10:00:00Z", Some("2026-10-17T09:00:00Z")).validate(now()).is_err());
+        assert!(request("events.>", "2026-10-18T10:00:00Z", Some("2026-10-19T09:00:00Z")).validate(now()).is_err());
+
+        let mut unlimited = request("events.>", "2026-10-17T09:00:00Z", None);
+        unlimited.limit = Some(0);
+        assert!(unlimited.validate(now()).is_err());
+    }
+
+    #[test]
+    fn test_start_times_keep_sub_second_precision() {
+        let from = DateTime::parse_from_rfc3339("2026-10-17T09:00:00.250Z").unwrap().with_timezone(&Utc);
+        let start = offset_time(from);
+        assert_eq!((start.unix_timestamp(), start.millisecond()), (from.timestamp(), 250));
+    }
+
+    #[test]
+    fn test_copies_are_published_under_the_replay_prefix() {
+        assert_eq!(subject("events.member.join"), "replay.events.member.join");
+    }
+}
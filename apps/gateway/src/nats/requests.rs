@@ -0,0 +1,67 @@
+//! Request-reply listeners shared by the `gateway.requests.*` handlers
+//!
+//! Every pool subscribes to each request subject and hears every request;
+//! the pool the request is about (the one running the guild's shard, or a
+//! handoff's target) answers it and the others stay silent. A `Listener`
+//! yields the requests that parse, with the subject to answer them on, and
+//! `reply` sends the answer.
+
+use super::NatsPublisher;
+use async_nats::{Subject, Subscriber};
+use futures_util::StreamExt as _;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tracing::{debug, info, warn};
+
+/// A request with the subject its answer goes to
+pub struct Request<T> {
+    pub body: T,
+    pub reply: Subject,
+}
+
+/// Requests on one subject
+pub struct Listener {
+    subject: &'static str,
+    /// What the requests are, for logs ("guild shard request")
+    name: &'static str,
+    messages: Subscriber,
+}
+
+impl Listener {
+    /// Subscribe to `subject`; None (logged) when NATS refuses
+    pub async fn subscribe(nats: &NatsPublisher, subject: &'static str, name: &'static str) -> Option<Self> {
+        match nats.client().subscribe(subject).await {
+            Ok(messages) => {
+                info!(subject, "Listening for {name}s");
+                Some(Self { subject, name, messages })
+            }
+            Err(e) => {
+                warn!(subject, error = %e, "Failed to subscribe to {name}s");
+                None
+            }
+        }
+    }
+
+    /// The next request, skipping messages with no reply subject or a body
+    /// that doesn't parse; None once the subscription ends
+    pub async fn next<T: DeserializeOwned>(&mut self) -> Option<Request<T>> {
+        while let Some(message) = self.messages.next().await {
+            let Some(reply) = message.reply else {
+                continue;
+            };
+            match serde_json::from_slice(&message.payload) {
+                Ok(body) => return Some(Request { body, reply }),
+                Err(e) => debug!(subject = self.subject, error = %e, "Ignoring malformed {}", self.name),
+            }
+        }
+        None
+    }
+}
+
+/// Answer a request; a failed reply is only logged, the requester times out
+pub async fn reply(nats: &NatsPublisher, reply: Subject, answer: &impl Serialize) {
+    let payload = serde_json::to_vec(answer).expect("reply serializes");
+    if let Err(e) = nats.client().publish(reply, payload.into()).await {
+        debug!(error = %e, "Request reply failed");
+    }
+}
@@ -0,0 +1,278 @@
+//! JetStream stream settings from nats-routing.json (`ENSURE_STREAMS`)
+//!
+//! The streams the gateway publishes to carry their retention, storage,
+//! max age, limits and replica count in `packages/shared/nats-schemas/
+//! nats-routing.json` (embedded at build time, see build.rs;
+//! `NATS_ROUTING_FILE` overrides it), next to their names and subjects. With
+//! `ENSURE_STREAMS`, the gateway creates any of them that are missing at
+//! startup, so a fresh environment's first publish has a stream to land in.
+//!
+//! An entry's `profiles` adjust it per deployment (`STREAM_PROFILE`, by
+//! default `production` in production): there, `EVENTS` is kept on disk with
+//! three replicas for a day, so a worker outage doesn't lose member events.
+//! `STREAM_<NAME>_STORAGE`, `_MAX_AGE_SECS`, `_MAX_MSGS`, `_MAX_BYTES` and
+//! `_REPLICAS` override a stream's settings in any profile.
+
+use super::publisher::streams;
+use crate::error::GatewayError;
+use async_nats::jetstream::stream::{Config, RetentionPolicy, StorageType};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::Duration;
+
+mod embedded {
+    include!(concat!(env!("OUT_DIR"), "/routing.rs"));
+}
+
+/// Streams the gateway publishes to, created by `ENSURE_STREAMS`
+pub const GATEWAY_STREAMS: [&str; 5] =
+    [streams::COMMANDS, streams::EVENTS, streams::MESSAGES, streams::VOICE, streams::ELIGIBILITY];
+
+/// JetStream allows at most 5 replicas
+const MAX_REPLICAS: usize = 5;
+
+/// One stream's entry in nats-routing.json
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct StreamSpec {
+    pub name: String,
+    pub subjects: Vec<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub retention: RetentionPolicy,
+    pub storage: StorageType,
+    /// 0 keeps messages until another limit is reached
+    pub max_age_secs: u64,
+    #[serde(default)]
+    pub max_msgs: Option<i64>,
+    #[serde(default)]
+    pub max_bytes: Option<i64>,
+    pub replicas: usize,
+}
+
+/// Settings a profile or the environment changes
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StreamOverrides {
+    pub storage: Option<StorageType>,
+    pub max_age_secs: Option<u64>,
+    /// -1 lifts the base entry's limit
+    pub max_msgs: Option<i64>,
+    pub max_bytes: Option<i64>,
+    pub replicas: Option<usize>,
+}
+
+/// A stream's entry: its settings, and their changes per profile
+#[derive(Deserialize)]
+struct Entry {
+    #[serde(flatten)]
+    spec: StreamSpec,
+    #[serde(default)]
+    profiles: BTreeMap<String, StreamOverrides>,
+}
+
+impl StreamSpec {
+    pub fn apply(&mut self, overrides: &StreamOverrides) {
+        if let Some(storage) = overrides.storage {
+            self.storage = storage;
+        }
+        if let Some(max_age_secs) = overrides.max_age_secs {
+            self.max_age_secs = max_age_secs;
+        }
+        if let Some(max_msgs) = overrides.max_msgs {
+            self.max_msgs = Some(max_msgs);
+        }
+        if let Some(max_bytes) = overrides.max_bytes {
+            self.max_bytes = Some(max_bytes);
+        }
+        if let Some(replicas) = overrides.replicas {
+            self.replicas = replicas;
+        }
+    }
+
+    fn validate(&self) -> Result<(), GatewayError> {
+        if !(1..=MAX_REPLICAS).contains(&self.replicas) {
+            return Err(GatewayError::Config(format!(
+                "stream {} needs 1 to {MAX_REPLICAS} replicas, got {}",
+                self.name, self.replicas
+            )));
+        }
+        Ok(())
+    }
+
+    /// JetStream configuration for the stream
+    pub fn config(&self) -> Config {
+        Config {
+            name: self.name.clone(),
+            subjects: self.subjects.clone(),
+            description: self.description.clone(),
+            retention: self.retention,
+            storage: self.storage,
+            max_age: Duration::from_secs(self.max_age_secs),
+            max_messages: self.max_msgs.unwrap_or(-1),
+            max_bytes: self.max_bytes.unwrap_or(-1),
+            num_replicas: self.replicas,
+            ..Default::default()
+        }
+    }
+}
+
+/// The gateway's streams from a nats-routing.json document, under a profile
+/// and per-stream overrides
+pub fn parse(
+    json: &str,
+    profile: Option<&str>,
+    overrides: impl Fn(&str) -> Result<StreamOverrides, GatewayError>,
+) -> Result<Vec<StreamSpec>, GatewayError> {
+    let routing: Value =
+        serde_json::from_str(json).map_err(|e| GatewayError::Config(format!("nats-routing.json is invalid: {e}")))?;
+    GATEWAY_STREAMS
+        .iter()
+        .map(|name| {
+            let entry = routing["streams"].get(name).ok_or_else(|| {
+                GatewayError::Config(format!("nats-routing.json has no {name} stream"))
+            })?;
+            let Entry { mut spec, profiles } = Entry::deserialize(entry)
+                .map_err(|e| GatewayError::Config(format!("nats-routing.json stream {name} is invalid: {e}")))?;
+            if spec.name != *name {
+                return Err(GatewayError::Config(format!("nats-routing.json stream {name} is named {:?}", spec.name)));
+            }
+            if let Some(changes) = profile.and_then(|profile| profiles.get(profile)) {
+                spec.apply(changes);
+            }
+            spec.apply(&overrides(name)?);
+            spec.validate()?;
+            Ok(spec)
+        })
+        .collect()
+}
+
+/// From `NATS_ROUTING_FILE`, else the copy embedded at build time
+pub fn load(
+    path: Option<&Path>,
+    profile: Option<&str>,
+    overrides: impl Fn(&str) -> Result<StreamOverrides, GatewayError>,
+) -> Result<Vec<StreamSpec>, GatewayError> {
+    match path {
+        Some(path) => parse(
+            &std::fs::read_to_string(path).map_err(|e| {
+                GatewayError::Config(format!("NATS_ROUTING_FILE {} can't be read: {e}", path.display()))
+            })?,
+            profile,
+            overrides,
+        ),
+        None => parse(
+            embedded::ROUTING.ok_or_else(|| {
+                GatewayError::Config("ENSURE_STREAMS needs NATS_ROUTING_FILE (this build has no nats-routing.json)".to_string())
+            })?,
+            profile,
+            overrides,
+        ),
+    }
+}
+
+/// What keeps an existing stream from matching its spec: settings that can
+/// be updated in place, and ones that can't (storage and retention)
+pub fn drift(current: &Config, wanted: &Config) -> (bool, Vec<&'static str>) {
+    let updatable = current.max_age != wanted.max_age
+        || current.max_messages != wanted.max_messages
+        || current.max_bytes != wanted.max_bytes
+        || current.num_replicas != wanted.num_replicas;
+    let mut fixed = Vec::new();
+    if current.storage != wanted.storage {
+        fixed.push("storage");
+    }
+    if current.retention != wanted.retention {
+        fixed.push("retention");
+    }
+    (updatable, fixed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn none(_: &str) -> Result<StreamOverrides, GatewayError> {
+        Ok(StreamOverrides::default())
+    }
+
+    #[test]
+    fn embedded_routing_has_every_gateway_stream() {
+        let specs = load(None, None, none).expect("nats-routing.json is embedded in the monorepo");
+        let names: Vec<_> = specs.iter().map(|spec| spec.name.as_str()).collect();
+        assert_eq!(names, GATEWAY_STREAMS);
+
+        let commands = specs[0].config();
+        assert_eq!(commands.subjects, ["commands.>"]);
+        assert_eq!(commands.retention, RetentionPolicy::WorkQueue);
+        assert_eq!(commands.max_age, Duration::from_secs(60));
+
+        let eligibility = specs.iter().find(|spec| spec.name == streams::ELIGIBILITY).unwrap().config();
+        assert_eq!(eligibility.storage, StorageType::File);
+        assert_eq!(eligibility.max_messages, -1, "unset limits are unlimited");
+    }
+
+    #[test]
+    fn streams_without_settings_are_rejected() {
+        let routing = |events: &str| {
+            let mut routing: Value = serde_json::from_str(embedded::ROUTING.unwrap()).unwrap();
+            routing["streams"]["EVENTS"] = serde_json::from_str(events).unwrap();
+            routing.to_string()
+        };
+
+        let bare = routing(r#"{ "name": "EVENTS", "subjects": ["events.>"], "description": "" }"#);
+        assert!(parse(&bare, None, none).unwrap_err().to_string().contains("stream EVENTS is invalid"));
+
+        let replicas = routing(
+            r#"{ "name": "EVENTS", "subjects": ["events.>"], "retention": "limits", "storage": "file",
+                 "max_age_secs": 0, "replicas": 7 }"#,
+        );
+        assert!(parse(&replicas, None, none).unwrap_err().to_string().contains("1 to 5 replicas"));
+
+        let unknown = routing(
+            r#"{ "name": "EVENTS", "subjects": ["events.>"], "retention": "limits", "storage": "file",
+                 "max_age_secs": 0, "replicas": 1, "profiles": { "production": { "replica": 3 } } }"#,
+        );
+        assert!(parse(&unknown, Some("production"), none).is_err(), "a misspelt setting isn't ignored");
+    }
+
+    #[test]
+    fn production_keeps_events_on_disk_with_replicas() {
+        let routing = embedded::ROUTING.unwrap();
+        let events = |specs: Vec<StreamSpec>| specs.into_iter().find(|spec| spec.name == streams::EVENTS).unwrap();
+
+        let development = events(parse(routing, None, none).unwrap());
+        assert_eq!((development.storage, development.replicas), (StorageType::Memory, 1));
+
+        let production = events(parse(routing, Some("production"), none).unwrap());
+        assert_eq!((production.storage, production.replicas), (StorageType::File, 3));
+        assert!(production.max_age_secs >= 3600, "outlasts a worker outage");
+
+        // The environment wins over the profile
+        let overridden = parse(routing, Some("production"), |name| {
+            Ok(StreamOverrides {
+                replicas: (name == streams::EVENTS).then_some(5),
+                storage: (name == streams::EVENTS).then_some(StorageType::Memory),
+                ..Default::default()
+            })
+        })
+        .unwrap();
+        let overridden = events(overridden);
+        assert_eq!((overridden.storage, overridden.replicas), (StorageType::Memory, 5));
+    }
+
+    #[test]
+    fn drift_separates_updatable_settings() {
+        let spec = load(None, None, none).unwrap().remove(1);
+        let current = spec.config();
+
+        let mut wanted = spec.clone();
+        wanted.apply(&StreamOverrides { max_age_secs: Some(86_400), replicas: Some(3), ..Default::default() });
+        assert_eq!(drift(&current, &wanted.config()), (true, vec![]));
+
+        wanted.storage = StorageType::File;
+        assert_eq!(drift(&current, &wanted.config()), (true, vec!["storage"]));
+        assert_eq!(drift(&current, &current), (false, vec![]));
+    }
+}
@@ -0,0 +1,118 @@
+//! Gateway request-reply endpoints
+//!
+//! Registers the gateway's state queries with the NATS micro service API, so
+//! every pool shows up in `nats micro ls` and answers the standard PING, INFO
+//! and STATS requests. Each pool answers on `gateway.{pool_id}.{endpoint}`.
+
+use super::NatsPublisher;
+use crate::error::GatewayError;
+use crate::health::{self, AppState};
+use async_nats::service::{self, Service, ServiceExt};
+use futures_util::StreamExt;
+use std::collections::HashMap;
+use tracing::{debug, info, warn};
+
+/// Service name (mirrors `service.name` in nats-routing.json)
+pub const SERVICE_NAME: &str = "arrakis-gateway";
+/// Subject prefix of every endpoint
+pub const SUBJECT_PREFIX: &str = "gateway";
+
+/// A state query answered over NATS
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endpoint {
+    /// Same body as `GET /health`
+    Health,
+    /// Same body as `GET /ready`
+    Ready,
+    /// Same body as `GET /buildinfo`
+    BuildInfo,
+    /// Same body as `GET /debug/memory`
+    Memory,
+}
+
+impl Endpoint {
+    pub const ALL: [Self; 4] = [Self::Health, Self::Ready, Self::BuildInfo, Self::Memory];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Health => "health",
+            Self::Ready => "ready",
+            Self::BuildInfo => "buildinfo",
+            Self::Memory => "memory",
+        }
+    }
+
+    /// Answer the query from current state
+    fn respond(self, state: &AppState) -> Result<Vec<u8>, serde_json::Error> {
+        match self {
+            Self::Health => serde_json::to_vec(&health::health(state)),
+            Self::Ready => serde_json::to_vec(&health::readiness(state)),
+            Self::BuildInfo => serde_json::to_vec(&health::build_info(state)),
+            Self::Memory => serde_json::to_vec(&state.metrics.memory_report()),
+        }
+    }
+}
+
+/// Subject a pool answers an endpoint on
+pub fn subject(pool_id: u64, endpoint: Endpoint) -> String {
+    format!("{SUBJECT_PREFIX}.{pool_id}.{}", endpoint.name())
+}
+
+/// Register the service and serve its endpoints until the service is stopped
+pub async fn start(nats: &NatsPublisher, state: AppState) -> Result<Service, GatewayError> {
+    let pool_id = state.shard_state.pool_id();
+    let service = nats
+        .client()
+        .service_builder()
+        .description("Discord gateway shard pool state")
+        .metadata(HashMap::from([("pool_id".to_string(), pool_id.to_string())]))
+        .start(SERVICE_NAME, env!("CARGO_PKG_VERSION"))
+        .await
+        .map_err(GatewayError::NatsServiceFailed)?;
+
+    let group = service.group(format!("{SUBJECT_PREFIX}.{pool_id}"));
+    for endpoint in Endpoint::ALL {
+        let mut requests = group
+            .endpoint(endpoint.name())
+            .await
+            .map_err(GatewayError::NatsServiceFailed)?;
+        let state = state.clone();
+
+        tokio::spawn(async move {
+            while let Some(request) = requests.next().await {
+                let response = endpoint.respond(&state).map(Into::into).map_err(|e| service::error::Error {
+                    status: e.to_string(),
+                    code: 500,
+                });
+                if let Err(e) = request.respond(response).await {
+                    debug!(endpoint = endpoint.name(), error = %e, "Service reply failed");
+                }
+            }
+        });
+    }
+
+    info!(
+        service = SERVICE_NAME,
+        subjects = ?Endpoint::ALL.map(|e| subject(pool_id, e)),
+        "NATS service registered"
+    );
+    Ok(service)
+}
+
+/// Stop answering requests and deregister
+pub async fn stop(service: Service) {
+    if let Err(e) = service.stop().await {
+        warn!(error = %e, "Failed to stop NATS service");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subjects() {
+        assert_eq!(subject(2, Endpoint::Ready), "gateway.2.ready");
+        assert_eq!(subject(0, Endpoint::BuildInfo), "gateway.0.buildinfo");
+    }
+}
@@ -0,0 +1,166 @@
+//! Event signatures
+//!
+//! With `EVENT_SIGNING` set, every event publish carries a signature over the
+//! payload bytes, so workers can tell events the gateway published from ones
+//! injected by another service holding NATS credentials:
+//!
+//! - `Arrakis-Signature`: hex signature of the message payload
+//! - `Arrakis-Signature-Alg`: `hmac-sha256` or `ed25519`
+//! - `Arrakis-Signature-Key`: `EVENT_SIGNING_KEY_ID`, when set (key rotation)
+//!
+//! HMAC needs the secret on every worker; Ed25519 workers only need the public
+//! key, which is logged at startup.
+
+use crate::error::GatewayError;
+use crate::hex;
+use ring::signature::KeyPair;
+use ring::{hmac, signature};
+
+pub const SIGNATURE_HEADER: &str = "Arrakis-Signature";
+pub const ALGORITHM_HEADER: &str = "Arrakis-Signature-Alg";
+pub const KEY_ID_HEADER: &str = "Arrakis-Signature-Key";
+
+/// Signature scheme for published events
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SigningAlgorithm {
+    /// Shared secret
+    HmacSha256,
+    /// Key pair; workers verify with the public key
+    Ed25519,
+}
+
+impl SigningAlgorithm {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim() {
+            "hmac-sha256" => Some(Self::HmacSha256),
+            "ed25519" => Some(Self::Ed25519),
+            _ => None,
+        }
+    }
+
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::HmacSha256 => "hmac-sha256",
+            Self::Ed25519 => "ed25519",
+        }
+    }
+}
+
+/// Event signing settings
+#[derive(Clone)]
+pub struct SigningConfig {
+    pub algorithm: SigningAlgorithm,
+    /// HMAC secret, or the Ed25519 private key seed (32 bytes)
+    pub key: Vec<u8>,
+    pub key_id: Option<String>,
+}
+
+impl std::fmt::Debug for SigningConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SigningConfig")
+            .field("algorithm", &self.algorithm)
+            .field("key", &"<redacted>")
+            .field("key_id", &self.key_id)
+            .finish()
+    }
+}
+
+enum SigningKey {
+    Hmac(hmac::Key),
+    Ed25519(signature::Ed25519KeyPair),
+}
+
+/// Signs event payloads and adds the signature headers
+pub struct EventSigner {
+    algorithm: SigningAlgorithm,
+    key: SigningKey,
+    key_id: Option<String>,
+}
+
+impl EventSigner {
+    pub fn new(config: &SigningConfig) -> Result<Self, GatewayError> {
+        let key = match config.algorithm {
+            SigningAlgorithm::HmacSha256 => {
+                if config.key.len() < 32 {
+                    return Err(GatewayError::Config("EVENT_SIGNING_KEY must be at least 32 bytes for hmac-sha256".into()));
+                }
+                SigningKey::Hmac(hmac::Key::new(hmac::HMAC_SHA256, &config.key))
+            }
+            SigningAlgorithm::Ed25519 => signature::Ed25519KeyPair::from_seed_unchecked(&config.key)
+                .map(SigningKey::Ed25519)
+                .map_err(|_| GatewayError::Config("EVENT_SIGNING_KEY must be a 32-byte seed for ed25519".into()))?,
+        };
+        Ok(Self { algorithm: config.algorithm, key, key_id: config.key_id.clone() })
+    }
+
+    pub fn algorithm(&self) -> SigningAlgorithm {
+        self.algorithm
+    }
+
+    /// Hex public key workers verify with (Ed25519 only)
+    pub fn public_key(&self) -> Option<String> {
+        match self.key {
+            SigningKey::Hmac(_) => None,
+            SigningKey::Ed25519(ref pair) => Some(hex::encode(pair.public_key().as_ref())),
+        }
+    }
+
+    /// Hex signature of a payload
+    pub fn sign(&self, payload: &[u8]) -> String {
+        match self.key {
+            SigningKey::Hmac(ref key) => hex::encode(hmac::sign(key, payload).as_ref()),
+            SigningKey::Ed25519(ref pair) => hex::encode(pair.sign(payload).as_ref()),
+        }
+    }
+
+    /// Add the signature headers for a payload
+    pub fn apply(&self, headers: &mut async_nats::HeaderMap, payload: &[u8]) {
+        headers.insert(SIGNATURE_HEADER, self.sign(payload).as_str());
+        headers.insert(ALGORITHM_HEADER, self.algorithm.as_str());
+        if let Some(ref key_id) = self.key_id {
+            headers.insert(KEY_ID_HEADER, key_id.as_str());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signer(algorithm: SigningAlgorithm, key_id: Option<&str>) -> EventSigner {
+        let key = hex::decode("000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f").unwrap();
+        EventSigner::new(&SigningConfig { algorithm, key, key_id: key_id.map(str::to_string) }).unwrap()
+    }
+
+    #[test]
+    fn hmac_signature_matches_a_reference_implementation() {
+        // Python: hmac.new(bytes(range(32)), payload, hashlib.sha256).hexdigest()
+        assert_eq!(
+            signer(SigningAlgorithm::HmacSha256, None).sign(br#"{"event_id":"1"}"#),
+            "93fc6e6f2daf13bcb490744b562ab5c4cc91f7e5efa6689121f65d4da8a6cb25"
+        );
+    }
+
+    #[test]
+    fn ed25519_signature_verifies_with_the_public_key() {
+        let signer = signer(SigningAlgorithm::Ed25519, Some("2026-10"));
+        let payload = br#"{"event_id":"1"}"#;
+        let public_key = hex::decode(&signer.public_key().unwrap()).unwrap();
+        let verifier = signature::UnparsedPublicKey::new(&signature::ED25519, public_key);
+
+        let mut headers = async_nats::HeaderMap::new();
+        signer.apply(&mut headers, payload);
+        let signature = hex::decode(headers.get(SIGNATURE_HEADER).unwrap().as_str()).unwrap();
+        assert!(verifier.verify(payload, &signature).is_ok());
+        assert!(verifier.verify(br#"{"event_id":"2"}"#, &signature).is_err());
+        assert_eq!(headers.get(ALGORITHM_HEADER).unwrap().as_str(), "ed25519");
+        assert_eq!(headers.get(KEY_ID_HEADER).unwrap().as_str(), "2026-10");
+    }
+
+    #[test]
+    fn keys_are_checked() {
+        let config = |algorithm, key: &str| SigningConfig { algorithm, key: hex::decode(key).unwrap(), key_id: None };
+        assert!(EventSigner::new(&config(SigningAlgorithm::HmacSha256, "00ff")).is_err());
+        assert!(EventSigner::new(&config(SigningAlgorithm::Ed25519, "00ff")).is_err());
+    }
+}
@@ -0,0 +1,298 @@
+//! Scheduled tick events
+//!
+//! A single clock for periodic worker jobs: `ticks.minute` on every minute
+//! boundary, `ticks.hour` on every hour, and `ticks.guild.{guild_id}.{name}`
+//! for per-guild cron schedules stored in the `guild_schedules` KV bucket.
+//!
+//! Every pool publishes the minute and hour ticks with a message ID, so the
+//! TICKS stream keeps one copy and ticks continue while any pool is up. Guild
+//! schedules fire from the pool that runs the guild's shard.
+
+use super::kv::{self, buckets};
+use super::publisher::subject_token;
+use super::NatsPublisher;
+use crate::events::serialize::now_millis;
+use crate::shard::{shard_for_guild, ShardState};
+use async_nats::jetstream::kv::{Entry, Operation, Watch};
+use async_nats::jetstream::stream::{Config, RetentionPolicy, StorageType};
+use async_nats::HeaderMap;
+use chrono::{DateTime, Utc};
+use croner::parser::{CronParser, Seconds};
+use croner::Cron;
+use futures_util::StreamExt as _;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// JetStream stream holding tick events (mirrors nats-routing.json)
+pub const STREAM: &str = "TICKS";
+
+/// Tick subjects (mirror nats-routing.json)
+pub mod subjects {
+    pub const MINUTE: &str = "ticks.minute";
+    pub const HOUR: &str = "ticks.hour";
+    /// Guild schedules: ticks.guild.{guild_id}.{name}
+    pub const GUILD: &str = "ticks.guild";
+}
+
+/// Ticks nobody consumed are dropped after this long
+const MAX_AGE: Duration = Duration::from_secs(3600);
+
+const MINUTE_MS: u64 = 60_000;
+const HOUR_MS: u64 = 3_600_000;
+
+/// One tick (`fixtures/tick.json`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tick {
+    /// `minute`, `hour`, or the guild schedule's name
+    pub tick: String,
+    /// Set for guild schedules
+    pub guild_id: Option<String>,
+    /// The boundary this tick is for (Unix milliseconds, UTC)
+    pub scheduled_at: u64,
+}
+
+/// A guild's schedule as stored in KV: the value under key `{guild_id}` is a
+/// JSON array of these
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScheduleEntry {
+    pub name: String,
+    /// Five-field cron expression, evaluated in UTC
+    pub cron: String,
+}
+
+#[derive(Debug, Clone)]
+struct Schedule {
+    name: String,
+    cron: Cron,
+}
+
+/// Parse a KV value, skipping entries with an invalid name or expression
+fn parse_schedules(guild_id: u64, value: &[u8]) -> Vec<Schedule> {
+    let entries: Vec<ScheduleEntry> = match serde_json::from_slice(value) {
+        Ok(entries) => entries,
+        Err(e) => {
+            warn!(guild_id, error = %e, "Ignoring malformed guild schedule");
+            return Vec::new();
+        }
+    };
+    let parser = CronParser::builder().seconds(Seconds::Disallowed).build();
+
+    entries
+        .into_iter()
+        .filter_map(|entry| {
+            if entry.name.is_empty() || subject_token(&entry.name) != entry.name {
+                warn!(guild_id, name = %entry.name, "Schedule name must be a single subject token");
+                return None;
+            }
+            match parser.parse(&entry.cron) {
+                Ok(cron) => Some(Schedule { name: entry.name, cron }),
+                Err(e) => {
+                    warn!(guild_id, name = %entry.name, error = %e, "Ignoring invalid cron expression");
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// Ticks due at the minute boundary `at_ms`, with their subjects.
+/// `owns` says whether this pool runs a guild's shard.
+fn due(at_ms: u64, schedules: &HashMap<u64, Vec<Schedule>>, owns: impl Fn(u64) -> bool) -> Vec<(String, Tick)> {
+    let tick = |name: &str, guild_id: Option<u64>| Tick {
+        tick: name.to_string(),
+        guild_id: guild_id.map(|id| id.to_string()),
+        scheduled_at: at_ms,
+    };
+
+    let mut ticks = vec![(subjects::MINUTE.to_string(), tick("minute", None))];
+    if at_ms.is_multiple_of(HOUR_MS) {
+        ticks.push((subjects::HOUR.to_string(), tick("hour", None)));
+    }
+
+    let Some(at) = DateTime::<Utc>::from_timestamp_millis(at_ms as i64) else {
+        return ticks;
+    };
+    for (&guild_id, guild_schedules) in schedules.iter().filter(|(&id, _)| owns(id)) {
+        for schedule in guild_schedules {
+            if schedule.cron.is_time_matching(&at).unwrap_or(false) {
+                ticks.push((
+                    format!("{}.{guild_id}.{}", subjects::GUILD, schedule.name),
+                    tick(&schedule.name, Some(guild_id)),
+                ));
+            }
+        }
+    }
+    ticks
+}
+
+/// Publish ticks until the process exits
+pub async fn run_scheduler(nats: Arc<NatsPublisher>, state: ShardState) {
+    info!(stream = STREAM, "Tick scheduler started");
+
+    let js = nats.jetstream();
+    let mut stream_ready = false;
+    let mut watch: Option<Watch> = None;
+    let mut schedules: HashMap<u64, Vec<Schedule>> = HashMap::new();
+
+    loop {
+        if watch.is_none() {
+            watch = open_watch(&nats).await;
+        }
+
+        let now = now_millis();
+        let next = (now / MINUTE_MS + 1) * MINUTE_MS;
+        let sleep = tokio::time::sleep(Duration::from_millis(next - now));
+        tokio::pin!(sleep);
+
+        // Apply schedule changes until the boundary
+        loop {
+            tokio::select! {
+                () = &mut sleep => break,
+                entry = next_entry(&mut watch) => match entry {
+                    Some(entry) => apply(&mut schedules, &entry),
+                    None => {
+                        debug!("Guild schedule watch ended");
+                        watch = None;
+                    }
+                },
+            }
+        }
+
+        if !stream_ready {
+            stream_ready = ensure_stream(&nats).await;
+        }
+
+        let total_shards = state.total_shards();
+        let owns = |guild_id: u64| state.get_health(shard_for_guild(guild_id, total_shards)).is_some();
+        for (subject, tick) in due(next, &schedules, owns) {
+            let Ok(payload) = serde_json::to_vec(&tick) else {
+                continue;
+            };
+            let mut headers = HeaderMap::new();
+            headers.insert(async_nats::header::NATS_MESSAGE_ID, format!("{subject}-{next}").as_str());
+
+            match js.publish_with_headers(subject.clone(), headers, payload.into()).await {
+                Ok(ack) => {
+                    if let Err(e) = ack.await {
+                        debug!(subject, error = %e, "Tick not acknowledged");
+                    }
+                }
+                Err(e) => debug!(subject, error = %e, "Tick publish failed"),
+            }
+        }
+    }
+}
+
+async fn open_watch(nats: &NatsPublisher) -> Option<Watch> {
+    let store = kv::open_bucket(nats.jetstream(), buckets::GUILD_SCHEDULES, "Per-guild tick schedules")
+        .await
+        .map_err(|e| debug!(error = %e, "Guild schedule bucket unavailable"))
+        .ok()?;
+    store
+        .watch_with_history(">")
+        .await
+        .map_err(|e| debug!(error = %e, "Guild schedule watch failed"))
+        .ok()
+}
+
+/// Next bucket change; never resolves without a watch
+async fn next_entry(watch: &mut Option<Watch>) -> Option<Entry> {
+    match watch {
+        Some(watch) => watch.next().await.and_then(Result::ok),
+        None => std::future::pending().await,
+    }
+}
+
+fn apply(schedules: &mut HashMap<u64, Vec<Schedule>>, entry: &Entry) {
+    let Ok(guild_id) = entry.key.parse::<u64>() else {
+        warn!(key = %entry.key, "Guild schedule keys must be guild IDs");
+        return;
+    };
+    match entry.operation {
+        Operation::Put => {
+            schedules.insert(guild_id, parse_schedules(guild_id, &entry.value));
+        }
+        Operation::Delete | Operation::Purge => {
+            schedules.remove(&guild_id);
+        }
+    }
+}
+
+/// Create the TICKS stream if needed. Its duplicate window drops the copies
+/// other pools publish for the same boundary.
+async fn ensure_stream(nats: &NatsPublisher) -> bool {
+    let config = Config {
+        name: STREAM.to_string(),
+        subjects: vec!["ticks.>".to_string()],
+        retention: RetentionPolicy::Limits,
+        max_age: MAX_AGE,
+        storage: StorageType::Memory,
+        duplicate_window: Duration::from_secs(120),
+        ..Default::default()
+    };
+
+    match nats.jetstream().get_or_create_stream(config).await {
+        Ok(_) => true,
+        Err(e) => {
+            warn!(stream = STREAM, error = %e, "Failed to create tick stream");
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../packages/shared/nats-schemas/fixtures/tick.json"
+    );
+
+    /// 2023-11-14T22:00:00Z
+    const HOUR_BOUNDARY: u64 = 1_700_000_000_000 - 1_700_000_000_000 % HOUR_MS;
+
+    #[test]
+    fn test_minute_and_hour_ticks() {
+        let none = HashMap::new();
+        let subjects = |at| due(at, &none, |_| true).into_iter().map(|(s, _)| s).collect::<Vec<_>>();
+
+        assert_eq!(subjects(HOUR_BOUNDARY), [subjects::MINUTE, subjects::HOUR]);
+        assert_eq!(subjects(HOUR_BOUNDARY + MINUTE_MS), [subjects::MINUTE]);
+    }
+
+    #[test]
+    fn test_guild_schedules_fire_on_owning_pool() {
+        let guild = 123456789012345678;
+        let schedules = HashMap::from([(
+            guild,
+            parse_schedules(guild, br#"[{"name":"digest","cron":"0 22 * * *"},{"name":"bad","cron":"61 * * * *"}]"#),
+        )]);
+        assert_eq!(schedules[&guild].len(), 1);
+
+        let ticks = due(HOUR_BOUNDARY, &schedules, |_| true);
+        let (subject, tick) = ticks.last().unwrap();
+        assert_eq!(subject, "ticks.guild.123456789012345678.digest");
+        assert_eq!(tick.guild_id.as_deref(), Some("123456789012345678"));
+
+        assert_eq!(due(HOUR_BOUNDARY, &schedules, |_| false).len(), 2);
+        assert_eq!(due(HOUR_BOUNDARY + MINUTE_MS, &schedules, |_| true).len(), 1);
+    }
+
+    #[test]
+    fn test_schedule_names_are_subject_tokens() {
+        assert!(parse_schedules(1, br#"[{"name":"a.b","cron":"* * * * *"}]"#).is_empty());
+        assert!(parse_schedules(1, b"not json").is_empty());
+    }
+
+    #[test]
+    fn fixture_round_trips() {
+        let fixture: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(FIXTURE).expect("Failed to read tick.json")).unwrap();
+        let tick: Tick = serde_json::from_value(fixture.clone()).expect("fixture matches Tick");
+        assert_eq!(serde_json::to_value(&tick).unwrap(), fixture);
+    }
+}
@@ -0,0 +1,349 @@
+//! Cluster topology snapshots
+//!
+//! Each pool periodically describes itself — shards, their health and guild
+//! counts, versions — on `topology.gateway` and under `pool-{pool_id}` in the
+//! `gateway_topology` KV bucket. Workers doing guild-affinity routing list the
+//! bucket for the whole cluster instead of scraping every pool's HTTP API; a
+//! guild lives on shard `(guild_id >> 22) % total_shards`.
+//!
+//! `GET /cluster` on any pool reads the bucket back and sums it up in a
+//! `ClusterReport`: shards ready cluster-wide, shards no pool is running or
+//! more than one pool is running, and pools whose snapshots have gone stale.
+
+use super::kv::{self, buckets};
+use super::NatsPublisher;
+use crate::error::GatewayError;
+use crate::events::serialize::now_millis;
+use crate::health::AppState;
+use crate::shard::DEFAULT_SHARDS_PER_POOL;
+use async_nats::jetstream::kv::Store;
+use futures_util::StreamExt as _;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// Core NATS subject snapshots are published on (mirrors nats-routing.json)
+pub const SUBJECT: &str = "topology.gateway";
+
+/// Entries of pools that stop publishing expire after this long
+pub const TTL: Duration = Duration::from_secs(300);
+
+/// Snapshots older than this are flagged in cluster reports: a pool
+/// publishing at the longest `TOPOLOGY_INTERVAL_SECS` has missed one
+pub const STALE_AFTER: Duration = Duration::from_secs(TTL.as_secs() / 2);
+
+/// One pool's topology document (`fixtures/gateway-topology.json`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PoolTopology {
+    pub pool_id: u64,
+    /// Shard count of the whole cluster
+    pub total_shards: u64,
+    /// Size of each pool's shard range; absent from older pools' documents
+    #[serde(default = "default_shards_per_pool")]
+    pub shards_per_pool: u64,
+    pub version: String,
+    pub discord_api_version: u8,
+    pub discord_api_mode: String,
+    pub guilds_total: u64,
+    /// Shards this pool runs, in ID order
+    pub shards: Vec<ShardTopology>,
+    /// Unix milliseconds when the snapshot was taken
+    pub timestamp: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShardTopology {
+    pub shard_id: u64,
+    /// `connecting`, `ready`, `resuming`, `disconnected`, `dead` or `drained`
+    pub health: String,
+    pub guilds: u64,
+}
+
+impl PoolTopology {
+    /// Snapshot this pool's current state
+    pub fn capture(state: &AppState, timestamp: u64) -> Self {
+        let shards: Vec<ShardTopology> = state
+            .shard_state
+            .snapshots()
+            .into_iter()
+            .map(|shard| ShardTopology {
+                shard_id: shard.shard_id,
+                health: shard.health.as_str().to_string(),
+                guilds: shard.guilds,
+            })
+            .collect();
+
+        Self {
+            pool_id: state.shard_state.pool_id(),
+            total_shards: state.shard_state.total_shards(),
+            shards_per_pool: state.shard_state.shards_per_pool(),
+            version: state.build_info.version.to_string(),
+            discord_api_version: state.build_info.discord_api.gateway,
+            discord_api_mode: state.build_info.discord_api.mode.as_str().to_string(),
+            guilds_total: shards.iter().map(|s| s.guilds).sum(),
+            shards,
+            timestamp,
+        }
+    }
+}
+
+/// The whole cluster, aggregated from every pool's document
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ClusterReport {
+    /// Shard count the pools agree on (None when they disagree)
+    pub total_shards: Option<u64>,
+    pub shards_per_pool: Option<u64>,
+    pub shards_running: u64,
+    pub shards_ready: u64,
+    /// Running shards by health
+    pub shards_by_health: BTreeMap<String, u64>,
+    pub guilds_total: u64,
+    /// Shards of `total_shards` no pool is running
+    pub missing_shards: Vec<u64>,
+    /// Shards run by more than one pool
+    pub duplicate_shards: Vec<u64>,
+    /// Pool IDs by gateway version
+    pub versions: BTreeMap<String, Vec<u64>>,
+    pub pools: Vec<PoolSummary>,
+    /// Disagreements and stale snapshots, for a human to read
+    pub warnings: Vec<String>,
+}
+
+/// One pool's line in the cluster report
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PoolSummary {
+    pub pool_id: u64,
+    pub version: String,
+    pub shards: u64,
+    pub shards_ready: u64,
+    pub guilds: u64,
+    /// Seconds since the pool's snapshot was taken
+    pub snapshot_age_secs: u64,
+}
+
+impl ClusterReport {
+    /// Aggregate pool documents. A snapshot older than `stale_after` is
+    /// still counted, with a warning: the pool may be gone.
+    pub fn aggregate(pools: &[PoolTopology], now_ms: u64, stale_after: Duration) -> Self {
+        let mut pools: Vec<&PoolTopology> = pools.iter().collect();
+        pools.sort_by_key(|pool| pool.pool_id);
+        let mut warnings = Vec::new();
+
+        let agreed = |name: &str, setting: fn(&PoolTopology) -> u64, warnings: &mut Vec<String>| {
+            let values: BTreeSet<u64> = pools.iter().map(|pool| setting(pool)).collect();
+            if values.len() > 1 {
+                warnings.push(format!("pools disagree on {name}: {values:?}"));
+                return None;
+            }
+            values.into_iter().next()
+        };
+        let total_shards = agreed("total_shards", |pool| pool.total_shards, &mut warnings);
+        let shards_per_pool = agreed("shards_per_pool", |pool| pool.shards_per_pool, &mut warnings);
+
+        let mut runners: BTreeMap<u64, u64> = BTreeMap::new();
+        let mut shards_by_health: BTreeMap<String, u64> = BTreeMap::new();
+        let mut versions: BTreeMap<String, Vec<u64>> = BTreeMap::new();
+        let mut summaries = Vec::with_capacity(pools.len());
+        for pool in &pools {
+            for shard in &pool.shards {
+                *runners.entry(shard.shard_id).or_default() += 1;
+                *shards_by_health.entry(shard.health.clone()).or_default() += 1;
+            }
+            versions.entry(pool.version.clone()).or_default().push(pool.pool_id);
+
+            let age = Duration::from_millis(now_ms.saturating_sub(pool.timestamp));
+            if age > stale_after {
+                warnings.push(format!("pool {} has not published for {}s", pool.pool_id, age.as_secs()));
+            }
+            summaries.push(PoolSummary {
+                pool_id: pool.pool_id,
+                version: pool.version.clone(),
+                shards: pool.shards.len() as u64,
+                shards_ready: pool.shards.iter().filter(|shard| shard.health == "ready").count() as u64,
+                guilds: pool.guilds_total,
+                snapshot_age_secs: age.as_secs(),
+            });
+        }
+
+        let missing_shards = match total_shards {
+            Some(total) => (0..total).filter(|shard_id| !runners.contains_key(shard_id)).collect(),
+            None => Vec::new(),
+        };
+        if versions.len() > 1 {
+            warnings.push(format!("pools run {} gateway versions", versions.len()));
+        }
+
+        Self {
+            total_shards,
+            shards_per_pool,
+            shards_running: runners.len() as u64,
+            shards_ready: summaries.iter().map(|pool| pool.shards_ready).sum(),
+            shards_by_health,
+            guilds_total: summaries.iter().map(|pool| pool.guilds).sum(),
+            missing_shards,
+            duplicate_shards: runners.iter().filter(|(_, n)| **n > 1).map(|(shard_id, _)| *shard_id).collect(),
+            versions,
+            pools: summaries,
+            warnings,
+        }
+    }
+}
+
+fn default_shards_per_pool() -> u64 {
+    DEFAULT_SHARDS_PER_POOL
+}
+
+/// KV key for a pool's document
+pub fn key(pool_id: u64) -> String {
+    format!("pool-{pool_id}")
+}
+
+/// Publish a snapshot every `every` until the process exits
+pub async fn run_publisher(nats: Arc<NatsPublisher>, state: AppState, every: Duration) {
+    info!(interval_secs = every.as_secs(), subject = SUBJECT, "Topology publisher started");
+
+    let key = key(state.shard_state.pool_id());
+    let mut store: Option<Store> = None;
+    let mut interval = tokio::time::interval(every);
+    loop {
+        interval.tick().await;
+
+        let snapshot = PoolTopology::capture(&state, now_millis());
+        let payload = match serde_json::to_vec(&snapshot) {
+            Ok(payload) => payload,
+            Err(e) => {
+                warn!(error = %e, "Failed to serialize topology snapshot");
+                continue;
+            }
+        };
+
+        if let Err(e) = nats.client().publish(SUBJECT, payload.clone().into()).await {
+            debug!(error = %e, "Topology publish failed");
+        }
+
+        if store.is_none() {
+            match kv::open_expiring_bucket(nats.jetstream(), buckets::TOPOLOGY, "Gateway pool topology", TTL).await {
+                Ok(opened) => store = Some(opened),
+                Err(e) => debug!(error = %e, "Topology bucket unavailable"),
+            }
+        }
+        if let Some(ref kv) = store {
+            if let Err(e) = kv.put(key.as_str(), payload.into()).await {
+                debug!(error = %e, "Topology KV write failed");
+            }
+        }
+    }
+}
+
+/// Every pool document in the `gateway_topology` bucket
+pub async fn list(nats: &NatsPublisher) -> Result<Vec<PoolTopology>, GatewayError> {
+    let kv_error = |e: Box<dyn std::error::Error + Send + Sync>| GatewayError::NatsKvFailed {
+        bucket: buckets::TOPOLOGY.to_string(),
+        source: e,
+    };
+
+    let store = nats
+        .jetstream()
+        .get_key_value(buckets::TOPOLOGY)
+        .await
+        .map_err(|e| kv_error(Box::new(e)))?;
+    let mut keys = store.keys().await.map_err(|e| kv_error(Box::new(e)))?;
+    let mut pools = Vec::new();
+    while let Some(key) = keys.next().await {
+        let key = key.map_err(|e| kv_error(Box::new(e)))?;
+        if let Some(value) = store.get(&key).await.map_err(|e| kv_error(Box::new(e)))? {
+            pools.push(serde_json::from_slice(&value).map_err(|e| kv_error(Box::new(e)))?);
+        }
+    }
+    Ok(pools)
+}
+
+/// Remove this pool's entry on graceful shutdown, so workers stop routing to it
+pub async fn withdraw(nats: &NatsPublisher, pool_id: u64) -> Result<(), GatewayError> {
+    let kv_error = |e: Box<dyn std::error::Error + Send + Sync>| GatewayError::NatsKvFailed {
+        bucket: buckets::TOPOLOGY.to_string(),
+        source: e,
+    };
+
+    let store = nats
+        .jetstream()
+        .get_key_value(buckets::TOPOLOGY)
+        .await
+        .map_err(|e| kv_error(Box::new(e)))?;
+    store.delete(key(pool_id)).await.map_err(|e| kv_error(Box::new(e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../packages/shared/nats-schemas/fixtures/gateway-topology.json"
+    );
+
+    #[test]
+    fn fixture_round_trips() {
+        let fixture: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(FIXTURE).expect("Failed to read gateway-topology.json"))
+                .unwrap();
+
+        let topology: PoolTopology = serde_json::from_value(fixture.clone()).expect("fixture matches PoolTopology");
+        assert_eq!(serde_json::to_value(&topology).unwrap(), fixture);
+        assert_eq!(topology.guilds_total, topology.shards.iter().map(|s| s.guilds).sum::<u64>());
+    }
+
+    fn pool(pool_id: u64, shards: &[(u64, &str)], timestamp: u64) -> PoolTopology {
+        PoolTopology {
+            pool_id,
+            total_shards: 4,
+            shards_per_pool: 2,
+            version: "0.2.0".to_string(),
+            discord_api_version: 10,
+            discord_api_mode: "pinned".to_string(),
+            guilds_total: shards.len() as u64 * 10,
+            shards: shards
+                .iter()
+                .map(|&(shard_id, health)| ShardTopology { shard_id, health: health.to_string(), guilds: 10 })
+                .collect(),
+            timestamp,
+        }
+    }
+
+    #[test]
+    fn cluster_report_sums_pools() {
+        let pools = [pool(1, &[(2, "ready"), (1, "resuming")], 60_000), pool(0, &[(0, "ready"), (1, "ready")], 60_000)];
+        let report = ClusterReport::aggregate(&pools, 70_000, Duration::from_secs(90));
+
+        assert_eq!((report.total_shards, report.shards_per_pool), (Some(4), Some(2)));
+        assert_eq!((report.shards_running, report.shards_ready, report.guilds_total), (3, 3, 40));
+        assert_eq!(report.shards_by_health["ready"], 3);
+        assert_eq!(report.missing_shards, vec![3]);
+        assert_eq!(report.duplicate_shards, vec![1]);
+        assert_eq!(report.pools.iter().map(|pool| pool.pool_id).collect::<Vec<_>>(), vec![0, 1]);
+        assert_eq!(report.pools[0].snapshot_age_secs, 10);
+        assert!(report.warnings.is_empty());
+    }
+
+    #[test]
+    fn cluster_report_warns_about_disagreements() {
+        let mut resharded = pool(1, &[(2, "ready")], 0);
+        resharded.total_shards = 8;
+        resharded.version = "0.3.0".to_string();
+        let report = ClusterReport::aggregate(&[pool(0, &[(0, "ready")], 200_000), resharded], 200_000, Duration::from_secs(90));
+
+        assert_eq!(report.total_shards, None);
+        assert!(report.missing_shards.is_empty(), "no shard count to compare against");
+        assert_eq!(report.versions["0.3.0"], vec![1]);
+        assert_eq!(
+            report.warnings,
+            vec![
+                "pools disagree on total_shards: {4, 8}".to_string(),
+                "pool 1 has not published for 200s".to_string(),
+                "pools run 2 gateway versions".to_string(),
+            ]
+        );
+    }
+}
@@ -0,0 +1,18 @@
+//! `GET /debug/config`, served next to the health endpoints when
+//! `DYNAMIC_CONFIG_ENABLED` is set: the overrides in force and the most
+//! recent changes, newest first.
+
+use super::{Overrides, OverridesReport};
+use axum::extract::State;
+use axum::routing::get;
+use axum::{Json, Router};
+use std::sync::Arc;
+
+/// Override report
+pub fn router(overrides: Arc<Overrides>) -> Router {
+    Router::new().route("/debug/config", get(config_handler)).with_state(overrides)
+}
+
+async fn config_handler(State(overrides): State<Arc<Overrides>>) -> Json<OverridesReport> {
+    Json(overrides.report())
+}
@@ -0,0 +1,396 @@
+//! Runtime configuration overrides from NATS KV
+//!
+//! With `DYNAMIC_CONFIG_ENABLED`, every pool watches the `gateway_config` KV
+//! bucket and applies these keys without a restart:
+//!
+//! - `log_level`: level for the gateway's own logs (`debug`), or a full
+//!   filter directive list (`arrakis_gateway=debug,async_nats=info`)
+//! - `event_filter`: comma-separated event types or families (`message.*`)
+//!   that are not published
+//! - `event_sampling`: same syntax as `EVENT_SAMPLING`, replacing it
+//! - `circuit_breaker_max_errors`: consecutive receive errors before a
+//!   shard's circuit opens, replacing `CIRCUIT_BREAKER_MAX_ERRORS`
+//!
+//! Deleting a key restores the value the pool started with. A value that
+//! doesn't parse is rejected and the previous one stays. Every change,
+//! applied or rejected, is logged and kept in a short audit trail served at
+//! `GET /debug/config`.
+
+pub mod http;
+
+use crate::error::GatewayError;
+use crate::events::sampling::EventSampling;
+use crate::events::serialize::now_millis;
+use crate::metrics::GatewayMetrics;
+use crate::nats::kv::{self, buckets};
+use crate::nats::NatsPublisher;
+use async_nats::jetstream::kv::{Entry, Operation, Watch};
+use futures_util::StreamExt as _;
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// Override keys
+pub mod keys {
+    pub const LOG_LEVEL: &str = "log_level";
+    pub const EVENT_FILTER: &str = "event_filter";
+    pub const EVENT_SAMPLING: &str = "event_sampling";
+    pub const CIRCUIT_BREAKER_MAX_ERRORS: &str = "circuit_breaker_max_errors";
+
+    pub const ALL: &[&str] = &[LOG_LEVEL, EVENT_FILTER, EVENT_SAMPLING, CIRCUIT_BREAKER_MAX_ERRORS];
+}
+
+/// Consecutive receive errors before a shard's circuit opens, unless
+/// configured or overridden
+pub const DEFAULT_MAX_CONSECUTIVE_ERRORS: u32 = 10;
+
+/// Audit entries kept for `/debug/config`
+const AUDIT_CAPACITY: usize = 50;
+
+/// Wait before reopening a watch that failed or ended
+const REWATCH_DELAY: Duration = Duration::from_secs(5);
+
+/// Replaces the log filter; given the `log_level` value
+pub type LogReload = Box<dyn Fn(&str) -> Result<(), String> + Send + Sync>;
+
+/// One change seen in the bucket
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AuditEntry {
+    pub key: String,
+    /// None when the key was deleted
+    pub value: Option<String>,
+    pub previous: Option<String>,
+    /// KV revision of the change
+    pub revision: u64,
+    /// `applied`, `reset` or `rejected`
+    pub outcome: &'static str,
+    pub error: Option<String>,
+    /// Unix millis
+    pub at: u64,
+}
+
+/// Current overrides and recent changes, for `/debug/config`
+#[derive(Debug, Clone, Serialize)]
+pub struct OverridesReport {
+    pub bucket: &'static str,
+    pub active: BTreeMap<String, String>,
+    pub audit: Vec<AuditEntry>,
+}
+
+/// Settings the hot path reads, replaceable at runtime
+pub struct Overrides {
+    startup_sampling: Arc<EventSampling>,
+    sampling: RwLock<Arc<EventSampling>>,
+    /// Event types never published; rules are all `off`
+    filter: RwLock<Arc<EventSampling>>,
+    max_consecutive_errors: AtomicU32,
+    startup_max_consecutive_errors: u32,
+    startup_log_level: String,
+    log_reload: Option<LogReload>,
+    active: Mutex<BTreeMap<String, String>>,
+    audit: Mutex<VecDeque<AuditEntry>>,
+}
+
+impl std::fmt::Debug for Overrides {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Overrides")
+            .field("active", &self.active)
+            .field("log_reload", &self.log_reload.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for Overrides {
+    fn default() -> Self {
+        Self::new(EventSampling::default(), "info")
+    }
+}
+
+impl Overrides {
+    /// Start from the pool's configured values
+    pub fn new(sampling: EventSampling, log_level: impl Into<String>) -> Self {
+        let sampling = Arc::new(sampling);
+        Self {
+            startup_sampling: Arc::clone(&sampling),
+            sampling: RwLock::new(sampling),
+            filter: RwLock::default(),
+            max_consecutive_errors: AtomicU32::new(DEFAULT_MAX_CONSECUTIVE_ERRORS),
+            startup_max_consecutive_errors: DEFAULT_MAX_CONSECUTIVE_ERRORS,
+            startup_log_level: log_level.into(),
+            log_reload: None,
+            active: Mutex::default(),
+            audit: Mutex::default(),
+        }
+    }
+
+    /// Start from a configured circuit breaker threshold
+    pub fn with_max_consecutive_errors(mut self, max: u32) -> Self {
+        self.max_consecutive_errors = AtomicU32::new(max);
+        self.startup_max_consecutive_errors = max;
+        self
+    }
+
+    /// Let `log_level` overrides replace the log filter
+    pub fn with_log_reload(mut self, reload: LogReload) -> Self {
+        self.log_reload = Some(reload);
+        self
+    }
+
+    /// Whether to publish this event: not filtered, and inside its sample
+    pub fn admits(&self, event_type: &str) -> bool {
+        let filtered = self.filter.read().unwrap_or_else(|e| e.into_inner()).percent(event_type) <= 0.0;
+        !filtered && self.sampling.read().unwrap_or_else(|e| e.into_inner()).admits(event_type)
+    }
+
+    pub fn max_consecutive_errors(&self) -> u32 {
+        self.max_consecutive_errors.load(Ordering::Relaxed)
+    }
+
+    pub fn report(&self) -> OverridesReport {
+        OverridesReport {
+            bucket: buckets::CONFIG,
+            active: self.active.lock().unwrap_or_else(|e| e.into_inner()).clone(),
+            audit: self.audit.lock().unwrap_or_else(|e| e.into_inner()).iter().rev().cloned().collect(),
+        }
+    }
+
+    /// Apply a bucket change: `Some` sets the key, `None` restores the
+    /// startup value. Rejected values leave the current setting in place.
+    pub fn apply(&self, key: &str, value: Option<&str>, revision: u64) -> AuditEntry {
+        let previous = self.active.lock().unwrap_or_else(|e| e.into_inner()).get(key).cloned();
+        let result = match value {
+            Some(value) => self.set(key, value.trim()),
+            None => self.reset(key),
+        };
+
+        let entry = AuditEntry {
+            key: key.to_string(),
+            value: value.map(str::to_string),
+            previous,
+            revision,
+            outcome: match (&result, value) {
+                (Err(_), _) => "rejected",
+                (Ok(()), Some(_)) => "applied",
+                (Ok(()), None) => "reset",
+            },
+            error: result.err(),
+            at: now_millis(),
+        };
+
+        match &entry.error {
+            Some(error) => warn!(
+                key,
+                value = ?entry.value,
+                revision,
+                error = %error,
+                "Config override rejected"
+            ),
+            None => {
+                let mut active = self.active.lock().unwrap_or_else(|e| e.into_inner());
+                match value {
+                    Some(value) => active.insert(key.to_string(), value.to_string()),
+                    None => active.remove(key),
+                };
+                info!(
+                    key,
+                    value = ?entry.value,
+                    previous = ?entry.previous,
+                    revision,
+                    "Config override applied"
+                );
+            }
+        }
+
+        let mut audit = self.audit.lock().unwrap_or_else(|e| e.into_inner());
+        if audit.len() == AUDIT_CAPACITY {
+            audit.pop_front();
+        }
+        audit.push_back(entry.clone());
+        entry
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            keys::LOG_LEVEL => self.reload_log(value),
+            keys::EVENT_FILTER => {
+                let filter = parse_filter(value).map_err(|e| e.to_string())?;
+                *self.filter.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(filter);
+                Ok(())
+            }
+            keys::EVENT_SAMPLING => {
+                let sampling = EventSampling::parse(value).map_err(|e| e.to_string())?;
+                *self.sampling.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(sampling);
+                Ok(())
+            }
+            keys::CIRCUIT_BREAKER_MAX_ERRORS => {
+                let max = value
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|&max| max > 0)
+                    .ok_or_else(|| format!("{key} must be a positive integer, got {value:?}"))?;
+                self.max_consecutive_errors.store(max, Ordering::Relaxed);
+                Ok(())
+            }
+            _ => Err(format!("unknown key; expected one of {}", keys::ALL.join(", "))),
+        }
+    }
+
+    fn reset(&self, key: &str) -> Result<(), String> {
+        match key {
+            keys::LOG_LEVEL => self.reload_log(&self.startup_log_level),
+            keys::EVENT_FILTER => {
+                *self.filter.write().unwrap_or_else(|e| e.into_inner()) = Arc::default();
+                Ok(())
+            }
+            keys::EVENT_SAMPLING => {
+                *self.sampling.write().unwrap_or_else(|e| e.into_inner()) = Arc::clone(&self.startup_sampling);
+                Ok(())
+            }
+            keys::CIRCUIT_BREAKER_MAX_ERRORS => {
+                self.max_consecutive_errors.store(self.startup_max_consecutive_errors, Ordering::Relaxed);
+                Ok(())
+            }
+            _ => Err(format!("unknown key; expected one of {}", keys::ALL.join(", "))),
+        }
+    }
+
+    fn reload_log(&self, level: &str) -> Result<(), String> {
+        match self.log_reload {
+            Some(ref reload) => reload(level),
+            None => Err("log level can't be changed at runtime in this process".to_string()),
+        }
+    }
+}
+
+/// `event_filter`: event types or families, e.g. `presence.update,message.*`
+fn parse_filter(value: &str) -> Result<EventSampling, GatewayError> {
+    let rules: Vec<String> = value
+        .split(',')
+        .map(str::trim)
+        .filter(|event_type| !event_type.is_empty())
+        .map(|event_type| format!("{event_type}=off"))
+        .collect();
+    EventSampling::parse(&rules.join(","))
+}
+
+/// Apply bucket changes until the process exits
+pub async fn run_watcher(nats: Arc<NatsPublisher>, overrides: Arc<Overrides>, metrics: Arc<GatewayMetrics>) {
+    info!(bucket = buckets::CONFIG, "Watching for config overrides");
+
+    loop {
+        let Some(mut watch) = open_watch(&nats).await else {
+            tokio::time::sleep(REWATCH_DELAY).await;
+            continue;
+        };
+        while let Some(entry) = watch.next().await {
+            match entry {
+                Ok(entry) => {
+                    let audit = apply_entry(&overrides, &entry);
+                    metrics.record_config_override(&audit.key, audit.outcome);
+                }
+                Err(e) => debug!(error = %e, "Config override watch error"),
+            }
+        }
+        debug!("Config override watch ended");
+        tokio::time::sleep(REWATCH_DELAY).await;
+    }
+}
+
+async fn open_watch(nats: &NatsPublisher) -> Option<Watch> {
+    let store = kv::open_bucket(nats.jetstream(), buckets::CONFIG, "Runtime config overrides")
+        .await
+        .map_err(|e| warn!(error = %e, "Config override bucket unavailable"))
+        .ok()?;
+    // History first, so a restarted pool picks up the overrides in force
+    store
+        .watch_with_history(">")
+        .await
+        .map_err(|e| warn!(error = %e, "Config override watch failed"))
+        .ok()
+}
+
+fn apply_entry(overrides: &Overrides, entry: &Entry) -> AuditEntry {
+    match entry.operation {
+        Operation::Put => {
+            let value = String::from_utf8_lossy(&entry.value);
+            overrides.apply(&entry.key, Some(&value), entry.revision)
+        }
+        Operation::Delete | Operation::Purge => overrides.apply(&entry.key, None, entry.revision),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filter_and_sampling_changes_apply_and_reset() {
+        let overrides = Overrides::new(EventSampling::parse("member.update=off").unwrap(), "info");
+        assert!(!overrides.admits("member.update"));
+        assert!(overrides.admits("message.create"));
+
+        overrides.apply(keys::EVENT_FILTER, Some("message.*, guild.leave"), 1);
+        overrides.apply(keys::EVENT_SAMPLING, Some(""), 2);
+        assert!(!overrides.admits("message.create"));
+        assert!(!overrides.admits("guild.leave"));
+        assert!(overrides.admits("member.update"), "the override replaces EVENT_SAMPLING");
+
+        overrides.apply(keys::EVENT_FILTER, None, 3);
+        overrides.apply(keys::EVENT_SAMPLING, None, 4);
+        assert!(overrides.admits("message.create"));
+        assert!(!overrides.admits("member.update"), "deleting the key restores EVENT_SAMPLING");
+        assert!(overrides.report().active.is_empty());
+    }
+
+    #[test]
+    fn rejected_values_keep_the_current_setting() {
+        let overrides = Overrides::new(EventSampling::default(), "info");
+        assert_eq!(overrides.apply(keys::CIRCUIT_BREAKER_MAX_ERRORS, Some("25"), 1).outcome, "applied");
+        assert_eq!(overrides.max_consecutive_errors(), 25);
+
+        for (key, value) in [
+            (keys::CIRCUIT_BREAKER_MAX_ERRORS, "0"),
+            (keys::EVENT_FILTER, "interaction.create"),
+            (keys::LOG_LEVEL, "debug"),
+            ("shard_count", "4"),
+        ] {
+            let entry = overrides.apply(key, Some(value), 2);
+            assert_eq!(entry.outcome, "rejected", "{key}={value}");
+            assert!(entry.error.is_some());
+        }
+        assert_eq!(overrides.max_consecutive_errors(), 25);
+
+        let report = overrides.report();
+        assert_eq!(report.active.get(keys::CIRCUIT_BREAKER_MAX_ERRORS).map(String::as_str), Some("25"));
+        assert_eq!(report.audit.len(), 5);
+        assert_eq!(report.audit[0].key, "shard_count", "newest first");
+    }
+
+    #[test]
+    fn breaker_threshold_resets_to_the_configured_value() {
+        let overrides = Overrides::new(EventSampling::default(), "info").with_max_consecutive_errors(4);
+        assert_eq!(overrides.max_consecutive_errors(), 4);
+        overrides.apply(keys::CIRCUIT_BREAKER_MAX_ERRORS, Some("25"), 1);
+        overrides.apply(keys::CIRCUIT_BREAKER_MAX_ERRORS, None, 2);
+        assert_eq!(overrides.max_consecutive_errors(), 4, "deleting the key restores CIRCUIT_BREAKER_MAX_ERRORS");
+    }
+
+    #[test]
+    fn log_level_goes_through_the_reload_hook() {
+        let levels = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&levels);
+        let overrides = Overrides::new(EventSampling::default(), "info").with_log_reload(Box::new(move |level| {
+            seen.lock().unwrap().push(level.to_string());
+            Ok(())
+        }));
+
+        overrides.apply(keys::LOG_LEVEL, Some("debug"), 1);
+        let entry = overrides.apply(keys::LOG_LEVEL, None, 2);
+        assert_eq!(entry.outcome, "reset");
+        assert_eq!(entry.previous.as_deref(), Some("debug"));
+        assert_eq!(*levels.lock().unwrap(), ["debug", "info"]);
+    }
+}
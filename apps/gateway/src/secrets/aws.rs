@@ -0,0 +1,299 @@
+//! AWS Secrets Manager reads (`GetSecretValue`), signed with SigV4
+//!
+//! Credentials come from `AWS_ACCESS_KEY_ID` / `AWS_SECRET_ACCESS_KEY` /
+//! `AWS_SESSION_TOKEN`, else from the pod's IAM role (EKS IRSA: the token in
+//! `AWS_WEB_IDENTITY_TOKEN_FILE` exchanged for `AWS_ROLE_ARN`'s credentials
+//! with STS `AssumeRoleWithWebIdentity`), else from the container credentials
+//! endpoint (EKS Pod Identity, ECS task roles). The region is taken from an
+//! ARN secret id, else `AWS_REGION` or `AWS_DEFAULT_REGION`.
+
+use crate::config::var;
+use crate::hex;
+use crate::http_client::client;
+use chrono::{DateTime, Utc};
+use ring::{digest, hmac};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SERVICE: &str = "secretsmanager";
+const TARGET: &str = "secretsmanager.GetSecretValue";
+const CONTENT_TYPE: &str = "application/x-amz-json-1.1";
+/// Base for `AWS_CONTAINER_CREDENTIALS_RELATIVE_URI` (ECS)
+const ECS_CREDENTIALS_HOST: &str = "http://169.254.170.2";
+/// `RoleSessionName` when `AWS_ROLE_SESSION_NAME` is not set
+const ROLE_SESSION_NAME: &str = "arrakis-gateway";
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Credentials {
+    access_key_id: String,
+    secret_access_key: String,
+    /// `Token` from the container endpoint, `SessionToken` from STS
+    #[serde(default, alias = "SessionToken")]
+    token: Option<String>,
+}
+
+/// What SigV4 signs of a request to `/`
+struct Canonical<'a> {
+    method: &'a str,
+    /// Already in canonical form: sorted and URI-encoded
+    query: &'a str,
+    /// Lowercase names, including `host` and `x-amz-date`
+    headers: Vec<(&'a str, &'a str)>,
+    body: &'a [u8],
+}
+
+/// Read a secret's string, or one field of it when it holds JSON
+pub(super) async fn read(secret_id: &str, field: Option<&str>) -> Result<String, String> {
+    let region = region(secret_id)?;
+    let credentials = credentials(&region).await?;
+    let host = format!("{SERVICE}.{region}.amazonaws.com");
+    let body = json!({ "SecretId": secret_id }).to_string();
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let amz_date = DateTime::<Utc>::from_timestamp(secs as i64, 0)
+        .unwrap_or_default()
+        .format("%Y%m%dT%H%M%SZ")
+        .to_string();
+
+    let mut headers = vec![
+        ("content-type", CONTENT_TYPE),
+        ("host", host.as_str()),
+        ("x-amz-date", amz_date.as_str()),
+        ("x-amz-target", TARGET),
+    ];
+    if let Some(ref token) = credentials.token {
+        headers.push(("x-amz-security-token", token));
+    }
+    let signed = Canonical { method: "POST", query: "", headers: headers.clone(), body: body.as_bytes() };
+    let mut request = client()
+        .post(format!("https://{host}/"))
+        .header("authorization", authorization(&credentials, &region, SERVICE, &amz_date, signed));
+    // reqwest sets `host` itself
+    for (name, value) in headers.into_iter().filter(|(name, _)| *name != "host") {
+        request = request.header(name, value);
+    }
+    let response: Value = request
+        .body(body)
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(|e| e.to_string())?
+        .json()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let secret = response
+        .get("SecretString")
+        .and_then(Value::as_str)
+        .ok_or_else(|| format!("{secret_id} has no SecretString"))?;
+    let Some(field) = field else {
+        return Ok(secret.to_string());
+    };
+    serde_json::from_str::<Value>(secret)
+        .ok()
+        .and_then(|json| json.get(field)?.as_str().map(str::to_string))
+        .ok_or_else(|| format!("{secret_id} is not JSON with a string field {field:?}"))
+}
+
+/// `arn:aws:secretsmanager:<region>:...` names its region
+fn region(secret_id: &str) -> Result<String, String> {
+    if let Some(region) = secret_id.strip_prefix("arn:").and_then(|arn| arn.split(':').nth(2)) {
+        return Ok(region.to_string());
+    }
+    var("AWS_REGION")
+        .or_else(|_| var("AWS_DEFAULT_REGION"))
+        .map_err(|_| "set AWS_REGION or use the secret's ARN".to_string())
+}
+
+async fn credentials(region: &str) -> Result<Credentials, String> {
+    if let (Ok(access_key_id), Ok(secret_access_key)) = (var("AWS_ACCESS_KEY_ID"), var("AWS_SECRET_ACCESS_KEY")) {
+        return Ok(Credentials {
+            access_key_id,
+            secret_access_key,
+            token: var("AWS_SESSION_TOKEN").ok(),
+        });
+    }
+    if let Ok(token_file) = var("AWS_WEB_IDENTITY_TOKEN_FILE") {
+        return web_identity(&token_file, region).await;
+    }
+
+    let uri = match (var("AWS_CONTAINER_CREDENTIALS_FULL_URI"), var("AWS_CONTAINER_CREDENTIALS_RELATIVE_URI")) {
+        (Ok(uri), _) => uri,
+        (_, Ok(path)) => format!("{ECS_CREDENTIALS_HOST}{path}"),
+        _ => {
+            return Err(
+                "no AWS credentials (AWS_ACCESS_KEY_ID, AWS_WEB_IDENTITY_TOKEN_FILE or a container credentials endpoint)"
+                    .to_string(),
+            )
+        }
+    };
+    let authorization = match var("AWS_CONTAINER_AUTHORIZATION_TOKEN_FILE") {
+        Ok(path) => Some(tokio::fs::read_to_string(&path).await.map_err(|e| format!("{path}: {e}"))?),
+        Err(_) => var("AWS_CONTAINER_AUTHORIZATION_TOKEN").ok(),
+    };
+
+    let mut request = client().get(uri);
+    if let Some(authorization) = authorization {
+        request = request.header("authorization", authorization.trim());
+    }
+    request
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(|e| format!("container credentials: {e}"))?
+        .json()
+        .await
+        .map_err(|e| format!("container credentials: {e}"))
+}
+
+/// Exchange the service account token EKS projects for the role's
+/// temporary credentials (IRSA). The call itself is not signed.
+async fn web_identity(token_file: &str, region: &str) -> Result<Credentials, String> {
+    let role_arn = var("AWS_ROLE_ARN").map_err(|_| "AWS_WEB_IDENTITY_TOKEN_FILE is set without AWS_ROLE_ARN".to_string())?;
+    let session_name = var("AWS_ROLE_SESSION_NAME").unwrap_or_else(|_| ROLE_SESSION_NAME.to_string());
+    let token = tokio::fs::read_to_string(token_file).await.map_err(|e| format!("{token_file}: {e}"))?;
+
+    let response: Value = client()
+        .post(format!("https://sts.{region}.amazonaws.com/"))
+        .header("accept", "application/json")
+        .form(&[
+            ("Action", "AssumeRoleWithWebIdentity"),
+            ("Version", "2011-06-15"),
+            ("RoleArn", role_arn.as_str()),
+            ("RoleSessionName", session_name.as_str()),
+            ("WebIdentityToken", token.trim()),
+        ])
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(|e| format!("AssumeRoleWithWebIdentity: {e}"))?
+        .json()
+        .await
+        .map_err(|e| format!("AssumeRoleWithWebIdentity: {e}"))?;
+    assumed_role(&response).ok_or_else(|| "AssumeRoleWithWebIdentity returned no credentials".to_string())
+}
+
+/// The credentials in an `AssumeRoleWithWebIdentity` JSON response
+fn assumed_role(response: &Value) -> Option<Credentials> {
+    let credentials = response.pointer("/AssumeRoleWithWebIdentityResponse/AssumeRoleWithWebIdentityResult/Credentials")?;
+    serde_json::from_value(credentials.clone()).ok()
+}
+
+/// SigV4 `Authorization` header
+fn authorization(credentials: &Credentials, region: &str, service: &str, amz_date: &str, request: Canonical) -> String {
+    let date = &amz_date[..8];
+    let mut headers = request.headers;
+    headers.sort_unstable();
+
+    let canonical_headers: String = headers.iter().map(|(name, value)| format!("{name}:{}\n", value.trim())).collect();
+    let signed_headers = headers.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(";");
+    let canonical_request = format!(
+        "{}\n/\n{}\n{canonical_headers}\n{signed_headers}\n{}",
+        request.method,
+        request.query,
+        sha256_hex(request.body)
+    );
+
+    let scope = format!("{date}/{region}/{service}/aws4_request");
+    let string_to_sign = format!("AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}", sha256_hex(canonical_request.as_bytes()));
+    let key = signing_key(&credentials.secret_access_key, date, region, service);
+    let signature = hex::encode(hmac::sign(&hmac::Key::new(hmac::HMAC_SHA256, &key), string_to_sign.as_bytes()).as_ref());
+
+    format!(
+        "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={signed_headers}, Signature={signature}",
+        credentials.access_key_id
+    )
+}
+
+/// `kSigning` from the secret key, date, region and service
+fn signing_key(secret: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    [date, region, service, "aws4_request"]
+        .iter()
+        .fold(format!("AWS4{secret}").into_bytes(), |key, part| {
+            hmac::sign(&hmac::Key::new(hmac::HMAC_SHA256, &key), part.as_bytes()).as_ref().to_vec()
+        })
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(digest::digest(&digest::SHA256, data).as_ref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> Credentials {
+        Credentials {
+            access_key_id: "AKIDEXAMPLE".to_string(),
+            secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_string(),
+            token: None,
+        }
+    }
+
+    #[test]
+    fn signing_key_matches_the_sigv4_reference() {
+        // From the AWS SigV4 documentation
+        let key = signing_key("wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY", "20120215", "us-east-1", "iam");
+        assert_eq!(hex::encode(&key), "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d");
+    }
+
+    #[test]
+    fn authorization_matches_the_sigv4_reference() {
+        // The IAM ListUsers example of the AWS SigV4 documentation
+        let request = Canonical {
+            method: "GET",
+            query: "Action=ListUsers&Version=2010-05-08",
+            headers: vec![
+                ("x-amz-date", "20150830T123600Z"),
+                ("host", "iam.amazonaws.com"),
+                ("content-type", "application/x-www-form-urlencoded; charset=utf-8"),
+            ],
+            body: b"",
+        };
+        assert_eq!(
+            authorization(&example(), "us-east-1", "iam", "20150830T123600Z", request),
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/iam/aws4_request, \
+             SignedHeaders=content-type;host;x-amz-date, \
+             Signature=5d672d79c15b13162d9279b0855cfba6789a8edb4c82c400e06b5924a6f2b5d7"
+        );
+    }
+
+    #[test]
+    fn authorization_signs_the_session_token() {
+        let request = Canonical {
+            method: "POST",
+            query: "",
+            headers: vec![
+                ("content-type", CONTENT_TYPE),
+                ("host", "secretsmanager.eu-west-1.amazonaws.com"),
+                ("x-amz-date", "20260101T000000Z"),
+                ("x-amz-target", TARGET),
+                ("x-amz-security-token", "session"),
+            ],
+            body: br#"{"SecretId":"arrakis/gateway"}"#,
+        };
+        let credentials = Credentials { token: Some("session".to_string()), ..example() };
+        let header = authorization(&credentials, "eu-west-1", SERVICE, "20260101T000000Z", request);
+
+        assert!(header.starts_with("AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20260101/eu-west-1/secretsmanager/aws4_request, "));
+        assert!(header.contains("SignedHeaders=content-type;host;x-amz-date;x-amz-security-token;x-amz-target, "));
+    }
+
+    #[test]
+    fn web_identity_credentials_are_read_from_sts() {
+        let response = json!({ "AssumeRoleWithWebIdentityResponse": { "AssumeRoleWithWebIdentityResult": {
+            "Credentials": { "AccessKeyId": "ASIAEXAMPLE", "SecretAccessKey": "secret", "SessionToken": "session",
+                             "Expiration": 1.7e9 } } } });
+        let credentials = assumed_role(&response).unwrap();
+        assert_eq!(credentials.access_key_id, "ASIAEXAMPLE");
+        assert_eq!(credentials.token.as_deref(), Some("session"));
+        assert!(assumed_role(&json!({ "Error": { "Code": "AccessDenied" } })).is_none());
+    }
+
+    #[test]
+    fn region_comes_from_the_arn() {
+        let arn = "arn:aws:secretsmanager:eu-west-1:123456789012:secret:arrakis/gateway-AbCdEf";
+        assert_eq!(region(arn).unwrap(), "eu-west-1");
+    }
+}
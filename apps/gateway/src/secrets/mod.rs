@@ -0,0 +1,218 @@
+//! Secrets kept out of the pod environment
+//!
+//! A secret setting such as `DISCORD_TOKEN` can be given as:
+//! - `<NAME>_FILE=/run/secrets/...`: read from a file (a mounted Kubernetes
+//!   secret, or one synced by the Secrets Store CSI driver)
+//! - `<NAME>=vault:<path>#<field>`: read from HashiCorp Vault (`vault`)
+//! - `<NAME>=aws-sm:<secret id>[#<field>]`: read from AWS Secrets Manager (`aws`)
+//! - `<NAME>=<value>`: the value itself
+//!
+//! Everything but the value itself is read again every `SECRETS_REFRESH_SECS`;
+//! a changed Discord token is rolled through the pool (`shard::token`).
+
+mod aws;
+mod vault;
+
+use crate::config::var;
+use crate::error::GatewayError;
+use crate::metrics::GatewayMetrics;
+use crate::shard::token::{Rejected, TokenRotation};
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+/// Where a secret setting is read from
+#[derive(Clone, PartialEq, Eq)]
+pub enum SecretSource {
+    /// Given directly in the environment or config file
+    Inline(String),
+    File(PathBuf),
+    Vault { path: String, field: String },
+    AwsSecretsManager { secret_id: String, field: Option<String> },
+}
+
+impl SecretSource {
+    /// Where `name` comes from: `<name>_FILE`, else `<name>` (a reference
+    /// or the secret itself); None if neither is set
+    pub fn lookup(name: &str) -> Result<Option<Self>, GatewayError> {
+        if let Some(path) = var(&format!("{name}_FILE")).ok().filter(|path| !path.trim().is_empty()) {
+            return Ok(Some(Self::File(PathBuf::from(path.trim()))));
+        }
+        match var(name) {
+            Ok(value) if !value.is_empty() => Self::parse(name, &value).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    fn parse(name: &str, value: &str) -> Result<Self, GatewayError> {
+        if let Some(reference) = value.strip_prefix("vault:") {
+            let (path, field) = reference
+                .split_once('#')
+                .filter(|(path, field)| !path.is_empty() && !field.is_empty())
+                .ok_or_else(|| GatewayError::Config(format!("{name} must be vault:<path>#<field>")))?;
+            return Ok(Self::Vault {
+                path: path.trim_matches('/').to_string(),
+                field: field.to_string(),
+            });
+        }
+        if let Some(reference) = value.strip_prefix("aws-sm:") {
+            let (secret_id, field) = match reference.split_once('#') {
+                Some((secret_id, field)) => (secret_id, Some(field.to_string()).filter(|f| !f.is_empty())),
+                None => (reference, None),
+            };
+            if secret_id.is_empty() {
+                return Err(GatewayError::Config(format!("{name} must be aws-sm:<secret id>[#<field>]")));
+            }
+            return Ok(Self::AwsSecretsManager { secret_id: secret_id.to_string(), field });
+        }
+        Ok(Self::Inline(value.to_string()))
+    }
+
+    /// Provider name, for logs and metrics
+    pub const fn provider(&self) -> &'static str {
+        match self {
+            Self::Inline(_) => "environment",
+            Self::File(_) => "file",
+            Self::Vault { .. } => "vault",
+            Self::AwsSecretsManager { .. } => "aws-sm",
+        }
+    }
+
+    /// Whether reading again can return a new value
+    pub const fn refreshes(&self) -> bool {
+        !matches!(self, Self::Inline(_))
+    }
+
+    /// Read the secret; surrounding whitespace is trimmed
+    pub async fn read(&self, name: &str) -> Result<String, GatewayError> {
+        let value = match self {
+            Self::Inline(value) => Ok(value.clone()),
+            Self::File(path) => tokio::fs::read_to_string(path).await.map_err(|e| format!("{}: {e}", path.display())),
+            Self::Vault { path, field } => vault::read(path, field).await,
+            Self::AwsSecretsManager { secret_id, field } => aws::read(secret_id, field.as_deref()).await,
+        };
+        let failed = |detail: String| GatewayError::SecretReadFailed {
+            name: name.to_string(),
+            provider: self.provider(),
+            detail,
+        };
+        let value = value.map_err(failed)?;
+        match value.trim() {
+            "" => Err(failed("the secret is empty".to_string())),
+            value => Ok(value.to_string()),
+        }
+    }
+}
+
+/// Never prints an inline secret
+impl fmt::Debug for SecretSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Inline(_) => f.write_str("Inline(<redacted>)"),
+            Self::File(path) => f.debug_tuple("File").field(path).finish(),
+            Self::Vault { path, field } => f.debug_struct("Vault").field("path", path).field("field", field).finish(),
+            Self::AwsSecretsManager { secret_id, field } => f
+                .debug_struct("AwsSecretsManager")
+                .field("secret_id", secret_id)
+                .field("field", field)
+                .finish(),
+        }
+    }
+}
+
+/// Read the Discord token again every `every` and rotate the pool onto it
+/// when it changes
+pub async fn watch_token(
+    source: SecretSource,
+    mut current: String,
+    rotation: Arc<TokenRotation>,
+    metrics: Arc<GatewayMetrics>,
+    every: Duration,
+) {
+    info!(provider = source.provider(), every_secs = every.as_secs(), "Re-reading DISCORD_TOKEN for rotation");
+    // A token Discord refused isn't offered again until the secret changes
+    let mut refused: Option<String> = None;
+    let mut interval = tokio::time::interval(every);
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        let token = match source.read("DISCORD_TOKEN").await {
+            Ok(token) => {
+                metrics.record_secret_read(source.provider(), "ok");
+                token
+            }
+            Err(e) => {
+                metrics.record_secret_read(source.provider(), "failed");
+                warn!(error = %e, "Failed to re-read DISCORD_TOKEN - keeping the current token");
+                continue;
+            }
+        };
+        if token == current || refused.as_ref() == Some(&token) {
+            continue;
+        }
+
+        info!(provider = source.provider(), "DISCORD_TOKEN changed - rotating");
+        match rotation.start(token.clone()).await {
+            Ok(_) => current = token,
+            // Tried again on the next read
+            Err(Rejected::Busy) => {}
+            Err(Rejected::Invalid(reason)) => {
+                error!(reason, "New DISCORD_TOKEN refused - keeping the current token");
+                refused = Some(token);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn references_name_their_provider() {
+        let parse = |value: &str| SecretSource::parse("DISCORD_TOKEN", value);
+
+        assert_eq!(
+            parse("vault:/secret/data/arrakis/gateway#discord_token").unwrap(),
+            SecretSource::Vault {
+                path: "secret/data/arrakis/gateway".to_string(),
+                field: "discord_token".to_string(),
+            }
+        );
+        assert_eq!(
+            parse("aws-sm:arrakis/gateway#discord_token").unwrap(),
+            SecretSource::AwsSecretsManager {
+                secret_id: "arrakis/gateway".to_string(),
+                field: Some("discord_token".to_string()),
+            }
+        );
+        assert_eq!(
+            parse("aws-sm:arrakis/discord-token").unwrap(),
+            SecretSource::AwsSecretsManager { secret_id: "arrakis/discord-token".to_string(), field: None }
+        );
+        assert_eq!(parse("MTIz.abc.def").unwrap(), SecretSource::Inline("MTIz.abc.def".to_string()));
+
+        assert!(parse("vault:secret/data/arrakis/gateway").is_err(), "a Vault reference needs a field");
+        assert!(parse("aws-sm:#discord_token").is_err());
+    }
+
+    #[test]
+    fn inline_secrets_are_redacted() {
+        let debug = format!("{:?}", SecretSource::Inline("MTIz.abc.def".to_string()));
+        assert!(!debug.contains("MTIz"), "{debug}");
+    }
+
+    #[tokio::test]
+    async fn file_secrets_are_trimmed() {
+        let path = std::env::temp_dir().join(format!("gateway-secret-{}", std::process::id()));
+        std::fs::write(&path, "MTIz.abc.def\n").unwrap();
+        let source = SecretSource::File(path.clone());
+        assert_eq!(source.read("DISCORD_TOKEN").await.unwrap(), "MTIz.abc.def");
+
+        std::fs::write(&path, "\n").unwrap();
+        assert!(source.read("DISCORD_TOKEN").await.is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+}
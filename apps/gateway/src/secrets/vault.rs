@@ -0,0 +1,84 @@
+//! HashiCorp Vault KV reads
+//!
+//! `VAULT_ADDR` is the server. The client token is `VAULT_TOKEN`, the
+//! contents of `VAULT_TOKEN_FILE`, or a login with the pod's service account
+//! (`VAULT_K8S_ROLE`, on the `VAULT_K8S_MOUNT` auth mount, default
+//! `kubernetes`). `VAULT_NAMESPACE` is sent when set (Vault Enterprise).
+
+use crate::config::var;
+use crate::http_client::client;
+use serde_json::{json, Value};
+
+/// Where Kubernetes mounts the pod's service account token
+const SERVICE_ACCOUNT_TOKEN: &str = "/var/run/secrets/kubernetes.io/serviceaccount/token";
+
+/// Read `field` of the secret at `path` (KV v1 or v2)
+pub(super) async fn read(path: &str, field: &str) -> Result<String, String> {
+    let addr = var("VAULT_ADDR").map_err(|_| "VAULT_ADDR is not set".to_string())?;
+    let addr = addr.trim_end_matches('/');
+    let token = client_token(addr).await?;
+
+    let body = send(client().get(format!("{addr}/v1/{path}")).header("X-Vault-Token", token)).await?;
+    field_of(&body, field).ok_or_else(|| format!("{path} has no string field {field:?}"))
+}
+
+/// KV v2 nests the secret under `data.data`, v1 has it under `data`
+fn field_of(body: &Value, field: &str) -> Option<String> {
+    let data = body.get("data")?;
+    let value = data.get("data").and_then(|inner| inner.get(field)).or_else(|| data.get(field))?;
+    value.as_str().map(str::to_string)
+}
+
+async fn client_token(addr: &str) -> Result<String, String> {
+    if let Ok(token) = var("VAULT_TOKEN") {
+        return Ok(token);
+    }
+    if let Ok(path) = var("VAULT_TOKEN_FILE") {
+        let token = tokio::fs::read_to_string(&path).await.map_err(|e| format!("VAULT_TOKEN_FILE {path}: {e}"))?;
+        return Ok(token.trim().to_string());
+    }
+    let role = var("VAULT_K8S_ROLE").map_err(|_| "set VAULT_TOKEN, VAULT_TOKEN_FILE or VAULT_K8S_ROLE".to_string())?;
+    let mount = var("VAULT_K8S_MOUNT").unwrap_or_else(|_| "kubernetes".to_string());
+    let jwt = tokio::fs::read_to_string(SERVICE_ACCOUNT_TOKEN)
+        .await
+        .map_err(|e| format!("service account token {SERVICE_ACCOUNT_TOKEN}: {e}"))?;
+
+    let login = client()
+        .post(format!("{addr}/v1/auth/{}/login", mount.trim_matches('/')))
+        .json(&json!({ "role": role, "jwt": jwt.trim() }));
+    let body = send(login).await?;
+    body.pointer("/auth/client_token")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| "Vault login returned no client token".to_string())
+}
+
+async fn send(request: reqwest::RequestBuilder) -> Result<Value, String> {
+    let request = match var("VAULT_NAMESPACE") {
+        Ok(namespace) => request.header("X-Vault-Namespace", namespace),
+        Err(_) => request,
+    };
+    request
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(|e| e.to_string())?
+        .json()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fields_are_read_from_kv_v1_and_v2() {
+        let v2 = json!({ "data": { "data": { "discord_token": "v2" }, "metadata": { "version": 3 } } });
+        let v1 = json!({ "data": { "discord_token": "v1" } });
+
+        assert_eq!(field_of(&v2, "discord_token").as_deref(), Some("v2"));
+        assert_eq!(field_of(&v1, "discord_token").as_deref(), Some("v1"));
+        assert_eq!(field_of(&v2, "nats_password"), None);
+    }
+}
@@ -0,0 +1,196 @@
+//! Identify budget tracking for large bots
+//!
+//! Discord limits how many sessions a bot may start per 24h window
+//! (`session_start_limit`). Every pool draws from the same budget, so the
+//! remaining count is persisted in NATS KV and each pool reserves its identify
+//! burst before connecting. A pool refuses to start a burst that would leave
+//! fewer than the configured reserve: exhausting the budget locks the bot out
+//! of the gateway until the window resets.
+
+use crate::error::GatewayError;
+use crate::nats::kv::{self, buckets};
+use async_nats::jetstream::Context as JsContext;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+use twilight_model::gateway::SessionStartLimit;
+
+/// KV key holding the shared budget
+const BUDGET_KEY: &str = "session_start_limit";
+
+/// Attempts at the KV compare-and-swap before giving up
+const MAX_RESERVE_ATTEMPTS: u32 = 5;
+
+/// Snapshot of the bot's identify budget
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IdentifyBudget {
+    pub total: u32,
+    pub remaining: u32,
+    pub max_concurrency: u16,
+    /// Unix ms when `remaining` resets to `total`
+    pub reset_at: u64,
+}
+
+impl IdentifyBudget {
+    /// Build from Discord's `session_start_limit` as observed at `now_ms`
+    pub fn from_limit(limit: &SessionStartLimit, now_ms: u64) -> Self {
+        Self {
+            total: limit.total,
+            remaining: limit.remaining,
+            max_concurrency: limit.max_concurrency,
+            reset_at: now_ms + limit.reset_after,
+        }
+    }
+
+    /// Combine Discord's view with the persisted one.
+    ///
+    /// Other pools may have reserved identifies Discord has not counted yet,
+    /// so while the persisted window is still open the lower remaining wins.
+    pub fn merge(self, persisted: Option<&IdentifyBudget>, now_ms: u64) -> Self {
+        match persisted {
+            Some(p) if p.reset_at > now_ms && p.remaining < self.remaining => Self {
+                remaining: p.remaining,
+                reset_at: p.reset_at,
+                ..self
+            },
+            _ => self,
+        }
+    }
+
+    /// Check that `identifies` can start while keeping `reserve` in hand
+    pub fn check_burst(&self, identifies: u32, reserve: u32) -> Result<(), GatewayError> {
+        if identifies.saturating_add(reserve) > self.remaining {
+            return Err(GatewayError::IdentifyBudgetExhausted {
+                required: identifies,
+                remaining: self.remaining,
+                reserve,
+            });
+        }
+        Ok(())
+    }
+
+    /// Budget after `identifies` sessions have been started
+    pub fn consume(&self, identifies: u32) -> Self {
+        Self {
+            remaining: self.remaining.saturating_sub(identifies),
+            ..self.clone()
+        }
+    }
+}
+
+/// Validate the total shard count against large-bot sharding rules.
+///
+/// Bots with `max_concurrency > 1` (large-bot sharding) must run a shard count
+/// that is a multiple of `max_concurrency` (16 for most large bots).
+pub fn validate_shard_count(total_shards: u64, max_concurrency: u16) -> Result<(), GatewayError> {
+    let concurrency = u64::from(max_concurrency);
+    if concurrency > 1 && !total_shards.is_multiple_of(concurrency) {
+        return Err(GatewayError::Config(format!(
+            "TOTAL_SHARDS ({total_shards}) must be a multiple of max_concurrency ({concurrency}) for large-bot sharding"
+        )));
+    }
+    Ok(())
+}
+
+/// Reserve `identifies` from the shared budget.
+///
+/// With NATS, the budget is read from KV, checked, and written back with a
+/// compare-and-swap so concurrently starting pools cannot both spend the same
+/// identifies. Without NATS only Discord's view is checked.
+pub async fn reserve(
+    js: Option<&JsContext>,
+    limit: &SessionStartLimit,
+    identifies: u32,
+    reserve: u32,
+    now_ms: u64,
+    bot_id: Option<&str>,
+) -> Result<IdentifyBudget, GatewayError> {
+    let discord = IdentifyBudget::from_limit(limit, now_ms);
+
+    let Some(js) = js else {
+        discord.check_burst(identifies, reserve)?;
+        return Ok(discord.consume(identifies));
+    };
+
+    let store = kv::open_bucket(js, buckets::IDENTIFY_BUDGET, "Shared Discord identify budget").await?;
+    let kv_error = |e: Box<dyn std::error::Error + Send + Sync>| GatewayError::NatsKvFailed {
+        bucket: buckets::IDENTIFY_BUDGET.to_string(),
+        source: e,
+    };
+
+    let key = kv::bot_key(bot_id, BUDGET_KEY);
+    for attempt in 1..=MAX_RESERVE_ATTEMPTS {
+        let entry = store.entry(&key).await.map_err(|e| kv_error(Box::new(e)))?;
+        let persisted = entry
+            .as_ref()
+            .filter(|e| !e.value.is_empty())
+            .and_then(|e| serde_json::from_slice::<IdentifyBudget>(&e.value).ok());
+
+        let budget = discord.clone().merge(persisted.as_ref(), now_ms);
+        budget.check_burst(identifies, reserve)?;
+
+        let after = budget.consume(identifies);
+        let payload = serde_json::to_vec(&after).expect("IdentifyBudget serialization is infallible");
+
+        let written = match entry {
+            Some(e) => store.update(&key, payload.into(), e.revision).await.is_ok(),
+            None => store.create(&key, payload.into()).await.is_ok(),
+        };
+
+        if written {
+            info!(
+                identifies,
+                remaining = after.remaining,
+                total = after.total,
+                "Reserved identify budget"
+            );
+            return Ok(after);
+        }
+
+        warn!(attempt, "Identify budget changed concurrently - retrying");
+    }
+
+    Err(kv_error(
+        format!("could not reserve identify budget after {MAX_RESERVE_ATTEMPTS} attempts").into(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limit(remaining: u32) -> SessionStartLimit {
+        SessionStartLimit {
+            max_concurrency: 16,
+            remaining,
+            reset_after: 60_000,
+            total: 2000,
+        }
+    }
+
+    #[test]
+    fn large_bot_shard_count_must_be_multiple_of_concurrency() {
+        assert!(validate_shard_count(64, 16).is_ok());
+        assert!(validate_shard_count(100, 16).is_err());
+        // Regular bots (max_concurrency = 1) may use any count
+        assert!(validate_shard_count(7, 1).is_ok());
+    }
+
+    #[test]
+    fn burst_refused_when_it_would_eat_into_reserve() {
+        let budget = IdentifyBudget::from_limit(&limit(30), 0);
+        assert!(budget.check_burst(25, 5).is_ok());
+
+        let err = budget.check_burst(25, 6).unwrap_err();
+        assert_eq!(err.error_type_label(), "identify_budget");
+    }
+
+    #[test]
+    fn merge_prefers_lower_persisted_remaining_within_window() {
+        let discord = IdentifyBudget::from_limit(&limit(1000), 0);
+        let persisted = discord.consume(100);
+
+        assert_eq!(discord.clone().merge(Some(&persisted), 1_000).remaining, 900);
+        // Persisted window has reset: Discord's view wins
+        assert_eq!(discord.clone().merge(Some(&persisted), 60_001).remaining, 1000);
+    }
+}
@@ -0,0 +1,117 @@
+//! Canary shard for config and code rollouts (`CANARY_SHARD`)
+//!
+//! One shard, in whichever pool runs it, publishes with candidate settings
+//! while the rest keep the current ones: a wire format
+//! (`CANARY_SHARD_WIRE_FORMAT`), a schema version
+//! (`CANARY_SHARD_SCHEMA_VERSION`) and extra sampling or filters
+//! (`CANARY_SHARD_EVENT_SAMPLING`, applied on top of the pool's). A new build
+//! can be tried the same way by deploying it to the canary shard's pool only.
+//!
+//! Events from the canary shard carry an `Arrakis-Canary: shard` header, and
+//! every routed event is counted under a `cohort` (`canary` or `baseline`),
+//! so the canary's failure rate and publish latency can be compared with
+//! the rest of the pool before the settings roll out everywhere.
+
+use crate::events::sampling::EventSampling;
+use crate::events::serialize::WireFormat;
+
+/// Header on events published by the canary shard
+pub const HEADER: &str = "Arrakis-Canary";
+pub const HEADER_VALUE: &str = "shard";
+
+/// Which side of the comparison a shard is on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cohort {
+    Canary,
+    Baseline,
+}
+
+impl Cohort {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Canary => "canary",
+            Self::Baseline => "baseline",
+        }
+    }
+}
+
+/// The canary shard and the settings it runs with
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CanaryShard {
+    pub shard_id: u64,
+    /// Wire format instead of `WIRE_FORMAT`
+    pub wire_format: Option<WireFormat>,
+    /// Schema version instead of `WIRE_SCHEMA_VERSION`
+    pub schema_version: Option<u32>,
+    /// Sampling on top of `EVENT_SAMPLING` and runtime overrides
+    pub event_sampling: EventSampling,
+}
+
+impl CanaryShard {
+    pub fn cohort(&self, shard_id: u64) -> Cohort {
+        if shard_id == self.shard_id {
+            Cohort::Canary
+        } else {
+            Cohort::Baseline
+        }
+    }
+
+    /// Wire format for a shard's events
+    pub fn wire_format(&self, shard_id: u64, default: WireFormat) -> WireFormat {
+        self.wire_format.filter(|_| shard_id == self.shard_id).unwrap_or(default)
+    }
+
+    /// Schema version for a shard's events
+    pub fn schema_version(&self, shard_id: u64, default: u32) -> u32 {
+        self.schema_version.filter(|_| shard_id == self.shard_id).unwrap_or(default)
+    }
+
+    /// Whether the canary's own sampling keeps an event (always for other shards)
+    pub fn admits(&self, shard_id: u64, event_type: &str) -> bool {
+        shard_id != self.shard_id || self.event_sampling.admits(event_type)
+    }
+
+    /// Settings that differ from the pool's, for logs
+    pub fn changes(&self) -> Vec<String> {
+        let mut changes = Vec::new();
+        if let Some(format) = self.wire_format {
+            changes.push(format!("wire_format={}", format.as_str()));
+        }
+        if let Some(version) = self.schema_version {
+            changes.push(format!("schema_version={version}"));
+        }
+        for rule in self.event_sampling.rules() {
+            changes.push(format!("sampling {}={}", rule.event_type, rule.percent));
+        }
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_the_canary_shard_gets_candidate_settings() {
+        let canary = CanaryShard {
+            shard_id: 3,
+            wire_format: Some(WireFormat::Protobuf),
+            schema_version: Some(1),
+            event_sampling: EventSampling::parse("presence.update=off").unwrap(),
+        };
+
+        assert_eq!(canary.cohort(3), Cohort::Canary);
+        assert_eq!(canary.cohort(4).as_str(), "baseline");
+        assert_eq!(canary.wire_format(3, WireFormat::Json), WireFormat::Protobuf);
+        assert_eq!(canary.wire_format(4, WireFormat::Json), WireFormat::Json);
+        assert_eq!((canary.schema_version(3, 2), canary.schema_version(4, 2)), (1, 2));
+        assert!(!canary.admits(3, "presence.update"));
+        assert!(canary.admits(4, "presence.update"));
+        assert!(canary.admits(3, "member.join"));
+
+        // Without candidate settings the canary is a control for the pool
+        let control = CanaryShard { shard_id: 3, ..Default::default() };
+        assert_eq!(control.wire_format(3, WireFormat::Json), WireFormat::Json);
+        assert!(control.changes().is_empty());
+    }
+}
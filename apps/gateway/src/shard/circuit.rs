@@ -0,0 +1,269 @@
+//! Per-shard circuit breaker (`CIRCUIT_BREAKER_MAX_ERRORS`,
+//! `CIRCUIT_BREAKER_COOLDOWN_SECS`)
+//!
+//! A shard whose receives fail `CIRCUIT_BREAKER_MAX_ERRORS` times in a row
+//! (overridable at runtime with `circuit_breaker_max_errors`) trips its
+//! circuit: it is stopped and marked dead (`open`). After the cool-down, the
+//! pool tries one reconnect (`half_open`). The first error before Discord
+//! answers with Ready or Resumed opens the circuit again, with the cool-down
+//! doubled up to `CIRCUIT_BREAKER_MAX_COOLDOWN_SECS`; a Ready or Resumed
+//! closes it and resets the cool-down. With a cool-down of 0, a tripped shard
+//! stays dead until an operator restarts it.
+//!
+//! Every transition is counted in `gateway_circuit_transitions_total` and
+//! published as a `gateway.circuit_state` event.
+
+use crate::events::serialize::{now_millis, GatewayEvent};
+use crate::metrics::GatewayMetrics;
+use crate::nats::NatsPublisher;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::warn;
+use uuid::Uuid;
+
+/// Event type of the transitions
+pub const EVENT_TYPE: &str = "gateway.circuit_state";
+
+/// Cool-down before the first half-open attempt, unless configured
+pub const DEFAULT_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// Longest cool-down after repeated failed attempts, unless configured
+pub const DEFAULT_MAX_COOLDOWN: Duration = Duration::from_secs(600);
+
+/// Circuit breaker settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitConfig {
+    /// Wait before the first half-open attempt (zero: no attempts)
+    pub cooldown: Duration,
+    /// Cap on the doubled cool-down
+    pub max_cooldown: Duration,
+}
+
+impl Default for CircuitConfig {
+    fn default() -> Self {
+        Self { cooldown: DEFAULT_COOLDOWN, max_cooldown: DEFAULT_MAX_COOLDOWN }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Receiving normally
+    Closed,
+    /// Tripped; the shard is stopped until the cool-down ends
+    Open,
+    /// One reconnect in progress; any error opens the circuit again
+    HalfOpen,
+}
+
+impl CircuitState {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            CircuitState::Closed => "closed",
+            CircuitState::Open => "open",
+            CircuitState::HalfOpen => "half_open",
+        }
+    }
+}
+
+/// A shard's circuit moving from one state to another
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transition {
+    pub shard_id: u64,
+    pub from: CircuitState,
+    pub to: CircuitState,
+    /// Consecutive errors that opened the circuit (0 otherwise)
+    pub errors: u32,
+    /// Times the circuit opened since it was last closed
+    pub trips: u32,
+    /// Wait before the next half-open attempt (None when not open, or
+    /// without recovery)
+    pub cooldown: Option<Duration>,
+}
+
+impl Transition {
+    /// The transition as a `gateway.circuit_state` event
+    pub fn event(&self, bot_id: Option<String>) -> GatewayEvent {
+        GatewayEvent {
+            event_id: Uuid::new_v4().to_string(),
+            event_type: EVENT_TYPE.to_string(),
+            schema_version: crate::events::versions::CURRENT,
+            shard_id: self.shard_id,
+            bot_id,
+            seq: None,
+            seq_epoch: None,
+            timestamp: now_millis(),
+            guild_id: None,
+            channel_id: None,
+            user_id: None,
+            data: serde_json::json!({
+                "state": self.to.as_str(),
+                "previous": self.from.as_str(),
+                "errors": self.errors,
+                "trips": self.trips,
+                "cooldown_secs": self.cooldown.map(|cooldown| cooldown.as_secs()),
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Circuit {
+    state: CircuitState,
+    trips: u32,
+}
+
+/// Circuit state of a pool's shards (shards not listed are closed)
+#[derive(Debug, Default)]
+pub struct CircuitBreaker {
+    config: CircuitConfig,
+    shards: Mutex<HashMap<u64, Circuit>>,
+}
+
+impl CircuitBreaker {
+    pub fn new(config: CircuitConfig) -> Self {
+        Self { config, shards: Mutex::default() }
+    }
+
+    pub fn state(&self, shard_id: u64) -> CircuitState {
+        self.shards.lock().unwrap_or_else(|e| e.into_inner()).get(&shard_id).map_or(CircuitState::Closed, |c| c.state)
+    }
+
+    /// Consecutive errors that trip a shard's circuit: `max_errors`, or the
+    /// first error during a half-open attempt
+    pub fn threshold(&self, shard_id: u64, max_errors: u32) -> u32 {
+        match self.state(shard_id) {
+            CircuitState::HalfOpen => 1,
+            CircuitState::Closed | CircuitState::Open => max_errors,
+        }
+    }
+
+    /// Wait before the half-open attempt following `trips` trips
+    fn cooldown(&self, trips: u32) -> Option<Duration> {
+        if self.config.cooldown.is_zero() {
+            return None;
+        }
+        let doubled = self.config.cooldown.saturating_mul(1 << trips.saturating_sub(1).min(16));
+        Some(doubled.min(self.config.max_cooldown.max(self.config.cooldown)))
+    }
+
+    /// Open a shard's circuit after `errors` consecutive errors
+    pub fn trip(&self, shard_id: u64, errors: u32) -> Transition {
+        let mut shards = self.shards.lock().unwrap_or_else(|e| e.into_inner());
+        let circuit = shards.entry(shard_id).or_insert(Circuit { state: CircuitState::Closed, trips: 0 });
+        let from = circuit.state;
+        circuit.state = CircuitState::Open;
+        circuit.trips += 1;
+        Transition {
+            shard_id,
+            from,
+            to: CircuitState::Open,
+            errors,
+            trips: circuit.trips,
+            cooldown: self.cooldown(circuit.trips),
+        }
+    }
+
+    /// When to try an open circuit again: its trip number and cool-down
+    /// (None when closed, half-open, or without recovery)
+    pub fn retry(&self, shard_id: u64) -> Option<(u32, Duration)> {
+        let circuit = *self.shards.lock().unwrap_or_else(|e| e.into_inner()).get(&shard_id)?;
+        if circuit.state != CircuitState::Open {
+            return None;
+        }
+        Some((circuit.trips, self.cooldown(circuit.trips)?))
+    }
+
+    /// Whether a shard's circuit is still open from trip number `trips`
+    pub fn still_open(&self, shard_id: u64, trips: u32) -> bool {
+        self.shards
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&shard_id)
+            .is_some_and(|c| c.state == CircuitState::Open && c.trips == trips)
+    }
+
+    /// Start a half-open attempt on an open circuit
+    pub fn half_open(&self, shard_id: u64) -> Option<Transition> {
+        self.move_to(shard_id, CircuitState::Open, CircuitState::HalfOpen)
+    }
+
+    /// Close a half-open circuit once the shard is receiving again
+    pub fn recovered(&self, shard_id: u64) -> Option<Transition> {
+        let transition = self.move_to(shard_id, CircuitState::HalfOpen, CircuitState::Closed)?;
+        self.shards.lock().unwrap_or_else(|e| e.into_inner()).remove(&shard_id);
+        Some(transition)
+    }
+
+    fn move_to(&self, shard_id: u64, from: CircuitState, to: CircuitState) -> Option<Transition> {
+        let mut shards = self.shards.lock().unwrap_or_else(|e| e.into_inner());
+        let circuit = shards.get_mut(&shard_id).filter(|c| c.state == from)?;
+        circuit.state = to;
+        Some(Transition { shard_id, from, to, errors: 0, trips: circuit.trips, cooldown: None })
+    }
+}
+
+/// Count a transition; returns the publish of its event when there is NATS
+/// to publish it to, for the caller to run off its own task
+pub fn report(
+    nats: Option<Arc<NatsPublisher>>,
+    metrics: &GatewayMetrics,
+    bot_id: Option<String>,
+    transition: Transition,
+) -> Option<impl Future<Output = ()> + Send + 'static> {
+    metrics.record_circuit_transition(transition.shard_id, transition.from.as_str(), transition.to.as_str());
+    let nats = nats?;
+    Some(async move {
+        if let Err(e) = nats.publish_event(&transition.event(bot_id)).await {
+            warn!(shard_id = transition.shard_id, error = %e, "Failed to publish circuit state event");
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failed_attempts_back_off_and_recovery_resets() {
+        let breaker = CircuitBreaker::new(CircuitConfig {
+            cooldown: Duration::from_secs(30),
+            max_cooldown: Duration::from_secs(100),
+        });
+        assert_eq!(breaker.threshold(3, 10), 10);
+
+        let tripped = breaker.trip(3, 10);
+        assert_eq!((tripped.from, tripped.to, tripped.cooldown), (CircuitState::Closed, CircuitState::Open, Some(Duration::from_secs(30))));
+        assert!(breaker.still_open(3, 1));
+        assert_eq!(breaker.retry(3), Some((1, Duration::from_secs(30))));
+        assert!(breaker.recovered(3).is_none(), "only a half-open circuit closes");
+
+        // A half-open attempt trips on the first error, and waits longer next time
+        assert_eq!(breaker.half_open(3).map(|t| t.to), Some(CircuitState::HalfOpen));
+        assert!(!breaker.still_open(3, 1));
+        assert_eq!(breaker.threshold(3, 10), 1);
+        assert_eq!(breaker.trip(3, 1).cooldown, Some(Duration::from_secs(60)));
+        breaker.half_open(3);
+        assert_eq!(breaker.trip(3, 1).cooldown, Some(Duration::from_secs(100)));
+        assert!(!breaker.still_open(3, 2), "an earlier trip's retry is stale");
+
+        breaker.half_open(3);
+        let closed = breaker.recovered(3).unwrap();
+        assert_eq!((closed.from, closed.to, closed.trips), (CircuitState::HalfOpen, CircuitState::Closed, 3));
+        assert_eq!(breaker.state(3), CircuitState::Closed);
+        assert_eq!(breaker.trip(3, 10).cooldown, Some(Duration::from_secs(30)));
+
+        let event = closed.event(Some("bot-a".to_string()));
+        assert_eq!(event.event_type, EVENT_TYPE);
+        assert_eq!((event.data["state"].as_str(), event.data["previous"].as_str()), (Some("closed"), Some("half_open")));
+    }
+
+    #[test]
+    fn test_zero_cooldown_stays_open() {
+        let breaker = CircuitBreaker::new(CircuitConfig { cooldown: Duration::ZERO, max_cooldown: DEFAULT_MAX_COOLDOWN });
+        assert_eq!(breaker.trip(0, 10).cooldown, None);
+        assert_eq!(breaker.state(0), CircuitState::Open);
+        assert_eq!(breaker.retry(0), None);
+    }
+}
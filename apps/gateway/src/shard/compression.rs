@@ -0,0 +1,251 @@
+//! Gateway transport compression
+//!
+//! Twilight selects the transport codec at compile time (`compression-zlib` /
+//! `compression-zstd` features), so `GATEWAY_COMPRESSION` is checked against the
+//! build rather than switching codecs at runtime.
+//!
+//! Twilight decompresses inside the shard and never exposes the frames it read,
+//! so wire bytes are estimated: a per-shard shadow compressor re-compresses each
+//! payload the way Discord streams it (one shared context per connection,
+//! flushed per message). Decompressed bytes are exact.
+
+use std::fmt;
+
+/// Transport compression codec negotiated with Discord
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransportCompression {
+    None,
+    ZlibStream,
+    ZstdStream,
+}
+
+impl TransportCompression {
+    /// Codec compiled into this build (zstd wins if both features are enabled,
+    /// matching Twilight)
+    pub const fn compiled() -> Self {
+        if cfg!(feature = "compression-zstd") {
+            Self::ZstdStream
+        } else if cfg!(feature = "compression-zlib") {
+            Self::ZlibStream
+        } else {
+            Self::None
+        }
+    }
+
+    /// Parse a `GATEWAY_COMPRESSION` value
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "none" | "off" => Some(Self::None),
+            "zlib" | "zlib-stream" => Some(Self::ZlibStream),
+            "zstd" | "zstd-stream" => Some(Self::ZstdStream),
+            _ => None,
+        }
+    }
+
+    /// Codec name as used in Discord's `compress` query parameter
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::ZlibStream => "zlib-stream",
+            Self::ZstdStream => "zstd-stream",
+        }
+    }
+
+    /// Cargo feature that compiles this codec in
+    pub const fn feature(self) -> Option<&'static str> {
+        match self {
+            Self::None => None,
+            Self::ZlibStream => Some("compression-zlib"),
+            Self::ZstdStream => Some("compression-zstd"),
+        }
+    }
+}
+
+impl fmt::Display for TransportCompression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Per-shard estimator of transport bytes for the compiled codec
+pub struct WireMeter {
+    inner: Inner,
+}
+
+enum Inner {
+    /// Transport is uncompressed: wire bytes equal payload bytes
+    Passthrough,
+    #[cfg(feature = "compression-zlib")]
+    Zlib(zlib::Shadow),
+    #[cfg(feature = "compression-zstd")]
+    Zstd(zstd::Shadow),
+}
+
+impl WireMeter {
+    /// Create a meter for the compiled codec.
+    ///
+    /// Returns None when compression is compiled in but estimation is
+    /// disabled (`GATEWAY_COMPRESSION_METRICS=false`): shadow compression costs
+    /// roughly what Discord spends compressing the stream.
+    pub fn new(estimate: bool) -> Option<Self> {
+        let inner = match TransportCompression::compiled() {
+            TransportCompression::None => Inner::Passthrough,
+            _ if !estimate => return None,
+            #[cfg(feature = "compression-zstd")]
+            TransportCompression::ZstdStream => Inner::Zstd(zstd::Shadow::new()),
+            #[cfg(feature = "compression-zlib")]
+            TransportCompression::ZlibStream => Inner::Zlib(zlib::Shadow::new()),
+            #[allow(unreachable_patterns)]
+            _ => return None,
+        };
+        Some(Self { inner })
+    }
+
+    /// Estimated transport bytes for one payload
+    pub fn observe(&mut self, payload: &[u8]) -> u64 {
+        match &mut self.inner {
+            Inner::Passthrough => payload.len() as u64,
+            #[cfg(feature = "compression-zlib")]
+            Inner::Zlib(shadow) => shadow.compress(payload),
+            #[cfg(feature = "compression-zstd")]
+            Inner::Zstd(shadow) => shadow.compress(payload),
+        }
+    }
+
+    /// Start a fresh compression context (new connection)
+    pub fn reset(&mut self) {
+        match &mut self.inner {
+            Inner::Passthrough => {}
+            #[cfg(feature = "compression-zlib")]
+            Inner::Zlib(shadow) => shadow.reset(),
+            #[cfg(feature = "compression-zstd")]
+            Inner::Zstd(shadow) => shadow.reset(),
+        }
+    }
+}
+
+/// Scratch buffer size for shadow compression output
+#[cfg(any(feature = "compression-zlib", feature = "compression-zstd"))]
+const SCRATCH_SIZE: usize = 32 * 1024;
+
+#[cfg(feature = "compression-zlib")]
+mod zlib {
+    use flate2::{Compress, Compression, FlushCompress};
+
+    /// zlib-stream shadow: one deflate context, sync-flushed per message
+    pub struct Shadow {
+        compress: Compress,
+        scratch: Box<[u8]>,
+    }
+
+    impl Shadow {
+        pub fn new() -> Self {
+            Self {
+                compress: Compress::new(Compression::default(), true),
+                scratch: vec![0; super::SCRATCH_SIZE].into_boxed_slice(),
+            }
+        }
+
+        pub fn compress(&mut self, payload: &[u8]) -> u64 {
+            let (start_in, start_out) = (self.compress.total_in(), self.compress.total_out());
+            loop {
+                let consumed = (self.compress.total_in() - start_in) as usize;
+                let before = self.compress.total_out();
+                if self
+                    .compress
+                    .compress(&payload[consumed..], &mut self.scratch, FlushCompress::Sync)
+                    .is_err()
+                {
+                    break;
+                }
+                let produced = (self.compress.total_out() - before) as usize;
+                // Output space left over means the flush completed
+                if self.compress.total_in() - start_in == payload.len() as u64
+                    && produced < self.scratch.len()
+                {
+                    break;
+                }
+            }
+            self.compress.total_out() - start_out
+        }
+
+        pub fn reset(&mut self) {
+            self.compress.reset();
+        }
+    }
+}
+
+#[cfg(feature = "compression-zstd")]
+mod zstd {
+    use zstd_safe::zstd_sys::ZSTD_EndDirective;
+    use zstd_safe::{CCtx, InBuffer, OutBuffer, ResetDirective};
+
+    /// zstd-stream shadow: one compression context, flushed per message
+    pub struct Shadow {
+        cctx: CCtx<'static>,
+        scratch: Box<[u8]>,
+    }
+
+    impl Shadow {
+        pub fn new() -> Self {
+            Self {
+                cctx: CCtx::create(),
+                scratch: vec![0; super::SCRATCH_SIZE].into_boxed_slice(),
+            }
+        }
+
+        pub fn compress(&mut self, payload: &[u8]) -> u64 {
+            let mut input = InBuffer::around(payload);
+            let mut total = 0u64;
+            loop {
+                let mut output = OutBuffer::around(&mut self.scratch[..]);
+                let remaining = self
+                    .cctx
+                    .compress_stream2(&mut output, &mut input, ZSTD_EndDirective::ZSTD_e_flush);
+                total += output.pos() as u64;
+                match remaining {
+                    Ok(0) if input.pos() == payload.len() => break,
+                    Ok(_) => continue,
+                    Err(_) => break,
+                }
+            }
+            total
+        }
+
+        pub fn reset(&mut self) {
+            let _ = self.cctx.reset(ResetDirective::SessionOnly);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_accepts_codec_names() {
+        assert_eq!(TransportCompression::parse("zlib-stream"), Some(TransportCompression::ZlibStream));
+        assert_eq!(TransportCompression::parse(" ZSTD "), Some(TransportCompression::ZstdStream));
+        assert_eq!(TransportCompression::parse("none"), Some(TransportCompression::None));
+        assert_eq!(TransportCompression::parse("gzip"), None);
+    }
+
+    #[test]
+    fn test_wire_meter_estimates_below_payload_for_repetitive_stream() {
+        let Some(mut meter) = WireMeter::new(true) else {
+            return;
+        };
+        let payload = br#"{"op":11,"d":null,"s":null,"t":null}"#.repeat(64);
+
+        let first = meter.observe(&payload);
+        let second = meter.observe(&payload);
+
+        if TransportCompression::compiled() == TransportCompression::None {
+            assert_eq!(first, payload.len() as u64);
+        } else {
+            assert!(first > 0 && first < payload.len() as u64);
+            // Shared context: a repeated message compresses further
+            assert!(second <= first);
+        }
+    }
+}
@@ -0,0 +1,90 @@
+//! Operator control of individual shards
+//!
+//! The admin HTTP API sends commands here; the pool's supervisor loop
+//! applies them. A restarted shard resumes its session when it has one, so
+//! recovering a shard doesn't cost an identify unless Discord ended the
+//! session (a dead shard identifies again). A shard can also be handed to
+//! another pool, which resumes it (`handoff`).
+//!
+//! Gateway commands (opcodes sent over a shard's websocket, such as Request
+//! Guild Members) go straight to the shard's connection instead; each shard
+//! registers its sender when it starts.
+
+use dashmap::DashMap;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use twilight_gateway::{Command, MessageSender};
+
+/// Commands are rare; a full queue means the pool isn't keeping up
+const COMMAND_QUEUE: usize = 32;
+
+/// What to do with one shard
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShardCommand {
+    /// Reconnect a running shard, or start a drained or dead one
+    Restart,
+    /// Close the shard (keeping its session) and leave it stopped
+    Drain,
+    /// Close the shard, save its session and ask another pool to resume it
+    Handoff { to_pool: u64 },
+    /// Start a shard handed over by another pool, resuming its saved session.
+    /// When the source saved one (`session`), only the pool that takes it
+    /// from the store runs the shard.
+    Adopt { session: bool },
+    /// Take back the session of a shard whose handoff failed and resume it,
+    /// or release the shard if the target took the session first
+    Reclaim,
+    /// Forget a shard another pool has adopted
+    Release,
+}
+
+impl ShardCommand {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            ShardCommand::Restart => "restart",
+            ShardCommand::Drain => "drain",
+            ShardCommand::Handoff { .. } => "handoff",
+            ShardCommand::Adopt { .. } => "adopt",
+            ShardCommand::Reclaim => "reclaim",
+            ShardCommand::Release => "release",
+        }
+    }
+}
+
+/// Sends shard commands to a running pool, and gateway commands to its shards
+#[derive(Debug, Clone)]
+pub struct ShardControl {
+    tx: mpsc::Sender<(u64, ShardCommand)>,
+    /// Websocket sender of each shard's current connection
+    senders: Arc<DashMap<u64, MessageSender>>,
+}
+
+impl ShardControl {
+    pub(crate) fn channel() -> (Self, mpsc::Receiver<(u64, ShardCommand)>) {
+        let (tx, rx) = mpsc::channel(COMMAND_QUEUE);
+        (Self { tx, senders: Arc::default() }, rx)
+    }
+
+    /// Queue a command; false if the pool has stopped or is backed up
+    pub fn send(&self, shard_id: u64, command: ShardCommand) -> bool {
+        self.tx.try_send((shard_id, command)).is_ok()
+    }
+
+    /// Route gateway commands for a shard to its (new) connection
+    pub(crate) fn register(&self, shard_id: u64, sender: MessageSender) {
+        self.senders.insert(shard_id, sender);
+    }
+
+    /// Shards that have registered a connection
+    pub fn shard_ids(&self) -> Vec<u64> {
+        let mut shard_ids: Vec<u64> = self.senders.iter().map(|entry| *entry.key()).collect();
+        shard_ids.sort_unstable();
+        shard_ids
+    }
+
+    /// Send a gateway command over a shard's websocket (queued while it
+    /// reconnects); false if the pool doesn't run the shard or it has stopped
+    pub fn send_gateway(&self, shard_id: u64, command: &impl Command) -> bool {
+        self.senders.get(&shard_id).is_some_and(|sender| sender.command(command).is_ok())
+    }
+}
@@ -0,0 +1,124 @@
+//! Cross-pool identify coordination
+//!
+//! Discord allows one identify per rate-limit bucket (`shard_id %
+//! max_concurrency`) every 5 seconds, across the whole bot. Each pool's queue
+//! only sees its own shards, so pools starting together can exceed it. With
+//! NATS, every identify also claims its bucket in the
+//! `gateway_identify_slots` KV bucket: the entry holds the bucket's last
+//! identify time and is advanced with a compare-and-swap, so at most one pool
+//! identifies per bucket per window.
+
+use crate::error::GatewayError;
+use crate::events::serialize::now_millis;
+use crate::nats::kv::{self, buckets};
+use async_nats::jetstream::kv::Store;
+use async_nats::jetstream::Context as JsContext;
+use std::time::Duration;
+use tracing::{debug, warn};
+use twilight_gateway::queue::IDENTIFY_DELAY;
+
+/// Slots only matter for one identify window; expired entries keep the
+/// bucket small
+const SLOT_MAX_AGE: Duration = Duration::from_secs(60);
+
+/// Claims identify slots shared by every pool
+#[derive(Debug)]
+pub struct IdentifyCoordinator {
+    store: Store,
+    max_concurrency: u16,
+    /// Set for the other bots of the process (`BOTS`)
+    bot_id: Option<String>,
+}
+
+impl IdentifyCoordinator {
+    pub async fn open(js: &JsContext, max_concurrency: u16, bot_id: Option<&str>) -> Result<Self, GatewayError> {
+        let store = kv::open_expiring_bucket(
+            js,
+            buckets::IDENTIFY_SLOTS,
+            "Last identify per rate-limit bucket",
+            SLOT_MAX_AGE,
+        )
+        .await?;
+        Ok(Self { store, max_concurrency: max_concurrency.max(1), bot_id: bot_id.map(str::to_string) })
+    }
+
+    /// Wait until this shard's rate-limit bucket is free and claim it.
+    ///
+    /// KV failures let the identify through: Discord answers an identify
+    /// over the limit with an invalid session, which the shard retries.
+    pub async fn acquire(&self, shard: u32) {
+        let key = kv::bot_key(self.bot_id.as_deref(), &slot_key(shard, self.max_concurrency));
+        loop {
+            match self.try_claim(&key).await {
+                Ok(None) => {
+                    debug!(shard, key, "Claimed identify slot");
+                    return;
+                }
+                Ok(Some(wait)) => tokio::time::sleep(wait).await,
+                Err(e) => {
+                    warn!(shard, key, error = %e, "Identify coordination unavailable - identifying anyway");
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Claim the slot, or say how long until it frees up. A lost race
+    /// retries immediately.
+    async fn try_claim(&self, key: &str) -> Result<Option<Duration>, GatewayError> {
+        let kv_error = |e: Box<dyn std::error::Error + Send + Sync>| GatewayError::NatsKvFailed {
+            bucket: buckets::IDENTIFY_SLOTS.to_string(),
+            source: e,
+        };
+
+        let entry = self.store.entry(key).await.map_err(|e| kv_error(Box::new(e)))?;
+        let last = entry.as_ref().and_then(|entry| std::str::from_utf8(&entry.value).ok()?.parse().ok());
+        let now = now_millis();
+        let wait = slot_wait(last, now);
+        if !wait.is_zero() {
+            return Ok(Some(wait));
+        }
+
+        let value = now.to_string().into_bytes().into();
+        let claimed = match entry {
+            Some(entry) => self.store.update(key, value, entry.revision).await.is_ok(),
+            None => self.store.create(key, value).await.is_ok(),
+        };
+        Ok((!claimed).then_some(Duration::ZERO))
+    }
+}
+
+/// KV key of a shard's rate-limit bucket
+fn slot_key(shard: u32, max_concurrency: u16) -> String {
+    format!("bucket-{}", shard % u32::from(max_concurrency.max(1)))
+}
+
+/// Time left in a bucket's identify window, given its last identify (Unix ms)
+fn slot_wait(last: Option<u64>, now_ms: u64) -> Duration {
+    let Some(last) = last else {
+        return Duration::ZERO;
+    };
+    let free_at = last.saturating_add(u64::try_from(IDENTIFY_DELAY.as_millis()).unwrap_or(u64::MAX));
+    Duration::from_millis(free_at.saturating_sub(now_ms))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shards_share_buckets_by_max_concurrency() {
+        assert_eq!(slot_key(17, 16), "bucket-1");
+        assert_eq!(slot_key(1, 16), "bucket-1");
+        assert_eq!(slot_key(17, 1), "bucket-0");
+        assert_eq!(slot_key(17, 0), "bucket-0");
+    }
+
+    #[test]
+    fn slot_frees_five_seconds_after_the_last_identify() {
+        assert_eq!(slot_wait(None, 10_000), Duration::ZERO);
+        assert_eq!(slot_wait(Some(10_000), 12_000), Duration::from_secs(3));
+        assert_eq!(slot_wait(Some(10_000), 15_000), Duration::ZERO);
+        assert_eq!(slot_wait(Some(10_000), 20_000), Duration::ZERO);
+    }
+}
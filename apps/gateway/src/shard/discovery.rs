@@ -0,0 +1,114 @@
+//! Shard count discovery (`TOTAL_SHARDS=auto`)
+//!
+//! Discord recommends a shard count in `GET /gateway/bot`. The first pool to
+//! start rounds it up to a multiple of `max_concurrency` and records the
+//! decision in NATS KV; every later pool adopts the recorded count, so the
+//! cluster agrees on one topology even after Discord's recommendation moves.
+//! Resharding is deliberate: delete the key and restart the pools.
+
+use crate::discord;
+use crate::error::GatewayError;
+use crate::nats::kv::{self, buckets};
+use async_nats::jetstream::kv::CreateErrorKind;
+use async_nats::jetstream::Context as JsContext;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+/// KV key holding the cluster's shard count
+pub const SHARD_COUNT_KEY: &str = "total_shards";
+
+/// The shard count a cluster runs with, and where it came from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShardCount {
+    pub total_shards: u64,
+    pub max_concurrency: u16,
+    /// Discord's recommendation when the count was decided
+    pub recommended: u64,
+    /// Unix ms
+    pub decided_at: u64,
+}
+
+impl ShardCount {
+    /// Discord's recommendation, rounded up to a multiple of `max_concurrency`
+    /// (large-bot sharding rejects anything else)
+    pub fn from_recommendation(recommended: u64, max_concurrency: u16, now_ms: u64) -> Self {
+        let concurrency = u64::from(max_concurrency.max(1));
+        Self {
+            total_shards: recommended.max(1).div_ceil(concurrency) * concurrency,
+            max_concurrency,
+            recommended,
+            decided_at: now_ms,
+        }
+    }
+}
+
+/// Decide the cluster's shard count.
+///
+/// With NATS, a count recorded by an earlier pool wins; otherwise Discord's
+/// recommendation is recorded with a create-if-absent write, so pools
+/// starting together settle on whichever wrote first.
+pub async fn discover(token: &str, js: Option<&JsContext>, now_ms: u64) -> Result<ShardCount, GatewayError> {
+    let Some(js) = js else {
+        warn!("TOTAL_SHARDS=auto without NATS: pools can't share the count, run a single pool");
+        return recommend(token, now_ms).await;
+    };
+
+    let store = kv::open_bucket(js, buckets::SHARD_COUNT, "Cluster shard count (TOTAL_SHARDS=auto)").await?;
+    let kv_error = |e: Box<dyn std::error::Error + Send + Sync>| GatewayError::NatsKvFailed {
+        bucket: buckets::SHARD_COUNT.to_string(),
+        source: e,
+    };
+    let recorded = || async {
+        let value = store.get(SHARD_COUNT_KEY).await.map_err(|e| kv_error(Box::new(e)))?;
+        value
+            .map(|value| serde_json::from_slice::<ShardCount>(&value).map_err(|e| kv_error(Box::new(e))))
+            .transpose()
+    };
+
+    if let Some(count) = recorded().await? {
+        info!(total_shards = count.total_shards, decided_at = count.decided_at, "Using the recorded shard count");
+        return Ok(count);
+    }
+
+    let count = recommend(token, now_ms).await?;
+    let payload = serde_json::to_vec(&count).map_err(|e| kv_error(Box::new(e)))?;
+    match store.create(SHARD_COUNT_KEY, payload.into()).await {
+        Ok(_) => {
+            info!(
+                total_shards = count.total_shards,
+                recommended = count.recommended,
+                max_concurrency = count.max_concurrency,
+                "Recorded the cluster shard count"
+            );
+            Ok(count)
+        }
+        // Another pool recorded first: use its count
+        Err(e) if e.kind() == CreateErrorKind::AlreadyExists => {
+            recorded().await?.ok_or_else(|| kv_error("shard count vanished after a conflicting write".into()))
+        }
+        Err(e) => Err(kv_error(Box::new(e))),
+    }
+}
+
+async fn recommend(token: &str, now_ms: u64) -> Result<ShardCount, GatewayError> {
+    let client = twilight_http::Client::new(token.to_string());
+    let info = discord::fetch_gateway_bot(&client).await?;
+    Ok(ShardCount::from_recommendation(
+        u64::from(info.shards),
+        info.session_start_limit.max_concurrency,
+        now_ms,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recommendation_rounds_up_to_max_concurrency() {
+        assert_eq!(ShardCount::from_recommendation(3, 1, 0).total_shards, 3);
+        assert_eq!(ShardCount::from_recommendation(150, 16, 0).total_shards, 160);
+        assert_eq!(ShardCount::from_recommendation(160, 16, 0).total_shards, 160);
+        assert_eq!(ShardCount::from_recommendation(0, 1, 0).total_shards, 1);
+    }
+}
@@ -0,0 +1,183 @@
+//! Moving a shard to another pool without an identify
+//!
+//! To drain a node for maintenance, an operator hands its shards to other
+//! pools one at a time (`POST /admin/shards/{id}/handoff`). The source pool
+//! stops the shard between events, saves its session to `gateway_sessions`
+//! and sends `{ shard_id, total_shards, from_pool, to_pool }` as a request on
+//! `gateway.requests.handoff`. The target pool takes the saved session (so
+//! nobody resumes it twice), starts the shard on it and answers; Discord
+//! replays whatever was sent in between. The source then forgets the shard.
+//!
+//! When no answer comes, or the target refuses (it already runs the shard,
+//! or runs another shard count), the source takes the session back and
+//! resumes the shard itself. The saved session decides who runs the shard:
+//! both pools take it from the store, which lets exactly one of them have
+//! it, so a target that answers too late finds it gone and stands down, and
+//! a source whose answer was lost finds it gone and lets the shard go. A
+//! shard handed off without a session has no such token; when its target
+//! doesn't answer, the source identifies again.
+
+use super::control::{ShardCommand, ShardControl};
+use super::session::{SavedSession, SessionStore};
+use super::ShardState;
+use crate::metrics::GatewayMetrics;
+use crate::nats::requests::{self, Listener, Request};
+use crate::nats::NatsPublisher;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Request subject (mirrors `subjects.gateway_requests` in nats-routing.json)
+pub const SUBJECT: &str = "gateway.requests.handoff";
+
+/// How long the source waits for the target to adopt the shard
+const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A shard on its way from one pool to another
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HandoffRequest {
+    pub shard_id: u64,
+    pub total_shards: u64,
+    pub from_pool: u64,
+    pub to_pool: u64,
+    /// Whether the source saved a session for the target to take
+    #[serde(default)]
+    pub session: bool,
+}
+
+impl HandoffRequest {
+    /// Why this pool can't adopt the shard, if it can't
+    fn refusal(&self, state: &ShardState) -> Option<String> {
+        if self.total_shards != state.total_shards() {
+            return Some(format!(
+                "pool {} runs {} shards, not {}",
+                self.to_pool,
+                state.total_shards(),
+                self.total_shards
+            ));
+        }
+        if self.shard_id >= self.total_shards {
+            return Some(format!("shard {} is out of range for {} shards", self.shard_id, self.total_shards));
+        }
+        match state.get_health(self.shard_id) {
+            Some(health) if !matches!(health, super::ShardHealth::Drained | super::ShardHealth::Dead) => Some(
+                format!("pool {} is already running shard {} ({})", self.to_pool, self.shard_id, health.as_str()),
+            ),
+            _ => None,
+        }
+    }
+}
+
+/// Save a stopped shard's session and ask the target to adopt it, then
+/// release the shard here, or take it back if the target doesn't answer
+pub(super) async fn hand_off(
+    nats: Arc<NatsPublisher>,
+    control: ShardControl,
+    metrics: Arc<GatewayMetrics>,
+    sessions: Option<Arc<SessionStore>>,
+    saved: Option<SavedSession>,
+    request: HandoffRequest,
+) {
+    let session = match (sessions, saved) {
+        (Some(store), Some(saved)) => store.save(&[(request.shard_id, saved)]).await,
+        _ => false,
+    };
+    if !session {
+        warn!(shard_id = request.shard_id, "No session to hand off - the other pool will identify");
+    }
+    let request = HandoffRequest { session, ..request };
+
+    let payload = serde_json::to_vec(&request).expect("handoff request serializes");
+    let answer = match tokio::time::timeout(REPLY_TIMEOUT, nats.client().request(SUBJECT, payload.into())).await {
+        Ok(Ok(reply)) => serde_json::from_slice::<Value>(&reply.payload)
+            .map_err(|e| e.to_string())
+            .and_then(|reply| match reply["error"].as_str() {
+                Some(error) => Err(error.to_string()),
+                None => Ok(()),
+            }),
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(_) => Err(format!("pool {} did not answer within {}s", request.to_pool, REPLY_TIMEOUT.as_secs())),
+    };
+
+    let command = match answer {
+        Ok(()) => {
+            info!(shard_id = request.shard_id, to_pool = request.to_pool, "Shard handed off");
+            metrics.record_shard_handoff("source", "handed_off");
+            ShardCommand::Release
+        }
+        // Whether the target has it is settled by who takes the session
+        Err(error) if session => {
+            warn!(shard_id = request.shard_id, to_pool = request.to_pool, error, "Shard handoff failed, taking it back");
+            ShardCommand::Reclaim
+        }
+        Err(error) => {
+            warn!(shard_id = request.shard_id, to_pool = request.to_pool, error, "Shard handoff failed, identifying it here");
+            metrics.record_shard_handoff("source", "failed");
+            ShardCommand::Restart
+        }
+    };
+    if !control.send(request.shard_id, command) {
+        warn!(shard_id = request.shard_id, command = command.as_str(), "Pool did not accept the handoff outcome");
+    }
+}
+
+/// Adopt shards handed to this pool until the process exits
+pub async fn run(nats: Arc<NatsPublisher>, control: ShardControl, state: ShardState, metrics: Arc<GatewayMetrics>) {
+    let Some(mut incoming) = Listener::subscribe(&nats, SUBJECT, "shard handoff").await else {
+        return;
+    };
+    while let Some(Request { body: request, reply }) = incoming.next::<HandoffRequest>().await {
+        // Every pool hears the request; the target answers it
+        if request.to_pool != state.pool_id() {
+            continue;
+        }
+
+        let answer = match request.refusal(&state) {
+            Some(error) => {
+                metrics.record_shard_handoff("target", "rejected");
+                json!({ "error": error })
+            }
+            None if !control.send(request.shard_id, ShardCommand::Adopt { session: request.session }) => {
+                metrics.record_shard_handoff("target", "rejected");
+                json!({ "error": format!("pool {} is not accepting commands", request.to_pool) })
+            }
+            // Counted once the pool has taken the session
+            None => {
+                info!(shard_id = request.shard_id, from_pool = request.from_pool, "Adopting handed-off shard");
+                json!({ "shard_id": request.shard_id, "pool_id": request.to_pool })
+            }
+        };
+        requests::reply(&nats, reply, &answer).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shard::ShardHealth;
+
+    #[test]
+    fn test_targets_refuse_shards_they_cannot_run() {
+        let state = ShardState::new(2, 50..75, 100, 25);
+        let request =
+            |shard_id, total_shards| HandoffRequest { shard_id, total_shards, from_pool: 1, to_pool: 2, session: true };
+
+        assert_eq!(request(30, 100).refusal(&state), None, "shards from outside the range can be adopted");
+        assert!(request(30, 200).refusal(&state).unwrap().contains("runs 100 shards"));
+        assert!(request(100, 100).refusal(&state).unwrap().contains("out of range"));
+
+        state.set_health(60, ShardHealth::Ready);
+        assert!(request(60, 100).refusal(&state).unwrap().contains("already running"));
+        state.set_health(60, ShardHealth::Drained);
+        assert_eq!(request(60, 100).refusal(&state), None, "a drained shard can come back");
+    }
+
+    #[test]
+    fn test_requests_from_older_pools_carry_no_session() {
+        let request: HandoffRequest =
+            serde_json::from_value(json!({ "shard_id": 7, "total_shards": 100, "from_pool": 0, "to_pool": 1 })).unwrap();
+        assert!(!request.session);
+    }
+}
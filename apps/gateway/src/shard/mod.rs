@@ -3,8 +3,27 @@
 //! Sprint S-4: Twilight Gateway Core
 //! Implements shard pools per SDD §5.1.3
 
+pub mod budget;
+pub mod canary;
+pub mod circuit;
+mod compression;
+pub mod control;
+pub mod coordinator;
+pub mod discovery;
+pub mod handoff;
+mod pacing;
+pub mod pipeline;
 mod pool;
+pub mod rate;
+pub mod reshard;
+pub mod session;
 mod state;
+pub mod token;
+pub mod watchdog;
 
-pub use pool::ShardPool;
-pub use state::ShardState;
+pub use compression::TransportCompression;
+pub use pacing::IdentifyPacing;
+pub use pool::{
+    pool_for_shard, select_shards, shard_for_guild, validate_pool, PoolOptions, ShardPool, DEFAULT_SHARDS_PER_POOL,
+};
+pub use state::{ShardHealth, ShardSnapshot, ShardState};
@@ -0,0 +1,134 @@
+//! Identify pacing
+//!
+//! Layers a per-pool start delay and per-identify jitter on top of Twilight's
+//! max_concurrency queue. On a full cluster restart every pool would otherwise
+//! open its identify window at the same instant; staggering pools by ID and
+//! jittering each identify spreads them out so the burst stays clear of the
+//! session start limit. With a coordinator, each identify also claims its
+//! rate-limit bucket across pools (see `coordinator`).
+
+use super::coordinator::IdentifyCoordinator;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tokio::time::Instant;
+use tracing::debug;
+use twilight_gateway::queue::{InMemoryQueue, Queue};
+
+/// Identify pacing settings for a pool
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IdentifyPacing {
+    /// Delay per pool ID before the pool's first identify
+    pub pool_stagger: Duration,
+    /// Upper bound of the random delay added to every identify
+    pub jitter: Duration,
+}
+
+impl IdentifyPacing {
+    /// Start delay for a pool (pool_id × stagger)
+    pub fn pool_delay(&self, pool_id: u64) -> Duration {
+        let factor = u32::try_from(pool_id).unwrap_or(u32::MAX);
+        self.pool_stagger.saturating_mul(factor)
+    }
+}
+
+/// Identify queue shared by a pool's shards: waits out the pool delay and a
+/// random jitter, then defers to the max_concurrency queue and, if set, the
+/// cross-pool coordinator.
+#[derive(Debug, Clone)]
+pub struct PacedQueue {
+    inner: InMemoryQueue,
+    not_before: Instant,
+    jitter: Duration,
+    coordinator: Option<Arc<IdentifyCoordinator>>,
+}
+
+impl PacedQueue {
+    pub fn new(inner: InMemoryQueue, pacing: IdentifyPacing, pool_id: u64) -> Self {
+        Self {
+            inner,
+            not_before: Instant::now() + pacing.pool_delay(pool_id),
+            jitter: pacing.jitter,
+            coordinator: None,
+        }
+    }
+
+    /// Also claim each identify's rate-limit bucket across pools
+    pub fn with_coordinator(mut self, coordinator: Option<Arc<IdentifyCoordinator>>) -> Self {
+        self.coordinator = coordinator;
+        self
+    }
+
+    /// Random jitter in `0..=self.jitter`
+    fn sample_jitter(&self) -> Duration {
+        let max = u64::try_from(self.jitter.as_millis()).unwrap_or(u64::MAX);
+        Duration::from_millis(fastrand::u64(0..=max))
+    }
+}
+
+impl Queue for PacedQueue {
+    fn enqueue(&self, shard: u32) -> oneshot::Receiver<()> {
+        let (tx, rx) = oneshot::channel();
+        let inner = self.inner.clone();
+        let coordinator = self.coordinator.clone();
+        let ready_at = self.not_before.max(Instant::now()) + self.sample_jitter();
+
+        tokio::spawn(async move {
+            tokio::time::sleep_until(ready_at).await;
+            debug!(shard, "Identify pacing elapsed - entering max_concurrency queue");
+
+            // A closed inner channel makes the shard requeue: propagate by
+            // dropping our sender without sending
+            if inner.enqueue(shard).await.is_err() {
+                return;
+            }
+            if let Some(coordinator) = coordinator {
+                coordinator.acquire(shard).await;
+            }
+            let _ = tx.send(());
+        });
+
+        rx
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pool_delay_scales_with_pool_id() {
+        let pacing = IdentifyPacing {
+            pool_stagger: Duration::from_secs(5),
+            jitter: Duration::ZERO,
+        };
+        assert_eq!(pacing.pool_delay(0), Duration::ZERO);
+        assert_eq!(pacing.pool_delay(3), Duration::from_secs(15));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn identify_waits_for_pool_delay() {
+        let pacing = IdentifyPacing {
+            pool_stagger: Duration::from_secs(10),
+            jitter: Duration::ZERO,
+        };
+        let queue = PacedQueue::new(InMemoryQueue::default(), pacing, 2);
+        let start = Instant::now();
+
+        queue.enqueue(0).await.expect("identify allowed");
+        assert!(start.elapsed() >= Duration::from_secs(20));
+    }
+
+    #[tokio::test]
+    async fn jitter_stays_within_bound() {
+        let queue = PacedQueue {
+            inner: InMemoryQueue::default(),
+            not_before: Instant::now(),
+            jitter: Duration::from_millis(250),
+            coordinator: None,
+        };
+        for _ in 0..100 {
+            assert!(queue.sample_jitter() <= Duration::from_millis(250));
+        }
+    }
+}
@@ -0,0 +1,258 @@
+//! Bounded publish queues between a shard's read loop and NATS
+//!
+//! Publishing inline would hold the websocket read loop (and with it the
+//! heartbeats) on every JetStream ack. Each shard instead queues routable
+//! events on two lanes, `commands` (interactions, which Discord expects a
+//! reply to within 3 seconds) and `events` (everything else), each published
+//! in order by its own task. `PUBLISH_QUEUE_SIZE` bounds each lane. When one
+//! is full the shard either waits for room (`PUBLISH_QUEUE_OVERFLOW=block`) or
+//! drops the event (`drop`), counting it as an overflow and a route failure.
+//! A stopping shard's task publishes what is still queued before it ends.
+//!
+//! Events get their `seq` as they are published, not as they are queued, so
+//! an event that waits on Discord doesn't arrive behind higher numbers. An
+//! event dropped from a full lane still uses up its number: the gap it
+//! leaves is how consumers learn of the loss.
+//!
+//! Events that first wait on Discord (a deferred interaction response, an
+//! audit log lookup), and the shard's reports about itself
+//! (`gateway.capability_degraded`, `gateway.circuit_state`), are published
+//! from tasks of their own, so the shard keeps reading meanwhile. The
+//! pipeline tracks those too: closing it waits for them, so they're
+//! published before the shard is reported stopped.
+
+use super::pool::route;
+use super::state::ShardState;
+use crate::events::serialize::GatewayEvent;
+use crate::metrics::{GatewayMetrics, LaneDepth};
+use crate::nats::quota::stream_for_subject;
+use crate::nats::NatsPublisher;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+use tokio::task::{JoinHandle, JoinSet};
+use tracing::debug;
+
+/// What a shard does with an event when its lane is full
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Wait for room (no loss; a long NATS stall holds up the shard)
+    #[default]
+    Block,
+    /// Drop the event (the shard keeps reading)
+    Drop,
+}
+
+impl OverflowPolicy {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim() {
+            "block" => Some(Self::Block),
+            "drop" => Some(Self::Drop),
+            _ => None,
+        }
+    }
+
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Block => "block",
+            Self::Drop => "drop",
+        }
+    }
+}
+
+/// Publish queue settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueConfig {
+    /// Events each lane holds
+    pub capacity: usize,
+    pub overflow: OverflowPolicy,
+}
+
+impl Default for QueueConfig {
+    fn default() -> Self {
+        Self { capacity: 10_000, overflow: OverflowPolicy::Block }
+    }
+}
+
+/// A routable event waiting to be published
+struct Queued {
+    payload: GatewayEvent,
+    /// Discord payload, for raw passthrough
+    dispatch: Option<String>,
+    /// Size of the Discord message it came from (memory accounting)
+    bytes: usize,
+}
+
+struct Lane {
+    tx: mpsc::Sender<Queued>,
+    depth: Arc<LaneDepth>,
+    task: JoinHandle<()>,
+}
+
+impl Lane {
+    fn start(
+        name: &'static str,
+        capacity: usize,
+        nats: &Arc<NatsPublisher>,
+        state: &ShardState,
+        metrics: &Arc<GatewayMetrics>,
+    ) -> Self {
+        let (tx, rx) = mpsc::channel(capacity.max(1));
+        let depth = metrics.publish_queue_lane(name);
+        let task = tokio::spawn(publish_queued(
+            rx,
+            Arc::clone(nats),
+            state.clone(),
+            Arc::clone(metrics),
+            Arc::clone(&depth),
+        ));
+        Self { tx, depth, task }
+    }
+}
+
+/// One shard's publish queues
+pub struct PublishPipeline {
+    nats: Arc<NatsPublisher>,
+    state: ShardState,
+    metrics: Arc<GatewayMetrics>,
+    overflow: OverflowPolicy,
+    commands: Lane,
+    events: Lane,
+    /// Events published outside the lanes
+    detached: Mutex<JoinSet<()>>,
+}
+
+impl PublishPipeline {
+    pub fn start(nats: Arc<NatsPublisher>, state: ShardState, metrics: Arc<GatewayMetrics>, config: QueueConfig) -> Self {
+        Self {
+            commands: Lane::start("commands", config.capacity, &nats, &state, &metrics),
+            events: Lane::start("events", config.capacity, &nats, &state, &metrics),
+            overflow: config.overflow,
+            detached: Mutex::default(),
+            nats,
+            state,
+            metrics,
+        }
+    }
+
+    pub fn nats(&self) -> &Arc<NatsPublisher> {
+        &self.nats
+    }
+
+    /// Queue an event for publishing (waits for room under `block`)
+    pub async fn send(&self, payload: GatewayEvent, dispatch: Option<String>, bytes: usize) {
+        let lane = if payload.event_type == "interaction.create" { &self.commands } else { &self.events };
+        let shard_id = payload.shard_id;
+
+        lane.depth.record_enqueue(bytes);
+        self.state.record_queued(shard_id, true);
+        let queued = Queued { payload, dispatch, bytes };
+        let sent = match self.overflow {
+            OverflowPolicy::Block => lane.tx.send(queued).await.map_err(|e| e.0),
+            OverflowPolicy::Drop => lane.tx.try_send(queued).map_err(|e| e.into_inner()),
+        };
+        if let Err(Queued { mut payload, .. }) = sent {
+            // Its number goes unused: the gap reports the loss
+            let subject = self.nats.subject(&payload);
+            number(&mut payload, &subject, &self.state);
+            lane.depth.record_dequeue(bytes);
+            lane.depth.record_overflow();
+            self.state.record_queued(shard_id, false);
+            self.state.record_route_failure(shard_id);
+            self.metrics.record_route_failure(shard_id);
+            debug!(shard_id, "Publish queue full - event dropped");
+        }
+    }
+
+    /// Publish from a task of its own, so the shard keeps reading while
+    /// `task` waits (on Discord, or for the ack)
+    pub fn spawn(&self, task: impl Future<Output = ()> + Send + 'static) {
+        let mut detached = self.detached.lock().unwrap_or_else(|e| e.into_inner());
+        // Forget the ones already done
+        while detached.try_join_next().is_some() {}
+        detached.spawn(task);
+    }
+
+    /// Publish everything still queued or waiting on Discord, then stop
+    pub async fn close(self) {
+        let Self { commands, events, detached, .. } = self;
+        drop((commands.tx, events.tx));
+        let mut detached = detached.into_inner().unwrap_or_else(|e| e.into_inner());
+        let _ = tokio::join!(commands.task, events.task, async {
+            while detached.join_next().await.is_some() {}
+        });
+    }
+}
+
+/// Give an event its shard's next `seq` on the stream of `subject`
+pub(super) fn number(payload: &mut GatewayEvent, subject: &str, state: &ShardState) {
+    if let Some(stream) = stream_for_subject(subject) {
+        let (epoch, seq) = state.next_seq(payload.shard_id, stream);
+        payload.seq_epoch = Some(epoch);
+        payload.seq = Some(seq);
+    }
+}
+
+async fn publish_queued(
+    mut rx: mpsc::Receiver<Queued>,
+    nats: Arc<NatsPublisher>,
+    state: ShardState,
+    metrics: Arc<GatewayMetrics>,
+    depth: Arc<LaneDepth>,
+) {
+    while let Some(Queued { payload, dispatch, bytes }) = rx.recv().await {
+        depth.record_dequeue(bytes);
+        if let Some(dispatch) = dispatch {
+            nats.publish_raw(&payload, dispatch).await;
+        }
+        let shard_id = payload.shard_id;
+        route(&nats, payload, &state, &metrics).await;
+        state.record_queued(shard_id, false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overflow_policy_names() {
+        assert_eq!(OverflowPolicy::parse("drop"), Some(OverflowPolicy::Drop));
+        assert_eq!(OverflowPolicy::parse(" block "), Some(OverflowPolicy::Block));
+        assert_eq!(OverflowPolicy::parse("drop-oldest"), None);
+        assert_eq!(OverflowPolicy::Drop.as_str(), "drop");
+    }
+
+    #[test]
+    fn events_are_numbered_per_stream_as_published() {
+        let state = ShardState::new(0, 0..2, 2, 25);
+        let mut deferred = GatewayEvent::for_test("interaction.create");
+        let mut next = GatewayEvent::for_test("interaction.create");
+        let mut join = GatewayEvent::for_test("member.join");
+
+        // An interaction published after a later one (its deferral took a
+        // round trip to Discord) is numbered after it
+        number(&mut next, "commands.interaction", &state);
+        number(&mut join, "events.member.join", &state);
+        number(&mut deferred, "commands.interaction", &state);
+        assert_eq!((next.seq, deferred.seq, join.seq), (Some(1), Some(2), Some(1)));
+        assert_eq!(deferred.seq_epoch, next.seq_epoch);
+
+        let mut summary = GatewayEvent::for_test("event.summary");
+        number(&mut summary, "canary.json.events.member.join", &state);
+        assert_eq!(summary.seq, None);
+    }
+
+    #[test]
+    fn queued_events_count_towards_the_shard() {
+        let state = ShardState::new(0, 0..2, 2, 25);
+        state.record_queued(1, true);
+        state.record_queued(1, true);
+        state.record_queued(1, false);
+        assert_eq!(state.snapshot(1).unwrap().events_queued, 1);
+
+        // Never below zero
+        state.record_queued(0, false);
+        assert_eq!(state.snapshot(0).unwrap().events_queued, 0);
+    }
+}
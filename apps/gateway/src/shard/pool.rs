@@ -4,32 +4,215 @@
 //! Manages multiple Discord shards per process per SDD §5.1.3
 #![allow(dead_code)] // Scaffolded for multi-shard gateway
 
+use crate::cache::redis::RedisCache;
+use crate::cache::GuildCache;
+use crate::config::PRIVILEGED_INTENTS;
+use crate::discord::audit::{self, RemovalReason};
 use crate::error::GatewayError;
-use crate::events::serialize::serialize_event;
+use crate::discord::defer::{CommandDefer, Deferral};
+use crate::events::aggregate::Aggregator;
+use crate::events::guild_limit::GuildRateLimiter;
+use crate::events::raid::RaidDetector;
+use crate::events::serialize::{
+    capability_degraded_event, dispatch_event_id, now_millis, serialize_event, set_removal_reason, GatewayEvent,
+    STABLE_EVENT_TYPES,
+};
+use crate::flags::{FeatureFlags, AUTO_DEFER, NEW_EVENT_TYPES};
 use crate::metrics::GatewayMetrics;
+use crate::nats::members::MemberRequests;
+use crate::nats::presence::PresenceUpdates;
 use crate::nats::NatsPublisher;
+use crate::overrides::Overrides;
+use crate::shard::canary::CanaryShard;
+use crate::shard::circuit::{self, CircuitBreaker, CircuitConfig};
+use crate::shard::compression::WireMeter;
+use crate::shard::control::{ShardCommand, ShardControl};
+use crate::shard::coordinator::IdentifyCoordinator;
+use crate::shard::handoff::{self, HandoffRequest};
+use crate::shard::pacing::{IdentifyPacing, PacedQueue};
+use crate::shard::pipeline::{self, PublishPipeline, QueueConfig};
+use crate::shard::reshard::Resharding;
+use crate::shard::session::{SavedSession, SessionStore};
 use crate::shard::state::{ShardHealth, ShardState};
+use crate::shard::token::BotToken;
 
+use futures_util::StreamExt as _;
+use std::collections::{BTreeSet, HashMap};
+use std::ops::Range;
 use std::sync::Arc;
-use std::time::Instant;
-use tokio::sync::broadcast;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, mpsc, watch};
+use tokio::task::JoinSet;
 use tracing::{debug, error, info, warn};
-use twilight_gateway::{Config, EventTypeFlags, Intents, Shard, StreamExt as _};
-use twilight_model::gateway::{ShardId, event::Event};
+use twilight_gateway::queue::{InMemoryQueue, Queue};
+use twilight_gateway::{CloseFrame, ConfigBuilder, EventTypeFlags, Intents, Message, Shard};
+use twilight_model::gateway::payload::incoming::MemberRemove;
+use twilight_model::gateway::{SessionStartLimit, ShardId, event::Event};
 
-/// Number of shards per gateway process (pool)
-pub const SHARDS_PER_POOL: u64 = 25;
+/// Shards per gateway process (pool) unless `SHARDS_PER_POOL` is set
+pub const DEFAULT_SHARDS_PER_POOL: u64 = 25;
+
+/// Discord close code for disallowed (privileged, not enabled) intents
+const CLOSE_CODE_DISALLOWED_INTENTS: u16 = 4014;
+
+/// How long a shutting-down shard waits for Discord to acknowledge its close
+const SUSPEND_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Shard IDs owned by a pool
+pub fn shard_range(pool_id: u64, total_shards: u64, shards_per_pool: u64) -> Range<u64> {
+    let start = pool_id.saturating_mul(shards_per_pool).min(total_shards);
+    let end = pool_id.saturating_add(1).saturating_mul(shards_per_pool).min(total_shards);
+    start..end
+}
+
+/// Reject a pool that would own no shards (`POOL_ID` × `SHARDS_PER_POOL` at
+/// or past `TOTAL_SHARDS`)
+pub fn validate_pool(pool_id: u64, total_shards: u64, shards_per_pool: u64) -> Result<(), GatewayError> {
+    if shard_range(pool_id, total_shards, shards_per_pool).is_empty() {
+        let pools = total_shards.div_ceil(shards_per_pool.max(1));
+        return Err(GatewayError::Config(format!(
+            "POOL_ID {pool_id} owns no shards: {total_shards} shards at {shards_per_pool} per pool need pools 0..{pools}"
+        )));
+    }
+    Ok(())
+}
+
+/// Shard that receives a guild's events (Discord's sharding formula)
+pub fn shard_for_guild(guild_id: u64, total_shards: u64) -> u64 {
+    (guild_id >> 22) % total_shards.max(1)
+}
+
+/// Pool whose range holds a shard
+pub fn pool_for_shard(shard_id: u64, shards_per_pool: u64) -> u64 {
+    shard_id / shards_per_pool.max(1)
+}
+
+/// Shards this pool runs: its whole range, or the selected subset of it.
+///
+/// Selected shards outside the pool's range are rejected rather than ignored,
+/// so a typo can't silently start nothing.
+pub fn select_shards(
+    pool_id: u64,
+    total_shards: u64,
+    shards_per_pool: u64,
+    only: Option<&BTreeSet<u64>>,
+) -> Result<Vec<u64>, GatewayError> {
+    let range = shard_range(pool_id, total_shards, shards_per_pool);
+    let Some(only) = only else {
+        return Ok(range.collect());
+    };
+
+    let outside: Vec<u64> = only.iter().copied().filter(|id| !range.contains(id)).collect();
+    if !outside.is_empty() {
+        return Err(GatewayError::Config(format!(
+            "--only-shards {outside:?} outside pool {pool_id}'s range {}..{}",
+            range.start, range.end
+        )));
+    }
+    Ok(only.iter().copied().collect())
+}
+
+/// Optional pool behaviour
+#[derive(Debug, Clone, Default)]
+pub struct PoolOptions {
+    /// Size of each pool's shard range (`SHARDS_PER_POOL`)
+    pub shards_per_pool: u64,
+    /// Stamped on every event the pool publishes (`BOT_ID`, `BOTS`)
+    pub bot_id: Option<String>,
+    /// Shadow-compress payloads to estimate transport bytes
+    pub estimate_wire_bytes: bool,
+    /// Pool start delay and identify jitter
+    pub pacing: IdentifyPacing,
+    /// Run only these shards from the pool's range (debugging)
+    pub only_shards: Option<BTreeSet<u64>>,
+    /// Runtime feature flags
+    pub flags: Arc<FeatureFlags>,
+    /// Classify `member.leave` via the audit log (None disables)
+    pub removal_audit: Option<Arc<twilight_http::Client>>,
+    /// Defer configured slash commands before publishing them (None disables)
+    pub command_defer: Option<Arc<CommandDefer>>,
+    /// Windowed summaries of high-volume event types
+    pub aggregator: Arc<Aggregator>,
+    /// Join raid detection (None disables)
+    pub raid_detector: Option<Arc<RaidDetector>>,
+    /// Per-guild event rate limits (None disables)
+    pub guild_limit: Option<Arc<GuildRateLimiter>>,
+    /// Guilds this layout publishes while resharding (None publishes all)
+    pub resharding: Option<Arc<Resharding>>,
+    /// Event filters, sampling and the circuit breaker threshold, as
+    /// currently overridden
+    pub overrides: Arc<Overrides>,
+    /// Cool-down before a tripped shard's half-open reconnect
+    pub circuit_breaker: CircuitConfig,
+    /// Extra sampling for the canary shard, if this pool runs it
+    pub canary_shard: Option<CanaryShard>,
+    /// Session start limit from `/gateway/bot`, sizing the identify queue
+    /// (None uses a max_concurrency of 1)
+    pub identify_limit: Option<SessionStartLimit>,
+    /// Claims identify slots across pools (None when NATS is off)
+    pub coordinator: Option<Arc<IdentifyCoordinator>>,
+    /// Resume saved sessions on start and save them on shutdown
+    pub sessions: Option<Arc<SessionStore>>,
+    /// Bounded queues between each shard and NATS
+    pub publish_queue: QueueConfig,
+    /// Relays member chunks to the workers that requested them (None when NATS is off)
+    pub member_requests: Option<Arc<MemberRequests>>,
+    /// Presence workers set, sent again to shards that start a new session
+    pub presence: Option<Arc<PresenceUpdates>>,
+    /// Guilds, channels and roles the shards see (None disables)
+    pub guild_cache: Option<Arc<GuildCache>>,
+    /// Writes guild, role and member state to Redis (None disables)
+    pub redis_cache: Option<Arc<RedisCache>>,
+}
+
+/// How a pool's shards route events, shared between them
+#[derive(Clone)]
+struct Routing {
+    bot_id: Option<String>,
+    flags: Arc<FeatureFlags>,
+    removal_audit: Option<Arc<twilight_http::Client>>,
+    command_defer: Option<Arc<CommandDefer>>,
+    aggregator: Arc<Aggregator>,
+    raid_detector: Option<Arc<RaidDetector>>,
+    guild_limit: Option<Arc<GuildRateLimiter>>,
+    resharding: Option<Arc<Resharding>>,
+    overrides: Arc<Overrides>,
+    circuit: Arc<CircuitBreaker>,
+    canary_shard: Option<CanaryShard>,
+    member_requests: Option<Arc<MemberRequests>>,
+    presence: Option<Arc<PresenceUpdates>>,
+    guild_cache: Option<Arc<GuildCache>>,
+    redis_cache: Option<Arc<RedisCache>>,
+    /// Where each shard registers its connection for gateway commands
+    control: ShardControl,
+}
 
 /// Shard pool managing multiple Discord shards
 pub struct ShardPool {
     pool_id: u64,
-    shards: Vec<Shard>,
+    shards: Vec<Shard<PacedQueue>>,
     nats: Option<Arc<NatsPublisher>>,
     state: ShardState,
     metrics: Arc<GatewayMetrics>,
+    estimate_wire_bytes: bool,
+    routing: Routing,
+    sessions: Option<Arc<SessionStore>>,
+    publish_queue: QueueConfig,
+    control: ShardControl,
+    commands: mpsc::Receiver<(u64, ShardCommand)>,
+    /// Token for shards the supervisor (re)builds; rotated without a restart
+    token: BotToken,
+    /// Intents and identify queue for shards the supervisor builds from
+    /// scratch
+    intents: Intents,
+    queue: PacedQueue,
     shutdown_tx: broadcast::Sender<()>,
 }
 
+/// A shard task hands its shard back when it stops, with the session to
+/// resume if it closed cleanly
+type ShardTask = (Shard<PacedQueue>, Option<SavedSession>);
+
 impl ShardPool {
     /// Create a new shard pool
     ///
@@ -40,6 +223,7 @@ impl ShardPool {
     /// * `intents` - Discord gateway intents
     /// * `nats` - Optional NATS publisher (None for local testing)
     /// * `metrics` - Prometheus metrics
+    /// * `options` - Wire byte estimation, identify pacing, shard subset
     pub async fn new(
         pool_id: u64,
         total_shards: u64,
@@ -47,11 +231,13 @@ impl ShardPool {
         intents: Intents,
         nats: Option<Arc<NatsPublisher>>,
         metrics: Arc<GatewayMetrics>,
+        options: PoolOptions,
     ) -> Result<Self, GatewayError> {
-        let start_shard = pool_id * SHARDS_PER_POOL;
-        let end_shard = ((pool_id + 1) * SHARDS_PER_POOL).min(total_shards);
+        let shards_per_pool = options.shards_per_pool;
+        let range = shard_range(pool_id, total_shards, shards_per_pool);
+        let (start_shard, end_shard) = (range.start, range.end);
 
-        let shard_ids: Vec<u64> = (start_shard..end_shard).collect();
+        let shard_ids = select_shards(pool_id, total_shards, shards_per_pool, options.only_shards.as_ref())?;
 
         info!(
             pool_id,
@@ -60,26 +246,55 @@ impl ShardPool {
             shard_count = shard_ids.len(),
             "Creating shard pool"
         );
+        if options.only_shards.is_some() {
+            warn!(pool_id, ?shard_ids, "Running a subset of the pool's shards (--only-shards)");
+        }
+
+        let pacing = options.pacing;
 
-        let state = ShardState::new(pool_id, shard_ids.iter().copied(), total_shards);
+        let state = ShardState::new(pool_id, shard_ids.iter().copied(), total_shards, shards_per_pool);
 
         // BB60-19: Safe u64 → u32 cast at Twilight API boundary
         let total_shards_u32 = u32::try_from(total_shards)
             .map_err(|_| GatewayError::ShardIdOverflow { value: total_shards })?;
 
+        // One identify queue for the whole pool, so max_concurrency and the
+        // pacing delay apply across its shards rather than per shard
+        let inner = match &options.identify_limit {
+            Some(limit) => InMemoryQueue::new(
+                limit.max_concurrency,
+                limit.remaining,
+                Duration::from_millis(limit.reset_after),
+                limit.total,
+            ),
+            None => InMemoryQueue::default(),
+        };
+        let queue = PacedQueue::new(inner, pacing, pool_id).with_coordinator(options.coordinator);
+        info!(
+            pool_id,
+            delay_ms = pacing.pool_delay(pool_id).as_millis() as u64,
+            jitter_ms = pacing.jitter.as_millis() as u64,
+            "Identify pacing configured"
+        );
+
+        let mut saved = match &options.sessions {
+            Some(store) => store.load(&shard_ids, total_shards, now_millis()).await,
+            None => HashMap::new(),
+        };
+
         let mut shards = Vec::with_capacity(shard_ids.len());
 
         for shard_id in shard_ids {
             let shard_id_u32 = u32::try_from(shard_id)
                 .map_err(|_| GatewayError::ShardIdOverflow { value: shard_id })?;
-            let config = Config::new(token.clone(), intents);
-
-            let shard = Shard::with_config(ShardId::new(shard_id_u32, total_shards_u32), config);
+            let config = ConfigBuilder::new(token.clone(), intents).queue(queue.clone());
+            let shard = build_shard(ShardId::new(shard_id_u32, total_shards_u32), config, saved.remove(&shard_id));
 
             shards.push(shard);
         }
 
         let (shutdown_tx, _) = broadcast::channel(1);
+        let (control, commands) = ShardControl::channel();
 
         Ok(Self {
             pool_id,
@@ -87,6 +302,32 @@ impl ShardPool {
             nats,
             state,
             metrics,
+            estimate_wire_bytes: options.estimate_wire_bytes,
+            routing: Routing {
+                bot_id: options.bot_id,
+                flags: options.flags,
+                removal_audit: options.removal_audit,
+                command_defer: options.command_defer,
+                aggregator: options.aggregator,
+                raid_detector: options.raid_detector,
+                guild_limit: options.guild_limit,
+                resharding: options.resharding,
+                overrides: options.overrides,
+                circuit: Arc::new(CircuitBreaker::new(options.circuit_breaker)),
+                canary_shard: options.canary_shard,
+                member_requests: options.member_requests,
+                presence: options.presence,
+                guild_cache: options.guild_cache,
+                redis_cache: options.redis_cache,
+                control: control.clone(),
+            },
+            sessions: options.sessions,
+            publish_queue: options.publish_queue,
+            control,
+            commands,
+            token: BotToken::new(token),
+            intents,
+            queue,
             shutdown_tx,
         })
     }
@@ -101,205 +342,936 @@ impl ShardPool {
         self.state.clone()
     }
 
+    /// Sends restart and drain commands to this pool's shards
+    pub fn control(&self) -> ShardControl {
+        self.control.clone()
+    }
+
+    /// The pool's token, read whenever a shard is restarted
+    pub fn token(&self) -> BotToken {
+        self.token.clone()
+    }
+
     /// Run all shards in the pool
     ///
-    /// This spawns a task for each shard and waits for all to complete.
-    pub async fn run(self) -> Result<(), GatewayError> {
-        let mut handles = Vec::with_capacity(self.shards.len());
-
-        for shard in self.shards {
-            let shard_id: u64 = shard.id().number().into();
-            let nats = self.nats.clone();
-            let state = self.state.clone();
-            let metrics = Arc::clone(&self.metrics);
-            let mut shutdown_rx = self.shutdown_tx.subscribe();
-
-            let handle = tokio::spawn(async move {
-                tokio::select! {
-                    result = run_shard(shard, nats, state, metrics) => {
-                        if let Err(e) = result {
-                            error!(shard_id, error = %e, "Shard task failed");
+    /// Supervises a task per shard and applies operator commands until
+    /// shutdown, or until every shard has ended without one being drained or
+    /// waiting to retry its circuit.
+    pub async fn run(mut self) -> Result<(), GatewayError> {
+        let pool_id = self.pool_id;
+        let mut shutdown_rx = self.shutdown_tx.subscribe();
+        let mut tasks = JoinSet::new();
+        // Stop signal and task of each running shard
+        let mut running = HashMap::new();
+        // Commands waiting for their shard's task to stop
+        let mut pending = HashMap::new();
+        // Drained and ended shards, kept for a restart
+        let mut stopped: HashMap<u64, ShardTask> = HashMap::new();
+
+        for shard in std::mem::take(&mut self.shards) {
+            running.insert(shard_number(&shard), self.spawn_shard(&mut tasks, shard));
+        }
+
+        loop {
+            tokio::select! {
+                Some(joined) = tasks.join_next_with_id() => {
+                    match joined {
+                        Ok((_, (shard, saved))) => {
+                            let shard_id = shard_number(&shard);
+                            running.remove(&shard_id);
+
+                            match pending.remove(&shard_id) {
+                                Some(ShardCommand::Restart) => {
+                                    info!(shard_id, resuming = saved.is_some(), "Restarting shard");
+                                    running.insert(shard_id, self.spawn_shard(&mut tasks, rebuild_shard(&shard, self.token.get(), saved)));
+                                }
+                                Some(ShardCommand::Drain) => {
+                                    self.state.set_health(shard_id, ShardHealth::Drained);
+                                    info!(shard_id, "Shard drained");
+                                    stopped.insert(shard_id, (shard, saved));
+                                }
+                                Some(ShardCommand::Handoff { to_pool }) => {
+                                    // Kept (drained) until the target answers, in case it doesn't.
+                                    // The session goes to the store: whichever pool takes it
+                                    // from there resumes it, so this pool keeps no copy
+                                    self.state.set_health(shard_id, ShardHealth::Drained);
+                                    let saved = self.hand_off(shard_id, to_pool, saved);
+                                    stopped.insert(shard_id, (shard, saved));
+                                }
+                                Some(ShardCommand::Adopt { .. } | ShardCommand::Reclaim | ShardCommand::Release) | None => {
+                                    self.retry_when_cool(shard_id);
+                                    stopped.insert(shard_id, (shard, None));
+                                }
+                            }
+                        }
+                        // The task took the shard with it: build a fresh one and keep
+                        // it stopped like a dead shard, so a restart brings it back
+                        Err(e) => {
+                            let lost = running.iter().find_map(|(shard_id, (_, task))| (*task == e.id()).then_some(*shard_id));
+                            error!(pool_id, shard_id = ?lost, error = %e, "Shard task panicked");
+                            if let Some(shard_id) = lost {
+                                running.remove(&shard_id);
+                                pending.remove(&shard_id);
+                                self.state.set_health(shard_id, ShardHealth::Dead);
+                                end_session(&self.state, &self.metrics, shard_id);
+                                match self.new_shard(shard_id, None) {
+                                    Ok(shard) => {
+                                        stopped.insert(shard_id, (shard, None));
+                                    }
+                                    Err(e) => error!(shard_id, error = %e, "Failed to rebuild panicked shard"),
+                                }
+                            }
                         }
                     }
-                    _ = shutdown_rx.recv() => {
-                        info!(shard_id, "Shard received shutdown signal");
+
+                    // Drained shards wait for an operator, tripped ones for their cool-down
+                    let waiting = stopped.keys().any(|id| {
+                        self.state.get_health(*id) == Some(ShardHealth::Drained) || self.routing.circuit.retry(*id).is_some()
+                    });
+                    if running.is_empty() && !waiting {
+                        break;
                     }
                 }
-            });
-
-            handles.push(handle);
+                Some((shard_id, command)) = self.commands.recv() => {
+                    info!(shard_id, command = command.as_str(), "Shard command received");
+                    if let ShardCommand::Adopt { session } = command {
+                        if running.contains_key(&shard_id) {
+                            warn!(shard_id, "Adopt for a shard this pool is already running");
+                            continue;
+                        }
+                        let saved = take_session(self.sessions.clone(), shard_id, self.state.total_shards()).await;
+                        if session && saved.is_none() {
+                            warn!(shard_id, "Handed-off session was taken back before this pool could resume it");
+                            self.metrics.record_shard_handoff("target", "rejected");
+                            continue;
+                        }
+                        let shard = match self.adopt(shard_id, stopped.remove(&shard_id), saved) {
+                            Ok(shard) => shard,
+                            Err(e) => {
+                                error!(shard_id, error = %e, "Failed to adopt shard");
+                                self.metrics.record_shard_handoff("target", "rejected");
+                                continue;
+                            }
+                        };
+                        self.metrics.record_shard_handoff("target", "adopted");
+                        running.insert(shard_id, self.spawn_shard(&mut tasks, shard));
+                    } else if command == ShardCommand::Reclaim {
+                        let Some((shard, _)) = stopped.remove(&shard_id) else {
+                            continue;
+                        };
+                        match take_session(self.sessions.clone(), shard_id, self.state.total_shards()).await {
+                            Some(saved) => {
+                                info!(shard_id, "Resuming shard whose handoff failed");
+                                self.metrics.record_shard_handoff("source", "failed");
+                                let shard = rebuild_shard(&shard, self.token.get(), Some(saved));
+                                running.insert(shard_id, self.spawn_shard(&mut tasks, shard));
+                            }
+                            None => {
+                                self.state.release(shard_id);
+                                self.metrics.record_shard_handoff("source", "handed_off");
+                                info!(shard_id, "Handed-off session already taken, shard released to the other pool");
+                            }
+                        }
+                    } else if command == ShardCommand::Release {
+                        if stopped.remove(&shard_id).is_some() {
+                            self.state.release(shard_id);
+                            info!(shard_id, "Shard released to another pool");
+                        }
+                    } else if let Some((stop, _)) = running.get(&shard_id) {
+                        pending.insert(shard_id, command);
+                        let _ = stop.send(true);
+                    } else if command == ShardCommand::Restart {
+                        match stopped.remove(&shard_id) {
+                            Some((shard, saved)) => {
+                                if let Some(transition) = self.routing.circuit.half_open(shard_id) {
+                                    info!(shard_id, trips = transition.trips, "Circuit half-open, trying one reconnect");
+                                    self.report_circuit(transition);
+                                }
+                                info!(shard_id, resuming = saved.is_some(), "Starting stopped shard");
+                                let shard = rebuild_shard(&shard, self.token.get(), saved);
+                                running.insert(shard_id, self.spawn_shard(&mut tasks, shard));
+                            }
+                            None => warn!(shard_id, "Restart for a shard this pool doesn't run"),
+                        }
+                    }
+                }
+                _ = shutdown_rx.recv() => break,
+            }
         }
 
-        // Wait for all shards
-        for handle in handles {
-            let _ = handle.await;
+        // Stop what's still running and save every session worth resuming
+        for (stop, _) in running.values() {
+            let _ = stop.send(true);
+        }
+        let mut saved: Vec<_> = stopped
+            .into_iter()
+            .filter_map(|(shard_id, (_, saved))| Some((shard_id, saved?)))
+            .collect();
+        while let Some(joined) = tasks.join_next().await {
+            if let Ok((shard, Some(session))) = joined {
+                saved.push((shard_number(&shard), session));
+            }
+        }
+        if let Some(store) = self.sessions.as_ref().filter(|_| !saved.is_empty()) {
+            store.save(&saved).await;
         }
 
-        info!(pool_id = self.pool_id, "Shard pool shut down");
+        info!(pool_id, "Shard pool shut down");
         Ok(())
     }
 
+    /// Restart a shard whose circuit opened once its cool-down ends, unless
+    /// it was restarted (or tripped again) in the meantime
+    fn retry_when_cool(&self, shard_id: u64) {
+        let circuit = Arc::clone(&self.routing.circuit);
+        let Some((trips, cooldown)) = circuit.retry(shard_id) else {
+            return;
+        };
+        let control = self.control.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(cooldown).await;
+            if circuit.still_open(shard_id, trips) {
+                control.send(shard_id, ShardCommand::Restart);
+            }
+        });
+    }
+
+    fn report_circuit(&self, transition: circuit::Transition) {
+        let nats = self.nats.clone().filter(|_| self.routing.flags.is_enabled(NEW_EVENT_TYPES));
+        if let Some(publish) = circuit::report(nats, &self.metrics, self.routing.bot_id.clone(), transition) {
+            tokio::spawn(publish);
+        }
+    }
+
+    /// Ask another pool to resume a stopped shard; the outcome comes back as
+    /// a `Release`, `Reclaim` or `Restart`. Without NATS the session is
+    /// handed back to resume the shard here.
+    fn hand_off(&self, shard_id: u64, to_pool: u64, saved: Option<SavedSession>) -> Option<SavedSession> {
+        let request = HandoffRequest {
+            shard_id,
+            total_shards: self.state.total_shards(),
+            from_pool: self.pool_id,
+            to_pool,
+            session: false,
+        };
+        match &self.nats {
+            Some(nats) => {
+                info!(shard_id, to_pool, "Handing shard off");
+                let (control, metrics) = (self.control.clone(), Arc::clone(&self.metrics));
+                tokio::spawn(handoff::hand_off(Arc::clone(nats), control, metrics, self.sessions.clone(), saved, request));
+                None
+            }
+            None => {
+                warn!(shard_id, to_pool, "Shard handoff needs NATS, resuming it here");
+                self.control.send(shard_id, ShardCommand::Restart);
+                saved
+            }
+        }
+    }
+
+    /// A shard handed over by another pool, resuming the session it saved
+    fn adopt(&self, shard_id: u64, stopped: Option<ShardTask>, saved: Option<SavedSession>) -> Result<Shard<PacedQueue>, GatewayError> {
+        info!(shard_id, resuming = saved.is_some(), "Adopting shard");
+
+        let shard = match stopped {
+            Some((shard, _)) => rebuild_shard(&shard, self.token.get(), saved),
+            None => self.new_shard(shard_id, saved)?,
+        };
+        self.state.adopt(shard_id);
+        Ok(shard)
+    }
+
+    /// A shard built from the pool's config alone, for one it has no shard
+    /// to rebuild from
+    fn new_shard(&self, shard_id: u64, saved: Option<SavedSession>) -> Result<Shard<PacedQueue>, GatewayError> {
+        let total_shards = self.state.total_shards();
+        let id = ShardId::new(
+            u32::try_from(shard_id).map_err(|_| GatewayError::ShardIdOverflow { value: shard_id })?,
+            u32::try_from(total_shards).map_err(|_| GatewayError::ShardIdOverflow { value: total_shards })?,
+        );
+        let config = ConfigBuilder::new(self.token.get(), self.intents).queue(self.queue.clone());
+        Ok(build_shard(id, config, saved))
+    }
+
+    /// Start a shard's task; returns its stop signal and task ID
+    fn spawn_shard(&self, tasks: &mut JoinSet<ShardTask>, mut shard: Shard<PacedQueue>) -> (watch::Sender<bool>, tokio::task::Id) {
+        let (stop_tx, mut stop) = watch::channel(false);
+        let pipeline = self.nats.clone().map(|nats| {
+            PublishPipeline::start(nats, self.state.clone(), Arc::clone(&self.metrics), self.publish_queue)
+        });
+        let state = self.state.clone();
+        let metrics = Arc::clone(&self.metrics);
+        let wire_meter = WireMeter::new(self.estimate_wire_bytes);
+        let routing = self.routing.clone();
+
+        let task = tasks.spawn(async move {
+            let shard_id = shard_number(&shard);
+            let exit = run_shard(&mut shard, pipeline.as_ref(), state, metrics, wire_meter, routing, &mut stop).await;
+            let saved = match exit {
+                Ok(ShardExit::Stopped) => suspend(&mut shard).await,
+                Ok(ShardExit::Ended) => None,
+                Err(e) => {
+                    error!(shard_id, error = %e, "Shard task failed");
+                    None
+                }
+            };
+            // Events received before the stop are published before the
+            // shard (and its saved session) is handed back
+            if let Some(pipeline) = pipeline {
+                pipeline.close().await;
+            }
+            (shard, saved)
+        });
+        (stop_tx, task.id())
+    }
+
     /// Signal shutdown to all shards
     pub fn shutdown(&self) {
         let _ = self.shutdown_tx.send(());
     }
+
+    /// Sender that signals shutdown once `run` has taken the pool
+    pub fn shutdown_handle(&self) -> broadcast::Sender<()> {
+        self.shutdown_tx.clone()
+    }
+}
+
+/// Why a shard's event loop returned
+enum ShardExit {
+    /// The event stream ended
+    Ended,
+    /// A stop (shutdown, restart or drain) was signalled between events
+    Stopped,
+}
+
+fn shard_number(shard: &Shard<PacedQueue>) -> u64 {
+    shard.id().number().into()
+}
+
+/// Take a shard's saved session out of the store, if there is one
+async fn take_session(sessions: Option<Arc<SessionStore>>, shard_id: u64, total_shards: u64) -> Option<SavedSession> {
+    sessions?.take(shard_id, total_shards, now_millis()).await
+}
+
+/// Build a shard that resumes `saved` when given
+fn build_shard(id: ShardId, mut config: ConfigBuilder<PacedQueue>, saved: Option<SavedSession>) -> Shard<PacedQueue> {
+    if let Some(saved) = saved {
+        debug!(shard_id = id.number(), sequence = saved.session.sequence(), "Resuming saved session");
+        config = config.session(saved.session);
+        if let Some(url) = saved.resume_url {
+            config = config.resume_url(url);
+        }
+    }
+    Shard::with_config(id, config.build())
+}
+
+/// A new connection for a stopped shard, with the same (possibly degraded)
+/// intents and identify queue, and the pool's current token
+fn rebuild_shard(shard: &Shard<PacedQueue>, token: String, saved: Option<SavedSession>) -> Shard<PacedQueue> {
+    let config = shard.config();
+    let builder = ConfigBuilder::new(token, config.intents()).queue(config.queue().clone());
+    build_shard(shard.id(), builder, saved)
+}
+
+/// Close a shard without invalidating its session, returning the session to
+/// resume on the next start.
+///
+/// The session is taken before closing: anything Discord sends afterwards is
+/// replayed on resume rather than lost.
+async fn suspend(shard: &mut Shard<PacedQueue>) -> Option<SavedSession> {
+    let saved = SavedSession {
+        session: shard.session()?.clone(),
+        resume_url: shard.resume_url().map(str::to_string),
+        total_shards: shard.id().total().into(),
+        saved_at: now_millis(),
+    };
+
+    // Codes other than 1000/1001 keep the session alive; read until the
+    // close is acknowledged so it reaches Discord
+    shard.close(CloseFrame::RESUME);
+    let closed = async {
+        while let Some(message) = shard.next().await {
+            if matches!(message, Ok(Message::Close(_))) {
+                break;
+            }
+        }
+    };
+    let _ = tokio::time::timeout(SUSPEND_TIMEOUT, closed).await;
+
+    Some(saved)
 }
 
 /// Run a single shard's event loop
 async fn run_shard(
-    mut shard: Shard,
-    nats: Option<Arc<NatsPublisher>>,
+    shard: &mut Shard<PacedQueue>,
+    pipeline: Option<&PublishPipeline>,
     state: ShardState,
     metrics: Arc<GatewayMetrics>,
-) -> Result<(), GatewayError> {
+    mut wire_meter: Option<WireMeter>,
+    routing: Routing,
+    stop: &mut watch::Receiver<bool>,
+) -> Result<ShardExit, GatewayError> {
+    let Routing {
+        bot_id,
+        flags,
+        removal_audit,
+        command_defer,
+        aggregator,
+        raid_detector,
+        guild_limit,
+        resharding,
+        overrides,
+        circuit,
+        canary_shard,
+        member_requests,
+        presence,
+        guild_cache,
+        redis_cache,
+        control,
+    } = routing;
+    let nats = pipeline.map(PublishPipeline::nats);
     let shard_id: u64 = shard.id().number().into();
     let pool_id = state.pool_id();
+    control.register(shard_id, shard.sender());
 
     state.set_health(shard_id, ShardHealth::Connecting);
 
     info!(shard_id, pool_id, "Shard starting");
 
-    // Circuit breaker: mark shard dead after N consecutive errors without success
-    const MAX_CONSECUTIVE_ERRORS: u32 = 10;
+    // Circuit breaker: mark shard dead after N consecutive errors without
+    // success (N can be overridden at runtime; 1 during a half-open attempt)
     let mut consecutive_errors: u32 = 0;
+    let mut last_close_code: Option<u16> = None;
 
-    while let Some(item) = shard.next_event(EventTypeFlags::all()).await {
-        let event = match item {
-            Ok(event) => {
-                consecutive_errors = 0;
-                event
-            }
-            Err(source) => {
-                consecutive_errors += 1;
-                warn!(shard_id, error = %source, consecutive = consecutive_errors, "Error receiving event");
+    loop {
+        // Read raw messages (rather than `next_event`) so payload bytes can be
+        // counted before parsing
+        loop {
+            // Stop only between events, so the saved session never skips one
+            let message = tokio::select! {
+                message = shard.next() => message,
+                _ = stop.changed() => {
+                    info!(shard_id, "Shard received stop signal");
+                    return Ok(ShardExit::Stopped);
+                }
+            };
+            let Some(message) = message else {
+                break;
+            };
+            let mut dispatch = None;
+            let mut message_bytes = 0;
+            let item = match message {
+                Ok(Message::Close(frame)) => Ok(Event::GatewayClose(frame)),
+                Ok(Message::Text(json)) => {
+                    message_bytes = json.len();
+                    let wire = wire_meter.as_mut().map(|m| m.observe(json.as_bytes()));
+                    metrics.record_transport_bytes(shard_id, json.len() as u64, wire);
+                    // Raw passthrough needs the payload after parsing consumes it
+                    if nats.is_some_and(|nats| nats.wants_raw()) {
+                        dispatch = Some(json.clone());
+                    }
 
-                // Immediate fatal: reconnect failure
-                if matches!(source.kind(), twilight_gateway::error::ReceiveMessageErrorType::Reconnect) {
-                    let err = GatewayError::ShardReconnectFailed {
-                        shard_id,
-                        source: Box::new(source),
-                    };
-                    metrics.record_error(shard_id, err.error_type_label());
-                    state.set_health(shard_id, ShardHealth::Dead);
-                    error!(shard_id, "Fatal gateway error (reconnect failed)");
-                    return Err(err);
+                    match twilight_gateway::parse(json, EventTypeFlags::all()) {
+                        Ok(Some(event)) => Ok(event.into()),
+                        Ok(None) => continue,
+                        Err(source) => Err(source),
+                    }
                 }
+                Err(source) => Err(source),
+            };
 
-                // Circuit breaker: too many consecutive errors
-                if consecutive_errors >= MAX_CONSECUTIVE_ERRORS {
-                    let err = GatewayError::ShardCircuitBroken {
-                        shard_id,
-                        count: consecutive_errors,
-                        max: MAX_CONSECUTIVE_ERRORS,
-                    };
-                    metrics.record_error(shard_id, err.error_type_label());
-                    state.set_health(shard_id, ShardHealth::Dead);
-                    error!(shard_id, consecutive = consecutive_errors, "Shard dead: consecutive error threshold exceeded");
-                    return Err(err);
+            let event = match item {
+                Ok(event) => {
+                    consecutive_errors = 0;
+                    event
                 }
+                Err(source) => {
+                    consecutive_errors += 1;
+                    warn!(shard_id, error = %source, consecutive = consecutive_errors, "Error receiving event");
 
-                // Non-fatal transient error
-                metrics.record_error(shard_id, "receive_error");
-                state.set_health(shard_id, ShardHealth::Disconnected);
-                continue;
-            }
-        };
+                    // Immediate fatal: reconnect failure
+                    if matches!(source.kind(), twilight_gateway::error::ReceiveMessageErrorType::Reconnect) {
+                        let err = GatewayError::ShardReconnectFailed {
+                            shard_id,
+                            source: Box::new(source),
+                        };
+                        metrics.record_error(shard_id, err.error_type_label());
+                        state.set_health(shard_id, ShardHealth::Dead);
+                        end_session(&state, &metrics, shard_id);
+                        error!(shard_id, "Fatal gateway error (reconnect failed)");
+                        return Err(err);
+                    }
 
-        // Record event received
-        state.record_event(shard_id);
-        metrics.record_event(shard_id, &event);
-
-        // Handle special events
-        match &event {
-            Event::Ready(ready) => {
-                state.set_health(shard_id, ShardHealth::Ready);
-                state.set_guilds(shard_id, ready.guilds.len() as u64);
-                metrics.set_guilds(shard_id, ready.guilds.len() as u64);
-                info!(
-                    shard_id,
-                    guilds = ready.guilds.len(),
-                    session_id = %ready.session_id,
-                    "Shard ready"
-                );
-            }
-            Event::Resumed => {
-                state.set_health(shard_id, ShardHealth::Ready);
-                info!(shard_id, "Shard resumed");
+                    // Circuit breaker: too many consecutive errors
+                    let max_errors = circuit.threshold(shard_id, overrides.max_consecutive_errors());
+                    if consecutive_errors >= max_errors {
+                        let err = GatewayError::ShardCircuitBroken {
+                            shard_id,
+                            count: consecutive_errors,
+                            max: max_errors,
+                        };
+                        metrics.record_error(shard_id, err.error_type_label());
+                        state.set_health(shard_id, ShardHealth::Dead);
+                        end_session(&state, &metrics, shard_id);
+                        let transition = circuit.trip(shard_id, consecutive_errors);
+                        error!(
+                            shard_id,
+                            consecutive = consecutive_errors,
+                            trips = transition.trips,
+                            retry_in_secs = transition.cooldown.map(|cooldown| cooldown.as_secs()),
+                            "Shard dead: circuit opened"
+                        );
+                        let nats = nats.filter(|_| flags.is_enabled(NEW_EVENT_TYPES)).cloned();
+                        let publish = circuit::report(nats, &metrics, bot_id.clone(), transition);
+                        if let (Some(pipeline), Some(publish)) = (pipeline, publish) {
+                            pipeline.spawn(publish);
+                        }
+                        return Err(err);
+                    }
+
+                    // Non-fatal transient error
+                    metrics.record_error(shard_id, "receive_error");
+                    state.set_health(shard_id, ShardHealth::Disconnected);
+                    continue;
+                }
+            };
+
+            // Record event received
+            state.record_event(shard_id);
+            metrics.record_event(shard_id, &event);
+            if let Some(cache) = &guild_cache {
+                cache.update(&event);
             }
-            Event::GatewayHeartbeatAck => {
-                state.record_heartbeat(shard_id);
-                metrics.record_heartbeat(shard_id);
+            if let Some(redis) = &redis_cache {
+                redis.observe(&event);
             }
-            Event::GuildCreate(guild) => {
-                // NOTE: Guild count is approximate (non-atomic read-modify-write).
-                // Suitable for metrics/observability only, not authorization decisions.
-                let current = state.total_guilds();
-                state.set_guilds(shard_id, current + 1);
-                debug!(shard_id, guild_id = %guild.id(), "Guild joined");
+
+            if matches!(event, Event::Ready(_) | Event::Resumed) {
+                if let Some(transition) = circuit.recovered(shard_id) {
+                    info!(shard_id, trips = transition.trips, "Circuit closed: shard recovered");
+                    let nats = nats.filter(|_| flags.is_enabled(NEW_EVENT_TYPES)).cloned();
+                    let publish = circuit::report(nats, &metrics, bot_id.clone(), transition);
+                    if let (Some(pipeline), Some(publish)) = (pipeline, publish) {
+                        pipeline.spawn(publish);
+                    }
+                }
             }
-            Event::GuildDelete(guild) => {
-                // Decrement guild count on leave (unavailable is Option<bool> in 0.17)
-                // Same approximate-count caveat as GuildCreate above.
-                if guild.unavailable != Some(true) {
+
+            // Handle special events
+            match &event {
+                Event::Ready(ready) => {
+                    state.set_health(shard_id, ShardHealth::Ready);
+                    state.set_guilds(shard_id, ready.guilds.len() as u64);
+                    metrics.set_guilds(shard_id, ready.guilds.len() as u64);
+                    metrics.record_identify(shard_id);
+                    if let Some(lifetime) =
+                        state.start_session(shard_id, &ready.session_id, &ready.resume_gateway_url)
+                    {
+                        metrics.record_session_lifetime(shard_id, lifetime);
+                    }
+                    info!(
+                        shard_id,
+                        guilds = ready.guilds.len(),
+                        session_id = %ready.session_id,
+                        "Shard ready"
+                    );
+                    if let Some(presence) = &presence {
+                        presence.restore(shard_id, &control);
+                    }
+                }
+                Event::Resumed => {
+                    state.set_health(shard_id, ShardHealth::Ready);
+                    metrics.record_resume(shard_id);
+                    state.record_resume(shard_id);
+                    // Twilight resumes against resume_gateway_url, but drops
+                    // it after a failed connect and falls back to the default
+                    // gateway URL, which Discord may reject or route poorly
+                    if shard.resume_url().is_none() {
+                        metrics.record_resume_fallback(shard_id);
+                        warn!(shard_id, "Shard resumed via default gateway URL (resume_gateway_url was dropped)");
+                    } else {
+                        info!(shard_id, "Shard resumed");
+                    }
+                }
+                Event::GatewayInvalidateSession(resumable) => {
+                    state.set_health(shard_id, ShardHealth::Resuming);
+                    metrics.record_invalid_session(shard_id, *resumable);
+                    if !resumable {
+                        end_session(&state, &metrics, shard_id);
+                    }
+                    warn!(shard_id, resumable, "Session invalidated by Discord");
+                }
+                Event::GatewayReconnect => {
+                    state.set_health(shard_id, ShardHealth::Resuming);
+                    metrics.record_forced_reconnect(shard_id);
+                    info!(shard_id, "Discord requested reconnect");
+                }
+                Event::GatewayClose(frame) => {
+                    last_close_code = frame.as_ref().map(|f| f.code);
+                    state.set_health(shard_id, ShardHealth::Disconnected);
+                    // The next connection starts a fresh compression context
+                    if let Some(ref mut meter) = wire_meter {
+                        meter.reset();
+                    }
+                    debug!(shard_id, close_code = ?last_close_code, "Gateway closed connection");
+                }
+                Event::GatewayHeartbeatAck => {
+                    state.record_heartbeat(shard_id);
+                    metrics.record_heartbeat(shard_id);
+                    // Twilight keeps the latest round trips newest first
+                    if let Some(latency) = shard.latency().recent().first() {
+                        metrics.set_shard_latency(shard_id, *latency);
+                    }
+                }
+                Event::GuildCreate(guild) => {
+                    // NOTE: Guild count is approximate (non-atomic read-modify-write).
+                    // Suitable for metrics/observability only, not authorization decisions.
                     let current = state.total_guilds();
-                    if current > 0 {
-                        state.set_guilds(shard_id, current - 1);
+                    state.set_guilds(shard_id, current + 1);
+                    debug!(shard_id, guild_id = %guild.id(), "Guild joined");
+                }
+                Event::GuildDelete(guild) => {
+                    // Decrement guild count on leave (unavailable is Option<bool> in 0.17)
+                    // Same approximate-count caveat as GuildCreate above.
+                    if guild.unavailable != Some(true) {
+                        let current = state.total_guilds();
+                        if current > 0 {
+                            state.set_guilds(shard_id, current - 1);
+                        }
+                    }
+                    debug!(shard_id, guild_id = %guild.id, "Guild left");
+                }
+                Event::MemberChunk(chunk) => {
+                    let delivery = member_requests.as_ref().and_then(|requests| requests.deliver(chunk));
+                    if let (Some(pipeline), Some(delivery)) = (pipeline, delivery) {
+                        pipeline.spawn(delivery);
                     }
                 }
-                debug!(shard_id, guild_id = %guild.id, "Guild left");
+                _ => {}
+            }
+
+            // Route event to NATS if available; everything else counts as filtered
+            // The session has already advanced to this dispatch's sequence
+            let session = shard.session().map(|session| (session.id(), session.sequence()));
+            let routable = pipeline.and_then(|pipeline| {
+                serialize_event(&event, shard_id)
+                    .map(|payload| GatewayEvent {
+                        event_id: dispatch_event_id(&event, &payload.event_type, session)
+                            .unwrap_or(payload.event_id),
+                        bot_id: bot_id.clone(),
+                        ..payload
+                    })
+                    .filter(|payload| publish_allowed(&flags, &payload.event_type))
+                    // Before anything counts the event, so only the owning layout does
+                    .filter(|payload| {
+                        let owned = resharding.as_ref().is_none_or(|resharding| resharding.admits(payload));
+                        if !owned {
+                            metrics.record_reshard_suppressed(&payload.event_type);
+                        }
+                        owned
+                    })
+                    // Bursts are a newer event type too; a raid's joins are counted in them
+                    .filter(|payload| {
+                        let absorbed = flags.is_enabled(NEW_EVENT_TYPES)
+                            && raid_detector.as_ref().is_some_and(|detector| detector.observe(payload));
+                        if absorbed {
+                            metrics.record_raid_join();
+                        }
+                        !absorbed
+                    })
+                    // Summaries are a newer event type; without them, keep the raw stream
+                    .filter(|payload| !(flags.is_enabled(NEW_EVENT_TYPES) && aggregator.observe(payload)))
+                    // After aggregation, so summaries still count every event
+                    .filter(|payload| {
+                        let admitted = overrides.admits(&payload.event_type)
+                            && canary_shard.as_ref().is_none_or(|canary| canary.admits(shard_id, &payload.event_type));
+                        if !admitted {
+                            metrics.record_sampled_out(&payload.event_type);
+                        }
+                        admitted
+                    })
+                    // After sampling, so sampled-out events don't spend a guild's tokens
+                    .filter(|payload| {
+                        let admitted = guild_limit.as_ref().is_none_or(|limit| limit.admits(payload));
+                        if !admitted {
+                            metrics.record_guild_rate_limited(&payload.event_type);
+                        }
+                        admitted
+                    })
+                    .map(|payload| (pipeline, payload))
+            });
+            let deferral = command_defer
+                .as_ref()
+                .filter(|_| flags.is_enabled(AUTO_DEFER))
+                .and_then(|defer| Some((defer, defer.select(&event)?)));
+            match (routable, &event, &removal_audit, deferral) {
+                // The deferral waits on Discord; don't hold up the shard
+                (Some((pipeline, payload)), _, _, Some((defer, deferral))) => {
+                    pipeline.spawn(defer_and_route(
+                        Arc::clone(defer),
+                        deferral,
+                        payload,
+                        dispatch,
+                        Arc::clone(pipeline.nats()),
+                        state.clone(),
+                        Arc::clone(&metrics),
+                    ));
+                }
+                // The lookup waits on Discord; don't hold up the shard
+                (Some((pipeline, payload)), Event::MemberRemove(member), Some(client), _) => {
+                    pipeline.spawn(route_removal(
+                        Arc::clone(client),
+                        member.clone(),
+                        payload,
+                        dispatch,
+                        Arc::clone(pipeline.nats()),
+                        state.clone(),
+                        Arc::clone(&metrics),
+                    ));
+                }
+                (Some((pipeline, payload)), _, _, _) => pipeline.send(payload, dispatch, message_bytes).await,
+                (None, _, _, _) => {
+                    state.record_filtered(shard_id);
+                    metrics.record_filtered(shard_id);
+                }
             }
-            _ => {}
         }
 
-        // Route event to NATS if available
-        if let Some(ref nats) = nats {
-            let start = Instant::now();
+        // Stream ended. A 4014 close means Discord rejected our privileged
+        // intents: keep serving what we can instead of dying.
+        if last_close_code == Some(CLOSE_CODE_DISALLOWED_INTENTS) {
+            if let Some((degraded, missing)) = degrade_shard(shard) {
+                *shard = degraded;
+                control.register(shard_id, shard.sender());
+                state.mark_degraded(&missing);
+                metrics.set_capability_degraded(&missing);
+                error!(
+                    shard_id,
+                    ?missing,
+                    "Disallowed intents (4014) - reconnecting without privileged intents"
+                );
 
-            if let Some(payload) = serialize_event(&event, shard_id) {
-                match nats.publish_event(&payload).await {
-                    Ok(()) => {
-                        state.record_route(shard_id);
-                        metrics.record_route_success(shard_id, start.elapsed());
-                    }
-                    Err(e) => {
-                        state.record_route_failure(shard_id);
-                        metrics.record_route_failure(shard_id);
-                        warn!(shard_id, error = %e, "Failed to publish event to NATS");
-                    }
+                if let Some(pipeline) = pipeline.filter(|_| flags.is_enabled(NEW_EVENT_TYPES)) {
+                    let active = intent_names(shard.config().intents());
+                    let event = GatewayEvent {
+                        bot_id: bot_id.clone(),
+                        ..capability_degraded_event(shard_id, &missing, &active)
+                    };
+                    let nats = Arc::clone(pipeline.nats());
+                    pipeline.spawn(async move {
+                        if let Err(e) = nats.publish_event(&event).await {
+                            warn!(shard_id, error = %e, "Failed to publish capability_degraded event");
+                        }
+                    });
                 }
+                continue;
             }
         }
+        break;
     }
 
     // Stream ended — shard closed
+    end_session(&state, &metrics, shard_id);
     info!(shard_id, "Shard event stream ended");
-    Ok(())
+    Ok(ShardExit::Ended)
+}
+
+/// Publish an event and record the outcome
+pub(super) async fn route(nats: &NatsPublisher, mut payload: GatewayEvent, state: &ShardState, metrics: &GatewayMetrics) {
+    let shard_id = payload.shard_id;
+    let start = Instant::now();
+
+    nats.publish_eligibility(&payload).await;
+    let subject = nats.subject(&payload);
+    pipeline::number(&mut payload, &subject, state);
+    let published = nats.publish_event(&payload).await;
+    if let Some(cohort) = nats.cohort(shard_id) {
+        metrics.record_cohort_route(cohort.as_str(), published.is_ok(), start.elapsed());
+    }
+    match published {
+        Ok(()) => {
+            state.record_route(shard_id);
+            metrics.record_route_success(shard_id, &payload.event_type, &subject, start.elapsed());
+        }
+        Err(e) => {
+            state.record_route_failure(shard_id);
+            metrics.record_route_failure(shard_id);
+            warn!(shard_id, error = %e, "Failed to publish event to NATS");
+        }
+    }
+}
+
+/// Classify a `member.leave` from the audit log, then publish it
+async fn route_removal(
+    client: Arc<twilight_http::Client>,
+    member: MemberRemove,
+    mut payload: GatewayEvent,
+    dispatch: Option<String>,
+    nats: Arc<NatsPublisher>,
+    state: ShardState,
+    metrics: Arc<GatewayMetrics>,
+) {
+    // The raw dispatch is Discord's as sent; it doesn't wait for the lookup
+    if let Some(dispatch) = dispatch {
+        nats.publish_raw(&payload, dispatch).await;
+    }
+    tokio::time::sleep(audit::LOOKUP_DELAY).await;
+
+    let reason = audit::removal_reason(&client, member.guild_id, member.user.id, payload.timestamp)
+        .await
+        .unwrap_or_else(|e| {
+            debug!(guild_id = %member.guild_id, error = %e, "Audit log lookup failed");
+            RemovalReason::Unknown
+        });
+    set_removal_reason(&mut payload.data, reason);
+
+    route(&nats, payload, &state, &metrics).await;
+}
+
+/// Send a command's deferred response, then publish it
+async fn defer_and_route(
+    defer: Arc<CommandDefer>,
+    deferral: Deferral,
+    mut payload: GatewayEvent,
+    dispatch: Option<String>,
+    nats: Arc<NatsPublisher>,
+    state: ShardState,
+    metrics: Arc<GatewayMetrics>,
+) {
+    let deferred = match defer.send(&deferral).await {
+        Ok(()) => true,
+        Err(e) => {
+            warn!(event_id = %payload.event_id, error = %e, "Failed to defer interaction - leaving the response to the worker");
+            false
+        }
+    };
+    metrics.record_interaction_defer(if deferred { "sent" } else { "failed" });
+    payload.data["deferred"] = deferred.into();
+
+    if let Some(dispatch) = dispatch {
+        nats.publish_raw(&payload, dispatch).await;
+    }
+    route(&nats, payload, &state, &metrics).await;
+}
+
+/// Rebuild a shard without privileged intents after a 4014 close.
+///
+/// Returns the new shard and the names of the dropped intents, or None when
+/// the shard has no privileged intents left to drop (4014 is then fatal).
+fn degrade_shard<Q: Queue + Clone>(shard: &Shard<Q>) -> Option<(Shard<Q>, Vec<&'static str>)> {
+    let intents = shard.config().intents();
+    let dropped = intents & PRIVILEGED_INTENTS;
+    if dropped.is_empty() {
+        return None;
+    }
+
+    let config = ConfigBuilder::new(shard.config().token().to_string(), intents - PRIVILEGED_INTENTS)
+        .queue(shard.config().queue().clone())
+        .build();
+    Some((Shard::with_config(shard.id(), config), intent_names(dropped)))
+}
+
+/// Whether an event type may be published under the current feature flags
+fn publish_allowed(flags: &FeatureFlags, event_type: &str) -> bool {
+    STABLE_EVENT_TYPES.contains(&event_type) || flags.is_enabled(NEW_EVENT_TYPES)
+}
+
+/// Flag names for a set of intents (e.g. "GUILD_MEMBERS")
+fn intent_names(intents: Intents) -> Vec<&'static str> {
+    intents.iter_names().map(|(name, _)| name).collect()
+}
+
+/// End the shard's current session and record its lifetime
+fn end_session(state: &ShardState, metrics: &GatewayMetrics, shard_id: u64) {
+    if let Some(lifetime) = state.end_session(shard_id) {
+        metrics.record_session_lifetime(shard_id, lifetime);
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[tokio::test]
+    async fn test_degrade_shard_drops_only_privileged_intents() {
+        crate::install_crypto_provider();
+        let intents = Intents::GUILDS | Intents::GUILD_MEMBERS;
+        let shard = Shard::new(ShardId::ONE, "token".to_string(), intents);
+
+        let (degraded, missing) = degrade_shard(&shard).expect("privileged intent present");
+        assert_eq!(degraded.config().intents(), Intents::GUILDS);
+        assert_eq!(missing, vec!["GUILD_MEMBERS"]);
+
+        // Nothing left to drop: 4014 is fatal
+        assert!(degrade_shard(&degraded).is_none());
+    }
+
     #[test]
-    fn test_shards_per_pool_constant() {
-        assert_eq!(SHARDS_PER_POOL, 25);
+    fn test_shards_per_pool_default() {
+        assert_eq!(DEFAULT_SHARDS_PER_POOL, 25);
     }
 
     #[test]
     fn test_shard_range_calculation() {
         // Pool 0: shards 0-24
-        let pool_id = 0u64;
-        let total_shards = 100u64;
-        let start = pool_id * SHARDS_PER_POOL;
-        let end = ((pool_id + 1) * SHARDS_PER_POOL).min(total_shards);
-        assert_eq!(start, 0);
-        assert_eq!(end, 25);
+        assert_eq!(shard_range(0, 100, DEFAULT_SHARDS_PER_POOL), 0..25);
 
         // Pool 3: shards 75-99
-        let pool_id = 3u64;
-        let start = pool_id * SHARDS_PER_POOL;
-        let end = ((pool_id + 1) * SHARDS_PER_POOL).min(total_shards);
-        assert_eq!(start, 75);
-        assert_eq!(end, 100);
+        assert_eq!(shard_range(3, 100, DEFAULT_SHARDS_PER_POOL), 75..100);
+
+        // Bigger machines, fewer pools
+        assert_eq!(shard_range(1, 100, 64), 64..100);
+        assert_eq!(pool_for_shard(79, 64), 1);
+    }
+
+    #[test]
+    fn test_pool_must_own_shards() {
+        assert!(validate_pool(3, 100, 25).is_ok());
+        assert!(validate_pool(1, 100, 64).is_ok());
+        let err = validate_pool(2, 100, 64).unwrap_err().to_string();
+        assert!(err.contains("need pools 0..2"), "{err}");
+    }
+
+    #[test]
+    fn test_guild_shard_and_pool() {
+        // (987654321098765432 >> 22) % 100 = 79
+        assert_eq!(shard_for_guild(987654321098765432, 100), 79);
+        assert_eq!(shard_for_guild(987654321098765432, 1), 0);
+        assert_eq!(pool_for_shard(79, 25), 3);
+        assert!(shard_range(pool_for_shard(79, 25), 100, 25).contains(&79));
+    }
+
+    #[test]
+    fn test_select_shards_subset_must_be_in_range() {
+        assert_eq!(select_shards(1, 100, 25, None).unwrap().len(), 25);
+
+        let only = BTreeSet::from([27, 30]);
+        assert_eq!(select_shards(1, 100, 25, Some(&only)).unwrap(), vec![27, 30]);
+
+        let outside = BTreeSet::from([3, 30]);
+        assert!(select_shards(1, 100, 25, Some(&outside)).is_err());
+    }
+
+    #[test]
+    fn test_new_event_types_gate_only_newer_types() {
+        use crate::flags::FlagEvaluation;
+
+        let flags = FeatureFlags::from_evaluations(vec![FlagEvaluation {
+            key: NEW_EVENT_TYPES,
+            value: false,
+            variant: Some("off".to_string()),
+            reason: "STATIC".to_string(),
+        }]);
+        assert!(!publish_allowed(&flags, "gateway.capability_degraded"));
+        assert!(publish_allowed(&flags, "member.join"));
+        assert!(publish_allowed(&FeatureFlags::default(), "gateway.capability_degraded"));
+    }
+
+    #[test]
+    fn test_shard_range_helper() {
+        assert_eq!(shard_range(0, 100, 25), 0..25);
+        assert_eq!(shard_range(3, 90, 25), 75..90);
+        // Pool beyond the shard count owns nothing
+        assert!(shard_range(5, 100, 25).is_empty());
     }
 }
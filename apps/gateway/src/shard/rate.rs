@@ -0,0 +1,96 @@
+//! Event counts over the last minute, for `/status`
+//!
+//! A ring of one-second buckets, each stamped with the second it counts. A
+//! bucket found holding an older second is reset before counting, so the
+//! ring never needs a background sweep. Counts are approximate under
+//! contention at a second boundary, which is fine for a status page.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+
+/// Seconds covered by a window
+pub const WINDOW_SECS: u64 = 60;
+
+#[derive(Debug, Default)]
+struct Bucket {
+    second: AtomicU64,
+    count: AtomicU64,
+}
+
+/// Counts over a sliding one-minute window
+#[derive(Debug)]
+pub struct RateWindow {
+    origin: Instant,
+    buckets: [Bucket; WINDOW_SECS as usize],
+}
+
+impl Default for RateWindow {
+    fn default() -> Self {
+        Self {
+            origin: Instant::now(),
+            buckets: std::array::from_fn(|_| Bucket::default()),
+        }
+    }
+}
+
+impl RateWindow {
+    /// Count one occurrence now
+    pub fn record(&self) {
+        self.record_at(self.origin.elapsed().as_secs());
+    }
+
+    /// Occurrences in the last `WINDOW_SECS` seconds
+    pub fn total(&self) -> u64 {
+        self.total_at(self.origin.elapsed().as_secs())
+    }
+
+    /// Average per second over the window
+    pub fn per_second(&self) -> f64 {
+        self.total() as f64 / WINDOW_SECS as f64
+    }
+
+    fn record_at(&self, now: u64) {
+        let bucket = &self.buckets[(now % WINDOW_SECS) as usize];
+        let stamped = bucket.second.load(Ordering::Acquire);
+        if stamped != now
+            && bucket.second.compare_exchange(stamped, now, Ordering::AcqRel, Ordering::Acquire).is_ok()
+        {
+            bucket.count.store(0, Ordering::Release);
+        }
+        bucket.count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn total_at(&self, now: u64) -> u64 {
+        self.buckets
+            .iter()
+            .filter(|bucket| now.saturating_sub(bucket.second.load(Ordering::Acquire)) < WINDOW_SECS)
+            .map(|bucket| bucket.count.load(Ordering::Relaxed))
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_only_the_last_minute() {
+        let window = RateWindow::default();
+        window.record_at(0);
+        window.record_at(0);
+        window.record_at(30);
+        assert_eq!(window.total_at(30), 3);
+        assert_eq!(window.total_at(59), 3);
+        assert_eq!(window.total_at(60), 1, "second 0 has left the window");
+        assert_eq!(window.total_at(90), 0);
+    }
+
+    #[test]
+    fn reused_bucket_starts_from_zero() {
+        let window = RateWindow::default();
+        window.record_at(5);
+        window.record_at(5);
+        window.record_at(65);
+        assert_eq!(window.total_at(65), 1);
+    }
+}
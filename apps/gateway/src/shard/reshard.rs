@@ -0,0 +1,243 @@
+//! Rolling resharding (`RESHARDING_ENABLED`, `RESHARD_FROM_SHARDS`)
+//!
+//! Raising `TOTAL_SHARDS` used to mean taking the bot offline. Instead, pools
+//! with the new shard count connect next to the old ones, and both layouts
+//! receive every guild's events from Discord. A layout is named by its shard
+//! count (its epoch), and each guild's events are published by one layout
+//! only, as decided by the `gateway_resharding` KV bucket:
+//!
+//! - `guild-{guild_id}`: the epoch that owns one guild
+//! - `default`: the epoch that owns every other guild, and events without one
+//!
+//! Without either key, guilds stay with the layout being replaced: new pools
+//! are started with `RESHARD_FROM_SHARDS` naming it, old pools own what
+//! isn't assigned elsewhere. Operators move guilds one by one, then set
+//! `default` to the new count, stop the old pools and delete the bucket.
+//!
+//! Events a pool doesn't own are dropped before they are numbered and counted
+//! in `gateway_reshard_suppressed_total`. Published events carry an
+//! `Arrakis-Shard-Epoch` header, so consumers tracking `seq` per shard can
+//! tell shard 5 of 100 from shard 5 of 200.
+
+use crate::events::serialize::GatewayEvent;
+use crate::metrics::GatewayMetrics;
+use crate::nats::kv::{self, buckets};
+use crate::nats::NatsPublisher;
+use async_nats::jetstream::kv::{Entry, Operation, Watch};
+use futures_util::StreamExt as _;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// Header on events published while resharding: the publishing layout's shard count
+pub const HEADER: &str = "Arrakis-Shard-Epoch";
+
+/// Key of the epoch owning unassigned guilds
+pub const DEFAULT_KEY: &str = "default";
+
+/// Prefix of per-guild keys: `guild-{guild_id}`
+pub const GUILD_KEY_PREFIX: &str = "guild-";
+
+/// Wait before reopening a watch that failed or ended
+const REWATCH_DELAY: Duration = Duration::from_secs(5);
+
+/// Resharding settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReshardConfig {
+    /// This pool's layout (`TOTAL_SHARDS`)
+    pub epoch: u64,
+    /// The layout this pool replaces, which keeps unassigned guilds
+    /// (`RESHARD_FROM_SHARDS`; None on the old layout's pools)
+    pub from: Option<u64>,
+}
+
+/// Which layout owns each guild, as last read from the bucket
+#[derive(Debug)]
+pub struct Resharding {
+    config: ReshardConfig,
+    default: RwLock<Option<u64>>,
+    guilds: RwLock<HashMap<String, u64>>,
+}
+
+impl Resharding {
+    pub fn new(config: ReshardConfig) -> Self {
+        Self { config, default: RwLock::default(), guilds: RwLock::default() }
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.config.epoch
+    }
+
+    /// The epoch publishing a guild's events (or, without a guild, the rest)
+    pub fn owner(&self, guild_id: Option<&str>) -> u64 {
+        let assigned = guild_id.and_then(|guild_id| {
+            self.guilds.read().unwrap_or_else(|e| e.into_inner()).get(guild_id).copied()
+        });
+        assigned
+            .or(*self.default.read().unwrap_or_else(|e| e.into_inner()))
+            .or(self.config.from)
+            .unwrap_or(self.config.epoch)
+    }
+
+    /// Whether this pool's layout publishes an event
+    pub fn admits(&self, event: &GatewayEvent) -> bool {
+        self.owner(event.guild_id.as_deref()) == self.config.epoch
+    }
+
+    /// Apply a bucket change: `Some` assigns the key's guilds to an epoch,
+    /// `None` unassigns them. Unknown keys and bad values are rejected.
+    pub fn apply(&self, key: &str, value: Option<&str>) -> Result<(), String> {
+        let epoch = match value {
+            Some(value) => Some(
+                value
+                    .trim()
+                    .parse::<u64>()
+                    .ok()
+                    .filter(|epoch| *epoch > 0)
+                    .ok_or_else(|| format!("value must be a shard count, got {value:?}"))?,
+            ),
+            None => None,
+        };
+
+        if key == DEFAULT_KEY {
+            *self.default.write().unwrap_or_else(|e| e.into_inner()) = epoch;
+            return Ok(());
+        }
+        let guild_id = key
+            .strip_prefix(GUILD_KEY_PREFIX)
+            .filter(|guild_id| guild_id.parse::<u64>().is_ok())
+            .ok_or_else(|| format!("unknown key; expected {DEFAULT_KEY} or {GUILD_KEY_PREFIX}{{guild_id}}"))?;
+        let mut guilds = self.guilds.write().unwrap_or_else(|e| e.into_inner());
+        match epoch {
+            Some(epoch) => guilds.insert(guild_id.to_string(), epoch),
+            None => guilds.remove(guild_id),
+        };
+        Ok(())
+    }
+
+    /// Guilds assigned to a layout, by epoch
+    pub fn assigned(&self) -> HashMap<u64, usize> {
+        let mut counts = HashMap::new();
+        for epoch in self.guilds.read().unwrap_or_else(|e| e.into_inner()).values() {
+            *counts.entry(*epoch).or_default() += 1;
+        }
+        counts
+    }
+}
+
+/// Apply bucket changes until the process exits
+pub async fn run_watcher(nats: Arc<NatsPublisher>, resharding: Arc<Resharding>, metrics: Arc<GatewayMetrics>) {
+    info!(
+        bucket = buckets::RESHARDING,
+        epoch = resharding.config.epoch,
+        from = ?resharding.config.from,
+        "Resharding: publishing only guilds this layout owns"
+    );
+
+    loop {
+        let Some(mut watch) = open_watch(&nats).await else {
+            tokio::time::sleep(REWATCH_DELAY).await;
+            continue;
+        };
+        while let Some(entry) = watch.next().await {
+            match entry {
+                Ok(entry) => apply_entry(&resharding, &entry),
+                Err(e) => debug!(error = %e, "Resharding watch error"),
+            }
+            for (epoch, guilds) in resharding.assigned() {
+                metrics.set_reshard_guilds(epoch, guilds);
+            }
+        }
+        debug!("Resharding watch ended");
+        tokio::time::sleep(REWATCH_DELAY).await;
+    }
+}
+
+async fn open_watch(nats: &NatsPublisher) -> Option<Watch> {
+    let store = kv::open_bucket(nats.jetstream(), buckets::RESHARDING, "Guild ownership during resharding")
+        .await
+        .map_err(|e| warn!(error = %e, "Resharding bucket unavailable"))
+        .ok()?;
+    // History first, so a restarted pool picks up the guilds already moved
+    store
+        .watch_with_history(">")
+        .await
+        .map_err(|e| warn!(error = %e, "Resharding watch failed"))
+        .ok()
+}
+
+fn apply_entry(resharding: &Resharding, entry: &Entry) {
+    let value = match entry.operation {
+        Operation::Put => Some(String::from_utf8_lossy(&entry.value).into_owned()),
+        Operation::Delete | Operation::Purge => None,
+    };
+    match resharding.apply(&entry.key, value.as_deref()) {
+        Ok(()) => info!(
+            key = %entry.key,
+            epoch = ?value,
+            revision = entry.revision,
+            owned = value.as_deref().is_none_or(|epoch| epoch.trim() == resharding.epoch().to_string()),
+            "Resharding ownership changed"
+        ),
+        Err(error) => warn!(key = %entry.key, value = ?value, error, "Resharding entry rejected"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(guild_id: Option<&str>) -> GatewayEvent {
+        GatewayEvent {
+            event_id: uuid::Uuid::new_v4().to_string(),
+            timestamp: 0,
+            guild_id: guild_id.map(str::to_string),
+            ..GatewayEvent::for_test("message.create")
+        }
+    }
+
+    #[test]
+    fn test_guilds_move_one_by_one() {
+        let old = Resharding::new(ReshardConfig { epoch: 100, from: None });
+        let new = Resharding::new(ReshardConfig { epoch: 200, from: Some(100) });
+        let moved = Some("123456789012345678");
+        let staying = Some("987654321098765432");
+
+        // Nothing assigned: the old layout keeps everything
+        for guild_id in [moved, staying, None] {
+            assert!(old.admits(&event(guild_id)));
+            assert!(!new.admits(&event(guild_id)));
+        }
+
+        for layout in [&old, &new] {
+            layout.apply("guild-123456789012345678", Some("200")).unwrap();
+        }
+        assert!(!old.admits(&event(moved)) && new.admits(&event(moved)));
+        assert!(old.admits(&event(staying)) && !new.admits(&event(staying)));
+        assert_eq!(new.assigned(), HashMap::from([(200, 1)]));
+
+        // Cutover: the rest follow, events without a guild too
+        for layout in [&old, &new] {
+            layout.apply(DEFAULT_KEY, Some("200")).unwrap();
+        }
+        for guild_id in [moved, staying, None] {
+            assert!(!old.admits(&event(guild_id)));
+            assert!(new.admits(&event(guild_id)));
+        }
+
+        // Deleting a key unassigns it
+        new.apply(DEFAULT_KEY, None).unwrap();
+        assert!(!new.admits(&event(staying)));
+    }
+
+    #[test]
+    fn test_bad_entries_are_rejected() {
+        let resharding = Resharding::new(ReshardConfig { epoch: 100, from: None });
+        assert!(resharding.apply("guild-123", Some("many")).is_err());
+        assert!(resharding.apply("guild-123", Some("0")).is_err());
+        assert!(resharding.apply("guild-abc", Some("200")).is_err());
+        assert!(resharding.apply("pool-1", Some("200")).is_err());
+        assert_eq!(resharding.owner(Some("123")), 100);
+    }
+}
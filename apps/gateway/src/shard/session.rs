@@ -0,0 +1,255 @@
+//! Session resume across restarts
+//!
+//! On a graceful shutdown each shard stops between events, closes with a
+//! resumable close code and saves its session (ID, sequence, resume URL).
+//! The next start hands the saved sessions to Twilight, which resumes instead
+//! of identifying: Discord replays what was missed during the deploy and no
+//! identify budget is spent. Sessions live in NATS KV (`gateway_sessions`,
+//! keyed `shard-{id}`) or, for a single pool without NATS, a local file.
+//!
+//! A session Discord no longer accepts is invalidated on resume and the shard
+//! identifies as usual, so a stale entry only costs one round trip.
+
+use crate::error::GatewayError;
+use crate::nats::kv::{self, buckets};
+use async_nats::jetstream::kv::{Operation, Store};
+use async_nats::jetstream::Context as JsContext;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::{debug, info, warn};
+use twilight_gateway::Session;
+
+/// Discord keeps sessions resumable for a short while only; older entries
+/// are ignored
+pub const SESSION_MAX_AGE: Duration = Duration::from_secs(300);
+
+/// A shard's session as saved at shutdown
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedSession {
+    pub session: Session,
+    pub resume_url: Option<String>,
+    /// Shard count the session was opened with; a reshard invalidates it
+    pub total_shards: u64,
+    /// Unix ms
+    pub saved_at: u64,
+}
+
+impl SavedSession {
+    fn usable(&self, total_shards: u64, now_ms: u64) -> bool {
+        self.total_shards == total_shards
+            && now_ms.saturating_sub(self.saved_at) <= SESSION_MAX_AGE.as_millis() as u64
+    }
+}
+
+/// Where sessions are kept between runs
+#[derive(Debug)]
+pub enum SessionStore {
+    /// Keys are prefixed with `bot_id` for the other bots of the process (`BOTS`)
+    Kv { store: Box<Store>, bot_id: Option<String> },
+    File(PathBuf),
+}
+
+impl SessionStore {
+    pub async fn open_kv(js: &JsContext, bot_id: Option<&str>) -> Result<Self, GatewayError> {
+        let store =
+            kv::open_expiring_bucket(js, buckets::SESSIONS, "Shard sessions saved for resume", SESSION_MAX_AGE).await?;
+        Ok(Self::Kv { store: Box::new(store), bot_id: bot_id.map(str::to_string) })
+    }
+
+    /// Saved sessions for these shards that can still be resumed.
+    ///
+    /// Failures are logged and leave the shards to identify.
+    pub async fn load(&self, shard_ids: &[u64], total_shards: u64, now_ms: u64) -> HashMap<u64, SavedSession> {
+        let saved = match self {
+            Self::Kv { store, bot_id } => load_kv(store, bot_id.as_deref(), shard_ids).await,
+            Self::File(path) => read_file(path).map_err(|e| file_error(path, e)),
+        };
+        let saved = saved.unwrap_or_else(|e| {
+            warn!(error = %e, "Failed to load saved sessions - shards will identify");
+            BTreeMap::new()
+        });
+
+        let sessions: HashMap<_, _> = saved
+            .into_iter()
+            .filter(|(shard_id, saved)| shard_ids.contains(shard_id) && saved.usable(total_shards, now_ms))
+            .collect();
+        if !sessions.is_empty() {
+            info!(resumable = sessions.len(), "Loaded saved sessions");
+        }
+        sessions
+    }
+
+    /// Save sessions for the next start; failures are logged. Returns
+    /// whether they were saved.
+    pub async fn save(&self, sessions: &[(u64, SavedSession)]) -> bool {
+        let result = match self {
+            Self::Kv { store, bot_id } => save_kv(store, bot_id.as_deref(), sessions).await,
+            Self::File(path) => write_file(path, sessions).map_err(|e| file_error(path, e)),
+        };
+        match result {
+            Ok(()) => info!(saved = sessions.len(), "Saved shard sessions for resume"),
+            Err(ref e) => warn!(error = %e, "Failed to save shard sessions - the next start will identify"),
+        }
+        result.is_ok()
+    }
+
+    /// Load one shard's session and remove it, so no other pool resumes it
+    /// too; failures are logged and leave the shard to identify.
+    ///
+    /// In KV, the removal only succeeds if nobody else took the session
+    /// first, so of two pools taking at once exactly one gets it.
+    pub async fn take(&self, shard_id: u64, total_shards: u64, now_ms: u64) -> Option<SavedSession> {
+        let saved = match self {
+            Self::Kv { store, bot_id } => take_kv(store, bot_id.as_deref(), shard_id).await.unwrap_or_else(|e| {
+                warn!(shard_id, error = %e, "Failed to take a saved session");
+                None
+            }),
+            Self::File(path) => {
+                let saved = self.load(&[shard_id], total_shards, now_ms).await.remove(&shard_id)?;
+                if let Err(e) = forget_file(path, shard_id) {
+                    warn!(shard_id, error = %file_error(path, e), "Failed to remove a taken session");
+                }
+                Some(saved)
+            }
+        };
+        saved.filter(|saved| saved.usable(total_shards, now_ms))
+    }
+}
+
+fn session_key(bot_id: Option<&str>, shard_id: u64) -> String {
+    kv::bot_key(bot_id, &format!("shard-{shard_id}"))
+}
+
+fn kv_error(e: Box<dyn std::error::Error + Send + Sync>) -> GatewayError {
+    GatewayError::NatsKvFailed { bucket: buckets::SESSIONS.to_string(), source: e }
+}
+
+fn file_error(path: &Path, e: std::io::Error) -> GatewayError {
+    GatewayError::Config(format!("session file {}: {e}", path.display()))
+}
+
+async fn load_kv(store: &Store, bot_id: Option<&str>, shard_ids: &[u64]) -> Result<BTreeMap<u64, SavedSession>, GatewayError> {
+    let mut sessions = BTreeMap::new();
+    for &shard_id in shard_ids {
+        let Some(value) = store.get(session_key(bot_id, shard_id)).await.map_err(|e| kv_error(Box::new(e)))? else {
+            continue;
+        };
+        match serde_json::from_slice(&value) {
+            Ok(saved) => {
+                sessions.insert(shard_id, saved);
+            }
+            Err(e) => warn!(shard_id, error = %e, "Ignoring unreadable saved session"),
+        }
+    }
+    Ok(sessions)
+}
+
+/// Remove a shard's session from the bucket, unless it changed since it
+/// was read; None when there was none, or another pool removed it first
+async fn take_kv(store: &Store, bot_id: Option<&str>, shard_id: u64) -> Result<Option<SavedSession>, GatewayError> {
+    let key = session_key(bot_id, shard_id);
+    let Some(entry) = store.entry(&key).await.map_err(|e| kv_error(Box::new(e)))? else {
+        return Ok(None);
+    };
+    if entry.operation != Operation::Put {
+        return Ok(None);
+    }
+    if let Err(e) = store.delete_expect_revision(&key, Some(entry.revision)).await {
+        debug!(shard_id, error = %e, "Saved session was taken by another pool");
+        return Ok(None);
+    }
+    serde_json::from_slice(&entry.value).map(Some).map_err(|e| kv_error(Box::new(e)))
+}
+
+async fn save_kv(store: &Store, bot_id: Option<&str>, sessions: &[(u64, SavedSession)]) -> Result<(), GatewayError> {
+    for (shard_id, saved) in sessions {
+        let value = serde_json::to_vec(saved).map_err(|e| kv_error(Box::new(e)))?;
+        store.put(session_key(bot_id, *shard_id), value.into()).await.map_err(|e| kv_error(Box::new(e)))?;
+    }
+    Ok(())
+}
+
+/// Sessions in the file, keyed by shard ID; a missing file holds none
+fn read_file(path: &Path) -> std::io::Result<BTreeMap<u64, SavedSession>> {
+    match std::fs::read(path) {
+        Ok(contents) => serde_json::from_slice(&contents).map_err(std::io::Error::other),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Remove a shard's session from the file
+fn forget_file(path: &Path, shard_id: u64) -> std::io::Result<()> {
+    let mut saved = read_file(path)?;
+    if saved.remove(&shard_id).is_some() {
+        replace_file(path, &saved)?;
+    }
+    Ok(())
+}
+
+/// Merge sessions into the file, atomically replacing it
+fn write_file(path: &Path, sessions: &[(u64, SavedSession)]) -> std::io::Result<()> {
+    let mut saved = read_file(path).unwrap_or_default();
+    saved.extend(sessions.iter().cloned());
+    replace_file(path, &saved)
+}
+
+fn replace_file(path: &Path, saved: &BTreeMap<u64, SavedSession>) -> std::io::Result<()> {
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, serde_json::to_vec_pretty(saved).map_err(std::io::Error::other)?)?;
+    std::fs::rename(&tmp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn saved(sequence: u64, saved_at: u64) -> SavedSession {
+        SavedSession {
+            session: Session::new(sequence, "3f2b6e0c".to_string()),
+            resume_url: Some("wss://gateway-us-east1-b.discord.gg".to_string()),
+            total_shards: 16,
+            saved_at,
+        }
+    }
+
+    #[test]
+    fn sessions_expire_and_do_not_survive_a_reshard() {
+        let now = 1_700_000_000_000;
+        assert!(saved(42, now - 60_000).usable(16, now));
+        assert!(!saved(42, now - 301_000).usable(16, now));
+        assert!(!saved(42, now).usable(32, now));
+    }
+
+    #[test]
+    fn other_bots_keep_their_sessions_apart() {
+        assert_eq!(session_key(None, 3), "shard-3");
+        assert_eq!(session_key(Some("staging"), 3), "staging.shard-3");
+    }
+
+    #[tokio::test]
+    async fn file_store_merges_and_filters_by_shard() {
+        let dir = std::env::temp_dir().join(format!("gateway-sessions-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let store = SessionStore::File(dir.join("sessions.json"));
+        let now = 1_700_000_000_000;
+
+        assert!(store.load(&[0, 1], 16, now).await.is_empty());
+        store.save(&[(0, saved(10, now))]).await;
+        store.save(&[(1, saved(20, now))]).await;
+
+        let loaded = store.load(&[1, 2], 16, now).await;
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[&1].session.sequence(), 20);
+        assert_eq!(store.load(&[0, 1], 16, now).await.len(), 2);
+
+        // A taken session can't be resumed a second time
+        assert_eq!(store.take(0, 16, now).await.map(|saved| saved.session.sequence()), Some(10));
+        assert!(store.take(0, 16, now).await.is_none());
+        assert_eq!(store.load(&[0, 1], 16, now).await.len(), 1);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
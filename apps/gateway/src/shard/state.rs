@@ -3,10 +3,13 @@
 //! Sprint S-4: Tracks health and status of individual shards
 #![allow(dead_code)] // Scaffolded for shard health monitoring
 
+use super::rate::RateWindow;
+use crate::events::serialize::now_millis;
 use dashmap::DashMap;
-use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Arc;
-use std::time::Instant;
+use std::collections::BTreeSet;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 /// Health status for a shard
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -21,6 +24,8 @@ pub enum ShardHealth {
     Disconnected,
     /// Shard encountered a fatal error
     Dead,
+    /// Shard was stopped by an operator (admin drain)
+    Drained,
 }
 
 impl ShardHealth {
@@ -33,6 +38,17 @@ impl ShardHealth {
     pub fn is_ready(&self) -> bool {
         matches!(self, ShardHealth::Ready)
     }
+
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            ShardHealth::Connecting => "connecting",
+            ShardHealth::Ready => "ready",
+            ShardHealth::Resuming => "resuming",
+            ShardHealth::Disconnected => "disconnected",
+            ShardHealth::Dead => "dead",
+            ShardHealth::Drained => "drained",
+        }
+    }
 }
 
 /// State for a single shard
@@ -43,8 +59,23 @@ pub struct ShardStateEntry {
     pub events_received: AtomicU64,
     pub events_routed: AtomicU64,
     pub route_failures: AtomicU64,
+    /// Events received but deliberately not published
+    pub events_filtered: AtomicU64,
+    /// Events waiting in the shard's publish queues
+    pub events_queued: AtomicU64,
+    /// Sessions resumed since the pool started
+    pub resumes: AtomicU64,
+    /// Events unaccounted for over the watchdog window, when beyond tolerance
+    pub divergence: Option<u64>,
     pub last_heartbeat: Option<Instant>,
+    /// When the current connection became ready (cleared when it drops)
     pub connected_at: Option<Instant>,
+    /// Start of the current Discord session (READY), preserved across resumes
+    pub session_started_at: Option<Instant>,
+    /// Current session ID (from READY)
+    pub session_id: Option<String>,
+    /// `resume_gateway_url` from READY; Discord requires resumes to use it
+    pub resume_url: Option<String>,
 }
 
 impl Default for ShardStateEntry {
@@ -55,8 +86,56 @@ impl Default for ShardStateEntry {
             events_received: AtomicU64::new(0),
             events_routed: AtomicU64::new(0),
             route_failures: AtomicU64::new(0),
+            events_filtered: AtomicU64::new(0),
+            events_queued: AtomicU64::new(0),
+            resumes: AtomicU64::new(0),
+            divergence: None,
             last_heartbeat: None,
             connected_at: None,
+            session_started_at: None,
+            session_id: None,
+            resume_url: None,
+        }
+    }
+}
+
+/// Point-in-time view of one shard, for admin queries
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShardSnapshot {
+    pub shard_id: u64,
+    pub health: ShardHealth,
+    pub guilds: u64,
+    pub events_received: u64,
+    pub events_routed: u64,
+    pub route_failures: u64,
+    pub events_filtered: u64,
+    pub events_queued: u64,
+    pub resumes: u64,
+    /// Time since the last heartbeat ack
+    pub heartbeat_age: Option<Duration>,
+    /// Time since the current connection became ready
+    pub uptime: Option<Duration>,
+    pub session_uptime: Option<Duration>,
+    /// Session ID and resume URL are both known
+    pub resumable: bool,
+}
+
+impl ShardSnapshot {
+    fn new(shard_id: u64, entry: &ShardStateEntry) -> Self {
+        Self {
+            shard_id,
+            health: entry.health,
+            guilds: entry.guilds,
+            events_received: entry.events_received.load(Ordering::Relaxed),
+            events_routed: entry.events_routed.load(Ordering::Relaxed),
+            route_failures: entry.route_failures.load(Ordering::Relaxed),
+            events_filtered: entry.events_filtered.load(Ordering::Relaxed),
+            events_queued: entry.events_queued.load(Ordering::Relaxed),
+            resumes: entry.resumes.load(Ordering::Relaxed),
+            heartbeat_age: entry.last_heartbeat.map(|at| at.elapsed()),
+            uptime: entry.connected_at.map(|at| at.elapsed()),
+            session_uptime: entry.session_started_at.map(|at| at.elapsed()),
+            resumable: entry.session_id.is_some() && entry.resume_url.is_some(),
         }
     }
 }
@@ -72,11 +151,25 @@ struct ShardStateInner {
     pool_id: u64,
     shards: DashMap<u64, ShardStateEntry>,
     total_shards: u64,
+    shards_per_pool: u64,
+    /// Capabilities (intent names) lost to a disallowed-intents close
+    degraded: Mutex<BTreeSet<&'static str>>,
+    /// Set once shutdown starts; the pool reports not ready from then on
+    draining: AtomicBool,
+    started_at: Instant,
+    /// Pool-wide counts over the last minute
+    received: RateWindow,
+    routed: RateWindow,
+    route_failed: RateWindow,
+    /// Events numbered so far per shard and stream
+    sequences: DashMap<(u64, &'static str), u64>,
+    /// When numbering started (Unix ms)
+    seq_epoch: u64,
 }
 
 impl ShardState {
     /// Create a new shard state tracker
-    pub fn new(pool_id: u64, shard_ids: impl Iterator<Item = u64>, total_shards: u64) -> Self {
+    pub fn new(pool_id: u64, shard_ids: impl Iterator<Item = u64>, total_shards: u64, shards_per_pool: u64) -> Self {
         let shards = DashMap::new();
         for shard_id in shard_ids {
             shards.insert(shard_id, ShardStateEntry::default());
@@ -87,6 +180,15 @@ impl ShardState {
                 pool_id,
                 shards,
                 total_shards,
+                shards_per_pool,
+                degraded: Mutex::new(BTreeSet::new()),
+                draining: AtomicBool::new(false),
+                started_at: Instant::now(),
+                received: RateWindow::default(),
+                routed: RateWindow::default(),
+                route_failed: RateWindow::default(),
+                sequences: DashMap::new(),
+                seq_epoch: now_millis(),
             }),
         }
     }
@@ -101,12 +203,21 @@ impl ShardState {
         self.inner.total_shards
     }
 
+    /// Size of each pool's shard range
+    pub fn shards_per_pool(&self) -> u64 {
+        self.inner.shards_per_pool
+    }
+
     /// Update shard health
     pub fn set_health(&self, shard_id: u64, health: ShardHealth) {
         if let Some(mut entry) = self.inner.shards.get_mut(&shard_id) {
             entry.health = health;
-            if health == ShardHealth::Ready && entry.connected_at.is_none() {
-                entry.connected_at = Some(Instant::now());
+            match health {
+                ShardHealth::Ready if entry.connected_at.is_none() => entry.connected_at = Some(Instant::now()),
+                ShardHealth::Connecting | ShardHealth::Disconnected | ShardHealth::Dead | ShardHealth::Drained => {
+                    entry.connected_at = None
+                }
+                _ => {}
             }
         }
     }
@@ -122,6 +233,7 @@ impl ShardState {
     pub fn record_event(&self, shard_id: u64) {
         if let Some(entry) = self.inner.shards.get(&shard_id) {
             entry.events_received.fetch_add(1, Ordering::Relaxed);
+            self.inner.received.record();
         }
     }
 
@@ -129,6 +241,7 @@ impl ShardState {
     pub fn record_route(&self, shard_id: u64) {
         if let Some(entry) = self.inner.shards.get(&shard_id) {
             entry.events_routed.fetch_add(1, Ordering::Relaxed);
+            self.inner.routed.record();
         }
     }
 
@@ -136,9 +249,75 @@ impl ShardState {
     pub fn record_route_failure(&self, shard_id: u64) {
         if let Some(entry) = self.inner.shards.get(&shard_id) {
             entry.route_failures.fetch_add(1, Ordering::Relaxed);
+            self.inner.route_failed.record();
+        }
+    }
+
+    /// Number the shard's next event on `stream`: the epoch and its `seq`.
+    /// Counts survive shard restarts and start again with a new process.
+    pub fn next_seq(&self, shard_id: u64, stream: &'static str) -> (u64, u64) {
+        let mut seq = self.inner.sequences.entry((shard_id, stream)).or_insert(0);
+        *seq += 1;
+        (self.inner.seq_epoch, *seq)
+    }
+
+    /// Increment the shard's resume counter
+    pub fn record_resume(&self, shard_id: u64) {
+        if let Some(entry) = self.inner.shards.get(&shard_id) {
+            entry.resumes.fetch_add(1, Ordering::Relaxed);
         }
     }
 
+    /// Events received, published and failed to publish over the last minute
+    pub fn event_rates(&self) -> (&RateWindow, &RateWindow, &RateWindow) {
+        (&self.inner.received, &self.inner.routed, &self.inner.route_failed)
+    }
+
+    /// Time since the pool started
+    pub fn uptime(&self) -> Duration {
+        self.inner.started_at.elapsed()
+    }
+
+    /// Increment the counter of events not published (not forwarded, gated
+    /// by a flag, aggregated, or no NATS)
+    pub fn record_filtered(&self, shard_id: u64) {
+        if let Some(entry) = self.inner.shards.get(&shard_id) {
+            entry.events_filtered.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Count an event entering (+1) or leaving (-1) the shard's publish queues
+    pub fn record_queued(&self, shard_id: u64, entered: bool) {
+        if let Some(entry) = self.inner.shards.get(&shard_id) {
+            if entered {
+                entry.events_queued.fetch_add(1, Ordering::Relaxed);
+            } else {
+                let _ = entry
+                    .events_queued
+                    .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |queued| Some(queued.saturating_sub(1)));
+            }
+        }
+    }
+
+    /// Set or clear a shard's received-vs-routed divergence
+    pub fn set_divergence(&self, shard_id: u64, unaccounted: Option<u64>) {
+        if let Some(mut entry) = self.inner.shards.get_mut(&shard_id) {
+            entry.divergence = unaccounted;
+        }
+    }
+
+    /// Shards whose counters diverged, with the events unaccounted for, in ID order
+    pub fn diverged_shards(&self) -> Vec<(u64, u64)> {
+        let mut diverged: Vec<(u64, u64)> = self
+            .inner
+            .shards
+            .iter()
+            .filter_map(|e| e.divergence.map(|unaccounted| (*e.key(), unaccounted)))
+            .collect();
+        diverged.sort_unstable();
+        diverged
+    }
+
     /// Record heartbeat
     pub fn record_heartbeat(&self, shard_id: u64) {
         if let Some(mut entry) = self.inner.shards.get_mut(&shard_id) {
@@ -146,6 +325,87 @@ impl ShardState {
         }
     }
 
+    /// Time since any shard last heartbeated (since the pool started, before
+    /// the first heartbeat)
+    pub fn heartbeat_silence(&self) -> Duration {
+        self.inner
+            .shards
+            .iter()
+            .filter_map(|e| e.last_heartbeat)
+            .max()
+            .unwrap_or(self.inner.started_at)
+            .elapsed()
+    }
+
+    /// Mark the start of a new session (READY), recording its ID and resume URL.
+    ///
+    /// Returns the lifetime of the session it replaced, if any.
+    pub fn start_session(&self, shard_id: u64, session_id: &str, resume_url: &str) -> Option<Duration> {
+        let mut entry = self.inner.shards.get_mut(&shard_id)?;
+        entry.session_id = Some(session_id.to_string());
+        entry.resume_url = Some(resume_url.to_string());
+        let previous = entry.session_started_at.replace(Instant::now());
+        previous.map(|started| started.elapsed())
+    }
+
+    /// Mark the current session as ended (invalidated or shard dead).
+    ///
+    /// Returns the ended session's lifetime, if a session was active.
+    pub fn end_session(&self, shard_id: u64) -> Option<Duration> {
+        let mut entry = self.inner.shards.get_mut(&shard_id)?;
+        entry.session_id = None;
+        entry.resume_url = None;
+        entry.session_started_at.take().map(|started| started.elapsed())
+    }
+
+    /// Current session ID and resume URL for a shard
+    pub fn session(&self, shard_id: u64) -> Option<(String, String)> {
+        let entry = self.inner.shards.get(&shard_id)?;
+        Some((entry.session_id.clone()?, entry.resume_url.clone()?))
+    }
+
+    /// Current session uptime of every shard, zero without an active
+    /// session so an ended session's gauge doesn't keep its last value
+    pub fn session_uptimes(&self) -> Vec<(u64, Duration)> {
+        self.inner
+            .shards
+            .iter()
+            .map(|e| (*e.key(), e.session_started_at.map_or(Duration::ZERO, |started| started.elapsed())))
+            .collect()
+    }
+
+    /// Record capabilities missing because Discord disallowed their intents
+    pub fn mark_degraded(&self, capabilities: &[&'static str]) {
+        let mut degraded = self.inner.degraded.lock().unwrap_or_else(|e| e.into_inner());
+        degraded.extend(capabilities.iter().copied());
+    }
+
+    /// Capabilities currently degraded, sorted by name
+    pub fn degraded_capabilities(&self) -> Vec<&'static str> {
+        let degraded = self.inner.degraded.lock().unwrap_or_else(|e| e.into_inner());
+        degraded.iter().copied().collect()
+    }
+
+    /// Mark the pool as shutting down
+    pub fn set_draining(&self) {
+        self.inner.draining.store(true, Ordering::SeqCst);
+    }
+
+    /// Whether shutdown has started
+    pub fn is_draining(&self) -> bool {
+        self.inner.draining.load(Ordering::SeqCst)
+    }
+
+    /// Start tracking a shard handed over by another pool
+    pub fn adopt(&self, shard_id: u64) {
+        self.inner.shards.entry(shard_id).or_default();
+    }
+
+    /// Stop tracking a shard handed over to another pool
+    pub fn release(&self, shard_id: u64) {
+        self.inner.shards.remove(&shard_id);
+    }
+
     /// Get health for a specific shard
     pub fn get_health(&self, shard_id: u64) -> Option<ShardHealth> {
         self.inner.shards.get(&shard_id).map(|e| e.health)
@@ -192,6 +452,39 @@ impl ShardState {
             .count()
     }
 
+    /// Shards marked dead, in ID order
+    pub fn dead_shards(&self) -> Vec<u64> {
+        let mut dead: Vec<u64> = self
+            .inner
+            .shards
+            .iter()
+            .filter(|e| e.health == ShardHealth::Dead)
+            .map(|e| *e.key())
+            .collect();
+        dead.sort_unstable();
+        dead
+    }
+
+    /// Snapshot of one shard, if this pool runs it
+    pub fn snapshot(&self, shard_id: u64) -> Option<ShardSnapshot> {
+        self.inner
+            .shards
+            .get(&shard_id)
+            .map(|entry| ShardSnapshot::new(shard_id, &entry))
+    }
+
+    /// Snapshots of every shard, in ID order
+    pub fn snapshots(&self) -> Vec<ShardSnapshot> {
+        let mut shards: Vec<ShardSnapshot> = self
+            .inner
+            .shards
+            .iter()
+            .map(|entry| ShardSnapshot::new(*entry.key(), &entry))
+            .collect();
+        shards.sort_unstable_by_key(|s| s.shard_id);
+        shards
+    }
+
     /// Get total shard count in this pool
     pub fn shard_count(&self) -> usize {
         self.inner.shards.len()
@@ -207,3 +500,81 @@ impl ShardState {
         self.healthy_shards() == self.shard_count()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn session_lifetime_reported_when_replaced_or_ended() {
+        let state = ShardState::new(0, 0..2, 2, 25);
+
+        assert_eq!(state.start_session(0, "a", "wss://resume-a"), None);
+        assert!(state.start_session(0, "b", "wss://resume-b").is_some(), "re-identify replaces the session");
+        assert_eq!(state.session_uptimes().len(), 2, "every shard is reported");
+        assert_eq!(state.session(0), Some(("b".to_string(), "wss://resume-b".to_string())));
+
+        assert!(state.end_session(0).is_some());
+        assert_eq!(state.session(0), None, "ended session forgets its resume URL");
+        assert_eq!(state.end_session(0), None, "ending twice reports nothing");
+        assert!(state.session_uptimes().contains(&(0, Duration::ZERO)), "an ended session reports zero");
+    }
+
+    #[test]
+    fn uptime_covers_the_current_connection_only() {
+        let state = ShardState::new(0, 0..1, 1, 25);
+        assert_eq!(state.snapshot(0).unwrap().uptime, None);
+
+        state.set_health(0, ShardHealth::Ready);
+        assert!(state.snapshot(0).unwrap().uptime.is_some());
+        state.set_health(0, ShardHealth::Resuming);
+        assert!(state.snapshot(0).unwrap().uptime.is_some(), "a resume keeps the connection's uptime");
+
+        state.set_health(0, ShardHealth::Disconnected);
+        assert_eq!(state.snapshot(0).unwrap().uptime, None);
+    }
+
+    #[test]
+    fn heartbeat_silence_follows_the_newest_heartbeat() {
+        let state = ShardState::new(0, 0..2, 2, 25);
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(state.heartbeat_silence() >= Duration::from_millis(20), "counts from start before any heartbeat");
+
+        state.record_heartbeat(1);
+        assert!(state.heartbeat_silence() < Duration::from_millis(20));
+    }
+
+    #[test]
+    fn sequences_count_per_shard_and_stream() {
+        let state = ShardState::new(0, 0..2, 2, 25);
+        let (epoch, first) = state.next_seq(0, "EVENTS");
+        assert_eq!(first, 1);
+        assert_eq!(state.next_seq(0, "EVENTS"), (epoch, 2));
+        assert_eq!(state.next_seq(0, "COMMANDS"), (epoch, 1));
+        assert_eq!(state.next_seq(1, "EVENTS"), (epoch, 1));
+        assert_eq!(state.clone().next_seq(0, "EVENTS"), (epoch, 3));
+    }
+
+    #[test]
+    fn draining_is_shared_by_clones() {
+        let state = ShardState::new(0, 0..1, 1, 25);
+        assert!(!state.is_draining());
+        state.clone().set_draining();
+        assert!(state.is_draining());
+    }
+
+    #[test]
+    fn degraded_capabilities_are_deduplicated() {
+        let state = ShardState::new(0, 0..2, 2, 25);
+        state.mark_degraded(&["GUILD_MEMBERS"]);
+        state.mark_degraded(&["GUILD_MEMBERS", "GUILD_PRESENCES"]);
+        assert_eq!(state.degraded_capabilities(), vec!["GUILD_MEMBERS", "GUILD_PRESENCES"]);
+    }
+
+    #[test]
+    fn session_calls_ignore_unknown_shards() {
+        let state = ShardState::new(0, 0..1, 1, 25);
+        assert_eq!(state.start_session(99, "a", "wss://resume-a"), None);
+        assert_eq!(state.end_session(99), None);
+    }
+}
@@ -0,0 +1,210 @@
+//! Discord token rotation without a restart
+//!
+//! `POST /admin/token` stores a new token for the pool, then restarts its
+//! shards one at a time, waiting for each to be ready on the new token before
+//! moving to the next. A shard with a session resumes it (Discord checks the
+//! token on resume); one without identifies through the pool's identify
+//! queue, so a rotation never turns into an identify storm. Drained shards
+//! aren't restarted; they pick the new token up when an operator starts them.
+
+use super::control::{ShardCommand, ShardControl};
+use super::state::{ShardHealth, ShardState};
+use crate::discord;
+use crate::metrics::GatewayMetrics;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
+
+/// How often a restarted shard's health is checked
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The pool's current Discord token; shards read it whenever they are rebuilt
+#[derive(Clone)]
+pub struct BotToken(Arc<RwLock<String>>);
+
+impl BotToken {
+    pub fn new(token: String) -> Self {
+        Self(Arc::new(RwLock::new(token)))
+    }
+
+    pub fn get(&self) -> String {
+        self.0.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    fn set(&self, token: String) {
+        *self.0.write().unwrap_or_else(|e| e.into_inner()) = token;
+    }
+}
+
+impl std::fmt::Debug for BotToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("BotToken(<redacted>)")
+    }
+}
+
+/// Why a new token was refused
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rejected {
+    /// Another rotation is still rolling through the pool
+    Busy,
+    /// The token is for another bot, or Discord didn't accept it
+    Invalid(String),
+}
+
+/// Rolls a new token through a pool's shards
+pub struct TokenRotation {
+    token: BotToken,
+    control: ShardControl,
+    state: ShardState,
+    metrics: Arc<GatewayMetrics>,
+    /// How long each shard may take to be ready again
+    shard_timeout: Duration,
+    running: AtomicBool,
+}
+
+impl TokenRotation {
+    pub fn new(
+        token: BotToken,
+        control: ShardControl,
+        state: ShardState,
+        metrics: Arc<GatewayMetrics>,
+        shard_timeout: Duration,
+    ) -> Self {
+        Self {
+            token,
+            control,
+            state,
+            metrics,
+            shard_timeout,
+            running: AtomicBool::new(false),
+        }
+    }
+
+    /// Check the token, store it and start restarting shards in the
+    /// background; returns the shards that will be restarted, in order
+    pub async fn start(self: &Arc<Self>, token: String) -> Result<Vec<u64>, Rejected> {
+        if self.running.swap(true, Ordering::AcqRel) {
+            return Err(Rejected::Busy);
+        }
+        if let Err(reason) = self.check(&token).await {
+            self.running.store(false, Ordering::Release);
+            self.metrics.record_token_rotation("rejected");
+            warn!(reason, "Rejected token rotation");
+            return Err(Rejected::Invalid(reason));
+        }
+
+        self.token.set(token);
+        let shard_ids: Vec<u64> = self
+            .state
+            .snapshots()
+            .iter()
+            .filter(|shard| shard.health != ShardHealth::Drained)
+            .map(|shard| shard.shard_id)
+            .collect();
+        info!(shards = ?shard_ids, "Token rotated - restarting shards one at a time");
+
+        let rotation = Arc::clone(self);
+        let order = shard_ids.clone();
+        tokio::spawn(async move {
+            let outcome = match roll(&rotation.control, &rotation.state, &order, rotation.shard_timeout).await {
+                Ok(()) => {
+                    info!(shards = order.len(), "Token rotation complete");
+                    "completed"
+                }
+                Err(shard_id) => {
+                    error!(shard_id, "Shard not ready on the new token - token rotation stopped");
+                    "failed"
+                }
+            };
+            rotation.metrics.record_token_rotation(outcome);
+            rotation.running.store(false, Ordering::Release);
+        });
+        Ok(shard_ids)
+    }
+
+    /// The token must be for this bot and accepted by Discord
+    async fn check(&self, token: &str) -> Result<(), String> {
+        if !same_bot(token, &self.token.get()) {
+            return Err("the token is not for this bot".to_string());
+        }
+        let client = twilight_http::Client::new(token.to_string());
+        discord::fetch_gateway_bot(&client)
+            .await
+            .map(|_| ())
+            .map_err(|e| format!("Discord rejected the token: {e}"))
+    }
+
+}
+
+/// Restart each shard and wait until it is ready again; the first shard
+/// that doesn't make it within `timeout` stops the rotation
+async fn roll(control: &ShardControl, state: &ShardState, shard_ids: &[u64], timeout: Duration) -> Result<(), u64> {
+    for &shard_id in shard_ids {
+        let restarted = Instant::now();
+        if !control.send(shard_id, ShardCommand::Restart) || !wait_ready(state, shard_id, restarted, timeout).await {
+            return Err(shard_id);
+        }
+        info!(shard_id, secs = restarted.elapsed().as_secs_f64(), "Shard ready on the new token");
+    }
+    Ok(())
+}
+
+/// Ready on a connection made after `since`
+async fn wait_ready(state: &ShardState, shard_id: u64, since: Instant, timeout: Duration) -> bool {
+    let polls = timeout.as_millis().div_ceil(POLL_INTERVAL.as_millis()).max(1);
+    for _ in 0..polls {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        let reconnected = state.snapshot(shard_id).is_some_and(|shard| {
+            shard.health == ShardHealth::Ready && shard.uptime.is_some_and(|uptime| uptime < since.elapsed())
+        });
+        if reconnected {
+            return true;
+        }
+    }
+    false
+}
+
+/// Discord tokens start with the bot's user ID (base64), so two tokens for
+/// the same bot share their first segment
+fn same_bot(token: &str, current: &str) -> bool {
+    let bot = |token: &str| token.split('.').next().filter(|id| !id.is_empty()).map(str::to_string);
+    bot(token).is_some() && bot(token) == bot(current)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_tokens_must_be_for_the_same_bot() {
+        let current = "MTIzNDU2Nzg5MDEyMzQ1Njc4.GaBcDe.old-secret";
+        assert!(same_bot("MTIzNDU2Nzg5MDEyMzQ1Njc4.HxYzWv.new-secret", current));
+        assert!(!same_bot("OTg3NjU0MzIxMDk4NzY1NDMy.HxYzWv.new-secret", current));
+        assert!(!same_bot("", current));
+        assert!(!same_bot(".HxYzWv.new-secret", ".GaBcDe.old-secret"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn shards_restart_one_at_a_time() {
+        let state = ShardState::new(0, 0..2, 2, 25);
+        state.set_health(0, ShardHealth::Ready);
+        state.set_health(1, ShardHealth::Ready);
+        let (control, mut commands) = ShardControl::channel();
+
+        let rolling = state.clone();
+        let roll = tokio::spawn(async move { roll(&control, &rolling, &[0, 1], Duration::from_secs(5)).await });
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        assert_eq!(commands.try_recv().unwrap(), (0, ShardCommand::Restart));
+        assert!(commands.try_recv().is_err(), "shard 1 waits for shard 0");
+
+        // Shard 0 reconnects
+        state.set_health(0, ShardHealth::Connecting);
+        state.set_health(0, ShardHealth::Ready);
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        assert_eq!(commands.try_recv().unwrap(), (1, ShardCommand::Restart));
+
+        // Shard 1 never comes back, which stops the rotation
+        assert_eq!(roll.await.unwrap(), Err(1));
+    }
+}
@@ -0,0 +1,160 @@
+//! Received-vs-routed divergence watchdog
+//!
+//! Every event a shard receives ends up routed, filtered (not forwarded,
+//! flag-gated, aggregated, or no NATS) or failed. Over a sliding window the
+//! received count should match the other three, plus any events still queued
+//! for publishing; a gap means events vanished
+//! between the counters. Gaps beyond the tolerance are flagged on the shard
+//! state (picked up by ops alerts) and exported as metrics.
+
+use super::state::{ShardSnapshot, ShardState};
+use crate::metrics::GatewayMetrics;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+/// How often the counters are sampled
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Watchdog settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DivergenceConfig {
+    /// Sliding window the counters are compared over
+    pub window: Duration,
+    /// Events that may go unaccounted for within a window (in flight)
+    pub tolerance: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    at: Instant,
+    received: u64,
+    accounted: u64,
+}
+
+impl Sample {
+    fn new(at: Instant, shard: &ShardSnapshot) -> Self {
+        Self {
+            at,
+            received: shard.events_received,
+            // Queued events are on their way to being routed
+            accounted: shard.events_routed + shard.events_filtered + shard.route_failures + shard.events_queued,
+        }
+    }
+}
+
+/// Per-shard counter samples covering the window
+#[derive(Debug)]
+pub struct Watchdog {
+    config: DivergenceConfig,
+    samples: HashMap<u64, VecDeque<Sample>>,
+}
+
+impl Watchdog {
+    pub fn new(config: DivergenceConfig) -> Self {
+        Self {
+            config,
+            samples: HashMap::new(),
+        }
+    }
+
+    /// Record a sample of every shard and return the events unaccounted for
+    /// over the window, per shard
+    pub fn observe(&mut self, now: Instant, shards: &[ShardSnapshot]) -> Vec<(u64, u64)> {
+        shards
+            .iter()
+            .map(|shard| {
+                let samples = self.samples.entry(shard.shard_id).or_default();
+                samples.push_back(Sample::new(now, shard));
+                while samples[0].at + self.config.window < now {
+                    samples.pop_front();
+                }
+
+                let (oldest, latest) = (samples[0], samples[samples.len() - 1]);
+                let received = latest.received.saturating_sub(oldest.received);
+                let accounted = latest.accounted.saturating_sub(oldest.accounted);
+                (shard.shard_id, received.saturating_sub(accounted))
+            })
+            .collect()
+    }
+
+    /// Whether a gap is beyond the tolerance
+    pub fn diverged(&self, unaccounted: u64) -> bool {
+        unaccounted > self.config.tolerance
+    }
+}
+
+/// Sample the shard counters until the process exits
+pub async fn run_watchdog(config: DivergenceConfig, state: ShardState, metrics: Arc<GatewayMetrics>) {
+    let mut watchdog = Watchdog::new(config);
+    let mut diverged_shards = HashSet::new();
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+
+    info!(window_secs = config.window.as_secs(), tolerance = config.tolerance, "Divergence watchdog started");
+
+    loop {
+        interval.tick().await;
+
+        for (shard_id, unaccounted) in watchdog.observe(Instant::now(), &state.snapshots()) {
+            metrics.set_events_unaccounted(shard_id, unaccounted);
+
+            let diverged = watchdog.diverged(unaccounted);
+            if !diverged {
+                diverged_shards.remove(&shard_id);
+            } else if diverged_shards.insert(shard_id) {
+                metrics.record_event_divergence(shard_id);
+                warn!(
+                    shard_id,
+                    unaccounted,
+                    window_secs = config.window.as_secs(),
+                    "Received events diverged from routed + filtered + failed"
+                );
+            }
+            state.set_divergence(shard_id, diverged.then_some(unaccounted));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shard::ShardHealth;
+
+    fn shard(received: u64, routed: u64, filtered: u64, failed: u64) -> ShardSnapshot {
+        ShardSnapshot {
+            shard_id: 4,
+            health: ShardHealth::Ready,
+            guilds: 0,
+            events_received: received,
+            events_routed: routed,
+            route_failures: failed,
+            events_filtered: filtered,
+            events_queued: 0,
+            resumes: 0,
+            heartbeat_age: None,
+            uptime: None,
+            session_uptime: None,
+            resumable: true,
+        }
+    }
+
+    #[test]
+    fn test_gap_is_measured_over_the_window() {
+        let config = DivergenceConfig { window: Duration::from_secs(60), tolerance: 5 };
+        let mut watchdog = Watchdog::new(config);
+        let start = Instant::now();
+
+        // Only changes within the window count, not the counters' history
+        assert_eq!(watchdog.observe(start, &[shard(1_000, 900, 50, 0)]), [(4, 0)]);
+        assert_eq!(watchdog.observe(start + CHECK_INTERVAL, &[shard(1_100, 980, 60, 0)]), [(4, 10)]);
+        assert!(watchdog.diverged(10));
+        assert!(!watchdog.diverged(5));
+
+        // Samples older than the window are dropped, so the first one no longer counts
+        let later = start + Duration::from_secs(120);
+        assert_eq!(watchdog.observe(later, &[shard(1_200, 1_080, 70, 40)]), [(4, 0)]);
+        assert_eq!(watchdog.observe(later + CHECK_INTERVAL, &[shard(1_300, 1_080, 70, 40)]), [(4, 100)]);
+    }
+}
@@ -0,0 +1,144 @@
+//! Distributed tracing (OpenTelemetry)
+//!
+//! With `OTEL_EXPORTER_OTLP_ENDPOINT` set, `tracing` spans are exported over
+//! OTLP/gRPC and every event publish carries a W3C `traceparent` header, so
+//! TS workers can continue the trace from the NATS message. The trace for an
+//! event starts at `nats.publish` (schema check, publish budget wait, ack);
+//! unsampled events are published without the header.
+
+use crate::error::GatewayError;
+use opentelemetry::propagation::{Injector, TextMapPropagator};
+use opentelemetry::trace::{TraceContextExt, TracerProvider as _};
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{Sampler, SdkTracerProvider};
+use opentelemetry_sdk::Resource;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+/// Service name reported with every span
+pub const SERVICE_NAME: &str = "arrakis-gateway";
+
+/// OTLP trace export
+#[derive(Debug, Clone)]
+pub struct TraceConfig {
+    /// Collector endpoint (`OTEL_EXPORTER_OTLP_ENDPOINT`, e.g. `http://otel-collector:4317`)
+    pub endpoint: String,
+    /// Share of events traced, 0.0 to 1.0 (`OTEL_TRACES_SAMPLE_RATIO`)
+    pub sample_ratio: f64,
+}
+
+/// Build the tracer provider exporting this pool's spans.
+///
+/// Must run inside the Tokio runtime (the gRPC exporter spawns onto it).
+pub fn init(config: &TraceConfig, pool_id: u64) -> Result<SdkTracerProvider, GatewayError> {
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_tonic()
+        .with_endpoint(config.endpoint.clone())
+        .build()
+        .map_err(|e| GatewayError::Config(format!("OTEL_EXPORTER_OTLP_ENDPOINT: {e}")))?;
+
+    let resource = Resource::builder()
+        .with_service_name(SERVICE_NAME)
+        .with_attributes([
+            KeyValue::new("service.version", env!("CARGO_PKG_VERSION")),
+            KeyValue::new("gateway.pool_id", pool_id as i64),
+        ])
+        .build();
+
+    Ok(SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_sampler(Sampler::TraceIdRatioBased(config.sample_ratio))
+        .with_resource(resource)
+        .build())
+}
+
+/// `tracing` layer feeding spans to the provider
+pub fn layer<S>(provider: &SdkTracerProvider) -> impl tracing_subscriber::Layer<S>
+where
+    S: tracing::Subscriber + for<'span> tracing_subscriber::registry::LookupSpan<'span>,
+{
+    tracing_opentelemetry::layer().with_tracer(provider.tracer(SERVICE_NAME))
+}
+
+/// Flush buffered spans; call once on shutdown
+pub async fn shutdown(provider: SdkTracerProvider) {
+    // The batch processor blocks while flushing
+    let flushed = tokio::task::spawn_blocking(move || provider.shutdown()).await;
+    if let Ok(Err(e)) = flushed {
+        tracing::warn!(error = %e, "Failed to flush traces");
+    }
+}
+
+/// Trace context headers for a publish made in the current span.
+///
+/// None when tracing is off or the event isn't sampled.
+pub fn trace_headers() -> Option<async_nats::HeaderMap> {
+    let context = tracing::Span::current().context();
+    if !context.span().span_context().is_sampled() {
+        return None;
+    }
+    let mut headers = NatsHeaders(async_nats::HeaderMap::new());
+    TraceContextPropagator::new().inject_context(&context, &mut headers);
+    Some(headers.0)
+}
+
+/// Hex trace ID of a span, when it is sampled
+pub fn trace_id(span: &tracing::Span) -> Option<String> {
+    let context = span.context();
+    let span_context = context.span().span_context().clone();
+    span_context.is_sampled().then(|| span_context.trace_id().to_string())
+}
+
+/// Add the current trace context to headers already being sent
+pub fn inject(headers: &mut async_nats::HeaderMap) {
+    if let Some(trace) = trace_headers() {
+        for (name, values) in trace.iter() {
+            for value in values {
+                headers.insert(name.clone(), value.clone());
+            }
+        }
+    }
+}
+
+struct NatsHeaders(async_nats::HeaderMap);
+
+impl Injector for NatsHeaders {
+    fn set(&mut self, key: &str, value: String) {
+        self.0.insert(key, value.as_str());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::trace::{SpanContext, SpanId, TraceFlags, TraceId, TraceState};
+
+    #[test]
+    fn traceparent_carries_trace_and_span_ids() {
+        let span_context = |flags| {
+            SpanContext::new(
+                TraceId::from_hex("4bf92f3577b34da6a3ce929d0e0e4736").unwrap(),
+                SpanId::from_hex("00f067aa0ba902b7").unwrap(),
+                flags,
+                false,
+                TraceState::default(),
+            )
+        };
+        let inject = |context: opentelemetry::Context| {
+            let mut headers = NatsHeaders(async_nats::HeaderMap::new());
+            TraceContextPropagator::new().inject_context(&context, &mut headers);
+            headers.0.get("traceparent").map(|value| value.to_string())
+        };
+
+        let sampled = opentelemetry::Context::new().with_remote_span_context(span_context(TraceFlags::SAMPLED));
+        assert_eq!(inject(sampled).as_deref(), Some("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"));
+        let unsampled = opentelemetry::Context::new().with_remote_span_context(span_context(TraceFlags::default()));
+        assert_eq!(inject(unsampled).as_deref(), Some("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-00"));
+    }
+
+    #[test]
+    fn no_headers_without_tracing() {
+        assert!(trace_headers().is_none());
+    }
+}
@@ -0,0 +1,295 @@
+//! `arrakis-gateway topo`: where a guild's events should arrive
+//!
+//! The first question in a "missing events" incident. Given a guild ID and
+//! the cluster's shard count, prints the owning shard and pool; given
+//! topology documents (a saved snapshot of the `gateway_topology` bucket, or
+//! the live bucket), also prints the running pool serving that shard.
+//!
+//! ```text
+//! arrakis-gateway topo <guild_id> --shards <total>
+//! arrakis-gateway topo <guild_id> --snapshot <file, or - for stdin>
+//! arrakis-gateway topo <guild_id> --nats <url>
+//! ```
+//!
+//! A snapshot holds topology documents as a JSON array or one per line.
+//! `--shards` and `--shards-per-pool` override what the documents report;
+//! without documents the pool size defaults to 25.
+
+use crate::config;
+use crate::error::GatewayError;
+use crate::events::serialize::now_millis;
+use crate::nats::topology::{self, PoolTopology};
+use crate::nats::{NatsPublisher, PublisherOptions};
+use crate::shard::{pool_for_shard, shard_for_guild, DEFAULT_SHARDS_PER_POOL};
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+use std::io::Read as _;
+
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Args {
+    guild_id: u64,
+    shards: Option<u64>,
+    shards_per_pool: Option<u64>,
+    snapshot: Option<String>,
+    nats: Option<String>,
+}
+
+/// Run the subcommand with the arguments after `topo`
+pub async fn run(args: &[String]) -> Result<(), GatewayError> {
+    let args = parse_args(args)?;
+
+    let pools = match (&args.snapshot, &args.nats) {
+        (Some(path), _) => Some(parse_documents(&read_snapshot(path)?)?),
+        (None, Some(url)) => Some(read_bucket(url).await?),
+        (None, None) => None,
+    };
+    let documents = pools.as_deref().unwrap_or_default();
+    let total_shards = match args.shards {
+        Some(total) => total,
+        None => reported_total(documents)?,
+    };
+    let shards_per_pool = match args.shards_per_pool {
+        Some(size) => size,
+        None => agreed(documents, "shards_per_pool", "--shards-per-pool", |pool| pool.shards_per_pool)?
+            .unwrap_or(DEFAULT_SHARDS_PER_POOL),
+    };
+
+    print!("{}", report(args.guild_id, total_shards, shards_per_pool, pools.as_deref(), now_millis()));
+    Ok(())
+}
+
+fn parse_args(args: &[String]) -> Result<Args, GatewayError> {
+    let usage = || {
+        GatewayError::Config(
+            "usage: arrakis-gateway topo <guild_id> (--shards <total> | --snapshot <file> | --nats <url>) \
+             [--shards-per-pool <size>]"
+                .to_string(),
+        )
+    };
+
+    let mut parsed = Args::default();
+    let mut guild_id = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag, Some(value.to_string())),
+            _ => (arg.as_str(), None),
+        };
+        if !flag.starts_with("--") {
+            let id = arg.parse().map_err(|_| GatewayError::Config(format!("Invalid guild ID: {arg}")))?;
+            if guild_id.replace(id).is_some() {
+                return Err(usage());
+            }
+            continue;
+        }
+
+        let value = inline.or_else(|| args.next().cloned()).ok_or_else(usage)?;
+        let positive = || {
+            value
+                .parse()
+                .ok()
+                .filter(|n| *n > 0)
+                .ok_or_else(|| GatewayError::Config(format!("{flag} must be a positive number, got {value:?}")))
+        };
+        match flag {
+            "--shards" => parsed.shards = Some(positive()?),
+            "--shards-per-pool" => parsed.shards_per_pool = Some(positive()?),
+            "--snapshot" => parsed.snapshot = Some(value),
+            "--nats" => parsed.nats = Some(value),
+            _ => return Err(GatewayError::Config(format!("Unknown argument: {arg}"))),
+        }
+    }
+
+    parsed.guild_id = guild_id.ok_or_else(usage)?;
+    let sources = usize::from(parsed.snapshot.is_some()) + usize::from(parsed.nats.is_some());
+    if sources > 1 || (sources == 0 && parsed.shards.is_none()) {
+        return Err(usage());
+    }
+    Ok(parsed)
+}
+
+fn read_snapshot(path: &str) -> Result<String, GatewayError> {
+    let contents = if path == "-" {
+        let mut contents = String::new();
+        std::io::stdin().read_to_string(&mut contents).map(|_| contents)
+    } else {
+        std::fs::read_to_string(path)
+    };
+    contents.map_err(|e| GatewayError::Config(format!("Failed to read topology snapshot {path}: {e}")))
+}
+
+/// Topology documents from a JSON array, or one document per line
+fn parse_documents(contents: &str) -> Result<Vec<PoolTopology>, GatewayError> {
+    let invalid = |e: serde_json::Error| GatewayError::Config(format!("Invalid topology snapshot: {e}"));
+
+    let mut pools = Vec::new();
+    for value in serde_json::Deserializer::from_str(contents).into_iter::<Value>() {
+        match value.map_err(invalid)? {
+            Value::Array(documents) => {
+                for document in documents {
+                    pools.push(serde_json::from_value(document).map_err(invalid)?);
+                }
+            }
+            document => pools.push(serde_json::from_value(document).map_err(invalid)?),
+        }
+    }
+    Ok(pools)
+}
+
+/// Every pool document in the live `gateway_topology` bucket
+async fn read_bucket(url: &str) -> Result<Vec<PoolTopology>, GatewayError> {
+    let options = PublisherOptions {
+        security: config::nats_security_from_env()?,
+        ..PublisherOptions::default()
+    };
+    let nats = NatsPublisher::connect(url, options).await?;
+    topology::list(&nats).await
+}
+
+/// The cluster shard count the pools agree on
+fn reported_total(pools: &[PoolTopology]) -> Result<u64, GatewayError> {
+    agreed(pools, "total_shards", "--shards", |pool| pool.total_shards)?
+        .ok_or_else(|| GatewayError::Config("No topology documents found".to_string()))
+}
+
+/// A setting every pool reports the same value for (None without documents)
+fn agreed(
+    pools: &[PoolTopology],
+    name: &str,
+    flag: &str,
+    setting: impl Fn(&PoolTopology) -> u64,
+) -> Result<Option<u64>, GatewayError> {
+    let values: BTreeSet<u64> = pools.iter().map(setting).collect();
+    if values.len() > 1 {
+        return Err(GatewayError::Config(format!(
+            "Pools disagree on {name} {values:?}; pass {flag} to pick one"
+        )));
+    }
+    Ok(values.into_iter().next())
+}
+
+fn report(
+    guild_id: u64,
+    total_shards: u64,
+    shards_per_pool: u64,
+    pools: Option<&[PoolTopology]>,
+    now_ms: u64,
+) -> String {
+    let shard_id = shard_for_guild(guild_id, total_shards);
+    let pool_id = pool_for_shard(shard_id, shards_per_pool);
+
+    let mut out = format!("guild {guild_id}: shard {shard_id} of {total_shards}, pool {pool_id}\n");
+    let Some(pools) = pools else {
+        return out;
+    };
+
+    for pool in pools.iter().filter(|pool| pool.total_shards != total_shards) {
+        let _ = writeln!(out, "warning: pool {} runs with total_shards {}", pool.pool_id, pool.total_shards);
+    }
+
+    let serving: Vec<_> = pools
+        .iter()
+        .filter_map(|pool| Some((pool, pool.shards.iter().find(|shard| shard.shard_id == shard_id)?)))
+        .collect();
+    for (pool, shard) in &serving {
+        let _ = writeln!(
+            out,
+            "served by pool {} (version {}, snapshot {}s old): shard {} {}, {} guilds",
+            pool.pool_id,
+            pool.version,
+            now_ms.saturating_sub(pool.timestamp) / 1000,
+            shard.shard_id,
+            shard.health,
+            shard.guilds
+        );
+    }
+
+    match serving.len() {
+        0 if pools.iter().any(|pool| pool.pool_id == pool_id) => {
+            let _ = writeln!(out, "not served: pool {pool_id} is running but not shard {shard_id} (--only-shards?)");
+        }
+        0 => {
+            let _ = writeln!(out, "not served: pool {pool_id} has no topology entry (down or not publishing)");
+        }
+        1 => {}
+        n => {
+            let _ = writeln!(out, "warning: shard {shard_id} is run by {n} pools");
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nats::topology::ShardTopology;
+
+    const GUILD: u64 = 987654321098765432;
+
+    fn args(line: &str) -> Result<Args, GatewayError> {
+        parse_args(&line.split_whitespace().map(String::from).collect::<Vec<_>>())
+    }
+
+    fn pool(pool_id: u64, shards: &[u64]) -> PoolTopology {
+        PoolTopology {
+            pool_id,
+            total_shards: 100,
+            shards_per_pool: 25,
+            version: "0.2.0".to_string(),
+            discord_api_version: 10,
+            discord_api_mode: "pinned".to_string(),
+            guilds_total: 0,
+            shards: shards
+                .iter()
+                .map(|&shard_id| ShardTopology { shard_id, health: "ready".to_string(), guilds: 40 })
+                .collect(),
+            timestamp: 1_700_000_000_000,
+        }
+    }
+
+    #[test]
+    fn test_args_need_a_guild_and_a_source() {
+        assert_eq!(
+            args("987654321098765432 --shards=100").unwrap(),
+            Args { guild_id: GUILD, shards: Some(100), ..Default::default() }
+        );
+        let live = args("--nats nats://localhost:4222 987654321098765432").unwrap();
+        assert_eq!(live.nats.as_deref(), Some("nats://localhost:4222"));
+
+        assert!(args("987654321098765432").is_err());
+        assert!(args("--shards 100").is_err());
+        assert!(args("987654321098765432 --shards 0").is_err());
+        assert!(args("987654321098765432 --snapshot a.json --nats nats://x").is_err());
+        assert!(args("987654321098765432 --pool 3").is_err());
+        assert_eq!(args("987654321098765432 --shards 100 --shards-per-pool 40").unwrap().shards_per_pool, Some(40));
+        assert!(args("987654321098765432 --shards 100 --shards-per-pool 0").is_err());
+    }
+
+    #[test]
+    fn test_report_names_the_serving_pool() {
+        let pools = [pool(0, &[0, 1]), pool(3, &[78, 79])];
+        assert_eq!(
+            report(GUILD, 100, 25, Some(&pools), 1_700_000_012_000),
+            "guild 987654321098765432: shard 79 of 100, pool 3\n\
+             served by pool 3 (version 0.2.0, snapshot 12s old): shard 79 ready, 40 guilds\n"
+        );
+
+        let report = report(GUILD, 100, 25, Some(&[pool(3, &[78])]), 1_700_000_000_000);
+        assert!(report.ends_with("not served: pool 3 is running but not shard 79 (--only-shards?)\n"));
+
+        // Larger pools move the shard to a lower pool ID
+        assert!(super::report(GUILD, 100, 40, None, 0).ends_with("shard 79 of 100, pool 1\n"));
+    }
+
+    #[test]
+    fn test_snapshot_accepts_arrays_and_lines() {
+        let array = serde_json::to_string(&[pool(0, &[0]), pool(1, &[25])]).unwrap();
+        let line = |pool: PoolTopology| serde_json::to_string(&pool).unwrap();
+        let lines = format!("{}\n{}\n", line(pool(0, &[0])), line(pool(1, &[25])));
+
+        assert_eq!(parse_documents(&array).unwrap(), parse_documents(&lines).unwrap());
+        assert_eq!(reported_total(&parse_documents(&array).unwrap()).unwrap(), 100);
+        assert!(reported_total(&[]).is_err());
+    }
+}
@@ -44,6 +44,7 @@ fn build_deterministic_event(fixture_name: &str) -> Value {
         "guild-join" => serde_json::json!({
             "event_id": "00000000-0000-4000-8000-000000000001",
             "event_type": "guild.join",
+            "schema_version": 1,
             "shard_id": 0,
             "timestamp": 1700000000000_u64,
             "guild_id": "123456789012345678",
@@ -58,6 +59,7 @@ fn build_deterministic_event(fixture_name: &str) -> Value {
         "guild-leave" => serde_json::json!({
             "event_id": "00000000-0000-4000-8000-000000000002",
             "event_type": "guild.leave",
+            "schema_version": 1,
             "shard_id": 0,
             "timestamp": 1700000000000_u64,
             "guild_id": "123456789012345678",
@@ -70,6 +72,7 @@ fn build_deterministic_event(fixture_name: &str) -> Value {
         "interaction-create" => serde_json::json!({
             "event_id": "00000000-0000-4000-8000-000000000006",
             "event_type": "interaction.create",
+            "schema_version": 1,
             "shard_id": 0,
             "timestamp": 1700000000000_u64,
             "guild_id": "123456789012345678",
@@ -78,13 +81,377 @@ fn build_deterministic_event(fixture_name: &str) -> Value {
             "data": {
                 "interaction_id": "444444444444444444",
                 "interaction_type": "ApplicationCommand",
-                "interaction_token": "aW50ZXJhY3Rpb25fdG9rZW5fZXhhbXBsZQ"
+                "interaction_token": "aW50ZXJhY3Rpb25fdG9rZW5fZXhhbXBsZQ",
+                "application_id": "100000000000000001",
+                "is_bot": false,
+                "command_name": "verify",
+                "subcommand": null,
+                "custom_id": null,
+                "component_type": null,
+                "values": null,
+                "fields": null,
+                "context": "guild",
+                "authorizing_integration_owners": { "0": "123456789012345678" },
+                "options": [
+                    { "name": "member", "type": "User", "value": "222222222222222222" },
+                    { "name": "tier", "type": "Integer", "value": 3 }
+                ],
+                "resolved": {
+                    "users": {
+                        "222222222222222222": {
+                            "id": "222222222222222222",
+                            "username": "holder",
+                            "global_name": "Holder",
+                            "avatar": null,
+                            "bot": false
+                        }
+                    },
+                    "members": {
+                        "222222222222222222": {
+                            "nick": null,
+                            "roles": ["888888888888888888"],
+                            "joined_at": 1704067200000_u64,
+                            "avatar": null,
+                            "permissions": "1024"
+                        }
+                    },
+                    "roles": {},
+                    "channels": {},
+                    "attachments": {}
+                },
+                "locale": "en-US",
+                "guild_locale": "de",
+                "deferred": false
+            }
+        }),
+        "interaction-create-dm" => serde_json::json!({
+            "event_id": "00000000-0000-4000-8000-000000000008",
+            "event_type": "interaction.create",
+            "schema_version": 1,
+            "shard_id": 0,
+            "timestamp": 1700000000000_u64,
+            "guild_id": null,
+            "channel_id": "333333333333333333",
+            "user_id": "987654321098765432",
+            "data": {
+                "interaction_id": "444444444444444444",
+                "interaction_type": "ApplicationCommand",
+                "interaction_token": "aW50ZXJhY3Rpb25fdG9rZW5fZXhhbXBsZQ",
+                "application_id": "100000000000000001",
+                "is_bot": false,
+                "command_name": "verify",
+                "subcommand": null,
+                "custom_id": null,
+                "component_type": null,
+                "values": null,
+                "fields": null,
+                "context": "bot_dm",
+                "authorizing_integration_owners": { "1": "987654321098765432" },
+                "options": [],
+                "resolved": null,
+                "locale": "pt-BR",
+                "guild_locale": null,
+                "deferred": false
+            }
+        }),
+        "interaction-component" => serde_json::json!({
+            "event_id": "00000000-0000-4000-8000-000000000012",
+            "event_type": "interaction.create",
+            "schema_version": 1,
+            "shard_id": 0,
+            "timestamp": 1700000000000_u64,
+            "guild_id": "123456789012345678",
+            "channel_id": "333333333333333333",
+            "user_id": "987654321098765432",
+            "data": {
+                "interaction_id": "444444444444444444",
+                "interaction_type": "MessageComponent",
+                "interaction_token": "aW50ZXJhY3Rpb25fdG9rZW5fZXhhbXBsZQ",
+                "application_id": "100000000000000001",
+                "is_bot": false,
+                "command_name": null,
+                "subcommand": null,
+                "custom_id": "role-picker",
+                "component_type": "SelectMenu",
+                "values": ["holder", "og"],
+                "fields": null,
+                "context": "guild",
+                "authorizing_integration_owners": { "0": "123456789012345678" },
+                "options": null,
+                "resolved": null,
+                "locale": "en-US",
+                "guild_locale": "de",
+                "deferred": false
+            }
+        }),
+        "interaction-modal" => serde_json::json!({
+            "event_id": "00000000-0000-4000-8000-000000000013",
+            "event_type": "interaction.create",
+            "schema_version": 1,
+            "shard_id": 0,
+            "timestamp": 1700000000000_u64,
+            "guild_id": "123456789012345678",
+            "channel_id": "333333333333333333",
+            "user_id": "987654321098765432",
+            "data": {
+                "interaction_id": "444444444444444444",
+                "interaction_type": "ModalSubmit",
+                "interaction_token": "aW50ZXJhY3Rpb25fdG9rZW5fZXhhbXBsZQ",
+                "application_id": "100000000000000001",
+                "is_bot": false,
+                "command_name": null,
+                "subcommand": null,
+                "custom_id": "verify-modal",
+                "component_type": null,
+                "values": null,
+                "fields": [
+                    { "custom_id": "wallet", "value": "0x1234" },
+                    { "custom_id": "note", "value": "gm" },
+                    { "custom_id": "chain", "values": ["berachain"] }
+                ],
+                "context": "guild",
+                "authorizing_integration_owners": { "0": "123456789012345678" },
+                "options": null,
+                "resolved": null,
+                "locale": "en-US",
+                "guild_locale": "de",
+                "deferred": false
+            }
+        }),
+        "message-create" => serde_json::json!({
+            "event_id": "00000000-0000-4000-8000-000000000009",
+            "event_type": "message.create",
+            "schema_version": 1,
+            "shard_id": 0,
+            "timestamp": 1700000000000_u64,
+            "guild_id": "123456789012345678",
+            "channel_id": "333333333333333333",
+            "user_id": "987654321098765432",
+            "data": {
+                "message_id": "666666666666666666",
+                "content": "",
+                "author": {
+                    "id": "987654321098765432",
+                    "username": "user",
+                    "global_name": null,
+                    "avatar": null,
+                    "bot": false
+                },
+                "attachments": [
+                    {
+                        "id": "777777777777777777",
+                        "filename": "clip.mp4",
+                        "content_type": "video/mp4",
+                        "size": 1048576,
+                        "url": "https://cdn.discordapp.com/attachments/333333333333333333/777777777777777777/clip.mp4"
+                    }
+                ]
+            }
+        }),
+        "message-update" => {
+            let mut event = build_deterministic_event("message-create");
+            event["event_id"] = Value::from("00000000-0000-4000-8000-000000000014");
+            event["event_type"] = Value::from("message.update");
+            event["data"]["edited_at"] = Value::from(1700000100000_u64);
+            event
+        }
+        "message-delete" => serde_json::json!({
+            "event_id": "00000000-0000-4000-8000-000000000015",
+            "event_type": "message.delete",
+            "schema_version": 1,
+            "shard_id": 0,
+            "timestamp": 1700000000000_u64,
+            "guild_id": "123456789012345678",
+            "channel_id": "333333333333333333",
+            "user_id": null,
+            "data": { "message_id": "666666666666666666" }
+        }),
+        "message-bulk-delete" => serde_json::json!({
+            "event_id": "00000000-0000-4000-8000-000000000016",
+            "event_type": "message.bulk_delete",
+            "schema_version": 1,
+            "shard_id": 0,
+            "timestamp": 1700000000000_u64,
+            "guild_id": "123456789012345678",
+            "channel_id": "333333333333333333",
+            "user_id": null,
+            "data": { "message_ids": ["666666666666666666", "666666666666666667"] }
+        }),
+        "reaction-add" => serde_json::json!({
+            "event_id": "00000000-0000-4000-8000-000000000017",
+            "event_type": "reaction.add",
+            "schema_version": 1,
+            "shard_id": 0,
+            "timestamp": 1700000000000_u64,
+            "guild_id": "123456789012345678",
+            "channel_id": "333333333333333333",
+            "user_id": "987654321098765432",
+            "data": {
+                "message_id": "666666666666666666",
+                "message_author_id": "222222222222222222",
+                "emoji": { "id": "999999999999999999", "name": "honey", "animated": false },
+                "burst": false,
+                "is_bot": false
+            }
+        }),
+        "reaction-remove-all" => serde_json::json!({
+            "event_id": "00000000-0000-4000-8000-000000000018",
+            "event_type": "reaction.remove_all",
+            "schema_version": 1,
+            "shard_id": 0,
+            "timestamp": 1700000000000_u64,
+            "guild_id": "123456789012345678",
+            "channel_id": "333333333333333333",
+            "user_id": null,
+            "data": { "message_id": "666666666666666666" }
+        }),
+        "voice-state-update" => serde_json::json!({
+            "event_id": "00000000-0000-4000-8000-000000000019",
+            "event_type": "voice.state_update",
+            "schema_version": 1,
+            "shard_id": 0,
+            "timestamp": 1700000000000_u64,
+            "guild_id": "123456789012345678",
+            "channel_id": "777777777777777777",
+            "user_id": "987654321098765432",
+            "data": {
+                "session_id": "9f8e7d6c5b4a39281706f5e4d3c2b1a0",
+                "deaf": false,
+                "mute": false,
+                "self_deaf": false,
+                "self_mute": true,
+                "self_stream": false,
+                "self_video": false,
+                "suppress": false,
+                "request_to_speak_at": null,
+                "is_bot": false
+            }
+        }),
+        "voice-server-update" => serde_json::json!({
+            "event_id": "00000000-0000-4000-8000-000000000020",
+            "event_type": "voice.server_update",
+            "schema_version": 1,
+            "shard_id": 0,
+            "timestamp": 1700000000000_u64,
+            "guild_id": "123456789012345678",
+            "channel_id": null,
+            "user_id": null,
+            "data": { "endpoint": "us-east1234.discord.media:443", "token": "0f1e2d3c4b5a6978" }
+        }),
+        "role-create" => serde_json::json!({
+            "event_id": "00000000-0000-4000-8000-000000000024",
+            "event_type": "role.create",
+            "schema_version": 1,
+            "shard_id": 0,
+            "timestamp": 1700000000000_u64,
+            "guild_id": "123456789012345678",
+            "channel_id": null,
+            "user_id": null,
+            "data": {
+                "id": "888888888888888888",
+                "name": "Holder",
+                "color": 15844367,
+                "position": 5,
+                "permissions": "104324673",
+                "managed": false,
+                "mentionable": true,
+                "hoist": true,
+                "icon": null,
+                "unicode_emoji": "🍯",
+                "tags": null
+            }
+        }),
+        "role-delete" => serde_json::json!({
+            "event_id": "00000000-0000-4000-8000-000000000025",
+            "event_type": "role.delete",
+            "schema_version": 1,
+            "shard_id": 0,
+            "timestamp": 1700000000000_u64,
+            "guild_id": "123456789012345678",
+            "channel_id": null,
+            "user_id": null,
+            "data": { "role_id": "888888888888888888" }
+        }),
+        "channel-create" => serde_json::json!({
+            "event_id": "00000000-0000-4000-8000-000000000021",
+            "event_type": "channel.create",
+            "schema_version": 1,
+            "shard_id": 0,
+            "timestamp": 1700000000000_u64,
+            "guild_id": "123456789012345678",
+            "channel_id": "333333333333333333",
+            "user_id": null,
+            "data": {
+                "id": "333333333333333333",
+                "name": "holders-lounge",
+                "type": "GuildText",
+                "parent_id": "444444444444444444",
+                "position": 4,
+                "topic": "Verified holders only",
+                "nsfw": false
+            }
+        }),
+        "thread-create" => serde_json::json!({
+            "event_id": "00000000-0000-4000-8000-000000000022",
+            "event_type": "thread.create",
+            "schema_version": 1,
+            "shard_id": 0,
+            "timestamp": 1700000000000_u64,
+            "guild_id": "123456789012345678",
+            "channel_id": "555555555555555555",
+            "user_id": "987654321098765432",
+            "data": thread()
+        }),
+        "thread-list-sync" => serde_json::json!({
+            "event_id": "00000000-0000-4000-8000-000000000023",
+            "event_type": "thread.list_sync",
+            "schema_version": 1,
+            "shard_id": 0,
+            "timestamp": 1700000000000_u64,
+            "guild_id": "123456789012345678",
+            "channel_id": null,
+            "user_id": null,
+            "data": {
+                "channel_ids": ["333333333333333333"],
+                "threads": [thread()]
+            }
+        }),
+        "gateway-capability-degraded" => serde_json::json!({
+            "event_id": "00000000-0000-4000-8000-000000000007",
+            "event_type": "gateway.capability_degraded",
+            "schema_version": 1,
+            "shard_id": 0,
+            "timestamp": 1700000000000_u64,
+            "guild_id": null,
+            "channel_id": null,
+            "user_id": null,
+            "data": {
+                "close_code": 4014,
+                "missing_intents": ["GUILD_MEMBERS"],
+                "active_intents": ["GUILDS"]
             }
         }),
         other => panic!("Unknown fixture: {other}"),
     }
 }
 
+/// The thread in `thread-create` and `thread-list-sync`
+fn thread() -> Value {
+    serde_json::json!({
+        "id": "555555555555555555",
+        "name": "Mint day",
+        "type": "PublicThread",
+        "parent_id": "333333333333333333",
+        "owner_id": "987654321098765432",
+        "archived": false,
+        "locked": false,
+        "auto_archive_minutes": 1440,
+        "created_at": 1700000000000_u64,
+        "member_count": 3,
+        "message_count": 12
+    })
+}
+
 /// Write a fixture to disk (for regeneration mode).
 fn write_fixture(name: &str, value: &Value) {
     let path = fixtures_dir().join(format!("{name}.json"));
@@ -100,6 +467,23 @@ const DETERMINISTIC_FIXTURES: &[&str] = &[
     "guild-join",
     "guild-leave",
     "interaction-create",
+    "interaction-create-dm",
+    "interaction-component",
+    "interaction-modal",
+    "message-create",
+    "message-update",
+    "message-delete",
+    "message-bulk-delete",
+    "reaction-add",
+    "reaction-remove-all",
+    "voice-state-update",
+    "voice-server-update",
+    "role-create",
+    "role-delete",
+    "channel-create",
+    "thread-create",
+    "thread-list-sync",
+    "gateway-capability-degraded",
 ];
 
 /// All committed fixtures (some are hand-authored, not Rust-generated).
@@ -108,8 +492,31 @@ const ALL_FIXTURES: &[&str] = &[
     "guild-leave",
     "member-join",
     "member-leave",
+    "member-leave-kick",
     "member-update",
     "interaction-create",
+    "interaction-create-dm",
+    "interaction-component",
+    "interaction-modal",
+    "message-create",
+    "message-update",
+    "message-delete",
+    "message-bulk-delete",
+    "reaction-add",
+    "reaction-remove-all",
+    "voice-state-update",
+    "voice-server-update",
+    "role-create",
+    "role-delete",
+    "channel-create",
+    "thread-create",
+    "thread-list-sync",
+    "gateway-capability-degraded",
+    "event-summary",
+    "member-join-burst",
+    "guild-raid-alert",
+    "member-sync",
+    "gateway-circuit-state",
 ];
 
 /// Required envelope fields for every GatewayEvent.
@@ -47,7 +47,13 @@ function toDiscordEventPayload(payload: InteractionPayload): DiscordEventPayload
     interactionToken: payload.data.interaction_token,
     commandName: payload.data.command_name,
     subcommand: payload.data.subcommand,
-    data: payload.data.options,
+    locale: payload.data.locale ?? undefined,
+    guildLocale: payload.data.guild_locale ?? undefined,
+    // Handlers read Discord's shape: data.options (array) and data.resolved
+    data: {
+      options: payload.data.options ?? [],
+      resolved: payload.data.resolved ?? undefined,
+    },
   };
 }
 
@@ -309,5 +309,32 @@ export function createEventNatsConsumer(
   );
 }
 
+/**
+ * Create a consumer for the MESSAGES stream, dispatching to the same
+ * event-type handlers (e.g. message.create for agent threads)
+ * @param natsHandlers - NATS-native handlers
+ * @param logger - Pino logger instance
+ */
+export function createMessageNatsConsumer(
+  natsHandlers: Map<string, NatsEventHandler>,
+  logger: Logger
+): EventNatsConsumer {
+  const messagesStream = NATS_ROUTING.streams['MESSAGES'];
+  return new EventNatsConsumer(
+    {
+      streamName: messagesStream.name,
+      consumerName: 'message-worker',
+      filterSubjects: messagesStream.subjects,
+      maxAckPending: 100,
+      ackWait: 15_000,
+      maxDeliver: 5,
+      batchSize: 20,
+    },
+    natsHandlers,
+    new Map(),
+    logger
+  );
+}
+
 // Legacy export for backwards compatibility
 export { NatsEventHandler as EventHandler };
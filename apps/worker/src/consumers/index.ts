@@ -20,6 +20,7 @@ export { CommandNatsConsumer, createCommandNatsConsumer, type InteractionPayload
 export {
   EventNatsConsumer,
   createEventNatsConsumer,
+  createMessageNatsConsumer,
   createDefaultNatsEventHandlers,
   type GatewayEventPayload,
   type NatsEventHandler,
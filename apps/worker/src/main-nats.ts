@@ -17,6 +17,7 @@ import { createNatsClient, type NatsClient } from './services/NatsClient.js';
 import {
   createCommandNatsConsumer,
   createEventNatsConsumer,
+  createMessageNatsConsumer,
   createDefaultNatsEventHandlers,
   createEligibilityNatsConsumer,
   createUsageNatsConsumer,
@@ -56,6 +57,7 @@ const startTime = Date.now();
 let natsClient: NatsClient | null = null;
 let commandConsumer: CommandNatsConsumer | null = null;
 let eventConsumer: EventNatsConsumer | null = null;
+let messageConsumer: EventNatsConsumer | null = null;
 let eligibilityConsumer: EligibilityNatsConsumer | null = null;
 let usageConsumer: UsageNatsConsumer | null = null;
 let budgetRedis: Redis | null = null;
@@ -172,6 +174,11 @@ async function main(): Promise<void> {
   // Create NATS consumers
   commandConsumer = createCommandNatsConsumer(discordRest, commandHandlers, logger);
   eventConsumer = createEventNatsConsumer(natsEventHandlers, undefined, logger);
+  // Message events arrive on the MESSAGES stream; only consume it when
+  // something handles them (agent thread routing)
+  if (natsEventHandlers.has('message.create')) {
+    messageConsumer = createMessageNatsConsumer(natsEventHandlers, logger);
+  }
   eligibilityConsumer = createEligibilityNatsConsumer(discordRest, undefined, logger);
   usageConsumer = createUsageNatsConsumer({ budgetManager }, logger);
 
@@ -180,6 +187,7 @@ async function main(): Promise<void> {
   await Promise.all([
     commandConsumer.initialize(jsm),
     eventConsumer.initialize(jsm),
+    messageConsumer?.initialize(jsm),
     eligibilityConsumer.initialize(jsm),
     usageConsumer.initialize(jsm),
   ]);
@@ -190,6 +198,7 @@ async function main(): Promise<void> {
   await Promise.all([
     commandConsumer.start(js),
     eventConsumer.start(js),
+    messageConsumer?.start(js),
     eligibilityConsumer.start(js),
     usageConsumer.start(js),
   ]);
@@ -241,6 +250,9 @@ async function shutdown(signal: string): Promise<void> {
   if (eventConsumer) {
     stopPromises.push(eventConsumer.stop());
   }
+  if (messageConsumer) {
+    stopPromises.push(messageConsumer.stop());
+  }
   if (eligibilityConsumer) {
     stopPromises.push(eligibilityConsumer.stop());
   }
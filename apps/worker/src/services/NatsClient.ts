@@ -41,15 +41,16 @@ export interface StreamConfig {
  * Production uses 3 replicas for high availability (NATS cluster).
  * Staging/development use 1 replica (standalone NATS).
  */
-const getReplicaCount = (prodReplicas: number): number => {
-  const env = process.env.ENVIRONMENT || process.env.NODE_ENV || 'development';
-  return env === 'production' ? prodReplicas : 1;
-};
+const isProduction = (): boolean =>
+  (process.env.ENVIRONMENT || process.env.NODE_ENV || 'development') === 'production';
+
+const getReplicaCount = (prodReplicas: number): number => (isProduction() ? prodReplicas : 1);
 
 /**
  * Stream configurations per SDD §7.1.1
  * - COMMANDS: Slash commands, 60s retention, memory storage
  * - EVENTS: Guild/member events, 5min retention, memory storage
+ *   (production: 24h on file storage, so a worker outage doesn't lose them)
  * - ELIGIBILITY: Token checks, 7 days retention, file storage
  * - INTERNAL: Health/metrics, 1min retention, memory storage
  */
@@ -68,11 +69,33 @@ export const STREAM_CONFIGS: StreamConfig[] = [
     name: 'EVENTS',
     subjects: ['events.>'],
     retention: RetentionPolicy.Limits,
+    // Matches the production profile in nats-routing.json
+    storage: isProduction() ? StorageType.File : StorageType.Memory,
+    maxAge: (isProduction() ? 24 * 60 : 5) * 60 * 1_000_000_000, // 24 hours / 5 minutes
+    maxMsgs: isProduction() ? undefined : 500_000,
+    maxBytes: isProduction() ? 10 * 1024 * 1024 * 1024 : undefined, // 10GiB
+    replicas: getReplicaCount(3),
+    description: 'Guild, member, role, channel and thread lifecycle events',
+  },
+  {
+    name: 'MESSAGES',
+    subjects: ['messages.>'],
+    retention: RetentionPolicy.Limits,
     storage: StorageType.Memory,
     maxAge: 5 * 60 * 1_000_000_000, // 5 minutes
     maxMsgs: 500_000,
     replicas: getReplicaCount(3),
-    description: 'Guild and member lifecycle events',
+    description: 'Message events (gateway FORWARD_MESSAGES)',
+  },
+  {
+    name: 'VOICE',
+    subjects: ['voice.>'],
+    retention: RetentionPolicy.Limits,
+    storage: StorageType.Memory,
+    maxAge: 60 * 1_000_000_000, // 60 seconds
+    maxMsgs: 100_000,
+    replicas: getReplicaCount(3),
+    description: 'Voice state and server updates (gateway FORWARD_VOICE)',
   },
   {
     name: 'ELIGIBILITY',
@@ -142,6 +165,16 @@ export const CONSUMER_CONFIGS: ConsumerConfig[] = [
     maxDeliver: 5,
     description: 'Processes guild/member events',
   },
+  {
+    streamName: 'MESSAGES',
+    consumerName: 'message-worker',
+    filterSubjects: ['messages.>'],
+    ackPolicy: AckPolicy.Explicit,
+    maxAckPending: 100,
+    ackWait: 15_000, // 15 seconds
+    maxDeliver: 5,
+    description: 'Processes message events (agent threads)',
+  },
   {
     streamName: 'ELIGIBILITY',
     consumerName: 'eligibility-worker',
@@ -22,6 +22,10 @@ export interface DiscordEventPayload {
   commandName?: string;
   subcommand?: string;
 
+  // Discord locales: the invoking user's and the guild's preferred one
+  locale?: string;
+  guildLocale?: string;
+
   // Component interaction data (buttons/selects)
   customId?: string;
   componentType?: number;
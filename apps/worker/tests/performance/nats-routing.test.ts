@@ -250,7 +250,7 @@ describe('NATS Event Routing Performance', () => {
         { type: 'INTERACTION_CREATE', subject: 'commands.interaction' },
         { type: 'GUILD_CREATE', subject: 'events.guild.create' },
         { type: 'GUILD_MEMBER_ADD', subject: 'events.member.add' },
-        { type: 'MESSAGE_CREATE', subject: 'events.message.create' },
+        { type: 'MESSAGE_CREATE', subject: 'messages.create' },
       ];
 
       const routingTimes: number[] = [];
@@ -292,7 +292,7 @@ function determineSubject(eventType: string): string {
     case 'GUILD_MEMBER_REMOVE':
       return 'events.member.remove';
     case 'MESSAGE_CREATE':
-      return 'events.message.create';
+      return 'messages.create';
     default:
       return 'events.other';
   }
@@ -24,7 +24,40 @@ import {
   MemberLeaveDataSchema,
   MemberUpdateDataSchema,
   InteractionCreateDataSchema,
+  MessageCreateDataSchema,
+  MessageUpdateDataSchema,
+  MessageDeleteDataSchema,
+  MessageBulkDeleteDataSchema,
+  ReactionDataSchema,
+  ReactionRemoveAllDataSchema,
+  VoiceStateUpdateDataSchema,
+  VoiceServerUpdateDataSchema,
+  RoleDataSchema,
+  RoleDeleteDataSchema,
+  ChannelDataSchema,
+  ThreadDataSchema,
+  ThreadListSyncDataSchema,
+  GatewayCapabilityDegradedDataSchema,
+  EventSummaryDataSchema,
+  MemberJoinBurstDataSchema,
+  GuildRaidAlertDataSchema,
+  GatewayCircuitStateDataSchema,
 } from '../schemas/event-data.js';
+import { GatewayTopologySchema, GuildShardSchema } from '../schemas/topology.js';
+import { TickSchema, GuildScheduleEntrySchema } from '../schemas/ticks.js';
+import { CanaryResultSchema } from '../schemas/canary.js';
+import { RawEventSchema } from '../schemas/raw.js';
+import { EligibilityCheckSchema } from '../schemas/eligibility.js';
+import { RestRequestSchema, RestResponseSchema } from '../schemas/rest.js';
+import {
+  MemberChunkRequestSchema,
+  MemberChunkSchema,
+  BackfillRequestSchema,
+  BackfillProgressSchema,
+  MemberSyncDataSchema,
+} from '../schemas/members.js';
+import { PresenceUpdateSchema } from '../schemas/presence.js';
+import { CachedGuildSchema, GuildRequestSchema } from '../schemas/cache.js';
 
 const __dirname = dirname(fileURLToPath(import.meta.url));
 const FIXTURES_DIR = join(__dirname, '../../fixtures');
@@ -40,8 +73,31 @@ describe('Fixture conformance: GatewayEventSchema', () => {
     'guild-leave',
     'member-join',
     'member-leave',
+    'member-leave-kick',
     'member-update',
     'interaction-create',
+    'interaction-create-dm',
+    'interaction-component',
+    'interaction-modal',
+    'message-create',
+    'message-update',
+    'message-delete',
+    'message-bulk-delete',
+    'reaction-add',
+    'reaction-remove-all',
+    'voice-state-update',
+    'voice-server-update',
+    'role-create',
+    'role-delete',
+    'channel-create',
+    'thread-create',
+    'thread-list-sync',
+    'gateway-capability-degraded',
+    'event-summary',
+    'member-join-burst',
+    'guild-raid-alert',
+    'member-sync',
+    'gateway-circuit-state',
   ];
 
   for (const name of fixtures) {
@@ -70,6 +126,9 @@ describe('Fixture conformance: event-specific data schemas', () => {
     const fixture = loadFixture('member-join') as { data: unknown };
     const result = MemberJoinDataSchema.safeParse(fixture.data);
     expect(result.success).toBe(true);
+    if (result.success) {
+      expect(result.data.discriminator).toBe('0');
+    }
   });
 
   it('member-leave data validates against MemberLeaveDataSchema', () => {
@@ -78,6 +137,15 @@ describe('Fixture conformance: event-specific data schemas', () => {
     expect(result.success).toBe(true);
   });
 
+  it('member-leave-kick data carries a removal_reason', () => {
+    const fixture = loadFixture('member-leave-kick') as { data: unknown };
+    const result = MemberLeaveDataSchema.safeParse(fixture.data);
+    expect(result.success).toBe(true);
+    if (result.success) {
+      expect(result.data?.removal_reason).toBe('kick');
+    }
+  });
+
   it('member-update data validates against MemberUpdateDataSchema', () => {
     const fixture = loadFixture('member-update') as { data: unknown };
     const result = MemberUpdateDataSchema.safeParse(fixture.data);
@@ -89,6 +157,97 @@ describe('Fixture conformance: event-specific data schemas', () => {
     const result = InteractionCreateDataSchema.safeParse(fixture.data);
     expect(result.success).toBe(true);
   });
+
+  it('message-create data validates against MessageCreateDataSchema', () => {
+    const fixture = loadFixture('message-create') as { data: unknown };
+    const result = MessageCreateDataSchema.safeParse(fixture.data);
+    expect(result.success).toBe(true);
+  });
+
+  it('message-update data validates against MessageUpdateDataSchema', () => {
+    const fixture = loadFixture('message-update') as { data: unknown };
+    const result = MessageUpdateDataSchema.safeParse(fixture.data);
+    expect(result.success).toBe(true);
+  });
+
+  it('message delete data validates against the delete schemas', () => {
+    const single = loadFixture('message-delete') as { data: unknown };
+    expect(MessageDeleteDataSchema.safeParse(single.data).success).toBe(true);
+    const bulk = loadFixture('message-bulk-delete') as { data: unknown };
+    expect(MessageBulkDeleteDataSchema.safeParse(bulk.data).success).toBe(true);
+  });
+
+  it('reaction fixtures validate against the reaction schemas', () => {
+    const add = loadFixture('reaction-add') as { data: unknown };
+    const result = ReactionDataSchema.safeParse(add.data);
+    expect(result.success).toBe(true);
+    if (result.success) {
+      expect(result.data.emoji).toEqual({ id: '999999999999999999', name: 'honey', animated: false });
+    }
+    const removeAll = loadFixture('reaction-remove-all') as { data: unknown };
+    expect(ReactionRemoveAllDataSchema.safeParse(removeAll.data).success).toBe(true);
+  });
+
+  it('voice fixtures validate against the voice schemas', () => {
+    const state = loadFixture('voice-state-update') as { data: unknown };
+    expect(VoiceStateUpdateDataSchema.safeParse(state.data).success).toBe(true);
+    const server = loadFixture('voice-server-update') as { data: unknown };
+    expect(VoiceServerUpdateDataSchema.safeParse(server.data).success).toBe(true);
+  });
+
+  it('role fixtures validate against the role schemas', () => {
+    const created = loadFixture('role-create') as { data: unknown };
+    const result = RoleDataSchema.safeParse(created.data);
+    expect(result.success).toBe(true);
+    if (result.success) {
+      expect(result.data.permissions).toBe('104324673');
+    }
+    const deleted = loadFixture('role-delete') as { data: unknown };
+    expect(RoleDeleteDataSchema.safeParse(deleted.data).success).toBe(true);
+  });
+
+  it('channel and thread fixtures validate against their schemas', () => {
+    const channel = loadFixture('channel-create') as { data: unknown };
+    expect(ChannelDataSchema.safeParse(channel.data).success).toBe(true);
+    const thread = loadFixture('thread-create') as { data: unknown };
+    expect(ThreadDataSchema.safeParse(thread.data).success).toBe(true);
+    const sync = loadFixture('thread-list-sync') as { data: unknown };
+    const result = ThreadListSyncDataSchema.safeParse(sync.data);
+    expect(result.success).toBe(true);
+    if (result.success) {
+      expect(result.data.threads[0].parent_id).toBe(result.data.channel_ids[0]);
+    }
+  });
+
+  it('event-summary data validates against EventSummaryDataSchema', () => {
+    const fixture = loadFixture('event-summary') as { data: unknown };
+    const result = EventSummaryDataSchema.safeParse(fixture.data);
+    expect(result.success).toBe(true);
+  });
+
+  it('gateway-capability-degraded data validates against GatewayCapabilityDegradedDataSchema', () => {
+    const fixture = loadFixture('gateway-capability-degraded') as { data: unknown };
+    const result = GatewayCapabilityDegradedDataSchema.safeParse(fixture.data);
+    expect(result.success).toBe(true);
+  });
+
+  it('member-join-burst data validates against MemberJoinBurstDataSchema', () => {
+    const fixture = loadFixture('member-join-burst') as { data: unknown };
+    const result = MemberJoinBurstDataSchema.safeParse(fixture.data);
+    expect(result.success).toBe(true);
+  });
+
+  it('guild-raid-alert data validates against GuildRaidAlertDataSchema', () => {
+    const fixture = loadFixture('guild-raid-alert') as { data: unknown };
+    const result = GuildRaidAlertDataSchema.safeParse(fixture.data);
+    expect(result.success).toBe(true);
+  });
+
+  it('gateway-circuit-state data validates against GatewayCircuitStateDataSchema', () => {
+    const fixture = loadFixture('gateway-circuit-state') as { data: unknown };
+    const result = GatewayCircuitStateDataSchema.safeParse(fixture.data);
+    expect(result.success).toBe(true);
+  });
 });
 
 describe('Fixture conformance: InteractionPayloadSchema', () => {
@@ -97,6 +256,249 @@ describe('Fixture conformance: InteractionPayloadSchema', () => {
     const result = InteractionPayloadSchema.safeParse(data);
     expect(result.success).toBe(true);
   });
+
+  it('interaction-create-dm.json validates with null guild_id', () => {
+    const data = loadFixture('interaction-create-dm');
+    const result = InteractionPayloadSchema.safeParse(data);
+    expect(result.success).toBe(true);
+    if (result.success) {
+      expect(result.data.guild_id).toBeNull();
+      expect(result.data.data.context).toBe('bot_dm');
+      expect(result.data.data.authorizing_integration_owners?.['1']).toBe('987654321098765432');
+      expect(result.data.data.locale).toBe('pt-BR');
+      expect(result.data.data.guild_locale).toBeNull();
+    }
+  });
+
+  it('interaction-create.json carries typed options and resolved entities', () => {
+    const data = loadFixture('interaction-create');
+    const result = InteractionPayloadSchema.safeParse(data);
+    expect(result.success).toBe(true);
+    if (result.success) {
+      const [member, tier] = result.data.data.options ?? [];
+      expect(member).toEqual({ name: 'member', type: 'User', value: '222222222222222222' });
+      expect(tier?.value).toBe(3);
+      expect(result.data.data.resolved?.users['222222222222222222']?.username).toBe('holder');
+      expect(result.data.data.resolved?.members['222222222222222222']?.permissions).toBe('1024');
+      expect(result.data.data.resolved?.members['222222222222222222']?.joined_at).toBe(1704067200000);
+    }
+  });
+
+  it('interaction-component.json carries the custom_id and selected values', () => {
+    const data = loadFixture('interaction-component');
+    const result = InteractionPayloadSchema.safeParse(data);
+    expect(result.success).toBe(true);
+    if (result.success) {
+      expect(result.data.data.command_name).toBeUndefined();
+      expect(result.data.data.custom_id).toBe('role-picker');
+      expect(result.data.data.component_type).toBe('SelectMenu');
+      expect(result.data.data.values).toEqual(['holder', 'og']);
+    }
+  });
+
+  it('interaction-modal.json carries submitted fields', () => {
+    const data = loadFixture('interaction-modal');
+    const result = InteractionPayloadSchema.safeParse(data);
+    expect(result.success).toBe(true);
+    if (result.success) {
+      expect(result.data.data.custom_id).toBe('verify-modal');
+      expect(result.data.data.fields).toEqual([
+        { custom_id: 'wallet', value: '0x1234' },
+        { custom_id: 'note', value: 'gm' },
+        { custom_id: 'chain', values: ['berachain'] },
+      ]);
+    }
+  });
+
+  it('accepts nested subcommand options', () => {
+    const data = loadFixture('interaction-create') as { data: Record<string, unknown> };
+    const result = InteractionPayloadSchema.safeParse({
+      ...data,
+      data: {
+        ...data.data,
+        options: [
+          {
+            name: 'award',
+            type: 'SubCommand',
+            options: [{ name: 'nym', type: 'String', value: 'ali', focused: true }],
+          },
+        ],
+      },
+    });
+    expect(result.success).toBe(true);
+  });
+
+  it('normalizes a null command_name to undefined', () => {
+    const data = loadFixture('interaction-create') as { data: Record<string, unknown> };
+    const result = InteractionPayloadSchema.safeParse({
+      ...data,
+      data: { ...data.data, command_name: null },
+    });
+    expect(result.success).toBe(true);
+    if (result.success) {
+      expect(result.data.data.command_name).toBeUndefined();
+    }
+  });
+});
+
+describe('Fixture conformance: GatewayTopologySchema', () => {
+  it('gateway-topology.json validates against GatewayTopologySchema', () => {
+    const result = GatewayTopologySchema.safeParse(loadFixture('gateway-topology'));
+    expect(result.success).toBe(true);
+    if (result.success) {
+      const guilds = result.data.shards.reduce((sum, s) => sum + s.guilds, 0);
+      expect(result.data.guilds_total).toBe(guilds);
+    }
+  });
+
+  it('defaults shards_per_pool for documents from older pools', () => {
+    const data = { ...(loadFixture('gateway-topology') as Record<string, unknown>) };
+    delete data.shards_per_pool;
+    const result = GatewayTopologySchema.safeParse(data);
+    expect(result.success).toBe(true);
+    if (result.success) {
+      expect(result.data.shards_per_pool).toBe(25);
+    }
+  });
+
+  it('rejects an unknown shard health', () => {
+    const data = loadFixture('gateway-topology') as { shards: Record<string, unknown>[] };
+    const result = GatewayTopologySchema.safeParse({
+      ...data,
+      shards: [{ ...data.shards[0], health: 'zombie' }],
+    });
+    expect(result.success).toBe(false);
+  });
+});
+
+describe('Fixture conformance: GuildShardSchema', () => {
+  it('guild-shard.json validates against GuildShardSchema', () => {
+    const result = GuildShardSchema.safeParse(loadFixture('guild-shard'));
+    expect(result.success).toBe(true);
+    if (result.success) {
+      expect(result.data.pool_id).toBe(Math.floor(result.data.shard_id / result.data.shards_per_pool));
+    }
+  });
+
+  it('accepts a pool that does not run the shard', () => {
+    const data = loadFixture('guild-shard') as Record<string, unknown>;
+    const result = GuildShardSchema.safeParse({ ...data, running: false, health: null });
+    expect(result.success).toBe(true);
+  });
+});
+
+describe('Fixture conformance: TickSchema', () => {
+  it('tick.json validates against TickSchema', () => {
+    const result = TickSchema.safeParse(loadFixture('tick'));
+    expect(result.success).toBe(true);
+  });
+
+  it('rejects schedule names that are not a single subject token', () => {
+    expect(GuildScheduleEntrySchema.safeParse({ name: 'daily', cron: '0 9 * * *' }).success).toBe(true);
+    expect(GuildScheduleEntrySchema.safeParse({ name: 'a.b', cron: '0 9 * * *' }).success).toBe(false);
+  });
+});
+
+describe('Fixture conformance: CanaryResultSchema', () => {
+  it('canary-result.json validates against CanaryResultSchema', () => {
+    const result = CanaryResultSchema.safeParse(loadFixture('canary-result'));
+    expect(result.success).toBe(true);
+  });
+
+  it('rejects unknown outcomes', () => {
+    const fixture = loadFixture('canary-result') as Record<string, unknown>;
+    expect(CanaryResultSchema.safeParse({ ...fixture, outcome: 'maybe' }).success).toBe(false);
+  });
+});
+
+describe('Fixture conformance: RawEventSchema', () => {
+  it('raw-event.json validates against RawEventSchema', () => {
+    const result = RawEventSchema.safeParse(loadFixture('raw-event'));
+    expect(result.success).toBe(true);
+  });
+
+  it('pairs with the member-update envelope it became', () => {
+    const raw = loadFixture('raw-event') as { event_id: string };
+    const envelope = loadFixture('member-update') as { event_id: string };
+    expect(raw.event_id).toBe(envelope.event_id);
+  });
+});
+
+describe('Fixture conformance: EligibilityCheckSchema', () => {
+  it('eligibility-check.json validates against EligibilityCheckSchema', () => {
+    const result = EligibilityCheckSchema.safeParse(loadFixture('eligibility-check'));
+    expect(result.success).toBe(true);
+  });
+
+  it('rejects unknown check types', () => {
+    const fixture = loadFixture('eligibility-check') as Record<string, unknown>;
+    expect(EligibilityCheckSchema.safeParse({ ...fixture, check_type: 'all' }).success).toBe(false);
+  });
+});
+
+describe('Fixture conformance: REST proxy', () => {
+  it('rest-request.json validates against RestRequestSchema', () => {
+    const result = RestRequestSchema.safeParse(loadFixture('rest-request'));
+    expect(result.success).toBe(true);
+  });
+
+  it('rest-response.json validates against RestResponseSchema', () => {
+    const result = RestResponseSchema.safeParse(loadFixture('rest-response'));
+    expect(result.success).toBe(true);
+  });
+
+  it('rejects methods the proxy does not execute', () => {
+    const fixture = loadFixture('rest-request') as Record<string, unknown>;
+    expect(RestRequestSchema.safeParse({ ...fixture, method: 'CONNECT' }).success).toBe(false);
+  });
+});
+
+describe('Fixture conformance: member lists', () => {
+  it('member-chunk-request.json validates against MemberChunkRequestSchema', () => {
+    const result = MemberChunkRequestSchema.safeParse(loadFixture('member-chunk-request'));
+    expect(result.success).toBe(true);
+  });
+
+  it('member-chunk.json validates against MemberChunkSchema', () => {
+    const result = MemberChunkSchema.safeParse(loadFixture('member-chunk'));
+    expect(result.success).toBe(true);
+  });
+
+  it('backfill fixtures validate against the backfill schemas', () => {
+    expect(BackfillRequestSchema.safeParse(loadFixture('backfill-request')).success).toBe(true);
+    expect(BackfillProgressSchema.safeParse(loadFixture('backfill-progress')).success).toBe(true);
+    const page = loadFixture('member-sync') as { data: unknown };
+    expect(MemberSyncDataSchema.safeParse(page.data).success).toBe(true);
+  });
+
+  it('rejects more than 100 user_ids', () => {
+    const user_ids = Array.from({ length: 101 }, (_, i) => String(i + 1));
+    const request = { guild_id: '123456789012345678', user_ids };
+    expect(MemberChunkRequestSchema.safeParse(request).success).toBe(false);
+  });
+});
+
+describe('Fixture conformance: guild cache', () => {
+  it('guild-request.json validates against GuildRequestSchema', () => {
+    const result = GuildRequestSchema.safeParse(loadFixture('guild-request'));
+    expect(result.success).toBe(true);
+  });
+
+  it('cached-guild.json validates against CachedGuildSchema', () => {
+    const result = CachedGuildSchema.safeParse(loadFixture('cached-guild'));
+    expect(result.success).toBe(true);
+  });
+});
+
+describe('Fixture conformance: presence', () => {
+  it('presence-update.json validates against PresenceUpdateSchema', () => {
+    const result = PresenceUpdateSchema.safeParse(loadFixture('presence-update'));
+    expect(result.success).toBe(true);
+  });
+
+  it('rejects the offline status', () => {
+    expect(PresenceUpdateSchema.safeParse({ status: 'offline' }).success).toBe(false);
+  });
 });
 
 describe('BB60-20 regression guard', () => {
@@ -27,6 +27,12 @@ import {
   MemberLeaveDataSchema,
   MemberUpdateDataSchema,
   InteractionCreateDataSchema,
+  MessageCreateDataSchema,
+  GatewayCapabilityDegradedDataSchema,
+  EventSummaryDataSchema,
+  MemberJoinBurstDataSchema,
+  GuildRaidAlertDataSchema,
+  GatewayCircuitStateDataSchema,
   KNOWN_EVENT_TYPES,
   isKnownEventType,
 } from '../index.js';
@@ -44,8 +50,31 @@ const ALL_FIXTURES = [
   'guild-leave',
   'member-join',
   'member-leave',
+  'member-leave-kick',
   'member-update',
   'interaction-create',
+  'interaction-create-dm',
+  'interaction-component',
+  'interaction-modal',
+  'message-create',
+  'message-update',
+  'message-delete',
+  'message-bulk-delete',
+  'reaction-add',
+  'reaction-remove-all',
+  'voice-state-update',
+  'voice-server-update',
+  'role-create',
+  'role-delete',
+  'channel-create',
+  'thread-create',
+  'thread-list-sync',
+  'gateway-capability-degraded',
+  'event-summary',
+  'member-join-burst',
+  'guild-raid-alert',
+  'member-sync',
+  'gateway-circuit-state',
 ];
 
 describe('Wire format round-trip (TypeScript side)', () => {
@@ -95,6 +124,42 @@ describe('Wire format round-trip (TypeScript side)', () => {
       const result = InteractionCreateDataSchema.safeParse(fixture.data);
       expect(result.success).toBe(true);
     });
+
+    it('message-create data validates against MessageCreateDataSchema', () => {
+      const fixture = loadFixture('message-create') as { data: unknown };
+      const result = MessageCreateDataSchema.safeParse(fixture.data);
+      expect(result.success).toBe(true);
+    });
+
+    it('event-summary data validates against EventSummaryDataSchema', () => {
+      const fixture = loadFixture('event-summary') as { data: unknown };
+      const result = EventSummaryDataSchema.safeParse(fixture.data);
+      expect(result.success).toBe(true);
+    });
+
+    it('gateway-capability-degraded data validates against GatewayCapabilityDegradedDataSchema', () => {
+      const fixture = loadFixture('gateway-capability-degraded') as { data: unknown };
+      const result = GatewayCapabilityDegradedDataSchema.safeParse(fixture.data);
+      expect(result.success).toBe(true);
+    });
+
+    it('member-join-burst data validates against MemberJoinBurstDataSchema', () => {
+      const fixture = loadFixture('member-join-burst') as { data: unknown };
+      const result = MemberJoinBurstDataSchema.safeParse(fixture.data);
+      expect(result.success).toBe(true);
+    });
+
+    it('guild-raid-alert data validates against GuildRaidAlertDataSchema', () => {
+      const fixture = loadFixture('guild-raid-alert') as { data: unknown };
+      const result = GuildRaidAlertDataSchema.safeParse(fixture.data);
+      expect(result.success).toBe(true);
+    });
+
+    it('gateway-circuit-state data validates against GatewayCircuitStateDataSchema', () => {
+      const fixture = loadFixture('gateway-circuit-state') as { data: unknown };
+      const result = GatewayCircuitStateDataSchema.safeParse(fixture.data);
+      expect(result.success).toBe(true);
+    });
   });
 
   describe('Interaction payload schemas', () => {
@@ -109,6 +174,12 @@ describe('Wire format round-trip (TypeScript side)', () => {
       const result = InteractionTransportPayloadSchema.safeParse(fixture);
       expect(result.success).toBe(true);
     });
+
+    it('interaction-create-dm validates against InteractionTransportPayloadSchema (strict)', () => {
+      const fixture = loadFixture('interaction-create-dm');
+      const result = InteractionTransportPayloadSchema.safeParse(fixture);
+      expect(result.success).toBe(true);
+    });
   });
 
   describe('KNOWN_EVENT_TYPES coverage', () => {
@@ -124,7 +195,7 @@ describe('Wire format round-trip (TypeScript side)', () => {
     });
 
     it('KNOWN_EVENT_TYPES has expected length', () => {
-      expect(KNOWN_EVENT_TYPES.length).toBe(7);
+      expect(KNOWN_EVENT_TYPES.length).toBe(32);
     });
   });
 
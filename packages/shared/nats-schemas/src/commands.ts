@@ -0,0 +1,20 @@
+/**
+ * Application command definitions loaded from the language-neutral
+ * commands.json.
+ *
+ * The gateway registers these with Discord at startup (`COMMAND_SYNC`), so
+ * workers and every environment agree on which commands exist. Each entry is
+ * Discord's create-command JSON body.
+ */
+
+import commandsData from '../commands.json' with { type: 'json' };
+
+/** Commands by scope */
+export interface ApplicationCommandSet {
+  /** Registered for every guild (and DMs, per each command's contexts) */
+  global: Record<string, unknown>[];
+  /** Registered in one guild, by guild ID */
+  guilds: Record<string, Record<string, unknown>[]>;
+}
+
+export const APPLICATION_COMMANDS: ApplicationCommandSet = commandsData as ApplicationCommandSet;
@@ -27,17 +27,127 @@ export {
   GuildLeaveDataSchema,
   MemberJoinDataSchema,
   MemberLeaveDataSchema,
+  MemberRemovalReasonSchema,
   MemberUpdateDataSchema,
   InteractionCreateDataSchema,
+  InteractionContextSchema,
+  InteractionOptionSchema,
+  InteractionResolvedSchema,
+  ModalFieldSchema,
+  UserEntitySchema,
+  AttachmentEntitySchema,
+  MessageCreateDataSchema,
+  MessageUpdateDataSchema,
+  MessageDeleteDataSchema,
+  MessageBulkDeleteDataSchema,
+  ReactionEmojiSchema,
+  ReactionDataSchema,
+  ReactionRemoveAllDataSchema,
+  VoiceStateUpdateDataSchema,
+  VoiceServerUpdateDataSchema,
+  RoleDataSchema,
+  RoleDeleteDataSchema,
+  ChannelDataSchema,
+  ThreadDataSchema,
+  ThreadDeleteDataSchema,
+  ThreadListSyncDataSchema,
+  GatewayCapabilityDegradedDataSchema,
+  EventSummaryDataSchema,
+  MemberJoinBurstDataSchema,
+  GuildRaidAlertDataSchema,
+  GatewayCircuitStateDataSchema,
   type GuildJoinData,
   type GuildLeaveData,
   type MemberJoinData,
   type MemberLeaveData,
+  type MemberRemovalReason,
   type MemberUpdateData,
   type InteractionCreateData,
+  type InteractionContext,
+  type InteractionOption,
+  type InteractionResolved,
+  type ModalField,
+  type MessageCreateData,
+  type MessageUpdateData,
+  type MessageDeleteData,
+  type MessageBulkDeleteData,
+  type ReactionEmoji,
+  type ReactionData,
+  type ReactionRemoveAllData,
+  type VoiceStateUpdateData,
+  type VoiceServerUpdateData,
+  type RoleData,
+  type RoleDeleteData,
+  type ChannelData,
+  type ThreadData,
+  type ThreadDeleteData,
+  type ThreadListSyncData,
+  type GatewayCapabilityDegradedData,
+  type EventSummaryData,
+  type MemberJoinBurstData,
+  type GuildRaidAlertData,
+  type GatewayCircuitStateData,
 } from './schemas/event-data.js';
 export {
   UsageFinalizedSchema,
   type UsageFinalizedEvent,
 } from './schemas/usage-finalized.js';
-export { NATS_ROUTING, type NatsRouting } from './routing.js';
+export {
+  GatewayTopologySchema,
+  ShardTopologySchema,
+  ShardHealthSchema,
+  GuildShardSchema,
+  type GatewayTopology,
+  type ShardTopology,
+  type ShardHealth,
+  type GuildShard,
+} from './schemas/topology.js';
+export {
+  TickSchema,
+  GuildScheduleEntrySchema,
+  type Tick,
+  type GuildScheduleEntry,
+} from './schemas/ticks.js';
+export { CanaryResultSchema, type CanaryResult } from './schemas/canary.js';
+export { RawEventSchema, type RawEvent } from './schemas/raw.js';
+export { EligibilityCheckSchema, type EligibilityCheck } from './schemas/eligibility.js';
+export {
+  RestRequestSchema,
+  RestResponseSchema,
+  type RestRequest,
+  type RestResponse,
+} from './schemas/rest.js';
+export {
+  MemberChunkRequestSchema,
+  ChunkMemberSchema,
+  MemberChunkSchema,
+  MemberChunkErrorSchema,
+  BackfillRequestSchema,
+  BackfillStartedSchema,
+  MemberSyncDataSchema,
+  BackfillProgressSchema,
+  type MemberChunkRequest,
+  type ChunkMember,
+  type MemberChunk,
+  type MemberChunkError,
+  type BackfillRequest,
+  type BackfillStarted,
+  type MemberSyncData,
+  type BackfillProgress,
+} from './schemas/members.js';
+export {
+  GuildRequestSchema,
+  CachedGuildSchema,
+  CachedGuildErrorSchema,
+  type GuildRequest,
+  type CachedGuild,
+  type CachedGuildError,
+} from './schemas/cache.js';
+export {
+  PresenceActivitySchema,
+  PresenceUpdateSchema,
+  type PresenceActivity,
+  type PresenceUpdate,
+} from './schemas/presence.js';
+export { NATS_ROUTING, type NatsRouting, type ConsumerRef, type ServiceConfig } from './routing.js';
+export { APPLICATION_COMMANDS, type ApplicationCommandSet } from './commands.js';
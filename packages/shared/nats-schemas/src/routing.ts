@@ -12,19 +12,57 @@ export interface StreamConfig {
   name: string;
   subjects: string[];
   description: string;
+  /**
+   * JetStream settings, on the streams the gateway creates with
+   * `ENSURE_STREAMS`. Absent on streams other services own.
+   */
+  retention?: 'limits' | 'interest' | 'workqueue';
+  storage?: 'memory' | 'file';
+  /** 0 keeps messages until another limit is reached */
+  max_age_secs?: number;
+  max_msgs?: number;
+  max_bytes?: number;
+  replicas?: number;
+  /** Changes to these settings per deployment profile, e.g. `production` */
+  profiles?: Record<string, StreamSettings>;
 }
 
+/** Stream settings a profile changes */
+export type StreamSettings = Partial<
+  Pick<StreamConfig, 'storage' | 'max_age_secs' | 'max_msgs' | 'max_bytes' | 'replicas'>
+>;
+
 /** Subject namespace */
 export interface SubjectNamespace {
   prefix: string;
   [key: string]: string;
 }
 
+/** Worker durable consumer (the gateway exports lag for each) */
+export interface ConsumerRef {
+  stream: string;
+  durable: string;
+}
+
+/**
+ * Gateway request-reply service (NATS micro API). Each pool answers on
+ * `{prefix}.{pool_id}.{endpoint}`.
+ */
+export interface ServiceConfig {
+  name: string;
+  prefix: string;
+  endpoints: string[];
+}
+
 /** Full routing configuration type */
 export interface NatsRouting {
   streams: Record<string, StreamConfig>;
   subjects: Record<string, SubjectNamespace>;
   event_type_to_subject: Record<string, string>;
+  /** JetStream KV buckets workers read (e.g. per-pool topology) */
+  kv_buckets: Record<string, string>;
+  service: ServiceConfig;
+  consumers: ConsumerRef[];
 }
 
 /**
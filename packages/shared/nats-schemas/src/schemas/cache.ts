@@ -0,0 +1,50 @@
+/**
+ * Guild Cache Schemas
+ *
+ * Workers read a guild's metadata, channels and roles from the gateway's
+ * guild cache with a request on `gateway.requests.guild` (or
+ * `GET /cache/guilds/{id}` on the pool running the guild). The pool running
+ * the guild's shard answers; a guild it hasn't cached is answered with
+ * `{ error }`.
+ */
+
+import { z } from 'zod';
+import { ChannelDataSchema, RoleDataSchema } from './event-data.js';
+
+// --------------------------------------------------------------------------
+// Schemas
+// --------------------------------------------------------------------------
+
+/** A request sent on `gateway.requests.guild` */
+export const GuildRequestSchema = z.object({
+  guild_id: z.string(),
+});
+
+/** A cached guild */
+export const CachedGuildSchema = z.object({
+  guild_id: z.string(),
+  name: z.string(),
+  icon: z.string().nullable(),
+  owner_id: z.string(),
+  member_count: z.number().int().nonnegative().nullable(),
+  preferred_locale: z.string(),
+  premium_tier: z.number().int().nonnegative(),
+  features: z.array(z.string()),
+  /** In position order, without threads */
+  channels: z.array(ChannelDataSchema),
+  /** Keyed by role ID */
+  roles: z.record(z.string(), RoleDataSchema),
+});
+
+/** The reply for a guild the gateway hasn't cached */
+export const CachedGuildErrorSchema = z.object({
+  error: z.string(),
+});
+
+// --------------------------------------------------------------------------
+// Types
+// --------------------------------------------------------------------------
+
+export type GuildRequest = z.infer<typeof GuildRequestSchema>;
+export type CachedGuild = z.infer<typeof CachedGuildSchema>;
+export type CachedGuildError = z.infer<typeof CachedGuildErrorSchema>;
@@ -0,0 +1,36 @@
+/**
+ * Canary Schemas
+ *
+ * With the gateway's canary dual-write on, a sample of published events is
+ * published again in a candidate wire format on `canary.{format}.{subject}`
+ * (CANARY stream; the `Arrakis-Wire-Format` header names the format).
+ * Consumers validating the candidate report every copy they handled on
+ * `canary.results`, and the gateway exports the success ratio per consumer.
+ * `results` is never a format name.
+ */
+
+import { z } from 'zod';
+
+// --------------------------------------------------------------------------
+// Schema
+// --------------------------------------------------------------------------
+
+/** A consumer's report on one canary copy */
+export const CanaryResultSchema = z.object({
+  /** event_id of the copy */
+  event_id: z.string(),
+  /** Wire format of the copy (the `{format}` subject token) */
+  format: z.string(),
+  /** Durable consumer name (reports from unknown consumers count as "other") */
+  consumer: z.string(),
+  /** "ok" when parsed and handled, "rejected" when parsing or validation failed */
+  outcome: z.enum(['ok', 'rejected']),
+  /** Why the copy was rejected */
+  error: z.string().nullable(),
+});
+
+// --------------------------------------------------------------------------
+// Types
+// --------------------------------------------------------------------------
+
+export type CanaryResult = z.infer<typeof CanaryResultSchema>;
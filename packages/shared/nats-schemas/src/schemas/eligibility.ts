@@ -0,0 +1,40 @@
+/**
+ * Eligibility Check Schemas
+ *
+ * With `ELIGIBILITY_COMMANDS` or `ELIGIBILITY_CLAIM_PREFIX` set, the gateway
+ * turns wallet-link commands and role-claim components into eligibility
+ * check requests on `eligibility.check.{guild_id}` (ELIGIBILITY stream),
+ * consumed by `eligibility-worker`. The interaction itself is still
+ * published on `commands.>` for the reply. `event_id` is derived from the
+ * interaction's, so a replayed interaction does not request a second check.
+ */
+
+import { z } from 'zod';
+
+// --------------------------------------------------------------------------
+// Schema
+// --------------------------------------------------------------------------
+
+/** An eligibility check request */
+export const EligibilityCheckSchema = z.object({
+  event_id: z.string(),
+  event_type: z.enum(['eligibility.check', 'eligibility.sync']),
+  timestamp: z.number().int().nonnegative(),
+  /** The guild ID unless the community is linked to another */
+  community_id: z.string(),
+  guild_id: z.string(),
+  user_id: z.string().nullable(),
+  /** Given to a wallet-link command; null uses the member's linked wallet */
+  wallet_address: z.string().nullable(),
+  check_type: z.enum(['single', 'batch', 'community_sync']),
+  /** Rules to check; all of the community's when absent */
+  rule_ids: z.array(z.string()).optional(),
+  /** `source` (`command` or `component`) and the interaction's IDs */
+  data: z.record(z.unknown()),
+});
+
+// --------------------------------------------------------------------------
+// Types
+// --------------------------------------------------------------------------
+
+export type EligibilityCheck = z.infer<typeof EligibilityCheckSchema>;
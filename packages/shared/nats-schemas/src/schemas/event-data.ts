@@ -37,7 +37,7 @@ export type GuildJoinData = z.infer<typeof GuildJoinDataSchema>;
  * data payload for event_type = "guild.leave"
  */
 export const GuildLeaveDataSchema = z.object({
-  unavailable: z.boolean().optional(),
+  unavailable: z.boolean().nullable().optional(),
 });
 
 export type GuildLeaveData = z.infer<typeof GuildLeaveDataSchema>;
@@ -48,19 +48,37 @@ export type GuildLeaveData = z.infer<typeof GuildLeaveDataSchema>;
 
 /**
  * data payload for event_type = "member.join"
+ * `discriminator` is a string as on Discord's wire ("0" for migrated users,
+ * "0042" otherwise). Gateways before the number policy sent a number, which
+ * is still accepted during a rolling deploy.
+ * `is_bot` is the user's bot flag (absent from older gateways).
  */
 export const MemberJoinDataSchema = z.object({
   username: z.string(),
-  discriminator: z.number().int().nullable(),
+  discriminator: z.union([z.string(), z.number().int()]).nullable(),
+  is_bot: z.boolean().optional(),
 });
 
 export type MemberJoinData = z.infer<typeof MemberJoinDataSchema>;
 
 /**
  * data payload for event_type = "member.leave"
- * Rust sends Value::Null — an empty object or null.
+ * `{ is_bot }`, plus `removal_reason` when the gateway runs with
+ * MEMBER_REMOVAL_AUDIT, which classifies the removal from the guild audit
+ * log. "unknown" means the audit log could not be read (e.g. missing View
+ * Audit Log permission). Gateways before `is_bot` sent null without the audit.
  */
-export const MemberLeaveDataSchema = z.union([z.null(), z.object({})]);
+export const MemberRemovalReasonSchema = z.enum(['leave', 'kick', 'ban', 'unknown']);
+
+export type MemberRemovalReason = z.infer<typeof MemberRemovalReasonSchema>;
+
+export const MemberLeaveDataSchema = z.union([
+  z.null(),
+  z.object({
+    is_bot: z.boolean().optional(),
+    removal_reason: MemberRemovalReasonSchema.optional(),
+  }),
+]);
 
 export type MemberLeaveData = z.infer<typeof MemberLeaveDataSchema>;
 
@@ -70,6 +88,7 @@ export type MemberLeaveData = z.infer<typeof MemberLeaveDataSchema>;
 export const MemberUpdateDataSchema = z.object({
   roles: z.array(z.string()),
   nick: z.string().nullable(),
+  is_bot: z.boolean().optional(),
 });
 
 export type MemberUpdateData = z.infer<typeof MemberUpdateDataSchema>;
@@ -78,16 +97,495 @@ export type MemberUpdateData = z.infer<typeof MemberUpdateDataSchema>;
 // Interaction events
 // ---------------------------------------------------------------------------
 
+/**
+ * Where an interaction was triggered (Discord interaction context type).
+ * null when Discord omits it (older app configurations).
+ */
+export const InteractionContextSchema = z.enum(['guild', 'bot_dm', 'private_channel', 'unknown']);
+
+export type InteractionContext = z.infer<typeof InteractionContextSchema>;
+
+/**
+ * A command option as sent by the gateway. `type` is Discord's option type
+ * name ("String", "Integer", "User", "SubCommand", ...). Entity options
+ * (User, Channel, Role, Mentionable, Attachment) carry the ID as `value`;
+ * look it up in `resolved`. Subcommands and groups nest `options` instead.
+ * `focused` marks the option being typed during autocomplete.
+ */
+export interface InteractionOption {
+  name: string;
+  type: string;
+  value?: string | number | boolean;
+  focused?: boolean;
+  options?: InteractionOption[];
+}
+
+export const InteractionOptionSchema: z.ZodType<InteractionOption> = z.lazy(() =>
+  z.object({
+    name: z.string(),
+    type: z.string(),
+    value: z.union([z.string(), z.number(), z.boolean()]).optional(),
+    focused: z.boolean().optional(),
+    options: z.array(InteractionOptionSchema).optional(),
+  })
+);
+
+/** A Discord user as normalized by the gateway. */
+export const UserEntitySchema = z.object({
+  id: z.string(),
+  username: z.string(),
+  global_name: z.string().nullable(),
+  avatar: z.string().nullable(),
+  bot: z.boolean(),
+});
+
+/** Attachment metadata; `url` is the Discord CDN URL. */
+export const AttachmentEntitySchema = z.object({
+  id: z.string(),
+  filename: z.string(),
+  content_type: z.string().nullable(),
+  size: z.number().int(),
+  url: z.string(),
+});
+
+/** Entities referenced by command options, keyed by ID (members by user ID). */
+export const InteractionResolvedSchema = z.object({
+  users: z.record(UserEntitySchema),
+  members: z.record(
+    z.object({
+      nick: z.string().nullable(),
+      roles: z.array(z.string()),
+      /** Unix milliseconds */
+      joined_at: z.number().int().nonnegative().nullable(),
+      avatar: z.string().nullable(),
+      permissions: z.string(),
+    })
+  ),
+  roles: z.record(
+    z.object({
+      id: z.string(),
+      name: z.string(),
+      color: z.number().int(),
+      position: z.number().int(),
+      permissions: z.string(),
+      managed: z.boolean(),
+      mentionable: z.boolean(),
+    })
+  ),
+  channels: z.record(
+    z.object({
+      id: z.string(),
+      name: z.string(),
+      type: z.string(),
+      parent_id: z.string().nullable(),
+      permissions: z.string(),
+    })
+  ),
+  attachments: z.record(AttachmentEntitySchema),
+});
+
+export type InteractionResolved = z.infer<typeof InteractionResolvedSchema>;
+
+/**
+ * A submitted modal field: `value` for text inputs, `values` for select menus
+ * and file uploads (attachment IDs)
+ */
+export const ModalFieldSchema = z.object({
+  custom_id: z.string(),
+  value: z.string().optional(),
+  values: z.array(z.string()).optional(),
+});
+
+export type ModalField = z.infer<typeof ModalFieldSchema>;
+
 /**
  * data payload for event_type = "interaction.create"
  *
- * Maps directly to the serde_json::json! block in serialize.rs lines 133-137.
+ * Maps directly to InteractionEvent in serialize.rs.
  * Note: field is "interaction_token" (NOT "token") per BB60-20 fix.
+ *
+ * guild_id is null for DMs and user-installed apps; those interactions are
+ * routed to commands.dm.{command_name}. `authorizing_integration_owners` is
+ * Discord's installation map: "0" = guild install (guild ID), "1" = user
+ * install (user ID). `options` is null for non-command interactions;
+ * `resolved` is also set for user, role and channel select menus.
+ * `subcommand` is the invoked subcommand ("group award" inside a group).
+ * Components carry `custom_id`, `component_type` (e.g. "Button") and, for
+ * select menus, `values`; modal submits carry `custom_id` and `fields`. `locale` is the invoking user's Discord locale (e.g. "pt-BR");
+ * `guild_locale` is the guild's preferred locale, null outside guilds.
+ * `application_id` is the application the interaction was sent to; `is_bot`
+ * the invoking user's bot flag. `deferred` is true when the gateway already
+ * sent a deferred response, so the handler must edit the original response
+ * instead of creating one. Everything after interaction_token is optional so
+ * payloads from gateways that predate them still validate during a rolling
+ * deploy.
  */
 export const InteractionCreateDataSchema = z.object({
   interaction_id: z.string(),
   interaction_type: z.string(),
   interaction_token: z.string(),
+  application_id: z.string().optional(),
+  is_bot: z.boolean().nullable().optional(),
+  command_name: z.string().nullable().optional(),
+  subcommand: z.string().nullable().optional(),
+  custom_id: z.string().nullable().optional(),
+  component_type: z.string().nullable().optional(),
+  values: z.array(z.string()).nullable().optional(),
+  fields: z.array(ModalFieldSchema).nullable().optional(),
+  context: InteractionContextSchema.nullable().optional(),
+  authorizing_integration_owners: z
+    .object({
+      '0': z.string().optional(),
+      '1': z.string().optional(),
+    })
+    .nullable()
+    .optional(),
+  options: z.array(InteractionOptionSchema).nullable().optional(),
+  resolved: InteractionResolvedSchema.nullable().optional(),
+  locale: z.string().nullable().optional(),
+  guild_locale: z.string().nullable().optional(),
+  deferred: z.boolean().optional(),
 });
 
 export type InteractionCreateData = z.infer<typeof InteractionCreateDataSchema>;
+
+/**
+ * data payload for event_type = "message.create"
+ *
+ * Only published by gateways with FORWARD_MESSAGES enabled, like the other
+ * message events (MESSAGES stream). Carries the text, author and attachment
+ * metadata; embeds, components and the rest of Discord's message object are
+ * not forwarded.
+ */
+export const MessageCreateDataSchema = z.object({
+  message_id: z.string(),
+  content: z.string(),
+  author: UserEntitySchema,
+  attachments: z.array(AttachmentEntitySchema),
+});
+
+export type MessageCreateData = z.infer<typeof MessageCreateDataSchema>;
+
+/**
+ * data payload for event_type = "message.update"
+ *
+ * The edited message in full, with `edited_at` (Unix ms). Discord also sends
+ * updates for embed unfurls, where `edited_at` is null.
+ */
+export const MessageUpdateDataSchema = MessageCreateDataSchema.extend({
+  edited_at: z.number().int().nullable(),
+});
+
+export type MessageUpdateData = z.infer<typeof MessageUpdateDataSchema>;
+
+/**
+ * data payload for event_type = "message.delete"
+ *
+ * Only the ID: Discord doesn't send the deleted content or who deleted it.
+ * The envelope's user_id is null.
+ */
+export const MessageDeleteDataSchema = z.object({
+  message_id: z.string(),
+});
+
+export type MessageDeleteData = z.infer<typeof MessageDeleteDataSchema>;
+
+/** data payload for event_type = "message.bulk_delete" (moderator purges) */
+export const MessageBulkDeleteDataSchema = z.object({
+  message_ids: z.array(z.string()),
+});
+
+export type MessageBulkDeleteData = z.infer<typeof MessageBulkDeleteDataSchema>;
+
+/**
+ * A reaction's emoji. `id` is null for Unicode emoji (`name` is the emoji
+ * itself); `name` is null for custom emoji that no longer exist.
+ */
+export const ReactionEmojiSchema = z.object({
+  id: z.string().nullable(),
+  name: z.string().nullable(),
+  animated: z.boolean(),
+});
+
+export type ReactionEmoji = z.infer<typeof ReactionEmojiSchema>;
+
+/**
+ * data payload for event_type = "reaction.add" and "reaction.remove"
+ *
+ * Only published by gateways with FORWARD_REACTIONS enabled. The reacting
+ * user is the envelope's user_id. `burst` marks a super reaction. `is_bot`
+ * is only known on add (null on remove); `message_author_id` is null when
+ * Discord omits it.
+ */
+export const ReactionDataSchema = z.object({
+  message_id: z.string(),
+  message_author_id: z.string().nullable().optional(),
+  emoji: ReactionEmojiSchema,
+  burst: z.boolean(),
+  is_bot: z.boolean().nullable().optional(),
+});
+
+export type ReactionData = z.infer<typeof ReactionDataSchema>;
+
+/** data payload for event_type = "reaction.remove_all" (user_id is null) */
+export const ReactionRemoveAllDataSchema = z.object({
+  message_id: z.string(),
+});
+
+export type ReactionRemoveAllData = z.infer<typeof ReactionRemoveAllDataSchema>;
+
+// ---------------------------------------------------------------------------
+// Voice events (VOICE stream)
+// ---------------------------------------------------------------------------
+
+/**
+ * data payload for event_type = "voice.state_update"
+ *
+ * Only published by gateways with FORWARD_VOICE enabled. The user is the
+ * envelope's user_id and the voice channel its channel_id (null once the user
+ * has left). `session_id` pairs the bot's own state with voice.server_update
+ * when joining a call. `is_bot` is null when Discord omits the member.
+ */
+export const VoiceStateUpdateDataSchema = z.object({
+  session_id: z.string(),
+  deaf: z.boolean(),
+  mute: z.boolean(),
+  self_deaf: z.boolean(),
+  self_mute: z.boolean(),
+  self_stream: z.boolean(),
+  self_video: z.boolean(),
+  suppress: z.boolean(),
+  /** Stage hand raise (Unix milliseconds) */
+  request_to_speak_at: z.number().int().nonnegative().nullable().optional(),
+  is_bot: z.boolean().nullable().optional(),
+});
+
+export type VoiceStateUpdateData = z.infer<typeof VoiceStateUpdateDataSchema>;
+
+/**
+ * data payload for event_type = "voice.server_update" (channel_id and
+ * user_id are null)
+ *
+ * The voice server for the bot's connection in the guild. `endpoint` is null
+ * while Discord reallocates the server. `token` authenticates the voice
+ * connection: treat it as a secret.
+ */
+export const VoiceServerUpdateDataSchema = z.object({
+  endpoint: z.string().nullable(),
+  token: z.string(),
+});
+
+export type VoiceServerUpdateData = z.infer<typeof VoiceServerUpdateDataSchema>;
+
+// ---------------------------------------------------------------------------
+// Role events (EVENTS stream)
+// ---------------------------------------------------------------------------
+
+/**
+ * data payload for event_type = "role.create" | "role.update"
+ *
+ * The whole role, so workers can update cached role mappings. `permissions`
+ * is the bitset as a decimal string. `tags` is set for managed roles and says
+ * what manages them: a bot (`bot_id`), an integration, server boosting
+ * (`premium_subscriber`) or a role subscription.
+ */
+export const RoleDataSchema = z.object({
+  id: z.string(),
+  name: z.string(),
+  color: z.number().int(),
+  position: z.number().int(),
+  permissions: z.string(),
+  managed: z.boolean(),
+  mentionable: z.boolean(),
+  hoist: z.boolean(),
+  icon: z.string().nullable().optional(),
+  unicode_emoji: z.string().nullable().optional(),
+  tags: z
+    .object({
+      bot_id: z.string().nullable().optional(),
+      integration_id: z.string().nullable().optional(),
+      premium_subscriber: z.boolean().optional(),
+      subscription_listing_id: z.string().nullable().optional(),
+      available_for_purchase: z.boolean().optional(),
+      guild_connections: z.boolean().optional(),
+    })
+    .nullable()
+    .optional(),
+});
+
+export type RoleData = z.infer<typeof RoleDataSchema>;
+
+/** data payload for event_type = "role.delete" */
+export const RoleDeleteDataSchema = z.object({
+  role_id: z.string(),
+});
+
+export type RoleDeleteData = z.infer<typeof RoleDeleteDataSchema>;
+
+// ---------------------------------------------------------------------------
+// Channel and thread events (EVENTS stream)
+// ---------------------------------------------------------------------------
+
+/**
+ * data payload for event_type = "channel.create" | "channel.update" |
+ * "channel.delete"
+ *
+ * The channel is also the envelope's channel_id. `type` is Discord's channel
+ * type name (e.g. "GuildText", "GuildCategory"); `parent_id` is the category.
+ */
+export const ChannelDataSchema = z.object({
+  id: z.string(),
+  name: z.string().nullable().optional(),
+  type: z.string(),
+  parent_id: z.string().nullable().optional(),
+  position: z.number().int().nullable().optional(),
+  topic: z.string().nullable().optional(),
+  nsfw: z.boolean().nullable().optional(),
+});
+
+export type ChannelData = z.infer<typeof ChannelDataSchema>;
+
+/**
+ * data payload for event_type = "thread.create" | "thread.update", and each
+ * of thread.list_sync's `threads`
+ *
+ * `parent_id` is the channel the thread lives in; the creator is `owner_id`
+ * (also the envelope's user_id). `created_at` is Unix milliseconds, null for
+ * threads older than 2022-01-09.
+ */
+export const ThreadDataSchema = z.object({
+  id: z.string(),
+  name: z.string().nullable().optional(),
+  type: z.string(),
+  parent_id: z.string().nullable().optional(),
+  owner_id: z.string().nullable().optional(),
+  archived: z.boolean().nullable().optional(),
+  locked: z.boolean().nullable().optional(),
+  auto_archive_minutes: z.number().int().nullable().optional(),
+  created_at: z.number().int().nonnegative().nullable().optional(),
+  member_count: z.number().int().nullable().optional(),
+  message_count: z.number().int().nullable().optional(),
+});
+
+export type ThreadData = z.infer<typeof ThreadDataSchema>;
+
+/** data payload for event_type = "thread.delete" (the thread is the envelope's channel_id) */
+export const ThreadDeleteDataSchema = z.object({
+  type: z.string(),
+  parent_id: z.string(),
+});
+
+export type ThreadDeleteData = z.infer<typeof ThreadDeleteDataSchema>;
+
+/**
+ * data payload for event_type = "thread.list_sync"
+ *
+ * Sent when the bot gains access to channels: every active thread in
+ * `channel_ids`, or in the whole guild when `channel_ids` is empty.
+ */
+export const ThreadListSyncDataSchema = z.object({
+  channel_ids: z.array(z.string()),
+  threads: z.array(ThreadDataSchema),
+});
+
+export type ThreadListSyncData = z.infer<typeof ThreadListSyncDataSchema>;
+
+// ---------------------------------------------------------------------------
+// Gateway operational events
+// ---------------------------------------------------------------------------
+
+/**
+ * data payload for event_type = "gateway.capability_degraded"
+ *
+ * Emitted when Discord closes a shard with 4014 (disallowed intents) and the
+ * gateway reconnects without its privileged intents. Intent names use the
+ * Discord flag spelling (e.g. "GUILD_MEMBERS").
+ */
+export const GatewayCapabilityDegradedDataSchema = z.object({
+  close_code: z.number().int(),
+  missing_intents: z.array(z.string()),
+  active_intents: z.array(z.string()),
+});
+
+export type GatewayCapabilityDegradedData = z.infer<typeof GatewayCapabilityDegradedDataSchema>;
+
+/**
+ * data payload for event_type = "gateway.circuit_state"
+ *
+ * Published on `events.gateway.circuit_state` when a shard's circuit breaker
+ * changes state: `open` after `errors` consecutive receive errors (the shard
+ * is stopped), `half_open` when one reconnect is tried after `cooldown_secs`,
+ * `closed` once that reconnect is ready. `trips` counts the openings since the
+ * circuit was last closed. `cooldown_secs` is null except on `open`, and on
+ * `open` too when CIRCUIT_BREAKER_COOLDOWN_SECS is 0 (no retry).
+ */
+export const GatewayCircuitStateDataSchema = z.object({
+  state: z.enum(['closed', 'open', 'half_open']),
+  previous: z.enum(['closed', 'open', 'half_open']),
+  errors: z.number().int().nonnegative(),
+  trips: z.number().int().positive(),
+  cooldown_secs: z.number().int().nonnegative().nullable(),
+});
+
+export type GatewayCircuitStateData = z.infer<typeof GatewayCircuitStateDataSchema>;
+
+/**
+ * data payload for event_type = "event.summary"
+ *
+ * Published on `events.summary.{event_type}` (dots become underscores) when
+ * the gateway aggregates a type via AGGREGATE_EVENTS. Counts one guild's
+ * events of that type in [window_start, window_end) (Unix milliseconds).
+ * `sample_ids` holds up to 10 user IDs (event IDs for events without a user).
+ * `reason` is set when the counted events were dropped rather than
+ * aggregated: `rate_limited` for a guild over GUILD_RATE_LIMIT.
+ */
+export const EventSummaryDataSchema = z.object({
+  event_type: z.string(),
+  count: z.number().int().positive(),
+  sample_ids: z.array(z.string()),
+  window_start: z.number().int().nonnegative(),
+  window_end: z.number().int().nonnegative(),
+  reason: z.enum(['rate_limited']).optional(),
+});
+
+export type EventSummaryData = z.infer<typeof EventSummaryDataSchema>;
+
+// ---------------------------------------------------------------------------
+// Join raids
+// ---------------------------------------------------------------------------
+
+/**
+ * data payload for event_type = "member.join.burst"
+ *
+ * Published on `events.member.join_burst` while a guild is in a join raid
+ * (RAID_DETECTION_JOINS), in place of its individual member.join events.
+ * Counts the guild's joins in [window_start, window_end) (Unix milliseconds).
+ * `sample_user_ids` holds up to 25 of the joining users.
+ */
+export const MemberJoinBurstDataSchema = z.object({
+  count: z.number().int().positive(),
+  sample_user_ids: z.array(z.string()),
+  window_start: z.number().int().nonnegative(),
+  window_end: z.number().int().nonnegative(),
+});
+
+export type MemberJoinBurstData = z.infer<typeof MemberJoinBurstDataSchema>;
+
+/**
+ * data payload for event_type = "guild.raid_alert"
+ *
+ * Published on `events.guild.raid_alert` when a guild's joins in one window
+ * reach `threshold` (`phase: "started"`, `joins` is the threshold) and when a
+ * window falls below it again (`phase: "ended"`, `joins` is the raid's total).
+ * `ended_at` is null while the raid is ongoing.
+ */
+export const GuildRaidAlertDataSchema = z.object({
+  phase: z.enum(['started', 'ended']),
+  joins: z.number().int().positive(),
+  threshold: z.number().int().positive(),
+  window_secs: z.number().int().positive(),
+  started_at: z.number().int().nonnegative(),
+  ended_at: z.number().int().nonnegative().nullable(),
+});
+
+export type GuildRaidAlertData = z.infer<typeof GuildRaidAlertDataSchema>;
@@ -12,9 +12,13 @@ import { z } from 'zod';
  * Every message on the NATS wire matches this shape.
  *
  * Field-level contract (maps 1:1 to Rust GatewayEvent):
- *   event_id       — UUIDv4 string
+ *   event_id       — UUID string (v5 from the Discord dispatch, v4 for gateway-made events)
  *   event_type     — dot-separated event classifier (e.g. "guild.join")
+ *   schema_version — optional; envelope version, 1 when absent (see SCHEMA-GOVERNANCE.md)
  *   shard_id       — Discord shard that produced the event
+ *   bot_id         — optional; which bot, when one gateway runs several
+ *   seq            — optional; position among the shard's events on the stream, from 1
+ *   seq_epoch      — optional; Unix ms the numbering started; seq restarts under a new epoch
  *   timestamp      — Unix epoch milliseconds (u64 in Rust → number in JS)
  *   guild_id       — nullable Discord snowflake
  *   channel_id     — nullable Discord snowflake
@@ -24,7 +28,11 @@ import { z } from 'zod';
 export const GatewayEventSchema = z.object({
   event_id: z.string().uuid(),
   event_type: z.string().min(1),
+  schema_version: z.number().int().positive().optional(),
   shard_id: z.number().int().nonnegative(),
+  bot_id: z.string().min(1).optional(),
+  seq: z.number().int().positive().optional(),
+  seq_epoch: z.number().int().nonnegative().optional(),
   timestamp: z.number().int().nonnegative(),
   guild_id: z.string().nullable(),
   channel_id: z.string().nullable(),
@@ -69,6 +77,30 @@ export const KNOWN_EVENT_TYPES = [
   'member.update',
   'interaction.create',
   'message.create',
+  'message.update',
+  'message.delete',
+  'message.bulk_delete',
+  'reaction.add',
+  'reaction.remove',
+  'reaction.remove_all',
+  'voice.state_update',
+  'voice.server_update',
+  'role.create',
+  'role.update',
+  'role.delete',
+  'channel.create',
+  'channel.update',
+  'channel.delete',
+  'thread.create',
+  'thread.update',
+  'thread.delete',
+  'thread.list_sync',
+  'gateway.capability_degraded',
+  'event.summary',
+  'member.join.burst',
+  'guild.raid_alert',
+  'member.sync',
+  'gateway.circuit_state',
 ] as const;
 
 export type KnownEventType = (typeof KNOWN_EVENT_TYPES)[number];
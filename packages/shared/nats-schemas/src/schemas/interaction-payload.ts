@@ -5,11 +5,11 @@
  * Two schema tiers exist to separate transport from enrichment (BB60-S5-1):
  *
  * **Transport** (what Rust produces):
- *   - InteractionTransportDataSchema — 3 required fields from the gateway
+ *   - InteractionTransportDataSchema — the data fields the gateway serializes
  *   - InteractionTransportPayloadSchema — strict wire boundary validation
  *
  * **Enriched** (what TypeScript workers consume after middleware):
- *   - EnrichedInteractionDataSchema — transport + 3 optional command routing fields
+ *   - EnrichedInteractionDataSchema — transport + optional subcommand routing field
  *   - InteractionPayloadSchema — backward-compatible, permissive validation
  *
  * FAANG parallel: Confluent's schema governance post-mortem showed that mixing
@@ -25,7 +25,7 @@ import { InteractionCreateDataSchema } from './event-data.js';
 // ---------------------------------------------------------------------------
 
 /**
- * Strict transport data: only the 3 fields the Rust gateway serializes.
+ * Strict transport data: only the fields the Rust gateway serializes.
  * Use this when validating at the NATS wire boundary before any enrichment.
  */
 export const InteractionTransportDataSchema = InteractionCreateDataSchema;
@@ -35,14 +35,19 @@ export const InteractionTransportDataSchema = InteractionCreateDataSchema;
 // ---------------------------------------------------------------------------
 
 /**
- * Enriched interaction data with optional command routing fields.
- * The base 3 fields come from Rust; command_name/subcommand/options
- * may be populated by a middleware layer or enrichment step.
+ * Enriched interaction data with an optional subcommand routing field.
+ * Everything else comes from Rust, including the typed `options` array;
+ * `subcommand` may be populated by a middleware layer or enrichment step.
+ *
+ * Rust sends command_name = null for non-command interactions (components,
+ * modals); it is normalized to undefined so handlers see one "absent" value.
  */
 export const EnrichedInteractionDataSchema = InteractionCreateDataSchema.extend({
-  command_name: z.string().optional(),
+  command_name: z
+    .string()
+    .nullish()
+    .transform((name) => name ?? undefined),
   subcommand: z.string().optional(),
-  options: z.record(z.unknown()).optional(),
 });
 
 // ---------------------------------------------------------------------------
@@ -0,0 +1,110 @@
+/**
+ * Member List Schemas
+ *
+ * Workers request a guild's member list on `gateway.requests.member_chunk`.
+ * The pool running the guild's shard asks Discord over the websocket and
+ * publishes each member chunk to the request's reply subject; read replies
+ * until `chunk_count` have arrived. A request the gateway can't send is
+ * answered once with `{ error }`.
+ *
+ * A backfill (`gateway.requests.backfill`) lists every member of a guild the
+ * same way, but publishes the chunks as `member.sync` events on
+ * `events.member.sync` and reports progress on `gateway.backfill.progress`.
+ */
+
+import { z } from 'zod';
+
+// --------------------------------------------------------------------------
+// Schemas
+// --------------------------------------------------------------------------
+
+/** A request sent on `gateway.requests.member_chunk` */
+export const MemberChunkRequestSchema = z.object({
+  guild_id: z.string(),
+  /** Username prefix; empty with `limit` 0 lists every member */
+  query: z.string().optional(),
+  /** Most members to return (0 for no limit) */
+  limit: z.number().int().nonnegative().optional(),
+  /** Specific members instead of `query` */
+  user_ids: z.array(z.string()).max(100).optional(),
+  presences: z.boolean().optional(),
+});
+
+/** A guild member with its user */
+export const ChunkMemberSchema = z.object({
+  user: z.object({
+    id: z.string(),
+    username: z.string(),
+    global_name: z.string().nullable(),
+    avatar: z.string().nullable(),
+    bot: z.boolean(),
+  }),
+  nick: z.string().nullable(),
+  roles: z.array(z.string()),
+  /** Unix milliseconds */
+  joined_at: z.number().int().nullable(),
+  premium_since: z.number().int().nullable(),
+  avatar: z.string().nullable(),
+  pending: z.boolean(),
+});
+
+/** One reply: a chunk of the member list */
+export const MemberChunkSchema = z.object({
+  guild_id: z.string(),
+  chunk_index: z.number().int().nonnegative(),
+  chunk_count: z.number().int().positive(),
+  members: z.array(ChunkMemberSchema),
+  /** Requested `user_ids` that aren't members */
+  not_found: z.array(z.string()),
+  presences: z.array(z.object({ user_id: z.string(), status: z.string() })),
+});
+
+/** The single reply to a request the gateway couldn't send */
+export const MemberChunkErrorSchema = z.object({
+  error: z.string(),
+});
+
+/** A request sent on `gateway.requests.backfill` */
+export const BackfillRequestSchema = z.object({
+  guild_id: z.string(),
+});
+
+/** The reply to a backfill the gateway started (otherwise `{ error }`) */
+export const BackfillStartedSchema = z.object({
+  job_id: z.string(),
+  guild_id: z.string(),
+  shard_id: z.number().int().nonnegative(),
+});
+
+/** data payload for event_type = "member.sync": one page of a backfill, in order */
+export const MemberSyncDataSchema = z.object({
+  job_id: z.string(),
+  page: z.number().int().nonnegative(),
+  page_count: z.number().int().positive(),
+  members: z.array(ChunkMemberSchema),
+});
+
+/** Published on `gateway.backfill.progress` after each page and when a job ends */
+export const BackfillProgressSchema = z.object({
+  job_id: z.string(),
+  guild_id: z.string(),
+  shard_id: z.number().int().nonnegative(),
+  status: z.enum(['running', 'completed', 'failed', 'expired']),
+  pages_done: z.number().int().nonnegative(),
+  /** null when the job expired */
+  page_count: z.number().int().positive().nullable(),
+  members: z.number().int().nonnegative(),
+});
+
+// --------------------------------------------------------------------------
+// Types
+// --------------------------------------------------------------------------
+
+export type MemberChunkRequest = z.infer<typeof MemberChunkRequestSchema>;
+export type ChunkMember = z.infer<typeof ChunkMemberSchema>;
+export type MemberChunk = z.infer<typeof MemberChunkSchema>;
+export type MemberChunkError = z.infer<typeof MemberChunkErrorSchema>;
+export type BackfillRequest = z.infer<typeof BackfillRequestSchema>;
+export type BackfillStarted = z.infer<typeof BackfillStartedSchema>;
+export type MemberSyncData = z.infer<typeof MemberSyncDataSchema>;
+export type BackfillProgress = z.infer<typeof BackfillProgressSchema>;
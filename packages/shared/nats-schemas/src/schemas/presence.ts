@@ -0,0 +1,37 @@
+/**
+ * Presence Update Schema
+ *
+ * Workers set the bot's status and activity by publishing on
+ * `gateway.presence.update`. Without `shard_id` every pool applies it to all
+ * of its shards; leaving out `activity` clears the activity.
+ */
+
+import { z } from 'zod';
+
+// --------------------------------------------------------------------------
+// Schemas
+// --------------------------------------------------------------------------
+
+export const PresenceActivitySchema = z.object({
+  type: z.enum(['playing', 'streaming', 'listening', 'watching', 'competing', 'custom']),
+  /** Text after the verb ("12,345 holders"), or the custom status */
+  name: z.string().min(1),
+  /** Stream URL, for `streaming` */
+  url: z.string().nullable().optional(),
+});
+
+/** An update published on `gateway.presence.update` */
+export const PresenceUpdateSchema = z.object({
+  /** Only this shard; all of them when unset */
+  shard_id: z.number().int().nonnegative().nullable().optional(),
+  status: z.enum(['online', 'idle', 'dnd', 'invisible']).optional(),
+  activity: PresenceActivitySchema.nullable().optional(),
+  afk: z.boolean().optional(),
+});
+
+// --------------------------------------------------------------------------
+// Types
+// --------------------------------------------------------------------------
+
+export type PresenceActivity = z.infer<typeof PresenceActivitySchema>;
+export type PresenceUpdate = z.infer<typeof PresenceUpdateSchema>;
@@ -0,0 +1,40 @@
+/**
+ * Raw Passthrough Schemas
+ *
+ * For event types or guilds selected with `RAW_PASSTHROUGH_EVENTS` /
+ * `RAW_PASSTHROUGH_GUILDS`, the gateway publishes the Discord dispatch an
+ * event was normalized from on `raw.{subject}` (RAW stream, 15 min max age).
+ * Join it to the normalized envelope on `event_id` to compare what Discord
+ * sent with what the gateway emitted. Debugging only: nothing should consume
+ * `raw.>` in production.
+ */
+
+import { z } from 'zod';
+
+// --------------------------------------------------------------------------
+// Schema
+// --------------------------------------------------------------------------
+
+/** A Discord dispatch next to the event it became */
+export const RawEventSchema = z.object({
+  /** event_id of the normalized envelope */
+  event_id: z.string(),
+  event_type: z.string(),
+  shard_id: z.number().int().nonnegative(),
+  guild_id: z.string().nullable(),
+  /** Subject the normalized envelope was published on */
+  subject: z.string(),
+  /** The gateway payload exactly as Discord sent it */
+  dispatch: z.object({
+    op: z.number().int(),
+    t: z.string().nullable(),
+    s: z.number().int().nullable(),
+    d: z.unknown(),
+  }),
+});
+
+// --------------------------------------------------------------------------
+// Types
+// --------------------------------------------------------------------------
+
+export type RawEvent = z.infer<typeof RawEventSchema>;
@@ -0,0 +1,43 @@
+/**
+ * REST Proxy Schemas
+ *
+ * With `REST_PROXY_ENABLED`, a worker sends a Discord REST call as a NATS
+ * request on `rest.requests.{caller}` and the gateway executes it behind its
+ * rate limiter (per-route buckets and the global limit). The reply carries
+ * Discord's status and JSON body; `error` is set only when Discord never
+ * answered (400 malformed request, 502 failed call, 504 timeout).
+ */
+
+import { z } from 'zod';
+
+// --------------------------------------------------------------------------
+// Schemas
+// --------------------------------------------------------------------------
+
+/** A Discord call sent on `rest.requests.{caller}` */
+export const RestRequestSchema = z.object({
+  method: z.enum(['GET', 'POST', 'PATCH', 'PUT', 'DELETE']),
+  /** API path with optional query, without the `/api/v10/` prefix */
+  path: z.string().min(1),
+  /** JSON body */
+  body: z.unknown().optional(),
+  /** Audit log reason */
+  reason: z.string().nullable().optional(),
+});
+
+/** The gateway's reply */
+export const RestResponseSchema = z.object({
+  /** Discord's HTTP status, or the gateway's when Discord never answered */
+  status: z.number().int(),
+  /** Discord's JSON body (absent when empty) */
+  body: z.unknown().optional(),
+  /** Why the call failed before reaching Discord */
+  error: z.string().optional(),
+});
+
+// --------------------------------------------------------------------------
+// Types
+// --------------------------------------------------------------------------
+
+export type RestRequest = z.infer<typeof RestRequestSchema>;
+export type RestResponse = z.infer<typeof RestResponseSchema>;
@@ -0,0 +1,43 @@
+/**
+ * Tick Schemas
+ *
+ * Scheduled ticks the gateway publishes to the TICKS stream: `ticks.minute`
+ * on every UTC minute boundary, `ticks.hour` on every hour, and
+ * `ticks.guild.{guild_id}.{name}` for per-guild schedules. Each boundary is
+ * published once even though every pool runs the clock (JetStream message-ID
+ * deduplication).
+ *
+ * Guild schedules live in the `guild_schedules` KV bucket: the value under
+ * key `{guild_id}` is a JSON array of GuildScheduleEntry. Names must be a
+ * single subject token (no dots, wildcards or spaces); cron expressions have
+ * five fields and are evaluated in UTC.
+ */
+
+import { z } from 'zod';
+
+// --------------------------------------------------------------------------
+// Schema
+// --------------------------------------------------------------------------
+
+/** One tick */
+export const TickSchema = z.object({
+  /** "minute", "hour", or the guild schedule's name */
+  tick: z.string(),
+  /** Set for guild schedules */
+  guild_id: z.string().nullable(),
+  /** The boundary this tick is for (Unix milliseconds, UTC) */
+  scheduled_at: z.number().int().nonnegative(),
+});
+
+/** One schedule in a guild's `guild_schedules` KV value */
+export const GuildScheduleEntrySchema = z.object({
+  name: z.string().regex(/^[^.*>\s]+$/),
+  cron: z.string(),
+});
+
+// --------------------------------------------------------------------------
+// Types
+// --------------------------------------------------------------------------
+
+export type Tick = z.infer<typeof TickSchema>;
+export type GuildScheduleEntry = z.infer<typeof GuildScheduleEntrySchema>;
@@ -0,0 +1,68 @@
+/**
+ * Gateway Topology Schema
+ *
+ * Wire format of the per-pool topology documents the gateway publishes on
+ * `topology.gateway` and stores under `pool-{pool_id}` in the
+ * `gateway_topology` KV bucket (entries expire 5 minutes after a pool's last
+ * write). A guild lives on shard `(guild_id >> 22) % total_shards`.
+ */
+
+import { z } from 'zod';
+
+// --------------------------------------------------------------------------
+// Schema
+// --------------------------------------------------------------------------
+
+/** Shard health as tracked by the gateway */
+export const ShardHealthSchema = z.enum(['connecting', 'ready', 'resuming', 'disconnected', 'dead', 'drained']);
+
+/** One shard run by a pool */
+export const ShardTopologySchema = z.object({
+  shard_id: z.number().int().nonnegative(),
+  health: ShardHealthSchema,
+  guilds: z.number().int().nonnegative(),
+});
+
+/** One pool's topology document */
+export const GatewayTopologySchema = z.object({
+  pool_id: z.number().int().nonnegative(),
+  /** Shard count of the whole cluster */
+  total_shards: z.number().int().positive(),
+  /** Size of each pool's shard range (absent from pools before SHARDS_PER_POOL) */
+  shards_per_pool: z.number().int().positive().default(25),
+  /** Gateway build version */
+  version: z.string(),
+  discord_api_version: z.number().int().positive(),
+  discord_api_mode: z.enum(['pinned', 'canary']),
+  guilds_total: z.number().int().nonnegative(),
+  /** Shards this pool runs, in ID order */
+  shards: z.array(ShardTopologySchema),
+  /** Unix milliseconds when the snapshot was taken */
+  timestamp: z.number().int().nonnegative(),
+});
+
+/**
+ * Where a guild lives: the reply on `gateway.requests.guild_shard` (sent
+ * with `GuildRequestSchema`) and the body of `GET /guilds/{id}/shard`
+ */
+export const GuildShardSchema = z.object({
+  guild_id: z.string(),
+  shard_id: z.number().int().nonnegative(),
+  total_shards: z.number().int().positive(),
+  shards_per_pool: z.number().int().positive(),
+  /** Pool whose shard range holds the shard */
+  pool_id: z.number().int().nonnegative(),
+  /** Whether the answering pool runs the shard (always true over NATS) */
+  running: z.boolean(),
+  /** The shard's health, when the answering pool runs it */
+  health: ShardHealthSchema.nullable(),
+});
+
+// --------------------------------------------------------------------------
+// Types
+// --------------------------------------------------------------------------
+
+export type ShardHealth = z.infer<typeof ShardHealthSchema>;
+export type ShardTopology = z.infer<typeof ShardTopologySchema>;
+export type GatewayTopology = z.infer<typeof GatewayTopologySchema>;
+export type GuildShard = z.infer<typeof GuildShardSchema>;